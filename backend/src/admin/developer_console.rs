@@ -14,6 +14,7 @@ use rand::{distributions::Alphanumeric, Rng};
 use sea_orm::ActiveValue::Set;
 
 use crate::entities::{api_token, webhook_endpoint, webhook_delivery};
+use crate::services::{event_catalog, webhook};
 
 // --- API TOKENS ---
 
@@ -59,8 +60,10 @@ pub async fn create_api_token(
         id: Set(Uuid::new_v4()),
         tenant_id: Set(tenant_id),
         token_hash: Set(hash),
+        token_prefix: Set(Some(crate::middleware::api_token::lookup_prefix(&token))),
         scopes: Set(payload.scopes.clone()),
         expires_at: Set(Some(expires_at.into())),
+        last_used_at: Set(None),
         created_at: Set(Some(Utc::now().into())),
         updated_at: Set(Some(Utc::now().into())),
     }
@@ -101,12 +104,20 @@ pub async fn revoke_api_token(
     Ok(StatusCode::NO_CONTENT)
 }
 
+// --- EVENT CATALOG ---
+
+pub async fn list_events() -> Json<Vec<event_catalog::EventDescriptor>> {
+    Json(event_catalog::catalog())
+}
+
 // --- WEBHOOK ENDPOINTS ---
 
 #[derive(Deserialize)]
 pub struct CreateWebhookRequest {
     pub target_url: String,
     pub subscribed_events: serde_json::Value,
+    /// Consecutive failed deliveries before the endpoint is disabled; 0 never disables.
+    pub failure_threshold: Option<i32>,
 }
 
 pub async fn create_webhook_endpoint(
@@ -114,6 +125,9 @@ pub async fn create_webhook_endpoint(
     Path(tenant_id): Path<Uuid>,
     Json(payload): Json<CreateWebhookRequest>,
 ) -> Result<Json<webhook_endpoint::Model>, (StatusCode, String)> {
+    event_catalog::validate_subscriptions(&payload.subscribed_events)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
     // Generate secret key (whsec_...)
     let raw_secret: String = rand::thread_rng()
         .sample_iter(&Alphanumeric)
@@ -129,6 +143,9 @@ pub async fn create_webhook_endpoint(
         secret_key: Set(secret_key),
         subscribed_events: Set(payload.subscribed_events),
         is_active: Set(true),
+        consecutive_failures: Set(0),
+        failure_threshold: Set(payload.failure_threshold.unwrap_or(10).max(0)),
+        disabled_at: Set(None),
         created_at: Set(Some(Utc::now().into())),
         updated_at: Set(Some(Utc::now().into())),
     }
@@ -152,6 +169,64 @@ pub async fn list_webhook_endpoints(
     Ok(Json(endpoints))
 }
 
+#[derive(Deserialize)]
+pub struct UpdateWebhookRequest {
+    pub target_url: Option<String>,
+    pub subscribed_events: Option<serde_json::Value>,
+    pub failure_threshold: Option<i32>,
+    /// Re-enabling an endpoint clears its failure streak.
+    pub is_active: Option<bool>,
+}
+
+pub async fn update_webhook_endpoint(
+    State(db): State<DatabaseConnection>,
+    Path((tenant_id, endpoint_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<UpdateWebhookRequest>,
+) -> Result<Json<webhook_endpoint::Model>, (StatusCode, String)> {
+    if let Some(subscribed_events) = &payload.subscribed_events {
+        event_catalog::validate_subscriptions(subscribed_events)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+
+    let endpoint = webhook_endpoint::Entity::find_by_id(endpoint_id)
+        .filter(webhook_endpoint::Column::TenantId.eq(tenant_id))
+        .one(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Webhook endpoint not found".to_string()))?;
+
+    let mut active_endpoint: webhook_endpoint::ActiveModel = endpoint.into();
+    if let Some(target_url) = payload.target_url {
+        active_endpoint.target_url = Set(target_url);
+    }
+    if let Some(subscribed_events) = payload.subscribed_events {
+        active_endpoint.subscribed_events = Set(subscribed_events);
+    }
+    if let Some(failure_threshold) = payload.failure_threshold {
+        active_endpoint.failure_threshold = Set(failure_threshold.max(0));
+    }
+    match payload.is_active {
+        Some(true) => {
+            active_endpoint.is_active = Set(true);
+            active_endpoint.consecutive_failures = Set(0);
+            active_endpoint.disabled_at = Set(None);
+        }
+        Some(false) => {
+            active_endpoint.is_active = Set(false);
+            active_endpoint.disabled_at = Set(Some(Utc::now().into()));
+        }
+        None => {}
+    }
+    active_endpoint.updated_at = Set(Some(Utc::now().into()));
+
+    let updated = active_endpoint
+        .update(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(updated))
+}
+
 pub async fn delete_webhook_endpoint(
     State(db): State<DatabaseConnection>,
     Path((_tenant_id, endpoint_id)): Path<(Uuid, Uuid)>,
@@ -180,3 +255,70 @@ pub async fn list_webhook_deliveries(
     
     Ok(Json(deliveries))
 }
+
+pub async fn redeliver_webhook_delivery(
+    State(db): State<DatabaseConnection>,
+    Path((tenant_id, delivery_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<webhook_delivery::Model>, (StatusCode, String)> {
+    let delivery = webhook_delivery::Entity::find_by_id(delivery_id)
+        .filter(webhook_delivery::Column::TenantId.eq(tenant_id))
+        .one(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Webhook delivery not found".to_string()))?;
+
+    let endpoint = webhook_endpoint::Entity::find_by_id(delivery.endpoint_id)
+        .one(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Webhook endpoint not found".to_string()))?;
+    if !endpoint.is_active {
+        return Err((StatusCode::CONFLICT, "Webhook endpoint is disabled".to_string()));
+    }
+
+    let delivery = webhook::redeliver(&db, delivery)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(delivery))
+}
+
+#[derive(Deserialize)]
+pub struct ReplayWebhookDeliveriesRequest {
+    pub from: chrono::DateTime<Utc>,
+    pub to: chrono::DateTime<Utc>,
+    pub endpoint_id: Option<Uuid>,
+    /// Defaults to `["dead_letter"]`.
+    pub statuses: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct ReplayWebhookDeliveriesResponse {
+    pub replayed: usize,
+}
+
+pub async fn replay_webhook_deliveries(
+    State(db): State<DatabaseConnection>,
+    Path(tenant_id): Path<Uuid>,
+    Json(payload): Json<ReplayWebhookDeliveriesRequest>,
+) -> Result<Json<ReplayWebhookDeliveriesResponse>, (StatusCode, String)> {
+    if payload.from > payload.to {
+        return Err((StatusCode::BAD_REQUEST, "`from` must not be after `to`".to_string()));
+    }
+    let statuses = payload
+        .statuses
+        .unwrap_or_else(|| vec![webhook::STATUS_DEAD_LETTER.to_string()]);
+
+    let replayed = webhook::replay_range(
+        &db,
+        tenant_id,
+        payload.from.into(),
+        payload.to.into(),
+        payload.endpoint_id,
+        &statuses,
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(ReplayWebhookDeliveriesResponse { replayed }))
+}
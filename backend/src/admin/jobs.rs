@@ -0,0 +1,249 @@
+use axum::{
+    extract::{Path, Query, State, Json},
+    http::StatusCode,
+};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::Utc;
+use sea_orm::ActiveValue::Set;
+
+use crate::entities::{job_run, tenant_background_job};
+use crate::services::job_scheduler::{self, JobScheduler};
+use crate::traits::atlas_app::BackgroundJob;
+
+// --- JOB TYPES ---
+
+#[derive(Serialize)]
+pub struct JobTypeResponse {
+    pub app_id: String,
+    pub job_type: String,
+    pub default_interval_seconds: i32,
+    pub is_active_by_default: bool,
+    pub default_config_payload: Option<serde_json::Value>,
+}
+
+pub async fn list_job_types() -> Json<Vec<JobTypeResponse>> {
+    let types = crate::atlas_apps::get_active_apps()
+        .into_iter()
+        .map(|app| (app.app_id().to_string(), app.background_jobs()))
+        // Platform jobs are listed under "core"
+        .chain(std::iter::once(("core".to_string(), job_scheduler::core_jobs())))
+        .flat_map(|(app_id, definitions)| {
+            definitions.into_iter().map(move |definition| JobTypeResponse {
+                app_id: app_id.clone(),
+                job_type: definition.job_type,
+                default_interval_seconds: definition.default_interval_seconds,
+                is_active_by_default: definition.is_active_by_default,
+                default_config_payload: definition.default_config_payload,
+            })
+        })
+        .collect();
+
+    Json(types)
+}
+
+// --- TENANT JOBS ---
+
+fn definition_for(job_type: &str) -> Result<BackgroundJob, (StatusCode, String)> {
+    job_scheduler::find_definition(job_type)
+        .ok_or((StatusCode::BAD_REQUEST, format!("Unknown job type '{}'", job_type)))
+}
+
+fn validate_schedule(cron_expression: Option<&str>, interval_seconds: Option<i32>) -> Result<(), (StatusCode, String)> {
+    if let Some(expression) = cron_expression {
+        job_scheduler::parse_cron(expression).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    if interval_seconds.is_some_and(|seconds| seconds < 1) {
+        return Err((StatusCode::BAD_REQUEST, "interval_seconds must be at least 1".to_string()));
+    }
+    Ok(())
+}
+
+async fn find_job(db: &DatabaseConnection, tenant_id: Uuid, job_id: Uuid) -> Result<tenant_background_job::Model, (StatusCode, String)> {
+    tenant_background_job::Entity::find_by_id(job_id)
+        .filter(tenant_background_job::Column::TenantId.eq(tenant_id))
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Background job not found".to_string()))
+}
+
+pub async fn list_tenant_jobs(
+    State(db): State<DatabaseConnection>,
+    Path(tenant_id): Path<Uuid>,
+) -> Result<Json<Vec<tenant_background_job::Model>>, (StatusCode, String)> {
+    let jobs = tenant_background_job::Entity::find()
+        .filter(tenant_background_job::Column::TenantId.eq(tenant_id))
+        .order_by_asc(tenant_background_job::Column::JobType)
+        .all(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(jobs))
+}
+
+#[derive(Deserialize)]
+pub struct CreateJobRequest {
+    pub job_type: String,
+    pub is_active: Option<bool>,
+    pub interval_seconds: Option<i32>,
+    pub cron_expression: Option<String>,
+    pub config: Option<serde_json::Value>,
+    pub max_concurrency: Option<i32>,
+    pub timeout_seconds: Option<i32>,
+}
+
+pub async fn create_tenant_job(
+    State(db): State<DatabaseConnection>,
+    Path(tenant_id): Path<Uuid>,
+    Json(payload): Json<CreateJobRequest>,
+) -> Result<(StatusCode, Json<tenant_background_job::Model>), (StatusCode, String)> {
+    let definition = definition_for(&payload.job_type)?;
+    if let Some(config) = &payload.config {
+        job_scheduler::validate_config(&definition, config).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+    validate_schedule(payload.cron_expression.as_deref(), payload.interval_seconds)?;
+
+    let existing = tenant_background_job::Entity::find()
+        .filter(tenant_background_job::Column::TenantId.eq(tenant_id))
+        .filter(tenant_background_job::Column::JobType.eq(&payload.job_type))
+        .count(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if existing > 0 {
+        return Err((StatusCode::CONFLICT, format!("Tenant already has a '{}' job", payload.job_type)));
+    }
+
+    let job = tenant_background_job::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant_id),
+        job_type: Set(definition.job_type.clone()),
+        config: Set(payload.config),
+        interval_seconds: Set(payload.interval_seconds.unwrap_or(definition.default_interval_seconds)),
+        last_run: Set(None),
+        is_active: Set(payload.is_active.unwrap_or(definition.is_active_by_default)),
+        cron_expression: Set(payload.cron_expression),
+        // Due on the next scheduler tick
+        next_run_at: Set(None),
+        max_concurrency: Set(payload.max_concurrency.unwrap_or(1).max(1)),
+        timeout_seconds: Set(payload.timeout_seconds.unwrap_or(300).max(1)),
+    }
+    .insert(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok((StatusCode::CREATED, Json(job)))
+}
+
+pub async fn get_tenant_job(
+    State(db): State<DatabaseConnection>,
+    Path((tenant_id, job_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<tenant_background_job::Model>, (StatusCode, String)> {
+    Ok(Json(find_job(&db, tenant_id, job_id).await?))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateJobRequest {
+    pub is_active: Option<bool>,
+    pub interval_seconds: Option<i32>,
+    /// `""` clears the cron expression and falls back to `interval_seconds`.
+    pub cron_expression: Option<String>,
+    pub config: Option<serde_json::Value>,
+    pub max_concurrency: Option<i32>,
+    pub timeout_seconds: Option<i32>,
+}
+
+pub async fn update_tenant_job(
+    State(db): State<DatabaseConnection>,
+    Path((tenant_id, job_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<UpdateJobRequest>,
+) -> Result<Json<tenant_background_job::Model>, (StatusCode, String)> {
+    let job = find_job(&db, tenant_id, job_id).await?;
+
+    let cron_expression = payload.cron_expression.map(|expression| {
+        let expression = expression.trim().to_string();
+        (!expression.is_empty()).then_some(expression)
+    });
+    validate_schedule(cron_expression.clone().flatten().as_deref(), payload.interval_seconds)?;
+    if let Some(config) = &payload.config {
+        let definition = definition_for(&job.job_type)?;
+        job_scheduler::validate_config(&definition, config).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+
+    let schedule_changed = payload.interval_seconds.is_some() || cron_expression.is_some();
+    let mut active_job: tenant_background_job::ActiveModel = job.into();
+    if let Some(is_active) = payload.is_active {
+        active_job.is_active = Set(is_active);
+    }
+    if let Some(interval_seconds) = payload.interval_seconds {
+        active_job.interval_seconds = Set(interval_seconds);
+    }
+    if let Some(cron_expression) = cron_expression {
+        active_job.cron_expression = Set(cron_expression);
+    }
+    if let Some(config) = payload.config {
+        active_job.config = Set(Some(config));
+    }
+    if let Some(max_concurrency) = payload.max_concurrency {
+        active_job.max_concurrency = Set(max_concurrency.max(1));
+    }
+    if let Some(timeout_seconds) = payload.timeout_seconds {
+        active_job.timeout_seconds = Set(timeout_seconds.max(1));
+    }
+
+    let mut updated = active_job
+        .update(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Re-plan the next occurrence from the last run so a new schedule applies immediately
+    if schedule_changed {
+        let from = updated.last_run.map(|t| t.with_timezone(&Utc)).unwrap_or_else(Utc::now);
+        let mut active_job: tenant_background_job::ActiveModel = updated.clone().into();
+        active_job.next_run_at = Set(Some(job_scheduler::next_run_after(&updated, from).into()));
+        updated = active_job
+            .update(&db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+
+    Ok(Json(updated))
+}
+
+pub async fn run_tenant_job(
+    State(db): State<DatabaseConnection>,
+    Path((tenant_id, job_id)): Path<(Uuid, Uuid)>,
+) -> Result<(StatusCode, Json<job_run::Model>), (StatusCode, String)> {
+    let job = find_job(&db, tenant_id, job_id).await?;
+
+    let run = JobScheduler::run_now(&db, job.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::CONFLICT, "Job is already running at its concurrency limit".to_string()))?;
+
+    Ok((StatusCode::ACCEPTED, Json(run)))
+}
+
+#[derive(Deserialize)]
+pub struct JobRunsQuery {
+    pub limit: Option<u64>,
+}
+
+pub async fn list_tenant_job_runs(
+    State(db): State<DatabaseConnection>,
+    Path((tenant_id, job_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<JobRunsQuery>,
+) -> Result<Json<Vec<job_run::Model>>, (StatusCode, String)> {
+    let job = find_job(&db, tenant_id, job_id).await?;
+
+    let runs = job_run::Entity::find()
+        .filter(job_run::Column::JobId.eq(job.id))
+        .order_by_desc(job_run::Column::StartedAt)
+        .limit(query.limit.unwrap_or(50).min(200))
+        .all(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(runs))
+}
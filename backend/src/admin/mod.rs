@@ -3,3 +3,8 @@ pub mod setup;
 pub mod billing;
 pub mod analytics;
 pub mod developer_console;
+pub mod jobs;
+pub mod modules;
+pub mod tenant_lifecycle;
+pub mod tenant_archive;
+pub mod seed_packs;
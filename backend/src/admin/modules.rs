@@ -0,0 +1,81 @@
+use axum::{
+    extract::{Path, State, Json},
+    http::StatusCode,
+};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::config::ModuleFlags;
+use crate::entities::tenant;
+use crate::middleware::site_context::invalidate_tenant;
+
+#[derive(Serialize)]
+pub struct TenantModulesResponse {
+    pub tenant_id: Uuid,
+    pub enabled_modules: Vec<String>,
+    pub available_modules: Vec<String>,
+}
+
+impl TenantModulesResponse {
+    fn new(tenant_id: Uuid, modules: ModuleFlags) -> Self {
+        Self {
+            tenant_id,
+            enabled_modules: modules.names(),
+            available_modules: ModuleFlags::all().names(),
+        }
+    }
+}
+
+async fn find_tenant(db: &DatabaseConnection, tenant_id: Uuid) -> Result<tenant::Model, (StatusCode, String)> {
+    tenant::Entity::find_by_id(tenant_id)
+        .one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Tenant not found".to_string()))
+}
+
+pub async fn get_tenant_modules(
+    State(db): State<DatabaseConnection>,
+    Path(tenant_id): Path<Uuid>,
+) -> Result<Json<TenantModulesResponse>, (StatusCode, String)> {
+    let tenant = find_tenant(&db, tenant_id).await?;
+    Ok(Json(TenantModulesResponse::new(tenant.id, ModuleFlags::from_db(tenant.enabled_modules))))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateTenantModulesRequest {
+    /// Module name to desired state, e.g. `{"LISTINGS": true, "REVIEWS": false}`.
+    /// Modules left out keep their current state.
+    pub modules: HashMap<String, bool>,
+}
+
+pub async fn update_tenant_modules(
+    State(db): State<DatabaseConnection>,
+    Path(tenant_id): Path<Uuid>,
+    Json(payload): Json<UpdateTenantModulesRequest>,
+) -> Result<Json<TenantModulesResponse>, (StatusCode, String)> {
+    let tenant = find_tenant(&db, tenant_id).await?;
+
+    let mut modules = ModuleFlags::from_db(tenant.enabled_modules);
+    for (name, enabled) in &payload.modules {
+        let flag = ModuleFlags::from_name(&name.to_uppercase())
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("Unknown module '{}'", name)))?;
+        modules.set(flag, *enabled);
+    }
+
+    let mut active_tenant: tenant::ActiveModel = tenant.into();
+    active_tenant.enabled_modules = Set(modules.to_db());
+    active_tenant.updated_at = Set(Utc::now());
+    let updated = active_tenant
+        .update(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Sites resolve their modules from the cached SiteConfig
+    invalidate_tenant(updated.id);
+
+    Ok(Json(TenantModulesResponse::new(updated.id, modules)))
+}
@@ -1,4 +1,5 @@
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{get, post, put, delete},
     Router,
 };
@@ -61,6 +62,9 @@ pub fn admin_routes(db: DatabaseConnection) -> Router<DatabaseConnection> {
                 .route("/api/admin/platform/apps", get(admin::get_platform_apps))
                 .route("/api/admin/platform/apps/{instance_id}/domains", get(admin::get_app_domains).post(admin::add_app_domain))
                 .route("/api/admin/platform/apps/{instance_id}/domains/{domain_name}", delete(admin::remove_app_domain))
+                .route("/api/admin/platform/apps/{instance_id}/domains/{domain_name}/verify", post(admin::verify_app_domain))
+                .route("/api/admin/platform/apps/{instance_id}/seed", post(crate::admin::seed_packs::apply_seed_pack))
+                .route("/api/admin/seed-packs/{app_type}", get(crate::admin::seed_packs::list_seed_packs))
                 // Tenant management API is handled via tenant::authenticated_routes
                 // Tenant management API is handled via tenant::authenticated_routes
 
@@ -108,11 +112,29 @@ pub fn admin_routes(db: DatabaseConnection) -> Router<DatabaseConnection> {
                 .route("/api/admin/billing/transactions", get(crate::admin::billing::list_transactions))
                 .route("/api/admin/billing/tenant/{tenant_id}", get(crate::admin::billing::get_tenant_ledger))
                 // Developer Console
+                .route("/api/admin/developer/events", get(crate::admin::developer_console::list_events))
                 .route("/api/admin/developer/tenant/{tenant_id}/api-tokens", get(crate::admin::developer_console::list_api_tokens).post(crate::admin::developer_console::create_api_token))
                 .route("/api/admin/developer/tenant/{tenant_id}/api-tokens/{token_id}", delete(crate::admin::developer_console::revoke_api_token))
                 .route("/api/admin/developer/tenant/{tenant_id}/webhooks", get(crate::admin::developer_console::list_webhook_endpoints).post(crate::admin::developer_console::create_webhook_endpoint))
-                .route("/api/admin/developer/tenant/{tenant_id}/webhooks/{endpoint_id}", delete(crate::admin::developer_console::delete_webhook_endpoint))
+                .route("/api/admin/developer/tenant/{tenant_id}/webhooks/{endpoint_id}", put(crate::admin::developer_console::update_webhook_endpoint).delete(crate::admin::developer_console::delete_webhook_endpoint))
                 .route("/api/admin/developer/tenant/{tenant_id}/webhook-deliveries", get(crate::admin::developer_console::list_webhook_deliveries))
+                .route("/api/admin/developer/tenant/{tenant_id}/webhook-deliveries/replay", post(crate::admin::developer_console::replay_webhook_deliveries))
+                .route("/api/admin/developer/tenant/{tenant_id}/webhook-deliveries/{delivery_id}/redeliver", post(crate::admin::developer_console::redeliver_webhook_delivery))
+                // Background jobs
+                .route("/api/admin/jobs/types", get(crate::admin::jobs::list_job_types))
+                .route("/api/admin/jobs/tenant/{tenant_id}", get(crate::admin::jobs::list_tenant_jobs).post(crate::admin::jobs::create_tenant_job))
+                .route("/api/admin/jobs/tenant/{tenant_id}/{job_id}", get(crate::admin::jobs::get_tenant_job).put(crate::admin::jobs::update_tenant_job))
+                .route("/api/admin/jobs/tenant/{tenant_id}/{job_id}/run", post(crate::admin::jobs::run_tenant_job))
+                .route("/api/admin/jobs/tenant/{tenant_id}/{job_id}/runs", get(crate::admin::jobs::list_tenant_job_runs))
+                // Platform modules
+                .route("/api/admin/modules/tenant/{tenant_id}", get(crate::admin::modules::get_tenant_modules).put(crate::admin::modules::update_tenant_modules))
+                // Tenant lifecycle
+                .route("/api/admin/tenants/{tenant_id}/suspend", post(crate::admin::tenant_lifecycle::suspend_tenant))
+                .route("/api/admin/tenants/{tenant_id}/reactivate", post(crate::admin::tenant_lifecycle::reactivate_tenant))
+                .route("/api/admin/tenants/{tenant_id}/schedule-deletion", post(crate::admin::tenant_lifecycle::schedule_tenant_deletion))
+                // Tenant archives
+                .route("/api/admin/tenants/{tenant_id}/export", get(crate::admin::tenant_archive::export_tenant))
+                .route("/api/admin/tenants/import", post(crate::admin::tenant_archive::import_tenant).layer(DefaultBodyLimit::max(crate::admin::tenant_archive::MAX_ARCHIVE_BYTES)))
                 //.layer(axum::middleware::from_fn_with_state(db.clone(), auth_middleware))
                 .with_state(db)
         })
@@ -0,0 +1,59 @@
+use axum::{
+    extract::{Path, Query, State, Json},
+    http::StatusCode,
+    Extension,
+};
+use sea_orm::{DatabaseConnection, EntityTrait};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::entities::{app_instance, user};
+use crate::services::seed_packs::{PackListing, SeedPackError, SeedPacks, SeedReport};
+
+pub(crate) fn seed_pack_error(e: SeedPackError) -> (StatusCode, String) {
+    match e {
+        SeedPackError::NotFound(_) => (StatusCode::NOT_FOUND, e.to_string()),
+        SeedPackError::Invalid(_) => (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()),
+        SeedPackError::Io(_) | SeedPackError::Db(_) => {
+            tracing::error!("Seed pack failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Seed pack failed".to_string())
+        }
+    }
+}
+
+/// Lists the seed packs for an app type with their version and any validation errors.
+pub async fn list_seed_packs(Path(app_type): Path<String>) -> Json<Vec<PackListing>> {
+    Json(SeedPacks::list(&app_type))
+}
+
+#[derive(Deserialize)]
+pub struct ApplySeedPackQuery {
+    /// Pack to apply; defaults to the instance's `data_seed_name`.
+    pub pack: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Applies a seed pack to an app instance, or with `?dry_run=true` reports what it would change.
+pub async fn apply_seed_pack(
+    State(db): State<DatabaseConnection>,
+    Extension(current_user): Extension<user::Model>,
+    Path(instance_id): Path<Uuid>,
+    Query(query): Query<ApplySeedPackQuery>,
+) -> Result<Json<SeedReport>, (StatusCode, String)> {
+    let instance = app_instance::Entity::find_by_id(instance_id)
+        .one(&db)
+        .await
+        .map_err(|e| seed_pack_error(e.into()))?
+        .ok_or((StatusCode::NOT_FOUND, "App instance not found".to_string()))?;
+    let name = query.pack.or_else(|| instance.data_seed_name.clone()).ok_or((
+        StatusCode::BAD_REQUEST,
+        "The app instance has no data_seed_name; pass ?pack=".to_string(),
+    ))?;
+
+    let pack = SeedPacks::load(&instance.app_type, &name).map_err(seed_pack_error)?;
+    let report = SeedPacks::apply(&db, &instance, &pack, Some(current_user.id), query.dry_run)
+        .await
+        .map_err(seed_pack_error)?;
+    Ok(Json(report))
+}
@@ -0,0 +1,53 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State, Json},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::services::tenant_archive::{ArchiveError, ImportOptions, ImportReport, TenantArchive};
+
+/// Largest archive `import_tenant` accepts.
+pub const MAX_ARCHIVE_BYTES: usize = 256 * 1024 * 1024;
+
+fn archive_error(e: ArchiveError) -> (StatusCode, String) {
+    match e {
+        ArchiveError::NotFound => (StatusCode::NOT_FOUND, e.to_string()),
+        ArchiveError::Invalid(_) | ArchiveError::UnsupportedVersion(_) => (StatusCode::BAD_REQUEST, e.to_string()),
+        ArchiveError::Conflict(_) => (StatusCode::CONFLICT, e.to_string()),
+        ArchiveError::Io(_) | ArchiveError::Db(_) => {
+            tracing::error!("Tenant archive failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Tenant archive failed".to_string())
+        }
+    }
+}
+
+/// Downloads the tenant as a versioned `.tar.gz` archive.
+pub async fn export_tenant(
+    State(db): State<DatabaseConnection>,
+    Path(tenant_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let archive = TenantArchive::export(&db, tenant_id).await.map_err(archive_error)?;
+    let disposition = format!(
+        "attachment; filename=\"tenant-{}-{}.tar.gz\"",
+        tenant_id,
+        chrono::Utc::now().format("%Y%m%d%H%M%S")
+    );
+    Ok((
+        [(header::CONTENT_TYPE, "application/gzip".to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        archive,
+    ))
+}
+
+/// Imports an archive sent as the request body. `?tenant_id=` and `?slug=` override the
+/// imported tenant's id and slug.
+pub async fn import_tenant(
+    State(db): State<DatabaseConnection>,
+    Query(options): Query<ImportOptions>,
+    body: Bytes,
+) -> Result<Json<ImportReport>, (StatusCode, String)> {
+    let report = TenantArchive::import(&db, &body, options).await.map_err(archive_error)?;
+    Ok(Json(report))
+}
@@ -0,0 +1,95 @@
+use axum::{
+    extract::{Path, State, Json},
+    http::StatusCode,
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::entities::{tenant, user};
+use crate::services::tenant::{LifecycleError, TenantService};
+
+#[derive(Serialize)]
+pub struct TenantLifecycleResponse {
+    pub tenant_id: Uuid,
+    pub site_status: String,
+    pub suspended_at: Option<DateTime<Utc>>,
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
+    pub purge_after: Option<DateTime<Utc>>,
+    pub purged_at: Option<DateTime<Utc>>,
+}
+
+impl From<tenant::Model> for TenantLifecycleResponse {
+    fn from(tenant: tenant::Model) -> Self {
+        Self {
+            tenant_id: tenant.id,
+            site_status: tenant.site_status,
+            suspended_at: tenant.suspended_at,
+            deletion_scheduled_at: tenant.deletion_scheduled_at,
+            purge_after: tenant.purge_after,
+            purged_at: tenant.purged_at,
+        }
+    }
+}
+
+fn lifecycle_error(e: LifecycleError) -> (StatusCode, String) {
+    match e {
+        LifecycleError::NotFound => (StatusCode::NOT_FOUND, e.to_string()),
+        LifecycleError::InvalidTransition { .. } => (StatusCode::CONFLICT, e.to_string()),
+        LifecycleError::InvalidGracePeriod(_) => (StatusCode::BAD_REQUEST, e.to_string()),
+        LifecycleError::Db(e) => {
+            tracing::error!("Tenant lifecycle change failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Tenant lifecycle change failed".to_string())
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct SuspendTenantRequest {
+    pub reason: Option<String>,
+}
+
+pub async fn suspend_tenant(
+    State(db): State<DatabaseConnection>,
+    Extension(current_user): Extension<user::Model>,
+    Path(tenant_id): Path<Uuid>,
+    payload: Option<Json<SuspendTenantRequest>>,
+) -> Result<Json<TenantLifecycleResponse>, (StatusCode, String)> {
+    let Json(payload) = payload.unwrap_or_default();
+    let tenant = TenantService::suspend(&db, tenant_id, Some(current_user.id), payload.reason)
+        .await
+        .map_err(lifecycle_error)?;
+    Ok(Json(tenant.into()))
+}
+
+pub async fn reactivate_tenant(
+    State(db): State<DatabaseConnection>,
+    Extension(current_user): Extension<user::Model>,
+    Path(tenant_id): Path<Uuid>,
+) -> Result<Json<TenantLifecycleResponse>, (StatusCode, String)> {
+    let tenant = TenantService::reactivate(&db, tenant_id, Some(current_user.id))
+        .await
+        .map_err(lifecycle_error)?;
+    Ok(Json(tenant.into()))
+}
+
+#[derive(Deserialize, Default)]
+pub struct ScheduleDeletionRequest {
+    /// Overrides `TENANT_DELETION_GRACE_DAYS` for this tenant, up to `MAX_DELETION_GRACE_DAYS`.
+    pub grace_days: Option<i64>,
+}
+
+pub async fn schedule_tenant_deletion(
+    State(db): State<DatabaseConnection>,
+    Extension(current_user): Extension<user::Model>,
+    Path(tenant_id): Path<Uuid>,
+    payload: Option<Json<ScheduleDeletionRequest>>,
+) -> Result<Json<TenantLifecycleResponse>, (StatusCode, String)> {
+    let Json(payload) = payload.unwrap_or_default();
+    let tenant = TenantService::schedule_deletion(&db, tenant_id, Some(current_user.id), payload.grace_days)
+        .await
+        .map_err(lifecycle_error)?;
+    Ok(Json(tenant.into()))
+}
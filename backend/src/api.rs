@@ -17,9 +17,13 @@ use axum::{Router, Extension, routing::post, routing::get};
 use sea_orm::DatabaseConnection;
 use crate::handlers::{users, profiles, listings, accounts, my_accounts, ab_testing, user_accounts, ad_purchases, tenant, app_instance, app_pages, app_menus, sessions, health, auth_frontend, communications, setup, magic_links, search, forms};
 use crate::middleware::{auth_middleware, site_context_middleware};
+use crate::middleware::app_gate::require_app_enabled;
+use crate::middleware::api_token::reject_api_tokens;
 use crate::admin::routes::admin_routes;
-use tower_http::trace::TraceLayer;
-use crate::middleware::rate_limiter::RateLimiter;
+use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
+use tracing::Level;
+use crate::middleware::rate_limiter::{rate_limit, Policy, RateLimiter};
+use crate::middleware::request_logger::{log_requests, RedactedHeaders, RequestLogger};
 use axum::{extract::Request, middleware::Next};
 use std::env;
 
@@ -37,7 +41,17 @@ use std::env;
 //     })
 // }
 
-pub fn create_router(db: DatabaseConnection) -> Router {
+/// Hides an app's routes from sites whose tenant hasn't installed the app.
+/// `site_context_middleware` runs first, as an outer layer, and provides the `SiteConfig`.
+fn gate_app_router(app_id: &'static str, router: Router<DatabaseConnection>) -> Router<DatabaseConnection> {
+    // `route_layer` panics on a router without routes
+    if !router.has_routes() {
+        return router;
+    }
+    router.route_layer(axum::middleware::from_fn_with_state(app_id, require_app_enabled))
+}
+
+pub fn create_router(db: DatabaseConnection, request_logger: RequestLogger) -> Router {
     // Check environment
     let is_production = env::var("ENVIRONMENT").unwrap_or_else(|_| "development".to_string()) == "production";
     tracing::info!("Environment: {}", if is_production { "production" } else { "development" });
@@ -47,8 +61,9 @@ pub fn create_router(db: DatabaseConnection) -> Router {
     let auth_routes = Router::new()
         .route("/login", post(users::login_user))
         .route("/register", post(users::register_user))
-        .route("/validate-session", get(sessions::validate_session))
         .route("/refresh-token", post(sessions::refresh_token))
+        .route_layer(axum::middleware::from_fn_with_state(Policy::Auth, rate_limit))
+        .route("/validate-session", get(sessions::validate_session))
         .layer(Extension(db.clone()))
         .layer(axum::middleware::from_fn(site_context_middleware));
 
@@ -61,20 +76,22 @@ pub fn create_router(db: DatabaseConnection) -> Router {
         .merge(ab_testing::public_routes())
         .merge(crate::handlers::passkeys::public_routes())
         .merge(setup::public_routes())
-        .merge(magic_links::public_routes())
+        .merge(magic_links::public_routes().route_layer(axum::middleware::from_fn_with_state(Policy::Auth, rate_limit)))
+        .merge(crate::handlers::tls::public_routes())
         .merge(app_instance::public_routes(db.clone()))
         .merge(app_menus::public_routes(db.clone()))
         .route("/health", get(health::health_check));
 
     for app in crate::atlas_apps::get_active_apps() {
-        public_routes = public_routes.merge(app.public_router(db.clone()));
+        public_routes = public_routes.merge(gate_app_router(app.app_id(), app.public_router(db.clone())));
     }
 
     let public_routes = public_routes
+        .layer(axum::middleware::from_fn_with_state(Policy::PublicRead, rate_limit))
         .layer(Extension(db.clone()))
         .layer(axum::middleware::from_fn(site_context_middleware));
 
-    let rate_limiter = RateLimiter::new();
+    let rate_limiter = RateLimiter::from_env(&db);
     let db_clone = db.clone();
 
     // Authenticated routes (requires state)
@@ -88,16 +105,26 @@ pub fn create_router(db: DatabaseConnection) -> Router {
         .merge(my_accounts::authenticated_routes())
         .merge(ab_testing::authenticated_routes())
         .merge(crate::handlers::passkeys::authenticated_routes())
-        .merge(crate::handlers::feeds::authenticated_routes(db.clone()))
         .merge(tenant::authenticated_routes(db.clone()))
         .merge(app_instance::authenticated_routes(db.clone()))
         .merge(communications::authenticated_routes(db.clone()))
         .merge(search::authenticated_routes())
-        .merge(crate::handlers::audit_logs::authenticated_routes())
+        .merge(crate::handlers::audit_logs::authenticated_routes());
+
+    for app in crate::atlas_apps::get_active_apps() {
+        authenticated_routes = authenticated_routes.merge(gate_app_router(app.app_id(), app.authenticated_router(db.clone())));
+    }
+
+    // API tokens are refused on every route above; only route groups merged below, each
+    // guarded by `require_api_scope`, accept them
+    let mut authenticated_routes = authenticated_routes
+        .route_layer(axum::middleware::from_fn(reject_api_tokens))
+        .merge(crate::handlers::feeds::authenticated_routes(db.clone()))
+        .merge(tenant::settings_routes(db.clone()))
         .merge(crate::handlers::telemetry::authenticated_routes());
 
     for app in crate::atlas_apps::get_active_apps() {
-        authenticated_routes = authenticated_routes.merge(app.authenticated_router(db.clone()));
+        authenticated_routes = authenticated_routes.merge(gate_app_router(app.app_id(), app.api_token_router(db.clone())));
     }
 
     // Combine all routes and apply state at the top level
@@ -122,10 +149,24 @@ pub fn create_router(db: DatabaseConnection) -> Router {
                     },
                 ))
                 .layer(axum::middleware::from_fn(site_context_middleware))
-                .layer(Extension(db_clone))
-                .layer(Extension(rate_limiter)),
+                .layer(Extension(db_clone)),
         )
         .layer(Extension(db.clone())) // For middleware that might need it
-        .layer(TraceLayer::new_for_http())
+        .layer(Extension(rate_limiter))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
+                .on_request(|request: &Request<_>, _span: &tracing::Span| {
+                    tracing::debug!(
+                        "Request: method={}, uri={}, headers={:?}",
+                        request.method(),
+                        request.uri(),
+                        RedactedHeaders(request.headers())
+                    );
+                })
+                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+        )
+        // Outermost, so the correlation ID covers every span and the log sees the final response
+        .layer(axum::middleware::from_fn_with_state(request_logger, log_requests))
         .with_state(db) // Apply state to the entire router
 }
\ No newline at end of file
@@ -0,0 +1,3 @@
+pub mod m20260408_000002_create_anchor_legacy_tables;
+pub mod m20260408_000003_seed_anchor_background_jobs;
+pub mod m20260408_000004_fix_anchor_tables_and_seed;
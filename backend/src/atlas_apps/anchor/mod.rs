@@ -0,0 +1,88 @@
+pub mod migrations;
+
+use crate::entities::app_instance;
+use crate::services::job_scheduler::JobScheduler;
+use crate::traits::atlas_app::{AtlasApp, BackgroundJob};
+use axum::Router;
+use sea_orm::{DatabaseConnection, DatabaseTransaction, DbErr};
+use sea_orm_migration::MigrationTrait;
+use async_trait::async_trait;
+use serde_json::json;
+
+pub struct AnchorApp;
+
+#[async_trait]
+impl AtlasApp for AnchorApp {
+    fn app_id(&self) -> &'static str {
+        "anchor"
+    }
+
+    fn public_router(&self, _state: DatabaseConnection) -> Router<DatabaseConnection> {
+        Router::new()
+    }
+
+    fn authenticated_router(&self, _state: DatabaseConnection) -> Router<DatabaseConnection> {
+        Router::new()
+    }
+
+    fn migrations(&self) -> Vec<Box<dyn MigrationTrait>> {
+        // Extract isolated Anchor specific tables correctly away from Core Infrastructure.
+        vec![
+            Box::new(migrations::m20260408_000002_create_anchor_legacy_tables::Migration),
+            Box::new(migrations::m20260408_000003_seed_anchor_background_jobs::Migration),
+            Box::new(migrations::m20260408_000004_fix_anchor_tables_and_seed::Migration),
+        ]
+    }
+
+    fn background_jobs(&self) -> Vec<BackgroundJob> {
+        vec![
+            BackgroundJob {
+                job_type: "BitcoinSync".to_string(), // Matching the explicit trigger previously hardcoded
+                default_interval_seconds: 600,
+                is_active_by_default: true,
+                default_config_payload: Some(json!({ "api_url": "https://mempool.space/api/blocks" })),
+                executor: Box::new(|db, tenant_id, config| {
+                    Box::pin(async move {
+                        let api_url = config
+                            .as_ref()
+                            .and_then(|c| c.get("api_url"))
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("https://mempool.space/api/blocks")
+                            .to_string();
+                        crate::services::data_sync::DataSyncService::sync_bitcoin_blocks(
+                            &db, 
+                            tenant_id, 
+                            &api_url
+                        )
+                        .await
+                        .map_err(|e| e.to_string())
+                    })
+                })
+            }
+        ]
+    }
+
+    fn settings_schema(&self) -> Option<serde_json::Value> {
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "site_title": { "type": "string" },
+                "contact_email": { "type": "string" },
+                "booking_url": { "type": "string" },
+                "github_url": { "type": "string" },
+                "x_url": { "type": "string" },
+                "linkedin_url": { "type": "string" },
+                "terms_html": { "type": "string" },
+                "privacy_html": { "type": "string" }
+            }
+        }))
+    }
+
+    async fn on_tenant_installed(&self, txn: &DatabaseTransaction, instance: &app_instance::Model) -> Result<(), DbErr> {
+        JobScheduler::provision(txn, instance.tenant_id, &self.background_jobs()).await
+    }
+
+    async fn on_tenant_uninstalled(&self, txn: &DatabaseTransaction, instance: &app_instance::Model) -> Result<(), DbErr> {
+        JobScheduler::deprovision(txn, instance.tenant_id, &self.background_jobs()).await
+    }
+}
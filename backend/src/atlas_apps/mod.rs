@@ -10,3 +10,20 @@ pub fn get_active_apps() -> Vec<Box<dyn AtlasApp>> {
         Box::new(network_instance::NetworkInstanceApp),
     ]
 }
+
+/// The registered app whose installs carry `app_type` in `app_instances`.
+pub fn find_app(app_type: &str) -> Option<Box<dyn AtlasApp>> {
+    get_active_apps().into_iter().find(|app| app.app_type() == app_type)
+}
+
+/// Ids of the registered apps behind a tenant's installed `app_type`s. Unregistered types are skipped.
+pub fn enabled_app_ids<'a>(app_types: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut app_ids: Vec<String> = app_types
+        .into_iter()
+        .filter_map(find_app)
+        .map(|app| app.app_id().to_string())
+        .collect();
+    app_ids.sort();
+    app_ids.dedup();
+    app_ids
+}
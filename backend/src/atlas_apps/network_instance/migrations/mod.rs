@@ -0,0 +1,13 @@
+pub mod m20230915_create_profiles_table;
+pub mod m20230916_create_categories_table;
+pub mod m20230917_create_templates_table;
+pub mod m20230918_create_listings_table;
+pub mod m20230919_create_listing_attributes_table;
+pub mod m20230920_create_ad_purchases_table;
+pub mod m20240922_create_crm_tables;
+pub mod m20240924_update_listings_nullable_category;
+pub mod m20241001_add_icon_and_slug_to_categories;
+pub mod m20241003_add_slug_to_listings;
+pub mod m20260324_000001_collapse_eav_to_jsonb;
+pub mod m20260326_add_service_area_to_profile;
+pub mod m20260326_create_lead_charges_table;
@@ -1,3 +1,5 @@
+pub mod migrations;
+
 use crate::traits::atlas_app::{AtlasApp, BackgroundJob};
 use axum::Router;
 use sea_orm::DatabaseConnection;
@@ -12,6 +14,11 @@ impl AtlasApp for NetworkInstanceApp {
         "network_instance"
     }
 
+    fn app_type(&self) -> &'static str {
+        // Instances predate app ids and were recorded under the product name
+        "Network"
+    }
+
     fn public_router(&self, db: DatabaseConnection) -> Router<DatabaseConnection> {
         // Expose all Network / Directory specific API routes that are accessible without login
         Router::new()
@@ -23,9 +30,14 @@ impl AtlasApp for NetworkInstanceApp {
     fn authenticated_router(&self, db: DatabaseConnection) -> Router<DatabaseConnection> {
         // Expose all Network / Directory specific API routes that require standard authentication
         Router::new()
-            .merge(crate::handlers::listings::authenticated_routes())
             .merge(crate::handlers::profiles::routes(db.clone()))
             .merge(crate::handlers::ad_purchases::routes())
+    }
+
+    fn api_token_router(&self, _db: DatabaseConnection) -> Router<DatabaseConnection> {
+        // Listing and CRM routes, each group guarded by its API token scope
+        Router::new()
+            .merge(crate::handlers::listings::authenticated_routes())
             .merge(crate::handlers::leads::authenticated_routes())
             .merge(crate::handlers::customers::routes())
             .merge(crate::handlers::contacts::routes())
@@ -33,25 +45,26 @@ impl AtlasApp for NetworkInstanceApp {
             .merge(crate::handlers::activities::routes())
             .merge(crate::handlers::cases::routes())
             .merge(crate::handlers::notes::routes())
+            .merge(crate::handlers::custom_fields::routes())
     }
 
     fn migrations(&self) -> Vec<Box<dyn MigrationTrait>> {
         // Collect ONLY the domain schemas for the Network / Listing / CRM modules as best practice.
         // Note: Legacy Directory migrations have been extracted to the Core Base Migrator since Directory was converted to a Core Tenant Architecture.
         vec![
-            Box::new(crate::migration::m20230915_create_profiles_table::Migration),
-            Box::new(crate::migration::m20230916_create_categories_table::Migration),
-            Box::new(crate::migration::m20230917_create_templates_table::Migration),
-            Box::new(crate::migration::m20230918_create_listings_table::Migration),
-            Box::new(crate::migration::m20230919_create_listing_attributes_table::Migration),
-            Box::new(crate::migration::m20230920_create_ad_purchases_table::Migration),
-            Box::new(crate::migration::m20240922_create_crm_tables::Migration),
-            Box::new(crate::migration::m20240924_update_listings_nullable_category::Migration),
-            Box::new(crate::migration::m20241001_add_icon_and_slug_to_categories::Migration),
-            Box::new(crate::migration::m20241003_add_slug_to_listings::Migration),
-            Box::new(crate::migration::m20260324_000001_collapse_eav_to_jsonb::Migration),
-            Box::new(crate::migration::m20260326_add_service_area_to_profile::Migration),
-            Box::new(crate::migration::m20260326_create_lead_charges_table::Migration),
+            Box::new(migrations::m20230915_create_profiles_table::Migration),
+            Box::new(migrations::m20230916_create_categories_table::Migration),
+            Box::new(migrations::m20230917_create_templates_table::Migration),
+            Box::new(migrations::m20230918_create_listings_table::Migration),
+            Box::new(migrations::m20230919_create_listing_attributes_table::Migration),
+            Box::new(migrations::m20230920_create_ad_purchases_table::Migration),
+            Box::new(migrations::m20240922_create_crm_tables::Migration),
+            Box::new(migrations::m20240924_update_listings_nullable_category::Migration),
+            Box::new(migrations::m20241001_add_icon_and_slug_to_categories::Migration),
+            Box::new(migrations::m20241003_add_slug_to_listings::Migration),
+            Box::new(migrations::m20260324_000001_collapse_eav_to_jsonb::Migration),
+            Box::new(migrations::m20260326_add_service_area_to_profile::Migration),
+            Box::new(migrations::m20260326_create_lead_charges_table::Migration),
         ]
     }
 
@@ -59,4 +72,17 @@ impl AtlasApp for NetworkInstanceApp {
         // Currently NetworkInstance endpoints hook mostly from direct HTTP. Background jobs handle Telemetry which is Core.
         vec![]
     }
+
+    fn settings_schema(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "site_title": { "type": "string" },
+                "contact_email": { "type": "string" },
+                "booking_url": { "type": "string" },
+                "terms_html": { "type": "string" },
+                "privacy_html": { "type": "string" }
+            }
+        }))
+    }
 }
@@ -0,0 +1,38 @@
+use sea_orm::Database;
+use dotenv::dotenv;
+use std::env;
+use uuid::Uuid;
+use atlas_backend::services::secrets::{Keyring, SecretsService};
+
+/// Usage: rotate_secrets [--data-keys [TENANT_ID]]
+///
+/// Always encrypts settings still stored as plaintext and re-wraps data keys under the
+/// current master key (list the old one in SECRETS_RETIRED_MASTER_KEYS). With `--data-keys`
+/// it also replaces the data keys of every tenant, or of TENANT_ID, and re-encrypts their settings.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let rotate_data_keys = args.first().map(String::as_str) == Some("--data-keys");
+    let tenant_id = match args.get(1) {
+        Some(id) if rotate_data_keys => Some(Uuid::parse_str(id)?),
+        _ => None,
+    };
+
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let db = Database::connect(&database_url).await?;
+    let keyring = Keyring::from_env()?;
+
+    let encrypted = SecretsService::encrypt_pending(&db, &keyring).await?;
+    println!("Encrypted {} plaintext secret(s).", encrypted);
+
+    let rewrapped = SecretsService::rotate_master_key(&db, &keyring, None).await?;
+    println!("Re-wrapped {} data key(s) under master key '{}'.", rewrapped, keyring.current_id());
+
+    if rotate_data_keys {
+        let reencrypted = SecretsService::rotate_data_keys(&db, &keyring, tenant_id).await?;
+        println!("Re-encrypted {} secret(s) under new data keys.", reencrypted);
+    }
+
+    Ok(())
+}
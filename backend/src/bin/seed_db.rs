@@ -4,6 +4,7 @@ use uuid::Uuid;
 use chrono::Utc;
 use dotenv::dotenv;
 use atlas_backend::auth::hash_password;
+use atlas_backend::services::deal_pipelines;
 use std::env;
 
 #[tokio::main]
@@ -70,7 +71,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         id: Set(dir_uuid),
         name: Set("CT Build Pros".to_string()),
         description: Set("The premier network for top-rated construction and renovation services across Connecticut.".to_string()),
-        site_status: Set("ACTIVE".to_string()),
+        site_status: Set("active".to_string()),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
         ..Default::default()
@@ -342,8 +343,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 6. CRM Entities
     // We will generate 3 Customers, some Contacts, Leads, Deals, Cases, Activities and Notes
     let mut cust_ids = Vec::new();
-    let statuses = ["Prospecting", "Qualification", "Closed Won", "Closed Lost"];
-    let stages = ["Initial Contact", "Meeting Scheduled", "Proposal Sent", "Contract Signed"];
+    let pipeline = deal_pipelines::default_pipeline(&db, Some(dir_uuid)).await?;
+    let stages = deal_pipelines::stages(&db, pipeline.id).await?;
 
     for j in 0..3 {
         let cust_id = Uuid::new_v4();
@@ -401,27 +402,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         // Deal
         let d_id = Uuid::new_v4();
-        let dl = deal::ActiveModel {
+        let mut dl = deal::ActiveModel {
             id: Set(d_id),
             customer_id: Set(cust_id),
             name: Set(format!("Renovation Deal {}", j)),
             amount: Set(5000.0 * (j as f64 + 1.0)),
-            status: Set(statuses[(j as usize) % statuses.len()].to_string()),
-            stage: Set(stages[(j as usize) % stages.len()].to_string()),
             close_date: Set(Some(Utc::now())),
             is_active: Set(true),
             created_at: Set(Utc::now()),
             updated_at: Set(Utc::now()),
             tenant_id: Set(Some(dir_uuid)),
             properties: Set(None),
+            ..Default::default()
         };
-        dl.insert(&db).await?;
+        let placement = deal_pipelines::Placement {
+            pipeline_id: pipeline.id,
+            stage: stages[(j as usize) % stages.len()].clone(),
+        };
+        placement.apply(&mut dl, Utc::now());
+        let dl = dl.insert(&db).await?;
+        deal_pipelines::record_stage_entry(&db, None, &dl, None).await?;
 
         // Case
         let cs_id = Uuid::new_v4();
         let cs = case::ActiveModel {
             id: Set(cs_id),
             customer_id: Set(cust_id),
+            tenant_id: Set(Some(dir_uuid)),
             title: Set(format!("Support Ticket #{}", j)),
             description: Set("Issue with the latest service.".to_string()),
             status: Set((if j % 2 == 0 { "Open" } else { "Closed" }).to_string()),
@@ -431,6 +438,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             updated_at: Set(Utc::now()),
             closed_at: Set(None),
             properties: Set(None),
+            ..Default::default()
         };
         cs.insert(&db).await?;
 
@@ -444,6 +452,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             lead_id: Set(None),
             contact_id: Set(Some(cnt_id)),
             case_id: Set(None),
+            tenant_id: Set(Some(dir_uuid)),
             activity_type: Set(activity::ActivityType::PhoneCall),
             title: Set("Follow-up Call".to_string()),
             description: Set(Some("Discussed the proposal specs.".to_string())),
@@ -451,10 +460,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             due_date: Set(Some(Utc::now())),
             completed_at: Set(Some(Utc::now())),
             associated_entities: Set(serde_json::json!([])),
-            created_by: Set(admin_user_id),
+            created_by: Set(Some(admin_user_id)),
             assigned_to: Set(Some(admin_user_id)),
             created_at: Set(Utc::now()),
             updated_at: Set(Utc::now()),
+            ..Default::default()
         };
         act.insert(&db).await?;
 
@@ -463,9 +473,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let nt = note::ActiveModel {
             id: Set(nt_id),
             content: Set("Client seemed very interested in premium options.".to_string()),
-            created_by: Set(admin_user_id),
+            created_by: Set(Some(admin_user_id)),
             entity_type: Set("Deal".to_string()),
             entity_id: Set(d_id),
+            tenant_id: Set(Some(dir_uuid)),
             created_at: Set(Utc::now()),
             updated_at: Set(Utc::now()),
         };
@@ -0,0 +1,62 @@
+use sea_orm::Database;
+use dotenv::dotenv;
+use std::env;
+use uuid::Uuid;
+use atlas_backend::services::tenant_archive::{ImportOptions, TenantArchive};
+
+const USAGE: &str = "Usage: tenant_archive export TENANT_ID [FILE]
+       tenant_archive import FILE [--tenant-id TENANT_ID] [--slug SLUG]";
+
+/// Moves tenants between environments (e.g. UAT to production).
+///
+/// `export` writes the tenant to FILE (default `tenant-<TENANT_ID>.tar.gz`). `import` loads
+/// an archive into the database at DATABASE_URL; running it again with the same options
+/// skips the rows it already created.
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let db = Database::connect(&database_url).await?;
+
+    match args.first().map(String::as_str) {
+        Some("export") => {
+            let tenant_id = Uuid::parse_str(args.get(1).ok_or(USAGE)?)?;
+            let path = args.get(2).cloned().unwrap_or_else(|| format!("tenant-{}.tar.gz", tenant_id));
+            let archive = TenantArchive::export(&db, tenant_id).await?;
+            std::fs::write(&path, &archive)?;
+            println!("Exported tenant {} to {} ({} bytes).", tenant_id, path, archive.len());
+        }
+        Some("import") => {
+            let path = args.get(1).ok_or(USAGE)?;
+            let mut options = ImportOptions::default();
+            let mut flags = args[2..].iter();
+            while let Some(flag) = flags.next() {
+                match (flag.as_str(), flags.next()) {
+                    ("--tenant-id", Some(id)) => options.tenant_id = Some(Uuid::parse_str(id)?),
+                    ("--slug", Some(slug)) => options.slug = Some(slug.clone()),
+                    _ => return Err(USAGE.into()),
+                }
+            }
+
+            let report = TenantArchive::import(&db, &std::fs::read(path)?, options).await?;
+            println!("Imported tenant {} (archive format {}).", report.tenant_id, report.format_version);
+            for (entity, counts) in &report.entities {
+                println!(
+                    "  {:<16} {} inserted, {} skipped, {} failed",
+                    entity, counts.inserted, counts.skipped, counts.failed
+                );
+            }
+            for error in &report.errors {
+                eprintln!("  {}", error);
+            }
+            if !report.omitted_secrets.is_empty() {
+                println!("Set these encrypted settings again: {}", report.omitted_secrets.join(", "));
+            }
+        }
+        _ => return Err(USAGE.into()),
+    }
+
+    Ok(())
+}
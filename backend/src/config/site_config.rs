@@ -43,6 +43,22 @@ bitflags! {
     }
 }
 
+impl ModuleFlags {
+    // Flags as persisted in tenant.enabled_modules; unknown bits are dropped
+    pub fn from_db(bits: i32) -> Self {
+        Self::from_bits_truncate(bits as u32)
+    }
+
+    pub fn to_db(&self) -> i32 {
+        self.bits() as i32
+    }
+
+    // Names of the set flags, e.g. ["LISTINGS", "PROFILES"]
+    pub fn names(&self) -> Vec<String> {
+        self.iter_names().map(|(name, _)| name.to_string()).collect()
+    }
+}
+
 // SiteConfig holds all configuration data for a specific network site
 // This structure is used throughout the application to determine site behavior
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -79,6 +95,11 @@ pub struct SiteConfig {
     // - SEO settings
     // - Custom integration settings
     pub custom_settings: HashMap<String, Value>,
+
+    // Ids of the AtlasApps installed for the tenant (e.g. "anchor", "network_instance")
+    // Routes of any other app answer 404 on this site
+    #[serde(default)]
+    pub enabled_apps: Vec<String>,
 }
 
 impl SiteConfig {
@@ -87,4 +108,10 @@ impl SiteConfig {
     pub fn is_module_enabled(&self, module: ModuleFlags) -> bool {
         self.enabled_modules.contains(module)
     }
+
+    // Helper method to check if the tenant has installed an AtlasApp
+    // Usage: site_config.is_app_enabled("anchor")
+    pub fn is_app_enabled(&self, app_id: &str) -> bool {
+        self.enabled_apps.iter().any(|enabled| enabled == app_id)
+    }
 }
@@ -18,6 +18,7 @@ pub struct Model {
     pub lead_id: Option<Uuid>,
     pub contact_id: Option<Uuid>,
     pub case_id: Option<Uuid>,
+    pub tenant_id: Option<Uuid>,
     pub activity_type: ActivityType,
     pub title: String,
     pub description: Option<String>,
@@ -26,12 +27,19 @@ pub struct Model {
     pub completed_at: Option<DateTime<Utc>>,
     #[sea_orm(column_type = "Json")]
     pub associated_entities: Json,
-    pub created_by: Uuid,
+    pub created_by: Option<Uuid>,
     pub assigned_to: Option<Uuid>,
     #[sea_orm(column_type = "TimestampWithTimeZone")]
     pub created_at: DateTime<Utc>,
     #[sea_orm(column_type = "TimestampWithTimeZone")]
     pub updated_at: DateTime<Utc>,
+    pub recurrence: Option<String>,  // "daily", "weekly" or "monthly"
+    pub recurrence_interval: i32,  // Repeats every this many periods
+    pub recurrence_ends_at: Option<DateTime<Utc>>,
+    pub series_id: Option<Uuid>,  // First occurrence of a recurring activity
+    pub snooze_count: i32,
+    pub upcoming_notified_at: Option<DateTime<Utc>>,
+    pub overdue_notified_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
@@ -8,10 +8,13 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: Uuid,
     pub tenant_id: Uuid,
+    #[serde(skip_serializing)]
     pub token_hash: String,
+    pub token_prefix: Option<String>,
     #[sea_orm(column_type = "JsonBinary")]
     pub scopes: Value,
     pub expires_at: Option<DateTimeWithTimeZone>,
+    pub last_used_at: Option<DateTimeWithTimeZone>,
     pub created_at: Option<DateTimeWithTimeZone>,
     pub updated_at: Option<DateTimeWithTimeZone>,
 }
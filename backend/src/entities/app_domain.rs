@@ -12,6 +12,17 @@ pub struct Model {
     pub domain_name: String,
     #[sea_orm(column_type = "TimestampWithTimeZone")]
     pub created_at: DateTime<Utc>,
+    /// `pending_verification`, `verifying`, `active` or `failed`; only active domains are served.
+    pub status: String,
+    /// Expected in the `_atlas-challenge` TXT record proving ownership.
+    pub verification_token: Option<String>,
+    /// The edge provider's id for the custom hostname, once provisioned.
+    pub provider_hostname_id: Option<String>,
+    pub ssl_status: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_error: Option<String>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub verified_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -15,6 +15,11 @@ pub struct Model {
     pub database_url: Option<String>,
     #[sea_orm(nullable)]
     pub data_seed_name: Option<String>,
+    /// Version of the `data_seed_name` pack last applied.
+    #[sea_orm(nullable)]
+    pub data_seed_version: Option<i32>,
+    #[sea_orm(column_type = "TimestampWithTimeZone", nullable)]
+    pub data_seed_applied_at: Option<DateTime<Utc>>,
     #[sea_orm(column_type = "JsonBinary", nullable)]
     pub settings: Option<Value>,
     #[sea_orm(column_type = "TimestampWithTimeZone")]
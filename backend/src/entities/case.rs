@@ -14,6 +14,7 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: Uuid,
     pub customer_id: Uuid,
+    pub tenant_id: Option<Uuid>,
     pub title: String,
     pub description: String,
     pub status: String,
@@ -27,6 +28,21 @@ pub struct Model {
     pub closed_at: Option<DateTime<Utc>>,
     #[sea_orm(column_type = "JsonBinary", nullable)]
     pub properties: Option<Value>,
+    // SLA targets from the priority's policy when the case was opened
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub first_response_due_at: Option<DateTime<Utc>>,
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub resolution_due_at: Option<DateTime<Utc>>,
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub first_responded_at: Option<DateTime<Utc>>,
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub resolved_at: Option<DateTime<Utc>>,
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub response_escalated_at: Option<DateTime<Utc>>,
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub resolution_escalated_at: Option<DateTime<Utc>>,
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub reopened_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter)]
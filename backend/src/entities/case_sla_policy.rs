@@ -0,0 +1,26 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// First response and resolution targets for a tenant's cases of one priority.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "case_sla_policy")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub tenant_id: Option<Uuid>,
+    pub priority: String,
+    pub first_response_minutes: i32,
+    pub resolution_minutes: i32,
+    pub escalate_to: Option<Uuid>,  // Breached cases are reassigned to this user
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub created_at: DateTime<Utc>,
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// A tenant-defined key of a lead's, deal's, customer's, contact's, case's or profile's
+/// `properties`.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "custom_field_definition")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub tenant_id: Option<Uuid>,
+    pub entity_type: String,
+    pub key: String,
+    pub label: String,
+    pub field_type: String,
+    pub required: bool,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub options: Option<Value>,  // Allowed values of a select field
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub default_value: Option<Value>,
+    pub position: i32,
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub created_at: DateTime<Utc>,
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -47,7 +47,7 @@ pub struct Model {
     pub properties: Option<Value>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
 pub struct CustomerAttributes {
     pub shipper: bool,
     pub carrier: bool,
@@ -29,6 +29,9 @@ pub struct Model {
     pub tenant_id: Option<Uuid>,
     #[sea_orm(column_type = "JsonBinary", nullable)]
     pub properties: Option<Value>,
+    pub pipeline_id: Option<Uuid>,
+    pub stage_id: Option<Uuid>,  // Stage definition `stage` was taken from
+    pub stage_entered_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter)]
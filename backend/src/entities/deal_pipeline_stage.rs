@@ -0,0 +1,44 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "deal_pipeline_stage")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub pipeline_id: Uuid,
+    pub name: String,
+    pub position: i32,  // Order within the pipeline, starting at 0
+    pub probability: i32,  // Chance (0-100) that a deal in this stage is won
+    pub outcome: String,  // "open", "won" or "lost"
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub created_at: DateTime<Utc>,
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Pipeline,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Pipeline => Entity::belongs_to(super::deal_pipeline::Entity)
+                .from(Column::PipelineId)
+                .to(super::deal_pipeline::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::deal_pipeline::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Pipeline.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,48 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// One row per stage a deal entered, including the stage it was created in. Stage names are
+/// copied so the history still reads correctly after a stage is renamed or removed.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "deal_stage_history")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub deal_id: Uuid,
+    pub tenant_id: Option<Uuid>,
+    pub pipeline_id: Option<Uuid>,
+    pub from_stage_id: Option<Uuid>,
+    pub to_stage_id: Option<Uuid>,
+    pub from_stage: Option<String>,
+    pub to_stage: String,
+    pub amount: f64,  // Deal amount when the stage was entered
+    pub changed_by: Option<Uuid>,
+    #[sea_orm(column_type = "TimestampWithTimeZone")]
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter)]
+pub enum Relation {
+    Deal,
+}
+
+impl RelationTrait for Relation {
+    fn def(&self) -> RelationDef {
+        match self {
+            Self::Deal => Entity::belongs_to(super::deal::Entity)
+                .from(Column::DealId)
+                .to(super::deal::Column::Id)
+                .into(),
+        }
+    }
+}
+
+impl Related<super::deal::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Deal.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
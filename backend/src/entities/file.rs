@@ -23,6 +23,7 @@ pub struct Model {
     pub date_last_view: Option<DateTimeWithTimeZone>,
     pub is_anonymous: bool,
     pub user_id: Option<String>,
+    pub tenant_id: Option<Uuid>,
 }
 
 #[derive(Debug, Display, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
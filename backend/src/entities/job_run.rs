@@ -0,0 +1,43 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "job_runs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub tenant_id: Uuid,
+    pub job_type: String,
+    /// One of `running`, `succeeded`, `failed` or `timed_out`.
+    pub status: String,
+    /// Replica that claimed the run.
+    pub worker_id: String,
+    pub started_at: DateTimeWithTimeZone,
+    /// A `running` row past this instant belongs to a worker that died and no longer counts towards concurrency.
+    pub lease_expires_at: DateTimeWithTimeZone,
+    pub finished_at: Option<DateTimeWithTimeZone>,
+    pub duration_ms: Option<i64>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub error: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::tenant_background_job::Entity",
+        from = "Column::JobId",
+        to = "super::tenant_background_job::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    TenantBackgroundJob,
+}
+
+impl Related<super::tenant_background_job::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TenantBackgroundJob.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
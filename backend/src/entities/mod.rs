@@ -2,14 +2,19 @@
 pub mod lead;
 pub mod deal;
 pub mod deal_contact;
+pub mod deal_pipeline;
+pub mod deal_pipeline_stage;
+pub mod deal_stage_history;
 pub mod customer;
 pub mod contact;
 pub mod activity;
 pub mod case;
+pub mod case_sla_policy;
 pub mod file;
 pub mod file_association;
 pub mod note;
 pub mod lead_charge;
+pub mod custom_field_definition;
 
 //DIRECTORIES
 pub mod user;
@@ -33,6 +38,7 @@ pub mod attachment;
 pub mod passkey;
 pub mod magic_link_token;
 pub mod tenant_setting;
+pub mod tenant_data_key;
 pub mod audit_log;
 // MULTI-TENANT ARCHITECTURE
 pub mod app_instance;
@@ -54,8 +60,10 @@ pub mod platform_metrics_daily;
 pub mod api_token;
 pub mod webhook_endpoint;
 pub mod webhook_delivery;
+pub mod outbox;
 
 // ANCHOR APP LEGACY
 pub mod page_view;
 pub mod bitcoin_block;
 pub mod tenant_background_job;
+pub mod job_run;
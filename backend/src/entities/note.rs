@@ -12,9 +12,10 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: Uuid,
     pub content: String,
-    pub created_by: Uuid,
+    pub created_by: Option<Uuid>,
     pub entity_type: String,
     pub entity_id: Uuid,
+    pub tenant_id: Option<Uuid>,
     #[sea_orm(column_type = "TimestampWithTimeZone")]
     pub created_at: DateTime<Utc>,
     #[sea_orm(column_type = "TimestampWithTimeZone")]
@@ -120,7 +121,7 @@ impl Related<super::activity::Entity> for Entity {
 impl ActiveModelBehavior for ActiveModel {}
 
 impl Model {
-    pub fn new(content: String, created_by: Uuid, entity_type: String, entity_id: Uuid) -> Self {
+    pub fn new(content: String, created_by: Option<Uuid>, entity_type: String, entity_id: Uuid, tenant_id: Option<Uuid>) -> Self {
         let now = Utc::now();
         Self {
             id: Uuid::new_v4(),
@@ -128,6 +129,7 @@ impl Model {
             created_by,
             entity_type,
             entity_id,
+            tenant_id,
             created_at: now,
             updated_at: now,
         }
@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "outbox")]
+pub struct Model {
+    /// Doubles as the idempotency key for everything the relay writes for this message.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// One of `audit`, `telemetry` or `webhook`.
+    pub kind: String,
+    pub tenant_id: Option<Uuid>,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub payload: Value,
+    /// One of `pending`, `processed` or `failed` (relay attempts exhausted).
+    pub status: String,
+    pub attempts: i32,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_error: Option<String>,
+    pub available_at: DateTimeWithTimeZone,
+    pub processed_at: Option<DateTimeWithTimeZone>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
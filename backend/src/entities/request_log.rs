@@ -20,6 +20,10 @@ pub struct Model {
     pub created_at: DateTime<Utc>,
     pub request_status: RequestStatus,
     pub failure_reason: Option<String>,
+    /// Correlation ID, also returned in the `X-Request-Id` response header.
+    pub request_id: Option<String>,
+    pub duration_ms: Option<i32>,
+    pub response_bytes: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter)]
@@ -12,6 +12,7 @@ pub struct Model {
     pub id: Uuid,
     pub name: String,
     pub description: String,
+    /// Lifecycle state; see `services::tenant` for the allowed transitions.
     pub site_status: String,
     #[sea_orm(unique, nullable)]
     pub slug: Option<String>,
@@ -41,6 +42,17 @@ pub struct Model {
     pub page_keywords: Option<String>,
     #[sea_orm(column_type = "Text", nullable)]
     pub canonical_url: Option<String>,
+    /// `ModuleFlags` bits of the modules enabled on the tenant's sites.
+    pub enabled_modules: i32,
+    #[sea_orm(column_type = "TimestampWithTimeZone", nullable)]
+    pub suspended_at: Option<DateTime<Utc>>,
+    #[sea_orm(column_type = "TimestampWithTimeZone", nullable)]
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
+    /// When a tenant scheduled for deletion becomes eligible for the purge job.
+    #[sea_orm(column_type = "TimestampWithTimeZone", nullable)]
+    pub purge_after: Option<DateTime<Utc>>,
+    #[sea_orm(column_type = "TimestampWithTimeZone", nullable)]
+    pub purged_at: Option<DateTime<Utc>>,
     #[sea_orm(column_type = "TimestampWithTimeZone")]
     pub created_at: DateTime<Utc>,
     #[sea_orm(column_type = "TimestampWithTimeZone")]
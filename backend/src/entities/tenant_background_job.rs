@@ -12,6 +12,12 @@ pub struct Model {
     pub interval_seconds: i32,
     pub last_run: Option<DateTimeWithTimeZone>,
     pub is_active: bool,
+    /// Standard 5-field (or 6-field with seconds) cron expression; takes precedence over `interval_seconds`.
+    pub cron_expression: Option<String>,
+    /// `None` means due immediately.
+    pub next_run_at: Option<DateTimeWithTimeZone>,
+    pub max_concurrency: i32,
+    pub timeout_seconds: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -24,6 +30,8 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Tenant,
+    #[sea_orm(has_many = "super::job_run::Entity")]
+    JobRun,
 }
 
 impl Related<super::tenant::Entity> for Entity {
@@ -32,4 +40,10 @@ impl Related<super::tenant::Entity> for Entity {
     }
 }
 
+impl Related<super::job_run::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::JobRun.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -13,6 +13,8 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub value: String,
     pub is_encrypted: bool,
+    /// Data key the value is encrypted with; `None` while the value is plaintext.
+    pub data_key_id: Option<Uuid>,
     #[sea_orm(column_type = "TimestampWithTimeZone")]
     pub updated_at: DateTime<Utc>,
     #[sea_orm(column_type = "TimestampWithTimeZone")]
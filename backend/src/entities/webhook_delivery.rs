@@ -12,6 +12,7 @@ pub struct Model {
     pub event_type: String,
     #[sea_orm(column_type = "JsonBinary")]
     pub payload: Value,
+    /// One of `pending`, `sent`, `failed` (retry scheduled) or `dead_letter` (retries exhausted).
     pub status: String,
     pub next_retry_at: Option<DateTimeWithTimeZone>,
     pub attempts: i32,
@@ -20,6 +21,8 @@ pub struct Model {
     pub response_body: Option<String>,
     pub created_at: Option<DateTimeWithTimeZone>,
     pub updated_at: Option<DateTimeWithTimeZone>,
+    /// `<outbox id>:<endpoint id>` for deliveries fanned out by the outbox relay.
+    pub idempotency_key: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
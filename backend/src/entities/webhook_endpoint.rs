@@ -13,6 +13,9 @@ pub struct Model {
     #[sea_orm(column_type = "JsonBinary")]
     pub subscribed_events: Value,
     pub is_active: bool,
+    pub consecutive_failures: i32,
+    pub failure_threshold: i32,
+    pub disabled_at: Option<DateTimeWithTimeZone>,
     pub created_at: Option<DateTimeWithTimeZone>,
     pub updated_at: Option<DateTimeWithTimeZone>,
 }
@@ -7,30 +7,128 @@ use axum::{
 };
 use sea_orm::{
     DatabaseConnection, EntityTrait, QueryFilter, Set, ColumnTrait,
-    ActiveModelTrait, ModelTrait, PaginatorTrait,
+    ActiveModelTrait, ModelTrait, PaginatorTrait, PrimaryKeyTrait, QuerySelect, TransactionTrait,
 };
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{TimeDelta, Utc};
 use std::collections::HashMap;
-use crate::entities::{activity, user};
-use crate::models::activity::{ActivityModel, CreateActivityInput, UpdateActivityInput};
+use crate::middleware::api_token::require_api_scope;
+use crate::middleware::permissions::{Permission, TenantAccess};
+use crate::entities::{account, activity, case, contact, customer, deal, lead};
+use crate::entities::activity::{ActivityStatus, AssociatedEntityType};
+use crate::models::activity::{
+    ActivityModel, CreateActivityInput, UpdateActivityInput, SnoozeActivityInput, CompleteActivityResult,
+    MyTasksQuery, MyTask, MyTasks, MyTasksSummary, RelatedRecord,
+};
 use crate::models::file::FileAssociation;
+use crate::services::activity_tasks::{self, TaskError, TaskService};
 
 pub fn routes() -> Router<DatabaseConnection> {
     Router::new()
         .route("/api/activities", post(create_activity))
         .route("/api/activities", get(get_activities))
+        .route("/api/activities/mine", get(get_my_tasks))
         .route("/api/activities/{id}", get(get_activity))
         .route("/api/activities/{id}", put(update_activity))
         .route("/api/activities/{id}", delete(delete_activity))
         .route("/api/activities/{id}/files", get(get_activity_files))
+        .route("/api/activities/{id}/snooze", post(snooze_activity))
+        .route("/api/activities/{id}/complete", post(complete_activity))
+        .route_layer(axum::middleware::from_fn_with_state("crm", require_api_scope))
+}
+
+/// Loads an activity visible to the caller; other tenants' activities are reported as missing.
+async fn find_activity(db: &DatabaseConnection, access: &TenantAccess, id: Uuid) -> Result<activity::Model, StatusCode> {
+    access
+        .scope(activity::Entity::find_by_id(id), activity::Column::TenantId)
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Fails with 404 unless the record an activity links to exists in `tenant_id`. Unscoped
+/// platform admins (`None`) may link any existing record.
+async fn ensure_linkable<E: EntityTrait>(
+    db: &DatabaseConnection,
+    tenant_id: Option<Uuid>,
+    id: Option<Uuid>,
+    tenant_column: E::Column,
+) -> Result<(), StatusCode>
+where
+    Uuid: Into<<E::PrimaryKey as PrimaryKeyTrait>::ValueType>,
+{
+    let Some(id) = id else {
+        return Ok(());
+    };
+    let mut select = E::find_by_id(id);
+    if let Some(tenant_id) = tenant_id {
+        select = select.filter(tenant_column.eq(tenant_id));
+    }
+    select
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(())
+}
+
+/// The records an activity can hang off.
+struct ActivityLinks {
+    account_id: Option<Uuid>,
+    deal_id: Option<Uuid>,
+    customer_id: Option<Uuid>,
+    lead_id: Option<Uuid>,
+    contact_id: Option<Uuid>,
+    case_id: Option<Uuid>,
+}
+
+impl ActivityLinks {
+    /// Checks that every linked record belongs to the activity's tenant, so an activity can't
+    /// point into another tenant.
+    async fn ensure_in_tenant(&self, db: &DatabaseConnection, tenant_id: Option<Uuid>) -> Result<(), StatusCode> {
+        ensure_linkable::<account::Entity>(db, tenant_id, self.account_id, account::Column::TenantId).await?;
+        ensure_linkable::<deal::Entity>(db, tenant_id, self.deal_id, deal::Column::TenantId).await?;
+        ensure_linkable::<customer::Entity>(db, tenant_id, self.customer_id, customer::Column::TenantId).await?;
+        ensure_linkable::<lead::Entity>(db, tenant_id, self.lead_id, lead::Column::TenantId).await?;
+        ensure_linkable::<contact::Entity>(db, tenant_id, self.contact_id, contact::Column::TenantId).await?;
+        ensure_linkable::<case::Entity>(db, tenant_id, self.case_id, case::Column::TenantId).await
+    }
+}
+
+pub(crate) fn task_error(e: TaskError) -> StatusCode {
+    match e {
+        TaskError::Closed => StatusCode::CONFLICT,
+        TaskError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        TaskError::Db(e) => {
+            tracing::error!("Activity task update failed: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
 }
 
 pub async fn create_activity(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Json(input): Json<CreateActivityInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+    if let Some(recurrence) = &input.recurrence {
+        activity_tasks::validate_recurrence(recurrence, input.due_date).map_err(task_error)?;
+    }
+    if let Some(assignee) = input.assigned_to {
+        TaskService::ensure_assignable(&db, access.tenant_id, assignee).await.map_err(task_error)?;
+    }
+    ActivityLinks {
+        account_id: Some(input.account_id),
+        deal_id: input.deal_id,
+        customer_id: input.customer_id,
+        lead_id: input.lead_id,
+        contact_id: input.contact_id,
+        case_id: input.case_id,
+    }
+    .ensure_in_tenant(&db, access.tenant_id)
+    .await?;
     let new_activity = activity::ActiveModel {
         id: Set(Uuid::new_v4()),
         account_id: Set(Some(input.account_id)),
@@ -39,6 +137,7 @@ pub async fn create_activity(
         lead_id: Set(input.lead_id),
         contact_id: Set(input.contact_id),
         case_id: Set(input.case_id),
+        tenant_id: Set(access.tenant_id),
         activity_type: Set(input.activity_type),
         title: Set(input.title),
         description: Set(input.description),
@@ -46,14 +145,19 @@ pub async fn create_activity(
         due_date: Set(input.due_date),
         completed_at: Set(None),
         associated_entities: Set(serde_json::to_value(input.associated_entities).unwrap()),
-        created_by: Set(current_user.id),
+        created_by: Set(access.user_id),
         assigned_to: Set(input.assigned_to),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
+        recurrence: Set(input.recurrence.as_ref().map(|recurrence| recurrence.frequency.as_str().to_string())),
+        recurrence_interval: Set(input.recurrence.as_ref().map_or(1, |recurrence| recurrence.interval)),
+        recurrence_ends_at: Set(input.recurrence.as_ref().and_then(|recurrence| recurrence.until)),
+        ..Default::default()
     };
 
     let activity = new_activity.insert(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
+    TaskService::ensure_reminders(&db, &activity).await.map_err(|e| task_error(e.into()))?;
+
     // Associate files with the activity
     for file_id in input.files {
         activity.add_file(&db, file_id.id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -64,9 +168,10 @@ pub async fn create_activity(
 
 pub async fn get_activities(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let mut query = activity::Entity::find();
+    let mut query = access.scope(activity::Entity::find(), activity::Column::TenantId);
 
     // Add filters based on query parameters
     if let Some(account_id) = params.get("account_id") {
@@ -85,23 +190,32 @@ pub async fn get_activities(
 
 pub async fn get_activity(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let activity = activity::Entity::find_by_id(id).one(&db).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    let activity = find_activity(&db, &access, id).await?;
 
     Ok(Json(activity))
 }
 
 pub async fn update_activity(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<UpdateActivityInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let activity = activity::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::NOT_FOUND)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let activity = find_activity(&db, &access, id).await?;
+    ActivityLinks {
+        account_id: None,
+        deal_id: input.deal_id,
+        customer_id: input.customer_id,
+        lead_id: input.lead_id,
+        contact_id: input.contact_id,
+        case_id: input.case_id,
+    }
+    .ensure_in_tenant(&db, activity.tenant_id)
+    .await?;
 
     // Create an ActiveModel from the existing model
     let mut activity_active: activity::ActiveModel = activity.clone().into();
@@ -137,6 +251,19 @@ pub async fn update_activity(
     if let Some(due_date) = input.due_date {
         activity_active.due_date = Set(Some(due_date));
     }
+    if let Some(recurrence) = &input.recurrence {
+        activity_tasks::validate_recurrence(recurrence, input.due_date.or(activity.due_date)).map_err(task_error)?;
+        activity_active.recurrence = Set(Some(recurrence.frequency.as_str().to_string()));
+        activity_active.recurrence_interval = Set(recurrence.interval);
+        activity_active.recurrence_ends_at = Set(recurrence.until);
+    }
+    // A new due date or assignee hears about the task afresh
+    if input.due_date.is_some_and(|due_date| Some(due_date) != activity.due_date)
+        || input.assigned_to.is_some_and(|assignee| Some(assignee) != activity.assigned_to)
+    {
+        activity_active.upcoming_notified_at = Set(None);
+        activity_active.overdue_notified_at = Set(None);
+    }
     if let Some(completed_at) = input.completed_at {
         activity_active.completed_at = Set(Some(completed_at));
     }
@@ -144,6 +271,7 @@ pub async fn update_activity(
         activity_active.associated_entities = Set(serde_json::to_value(associated_entities).unwrap());
     }
     if let Some(assigned_to) = input.assigned_to {
+        TaskService::ensure_assignable(&db, activity.tenant_id, assigned_to).await.map_err(task_error)?;
         activity_active.assigned_to = Set(Some(assigned_to));
     }
     
@@ -153,32 +281,28 @@ pub async fn update_activity(
         .update(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    TaskService::ensure_reminders(&db, &updated_activity).await.map_err(|e| task_error(e.into()))?;
 
     Ok(JsonResponse(ActivityModel::from(updated_activity)))
 }
 
 pub async fn delete_activity(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let activity = activity::Entity::find_by_id(id).one(&db).await.map_err(|_| StatusCode::NOT_FOUND)?;
-    if let Some(activity) = activity {
-        activity.delete(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    } else {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    access.require(Permission::Delete)?;
+    let activity = find_activity(&db, &access, id).await?;
+    activity.delete(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     Ok(())
 }
 
 pub async fn get_activity_files(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let activity = activity::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let activity = find_activity(&db, &access, id).await?;
 
     let files = activity.get_associated_files(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -187,9 +311,157 @@ pub async fn get_activity_files(
 
 pub async fn get_activity_notes(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let activity = activity::Entity::find_by_id(id).one(&db).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    let activity = find_activity(&db, &access, id).await?;
 
     Ok(Json(activity))
 }
+
+/// Pushes an open activity's due date to `until`, or `minutes` from now, and re-arms its reminders.
+pub async fn snooze_activity(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Path(id): Path<Uuid>,
+    Json(input): Json<SnoozeActivityInput>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+    let activity = find_activity(&db, &access, id).await?;
+
+    let now = Utc::now();
+    let until = match (input.until, input.minutes) {
+        (Some(until), _) => until,
+        (None, Some(minutes)) => TimeDelta::try_minutes(minutes)
+            .and_then(|snooze| now.checked_add_signed(snooze))
+            .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?,
+        (None, None) => return Err(StatusCode::UNPROCESSABLE_ENTITY),
+    };
+    let snoozed = TaskService::snooze(&db, activity, until, now).await.map_err(task_error)?;
+
+    Ok(JsonResponse(ActivityModel::from(snoozed)))
+}
+
+/// Completes an open activity. Recurring activities return their next occurrence as well.
+pub async fn complete_activity(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+    let activity = find_activity(&db, &access, id).await?;
+
+    // Locked so two concurrent completions can't both create the next occurrence
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let activity = activity::Entity::find_by_id(activity.id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let (completed, next) = TaskService::complete(&txn, activity, Utc::now()).await.map_err(task_error)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(JsonResponse(CompleteActivityResult {
+        completed: ActivityModel::from(completed),
+        next: next.map(ActivityModel::from),
+    }))
+}
+
+/// Names of the leads, deals, cases, customers and contacts the activities belong to, keyed by id.
+async fn related_names(
+    db: &DatabaseConnection,
+    access: &TenantAccess,
+    activities: &[activity::Model],
+) -> Result<HashMap<Uuid, String>, StatusCode> {
+    let ids = |column: fn(&activity::Model) -> Option<Uuid>| -> Vec<Uuid> { activities.iter().filter_map(column).collect() };
+    let mut names = HashMap::new();
+
+    let leads = access
+        .scope(lead::Entity::find().filter(lead::Column::Id.is_in(ids(|a| a.lead_id))), lead::Column::TenantId)
+        .all(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    names.extend(leads.into_iter().map(|lead| (lead.id, lead.name)));
+    let deals = access
+        .scope(deal::Entity::find().filter(deal::Column::Id.is_in(ids(|a| a.deal_id))), deal::Column::TenantId)
+        .all(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    names.extend(deals.into_iter().map(|deal| (deal.id, deal.name)));
+    let cases = access
+        .scope(case::Entity::find().filter(case::Column::Id.is_in(ids(|a| a.case_id))), case::Column::TenantId)
+        .all(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    names.extend(cases.into_iter().map(|case| (case.id, case.title)));
+    let customers = access
+        .scope(customer::Entity::find().filter(customer::Column::Id.is_in(ids(|a| a.customer_id))), customer::Column::TenantId)
+        .all(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    names.extend(customers.into_iter().map(|customer| (customer.id, customer.name)));
+    let contacts = access
+        .scope(contact::Entity::find().filter(contact::Column::Id.is_in(ids(|a| a.contact_id))), contact::Column::TenantId)
+        .all(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    names.extend(contacts.into_iter().map(|contact| (contact.id, contact.name)));
+
+    Ok(names)
+}
+
+/// The current user's open activities across leads, deals, cases and customers, with the
+/// records they belong to and counts of what is overdue, due today and coming up.
+pub async fn get_my_tasks(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Query(query): Query<MyTasksQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    // API tokens act for the tenant rather than a person, so they have no task list.
+    let user_id = access.user_id.ok_or(StatusCode::FORBIDDEN)?;
+    let mut select = access
+        .scope(activity::Entity::find(), activity::Column::TenantId)
+        .filter(activity::Column::AssignedTo.eq(user_id));
+    if !query.include_closed {
+        select = select.filter(activity::Column::Status.is_in([ActivityStatus::Pending, ActivityStatus::InProgress]));
+    }
+    if let Some(due_before) = query.due_before {
+        select = select.filter(activity::Column::DueDate.lt(due_before));
+    }
+    let mut activities = select.all(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    activities.sort_by_key(|activity| (activity.due_date.is_none(), activity.due_date));
+    let names = related_names(&db, &access, &activities).await?;
+
+    let now = Utc::now();
+    let mut summary = MyTasksSummary::default();
+    let mut tasks = Vec::with_capacity(activities.len());
+    for activity in activities {
+        let open = activity_tasks::is_open(&activity);
+        let overdue = open && activity.due_date.is_some_and(|due| due <= now);
+        if open {
+            match activity.due_date {
+                None => summary.unscheduled += 1,
+                Some(_) if overdue => summary.overdue += 1,
+                Some(due) if due.date_naive() == now.date_naive() => summary.due_today += 1,
+                Some(_) => summary.upcoming += 1,
+            }
+        }
+
+        let related = [
+            (AssociatedEntityType::Lead, activity.lead_id),
+            (AssociatedEntityType::Deal, activity.deal_id),
+            (AssociatedEntityType::Case, activity.case_id),
+            (AssociatedEntityType::Customer, activity.customer_id),
+            (AssociatedEntityType::Contact, activity.contact_id),
+        ]
+        .into_iter()
+        .filter_map(|(entity_type, id)| {
+            id.map(|entity_id| RelatedRecord { entity_type, entity_id, name: names.get(&entity_id).cloned() })
+        })
+        .collect();
+        tasks.push(MyTask { activity: ActivityModel::from(activity), related, overdue });
+    }
+
+    Ok(JsonResponse(MyTasks { summary, tasks }))
+}
@@ -15,6 +15,8 @@ use crate::entities::{
     ad_purchase, profile, user_account, user,
 };
 use crate::models::ad_purchase::*;
+use crate::config::ModuleFlags;
+use crate::middleware::app_gate::require_module_enabled;
 use uuid::Uuid;
 use chrono::Utc;
 
@@ -22,6 +24,8 @@ pub fn routes() -> Router<DatabaseConnection> {
     Router::new()
         .route("/api/ad-purchases", post(create_ad_purchase).get(get_ad_purchases))
         .route("/api/ad-purchases/{id}", get(get_ad_purchase_by_id).put(update_ad_purchase).delete(delete_ad_purchase))
+        // Ad purchases are paid placements
+        .route_layer(axum::middleware::from_fn_with_state(ModuleFlags::PAYMENTS, require_module_enabled))
 }
 
 pub async fn create_ad_purchase(
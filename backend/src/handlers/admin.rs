@@ -7,11 +7,12 @@ use axum::{
     http::StatusCode,
     response::IntoResponse,
 };
-use sea_orm::{DatabaseConnection, EntityTrait,QuerySelect, QueryFilter,Order, ColumnTrait,QueryOrder, Set, ActiveModelTrait, PaginatorTrait};
+use sea_orm::{DatabaseConnection, EntityTrait,QuerySelect, QueryFilter,Order, ColumnTrait,QueryOrder, Set, ActiveModelTrait, PaginatorTrait, TransactionTrait};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::models::listing::ListingStatus;
 use crate::models::user::UserAdminView;
+use crate::models::request_log::RequestType;
 use crate::models::ad_purchase::AdStatus;
 
 use std::collections::HashMap;
@@ -73,6 +74,10 @@ pub struct PlatformAppModel {
     pub instance_id: String,
     pub name: String,
     pub app_type: String,
+    /// Registered `AtlasApp` serving this instance, if any.
+    pub app_id: Option<String>,
+    /// Apps whose routes are reachable on the tenant's sites.
+    pub enabled_apps: Vec<String>,
     pub domain: String,
     pub site_status: String,
     pub description: String,
@@ -98,9 +103,18 @@ pub async fn get_platform_apps(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+    let mut app_types_by_tenant: HashMap<Uuid, Vec<&str>> = HashMap::new();
+    for (instance, _) in &instances {
+        app_types_by_tenant.entry(instance.tenant_id).or_default().push(instance.app_type.as_str());
+    }
+    let enabled_by_tenant: HashMap<Uuid, Vec<String>> = app_types_by_tenant
+        .into_iter()
+        .map(|(tenant_id, app_types)| (tenant_id, crate::atlas_apps::enabled_app_ids(app_types)))
+        .collect();
+
     let mut result = Vec::new();
     
-    for (instance, tenant_opt) in instances {
+    for (instance, tenant_opt) in &instances {
         if let Some(tenant_model) = tenant_opt {
             let domains = app_domain::Entity::find()
                 .filter(app_domain::Column::AppInstanceId.eq(instance.id))
@@ -115,6 +129,8 @@ pub async fn get_platform_apps(
                 instance_id: instance.id.to_string(),
                 name: tenant_model.name.clone(),
                 app_type: instance.app_type.clone(),
+                app_id: crate::atlas_apps::find_app(&instance.app_type).map(|app| app.app_id().to_string()),
+                enabled_apps: enabled_by_tenant.get(&instance.tenant_id).cloned().unwrap_or_default(),
                 domain: domain_name,
                 site_status: tenant_model.site_status.clone(),
                 description: tenant_model.description.clone(),
@@ -149,50 +165,141 @@ pub async fn get_app_domains(
     Ok(Json(domain_strings))
 }
 
+#[derive(Serialize)]
+pub struct AppDomainResponse {
+    #[serde(flatten)]
+    pub domain: crate::entities::app_domain::Model,
+    /// Records the customer can publish to prove ownership while the domain is pending.
+    pub verification_records: Vec<crate::services::dns::DnsRecord>,
+    /// Records that route the domain's traffic to the instance's edge provider.
+    pub routing_records: Vec<crate::services::dns::DnsRecord>,
+}
+
+impl AppDomainResponse {
+    fn new(domain: crate::entities::app_domain::Model, provider: Option<&dyn crate::services::dns::DnsProvider>) -> Self {
+        use crate::services::custom_domains::{CustomDomainService, STATUS_PENDING_VERIFICATION};
+        let verification_records = if domain.status == STATUS_PENDING_VERIFICATION {
+            CustomDomainService::verification_records(&domain)
+        } else {
+            Vec::new()
+        };
+        let routing_records = provider
+            .map(|provider| provider.routing_records(&domain.domain_name))
+            .unwrap_or_default();
+        Self { domain, verification_records, routing_records }
+    }
+}
+
+fn domain_error(e: crate::services::custom_domains::DomainError) -> StatusCode {
+    use crate::services::custom_domains::DomainError;
+    match e {
+        DomainError::Invalid(_) => StatusCode::BAD_REQUEST,
+        DomainError::Db(ref db_err) if matches!(db_err.sql_err(), Some(sea_orm::SqlErr::UniqueConstraintViolation(_))) => {
+            StatusCode::CONFLICT
+        }
+        DomainError::Provider(e) => {
+            tracing::error!("DNS edge provider failed: {}", e);
+            StatusCode::BAD_GATEWAY
+        }
+        DomainError::Db(e) => {
+            tracing::error!("Database error managing domain: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+fn dns_provider(
+    instance: &crate::entities::app_instance::Model,
+) -> Result<Option<Box<dyn crate::services::dns::DnsProvider>>, StatusCode> {
+    crate::services::dns::provider_for_instance(instance).map_err(|e| {
+        tracing::error!("DNS edge provider for app instance {} is misconfigured: {}", instance.id, e);
+        StatusCode::SERVICE_UNAVAILABLE
+    })
+}
+
+async fn find_app_instance(db: &DatabaseConnection, instance_id: Uuid) -> Result<crate::entities::app_instance::Model, StatusCode> {
+    crate::entities::app_instance::Entity::find_by_id(instance_id)
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn find_app_domain(
+    db: &DatabaseConnection,
+    instance_id: Uuid,
+    domain_name: &str,
+) -> Result<crate::entities::app_domain::Model, StatusCode> {
+    use crate::entities::app_domain;
+    app_domain::Entity::find()
+        .filter(app_domain::Column::AppInstanceId.eq(instance_id))
+        .filter(app_domain::Column::DomainName.eq(domain_name.to_lowercase()))
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Adds a custom domain. It is served once ownership is verified and the edge has a
+/// certificate for it; the response lists the DNS records that prove ownership and
+/// route traffic to the instance's edge provider.
 pub async fn add_app_domain(
     State(db): State<DatabaseConnection>,
     Extension(_current_user): Extension<user::Model>,
     Path(instance_id): Path<Uuid>,
     Json(input): Json<AppDomainInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    use crate::entities::app_domain;
-    
-    // Abstract Edge Infrastructure Hook - Connects to Cloudflare Custom Hostname API (or alternative)
-    if let Err(e) = crate::services::dns::provision_domain(&input.domain_name).await {
-        tracing::error!("DNS Edge provisioning failed for domain {}: {}", input.domain_name, e);
-        return Err(StatusCode::BAD_GATEWAY);
-    }
-    
-    let new_domain = app_domain::ActiveModel {
-        id: Set(Uuid::new_v4()),
-        app_instance_id: Set(instance_id),
-        domain_name: Set(input.domain_name.clone()),
-        created_at: Set(chrono::Utc::now()),
+    use crate::services::custom_domains::CustomDomainService;
+
+    let instance = find_app_instance(&db, instance_id).await?;
+    let provider = dns_provider(&instance)?;
+    let domain = CustomDomainService::create(&db, &instance, &input.domain_name, provider.as_deref())
+        .await
+        .map_err(domain_error)?;
+    Ok((StatusCode::CREATED, Json(AppDomainResponse::new(domain, provider.as_deref()))))
+}
+
+/// Re-checks a domain's ownership records and edge status right away instead of
+/// waiting for the CustomDomainSync job.
+pub async fn verify_app_domain(
+    State(db): State<DatabaseConnection>,
+    Extension(_current_user): Extension<user::Model>,
+    Path((instance_id, domain_name)): Path<(Uuid, String)>,
+) -> Result<impl IntoResponse, StatusCode> {
+    use crate::services::custom_domains::CustomDomainService;
+    use crate::services::dns::DohResolver;
+
+    let instance = find_app_instance(&db, instance_id).await?;
+    let domain = find_app_domain(&db, instance_id, &domain_name).await?;
+    let provider = dns_provider(&instance)?;
+    let domain = match provider.as_deref() {
+        Some(provider) => CustomDomainService::check(&db, provider, &DohResolver::from_env(), domain)
+            .await
+            .map_err(domain_error)?,
+        None => domain,
     };
-    new_domain.insert(&db).await.map_err(|e| {
-        tracing::error!("Failed to insert domain: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
-    Ok(StatusCode::CREATED)
+    Ok(Json(AppDomainResponse::new(domain, provider.as_deref())))
 }
 
+/// Removes the custom hostname from the edge and deletes the domain.
 pub async fn remove_app_domain(
     State(db): State<DatabaseConnection>,
     Extension(_current_user): Extension<user::Model>,
     Path((instance_id, domain_name)): Path<(Uuid, String)>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    use crate::entities::app_domain;
-    let domain = app_domain::Entity::find()
-        .filter(app_domain::Column::AppInstanceId.eq(instance_id))
-        .filter(app_domain::Column::DomainName.eq(domain_name))
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        
-    if let Some(d) = domain {
-        app_domain::Entity::delete_by_id(d.id).exec(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    use crate::services::custom_domains::CustomDomainService;
+
+    match find_app_domain(&db, instance_id, &domain_name).await {
+        Ok(domain) => {
+            let provider = dns_provider(&find_app_instance(&db, instance_id).await?)?;
+            CustomDomainService::remove(&db, provider.as_deref(), domain)
+                .await
+                .map_err(domain_error)?;
+        }
+        Err(StatusCode::NOT_FOUND) => {}
+        Err(status) => return Err(status),
     }
-    
+
     Ok(StatusCode::OK)
 }
 
@@ -316,7 +423,7 @@ pub async fn get_user(
     //filter by request type of LOGIN
     let login_history = request_log::Entity::find()
         .filter(request_log::Column::UserId.eq(user.id))
-        .filter(request_log::Column::RequestType.eq("LOGIN".to_string()))
+        .filter(request_log::Column::RequestType.eq(RequestType::Login))
         .all(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -491,41 +598,51 @@ pub async fn list_pending_listings(
 
 pub async fn approve_listing(
     State(db): State<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    Extension(current_user): Extension<user::Model>,
     Extension(_current_session): Extension<session::Model>,
     Path(listing_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
 
-    let mut listing: listing::ActiveModel = listing::Entity::find_by_id(listing_id)
+    let existing = listing::Entity::find_by_id(listing_id)
         .one(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?
-        .into();
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mut listing: listing::ActiveModel = existing.clone().into();
 
     listing.status = Set(ListingStatus::Approved);
 
-    let updated_listing = listing.update(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let updated_listing = listing.update(&txn).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    listings::emit_listing_update_events(&txn, Some(current_user.id), &existing, &updated_listing)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(updated_listing))
 }
 
 pub async fn reject_listing(
     State(db): State<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    Extension(current_user): Extension<user::Model>,
     Extension(_current_session): Extension<session::Model>,
     Path(listing_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let mut listing: listing::ActiveModel = listing::Entity::find_by_id(listing_id)
+    let existing = listing::Entity::find_by_id(listing_id)
         .one(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?
-        .into();
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mut listing: listing::ActiveModel = existing.clone().into();
 
     listing.status = Set(ListingStatus::Rejected);
 
-    let updated_listing = listing.update(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let updated_listing = listing.update(&txn).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    listings::emit_listing_update_events(&txn, Some(current_user.id), &existing, &updated_listing)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(updated_listing))
 }
@@ -3,11 +3,14 @@ use axum::{
     http::StatusCode,
     Json,
     response::IntoResponse,
-    routing::{get, post},
+    routing::{delete, get, post},
     Router,
 };
-use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait, ActiveModelTrait, Set};
+use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait, Set, TransactionTrait};
 use crate::entities::app_instance::{self, Entity as AppInstanceEntity};
+use crate::middleware::permissions::{Permission, TenantAccess};
+use crate::services::app_lifecycle::AppLifecycle;
+use crate::services::seed_packs::SeedPacks;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
@@ -27,6 +30,7 @@ pub struct CreateAppInstancePayload {
 pub fn authenticated_routes(db: DatabaseConnection) -> Router<DatabaseConnection> {
     Router::new()
         .route("/api/app-instances", post(create_app_instance))
+        .route("/api/app-instances/{instance_id}", delete(delete_app_instance))
         .route("/api/app-instances/seeds/{app_type}", get(list_data_seeds))
         .with_state(db)
 }
@@ -56,6 +60,8 @@ pub async fn get_app_instance(
         // Fetch and merge tenant_settings to hydrate metadata (site_title, hero_quote, etc.)
         let tenant_settings = crate::entities::tenant_setting::Entity::find()
             .filter(crate::entities::tenant_setting::Column::TenantId.eq(tenant_id))
+            // This endpoint is public, so secrets never leave the server
+            .filter(crate::entities::tenant_setting::Column::IsEncrypted.eq(false))
             .all(&db)
             .await
             .unwrap_or_default();
@@ -86,11 +92,32 @@ pub async fn get_app_instance(
     }
 }
 
-/// Creates a new AppInstance for a given Tenant.
+/// Creates a new AppInstance for a given Tenant and runs the app's install hook. The install
+/// and the requested seed pack commit together, so a failing pack leaves no instance behind.
 pub async fn create_app_instance(
+    access: TenantAccess,
     State(db): State<DatabaseConnection>,
     Json(payload): Json<CreateAppInstancePayload>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
+    access.ensure_visible(Some(payload.tenant_id)).map_err(|status| (status, "Tenant not found".into()))?;
+    access.require(Permission::Configure).map_err(|status| (status, "Installing apps requires an owner or admin".into()))?;
+
+    let app = crate::atlas_apps::find_app(&payload.app_type)
+        .ok_or((StatusCode::BAD_REQUEST, format!("Unknown app type '{}'", payload.app_type)))?;
+    AppLifecycle::validate_settings(app.as_ref(), payload.settings.as_ref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    // JSON seed packs are applied below; legacy `.sql` seeds are only recorded
+    let seed_pack = match payload.data_seed_name.as_deref() {
+        Some(name) if SeedPacks::exists(&payload.app_type, name) => Some(
+            SeedPacks::load(&payload.app_type, name).map_err(crate::admin::seed_packs::seed_pack_error)?,
+        ),
+        Some(name) if !is_legacy_seed(&payload.app_type, name) => {
+            return Err((StatusCode::BAD_REQUEST, format!("Unknown data seed '{}'", name)));
+        }
+        _ => None,
+    };
+
     let id = Uuid::new_v4();
 
     let new_instance = app_instance::ActiveModel {
@@ -99,29 +126,87 @@ pub async fn create_app_instance(
         app_type: Set(payload.app_type.clone()),
         database_url: Set(payload.database_url),
         data_seed_name: Set(payload.data_seed_name),
+        data_seed_version: Set(None),
+        data_seed_applied_at: Set(None),
         settings: Set(payload.settings),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
     };
 
-    let inserted = new_instance.insert(&db).await.map_err(|e| {
+    let create_error = |e: sea_orm::DbErr| {
         tracing::error!("Error creating App Instance: {:?}", e);
-        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create app instance".into())
-    })?;
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create app instance".to_string())
+    };
+    let txn = db.begin().await.map_err(create_error)?;
+    let mut inserted = AppLifecycle::install(&txn, app.as_ref(), new_instance).await.map_err(create_error)?;
+
+    if let Some(pack) = seed_pack {
+        SeedPacks::apply(&txn, &inserted, &pack, access.user_id, false)
+            .await
+            .map_err(crate::admin::seed_packs::seed_pack_error)?;
+        // The pack records its version and may have merged design tokens into the settings
+        if let Some(seeded) = AppInstanceEntity::find_by_id(inserted.id).one(&txn).await.map_err(create_error)? {
+            inserted = seeded;
+        }
+    }
+    txn.commit().await.map_err(create_error)?;
+
+    // Cached site configs carry the tenant's enabled apps
+    crate::middleware::site_context::invalidate_tenant(inserted.tenant_id);
 
     Ok((StatusCode::CREATED, Json(inserted)))
 }
 
+/// Runs the app's uninstall hook and deletes the AppInstance.
+pub async fn delete_app_instance(
+    access: TenantAccess,
+    Path(instance_id): Path<Uuid>,
+    State(db): State<DatabaseConnection>,
+) -> Result<StatusCode, StatusCode> {
+    access.require(Permission::Delete)?;
+
+    let instance = AppInstanceEntity::find_by_id(instance_id)
+        .one(&db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Database error fetching app instance: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    access.ensure_visible(Some(instance.tenant_id))?;
+
+    let app = crate::atlas_apps::find_app(&instance.app_type).ok_or_else(|| {
+        tracing::error!("App instance {} has unregistered app type {}", instance.id, instance.app_type);
+        StatusCode::CONFLICT
+    })?;
+
+    let tenant_id = instance.tenant_id;
+    AppLifecycle::uninstall(&db, app.as_ref(), instance).await.map_err(|err| {
+        tracing::error!("Error deleting App Instance: {:?}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    // Also drops the instance's domains, which cascade with it
+    crate::middleware::site_context::invalidate_tenant(tenant_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn seeds_dir(app_type: &str) -> PathBuf {
+    crate::services::seed_packs::seeds_root().join(app_type)
+}
+
+fn is_legacy_seed(app_type: &str, name: &str) -> bool {
+    !name.contains(['/', '\\', '.']) && seeds_dir(app_type).join(format!("{}.sql", name)).is_file()
+}
+
 /// Lists available data seeds for a specific application type.
 pub async fn list_data_seeds(
     Path(app_type): Path<String>,
 ) -> Result<Json<Vec<String>>, (StatusCode, String)> {
-    // Navigate relative to the backend running network, usually workspace root is one up.
-    let root_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .parent()
-        .unwrap()
-        .join("seeds")
-        .join(&app_type);
+    if app_type.contains(['/', '\\', '.']) {
+        return Ok(Json(vec![]));
+    }
+    let root_path = seeds_dir(&app_type);
 
     if !root_path.exists() || !root_path.is_dir() {
         return Ok(Json(vec![]));
@@ -185,7 +185,7 @@ pub async fn request_magic_link(
             body_html: format!("<h2>Atlas Platform Access</h2><p>Click the link below to securely log in to your account and configure your device passkey:</p><br><a href=\"{0}\">{0}</a>", setup_link),
         };
 
-        if let Err((status, msg)) = crate::handlers::communications::send_email_handler(State(db.clone()), Json(email_payload)).await {
+        if let Err((_, msg)) = crate::handlers::communications::send_email(&db, email_payload).await {
             tracing::error!("Failed to dispatch setup token email natively: {}", msg);
         } else {
             tracing::info!("Successfully dispatched Setup Token routing to {}", user_mod.email);
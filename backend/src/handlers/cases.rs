@@ -1,7 +1,8 @@
+use std::collections::HashMap;
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use axum::{
-    extract::{Extension, Path, Json, State},
+    extract::{Path, Json, Query, State},
     http::StatusCode,
     response::{IntoResponse, Json as JsonResponse},
     routing::{get, post, put, delete},
@@ -17,10 +18,26 @@ use sea_orm::{
     QueryFilter,
     
     ColumnTrait,
+    ModelTrait,
+    TransactionTrait,
 };
 use serde::Deserialize;
-use crate::entities::{case, activity, note, customer, user};
-use crate::models::case::{CaseModel, CreateCaseInput, UpdateCaseInput};
+use crate::middleware::api_token::require_api_scope;
+use crate::middleware::permissions::{Permission, TenantAccess};
+use crate::services::domain_events::{snapshot, DomainEvents};
+use crate::services::event_catalog::PlatformEvent;
+use crate::entities::{case, case_sla_policy, activity, note, customer};
+use crate::models::case::{
+    CaseModel, CreateCaseInput, UpdateCaseInput, CasePriority, CaseStatus, ChangeCaseStatusInput, SlaPolicyInput,
+    SlaReportQuery,
+};
+use crate::services::activity_tasks::TaskService;
+use crate::services::case_sla::{self, SlaError};
+use crate::services::custom_fields::{self, PropertyQuery};
+use crate::handlers::custom_fields::field_error;
+use crate::handlers::activities::task_error;
+use crate::handlers::files::find_file;
+use crate::models::custom_field::CustomFieldEntity;
 use crate::models::activity::ActivityModel;
 use crate::models::note::NoteModel;
 use crate::models::file::FileAssociation;
@@ -30,53 +47,114 @@ pub fn routes() -> Router<DatabaseConnection> {
     Router::new()
         .route("/api/cases", post(create_case))
         .route("/api/cases", get(get_cases))
+        .route("/api/cases/sla-policies", get(get_sla_policies))
+        .route("/api/cases/sla-policies/{priority}", put(put_sla_policy))
+        .route("/api/cases/sla-policies/{priority}", delete(delete_sla_policy))
+        .route("/api/cases/reports/sla", get(get_sla_report))
         .route("/api/cases/{id}", get(get_case))
         .route("/api/cases/{id}", put(update_case))
         .route("/api/cases/{id}", delete(delete_case))
+        .route("/api/cases/{id}/status", post(change_case_status))
         .route("/api/cases/{id}/activities", get(get_case_activities))
         .route("/api/cases/{id}/activities", post(create_case_activity))
         .route("/api/cases/{id}/notes", get(get_case_notes))
         .route("/api/cases/{id}/notes", post(create_case_note))
         .route("/api/cases/{id}/files", get(get_case_files))
         .route("/api/cases/{id}/files/{file_id}", post(add_file_to_case))
+        .route_layer(axum::middleware::from_fn_with_state("crm", require_api_scope))
+}
+
+/// Loads a case visible to the caller; other tenants' cases are reported as missing.
+async fn find_case(db: &DatabaseConnection, access: &TenantAccess, id: Uuid) -> Result<case::Model, StatusCode> {
+    access
+        .scope(case::Entity::find_by_id(id), case::Column::TenantId)
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+fn sla_error(e: SlaError) -> StatusCode {
+    match e {
+        SlaError::Invalid(_) => {
+            tracing::debug!("Rejected case change: {}", e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        SlaError::Conflict(_) => StatusCode::CONFLICT,
+        SlaError::Db(e) => {
+            tracing::error!("Case SLA update failed: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+fn parse_priority(value: &str) -> Result<CasePriority, StatusCode> {
+    CasePriority::parse(value).ok_or(StatusCode::UNPROCESSABLE_ENTITY)
 }
 
 pub async fn create_case(
     State(db): State<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Json(input): Json<CreateCaseInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+
     // Check if the customer exists
-    let customer = customer::Entity::find_by_id(input.customer_id)
+    let customer = access
+        .scope(customer::Entity::find_by_id(input.customer_id), customer::Column::TenantId)
         .one(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    let priority = parse_priority(&input.priority)?;
+    let policy = case_sla::policy_for(&db, customer.tenant_id, priority)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let properties = custom_fields::validate_properties(&db, customer.tenant_id, CustomFieldEntity::Case, input.properties, true)
+        .await
+        .map_err(field_error)?;
+    if let Some(assignee) = input.assigned_to {
+        TaskService::ensure_assignable(&db, customer.tenant_id, assignee).await.map_err(task_error)?;
+    }
 
-    let new_case = case::ActiveModel {
+    let now = Utc::now();
+    let mut new_case = case::ActiveModel {
         id: Set(Uuid::new_v4()),
         customer_id: Set(customer.id),
+        tenant_id: Set(customer.tenant_id),
         title: Set(input.title),
         description: Set(input.description),
-        status: Set("Open".to_string()),
-        priority: Set(input.priority),
+        status: Set(CaseStatus::New.as_str().to_string()),
+        priority: Set(priority.as_str().to_string()),
         assigned_to: Set(input.assigned_to),
-        created_at: Set(Utc::now()),
-        updated_at: Set(Utc::now()),
+        created_at: Set(now),
+        updated_at: Set(now),
         closed_at: Set(None),
-        properties: Set(None),
+        properties: Set(properties),
+        ..Default::default()
     };
+    case_sla::apply_targets(&mut new_case, now, policy.as_ref());
+
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let inserted_case = new_case.insert(&txn).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    DomainEvents::emit(&txn, PlatformEvent::CaseCreated, inserted_case.tenant_id, access.user_id, inserted_case.id, None, snapshot(&inserted_case))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let inserted_case = new_case.insert(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
     Ok((StatusCode::CREATED, JsonResponse(CaseModel::from(inserted_case))))
 }
 
 pub async fn get_cases(
     State(db): State<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
+    Query(params): Query<HashMap<String, String>>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let cases = case::Entity::find()
+    let properties = PropertyQuery::parse(&db, access.tenant_id, CustomFieldEntity::Case, &params)
+        .await
+        .map_err(field_error)?;
+    let cases = properties
+        .apply(access.scope(case::Entity::find(), case::Column::TenantId), case::Column::Properties)
         .all(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -87,67 +165,102 @@ pub async fn get_cases(
 
 pub async fn get_case(
     State(db): State<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let case = case::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let case = find_case(&db, &access, id).await?;
 
     Ok(JsonResponse(CaseModel::from(case)))
 }
 
 pub async fn update_case(
     State(db): State<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<UpdateCaseInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let mut case: case::ActiveModel = case::Entity::find_by_id(id)
-        .one(&db)
+    access.require(Permission::Write)?;
+    let existing = find_case(&db, &access, id).await?;
+    let before = snapshot(&existing);
+    let priority = match &input.priority {
+        Some(priority) => parse_priority(priority)?,
+        None => parse_priority(&existing.priority).unwrap_or(CasePriority::Medium),
+    };
+    let policy = case_sla::policy_for(&db, existing.tenant_id, priority)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?
-        .into();
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let properties = match input.properties {
+        Some(properties) => Some(
+            custom_fields::validate_properties(&db, existing.tenant_id, CustomFieldEntity::Case, Some(properties), false)
+                .await
+                .map_err(field_error)?,
+        ),
+        None => None,
+    };
+    if let Some(assignee) = input.assigned_to {
+        TaskService::ensure_assignable(&db, existing.tenant_id, assignee).await.map_err(task_error)?;
+    }
 
+    // Status changes follow the same workflow as POST /api/cases/{id}/status
+    let now = Utc::now();
+    let mut case: case::ActiveModel = match input.status.as_deref().map(CaseStatus::parse) {
+        Some(None) => return Err(StatusCode::UNPROCESSABLE_ENTITY),
+        Some(Some(status)) if status.as_str() != existing.status => {
+            case_sla::transition(&existing, status, policy.as_ref(), now).map_err(sla_error)?
+        }
+        _ => existing.clone().into(),
+    };
+    if priority.as_str() != existing.priority {
+        case.priority = Set(priority.as_str().to_string());
+        case_sla::retarget(&mut case, policy.as_ref());
+    }
     if let Some(title) = input.title { case.title = Set(title); }
     if let Some(description) = input.description { case.description = Set(description); }
-    if let Some(status) = input.status { case.status = Set(status); }
-    if let Some(priority) = input.priority { case.priority = Set(priority); }
     if let Some(assigned_to) = input.assigned_to { case.assigned_to = Set(Some(assigned_to)); }
-    case.updated_at = Set(Utc::now());
+    if let Some(properties) = properties { case.properties = Set(properties); }
+    case.updated_at = Set(now);
 
-    let updated_case = case.update(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let updated_case = case.update(&txn).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    DomainEvents::emit(&txn, PlatformEvent::CaseUpdated, updated_case.tenant_id, access.user_id, updated_case.id, before, snapshot(&updated_case))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(JsonResponse(CaseModel::from(updated_case)))
 }
 
 pub async fn delete_case(
     State(db): State<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let result = case::Entity::delete_by_id(id)
-        .exec(&db)
+    access.require(Permission::Delete)?;
+    let case = find_case(&db, &access, id).await?;
+    let (tenant_id, before) = (case.tenant_id, snapshot(&case));
+
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    case.delete(&txn)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    if result.rows_affected == 0 {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    DomainEvents::emit(&txn, PlatformEvent::CaseDeleted, tenant_id, access.user_id, id, before, None)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn get_case_activities(
     State(db): State<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let activities = activity::Entity::find()
-        .filter(activity::Column::CaseId.eq(id))
+    let case = find_case(&db, &access, id).await?;
+
+    let activities = access
+        .scope(activity::Entity::find(), activity::Column::TenantId)
+        .filter(activity::Column::CaseId.eq(case.id))
         .all(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -158,15 +271,15 @@ pub async fn get_case_activities(
 
 pub async fn create_case_activity(
     State(db): State<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<CreateCaseActivityInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let case = case::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let case = find_case(&db, &access, id).await?;
+    if let Some(assignee) = case.assigned_to {
+        TaskService::ensure_assignable(&db, case.tenant_id, assignee).await.map_err(task_error)?;
+    }
 
     let new_activity = activity::ActiveModel {
         id: Set(Uuid::new_v4()),
@@ -176,11 +289,12 @@ pub async fn create_case_activity(
         deal_id: Set(None),
         lead_id: Set(None),
         contact_id: Set(None),
+        tenant_id: Set(case.tenant_id),
         activity_type: Set(ActivityType::Task),
         title: Set(input.title),
         description: Set(input.description),
         status: Set(ActivityStatus::Pending),
-        due_date: Set(None),
+        due_date: Set(input.due_date),
         completed_at: Set(None),
         associated_entities: Set(serde_json::to_value(vec![
             AssociatedEntity {
@@ -188,30 +302,30 @@ pub async fn create_case_activity(
                 entity_id: case.id,
             }
         ]).unwrap()),
-        created_by: Set(current_user.id),
+        created_by: Set(access.user_id),
         assigned_to: Set(case.assigned_to),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
+        ..Default::default()
     };
 
     let inserted_activity = new_activity
         .insert(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
+    TaskService::ensure_reminders(&db, &inserted_activity).await.map_err(|e| task_error(e.into()))?;
+
     Ok((StatusCode::CREATED, JsonResponse(ActivityModel::from(inserted_activity))))
 }
 
 pub async fn add_file_to_case(
     State(db): State<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path((case_id, file_id)): Path<(Uuid, Uuid)>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let case = case::Entity::find_by_id(case_id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let case = find_case(&db, &access, case_id).await?;
+    find_file(&db, &access, file_id).await?;
 
     case.add_file(&db, file_id)
         .await
@@ -222,14 +336,10 @@ pub async fn add_file_to_case(
 
 pub async fn get_case_files(
     State(db): State<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(case_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let case = case::Entity::find_by_id(case_id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let case = find_case(&db, &access, case_id).await?;
 
     let file_ids = case.get_associated_files(&db)
         .await
@@ -240,16 +350,13 @@ pub async fn get_case_files(
 
 pub async fn get_case_notes(
     State(db): State<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let case = case::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let case = find_case(&db, &access, id).await?;
 
-    let notes = note::Entity::find()
+    let notes = access
+        .scope(note::Entity::find(), note::Column::TenantId)
         .filter(note::Column::EntityType.eq("Case"))
         .filter(note::Column::EntityId.eq(case.id))
         .all(&db)
@@ -262,35 +369,130 @@ pub async fn get_case_notes(
 
 pub async fn create_case_note(
     State(db): State<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<CreateCaseNoteInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let case = case::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let case = find_case(&db, &access, id).await?;
 
     let new_note = note::ActiveModel {
         id: Set(Uuid::new_v4()),
         content: Set(input.content),
-        created_by: Set(current_user.id),
+        created_by: Set(access.user_id),
         entity_type: Set("Case".to_string()),
         entity_id: Set(case.id),
+        tenant_id: Set(case.tenant_id),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
     };
 
     let inserted_note = new_note.insert(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
+    case_sla::record_first_response(&db, case.id, inserted_note.created_at)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     Ok((StatusCode::CREATED, JsonResponse(NoteModel::from(inserted_note))))
 }
 
+/// Moves a case through its workflow; transitions the current status doesn't allow are a `409`.
+pub async fn change_case_status(
+    State(db): State<DatabaseConnection>,
+    access: TenantAccess,
+    Path(id): Path<Uuid>,
+    Json(input): Json<ChangeCaseStatusInput>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+    let existing = find_case(&db, &access, id).await?;
+    let status = CaseStatus::parse(&input.status).ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    let priority = parse_priority(&existing.priority).unwrap_or(CasePriority::Medium);
+    let policy = case_sla::policy_for(&db, existing.tenant_id, priority)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let case = case_sla::transition(&existing, status, policy.as_ref(), Utc::now()).map_err(sla_error)?;
+
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let updated_case = case.update(&txn).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    DomainEvents::emit(&txn, PlatformEvent::CaseUpdated, updated_case.tenant_id, access.user_id, updated_case.id, snapshot(&existing), snapshot(&updated_case))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(JsonResponse(CaseModel::from(updated_case)))
+}
+
+pub async fn get_sla_policies(
+    State(db): State<DatabaseConnection>,
+    access: TenantAccess,
+) -> Result<impl IntoResponse, StatusCode> {
+    let policies = case_sla::policies(&db, access.tenant_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(JsonResponse(policies))
+}
+
+/// Sets the response and resolution targets for cases of one priority. Cases already open
+/// keep the targets they were opened with.
+pub async fn put_sla_policy(
+    State(db): State<DatabaseConnection>,
+    access: TenantAccess,
+    Path(priority): Path<String>,
+    Json(input): Json<SlaPolicyInput>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Configure)?;
+    let priority = parse_priority(&priority)?;
+
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let policy = case_sla::upsert_policy(&txn, access.tenant_id, priority, input, Utc::now())
+        .await
+        .map_err(sla_error)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(JsonResponse(policy))
+}
+
+pub async fn delete_sla_policy(
+    State(db): State<DatabaseConnection>,
+    access: TenantAccess,
+    Path(priority): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Delete)?;
+    let priority = parse_priority(&priority)?;
+    let policy = case_sla::policy_for(&db, access.tenant_id, priority)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    case_sla_policy::Entity::delete_by_id(policy.id)
+        .exec(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// First response and resolution compliance of the cases opened within `[from, to)`.
+pub async fn get_sla_report(
+    State(db): State<DatabaseConnection>,
+    access: TenantAccess,
+    Query(query): Query<SlaReportQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let mut select = access.scope(case::Entity::find(), case::Column::TenantId);
+    if let Some(from) = query.from {
+        select = select.filter(case::Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = query.to {
+        select = select.filter(case::Column::CreatedAt.lt(to));
+    }
+    let cases = select.all(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(JsonResponse(case_sla::compliance(&cases, query.from, query.to, Utc::now())))
+}
+
 #[derive(Debug, Deserialize)]
 struct CreateCaseActivityInput {
     title: String,
     description: Option<String>,
+    due_date: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -4,13 +4,11 @@ use axum::{
     routing::post,
     Router,
 };
-use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait};
+use sea_orm::DatabaseConnection;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use lettre::message::{header, MultiPart, SinglePart};
-use lettre::transport::smtp::authentication::Credentials;
-use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
-use crate::entities::tenant_setting::{self, Entity as TenantSetting};
+use crate::middleware::permissions::{Permission, TenantAccess};
+use crate::services::mailer::{self, Delivery, MailError};
 
 #[derive(Deserialize, Debug)]
 pub struct SendEmailPayload {
@@ -33,87 +31,36 @@ pub fn authenticated_routes(db: DatabaseConnection) -> Router<DatabaseConnection
 
 pub async fn send_email_handler(
     State(db): State<DatabaseConnection>,
+    access: TenantAccess,
     Json(payload): Json<SendEmailPayload>,
 ) -> Result<(StatusCode, Json<SendEmailResponse>), (StatusCode, String)> {
-    
-    // 1. Fetch Tenant Settings for SMTP override
-    let settings = TenantSetting::find()
-        .filter(tenant_setting::Column::TenantId.eq(payload.tenant_id))
-        .all(&db)
+    // Mail goes out through the tenant's own SMTP account, so only its members may send it
+    access
+        .ensure_visible(Some(payload.tenant_id))
+        .and_then(|_| access.require(Permission::Write))
+        .map_err(|status| (status, "Tenant not found".to_string()))?;
+    let response = send_email(&db, payload).await?;
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Sends `payload` without an access check, for mail the platform itself dispatches.
+pub async fn send_email(
+    db: &DatabaseConnection,
+    payload: SendEmailPayload,
+) -> Result<SendEmailResponse, (StatusCode, String)> {
+    let delivery = mailer::send_tenant_email(db, payload.tenant_id, &payload.to_email, &payload.subject, payload.body_html)
         .await
-        .map_err(|e| {
-            tracing::error!("DB error fetching tenant settings: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string())
+        .map_err(|e| match e {
+            MailError::InvalidFrom | MailError::InvalidTo => (StatusCode::BAD_REQUEST, e.to_string()),
+            MailError::Settings(_) | MailError::Build(_) | MailError::Transport(_) => {
+                tracing::error!("Failed to send email to {}: {}", payload.to_email, e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to send email".to_string())
+            }
         })?;
 
-    let mut custom_host = None;
-    let mut custom_port = None;
-    let mut custom_username = None;
-    let mut custom_token = None;
-    let mut custom_from = None;
-
-    for setting in settings {
-        match setting.key.as_str() {
-            "smtp_server" => custom_host = Some(setting.value),
-            "smtp_port" => custom_port = Some(setting.value.parse().unwrap_or(587)),
-            "smtp_username" => custom_username = Some(setting.value),
-            "smtp_token" => custom_token = Some(setting.value),
-            "smtp_from" => custom_from = Some(setting.value),
-            _ => {}
-        }
-    }
-
-    // 2. Fallback to System environment variables if no Custom settings
-    let host = custom_host.unwrap_or_else(|| std::env::var("SMTP_SERVER").unwrap_or_else(|_| "localhost".to_string()));
-    let port = custom_port.unwrap_or_else(|| std::env::var("SMTP_PORT").unwrap_or_else(|_| "587".to_string()).parse().unwrap_or(587));
-    let username = custom_username.unwrap_or_else(|| std::env::var("SMTP_USERNAME").unwrap_or_default());
-    let token = custom_token.unwrap_or_else(|| std::env::var("SMTP_TOKEN").unwrap_or_default());
-    let from_email = custom_from.unwrap_or_else(|| std::env::var("SMTP_FROM").unwrap_or_else(|_| "noreply@atlas-platform.local".to_string()));
-
-    // 3. Construct Message
-    let email = Message::builder()
-        .from(from_email.parse().map_err(|_| (StatusCode::BAD_REQUEST, "Invalid FROM email".to_string()))?)
-        .to(payload.to_email.parse().map_err(|_| (StatusCode::BAD_REQUEST, "Invalid TO email".to_string()))?)
-        .subject(&payload.subject)
-        .multipart(
-            MultiPart::alternative().singlepart(
-                SinglePart::builder()
-                    .header(header::ContentType::TEXT_HTML)
-                    .body(payload.body_html),
-            ),
-        )
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build email message".to_string()))?;
-
-    // If we're mocking local sending
-    if host == "localhost" || host.is_empty() {
-        tracing::warn!("SMTP Host not configured. Mocking email send to: {}", payload.to_email);
-        return Ok((StatusCode::OK, Json(SendEmailResponse { message: "Email mocked successfully".to_string() })));
-    }
-
-    let creds = Credentials::new(username, token);
-
-    let mailer: AsyncSmtpTransport<Tokio1Executor> = if port == 465 {
-        AsyncSmtpTransport::<Tokio1Executor>::relay(&host)
-            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Invalid SMTP relay host".to_string()))?
-            .port(port)
-            .credentials(creds)
-            .build()
-    } else {
-        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)
-            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Invalid SMTP STARTTLS host".to_string()))?
-            .port(port)
-            .credentials(creds)
-            .build()
+    let message = match delivery {
+        Delivery::Sent => "Email sent successfully",
+        Delivery::Mocked => "Email mocked successfully",
     };
-
-    match mailer.send(email).await {
-        Ok(_) => {
-            tracing::info!("Email sent successfully to {}", payload.to_email);
-            Ok((StatusCode::OK, Json(SendEmailResponse { message: "Email sent successfully".to_string() })))
-        }
-        Err(e) => {
-            tracing::error!("Failed to send email to {}: {:?}", payload.to_email, e);
-            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to send email over SMTP".to_string()))
-        }
-    }
+    Ok(SendEmailResponse { message: message.to_string() })
 }
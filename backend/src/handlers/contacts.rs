@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, Json},
+    extract::{Extension, Path, Json, Query},
     http::StatusCode,
     response::{IntoResponse, Json as JsonResponse},
     routing::{get, post, put, delete},
@@ -7,14 +7,25 @@ use axum::{
 };
 use sea_orm::{
     DatabaseConnection, EntityTrait, QueryFilter, Set, ColumnTrait,
-    ActiveModelTrait,
+    ActiveModelTrait, ModelTrait, TransactionTrait,
 };
+use std::collections::HashMap;
 use uuid::Uuid;
 use chrono::Utc;
+use crate::middleware::api_token::require_api_scope;
+use crate::middleware::permissions::{Permission, TenantAccess};
+use crate::services::domain_events::{snapshot, DomainEvents};
+use crate::services::event_catalog::PlatformEvent;
+use crate::services::custom_fields::{self, PropertyQuery};
+use crate::handlers::activities::task_error;
+use crate::handlers::custom_fields::field_error;
+use crate::services::activity_tasks::TaskService;
+use crate::handlers::files::find_file;
 use crate::handlers::Validate;
 use crate::models::{address::AddressJson, contact::Contact as ContactModel};
-use crate::entities::{contact, note, activity, user};
+use crate::entities::{contact, customer, note, activity};
 use crate::models::contact::{ CreateContactInput, UpdateContactInput};
+use crate::models::custom_field::CustomFieldEntity;
 use crate::models::file::FileAssociation;
 use crate::models::note::{NoteModel, CreateNoteInput};
 use crate::models::activity::{ActivityModel, CreateActivityInput};
@@ -33,15 +44,53 @@ pub fn routes() -> Router<DatabaseConnection> {
         .route("/api/contacts/{id}/notes", get(get_contact_notes))
         .route("/api/contacts/{id}/activities", post(create_contact_activity))
         .route("/api/contacts/{id}/activities", get(get_contact_activities))
+        .route_layer(axum::middleware::from_fn_with_state("crm", require_api_scope))
+}
+
+/// Loads a contact visible to the caller; other tenants' contacts are reported as missing.
+async fn find_contact(db: &DatabaseConnection, access: &TenantAccess, id: Uuid) -> Result<contact::Model, StatusCode> {
+    access
+        .scope(contact::Entity::find_by_id(id), contact::Column::TenantId)
+        .one(db)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch contact: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Ensures a referenced customer belongs to the caller's tenant before linking it.
+async fn ensure_customer_visible(db: &DatabaseConnection, access: &TenantAccess, customer_id: Uuid) -> Result<(), StatusCode> {
+    access
+        .scope(customer::Entity::find_by_id(customer_id), customer::Column::TenantId)
+        .one(db)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch customer: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(|_| ())
+        .ok_or(StatusCode::NOT_FOUND)
 }
 
 pub async fn create_contact(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Json(payload): Json<CreateContactInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+    if let Some(customer_id) = payload.customer_id {
+        ensure_customer_visible(&db, &access, customer_id).await?;
+    }
+    let properties = custom_fields::validate_properties(&db, access.tenant_id, CustomFieldEntity::Contact, payload.properties, true)
+        .await
+        .map_err(field_error)?;
+
     let new_contact = contact::ActiveModel {
         id: Set(Uuid::new_v4()),
         customer_id: Set(payload.customer_id),
+        tenant_id: Set(access.tenant_id),
         name: Set(payload.name),
         first_name: Set(payload.first_name),
         last_name: Set(payload.last_name),
@@ -54,14 +103,19 @@ pub async fn create_contact(
         facebook: Set(payload.facebook),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
-        properties: Set(None),
+        properties: Set(properties),
         ..Default::default()
     };
 
-    let mut contact = new_contact.insert(&db).await.map_err(|e| {
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut contact = new_contact.insert(&txn).await.map_err(|e| {
         tracing::error!("Failed to create contact: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
+    DomainEvents::emit(&txn, PlatformEvent::ContactCreated, contact.tenant_id, access.user_id, contact.id, None, snapshot(&contact))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     if let Some(billing_address) = payload.billing_address {
         contact.billing_address = Some(AddressJson(billing_address.into()));
@@ -77,8 +131,14 @@ pub async fn create_contact(
 
 pub async fn get_contacts(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Query(params): Query<HashMap<String, String>>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let contacts = contact::Entity::find()
+    let properties = PropertyQuery::parse(&db, access.tenant_id, CustomFieldEntity::Contact, &params)
+        .await
+        .map_err(field_error)?;
+    let contacts = properties
+        .apply(access.scope(contact::Entity::find(), contact::Column::TenantId), contact::Column::Properties)
         .all(&db)
         .await
         .map_err(|e| {
@@ -92,16 +152,10 @@ pub async fn get_contacts(
 
 pub async fn get_contact(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let contact = contact::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch contact: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let contact = find_contact(&db, &access, id).await?;
 
     let contact_model: ContactModel = contact.into();
     Ok((StatusCode::OK, JsonResponse(contact_model)))
@@ -109,20 +163,18 @@ pub async fn get_contact(
 
 pub async fn update_contact(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateContactInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let mut contact: contact::ActiveModel = contact::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch contact: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?
-        .into();
+    access.require(Permission::Write)?;
+    let existing = find_contact(&db, &access, id).await?;
+    let before = snapshot(&existing);
+    let tenant_id = existing.tenant_id;
+    let mut contact: contact::ActiveModel = existing.into();
 
     if let Some(customer_id) = payload.customer_id {
+        ensure_customer_visible(&db, &access, customer_id).await?;
         contact.customer_id = Set(Some(customer_id));
     }
     if let Some(name) = payload.name {
@@ -155,12 +207,24 @@ pub async fn update_contact(
     if let Some(facebook) = payload.facebook {
         contact.facebook = Set(Some(facebook));
     }
+    if let Some(properties) = payload.properties {
+        contact.properties = Set(
+            custom_fields::validate_properties(&db, tenant_id, CustomFieldEntity::Contact, Some(properties), false)
+                .await
+                .map_err(field_error)?,
+        );
+    }
     contact.updated_at = Set(Utc::now());
 
-    let mut updated_contact = contact.update(&db).await.map_err(|e| {
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut updated_contact = contact.update(&txn).await.map_err(|e| {
         tracing::error!("Failed to update contact: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
+    DomainEvents::emit(&txn, PlatformEvent::ContactUpdated, updated_contact.tenant_id, access.user_id, updated_contact.id, before, snapshot(&updated_contact))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     if let Some(billing_address) = payload.billing_address {
         // Validate the address
@@ -186,32 +250,34 @@ pub async fn update_contact(
 
 pub async fn delete_contact(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let result = contact::Entity::delete_by_id(id).exec(&db).await.map_err(|e| {
+    access.require(Permission::Delete)?;
+    let contact = find_contact(&db, &access, id).await?;
+    let (tenant_id, before) = (contact.tenant_id, snapshot(&contact));
+
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    contact.delete(&txn).await.map_err(|e| {
         tracing::error!("Failed to delete contact: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
-
-    if result.rows_affected == 0 {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    DomainEvents::emit(&txn, PlatformEvent::ContactDeleted, tenant_id, access.user_id, id, before, None)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn add_file_to_contact(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path((contact_id, file_id)): Path<(Uuid, Uuid)>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let contact = contact::Entity::find_by_id(contact_id)
-        .one(&db)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch contact: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let contact = find_contact(&db, &access, contact_id).await?;
+    find_file(&db, &access, file_id).await?;
 
     contact.add_file(&db, file_id)
         .await
@@ -225,16 +291,10 @@ pub async fn add_file_to_contact(
 
 pub async fn get_contact_files(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(contact_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let contact = contact::Entity::find_by_id(contact_id)
-        .one(&db)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch contact: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let contact = find_contact(&db, &access, contact_id).await?;
 
     let file_ids = contact.get_associated_files(&db)
         .await
@@ -248,22 +308,20 @@ pub async fn get_contact_files(
 
 pub async fn create_contact_note(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<CreateNoteInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let contact = contact::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let contact = find_contact(&db, &access, id).await?;
 
     let new_note = note::ActiveModel {
         id: Set(Uuid::new_v4()),
         content: Set(input.content),
-        created_by: Set(current_user.id),
+        created_by: Set(access.user_id),
         entity_type: Set("Contact".to_string()),
         entity_id: Set(contact.id),
+        tenant_id: Set(contact.tenant_id),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
     };
@@ -275,15 +333,13 @@ pub async fn create_contact_note(
 
 pub async fn get_contact_notes(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let contact = contact::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let contact = find_contact(&db, &access, id).await?;
 
-    let notes = note::Entity::find()
+    let notes = access
+        .scope(note::Entity::find(), note::Column::TenantId)
         .filter(note::Column::EntityType.eq("Contact"))
         .filter(note::Column::EntityId.eq(contact.id))
         .all(&db)
@@ -296,26 +352,27 @@ pub async fn get_contact_notes(
 
 pub async fn create_contact_activity(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<CreateActivityInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let contact = contact::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let contact = find_contact(&db, &access, id).await?;
+    if let Some(assignee) = input.assigned_to {
+        TaskService::ensure_assignable(&db, contact.tenant_id, assignee).await.map_err(task_error)?;
+    }
 
     let new_activity = activity::ActiveModel {
         id: Set(Uuid::new_v4()),
         contact_id: Set(Some(contact.id)),
+        tenant_id: Set(contact.tenant_id),
         activity_type: Set(input.activity_type),
         title: Set(input.title),
         description: Set(input.description),
         status: Set(input.status),
         due_date: Set(input.due_date),
         completed_at: Set(None),
-        created_by: Set(current_user.id),
+        created_by: Set(access.user_id),
         assigned_to: Set(input.assigned_to),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
@@ -323,21 +380,20 @@ pub async fn create_contact_activity(
     };
 
     let inserted_activity = new_activity.insert(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
+    TaskService::ensure_reminders(&db, &inserted_activity).await.map_err(|e| task_error(e.into()))?;
+
     Ok((StatusCode::CREATED, JsonResponse(ActivityModel::from(inserted_activity))))
 }
 
 pub async fn get_contact_activities(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let contact = contact::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let contact = find_contact(&db, &access, id).await?;
 
-    let activities = activity::Entity::find()
+    let activities = access
+        .scope(activity::Entity::find(), activity::Column::TenantId)
         .filter(activity::Column::ContactId.eq(Some(contact.id)))
         .all(&db)
         .await
@@ -0,0 +1,105 @@
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json as JsonResponse},
+    routing::{get, put},
+    Router,
+};
+use chrono::Utc;
+use sea_orm::DatabaseConnection;
+
+use crate::config::ModuleFlags;
+use crate::middleware::api_token::require_api_scope;
+use crate::middleware::app_gate::require_module_enabled;
+use crate::middleware::permissions::{Permission, TenantAccess};
+use crate::models::custom_field::{
+    CreateCustomFieldInput, CustomFieldEntity, CustomFieldModel, UpdateCustomFieldInput,
+};
+use crate::services::custom_fields::{self, FieldError};
+
+pub fn routes() -> Router<DatabaseConnection> {
+    Router::new()
+        .route("/api/custom-fields/{entity_type}", get(get_schema).post(create_field))
+        .route("/api/custom-fields/{entity_type}/{key}", put(update_field).delete(delete_field))
+        .route_layer(axum::middleware::from_fn_with_state("crm", require_api_scope))
+        .route_layer(axum::middleware::from_fn_with_state(ModuleFlags::CUSTOM_FIELDS, require_module_enabled))
+}
+
+/// Maps a custom field error to a response. Property validation failures on CRM records come
+/// through here as 422s as well.
+pub(crate) fn field_error(e: FieldError) -> StatusCode {
+    match e {
+        FieldError::NotFound => StatusCode::NOT_FOUND,
+        FieldError::Invalid(_) => {
+            tracing::debug!("Rejected custom field change: {}", e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        FieldError::Conflict(_) => StatusCode::CONFLICT,
+        FieldError::Db(e) => {
+            tracing::error!("Custom field operation failed: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+fn parse_entity(value: &str) -> Result<CustomFieldEntity, StatusCode> {
+    CustomFieldEntity::parse(value).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// The entity's field definitions plus the same fields laid out for the platform-admin `DynamicForm`.
+pub async fn get_schema(
+    State(db): State<DatabaseConnection>,
+    access: TenantAccess,
+    Path(entity_type): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let entity = parse_entity(&entity_type)?;
+    let schema = custom_fields::schema(&db, access.tenant_id, entity)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(JsonResponse(schema))
+}
+
+pub async fn create_field(
+    State(db): State<DatabaseConnection>,
+    access: TenantAccess,
+    Path(entity_type): Path<String>,
+    Json(input): Json<CreateCustomFieldInput>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Configure)?;
+    let entity = parse_entity(&entity_type)?;
+    let field = custom_fields::create(&db, access.tenant_id, entity, input, Utc::now())
+        .await
+        .map_err(field_error)?;
+
+    Ok((StatusCode::CREATED, JsonResponse(CustomFieldModel::from(field))))
+}
+
+pub async fn update_field(
+    State(db): State<DatabaseConnection>,
+    access: TenantAccess,
+    Path((entity_type, key)): Path<(String, String)>,
+    Json(input): Json<UpdateCustomFieldInput>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Configure)?;
+    let entity = parse_entity(&entity_type)?;
+    let field = custom_fields::update(&db, access.tenant_id, entity, &key, input, Utc::now())
+        .await
+        .map_err(field_error)?;
+
+    Ok(JsonResponse(CustomFieldModel::from(field)))
+}
+
+pub async fn delete_field(
+    State(db): State<DatabaseConnection>,
+    access: TenantAccess,
+    Path((entity_type, key)): Path<(String, String)>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Delete)?;
+    let entity = parse_entity(&entity_type)?;
+    custom_fields::delete(&db, access.tenant_id, entity, &key)
+        .await
+        .map_err(field_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
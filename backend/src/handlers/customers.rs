@@ -7,15 +7,24 @@ use axum::{
 };
 use sea_orm::{
     DatabaseConnection, EntityTrait, QueryFilter, Set, ColumnTrait,
-    ActiveModelTrait, ModelTrait, PaginatorTrait
+    ActiveModelTrait, ModelTrait, PaginatorTrait, TransactionTrait
 };
+use crate::middleware::api_token::require_api_scope;
+use crate::middleware::permissions::{Permission, TenantAccess};
+use crate::services::domain_events::{snapshot, DomainEvents};
+use crate::services::event_catalog::PlatformEvent;
+use crate::services::custom_fields::{self, PropertyQuery};
+use crate::handlers::activities::task_error;
+use crate::handlers::custom_fields::field_error;
+use crate::services::activity_tasks::TaskService;
 use crate::handlers::Validate;
 use uuid::Uuid;
 use chrono::Utc;
 use serde_json::json;
-use crate::entities::{customer, user, contact, note, activity};
+use crate::entities::{customer, contact, note, activity};
 use crate::entities::customer::CustomerType;
 use crate::models::customer::{CreateCustomerInput, UpdateCustomerInput};
+use crate::models::custom_field::CustomFieldEntity;
 use crate::models::contact::{ CreateContactInput};
 use crate::models::note::{CreateNoteInput};
 use crate::models::activity::{ActivityModel, CreateActivityInput};
@@ -39,13 +48,29 @@ pub fn routes() -> Router<DatabaseConnection> {
         .route("/api/customers/{id}/notes", get(get_customer_notes))
         .route("/api/customers/{id}/activities", post(create_customer_activity))
         .route("/api/customers/{id}/activities", get(get_customer_activities))
+        .route_layer(axum::middleware::from_fn_with_state("crm", require_api_scope))
+}
+
+/// Loads a customer visible to the caller; other tenants' customers are reported as missing.
+async fn find_customer(db: &DatabaseConnection, access: &TenantAccess, id: Uuid) -> Result<customer::Model, StatusCode> {
+    access
+        .scope(customer::Entity::find_by_id(id), customer::Column::TenantId)
+        .one(db)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch customer: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
 }
 
 pub async fn create_customer(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Json(payload): Json<CreateCustomerInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+
     // Parse the customer type string into the enum
     let customer_type = match payload.customer_type.as_str() {
         "Household" => CustomerType::Household,
@@ -53,6 +78,9 @@ pub async fn create_customer(
         "Person" => CustomerType::Person,
         _ => return Err(StatusCode::BAD_REQUEST),
     };
+    let properties = custom_fields::validate_properties(&db, access.tenant_id, CustomFieldEntity::Customer, payload.properties, true)
+        .await
+        .map_err(field_error)?;
 
     let new_customer = customer::ActiveModel {
         id: Set(Uuid::new_v4()),
@@ -78,11 +106,12 @@ pub async fn create_customer(
         updated_at: Set(Utc::now()),
         billing_address: Set(None),
         shipping_address: Set(None),
-        tenant_id: Set(None),
-        properties: Set(None),
+        tenant_id: Set(access.tenant_id),
+        properties: Set(properties),
     };
 
-    let mut customer = new_customer.insert(&db).await.map_err(|e| {
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut customer = new_customer.insert(&txn).await.map_err(|e| {
         tracing::error!("Failed to create customer: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
@@ -94,7 +123,7 @@ pub async fn create_customer(
         })?;
         let mut active_model: customer::ActiveModel = customer.clone().into();
         active_model.billing_address = Set(Some(billing_address));
-        customer = active_model.update(&db).await.map_err(|e| {
+        customer = active_model.update(&txn).await.map_err(|e| {
             tracing::error!("Failed to update customer: {:?}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
@@ -107,25 +136,35 @@ pub async fn create_customer(
         })?;
         let mut active_model: customer::ActiveModel = customer.clone().into();
         active_model.shipping_address = Set(Some(shipping_address));
-        customer = active_model.update(&db).await.map_err(|e| {
+        customer = active_model.update(&txn).await.map_err(|e| {
             tracing::error!("Failed to update customer: {:?}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
     }
 
+    DomainEvents::emit(&txn, PlatformEvent::CustomerCreated, customer.tenant_id, access.user_id, customer.id, None, snapshot(&customer))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     let customer_model: CustomerModel = customer.into();
     Ok((StatusCode::CREATED, JsonResponse(customer_model)))
 }
 
 pub async fn get_customers(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Query(params): Query<std::collections::HashMap<String, String>>,
 ) -> Result<impl IntoResponse, StatusCode> {
     let page: u64 = params.get("page").and_then(|v| v.parse().ok()).unwrap_or(1);
     let items_per_page: u64 = params.get("items_per_page").and_then(|v| v.parse().ok()).unwrap_or(10);
 
-    let paginator = customer::Entity::find()
+    let properties = PropertyQuery::parse(&db, access.tenant_id, CustomFieldEntity::Customer, &params)
+        .await
+        .map_err(field_error)?;
+
+    let paginator = properties
+        .apply(access.scope(customer::Entity::find(), customer::Column::TenantId), customer::Column::Properties)
         .paginate(&db, items_per_page);
     let total_pages = paginator.num_pages().await.map_err(|e| {
         tracing::error!("Failed to get total pages: {:?}", e);
@@ -151,17 +190,10 @@ pub async fn get_customers(
 
 pub async fn get_customer(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let customer = customer::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch customer: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let customer = find_customer(&db, &access, id).await?;
 
     let customer_model: CustomerModel = customer.into();
     Ok((StatusCode::OK, JsonResponse(customer_model)))
@@ -169,19 +201,15 @@ pub async fn get_customer(
 
 pub async fn update_customer(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(payload): Json<UpdateCustomerInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let mut customer: customer::ActiveModel = customer::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch customer: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?
-        .into();
+    access.require(Permission::Write)?;
+    let existing = find_customer(&db, &access, id).await?;
+    let before = snapshot(&existing);
+    let tenant_id = existing.tenant_id;
+    let mut customer: customer::ActiveModel = existing.into();
 
     if let Some(name) = payload.name {
         customer.name = Set(name);
@@ -243,12 +271,24 @@ pub async fn update_customer(
     if let Some(is_active) = payload.is_active {
         customer.is_active = Set(is_active);
     }
+    if let Some(properties) = payload.properties {
+        customer.properties = Set(
+            custom_fields::validate_properties(&db, tenant_id, CustomFieldEntity::Customer, Some(properties), false)
+                .await
+                .map_err(field_error)?,
+        );
+    }
     customer.updated_at = Set(Utc::now());
 
-    let mut updated_customer = customer.update(&db).await.map_err(|e| {
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut updated_customer = customer.update(&txn).await.map_err(|e| {
         tracing::error!("Failed to update customer: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
+    DomainEvents::emit(&txn, PlatformEvent::CustomerUpdated, updated_customer.tenant_id, access.user_id, updated_customer.id, before, snapshot(&updated_customer))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     if let Some(billing_address) = payload.billing_address {
         // Validate the address
@@ -274,37 +314,35 @@ pub async fn update_customer(
 
 pub async fn delete_customer(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let result = customer::Entity::delete_by_id(id)
-        .exec(&db)
+    access.require(Permission::Delete)?;
+    let customer = find_customer(&db, &access, id).await?;
+    let (tenant_id, before) = (customer.tenant_id, snapshot(&customer));
+
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    customer.delete(&txn)
         .await
         .map_err(|e| {
             tracing::error!("Failed to delete customer: {:?}", e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
-
-    if result.rows_affected == 0 {
-        return Err(StatusCode::NOT_FOUND);
-    }
+    DomainEvents::emit(&txn, PlatformEvent::CustomerDeleted, tenant_id, access.user_id, id, before, None)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn add_file_to_customer(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path((customer_id, file_id)): Path<(Uuid, Uuid)>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let customer = customer::Entity::find_by_id(customer_id)
-        .one(&db)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch customer: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let customer = find_customer(&db, &access, customer_id).await?;
 
     customer.add_file(&db, file_id)
         .await
@@ -318,17 +356,10 @@ pub async fn add_file_to_customer(
 
 pub async fn get_customer_files(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(_current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(customer_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let customer = customer::Entity::find_by_id(customer_id)
-        .one(&db)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch customer: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let customer = find_customer(&db, &access, customer_id).await?;
 
     let file_ids = customer.get_associated_files(&db)
         .await
@@ -342,18 +373,20 @@ pub async fn get_customer_files(
 
 pub async fn create_customer_contact(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(customer_id): Path<Uuid>,
     Json(input): Json<CreateContactInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let customer = customer::Entity::find_by_id(customer_id)
-        .one(&db)
+    access.require(Permission::Write)?;
+    let customer = find_customer(&db, &access, customer_id).await?;
+    let properties = custom_fields::validate_properties(&db, customer.tenant_id, CustomFieldEntity::Contact, input.properties, true)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+        .map_err(field_error)?;
 
     let new_contact = contact::ActiveModel {
         id: Set(Uuid::new_v4()),
         customer_id: Set(Some(customer.id)),
+        tenant_id: Set(customer.tenant_id),
         name: Set(input.name),
         first_name: Set(input.first_name),
         last_name: Set(input.last_name),
@@ -366,13 +399,19 @@ pub async fn create_customer_contact(
         facebook: Set(input.facebook),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
+        properties: Set(properties),
         ..Default::default()
     };
 
-    let contact = new_contact.insert(&db).await.map_err(|e| {
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let contact = new_contact.insert(&txn).await.map_err(|e| {
         tracing::error!("Failed to create contact: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
+    DomainEvents::emit(&txn, PlatformEvent::ContactCreated, contact.tenant_id, access.user_id, contact.id, None, snapshot(&contact))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     let contact_model: ContactModel = contact.into();
     Ok((StatusCode::CREATED, JsonResponse(contact_model)))
@@ -380,10 +419,14 @@ pub async fn create_customer_contact(
 
 pub async fn get_customer_contacts(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(customer_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let contacts = contact::Entity::find()
-        .filter(contact::Column::CustomerId.eq(customer_id))
+    let customer = find_customer(&db, &access, customer_id).await?;
+
+    let contacts = access
+        .scope(contact::Entity::find(), contact::Column::TenantId)
+        .filter(contact::Column::CustomerId.eq(customer.id))
         .all(&db)
         .await
         .map_err(|e| {
@@ -397,22 +440,20 @@ pub async fn get_customer_contacts(
 
 pub async fn create_customer_note(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(customer_id): Path<Uuid>,
     Json(input): Json<CreateNoteInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let customer = customer::Entity::find_by_id(customer_id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let customer = find_customer(&db, &access, customer_id).await?;
 
     let new_note = note::ActiveModel {
         id: Set(Uuid::new_v4()),
         content: Set(input.content),
-        created_by: Set(current_user.id),
+        created_by: Set(access.user_id),
         entity_type: Set("Customer".to_string()),
         entity_id: Set(customer.id),
+        tenant_id: Set(customer.tenant_id),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
     };
@@ -424,11 +465,15 @@ pub async fn create_customer_note(
 
 pub async fn get_customer_notes(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(customer_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let notes = note::Entity::find()
+    let customer = find_customer(&db, &access, customer_id).await?;
+
+    let notes = access
+        .scope(note::Entity::find(), note::Column::TenantId)
         .filter(note::Column::EntityType.eq("Customer"))
-        .filter(note::Column::EntityId.eq(customer_id))
+        .filter(note::Column::EntityId.eq(customer.id))
         .all(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -439,26 +484,27 @@ pub async fn get_customer_notes(
 
 pub async fn create_customer_activity(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(customer_id): Path<Uuid>,
     Json(input): Json<CreateActivityInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let customer = customer::Entity::find_by_id(customer_id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let customer = find_customer(&db, &access, customer_id).await?;
+    if let Some(assignee) = input.assigned_to {
+        TaskService::ensure_assignable(&db, customer.tenant_id, assignee).await.map_err(task_error)?;
+    }
 
     let new_activity = activity::ActiveModel {
         id: Set(Uuid::new_v4()),
         customer_id: Set(Some(customer.id)),
+        tenant_id: Set(customer.tenant_id),
         activity_type: Set(input.activity_type),
         title: Set(input.title),
         description: Set(input.description),
         status: Set(input.status),
         due_date: Set(input.due_date),
         completed_at: Set(None),
-        created_by: Set(current_user.id),
+        created_by: Set(access.user_id),
         assigned_to: Set(input.assigned_to),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
@@ -466,16 +512,21 @@ pub async fn create_customer_activity(
     };
 
     let inserted_activity = new_activity.insert(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
+    TaskService::ensure_reminders(&db, &inserted_activity).await.map_err(|e| task_error(e.into()))?;
+
     Ok((StatusCode::CREATED, JsonResponse(ActivityModel::from(inserted_activity))))
 }
 
 pub async fn get_customer_activities(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(customer_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let activities = activity::Entity::find()
-        .filter(activity::Column::CustomerId.eq(Some(customer_id)))
+    let customer = find_customer(&db, &access, customer_id).await?;
+
+    let activities = access
+        .scope(activity::Entity::find(), activity::Column::TenantId)
+        .filter(activity::Column::CustomerId.eq(Some(customer.id)))
         .all(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -1,19 +1,34 @@
 use axum::{
-    extract::{Extension, Path, Json, State},
+    extract::{Extension, Path, Json, Query, State},
     http::StatusCode,
     response::{IntoResponse, Json as JsonResponse},
     routing::{get, post, put, delete},
     Router,
 };
 use sea_orm::{
-    DatabaseConnection, EntityTrait, Set,
-    ActiveModelTrait, ModelTrait,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+    ActiveModelTrait, ModelTrait, TransactionTrait,
 };
+use std::collections::HashMap;
 use uuid::Uuid;
 use chrono::Utc;
 
-use crate::entities::{deal, customer, contact, user, note, activity};
-use crate::models::deal::{DealModel, CreateDealInput, UpdateDealInput};
+use crate::middleware::api_token::require_api_scope;
+use crate::middleware::permissions::{Permission, TenantAccess};
+use crate::services::domain_events::{snapshot, DomainEvents};
+use crate::services::deal_pipelines::{self, PipelineError, StageTarget};
+use crate::services::custom_fields::{self, PropertyQuery};
+use crate::handlers::activities::task_error;
+use crate::handlers::custom_fields::field_error;
+use crate::services::activity_tasks::TaskService;
+use crate::handlers::files::find_file;
+use crate::models::custom_field::CustomFieldEntity;
+use crate::services::event_catalog::PlatformEvent;
+use crate::entities::{deal, deal_pipeline, deal_stage_history, customer, contact, note, activity};
+use crate::models::deal::{
+    DealModel, CreateDealInput, UpdateDealInput, ChangeStageInput, PipelineModel, CreatePipelineInput,
+    UpdatePipelineInput, StageHistoryModel, DealReportQuery,
+};
 use crate::models::file::FileAssociation;
 use crate::models::note::{NoteModel, CreateNoteInput};
 use crate::models::activity::{ActivityModel, CreateActivityInput};
@@ -26,6 +41,14 @@ pub fn routes() -> Router<DatabaseConnection> {
         .route("/api/deals/{id}", get(get_deal))
         .route("/api/deals/{id}", put(update_deal))
         .route("/api/deals/{id}", delete(delete_deal))
+        .route("/api/deals/{id}/stage", post(change_deal_stage))
+        .route("/api/deals/{id}/history", get(get_deal_history))
+        .route("/api/deals/pipelines", get(get_pipelines))
+        .route("/api/deals/pipelines", post(create_pipeline))
+        .route("/api/deals/pipelines/{id}", put(update_pipeline))
+        .route("/api/deals/pipelines/{id}", delete(delete_pipeline))
+        .route("/api/deals/reports/forecast", get(get_forecast))
+        .route("/api/deals/reports/velocity", get(get_velocity))
         .route("/api/deals/{deal_id}/files/{file_id}", post(add_file_to_deal))
         .route("/api/deals/{id}/files", get(get_deal_files))
         .route("/api/deals/{id}/contacts", get(get_deal_contacts))
@@ -35,46 +58,110 @@ pub fn routes() -> Router<DatabaseConnection> {
         .route("/api/deals/{id}/notes", get(get_deal_notes))
         .route("/api/deals/{id}/activities", post(create_deal_activity))
         .route("/api/deals/{id}/activities", get(get_deal_activities))
+        .route_layer(axum::middleware::from_fn_with_state("crm", require_api_scope))
+}
+
+/// Loads a deal visible to the caller; other tenants' deals are reported as missing.
+async fn find_deal(db: &DatabaseConnection, access: &TenantAccess, id: Uuid) -> Result<deal::Model, StatusCode> {
+    access
+        .scope(deal::Entity::find_by_id(id), deal::Column::TenantId)
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn find_pipeline(db: &DatabaseConnection, access: &TenantAccess, id: Uuid) -> Result<deal_pipeline::Model, StatusCode> {
+    access
+        .scope(deal_pipeline::Entity::find_by_id(id), deal_pipeline::Column::TenantId)
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub(crate) fn pipeline_error(e: PipelineError) -> StatusCode {
+    match e {
+        PipelineError::NotFound => StatusCode::NOT_FOUND,
+        PipelineError::Invalid(_) => {
+            tracing::debug!("Rejected deal pipeline change: {}", e);
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        PipelineError::Conflict(_) => {
+            tracing::debug!("Rejected deal pipeline change: {}", e);
+            StatusCode::CONFLICT
+        }
+        PipelineError::Db(e) => {
+            tracing::error!("Deal pipeline operation failed: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
 }
 
 pub async fn create_deal(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Json(input): Json<CreateDealInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+
     // Check if the customer exists
-    let customer = customer::Entity::find_by_id(input.customer_id)
+    let customer = access
+        .scope(customer::Entity::find_by_id(input.customer_id), customer::Column::TenantId)
         .one(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    let properties = custom_fields::validate_properties(&db, customer.tenant_id, CustomFieldEntity::Deal, input.properties, true)
+        .await
+        .map_err(field_error)?;
+
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let placement = deal_pipelines::place_new_deal(&txn, customer.tenant_id, input.pipeline_id, input.stage.as_deref())
+        .await
+        .map_err(pipeline_error)?;
 
-    let new_deal = deal::ActiveModel {
+    let now = Utc::now();
+    let mut new_deal = deal::ActiveModel {
         id: Set(Uuid::new_v4()),
         customer_id: Set(customer.id),
         name: Set(input.name),
         amount: Set(input.amount),
-        status: Set(input.status),
-        stage: Set(input.stage),
         close_date: Set(input.close_date),
         is_active: Set(true),
-        created_at: Set(Utc::now()),
-        updated_at: Set(Utc::now()),
-        tenant_id: Set(None),
-        properties: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+        tenant_id: Set(customer.tenant_id),
+        properties: Set(properties),
+        ..Default::default()
     };
+    placement.apply(&mut new_deal, now);
 
-    let deal = new_deal.insert(&db).await.map_err(|e| {
+    let deal = new_deal.insert(&txn).await.map_err(|e| {
         eprintln!("Failed to insert deal DB ERROR: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
-    
+    deal_pipelines::record_stage_entry(&txn, None, &deal, access.user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    DomainEvents::emit(&txn, PlatformEvent::DealCreated, deal.tenant_id, access.user_id, deal.id, None, snapshot(&deal))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     Ok((StatusCode::CREATED, JsonResponse(DealModel::from(deal))))
 }
 
 pub async fn get_deals(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Query(params): Query<HashMap<String, String>>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let deals = deal::Entity::find()
+    let properties = PropertyQuery::parse(&db, access.tenant_id, CustomFieldEntity::Deal, &params)
+        .await
+        .map_err(field_error)?;
+    let deals = properties
+        .apply(access.scope(deal::Entity::find(), deal::Column::TenantId), deal::Column::Properties)
         .all(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -85,79 +172,186 @@ pub async fn get_deals(
 
 pub async fn get_deal(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let deal = deal::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let deal = find_deal(&db, &access, id).await?;
 
     Ok(JsonResponse(DealModel::from(deal)))
 }
 
 pub async fn update_deal(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<UpdateDealInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let mut deal: deal::ActiveModel = deal::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?
-        .into();
+    access.require(Permission::Write)?;
+    let existing = find_deal(&db, &access, id).await?;
+    let before = existing.clone();
+    let properties = match input.properties {
+        Some(properties) => Some(
+            custom_fields::validate_properties(&db, before.tenant_id, CustomFieldEntity::Deal, Some(properties), false)
+                .await
+                .map_err(field_error)?,
+        ),
+        None => None,
+    };
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let placement = match input.stage.as_deref() {
+        Some(stage) => deal_pipelines::plan_transition(&txn, &before, StageTarget { stage: Some(stage), ..Default::default() })
+            .await
+            .map_err(pipeline_error)?,
+        None => None,
+    };
 
+    let now = Utc::now();
+    let mut deal: deal::ActiveModel = existing.into();
     if let Some(name) = input.name {
         deal.name = Set(name);
     }
     if let Some(amount) = input.amount {
         deal.amount = Set(amount);
     }
-    if let Some(status) = input.status {
-        deal.status = Set(status);
-    }
-    if let Some(stage) = input.stage {
-        deal.stage = Set(stage);
-    }
     if let Some(close_date) = input.close_date {
         deal.close_date = Set(Some(close_date));
     }
     if let Some(is_active) = input.is_active {
         deal.is_active = Set(is_active);
     }
-    deal.updated_at = Set(Utc::now());
+    if let Some(properties) = properties {
+        deal.properties = Set(properties);
+    }
+    if let Some(placement) = &placement {
+        placement.apply(&mut deal, now);
+    }
+    deal.updated_at = Set(now);
 
-    let updated_deal = deal.update(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let updated_deal = deal.update(&txn).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if placement.is_some() {
+        deal_pipelines::record_stage_entry(&txn, Some(&before), &updated_deal, access.user_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+    emit_deal_update_events(&txn, &access, &before, &updated_deal)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(JsonResponse(DealModel::from(updated_deal)))
 }
 
-pub async fn delete_deal(
+/// Moves a deal to another stage of its pipeline, or to a stage of another pipeline. Moving to the
+/// stage the deal is already in is a conflict, as is moving a won or lost deal without `reopen`.
+pub async fn change_deal_stage(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Path(id): Path<Uuid>,
+    Json(input): Json<ChangeStageInput>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+    let before = find_deal(&db, &access, id).await?;
+
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let target = StageTarget {
+        pipeline_id: input.pipeline_id,
+        stage_id: input.stage_id,
+        stage: input.stage.as_deref(),
+        reopen: input.reopen,
+    };
+    let placement = deal_pipelines::plan_transition(&txn, &before, target)
+        .await
+        .map_err(pipeline_error)?
+        .ok_or(StatusCode::CONFLICT)?;
+
+    let now = Utc::now();
+    let mut deal: deal::ActiveModel = before.clone().into();
+    placement.apply(&mut deal, now);
+    deal.updated_at = Set(now);
+    let updated_deal = deal.update(&txn).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    deal_pipelines::record_stage_entry(&txn, Some(&before), &updated_deal, access.user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    emit_deal_update_events(&txn, &access, &before, &updated_deal)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(JsonResponse(DealModel::from(updated_deal)))
+}
+
+pub async fn get_deal_history(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let result = deal::Entity::delete_by_id(id)
-        .exec(&db)
+    let deal = find_deal(&db, &access, id).await?;
+
+    let history = deal_stage_history::Entity::find()
+        .filter(deal_stage_history::Column::DealId.eq(deal.id))
+        .order_by_asc(deal_stage_history::Column::ChangedAt)
+        .all(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    if result.rows_affected == 0 {
-        return Err(StatusCode::NOT_FOUND);
+    Ok(JsonResponse(history.into_iter().map(StageHistoryModel::from).collect::<Vec<_>>()))
+}
+
+fn is_won(deal: &deal::Model) -> bool {
+    [&deal.status, &deal.stage]
+        .iter()
+        .any(|value| value.eq_ignore_ascii_case("won") || value.eq_ignore_ascii_case("closed won"))
+}
+
+/// `crm.deal.updated` always fires; stage transitions and wins are also published on their own.
+async fn emit_deal_update_events<C: ConnectionTrait>(
+    conn: &C,
+    access: &TenantAccess,
+    before: &deal::Model,
+    after: &deal::Model,
+) -> Result<(), DbErr> {
+    let mut events = vec![PlatformEvent::DealUpdated];
+    if before.stage != after.stage {
+        events.push(PlatformEvent::DealStageChanged);
+    }
+    if is_won(after) && !is_won(before) {
+        events.push(PlatformEvent::DealWon);
     }
+    for event in events {
+        DomainEvents::emit(conn, event, after.tenant_id, access.user_id, after.id, snapshot(before), snapshot(after)).await?;
+    }
+    Ok(())
+}
+
+pub async fn delete_deal(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Delete)?;
+    let deal = find_deal(&db, &access, id).await?;
+    let (tenant_id, before) = (deal.tenant_id, snapshot(&deal));
+
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    deal.delete(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    DomainEvents::emit(&txn, PlatformEvent::DealDeleted, tenant_id, access.user_id, id, before, None)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn add_file_to_deal(
     State(db): State<DatabaseConnection>,
+    access: TenantAccess,
     Path((deal_id, file_id)): Path<(Uuid, Uuid)>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let deal = deal::Entity::find_by_id(deal_id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let deal = find_deal(&db, &access, deal_id).await?;
+    find_file(&db, &access, file_id).await?;
 
     deal.add_file(&db, file_id)
         .await
@@ -168,13 +362,10 @@ pub async fn add_file_to_deal(
 
 pub async fn get_deal_files(
     State(db): State<DatabaseConnection>,
+    access: TenantAccess,
     Path(deal_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let deal = deal::Entity::find_by_id(deal_id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let deal = find_deal(&db, &access, deal_id).await?;
 
     let file_ids = deal.get_associated_files(&db)
         .await
@@ -185,13 +376,10 @@ pub async fn get_deal_files(
 
 pub async fn get_deal_contacts(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let deal = deal::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let deal = find_deal(&db, &access, id).await?;
 
     let contacts = deal.get_contacts(&db)
         .await
@@ -202,15 +390,14 @@ pub async fn get_deal_contacts(
 
 pub async fn add_contact_to_deal(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path((deal_id, contact_id)): Path<(Uuid, Uuid)>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let deal = deal::Entity::find_by_id(deal_id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let deal = find_deal(&db, &access, deal_id).await?;
 
-    let contact = contact::Entity::find_by_id(contact_id)
+    let contact = access
+        .scope(contact::Entity::find_by_id(contact_id), contact::Column::TenantId)
         .one(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
@@ -225,15 +412,14 @@ pub async fn add_contact_to_deal(
 
 pub async fn remove_contact_from_deal(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path((deal_id, contact_id)): Path<(Uuid, Uuid)>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let deal = deal::Entity::find_by_id(deal_id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let deal = find_deal(&db, &access, deal_id).await?;
 
-    let contact = contact::Entity::find_by_id(contact_id)
+    let contact = access
+        .scope(contact::Entity::find_by_id(contact_id), contact::Column::TenantId)
         .one(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
@@ -248,22 +434,20 @@ pub async fn remove_contact_from_deal(
 
 pub async fn create_deal_note(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<CreateNoteInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let deal = deal::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let deal = find_deal(&db, &access, id).await?;
 
     let new_note = note::ActiveModel {
         id: Set(Uuid::new_v4()),
         content: Set(input.content),
-        created_by: Set(current_user.id),
+        created_by: Set(access.user_id),
         entity_type: Set("Deal".to_string()),
         entity_id: Set(deal.id),
+        tenant_id: Set(deal.tenant_id),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
     };
@@ -277,16 +461,13 @@ pub async fn create_deal_note(
 
 pub async fn get_deal_notes(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let deal = deal::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let deal = find_deal(&db, &access, id).await?;
 
-    let notes = deal
-        .find_related(note::Entity)
+    let notes = access
+        .scope(deal.find_related(note::Entity), note::Column::TenantId)
         .all(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -297,26 +478,27 @@ pub async fn get_deal_notes(
 
 pub async fn create_deal_activity(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<CreateActivityInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let deal = deal::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let deal = find_deal(&db, &access, id).await?;
+    if let Some(assignee) = input.assigned_to {
+        TaskService::ensure_assignable(&db, deal.tenant_id, assignee).await.map_err(task_error)?;
+    }
 
     let new_activity = activity::ActiveModel {
         id: Set(Uuid::new_v4()),
         deal_id: Set(Some(deal.id)),
+        tenant_id: Set(deal.tenant_id),
         activity_type: Set(input.activity_type),
         title: Set(input.title),
         description: Set(input.description),
         status: Set(input.status),
         due_date: Set(input.due_date),
         completed_at: Set(None),
-        created_by: Set(current_user.id),
+        created_by: Set(access.user_id),
         assigned_to: Set(input.assigned_to),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
@@ -326,22 +508,20 @@ pub async fn create_deal_activity(
     let activity = new_activity.insert(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    TaskService::ensure_reminders(&db, &activity).await.map_err(|e| task_error(e.into()))?;
 
     Ok((StatusCode::CREATED, JsonResponse(ActivityModel::from(activity))))
 }
 
 pub async fn get_deal_activities(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let deal = deal::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let deal = find_deal(&db, &access, id).await?;
 
-    let activities = deal
-        .find_related(activity::Entity)
+    let activities = access
+        .scope(deal.find_related(activity::Entity), activity::Column::TenantId)
         .all(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -349,3 +529,225 @@ pub async fn get_deal_activities(
     let activity_models: Vec<ActivityModel> = activities.into_iter().map(ActivityModel::from).collect();
     Ok(JsonResponse(activity_models))
 }
+
+async fn pipeline_model<C: ConnectionTrait>(conn: &C, pipeline: deal_pipeline::Model) -> Result<PipelineModel, StatusCode> {
+    let stages = deal_pipelines::stages(conn, pipeline.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(PipelineModel::new(pipeline, stages))
+}
+
+/// Clears the default flag on the tenant's other pipelines so `pipeline_id` can take it.
+async fn take_default<C: ConnectionTrait>(conn: &C, tenant_id: Option<Uuid>, pipeline_id: Uuid) -> Result<(), DbErr> {
+    let others = deal_pipeline::Entity::update_many()
+        .col_expr(deal_pipeline::Column::IsDefault, sea_orm::sea_query::Expr::value(false))
+        .filter(deal_pipeline::Column::Id.ne(pipeline_id));
+    let others = match tenant_id {
+        Some(tenant_id) => others.filter(deal_pipeline::Column::TenantId.eq(tenant_id)),
+        None => others.filter(deal_pipeline::Column::TenantId.is_null()),
+    };
+    others.exec(conn).await?;
+    Ok(())
+}
+
+/// Names are unique per tenant regardless of case.
+async fn pipeline_name_taken<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+    name: &str,
+    except: Option<Uuid>,
+) -> Result<bool, StatusCode> {
+    let pipelines = deal_pipeline::Entity::find()
+        .filter(match tenant_id {
+            Some(tenant_id) => deal_pipeline::Column::TenantId.eq(tenant_id),
+            None => deal_pipeline::Column::TenantId.is_null(),
+        })
+        .all(conn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(pipelines
+        .iter()
+        .any(|pipeline| Some(pipeline.id) != except && pipeline.name.eq_ignore_ascii_case(name)))
+}
+
+/// Lists the tenant's pipelines with their stages, creating the default pipeline on first use.
+pub async fn get_pipelines(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+) -> Result<impl IntoResponse, StatusCode> {
+    if access.tenant_id.is_some() {
+        deal_pipelines::default_pipeline(&db, access.tenant_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+    let pipelines = access
+        .scope(deal_pipeline::Entity::find(), deal_pipeline::Column::TenantId)
+        .order_by_asc(deal_pipeline::Column::CreatedAt)
+        .all(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut models = Vec::with_capacity(pipelines.len());
+    for pipeline in pipelines {
+        models.push(pipeline_model(&db, pipeline).await?);
+    }
+    Ok(JsonResponse(models))
+}
+
+pub async fn create_pipeline(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Json(input): Json<CreatePipelineInput>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Configure)?;
+    let name = input.name.trim().to_string();
+    if name.is_empty() {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+    deal_pipelines::validate_stages(&input.stages).map_err(pipeline_error)?;
+
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !input.is_default {
+        // The tenant keeps its default pipeline alongside the new one
+        deal_pipelines::default_pipeline(&txn, access.tenant_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+    if pipeline_name_taken(&txn, access.tenant_id, &name, None).await? {
+        return Err(StatusCode::CONFLICT);
+    }
+    let now = Utc::now();
+    let id = Uuid::new_v4();
+    if input.is_default {
+        take_default(&txn, access.tenant_id, id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+    let pipeline = deal_pipeline::ActiveModel {
+        id: Set(id),
+        tenant_id: Set(access.tenant_id),
+        name: Set(name),
+        is_default: Set(input.is_default),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(&txn)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    deal_pipelines::replace_stages(&txn, pipeline.id, &input.stages)
+        .await
+        .map_err(pipeline_error)?;
+    let model = pipeline_model(&txn, pipeline).await?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, JsonResponse(model)))
+}
+
+/// Renames a pipeline, makes it the default, or replaces its stages. Stages that still hold
+/// deals can't be removed.
+pub async fn update_pipeline(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Path(id): Path<Uuid>,
+    Json(input): Json<UpdatePipelineInput>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Configure)?;
+    let existing = find_pipeline(&db, &access, id).await?;
+    if input.is_default == Some(false) && existing.is_default {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let tenant_id = existing.tenant_id;
+    let mut pipeline: deal_pipeline::ActiveModel = existing.into();
+    if let Some(name) = input.name {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        if pipeline_name_taken(&txn, tenant_id, &name, Some(id)).await? {
+            return Err(StatusCode::CONFLICT);
+        }
+        pipeline.name = Set(name);
+    }
+    if input.is_default == Some(true) {
+        take_default(&txn, tenant_id, id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        pipeline.is_default = Set(true);
+    }
+    pipeline.updated_at = Set(Utc::now());
+    let pipeline = pipeline.update(&txn).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if let Some(stages) = &input.stages {
+        deal_pipelines::replace_stages(&txn, pipeline.id, stages)
+            .await
+            .map_err(pipeline_error)?;
+    }
+    let model = pipeline_model(&txn, pipeline).await?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(JsonResponse(model))
+}
+
+/// Deletes a pipeline nothing uses. The default pipeline and pipelines holding deals are kept.
+pub async fn delete_pipeline(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Delete)?;
+    let pipeline = find_pipeline(&db, &access, id).await?;
+    if pipeline.is_default {
+        return Err(StatusCode::CONFLICT);
+    }
+    let deals = deal::Entity::find()
+        .filter(deal::Column::PipelineId.eq(pipeline.id))
+        .count(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if deals > 0 {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    pipeline.delete(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn report_pipeline(
+    db: &DatabaseConnection,
+    access: &TenantAccess,
+    pipeline_id: Option<Uuid>,
+) -> Result<deal_pipeline::Model, StatusCode> {
+    match pipeline_id {
+        Some(id) => find_pipeline(db, access, id).await,
+        None => deal_pipelines::default_pipeline(db, access.tenant_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Weighted forecast of a pipeline's open deals: each stage's amount times its win probability.
+pub async fn get_forecast(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Query(query): Query<DealReportQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let pipeline = report_pipeline(&db, &access, query.pipeline_id).await?;
+    let report = deal_pipelines::forecast(&db, &pipeline, query.close_from, query.close_to)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(JsonResponse(report))
+}
+
+/// Time in stage, win rate and sales cycle length for a pipeline, from the stage history.
+pub async fn get_velocity(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Query(query): Query<DealReportQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let pipeline = report_pipeline(&db, &access, query.pipeline_id).await?;
+    let report = deal_pipelines::velocity(&db, &pipeline, Utc::now())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(JsonResponse(report))
+}
@@ -7,7 +7,11 @@ use axum::{
 use sea_orm::{
     DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait, Set, ActiveModelTrait, QueryOrder, Order, TransactionTrait, PaginatorTrait
 };
-use crate::entities::feed::Entity as Feed;
+use crate::entities::feed::{self, Entity as Feed};
+use crate::handlers::feeds::find_tenant_feed;
+use crate::middleware::permissions::{Permission, TenantAccess};
+use crate::services::domain_events::{snapshot, DomainEvents};
+use crate::services::event_catalog::PlatformEvent;
 use crate::entities::feed_item::{self, Entity as FeedItem};
 use crate::entities::attachment::{self, Entity as Attachment};
 use crate::models::feed_item::{FeedItemModel, CreateFeedItem, UpdateFeedItem};
@@ -139,22 +143,31 @@ pub async fn get_feed_items_by_feed(
     Ok((StatusCode::OK, Json(feed_item_models)))
 }
 
-pub async fn create_feed_item(
-    State(db): State<DatabaseConnection>,
-    Json(payload): Json<CreateFeedItem>,
-) -> Result<impl IntoResponse, StatusCode> {
-    // Check if feed exists
-    let feed_exists = Feed::find_by_id(payload.feed_id)
-        .count(&db)
+/// Loads a feed item, together with its parent feed, when that feed belongs to the caller's tenant.
+async fn find_tenant_feed_item(db: &DatabaseConnection, access: &TenantAccess, feed_item_id: Uuid) -> Result<(feed_item::Model, feed::Model), StatusCode> {
+    let feed_item = FeedItem::find_by_id(feed_item_id)
+        .one(db)
         .await
         .map_err(|err| {
             tracing::error!("Database error: {:?}", err);
             StatusCode::INTERNAL_SERVER_ERROR
-        })? > 0;
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
 
-    if !feed_exists {
-        return Err(StatusCode::BAD_REQUEST);
-    }
+    let feed = find_tenant_feed(db, access, feed_item.feed_id).await?;
+    Ok((feed_item, feed))
+}
+
+pub async fn create_feed_item(
+    State(db): State<DatabaseConnection>,
+    access: TenantAccess,
+    Json(payload): Json<CreateFeedItem>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+    // Feed must exist and belong to the caller's tenant
+    let feed = find_tenant_feed(&db, &access, payload.feed_id)
+        .await
+        .map_err(|status| if status == StatusCode::NOT_FOUND { StatusCode::BAD_REQUEST } else { status })?;
 
     // Start a transaction
     let txn = db.begin().await.map_err(|err| {
@@ -202,6 +215,7 @@ pub async fn create_feed_item(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+    let created_snapshot = snapshot(&feed_item);
     let mut feed_item_model = FeedItemModel::from(feed_item);
 
     // Create attachments if provided
@@ -234,6 +248,13 @@ pub async fn create_feed_item(
         feed_item_model.attachments = Some(attachment_models);
     }
 
+    DomainEvents::emit(&txn, PlatformEvent::FeedItemCreated, Some(feed.tenant_id), access.user_id, feed_item_id, None, created_snapshot)
+        .await
+        .map_err(|err| {
+            tracing::error!("Database error: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
     // Commit the transaction
     txn.commit().await.map_err(|err| {
         tracing::error!("Transaction commit error: {:?}", err);
@@ -246,16 +267,12 @@ pub async fn create_feed_item(
 pub async fn update_feed_item(
     Path(feed_item_id): Path<Uuid>,
     State(db): State<DatabaseConnection>,
+    access: TenantAccess,
     Json(payload): Json<UpdateFeedItem>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let feed_item = FeedItem::find_by_id(feed_item_id)
-        .one(&db)
-        .await
-        .map_err(|err| {
-            tracing::error!("Database error: {:?}", err);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let (feed_item, feed) = find_tenant_feed_item(&db, &access, feed_item_id).await?;
+    let before = snapshot(&feed_item);
 
     let mut feed_item_model: feed_item::ActiveModel = feed_item.into();
 
@@ -314,12 +331,17 @@ pub async fn update_feed_item(
     feed_item_model.date_modified = Set(Utc::now());
     feed_item_model.updated_at = Set(Utc::now());
 
-    let updated_feed_item = feed_item_model.update(&db)
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let updated_feed_item = feed_item_model.update(&txn)
         .await
         .map_err(|err| {
             tracing::error!("Database error: {:?}", err);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
+    DomainEvents::emit(&txn, PlatformEvent::FeedItemUpdated, Some(feed.tenant_id), access.user_id, updated_feed_item.id, before, snapshot(&updated_feed_item))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     let mut feed_item_model = FeedItemModel::from(updated_feed_item);
 
@@ -345,7 +367,11 @@ pub async fn update_feed_item(
 pub async fn delete_feed_item(
     Path(feed_item_id): Path<Uuid>,
     State(db): State<DatabaseConnection>,
+    access: TenantAccess,
 ) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Delete)?;
+    let (feed_item, feed) = find_tenant_feed_item(&db, &access, feed_item_id).await?;
+
     // Start a transaction
     let txn = db.begin().await.map_err(|err| {
         tracing::error!("Transaction error: {:?}", err);
@@ -354,7 +380,7 @@ pub async fn delete_feed_item(
 
     // Delete attachments for this feed item
     Attachment::delete_many()
-        .filter(attachment::Column::FeedItemId.eq(feed_item_id))
+        .filter(attachment::Column::FeedItemId.eq(feed_item.id))
         .exec(&txn)
         .await
         .map_err(|err| {
@@ -363,7 +389,7 @@ pub async fn delete_feed_item(
         })?;
 
     // Delete the feed item
-    let result = FeedItem::delete_by_id(feed_item_id)
+    let result = FeedItem::delete_by_id(feed_item.id)
         .exec(&txn)
         .await
         .map_err(|err| {
@@ -371,15 +397,21 @@ pub async fn delete_feed_item(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+    if result.rows_affected == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    DomainEvents::emit(&txn, PlatformEvent::FeedItemDeleted, Some(feed.tenant_id), access.user_id, feed_item.id, snapshot(&feed_item), None)
+        .await
+        .map_err(|err| {
+            tracing::error!("Database error: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
     // Commit the transaction
     txn.commit().await.map_err(|err| {
         tracing::error!("Transaction commit error: {:?}", err);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
-    if result.rows_affected == 0 {
-        return Err(StatusCode::NOT_FOUND);
-    }
-
     Ok(StatusCode::NO_CONTENT)
 } 
\ No newline at end of file
@@ -7,6 +7,8 @@ use axum::{
 use sea_orm::{
     DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait, Set, ActiveModelTrait, QueryOrder, Order
 };
+use crate::middleware::api_token::require_api_scope;
+use crate::middleware::permissions::{Permission, TenantAccess};
 use crate::entities::feed::{self, Entity as Feed};
 use crate::entities::feed_item::{self, Entity as FeedItem};
 use crate::entities::attachment::{self, Entity as Attachment};
@@ -50,6 +52,7 @@ pub fn authenticated_routes(db: DatabaseConnection) -> Router<DatabaseConnection
         .route("/api/feed-items", post(feed_items::create_feed_item))
         .route("/api/feed-items/{feed_item_id}", put(feed_items::update_feed_item))
         .route("/api/feed-items/{feed_item_id}", delete(feed_items::delete_feed_item))
+        .route_layer(axum::middleware::from_fn_with_state("feed", require_api_scope))
         .with_state(db)
 }
 
@@ -174,10 +177,27 @@ pub async fn get_feeds_by_network(
     Ok((StatusCode::OK, Json(feed_models)))
 }
 
+/// Loads a feed owned by the caller's tenant for management routes; other tenants' feeds are reported as missing.
+pub(crate) async fn find_tenant_feed(db: &DatabaseConnection, access: &TenantAccess, feed_id: Uuid) -> Result<feed::Model, StatusCode> {
+    access
+        .scope(Feed::find_by_id(feed_id), feed::Column::TenantId)
+        .one(db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Database error: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
 pub async fn create_feed(
     State(db): State<DatabaseConnection>,
+    access: TenantAccess,
     Json(payload): Json<CreateFeed>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+    access.ensure_visible(Some(payload.tenant_id)).map_err(|_| StatusCode::FORBIDDEN)?;
+
     // Generate a unique ID for the feed
     let feed_id = Uuid::new_v4();
     
@@ -219,16 +239,11 @@ pub async fn create_feed(
 pub async fn update_feed(
     Path(feed_id): Path<Uuid>,
     State(db): State<DatabaseConnection>,
+    access: TenantAccess,
     Json(payload): Json<UpdateFeed>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let feed = Feed::find_by_id(feed_id)
-        .one(&db)
-        .await
-        .map_err(|err| {
-            tracing::error!("Database error: {:?}", err);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let feed = find_tenant_feed(&db, &access, feed_id).await?;
 
     let mut feed_model: feed::ActiveModel = feed.into();
 
@@ -275,10 +290,14 @@ pub async fn update_feed(
 pub async fn delete_feed(
     Path(feed_id): Path<Uuid>,
     State(db): State<DatabaseConnection>,
+    access: TenantAccess,
 ) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Delete)?;
+    let feed = find_tenant_feed(&db, &access, feed_id).await?;
+
     // First, delete all feed items associated with this feed
     let feed_items = FeedItem::find()
-        .filter(feed_item::Column::FeedId.eq(feed_id))
+        .filter(feed_item::Column::FeedId.eq(feed.id))
         .all(&db)
         .await
         .map_err(|err| {
@@ -308,7 +327,7 @@ pub async fn delete_feed(
     }
 
     // Finally, delete the feed
-    Feed::delete_by_id(feed_id)
+    Feed::delete_by_id(feed.id)
         .exec(&db)
         .await
         .map_err(|err| {
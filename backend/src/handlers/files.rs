@@ -8,11 +8,24 @@ use sea_orm::{DatabaseConnection, EntityTrait, Set, ActiveModelTrait, QueryFilte
 use uuid::Uuid;
 use crate::entities::{file::{self, Entity as File}, file_association};
 use crate::models::file::{FileModel, CreateFileInput, UpdateFileInput};
+use crate::middleware::permissions::{Permission, TenantAccess};
+
+/// Loads a file visible to the caller; other tenants' files are reported as missing.
+pub(crate) async fn find_file(db: &DatabaseConnection, access: &TenantAccess, id: Uuid) -> Result<file::Model, StatusCode> {
+    access
+        .scope(File::find_by_id(id.to_string()), file::Column::TenantId)
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
 
 pub async fn create_file(
     State(db): State<DatabaseConnection>,
+    access: TenantAccess,
     Json(input): Json<CreateFileInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
     let new_file = file::ActiveModel {
         id: Set(Uuid::new_v4().to_string()),
         name: Set(input.name),
@@ -29,6 +42,7 @@ pub async fn create_file(
         date_last_view: Set(None),
         is_anonymous: Set(input.is_anonymous),
         user_id: Set(input.user_id.map(|id| id.to_string())),
+        tenant_id: Set(access.tenant_id),
     };
 
     let file = new_file.insert(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -38,14 +52,11 @@ pub async fn create_file(
 pub async fn update_file(
     State(db): State<DatabaseConnection>,
     Path(id): Path<Uuid>,
+    access: TenantAccess,
     Json(input): Json<UpdateFileInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let mut file: file::ActiveModel = File::find_by_id(id.to_string())
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?
-        .into();
+    access.require(Permission::Write)?;
+    let mut file: file::ActiveModel = find_file(&db, &access, id).await?.into();
 
     if let Some(name) = input.name {
         file.name = Set(name);
@@ -73,12 +84,9 @@ pub async fn update_file(
 pub async fn get_file(
     State(db): State<DatabaseConnection>,
     Path(id): Path<Uuid>,
+    access: TenantAccess,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let file = File::find_by_id(id.to_string())
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let file = find_file(&db, &access, id).await?;
 
     Ok((StatusCode::OK, Json(FileModel::from(file))))
 }
@@ -86,12 +94,9 @@ pub async fn get_file(
 pub async fn get_file_info(
     State(db): State<DatabaseConnection>,
     Path(id): Path<Uuid>,
+    access: TenantAccess,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let file = File::find_by_id(id.to_string())
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let file = find_file(&db, &access, id).await?;
 
     Ok((StatusCode::OK, Json(FileModel::from(file))))
 }
@@ -99,15 +104,12 @@ pub async fn get_file_info(
 pub async fn get_file_thumbnail(
     State(db): State<DatabaseConnection>,
     Path(id): Path<Uuid>,
+    access: TenantAccess,
     Query(_params): Query<std::collections::HashMap<String, String>>,
 ) -> Result<impl IntoResponse, StatusCode> {
     // Implement thumbnail generation logic here
     // For now, we'll just return the file info
-    let file = File::find_by_id(id.to_string())
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let file = find_file(&db, &access, id).await?;
 
     Ok((StatusCode::OK, Json(FileModel::from(file))))
 }
@@ -115,8 +117,11 @@ pub async fn get_file_thumbnail(
 pub async fn delete_file(
     State(db): State<DatabaseConnection>,
     Path(id): Path<Uuid>,
+    access: TenantAccess,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let result = File::delete_by_id(id.to_string())
+    access.require(Permission::Delete)?;
+    let file = find_file(&db, &access, id).await?;
+    let result = File::delete_by_id(file.id)
         .exec(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -131,8 +136,9 @@ pub async fn delete_file(
 pub async fn get_user_files(
     State(db): State<DatabaseConnection>,
     Path(user_id): Path<Uuid>,
+    access: TenantAccess,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let files = File::find()
+    let files = access.scope(File::find(), file::Column::TenantId)
         .filter(file::Column::UserId.eq(user_id.to_string()))
         .all(&db)
         .await
@@ -159,7 +165,11 @@ pub async fn get_user_lists(
 pub async fn associate_file(
     State(db): State<DatabaseConnection>,
     Path((file_id, entity_type, entity_id)): Path<(Uuid, String, Uuid)>,
+    access: TenantAccess,
 ) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+    find_file(&db, &access, file_id).await?;
+
     let file_association = file_association::ActiveModel {
         id: Set(Uuid::new_v4()),
         file_id: Set(file_id.to_string()),
@@ -177,7 +187,11 @@ pub async fn associate_file(
 pub async fn disassociate_file(
     State(db): State<DatabaseConnection>,
     Path((file_id, entity_type, entity_id)): Path<(Uuid, String, Uuid)>,
+    access: TenantAccess,
 ) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+    find_file(&db, &access, file_id).await?;
+
     let result = file_association::Entity::delete_many()
         .filter(file_association::Column::FileId.eq(file_id.to_string()))
         .filter(file_association::Column::AssociatedEntityType.eq(entity_type))
@@ -196,6 +210,7 @@ pub async fn disassociate_file(
 pub async fn get_associated_files(
     State(db): State<DatabaseConnection>,
     Path((entity_type, entity_id)): Path<(String, Uuid)>,
+    access: TenantAccess,
 ) -> Result<impl IntoResponse, StatusCode> {
     let associations = file_association::Entity::find()
         .filter(file_association::Column::AssociatedEntityType.eq(entity_type))
@@ -205,7 +220,7 @@ pub async fn get_associated_files(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     let file_ids: Vec<String> = associations.into_iter().map(|a| a.file_id).collect();
-    let files = File::find()
+    let files = access.scope(File::find(), file::Column::TenantId)
         .filter(file::Column::Id.is_in(file_ids))
         .all(&db)
         .await
@@ -5,7 +5,7 @@ use axum::{
     Json, Extension,
 };
 use serde::{Deserialize, Serialize};
-use sea_orm::{DatabaseConnection, ConnectionTrait};
+use sea_orm::{DatabaseConnection, ConnectionTrait, DbErr, TransactionTrait};
 use aws_sdk_s3::presigning::PresigningConfig;
 use aws_sdk_s3::Client;
 use crate::config::site_config::SiteConfig;
@@ -40,23 +40,21 @@ pub async fn submit_form(
         "INSERT INTO form_submissions (id, form_id, tenant_id, payload_json) VALUES ($1, $2, $3, $4)",
         vec![sub_id.into(), form_u.into(), tenant_id.into(), payload.payload_json.clone().into()]
     );
-    let res = db.execute(stmt).await;
+    // The submission and its webhook fan-out commit together
+    let res: Result<(), DbErr> = async {
+        let txn = db.begin().await?;
+        txn.execute(stmt).await?;
+        crate::services::webhook::dispatch_event(
+            &txn,
+            tenant_id,
+            crate::services::event_catalog::PlatformEvent::WebformSubmitted,
+            payload.payload_json.clone(),
+        ).await?;
+        txn.commit().await
+    }.await;
 
     match res {
         Ok(_) => {
-            let db_clone = db.clone();
-            let payload_clone = payload.payload_json.clone();
-            tokio::spawn(async move {
-                if let Err(e) = crate::services::webhook::dispatch_event(
-                    &db_clone,
-                    tenant_id,
-                    "webform.submitted",
-                    payload_clone,
-                ).await {
-                    tracing::error!("Webhook dispatch failed for form submission {}: {:?}", sub_id, e);
-                }
-            });
-
             (StatusCode::OK, Json(FormSubmissionResp { success: true, submission_id: sub_id.to_string() })).into_response()
         }
         Err(e) => {
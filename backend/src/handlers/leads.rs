@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Extension, Path, Json},
+    extract::{Extension, Path, Json, Query},
     http::StatusCode,
     response::{IntoResponse, Json as JsonResponse},
     routing::{get, post, put, delete},
@@ -7,27 +7,38 @@ use axum::{
 };
 use sea_orm::{
     DatabaseConnection, EntityTrait, QueryFilter, Set, ColumnTrait,
-    ActiveModelTrait, ModelTrait
+    ActiveModelTrait, ModelTrait, TransactionTrait, ActiveValue, DbErr, QueryOrder, QuerySelect, Select
 };
+use sea_orm::sea_query::{Expr, Func};
+use serde_json::Value;
+use std::collections::HashMap;
 use uuid::Uuid;
 use chrono::Utc;
-use crate::entities::{lead, listing, account, note,user, activity};
-use crate::models::lead::{LeadModel, CreateLeadInput, UpdateLeadInput};
+use crate::middleware::api_token::require_api_scope;
+use crate::middleware::permissions::{Permission, TenantAccess};
+use crate::services::domain_events::{snapshot, DomainEvents};
+use crate::services::deal_pipelines;
+use crate::services::event_catalog::PlatformEvent;
+use crate::handlers::deals::pipeline_error;
+use crate::handlers::activities::task_error;
+use crate::handlers::custom_fields::field_error;
+use crate::services::activity_tasks::TaskService;
+use crate::services::custom_fields::{self, PropertyQuery};
+use crate::models::custom_field::CustomFieldEntity;
+use crate::entities::{lead, listing, account, note, activity, customer, contact, deal, deal_contact, file_association};
+use crate::entities::customer::CustomerType;
+use crate::models::lead::{LeadModel, CreateLeadInput, UpdateLeadInput, ConvertLeadInput, ConversionOutcome, ConvertedRecord, LeadConversionResult};
+use crate::traits::file::FileAssociable;
 use crate::models::file::FileAssociation;
 use crate::models::note::{NoteModel, CreateNoteInput};
 use crate::models::activity::{ActivityModel, CreateActivityInput};
-use axum::http::HeaderMap;
-use std::time::{Instant, Duration};
-use dashmap::DashMap;
-use once_cell::sync::Lazy;
-use std::sync::Arc;
-
-static RATE_LIMITER: Lazy<Arc<DashMap<String, (u32, Instant)>>> = Lazy::new(|| Arc::new(DashMap::new()));
+use crate::middleware::rate_limiter::{rate_limit, Policy};
 
 pub fn public_routes() -> Router<DatabaseConnection> {
     Router::new()
         .route("/api/leads", post(create_lead))
         .route("/api/v1/leads/ingest", post(ingest_lead))
+        .route_layer(axum::middleware::from_fn_with_state(Policy::LeadIngest, rate_limit))
 }
 
 pub fn authenticated_routes() -> Router<DatabaseConnection> {
@@ -36,18 +47,21 @@ pub fn authenticated_routes() -> Router<DatabaseConnection> {
         .route("/api/leads/{id}", get(get_lead))
         .route("/api/leads/{id}", put(update_lead))
         .route("/api/leads/{id}", delete(delete_lead))
+        .route("/api/leads/{id}/convert", post(convert_lead))
         .route("/api/leads/{lead_id}/files/{file_id}", post(add_file_to_lead))
         .route("/api/leads/{id}/files", get(get_lead_files))
         .route("/api/leads/{id}/notes", get(get_lead_notes))
         .route("/api/leads/{id}/activities", get(get_lead_activities))
+        .route_layer(axum::middleware::from_fn_with_state("crm", require_api_scope))
 }
 
 pub async fn create_lead(
     Extension(db): Extension<DatabaseConnection>,
     site_config_opt: Option<Extension<crate::config::site_config::SiteConfig>>,
-    headers: HeaderMap,
     Json(input): Json<CreateLeadInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    let site_tenant_id = site_config_opt.as_ref().map(|Extension(site_config)| site_config.tenant_id);
+
     // 1. Honeypot check
     if let Some(bot_val) = &input._bot_check {
         if !bot_val.is_empty() {
@@ -56,23 +70,7 @@ pub async fn create_lead(
         }
     }
 
-    // 2. IP Rate Limiting
-    if let Some(ip) = headers.get("x-forwarded-for").and_then(|h| h.to_str().ok()).or_else(|| headers.get("x-real-ip").and_then(|h| h.to_str().ok())) {
-        let ip = ip.split(',').next().unwrap_or("").trim().to_string();
-        if !ip.is_empty() {
-            let mut entry = RATE_LIMITER.entry(ip).or_insert((0, Instant::now()));
-            if entry.value().1.elapsed() > Duration::from_secs(60) {
-                entry.value_mut().0 = 1;
-                entry.value_mut().1 = Instant::now();
-            } else {
-                entry.value_mut().0 += 1;
-                if entry.value().0 > 3 { // Max 3 leads per minute per IP
-                    tracing::warn!("Rate limit exceeded for lead submissions from IP");
-                    return Err(StatusCode::TOO_MANY_REQUESTS);
-                }
-            }
-        }
-    }
+    // 2. Per-IP rate limiting is the LeadIngest policy on these routes
 
     // Check if the listing exists if provided
     if let Some(listing_id) = input.listing_id {
@@ -112,11 +110,17 @@ pub async fn create_lead(
         }
     }
 
+    let tenant_id = resolve_lead_tenant(&db, site_tenant_id, resolved_account_id).await;
+    let properties = custom_fields::validate_properties(&db, tenant_id, CustomFieldEntity::Lead, input.properties, true)
+        .await
+        .map_err(field_error)?;
+
     let mut new_lead = lead::ActiveModel {
         id: Set(Uuid::new_v4()),
         name: Set(input.name),
         listing_id: Set(input.listing_id),
         account_id: Set(resolved_account_id),
+        tenant_id: Set(tenant_id),
         first_name: Set(input.first_name),
         last_name: Set(input.last_name),
         email: Set(input.email),
@@ -135,7 +139,7 @@ pub async fn create_lead(
         converted_contact_id: Set(None),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
-        properties: Set(None),
+        properties: Set(properties),
         ..Default::default()
     };
 
@@ -147,17 +151,23 @@ pub async fn create_lead(
         new_lead.shipping_address = Set(Some(shipping_address));
     }
 
-    let lead = new_lead.insert(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let lead = new_lead.insert(&txn).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    DomainEvents::emit(&txn, PlatformEvent::LeadCreated, lead.tenant_id, None, lead.id, None, snapshot(&lead))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     Ok((StatusCode::CREATED, JsonResponse(LeadModel::from(lead))))
 }
 
 pub async fn ingest_lead(
     Extension(db): Extension<DatabaseConnection>,
     site_config_opt: Option<Extension<crate::config::site_config::SiteConfig>>,
-    headers: HeaderMap,
     Json(input): Json<CreateLeadInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    let site_tenant_id = site_config_opt.as_ref().map(|Extension(site_config)| site_config.tenant_id);
+
     // 1. Honeypot/Spam check
     if let Some(bot_val) = &input._bot_check {
         if !bot_val.is_empty() {
@@ -166,23 +176,7 @@ pub async fn ingest_lead(
         }
     }
 
-    // 2. IP Rate Limiting (same pattern as create_lead)
-    if let Some(ip) = headers.get("x-forwarded-for").and_then(|h| h.to_str().ok()).or_else(|| headers.get("x-real-ip").and_then(|h| h.to_str().ok())) {
-        let ip = ip.split(',').next().unwrap_or("").trim().to_string();
-        if !ip.is_empty() {
-            let mut entry = RATE_LIMITER.entry(ip).or_insert((0, Instant::now()));
-            if entry.value().1.elapsed() > Duration::from_secs(60) {
-                entry.value_mut().0 = 1;
-                entry.value_mut().1 = Instant::now();
-            } else {
-                entry.value_mut().0 += 1;
-                if entry.value().0 > 3 {
-                    tracing::warn!("Rate limit exceeded for lead ingestions from IP");
-                    return Err(StatusCode::TOO_MANY_REQUESTS);
-                }
-            }
-        }
-    }
+    // 2. Per-IP rate limiting is the LeadIngest policy on these routes
 
     // 3. Deduplication & Exclusivity (30 days)
     let mut cond = sea_orm::Condition::any();
@@ -270,11 +264,17 @@ pub async fn ingest_lead(
         }
     }
 
+    let tenant_id = resolve_lead_tenant(&db, site_tenant_id, resolved_account_id).await;
+    let properties = custom_fields::validate_properties(&db, tenant_id, CustomFieldEntity::Lead, input.properties.clone(), true)
+        .await
+        .map_err(field_error)?;
+
     let mut new_lead = lead::ActiveModel {
         id: Set(Uuid::new_v4()),
         name: Set(input.name.clone()),
         listing_id: Set(input.listing_id),
         account_id: Set(resolved_account_id),
+        tenant_id: Set(tenant_id),
         first_name: Set(input.first_name.clone()),
         last_name: Set(input.last_name.clone()),
         email: Set(input.email.clone()),
@@ -288,6 +288,7 @@ pub async fn ingest_lead(
         source: Set(input.source.clone().or_else(|| Some("API Ingestion".to_string()))),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
+        properties: Set(properties),
         ..Default::default()
     };
 
@@ -298,10 +299,15 @@ pub async fn ingest_lead(
         new_lead.shipping_address = Set(Some(shipping_address.clone()));
     }
 
-    let lead = new_lead.insert(&db).await.map_err(|e| {
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let lead = new_lead.insert(&txn).await.map_err(|e| {
         tracing::error!("Failed to save ingested lead: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
+    DomainEvents::emit(&txn, PlatformEvent::LeadCreated, lead.tenant_id, None, lead.id, None, snapshot(&lead))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
     // 5. Trigger usage-based billing asynchronously
     if let Some(acct_id) = lead.account_id {
@@ -325,10 +331,44 @@ pub async fn ingest_lead(
     Ok((StatusCode::CREATED, JsonResponse(LeadModel::from(lead))))
 }
 
+/// Public lead capture has no caller tenant: attribute the lead to the site it was submitted on,
+/// falling back to the tenant of the account it was routed to.
+async fn resolve_lead_tenant(db: &DatabaseConnection, site_tenant_id: Option<Uuid>, account_id: Option<Uuid>) -> Option<Uuid> {
+    if site_tenant_id.is_some() {
+        return site_tenant_id;
+    }
+    let account_id = account_id?;
+    account::Entity::find_by_id(account_id)
+        .one(db)
+        .await
+        .ok()
+        .flatten()
+        .map(|acct| acct.tenant_id)
+}
+
+/// Loads a lead visible to the caller; other tenants' leads are reported as missing.
+async fn find_lead(db: &DatabaseConnection, access: &TenantAccess, id: Uuid) -> Result<lead::Model, StatusCode> {
+    access
+        .scope(lead::Entity::find_by_id(id), lead::Column::TenantId)
+        .one(db)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch lead: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
 pub async fn get_leads(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Query(params): Query<HashMap<String, String>>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let leads = lead::Entity::find()
+    let properties = PropertyQuery::parse(&db, access.tenant_id, CustomFieldEntity::Lead, &params)
+        .await
+        .map_err(field_error)?;
+    let leads = properties
+        .apply(access.scope(lead::Entity::find(), lead::Column::TenantId), lead::Column::Properties)
         .all(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -339,27 +379,25 @@ pub async fn get_leads(
 
 pub async fn get_lead(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let lead = lead::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let lead = find_lead(&db, &access, id).await?;
 
     Ok(JsonResponse(LeadModel::from(lead)))
 }
 
 pub async fn update_lead(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<UpdateLeadInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let mut lead: lead::ActiveModel = lead::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?.into();
+    access.require(Permission::Write)?;
+    let existing = find_lead(&db, &access, id).await?;
+    let before = snapshot(&existing);
+    let tenant_id = existing.tenant_id;
+    let mut lead: lead::ActiveModel = existing.into();
 
     if let Some(name) = input.name {
         lead.name = Set(name);
@@ -445,38 +483,50 @@ pub async fn update_lead(
         lead.converted_contact_id = Set(Some(converted_contact_id));
     }
 
-    let updated_lead = lead.update(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
+    if let Some(properties) = input.properties {
+        lead.properties = Set(
+            custom_fields::validate_properties(&db, tenant_id, CustomFieldEntity::Lead, Some(properties), false)
+                .await
+                .map_err(field_error)?,
+        );
+    }
+
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let updated_lead = lead.update(&txn).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    DomainEvents::emit(&txn, PlatformEvent::LeadUpdated, updated_lead.tenant_id, access.user_id, updated_lead.id, before, snapshot(&updated_lead))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     Ok(JsonResponse(LeadModel::from(updated_lead)))
 }
 
 pub async fn delete_lead(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let lead = lead::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Delete)?;
+    let lead = find_lead(&db, &access, id).await?;
+    let (tenant_id, before) = (lead.tenant_id, snapshot(&lead));
 
-    lead.delete(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    lead.delete(&txn).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    DomainEvents::emit(&txn, PlatformEvent::LeadDeleted, tenant_id, access.user_id, id, before, None)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn add_file_to_lead(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path((lead_id, file_id)): Path<(Uuid, Uuid)>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let lead = lead::Entity::find_by_id(lead_id)
-        .one(&db)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch lead: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let lead = find_lead(&db, &access, lead_id).await?;
 
     lead.add_file(&db, file_id)
         .await
@@ -490,16 +540,10 @@ pub async fn add_file_to_lead(
 
 pub async fn get_lead_files(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(lead_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let lead = lead::Entity::find_by_id(lead_id)
-        .one(&db)
-        .await
-        .map_err(|e| {
-            tracing::error!("Failed to fetch lead: {:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let lead = find_lead(&db, &access, lead_id).await?;
 
     let file_ids = lead.get_associated_files(&db)
         .await
@@ -513,15 +557,13 @@ pub async fn get_lead_files(
 
 pub async fn get_lead_notes(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let lead = lead::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let lead = find_lead(&db, &access, id).await?;
 
-    let notes = note::Entity::find()
+    let notes = access
+        .scope(note::Entity::find(), note::Column::TenantId)
         .filter(note::Column::EntityType.eq("Lead"))
         .filter(note::Column::EntityId.eq(lead.id))
         .all(&db)
@@ -534,15 +576,13 @@ pub async fn get_lead_notes(
 
 pub async fn get_lead_activities(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let lead = lead::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let lead = find_lead(&db, &access, id).await?;
 
-    let activities = activity::Entity::find()
+    let activities = access
+        .scope(activity::Entity::find(), activity::Column::TenantId)
         .filter(activity::Column::LeadId.eq(Some(lead.id)))
         .all(&db)
         .await
@@ -554,22 +594,20 @@ pub async fn get_lead_activities(
 
 pub async fn create_lead_note(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<CreateNoteInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let lead = lead::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let lead = find_lead(&db, &access, id).await?;
 
     let new_note = note::ActiveModel {
         id: Set(Uuid::new_v4()),
         content: Set(input.content),
-        created_by: Set(current_user.id),
+        created_by: Set(access.user_id),
         entity_type: Set("Lead".to_string()),
         entity_id: Set(lead.id),
+        tenant_id: Set(lead.tenant_id),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
     };
@@ -581,26 +619,27 @@ pub async fn create_lead_note(
 
 pub async fn create_lead_activity(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<CreateActivityInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let lead = lead::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let lead = find_lead(&db, &access, id).await?;
+    if let Some(assignee) = input.assigned_to {
+        TaskService::ensure_assignable(&db, lead.tenant_id, assignee).await.map_err(task_error)?;
+    }
 
     let new_activity = activity::ActiveModel {
         id: Set(Uuid::new_v4()),
         lead_id: Set(Some(lead.id)),
+        tenant_id: Set(lead.tenant_id),
         activity_type: Set(input.activity_type),
         title: Set(input.title),
         description: Set(input.description),
         status: Set(input.status),
         due_date: Set(input.due_date),
         completed_at: Set(None),
-        created_by: Set(current_user.id),
+        created_by: Set(access.user_id),
         assigned_to: Set(input.assigned_to),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
@@ -608,6 +647,403 @@ pub async fn create_lead_activity(
     };
 
     let inserted_activity = new_activity.insert(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
+    TaskService::ensure_reminders(&db, &inserted_activity).await.map_err(|e| task_error(e.into()))?;
+
     Ok((StatusCode::CREATED, JsonResponse(ActivityModel::from(inserted_activity))))
 }
+
+/// Restricts a select to the lead's own tenant, so matching never reaches another tenant's records
+/// even for unscoped platform admins.
+fn in_lead_tenant<E: EntityTrait>(select: Select<E>, column: E::Column, tenant_id: Option<Uuid>) -> Select<E> {
+    match tenant_id {
+        Some(tenant_id) => select.filter(column.eq(tenant_id)),
+        None => select.filter(column.is_null()),
+    }
+}
+
+/// Copies a lead value into a matched record's empty field, noting the field's name.
+fn fill_from_lead<T>(field: &mut ActiveValue<Option<T>>, current: &Option<T>, from_lead: &Option<T>, name: &str, filled: &mut Vec<String>)
+where
+    T: Clone,
+    Option<T>: Into<sea_orm::Value>,
+{
+    if current.is_none() && from_lead.is_some() {
+        *field = Set(from_lead.clone());
+        filled.push(name.to_string());
+    }
+}
+
+/// The lead's properties layered under the record's: keys the record already has are kept.
+/// `None` when nothing would change.
+fn merge_properties(current: &Option<Value>, from_lead: &Option<Value>) -> Option<Value> {
+    match (current, from_lead) {
+        (_, None) => None,
+        (None, Some(from_lead)) => Some(from_lead.clone()),
+        (Some(Value::Object(current)), Some(Value::Object(from_lead))) => {
+            let mut merged = current.clone();
+            for (key, value) in from_lead {
+                merged.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+            (merged.len() > current.len()).then_some(Value::Object(merged))
+        }
+        (Some(_), Some(_)) => None,
+    }
+}
+
+fn conversion_db_error(e: DbErr) -> StatusCode {
+    tracing::error!("Lead conversion failed: {:?}", e);
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+/// Converts a lead into a customer and a contact, and optionally opens a deal, in one transaction.
+///
+/// Explicit `customer_id`/`contact_id` win. Otherwise records in the lead's tenant are matched by
+/// email, and new ones are created only when nothing matches. Matched records keep their data; only
+/// their empty fields are filled from the lead. The lead's notes and files move to the customer, and
+/// its activities are linked to the customer, contact and deal.
+pub async fn convert_lead(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Path(id): Path<Uuid>,
+    input: Option<Json<ConvertLeadInput>>,
+) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
+    let input = input.map(|Json(input)| input).unwrap_or_default();
+    let lead = find_lead(&db, &access, id).await?;
+    if lead.is_converted {
+        return Err(StatusCode::CONFLICT);
+    }
+    let customer_type = match input.customer_type.as_deref().unwrap_or("Person") {
+        "Household" => CustomerType::Household,
+        "BusinessEntity" => CustomerType::BusinessEntity,
+        "Person" => CustomerType::Person,
+        _ => return Err(StatusCode::BAD_REQUEST),
+    };
+    let tenant_id = lead.tenant_id;
+    let email = lead.email.as_deref().map(str::trim).filter(|email| !email.is_empty()).map(str::to_lowercase);
+    let now = Utc::now();
+
+    let txn = db.begin().await.map_err(conversion_db_error)?;
+    // Locked and re-checked so concurrent conversions can't both create records for the lead
+    let lead = lead::Entity::find_by_id(lead.id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(conversion_db_error)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if lead.is_converted {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    // The contact is resolved first, since a matched contact brings its customer along
+    let matched_contact = match input.contact_id {
+        Some(contact_id) => Some(
+            in_lead_tenant(contact::Entity::find_by_id(contact_id), contact::Column::TenantId, tenant_id)
+                .one(&txn)
+                .await
+                .map_err(conversion_db_error)?
+                .ok_or(StatusCode::NOT_FOUND)?,
+        ),
+        None => match &email {
+            Some(email) => in_lead_tenant(contact::Entity::find(), contact::Column::TenantId, tenant_id)
+                .filter(Expr::expr(Func::lower(Expr::col(contact::Column::Email))).eq(email.as_str()))
+                .order_by_asc(contact::Column::CreatedAt)
+                .one(&txn)
+                .await
+                .map_err(conversion_db_error)?,
+            None => None,
+        },
+    };
+
+    let mut matched_customer = match input.customer_id {
+        Some(customer_id) => Some(
+            in_lead_tenant(customer::Entity::find_by_id(customer_id), customer::Column::TenantId, tenant_id)
+                .one(&txn)
+                .await
+                .map_err(conversion_db_error)?
+                .ok_or(StatusCode::NOT_FOUND)?,
+        ),
+        None => match matched_contact.as_ref().and_then(|contact| contact.customer_id) {
+            Some(customer_id) => in_lead_tenant(customer::Entity::find_by_id(customer_id), customer::Column::TenantId, tenant_id)
+                .one(&txn)
+                .await
+                .map_err(conversion_db_error)?,
+            None => None,
+        },
+    };
+    if matched_customer.is_none()
+        && let Some(email) = &email
+    {
+        matched_customer = in_lead_tenant(customer::Entity::find(), customer::Column::TenantId, tenant_id)
+            .filter(Expr::expr(Func::lower(Expr::col(customer::Column::Email))).eq(email.as_str()))
+            .order_by_asc(customer::Column::CreatedAt)
+            .one(&txn)
+            .await
+            .map_err(conversion_db_error)?;
+    }
+
+    let (customer_before, mut customer, mut customer_filled) = match matched_customer {
+        Some(existing) => {
+            let mut active: customer::ActiveModel = existing.clone().into();
+            let mut filled = Vec::new();
+            fill_from_lead(&mut active.email, &existing.email, &lead.email, "email", &mut filled);
+            fill_from_lead(&mut active.phone, &existing.phone, &lead.phone, "phone", &mut filled);
+            fill_from_lead(&mut active.whatsapp, &existing.whatsapp, &lead.whatsapp, "whatsapp", &mut filled);
+            fill_from_lead(&mut active.telegram, &existing.telegram, &lead.telegram, "telegram", &mut filled);
+            fill_from_lead(&mut active.twitter, &existing.twitter, &lead.twitter, "twitter", &mut filled);
+            fill_from_lead(&mut active.instagram, &existing.instagram, &lead.instagram, "instagram", &mut filled);
+            fill_from_lead(&mut active.facebook, &existing.facebook, &lead.facebook, "facebook", &mut filled);
+            fill_from_lead(&mut active.billing_address, &existing.billing_address, &lead.billing_address, "billing_address", &mut filled);
+            fill_from_lead(&mut active.shipping_address, &existing.shipping_address, &lead.shipping_address, "shipping_address", &mut filled);
+            if let Some(properties) = merge_properties(&existing.properties, &lead.properties) {
+                active.properties = Set(Some(properties));
+                filled.push("properties".to_string());
+            }
+            let before = snapshot(&existing);
+            let customer = if filled.is_empty() {
+                existing
+            } else {
+                active.updated_at = Set(now);
+                active.update(&txn).await.map_err(conversion_db_error)?
+            };
+            (Some(before), customer, filled)
+        }
+        None => {
+            let customer = customer::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                name: Set(lead.name.clone()),
+                primary_contact_id: Set(None),
+                customer_type: Set(customer_type),
+                attributes: Set(Default::default()),
+                cpf: Set(None),
+                cnpj: Set(None),
+                tin: Set(None),
+                email: Set(lead.email.clone()),
+                phone: Set(lead.phone.clone()),
+                whatsapp: Set(lead.whatsapp.clone()),
+                telegram: Set(lead.telegram.clone()),
+                twitter: Set(lead.twitter.clone()),
+                instagram: Set(lead.instagram.clone()),
+                facebook: Set(lead.facebook.clone()),
+                website: Set(None),
+                annual_revenue: Set(None),
+                employee_count: Set(None),
+                is_active: Set(true),
+                created_at: Set(now),
+                updated_at: Set(now),
+                billing_address: Set(lead.billing_address.clone()),
+                shipping_address: Set(lead.shipping_address.clone()),
+                tenant_id: Set(tenant_id),
+                properties: Set(lead.properties.clone()),
+            }
+            .insert(&txn)
+            .await
+            .map_err(conversion_db_error)?;
+            (None, customer, Vec::new())
+        }
+    };
+
+    let (contact_before, contact, contact_filled) = match matched_contact {
+        Some(existing) => {
+            let mut active: contact::ActiveModel = existing.clone().into();
+            let mut filled = Vec::new();
+            fill_from_lead(&mut active.customer_id, &existing.customer_id, &Some(customer.id), "customer_id", &mut filled);
+            fill_from_lead(&mut active.first_name, &existing.first_name, &lead.first_name, "first_name", &mut filled);
+            fill_from_lead(&mut active.last_name, &existing.last_name, &lead.last_name, "last_name", &mut filled);
+            fill_from_lead(&mut active.email, &existing.email, &lead.email, "email", &mut filled);
+            fill_from_lead(&mut active.phone, &existing.phone, &lead.phone, "phone", &mut filled);
+            fill_from_lead(&mut active.whatsapp, &existing.whatsapp, &lead.whatsapp, "whatsapp", &mut filled);
+            fill_from_lead(&mut active.telegram, &existing.telegram, &lead.telegram, "telegram", &mut filled);
+            fill_from_lead(&mut active.twitter, &existing.twitter, &lead.twitter, "twitter", &mut filled);
+            fill_from_lead(&mut active.instagram, &existing.instagram, &lead.instagram, "instagram", &mut filled);
+            fill_from_lead(&mut active.facebook, &existing.facebook, &lead.facebook, "facebook", &mut filled);
+            fill_from_lead(&mut active.billing_address, &existing.billing_address, &lead.billing_address, "billing_address", &mut filled);
+            fill_from_lead(&mut active.shipping_address, &existing.shipping_address, &lead.shipping_address, "shipping_address", &mut filled);
+            if let Some(properties) = merge_properties(&existing.properties, &lead.properties) {
+                active.properties = Set(Some(properties));
+                filled.push("properties".to_string());
+            }
+            let before = snapshot(&existing);
+            let contact = if filled.is_empty() {
+                existing
+            } else {
+                active.updated_at = Set(now);
+                active.update(&txn).await.map_err(conversion_db_error)?
+            };
+            (Some(before), contact, filled)
+        }
+        None => {
+            let full_name = [lead.first_name.as_deref(), lead.last_name.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let contact = contact::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                customer_id: Set(Some(customer.id)),
+                name: Set(if full_name.is_empty() { lead.name.clone() } else { full_name }),
+                first_name: Set(lead.first_name.clone()),
+                last_name: Set(lead.last_name.clone()),
+                email: Set(lead.email.clone()),
+                phone: Set(lead.phone.clone()),
+                whatsapp: Set(lead.whatsapp.clone()),
+                telegram: Set(lead.telegram.clone()),
+                twitter: Set(lead.twitter.clone()),
+                instagram: Set(lead.instagram.clone()),
+                facebook: Set(lead.facebook.clone()),
+                billing_address: Set(lead.billing_address.clone()),
+                shipping_address: Set(lead.shipping_address.clone()),
+                created_at: Set(now),
+                updated_at: Set(now),
+                tenant_id: Set(tenant_id),
+                properties: Set(lead.properties.clone()),
+            }
+            .insert(&txn)
+            .await
+            .map_err(conversion_db_error)?;
+            (None, contact, Vec::new())
+        }
+    };
+
+    if customer.primary_contact_id.is_none() {
+        let mut active: customer::ActiveModel = customer.into();
+        active.primary_contact_id = Set(Some(contact.id));
+        active.updated_at = Set(now);
+        customer = active.update(&txn).await.map_err(conversion_db_error)?;
+        if customer_before.is_some() {
+            customer_filled.push("primary_contact_id".to_string());
+        }
+    }
+
+    let deal = match input.deal {
+        Some(deal_input) => {
+            let placement = deal_pipelines::place_new_deal(
+                &txn,
+                tenant_id,
+                deal_input.pipeline_id,
+                deal_input.stage.as_deref(),
+            )
+            .await
+            .map_err(pipeline_error)?;
+            let mut new_deal = deal::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                customer_id: Set(customer.id),
+                name: Set(deal_input.name.unwrap_or_else(|| lead.name.clone())),
+                amount: Set(deal_input.amount),
+                close_date: Set(deal_input.close_date),
+                is_active: Set(true),
+                created_at: Set(now),
+                updated_at: Set(now),
+                tenant_id: Set(tenant_id),
+                properties: Set(lead.properties.clone()),
+                ..Default::default()
+            };
+            placement.apply(&mut new_deal, now);
+            let deal = new_deal.insert(&txn).await.map_err(conversion_db_error)?;
+            deal_pipelines::record_stage_entry(&txn, None, &deal, access.user_id)
+                .await
+                .map_err(conversion_db_error)?;
+            deal_contact::ActiveModel {
+                deal_id: Set(deal.id),
+                contact_id: Set(contact.id),
+            }
+            .insert(&txn)
+            .await
+            .map_err(conversion_db_error)?;
+            Some(deal)
+        }
+        None => None,
+    };
+
+    // Re-point the lead's history at the records it became
+    let lead_entity = <lead::Entity as FileAssociable>::entity_type();
+    let customer_entity = <customer::Entity as FileAssociable>::entity_type();
+    let notes_moved = note::Entity::update_many()
+        .col_expr(note::Column::EntityType, Expr::value(customer_entity))
+        .col_expr(note::Column::EntityId, Expr::value(customer.id))
+        .filter(note::Column::EntityType.eq(lead_entity))
+        .filter(note::Column::EntityId.eq(lead.id))
+        .exec(&txn)
+        .await
+        .map_err(conversion_db_error)?
+        .rows_affected;
+    let files_moved = file_association::Entity::update_many()
+        .col_expr(file_association::Column::AssociatedEntityType, Expr::value(customer_entity))
+        .col_expr(file_association::Column::AssociatedEntityId, Expr::value(customer.id))
+        .filter(file_association::Column::AssociatedEntityType.eq(lead_entity))
+        .filter(file_association::Column::AssociatedEntityId.eq(lead.id))
+        .exec(&txn)
+        .await
+        .map_err(conversion_db_error)?
+        .rows_affected;
+    // Activities keep their lead link; links they already have to other records are left alone
+    let link = |column: activity::Column, id: Uuid| {
+        Func::coalesce([Expr::col(column).into(), Expr::value(id)])
+    };
+    let mut activities = activity::Entity::update_many()
+        .col_expr(activity::Column::CustomerId, link(activity::Column::CustomerId, customer.id).into())
+        .col_expr(activity::Column::ContactId, link(activity::Column::ContactId, contact.id).into())
+        .col_expr(activity::Column::UpdatedAt, Expr::value(now));
+    if let Some(deal) = &deal {
+        activities = activities.col_expr(activity::Column::DealId, link(activity::Column::DealId, deal.id).into());
+    }
+    let activities_moved = activities
+        .filter(activity::Column::LeadId.eq(lead.id))
+        .exec(&txn)
+        .await
+        .map_err(conversion_db_error)?
+        .rows_affected;
+
+    let lead_before = snapshot(&lead);
+    let mut active: lead::ActiveModel = lead.into();
+    active.is_converted = Set(true);
+    active.converted_to_contact = Set(true);
+    active.converted_customer_id = Set(Some(customer.id));
+    active.converted_contact_id = Set(Some(contact.id));
+    if let Some(deal) = &deal {
+        active.associated_deal_id = Set(Some(deal.id));
+    }
+    active.updated_at = Set(now);
+    let lead = active.update(&txn).await.map_err(conversion_db_error)?;
+
+    let actor_id = access.user_id;
+    let mut events = Vec::new();
+    match &customer_before {
+        None => events.push((PlatformEvent::CustomerCreated, customer.id, None, snapshot(&customer))),
+        Some(before) if !customer_filled.is_empty() => {
+            events.push((PlatformEvent::CustomerUpdated, customer.id, before.clone(), snapshot(&customer)))
+        }
+        Some(_) => {}
+    }
+    match &contact_before {
+        None => events.push((PlatformEvent::ContactCreated, contact.id, None, snapshot(&contact))),
+        Some(before) if !contact_filled.is_empty() => {
+            events.push((PlatformEvent::ContactUpdated, contact.id, before.clone(), snapshot(&contact)))
+        }
+        Some(_) => {}
+    }
+    if let Some(deal) = &deal {
+        events.push((PlatformEvent::DealCreated, deal.id, None, snapshot(deal)));
+    }
+    events.push((PlatformEvent::LeadUpdated, lead.id, lead_before, snapshot(&lead)));
+    for (event, entity_id, before, after) in events {
+        DomainEvents::emit(&txn, event, tenant_id, actor_id, entity_id, before, after)
+            .await
+            .map_err(conversion_db_error)?;
+    }
+    txn.commit().await.map_err(conversion_db_error)?;
+
+    let outcome = |before: &Option<Option<Value>>| match before {
+        Some(_) => ConversionOutcome::Matched,
+        None => ConversionOutcome::Created,
+    };
+    Ok(JsonResponse(LeadConversionResult {
+        customer: ConvertedRecord { id: customer.id, outcome: outcome(&customer_before), filled: customer_filled },
+        contact: ConvertedRecord { id: contact.id, outcome: outcome(&contact_before), filled: contact_filled },
+        deal_id: deal.map(|deal| deal.id),
+        lead: LeadModel::from(lead),
+        notes_moved,
+        activities_moved,
+        files_moved,
+    }))
+}
@@ -1,3 +1,9 @@
+use crate::config::ModuleFlags;
+use crate::middleware::api_token::require_api_scope;
+use crate::middleware::app_gate::require_module_enabled;
+use crate::middleware::permissions::{Permission, TenantAccess};
+use crate::services::domain_events::{snapshot, DomainEvents};
+use crate::services::event_catalog::PlatformEvent;
 use crate::entities::{
     listing::{self, Entity as Listing},
     profile::{self, Entity as Profile},
@@ -6,7 +12,7 @@ use crate::entities::{
 };
 use crate::models::listing::{ListingCreate, ListingUpdate, ListingStatus, PaginatedListings};
 use sea_orm::{
-    DatabaseConnection, EntityTrait, Set, QueryFilter, ColumnTrait, ActiveModelTrait, TransactionTrait, IntoActiveModel, PaginatorTrait
+    ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, Set, QueryFilter, ColumnTrait, ActiveModelTrait, TransactionTrait, IntoActiveModel, PaginatorTrait
 };
 use axum::{
     extract::{Path, Json, Extension, Query},
@@ -27,6 +33,7 @@ pub fn public_routes(db: DatabaseConnection) -> Router<DatabaseConnection> {
         .route("/listings/{id}", get(get_listing_by_id))
         .route("/listings/by-slug/{slug}", get(get_listing_by_slug))
         .route("/listings/search", get(search_listings))
+        .route_layer(axum::middleware::from_fn_with_state(ModuleFlags::LISTINGS, require_module_enabled))
         .with_state(db)
 }
 
@@ -34,11 +41,13 @@ pub fn authenticated_routes() -> Router<DatabaseConnection> {
     Router::new()
         .route("/api/listings", post(create_listing))
         .route("/api/listings/my-listings", get(get_my_listings).post(create_my_listing))
-        .route("/api/listings/{id}", get(get_listing_by_id))
+        .route("/api/listings/{id}", get(get_tenant_listing))
         .route("/api/listings/{id}", put(update_listing))
         .route("/api/listings/{id}", delete(delete_listing))
         .route("/api/me/accounts/{account_id}/listings", get(get_account_listings))
         // Add other authenticated listing routes here
+        .route_layer(axum::middleware::from_fn_with_state("listing", require_api_scope))
+        .route_layer(axum::middleware::from_fn_with_state(ModuleFlags::LISTINGS, require_module_enabled))
 }
 
 pub async fn get_listings(
@@ -81,6 +90,28 @@ pub async fn get_listing_by_id(
     Ok(Json(listing))
 }
 
+/// Loads a listing owned by the caller's tenant; other tenants' listings are reported as missing.
+async fn find_tenant_listing(db: &DatabaseConnection, access: &TenantAccess, id: Uuid) -> Result<listing::Model, StatusCode> {
+    access
+        .scope(Listing::find_by_id(id), listing::Column::TenantId)
+        .one(db)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error fetching listing: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub async fn get_tenant_listing(
+    Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
+    Path(id): Path<Uuid>,
+) -> Result<Json<listing::Model>, StatusCode> {
+    let listing = find_tenant_listing(&db, &access, id).await?;
+    Ok(Json(listing))
+}
+
 pub async fn get_listing_by_slug(
     Extension(db): Extension<DatabaseConnection>,
     Path(slug): Path<String>,
@@ -98,36 +129,59 @@ pub async fn get_listing_by_slug(
     Ok(Json(listing))
 }
 
+/// Whether the caller may manage listings under `account_id`. Users need to belong to the
+/// account unless they are platform admins; API tokens carry no user and are already confined
+/// to their tenant and scopes.
+async fn can_manage_account<C: ConnectionTrait>(
+    conn: &C,
+    current_user: Option<&user::Model>,
+    account_id: Uuid,
+) -> Result<bool, StatusCode> {
+    let Some(current_user) = current_user else {
+        return Ok(true);
+    };
+    if current_user.is_admin {
+        return Ok(true);
+    }
+    let membership = UserAccount::find()
+        .filter(user_account::Column::UserId.eq(current_user.id))
+        .filter(user_account::Column::AccountId.eq(account_id))
+        .one(conn)
+        .await
+        .map_err(|err| {
+            tracing::error!("Error checking user_account association: {:?}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(membership.is_some())
+}
+
 pub async fn create_listing(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    current_user: Option<Extension<user::Model>>,
+    access: TenantAccess,
     Json(input): Json<ListingCreate>,
     //tuple of (status, listing)
 ) -> Result<(StatusCode, Json<listing::Model>), StatusCode> {
     println!("TEST LOG: from create_listing and input: {:?}", input);
+    access.require(Permission::Write)?;
     // Start transaction
     let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     println!("TEST LOG: from create_listing and txn: {:?}", txn);
-    println!("TEST LOG: from create_listing and current_user: {:?}", current_user);
     println!("TEST LOG: from create_listing and input.profile_id: {:?}", input.profile_id);
     // Verify user has permission to create listing under this profile
-    let profile = Profile::find_by_id(input.profile_id)
+    let profile = access
+        .scope(Profile::find_by_id(input.profile_id), profile::Column::TenantId)
         .one(&txn)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
     println!("TEST LOG: from create_listing and profile: {:?}", profile);
-    let user_account_exists = UserAccount::find()
-        .filter(user_account::Column::UserId.eq(current_user.id))
-        .filter(user_account::Column::AccountId.eq(profile.account_id))
-        .one(&txn)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .is_some();
-    println!("TEST LOG: from create_listing and user_account_exists: {:?}", user_account_exists);
-    if !current_user.is_admin && !user_account_exists {
+    if !can_manage_account(&txn, current_user.as_deref(), profile.account_id).await? {
         return Err(StatusCode::FORBIDDEN);
     }
+    if input.tenant_id != profile.tenant_id {
+        return Err(StatusCode::BAD_REQUEST);
+    }
 
     // Create the listing
     let new_listing = input.into_active_model();
@@ -138,6 +192,9 @@ pub async fn create_listing(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    DomainEvents::emit(&txn, PlatformEvent::ListingCreated, Some(inserted_listing.tenant_id), access.user_id, inserted_listing.id, None, snapshot(&inserted_listing))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     // send 201 created status code
     Ok((StatusCode::CREATED, Json(inserted_listing)))
@@ -146,21 +203,14 @@ pub async fn create_listing(
 
 pub async fn update_listing(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    current_user: Option<Extension<user::Model>>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<ListingUpdate>,
 ) -> Result<Json<listing::Model>, StatusCode> {
     println!("TEST LOG: from update_listing and input: {:?}", input);
-    let existing_listing = Listing::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|err| {
-            println!("TEST LOG: from update_listing and err: {:?}", err);
-            tracing::error!("Error fetching listing: {:?}", err);
-            StatusCode::INTERNAL_SERVER_ERROR
-            
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Write)?;
+    let existing_listing = find_tenant_listing(&db, &access, id).await?;
 
     let profile = Profile::find_by_id(existing_listing.profile_id)
         .one(&db)
@@ -172,22 +222,11 @@ pub async fn update_listing(
         })?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    let user_account_exists = UserAccount::find()
-        .filter(user_account::Column::UserId.eq(current_user.id))
-        .filter(user_account::Column::AccountId.eq(profile.account_id))
-        .one(&db)
-        .await
-        .map_err(|err| {
-            println!("TEST LOG: from update_listing and err: {:?}", err);
-            tracing::error!("Error checking user_account association: {:?}", err);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-    if !current_user.is_admin && user_account_exists.is_none() {
-        println!("TEST LOG: from update_listing and user_account_exists: {:?}", user_account_exists);
+    if !can_manage_account(&db, current_user.as_deref(), profile.account_id).await? {
         return Err(StatusCode::FORBIDDEN);
     }
 
+    let before = existing_listing.clone();
     let mut listing_active_model: listing::ActiveModel = existing_listing.into();
 
     // Update fields if provided in input
@@ -213,28 +252,28 @@ pub async fn update_listing(
 
     listing_active_model.updated_at = Set(Utc::now());
 
-    let updated_listing = listing_active_model.update(&db).await.map_err(|err| {
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let updated_listing = listing_active_model.update(&txn).await.map_err(|err| {
         println!("TEST LOG: from update_listing and err: {:?}", err);
         tracing::error!("Error updating listing: {:?}", err);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
     println!("TEST LOG: from update_listing and updated_listing: {:?}", updated_listing);
+    emit_listing_update_events(&txn, access.user_id, &before, &updated_listing)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     Ok(Json(updated_listing))
 }
 
 pub async fn delete_listing(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    current_user: Option<Extension<user::Model>>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, StatusCode> {
-    let listing = Listing::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error fetching listing: {:?}", err);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Delete)?;
+    let listing = find_tenant_listing(&db, &access, id).await?;
 
     let profile = Profile::find_by_id(listing.profile_id)
         .one(&db)
@@ -245,31 +284,47 @@ pub async fn delete_listing(
         })?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    let user_account_exists = UserAccount::find()
-        .filter(user_account::Column::UserId.eq(current_user.id))
-        .filter(user_account::Column::AccountId.eq(profile.account_id))
-        .one(&db)
-        .await
-        .map_err(|err| {
-            tracing::error!("Error checking user_account association: {:?}", err);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
-
-    if !current_user.is_admin && user_account_exists.is_none() {
+    if !can_manage_account(&db, current_user.as_deref(), profile.account_id).await? {
         return Err(StatusCode::FORBIDDEN);
     }
 
+    let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     Listing::delete_by_id(listing.id)
-        .exec(&db)
+        .exec(&txn)
         .await
         .map_err(|err| {
             tracing::error!("Error deleting listing: {:?}", err);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
+    DomainEvents::emit(&txn, PlatformEvent::ListingDeleted, Some(listing.tenant_id), access.user_id, listing.id, snapshot(&listing), None)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// `listing.updated` always fires; moderation outcomes are also published on their own.
+pub(crate) async fn emit_listing_update_events<C: ConnectionTrait>(
+    conn: &C,
+    actor_id: Option<Uuid>,
+    before: &listing::Model,
+    after: &listing::Model,
+) -> Result<(), DbErr> {
+    let mut events = vec![PlatformEvent::ListingUpdated];
+    if before.status != after.status {
+        match after.status {
+            ListingStatus::Approved => events.push(PlatformEvent::ListingApproved),
+            ListingStatus::Rejected => events.push(PlatformEvent::ListingRejected),
+            _ => {}
+        }
+    }
+    for event in events {
+        DomainEvents::emit(conn, event, Some(after.tenant_id), actor_id, after.id, snapshot(before), snapshot(after)).await?;
+    }
+    Ok(())
+}
+
 pub async fn search_listings(
     Extension(db): Extension<DatabaseConnection>,
     Query(q): Query<crate::models::listing::ListingSearch>,
@@ -321,19 +376,22 @@ pub async fn search_listings(
 
 pub async fn get_account_listings(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    current_user: Option<Extension<user::Model>>,
+    access: TenantAccess,
     Path(account_id): Path<Uuid>,
 ) -> Result<Json<Vec<listing::Model>>, StatusCode> {
-    // 1. Verify access
-    let user_account = UserAccount::find()
-        .filter(user_account::Column::UserId.eq(current_user.id))
-        .filter(user_account::Column::AccountId.eq(account_id))
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    if user_account.is_none() {
-        return Err(StatusCode::FORBIDDEN);
+    // 1. Verify access (API tokens are already confined to their tenant)
+    if let Some(Extension(current_user)) = current_user {
+        let user_account = UserAccount::find()
+            .filter(user_account::Column::UserId.eq(current_user.id))
+            .filter(user_account::Column::AccountId.eq(account_id))
+            .one(&db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        if user_account.is_none() {
+            return Err(StatusCode::FORBIDDEN);
+        }
     }
 
     // 2. Get profiles for this account
@@ -350,7 +408,8 @@ pub async fn get_account_listings(
     }
 
     // 3. Get listings
-    let listings = Listing::find()
+    let listings = access
+        .scope(Listing::find(), listing::Column::TenantId)
         .filter(listing::Column::ProfileId.is_in(profile_ids))
         .all(&db)
         .await
@@ -361,10 +420,11 @@ pub async fn get_account_listings(
 
 pub async fn get_my_listings(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
 ) -> Result<Json<Vec<listing::Model>>, StatusCode> {
+    let user_id = access.user_id.ok_or(StatusCode::FORBIDDEN)?;
     let user_accounts = UserAccount::find()
-        .filter(user_account::Column::UserId.eq(current_user.id))
+        .filter(user_account::Column::UserId.eq(user_id))
         .all(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -381,7 +441,8 @@ pub async fn get_my_listings(
     let profile_ids: Vec<Uuid> = profiles.into_iter().map(|p| p.id).collect();
     if profile_ids.is_empty() { return Ok(Json(vec![])); }
     
-    let listings = Listing::find()
+    let listings = access
+        .scope(Listing::find(), listing::Column::TenantId)
         .filter(listing::Column::ProfileId.is_in(profile_ids))
         .all(&db)
         .await
@@ -403,22 +464,26 @@ pub struct CreateMyListingInput {
 
 pub async fn create_my_listing(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Json(input): Json<CreateMyListingInput>,
 ) -> Result<(StatusCode, Json<listing::Model>), StatusCode> {
     use serde_json::Value;
 
+    access.require(Permission::Write)?;
+    let user_id = access.user_id.ok_or(StatusCode::FORBIDDEN)?;
+
     let txn = db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
     let user_accounts = UserAccount::find()
-        .filter(user_account::Column::UserId.eq(current_user.id))
+        .filter(user_account::Column::UserId.eq(user_id))
         .all(&txn)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         
     let acc_ids: Vec<Uuid> = user_accounts.into_iter().map(|ua| ua.account_id).collect();
     
-    let profile = Profile::find()
+    let profile = access
+        .scope(Profile::find(), profile::Column::TenantId)
         .filter(profile::Column::AccountId.is_in(acc_ids))
         .one(&txn)
         .await
@@ -458,7 +523,10 @@ pub async fn create_my_listing(
         tracing::error!("Error inserting listing: {:?}", e);
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
+    DomainEvents::emit(&txn, PlatformEvent::ListingCreated, Some(inserted.tenant_id), Some(user_id), inserted.id, None, snapshot(&inserted))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
+
     Ok((StatusCode::CREATED, Json(inserted)))
 }
\ No newline at end of file
@@ -8,6 +8,7 @@ pub mod customers;
 pub mod contacts;
 pub mod files;
 pub mod notes;
+pub mod custom_fields;
 
 //Admin
 pub mod ad_purchases;
@@ -26,7 +27,6 @@ pub mod forms;
 pub mod passkeys;
 pub mod admin;
 pub mod sessions;
-pub mod request_logs;
 pub mod health;
 pub mod auth_frontend;
 pub mod my_accounts;
@@ -39,3 +39,4 @@ pub mod magic_links;
 pub mod search;
 pub mod telemetry;
 pub mod audit_logs;
+pub mod tls;
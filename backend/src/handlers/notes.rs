@@ -12,7 +12,9 @@ use sea_orm::{
 use uuid::Uuid;
 use chrono::Utc;
 
-use crate::entities::{note, user};
+use crate::middleware::api_token::require_api_scope;
+use crate::middleware::permissions::{Permission, TenantAccess};
+use crate::entities::note;
 use crate::models::note::{NoteModel, CreateNoteInput, UpdateNoteInput};
 use crate::models::file::FileAssociation;
 
@@ -24,19 +26,32 @@ pub fn routes() -> Router<DatabaseConnection> {
         .route("/notes/{id}", put(update_note))
         .route("/notes/{id}", delete(delete_note))
         .route("/notes/{id}/files", get(get_note_files))
+        .route_layer(axum::middleware::from_fn_with_state("crm", require_api_scope))
+}
+
+/// Loads a note visible to the caller; other tenants' notes are reported as missing.
+async fn find_note(db: &DatabaseConnection, access: &TenantAccess, id: Uuid) -> Result<note::Model, StatusCode> {
+    access
+        .scope(note::Entity::find_by_id(id), note::Column::TenantId)
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
 }
 
 async fn create_note(
     Extension(db): Extension<DatabaseConnection>,
-    Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Json(input): Json<CreateNoteInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
+    access.require(Permission::Write)?;
     let new_note = note::ActiveModel {
         id: Set(Uuid::new_v4()),
         content: Set(input.content),
-        created_by: Set(current_user.id),
+        created_by: Set(access.user_id),
         entity_type: Set(input.entity_type),
         entity_id: Set(input.entity_id),
+        tenant_id: Set(access.tenant_id),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
     };
@@ -48,8 +63,10 @@ async fn create_note(
 
 async fn get_notes(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let notes = note::Entity::find()
+    let notes = access
+        .scope(note::Entity::find(), note::Column::TenantId)
         .all(&db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -60,28 +77,22 @@ async fn get_notes(
 
 async fn get_note(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let note = note::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let note = find_note(&db, &access, id).await?;
 
     Ok(JsonResponse(NoteModel::from(note)))
 }
 
 async fn update_note(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
     Json(input): Json<UpdateNoteInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let mut note: note::ActiveModel = note::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?
-        .into();
+    access.require(Permission::Write)?;
+    let mut note: note::ActiveModel = find_note(&db, &access, id).await?.into();
 
     note.content = Set(input.content);
     note.updated_at = Set(Utc::now());
@@ -94,13 +105,11 @@ async fn update_note(
 
 async fn delete_note(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let note = note::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    access.require(Permission::Delete)?;
+    let note = find_note(&db, &access, id).await?;
 
     note.delete(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -109,13 +118,10 @@ async fn delete_note(
 
 async fn get_note_files(
     Extension(db): Extension<DatabaseConnection>,
+    access: TenantAccess,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let note = note::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let note = find_note(&db, &access, id).await?;
 
     let files = note.get_associated_files(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -5,6 +5,12 @@ use crate::entities::{
     user_account::{self, Entity as UserAccount}
 };
 use crate::models::profile::{ProfileSearch, CreateProfileInput, UpdateProfileInput};
+use crate::models::custom_field::CustomFieldEntity;
+use crate::services::custom_fields::{self, PropertyQuery};
+use crate::handlers::custom_fields::field_error;
+use crate::config::ModuleFlags;
+use crate::middleware::app_gate::require_module_enabled;
+use crate::middleware::permissions::TenantAccess;
 use axum::{
     extract::{Extension, Json, Path, Query,State},
     http::StatusCode,
@@ -13,6 +19,7 @@ use axum::{
     Router,
 };
 use sea_orm::{DatabaseConnection, EntityTrait, Set, Condition, ColumnTrait, QueryFilter, ActiveModelTrait, IntoActiveModel};
+use std::collections::HashMap;
 use uuid::Uuid;
 use chrono::Utc;
 
@@ -22,19 +29,29 @@ pub fn routes(db_connection: DatabaseConnection) -> Router<DatabaseConnection> {
         .route("/api/profiles", post(create_profile).get(get_profiles))
         .route("/api/profiles/{id}", get(get_profile_by_id).put(update_profile).delete(delete_profile))
         .route("/api/profiles/search", get(search_profiles))
+        .route_layer(axum::middleware::from_fn_with_state(ModuleFlags::PROFILES, require_module_enabled))
         .with_state(db_connection)
 }
 
 pub async fn create_profile(
     State(db): State<DatabaseConnection>,
     Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
     Json(input): Json<CreateProfileInput>,
 ) -> Result<impl IntoResponse, StatusCode> {
     tracing::info!("Creating new profile for user: {}", current_user.id);
+    // Profiles are created in the caller's tenant; only unscoped platform admins pick one
+    if access.tenant_id.is_some_and(|tenant_id| tenant_id != input.tenant_id) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let tenant_id = access.tenant_id.unwrap_or(input.tenant_id);
+    let properties = custom_fields::validate_properties(&db, Some(tenant_id), CustomFieldEntity::Profile, input.properties, true)
+        .await
+        .map_err(field_error)?;
 
     // create or find the account
     let account = match Account::find()
-        .filter(account::Column::TenantId.eq(input.tenant_id))
+        .filter(account::Column::TenantId.eq(tenant_id))
         .one(&db)
         .await
         .map_err(|err| {
@@ -45,7 +62,7 @@ pub async fn create_profile(
             None => {
                 let new_account = account::ActiveModel {
                     id: Set(Uuid::new_v4()),
-                    tenant_id: Set(input.tenant_id),
+                    tenant_id: Set(tenant_id),
                     name: Set(input.display_name.clone()),
                     is_active: Set(true),
                     stripe_customer_id: sea_orm::NotSet,
@@ -63,7 +80,7 @@ pub async fn create_profile(
     // Create the profile
     let mut new_profile = profile::ActiveModel {
         id: Set(Uuid::new_v4()),
-        tenant_id: Set(input.tenant_id),
+        tenant_id: Set(tenant_id),
         account_id: Set(account.id),
         profile_type: Set(input.profile_type),
         service_area_zips: sea_orm::NotSet,
@@ -77,7 +94,7 @@ pub async fn create_profile(
         is_active: Set(true),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
-        properties: Set(None),
+        properties: Set(properties),
     };
 
     if let Some(business_details) = input.business_details {
@@ -145,6 +162,7 @@ pub async fn update_profile(
     }
 
     // Update the profile
+    let tenant_id = profile_to_update.tenant_id;
     let mut active_model = profile_to_update.into_active_model();
 
     if let Some(display_name) = input.display_name {
@@ -153,6 +171,13 @@ pub async fn update_profile(
     if let Some(contact_info) = input.contact_info {
         active_model.contact_info = Set(contact_info);
     }
+    if let Some(properties) = input.properties {
+        active_model.properties = Set(
+            custom_fields::validate_properties(&db, Some(tenant_id), CustomFieldEntity::Profile, Some(properties), false)
+                .await
+                .map_err(field_error)?,
+        );
+    }
     if let Some(business_details) = input.business_details {
         active_model.business_name = Set(Some(business_details.business_name));
         active_model.business_address = Set(Some(business_details.business_address));
@@ -171,6 +196,8 @@ pub async fn update_profile(
 pub async fn get_profiles(
     Extension(db): Extension<DatabaseConnection>,
     Extension(current_user): Extension<user::Model>,
+    access: TenantAccess,
+    Query(params): Query<HashMap<String, String>>,
 ) -> Result<Json<Vec<profile::Model>>, StatusCode> {
     println!("TEST LOG: Fetching profiles for user: {}", current_user.id);
     tracing::info!("Fetching profiles for user: {}", current_user.id);
@@ -187,7 +214,11 @@ pub async fn get_profiles(
 
     let profile_ids: Vec<Uuid> = user_accounts.into_iter().map(|up| up.account_id).collect();
 
-    let profiles = Profile::find()
+    let properties = PropertyQuery::parse(&db, access.tenant_id, CustomFieldEntity::Profile, &params)
+        .await
+        .map_err(field_error)?;
+    let profiles = properties
+        .apply(access.scope(Profile::find(), profile::Column::TenantId), profile::Column::Properties)
         .filter(profile::Column::AccountId.is_in(profile_ids))
         .all(&db)
         .await
@@ -21,7 +21,7 @@ use webauthn_rs::prelude::*;
 use once_cell::sync::Lazy;
 use moka::future::Cache;
 use std::time::Duration;
-use crate::handlers::communications::{send_email_handler, SendEmailPayload};
+use crate::handlers::communications::{send_email, SendEmailPayload};
 
 #[derive(Serialize)]
 pub struct SetupStatusResponse {
@@ -215,7 +215,7 @@ pub async fn initialize_finish(
         subject: "Welcome to Atlas Platform!".to_string(),
         body_html: format!("<h2>Atlas Platform Initialized</h2><p>Your administrator profile has been successfully generated and bound to your WebAuthn passkey.</p><br><a href=\"{0}/login\">Access the Platform</a>", frontend_url),
     };
-    let _ = send_email_handler(State(db.clone()), Json(email_payload)).await;
+    let _ = send_email(&db, email_payload).await;
 
     Ok((StatusCode::CREATED, Json(session_response)))
 }
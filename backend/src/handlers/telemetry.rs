@@ -4,16 +4,17 @@ use axum::{
     Router,
     http::StatusCode,
 };
-use sea_orm::DatabaseConnection;
+use sea_orm::{DatabaseConnection, TransactionTrait};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use crate::auth::Claims;
+use crate::middleware::api_token::{require_api_scope, ApiTokenPrincipal};
 use crate::services::telemetry::TelemetryService;
 use uuid::Uuid;
 
 pub fn authenticated_routes() -> Router<DatabaseConnection> {
     Router::new()
         .route("/api/v1/telemetry/events", post(ingest_telemetry_events))
+        .route_layer(axum::middleware::from_fn_with_state("telemetry", require_api_scope))
 }
 
 #[derive(Debug, Deserialize)]
@@ -36,21 +37,33 @@ pub struct IngestResponse {
 
 pub async fn ingest_telemetry_events(
     State(db): State<DatabaseConnection>,
-    Extension(_claims): Extension<Claims>,
+    api_principal: Option<Extension<ApiTokenPrincipal>>,
     headers: axum::http::HeaderMap,
     Json(payload): Json<TelemetryIngestPayload>,
 ) -> Result<axum::Json<IngestResponse>, (StatusCode, String)> {
-    // Extract tenant_id from X-Tenant-Id header
-    let tenant_id_str = headers.get("X-Tenant-Id").and_then(|v| v.to_str().ok()).ok_or_else(|| {
-        (StatusCode::FORBIDDEN, "Missing X-Tenant-Id header context".to_string())
-    })?;
-    
-    let tenant_id = Uuid::parse_str(&tenant_id_str).map_err(|_| {
-        (StatusCode::BAD_REQUEST, "Invalid tenant UUID".to_string())
-    })?;
+    // API tokens are bound to a single tenant, so their tenant wins over any X-Tenant-Id header
+    let tenant_id = match api_principal {
+        Some(Extension(principal)) => principal.tenant_id,
+        None => {
+            // Extract tenant_id from X-Tenant-Id header
+            let tenant_id_str = headers.get("X-Tenant-Id").and_then(|v| v.to_str().ok()).ok_or_else(|| {
+                (StatusCode::FORBIDDEN, "Missing X-Tenant-Id header context".to_string())
+            })?;
+
+            Uuid::parse_str(tenant_id_str).map_err(|_| {
+                (StatusCode::BAD_REQUEST, "Invalid tenant UUID".to_string())
+            })?
+        }
+    };
 
     let event_count = payload.events.len();
+    let store_error = |e: sea_orm::DbErr| {
+        tracing::error!("Failed to queue telemetry events: {:?}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to queue telemetry events".to_string())
+    };
 
+    // A batch is accepted or rejected as a whole
+    let txn = db.begin().await.map_err(store_error)?;
     for event in payload.events {
         // Enforce application namespaces: If an app tries to claim it is 'platform' we reject or override.
         let safe_source = if event.event_source.starts_with("app:") {
@@ -60,13 +73,16 @@ pub async fn ingest_telemetry_events(
         };
 
         TelemetryService::log_event(
-            db.clone(),
+            &txn,
             tenant_id,
             safe_source,
             event.event_type,
             event.payload,
-        );
+        )
+        .await
+        .map_err(store_error)?;
     }
+    txn.commit().await.map_err(store_error)?;
 
     Ok(axum::Json(IngestResponse {
         success: true,
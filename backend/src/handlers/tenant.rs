@@ -1,5 +1,6 @@
 use axum::{
     extract::{Path, State},
+    Extension,
     http::StatusCode,
     Json,
     routing::{get, post, put, delete},
@@ -7,11 +8,16 @@ use axum::{
 };
 use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait, ActiveModelTrait, Set};
 use crate::entities::tenant_setting::{self, Entity as TenantSetting};
+use crate::entities::user;
+use crate::entities::user_account::UserRole;
+use crate::middleware::api_token::{require_api_scope, ApiTokenPrincipal};
+use crate::middleware::permissions::{Permission, TenantAccess};
 use crate::models::tenant::{TenantModel, CreateTenant, UpdateTenant};
 use chrono::Utc;
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
-use crate::services::tenant::TenantService;
+use crate::services::secrets::{Keyring, SecretsError, SecretsService};
+use crate::services::tenant::{LifecycleError, TenantService};
 
 pub fn public_routes(db: DatabaseConnection) -> Router<DatabaseConnection> {
     Router::new()
@@ -26,8 +32,15 @@ pub fn authenticated_routes(db: DatabaseConnection) -> Router<DatabaseConnection
         .route("/api/tenants", post(create_tenant))
         .route("/api/tenants/{id}", put(update_tenant))
         .route("/api/tenants/{id}", delete(delete_tenant))
-        .route("/api/tenants/{id}/settings", get(get_tenant_settings))
-        .route("/api/tenants/{id}/settings", post(upsert_tenant_setting))
+        .with_state(db)
+}
+
+/// Tenant settings, which API tokens reach with the `secrets` scope.
+pub fn settings_routes(db: DatabaseConnection) -> Router<DatabaseConnection> {
+    Router::new()
+        .route("/api/tenants/{tenant_id}/settings", get(get_tenant_settings))
+        .route("/api/tenants/{tenant_id}/settings", post(upsert_tenant_setting))
+        .route_layer(axum::middleware::from_fn_with_state("secrets", require_api_scope))
         .with_state(db)
 }
 
@@ -104,19 +117,34 @@ pub async fn update_tenant(
             tracing::error!("Failed to update tenant: {:?}", err);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
+    crate::middleware::site_context::invalidate_tenant(tenant.id);
 
     Ok((StatusCode::OK, Json(TenantModel::from(tenant))))
 }
 
+/// Schedules the tenant for deletion; the purge job removes its data after the grace period.
+/// Only platform admins and the tenant's owners may do this.
 pub async fn delete_tenant(
     Path(tenant_id): Path<Uuid>,
     State(db): State<DatabaseConnection>,
+    access: TenantAccess,
 ) -> Result<StatusCode, StatusCode> {
-    TenantService::delete_tenant(&db, tenant_id)
+    access.ensure_visible(Some(tenant_id))?;
+    if !access.is_platform_admin && access.role != UserRole::Owner {
+        tracing::warn!("User {:?} with role {:?} may not delete tenant {}", access.user_id, access.role, tenant_id);
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    TenantService::schedule_deletion(&db, tenant_id, access.user_id, None)
         .await
-        .map_err(|err| {
-            tracing::error!("Failed to delete tenant: {:?}", err);
-            StatusCode::INTERNAL_SERVER_ERROR
+        .map_err(|err| match err {
+            LifecycleError::NotFound => StatusCode::NOT_FOUND,
+            LifecycleError::InvalidTransition { .. } => StatusCode::CONFLICT,
+            LifecycleError::InvalidGracePeriod(_) => StatusCode::BAD_REQUEST,
+            LifecycleError::Db(e) => {
+                tracing::error!("Failed to schedule tenant deletion: {:?}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
         })?;
 
     Ok(StatusCode::NO_CONTENT)
@@ -129,10 +157,32 @@ pub struct UpsertTenantSettingPayload {
     pub is_encrypted: Option<bool>,
 }
 
+/// Platform admins and API tokens holding `secrets:read` see decrypted values.
+fn can_read_secrets(api_principal: Option<&ApiTokenPrincipal>, current_user: Option<&user::Model>) -> bool {
+    match api_principal {
+        Some(principal) => principal.has_scope("secrets", "read"),
+        None => current_user.is_some_and(|u| u.is_admin),
+    }
+}
+
+fn secrets_error(err: SecretsError) -> StatusCode {
+    tracing::error!("Failed to process encrypted setting: {}", err);
+    match err {
+        SecretsError::MasterKey(_) => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 pub async fn get_tenant_settings(
     Path(tenant_id): Path<Uuid>,
     State(db): State<DatabaseConnection>,
+    access: TenantAccess,
+    api_principal: Option<Extension<ApiTokenPrincipal>>,
+    current_user: Option<Extension<user::Model>>,
 ) -> Result<(StatusCode, Json<Vec<tenant_setting::Model>>), StatusCode> {
+    // Tokens and members only ever see their own tenant's settings
+    access.ensure_visible(Some(tenant_id))?;
+
     let settings = TenantSetting::find()
         .filter(tenant_setting::Column::TenantId.eq(tenant_id))
         .all(&db)
@@ -142,14 +192,33 @@ pub async fn get_tenant_settings(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    Ok((StatusCode::OK, Json(settings)))
+    if !can_read_secrets(api_principal.as_deref(), current_user.as_deref()) {
+        return Ok((StatusCode::OK, Json(settings.into_iter().map(SecretsService::mask).collect())));
+    }
+
+    let keyring = Keyring::global().map_err(secrets_error)?;
+    let mut revealed = Vec::with_capacity(settings.len());
+    for mut setting in settings {
+        if setting.is_encrypted {
+            setting.value = SecretsService::reveal(&db, keyring, &setting).await.map_err(secrets_error)?;
+        }
+        revealed.push(setting);
+    }
+
+    Ok((StatusCode::OK, Json(revealed)))
 }
 
+/// Settings are tenant configuration, so only the tenant's owners and admins (or a token
+/// holding `secrets:write`) may change them.
 pub async fn upsert_tenant_setting(
     Path(tenant_id): Path<Uuid>,
     State(db): State<DatabaseConnection>,
+    access: TenantAccess,
     Json(payload): Json<UpsertTenantSettingPayload>,
 ) -> Result<(StatusCode, Json<tenant_setting::Model>), StatusCode> {
+    access.ensure_visible(Some(tenant_id))?;
+    access.require(Permission::Configure)?;
+
     let existing = TenantSetting::find()
         .filter(tenant_setting::Column::TenantId.eq(tenant_id))
         .filter(tenant_setting::Column::Key.eq(&payload.key))
@@ -160,26 +229,42 @@ pub async fn upsert_tenant_setting(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+    // Omitting `is_encrypted` keeps the current flag
+    let is_encrypted = payload
+        .is_encrypted
+        .or(existing.as_ref().map(|s| s.is_encrypted))
+        .unwrap_or(false);
+    let (value, data_key_id) = if is_encrypted {
+        let keyring = Keyring::global().map_err(secrets_error)?;
+        let (ciphertext, data_key_id) =
+            SecretsService::encrypt_value(&db, keyring, tenant_id, &payload.key, &payload.value)
+                .await
+                .map_err(secrets_error)?;
+        (ciphertext, Some(data_key_id))
+    } else {
+        (payload.value, None)
+    };
+
     if let Some(setting) = existing {
         let mut active: tenant_setting::ActiveModel = setting.into();
-        active.value = Set(payload.value);
-        if let Some(enc) = payload.is_encrypted {
-            active.is_encrypted = Set(enc);
-        }
+        active.value = Set(value);
+        active.is_encrypted = Set(is_encrypted);
+        active.data_key_id = Set(data_key_id);
         active.updated_at = Set(Utc::now());
         let updated = active.update(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        Ok((StatusCode::OK, Json(updated)))
+        Ok((StatusCode::OK, Json(SecretsService::mask(updated))))
     } else {
         let new_setting = tenant_setting::ActiveModel {
             id: Set(Uuid::new_v4()),
             tenant_id: Set(tenant_id),
             key: Set(payload.key),
-            value: Set(payload.value),
-            is_encrypted: Set(payload.is_encrypted.unwrap_or(false)),
+            value: Set(value),
+            is_encrypted: Set(is_encrypted),
+            data_key_id: Set(data_key_id),
             updated_at: Set(Utc::now()),
             created_at: Set(Utc::now()),
         };
         let inserted = new_setting.insert(&db).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        Ok((StatusCode::CREATED, Json(inserted)))
+        Ok((StatusCode::CREATED, Json(SecretsService::mask(inserted))))
     }
 }
\ No newline at end of file
@@ -0,0 +1,46 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    routing::get,
+    Router,
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::Deserialize;
+
+use crate::entities::app_domain;
+use crate::services::custom_domains::{CustomDomainService, STATUS_ACTIVE, STATUS_VERIFYING};
+
+pub fn public_routes() -> Router<DatabaseConnection> {
+    Router::new().route("/api/tls/ask", get(ask))
+}
+
+#[derive(Deserialize)]
+pub struct AskQuery {
+    pub domain: String,
+}
+
+/// Caddy's on-demand TLS `ask` hook: a certificate may only be issued for hostnames whose
+/// ownership has been verified. Anything else gets a 404 so random SNI names can't make
+/// the edge request certificates.
+pub async fn ask(
+    State(db): State<DatabaseConnection>,
+    Query(query): Query<AskQuery>,
+) -> StatusCode {
+    let Ok(domain_name) = CustomDomainService::normalize(&query.domain) else {
+        return StatusCode::NOT_FOUND;
+    };
+
+    match app_domain::Entity::find()
+        .filter(app_domain::Column::DomainName.eq(domain_name))
+        .filter(app_domain::Column::Status.is_in([STATUS_VERIFYING, STATUS_ACTIVE]))
+        .one(&db)
+        .await
+    {
+        Ok(Some(_)) => StatusCode::OK,
+        Ok(None) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            tracing::error!("Failed to look up domain for TLS ask: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
@@ -16,6 +16,8 @@ use crate::models::session::SessionResponse;
 use crate::models::user::UserRegistration;
 use crate::handlers::sessions::{refresh_token, validate_session, create_user_session};
 use crate::handlers::profiles::get_profile_by_id;
+use crate::middleware::request_logger::LoggedUser;
+use crate::services::tenant::TenantService;
 use sea_orm::{DatabaseConnection, EntityTrait, Set, ColumnTrait, QueryFilter, ActiveModelTrait};
 use uuid::Uuid;
 use chrono::{Utc};
@@ -67,7 +69,7 @@ pub async fn register_user(
     let tenant_id = user_data.tenant_id;
 
     // Verify that the network exists
-    let _network = TenantEntity::find_by_id(tenant_id)
+    let network = TenantEntity::find_by_id(tenant_id)
         .one(&db)
         .await
         .map_err(|err| {
@@ -80,6 +82,11 @@ pub async fn register_user(
             tracing::error!("{}", error_msg);
             (StatusCode::NOT_FOUND, error_msg)
         })?;
+    if network.site_status != crate::services::tenant::STATUS_ACTIVE {
+        let error_msg = format!("Network {} is not accepting registrations", tenant_id);
+        tracing::warn!("{}", error_msg);
+        return Err((StatusCode::FORBIDDEN, error_msg));
+    }
 
     // Step 1: Check if a user already exists with the same email in the network
     let existing_user = User::find()
@@ -276,13 +283,29 @@ pub async fn login_user(
         }
     }
 
+    // Members of suspended or deleted tenants can't sign in anywhere, not just on the tenant's site
+    if !user.is_admin {
+        match TenantService::user_can_sign_in(&db, user.id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::warn!("Login refused for user {}: none of their tenants is active", user.id);
+                return Err(StatusCode::FORBIDDEN);
+            }
+            Err(e) => {
+                tracing::error!("Database error when checking the tenants of user {}: {:?}", user.id, e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+
     // Replace direct handler call with shared logic
     let session_response = create_user_session(&db, &credentials.email, &credentials.password).await?;
 
     println!("TEST LOG: from login_user and session created successfully for user: {}", user.id);
     tracing::info!("Session created from user handler successfully for user: {}", user.id);
     
-    Ok(Json(session_response))
+    // Attributes the login to the user in the request log
+    Ok((Extension(LoggedUser(user.id)), Json(session_response)))
 }
 
 
@@ -12,30 +12,20 @@ mod config;
 mod services;
 pub mod atlas_apps;
 
-use axum::http::{self, HeaderValue, Method,Request, StatusCode, header};
-use axum::body::Body;
-use axum::middleware::from_fn_with_state;
+use axum::http::{self, HeaderValue, Method, StatusCode, header};
 use axum::{
     Router,
     Extension,
 };
 use tower_http::cors::CorsLayer;
-use tower::ServiceBuilder;
 use crate::sea_orm::{Database, ConnectionTrait};
 use sea_orm_migration::prelude::*;
-use migration::{Migrator};
 use std::net::SocketAddr;
-use tower_http::trace::{TraceLayer, DefaultMakeSpan, DefaultOnResponse};
-use tracing::Level;
 use tokio::net::TcpListener;
 use crate::api::create_router;
+use crate::middleware::request_logger::RequestLogger;
 
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use crate::middleware::{
-    request_logger::RequestLogger,
-    rate_limiter::RateLimiter,
-    middleware::log_request_middleware
-};
 use webauthn_rs::prelude::*;
 use crate::handlers::passkeys::{WebauthnStateRaw, WebauthnState};
 use moka::future::Cache;
@@ -82,7 +72,9 @@ fn configure_cors(network_client: &str, admin_client: &str) -> CorsLayer {
             header::ACCESS_CONTROL_ALLOW_ORIGIN,
             header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
             header::ACCESS_CONTROL_ALLOW_HEADERS,
+            http::HeaderName::from_static(crate::middleware::request_logger::REQUEST_ID_HEADER),
         ])
+        .expose_headers([http::HeaderName::from_static(crate::middleware::request_logger::REQUEST_ID_HEADER)])
         .allow_credentials(true)
 }
 #[tokio::main]
@@ -107,6 +99,10 @@ async fn main() {
     
     let _admin_email = std::env::var("ADMIN_USER").expect("ADMIN_USER must be set");
     let _admin_password = std::env::var("ADMIN_PASSWORD").expect("ADMIN_PASSWORD must be set");
+    // Without a master key the server still starts, but encrypted settings can't be written or read
+    if let Err(e) = services::secrets::Keyring::global() {
+        tracing::error!("Secrets master key is not usable, encrypted tenant settings are refused: {}", e);
+    }
     let create_admin = std::env::var("CREATE_ADMIN_ON_STARTUP")
         .unwrap_or_else(|_| "true".to_string())
         .parse::<bool>()
@@ -119,8 +115,6 @@ async fn main() {
         .await
         .expect("Failed to connect to the database");
 
-    let _request_logger = RequestLogger::new(conn.clone());
-
     if std::env::var("WIPE_DB_ON_STARTUP").unwrap_or_else(|_| "false".to_string()) == "true" {
         tracing::warn!("WIPE_DB_ON_STARTUP is enabled! Wiping the database to start from scratch...");
         use sea_orm::{ConnectionTrait, Statement};
@@ -131,7 +125,7 @@ async fn main() {
     }
 
     // Run migrations
-    Migrator::up(&conn, None).await.unwrap();
+    migration::runner::up(&conn).await.unwrap();
 
     tracing::info!("Migrations completed");
     let table_exists = &conn.execute_unprepared("SELECT 1 FROM request_log LIMIT 1").await.is_ok();
@@ -150,8 +144,8 @@ async fn main() {
 
 
 
-    let sync_db = conn.clone();
-    crate::services::data_sync::DataSyncService::start_worker(sync_db).await;
+    let scheduler_db = conn.clone();
+    crate::services::job_scheduler::JobScheduler::start_worker(scheduler_db).await;
     let telemetry_db = conn.clone();
     tokio::spawn(async move {
         // Run every hour
@@ -164,9 +158,15 @@ async fn main() {
         }
     });
 
+    let outbox_db = conn.clone();
+    crate::services::outbox::start_outbox_relay(outbox_db).await;
+
     let webhook_db = conn.clone();
     crate::services::webhook::start_webhook_sweeper(webhook_db).await;
 
+    let request_log_db = conn.clone();
+    crate::middleware::request_logger::start_request_log_pruner(request_log_db).await;
+
     let network_client = std::env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:5001".to_string());
     let admin_client = std::env::var("ADMIN_URL").unwrap_or_else(|_| "http://localhost:5002".to_string());
     tracing::info!("Network URL: {}", network_client);
@@ -174,8 +174,6 @@ async fn main() {
 
     let cors = configure_cors(&network_client, &admin_client);
 
-    let rate_limiter = RateLimiter::new();
-
     let rp_origin_str = std::env::var("WEBAUTHN_ORIGIN").unwrap_or_else(|_| admin_client.clone());
     let rp_origin = url::Url::parse(&rp_origin_str)
         .unwrap_or_else(|_| url::Url::parse("https://platform-admin.atlas-platform.orb.local").unwrap());
@@ -204,36 +202,52 @@ async fn main() {
         auth_state: Cache::builder().time_to_live(Duration::from_secs(300)).build(),
     });
 
+    let request_logger = RequestLogger::start(conn.clone());
     let app = Router::new()
-        .merge(create_router(conn.clone()))
+        .merge(create_router(conn.clone(), request_logger.clone()))
         .layer(cors)
-        .layer(
-            TraceLayer::new_for_http()
-                .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
-                .on_request(|request: &Request<_>, _span: &tracing::Span| {
-                    // Log every single request that hits the server
-                    tracing::info!(
-                        "RAW REQUEST: method={}, uri={}, headers={:?}",
-                        request.method(),
-                        request.uri(),
-                        request.headers()
-                    );
-                })
-                .on_response(DefaultOnResponse::new().level(Level::INFO))
-        )
-        .layer(
-            ServiceBuilder::new()
-                .layer(from_fn_with_state(conn.clone(), log_request_middleware::<Body>))
-                .into_inner()
-        )
         .layer(Extension(conn.clone()))
-        .layer(Extension(rate_limiter))
         .layer(Extension(webauthn_state));
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 8000));
     tracing::info!("Listening on {}", addr);
     let listener = TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app)
+    // The peer address is the starting point for resolving the client IP behind trusted proxies
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal())
         .await
         .unwrap();
+
+    // Write the request log entries still queued before exiting
+    request_logger.flush().await;
+}
+
+/// Resolves on Ctrl+C or SIGTERM, so in-flight requests finish before the server stops.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("Shutdown signal received, draining requests");
 }
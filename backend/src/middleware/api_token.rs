@@ -0,0 +1,240 @@
+// backend/src/middleware/api_token.rs
+use axum::{
+    body::Body,
+    extract::{rejection::RawPathParamsRejection, RawPathParams, State},
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use argon2::{
+    password_hash::{PasswordHash, PasswordVerifier},
+    Argon2,
+};
+use chrono::Utc;
+use moka::future::Cache;
+use once_cell::sync::Lazy;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use sea_orm::sea_query::Expr;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use uuid::Uuid;
+use crate::entities::{api_token, tenant};
+use crate::entities::user_account::UserRole;
+use crate::middleware::rate_limiter::{BucketKey, Policy, RateLimiter};
+use crate::services::tenant::STATUS_ACTIVE;
+
+/// Every developer-console token starts with this marker, which lets the auth middleware
+/// tell machine credentials apart from session bearer tokens without a DB round-trip.
+pub const API_TOKEN_PREFIX: &str = "atls_";
+
+/// Number of leading characters persisted in `api_tokens.token_prefix` to narrow the Argon2 lookup.
+const LOOKUP_PREFIX_LEN: usize = 12;
+
+// Tokens that passed Argon2 recently, by SHA-256 of the raw token, so a busy integration isn't
+// hashed (and charged against the auth rate limit) on every request. A hit only skips the hash;
+// the token row, its expiry and its tenant are still checked on each request.
+static VERIFIED_TOKENS: Lazy<Cache<[u8; 32], Uuid>> = Lazy::new(|| {
+    Cache::builder()
+        .max_capacity(10_000)
+        .time_to_live(Duration::from_secs(300))
+        .build()
+});
+
+/// Tenant-scoped machine principal resolved from an `Authorization: Bearer atls_...` header.
+#[derive(Clone, Debug)]
+pub struct ApiTokenPrincipal {
+    pub token_id: Uuid,
+    pub tenant_id: Uuid,
+    pub scopes: Vec<String>,
+}
+
+impl ApiTokenPrincipal {
+    /// Checks for `{resource}:{access}`. Grants rank `read` < `write` < `admin`, and a higher
+    /// grant satisfies the lower ones.
+    pub fn has_scope(&self, resource: &str, access: &str) -> bool {
+        let needed = access_rank(access);
+        needed > 0
+            && self.scopes.iter().filter_map(|scope| scope.split_once(':')).any(|(granted_resource, granted)| {
+                granted_resource == resource && access_rank(granted) >= needed
+            })
+    }
+}
+
+fn access_rank(access: &str) -> u8 {
+    match access {
+        "read" => 1,
+        "write" => 2,
+        "admin" => 3,
+        _ => 0,
+    }
+}
+
+/// Inserted by [`require_api_scope`] once a token's scope has been checked. Route groups that
+/// never declare a scope don't get it, so tokens are refused there by default.
+///
+/// `role` is what the token acts as within its tenant: `Admin` with a `{resource}:admin` grant,
+/// which allows deletes and configuration changes, and `Member` otherwise.
+#[derive(Clone, Debug)]
+pub struct ApiScopeGranted {
+    pub role: UserRole,
+}
+
+pub fn is_api_token(token: &str) -> bool {
+    token.starts_with(API_TOKEN_PREFIX)
+}
+
+pub fn lookup_prefix(token: &str) -> String {
+    token.chars().take(LOOKUP_PREFIX_LEN).collect()
+}
+
+/// Verifies `token` against the Argon2 hashes of `candidates`.
+fn find_matching(candidates: Vec<api_token::Model>, token: &str) -> Option<api_token::Model> {
+    let argon2 = Argon2::default();
+    candidates.into_iter().find(|candidate| {
+        PasswordHash::new(&candidate.token_hash)
+            .map(|parsed| argon2.verify_password(token.as_bytes(), &parsed).is_ok())
+            .unwrap_or(false)
+    })
+}
+
+/// Resolves a raw `atls_` token into its principal, rejecting unknown and expired tokens
+/// and stamping `last_used_at` on success. Rejections are ready-made responses.
+///
+/// Argon2 is expensive, so every verification that isn't served from [`VERIFIED_TOKENS`] first
+/// takes a request from the client's `Policy::Auth` bucket. Tokens minted before `token_prefix`
+/// was recorded can't be found by prefix and are never hashed; migration `m20260418_000020`
+/// expired them, and the rejection asks for a new token.
+pub async fn authenticate_api_token(
+    db: &DatabaseConnection,
+    limiter: &RateLimiter,
+    client: &BucketKey,
+    token: &str,
+) -> Result<ApiTokenPrincipal, Response> {
+    let db_error = |e: sea_orm::DbErr| {
+        tracing::error!("Database error while resolving API token: {:?}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error").into_response()
+    };
+    let digest: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+
+    let cached = match VERIFIED_TOKENS.get(&digest).await {
+        Some(token_id) => api_token::Entity::find_by_id(token_id).one(db).await.map_err(db_error)?,
+        None => None,
+    };
+    let record = match cached {
+        Some(record) => record,
+        None => {
+            let decision = limiter.check(Policy::Auth, client).await;
+            if !decision.allowed {
+                return Err(decision.into_response());
+            }
+            let candidates = api_token::Entity::find()
+                .filter(api_token::Column::TokenPrefix.eq(lookup_prefix(token)))
+                .all(db)
+                .await
+                .map_err(db_error)?;
+            let Some(record) = find_matching(candidates, token) else {
+                VERIFIED_TOKENS.invalidate(&digest).await;
+                tracing::debug!("No API token matched the presented credential");
+                return Err((
+                    StatusCode::UNAUTHORIZED,
+                    "Invalid API token. Tokens minted before token prefixes were recorded no longer work; mint a new token",
+                )
+                    .into_response());
+            };
+            VERIFIED_TOKENS.insert(digest, record.id).await;
+            record
+        }
+    };
+
+    if record.expires_at.is_some_and(|expires_at| expires_at < Utc::now()) {
+        tracing::warn!("API token {} is expired", record.id);
+        return Err((StatusCode::UNAUTHORIZED, "API token expired").into_response());
+    }
+
+    // Tokens stop working while their tenant is suspended or being deleted
+    let tenant_active = tenant::Entity::find_by_id(record.tenant_id)
+        .one(db)
+        .await
+        .map_err(db_error)?
+        .is_some_and(|tenant| tenant.site_status == STATUS_ACTIVE);
+    if !tenant_active {
+        tracing::warn!("API token {} belongs to an inactive tenant", record.id);
+        return Err((StatusCode::FORBIDDEN, "Tenant is not active").into_response());
+    }
+
+    if let Err(e) = api_token::Entity::update_many()
+        .col_expr(api_token::Column::LastUsedAt, Expr::value(Utc::now()))
+        .filter(api_token::Column::Id.eq(record.id))
+        .exec(db)
+        .await
+    {
+        tracing::error!("Failed to record last use of API token {}: {:?}", record.id, e);
+    }
+
+    let scopes = record
+        .scopes
+        .as_array()
+        .map(|values| values.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+
+    Ok(ApiTokenPrincipal {
+        token_id: record.id,
+        tenant_id: record.tenant_id,
+        scopes,
+    })
+}
+
+/// Route-group guard declaring the API token scope resource (e.g. "crm", "listing").
+/// Safe methods require `{resource}:read`, everything else `{resource}:write`, and a
+/// `{tenant_id}` path parameter must name the token's own tenant. Deletes and configuration
+/// changes additionally need `{resource}:admin`, enforced by the handlers' [`TenantAccess`] role.
+///
+/// [`TenantAccess`]: crate::middleware::permissions::TenantAccess
+/// Session-authenticated requests pass through untouched.
+///
+/// Usage: `.route_layer(axum::middleware::from_fn_with_state("crm", require_api_scope))`
+pub async fn require_api_scope(
+    State(resource): State<&'static str>,
+    path_params: Result<RawPathParams, RawPathParamsRejection>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if let Some(principal) = req.extensions().get::<ApiTokenPrincipal>() {
+        let access = match *req.method() {
+            Method::GET | Method::HEAD | Method::OPTIONS => "read",
+            _ => "write",
+        };
+
+        if !principal.has_scope(resource, access) {
+            tracing::warn!(
+                "API token {} lacks scope {}:{} for {}",
+                principal.token_id, resource, access, req.uri().path()
+            );
+            return Err(StatusCode::FORBIDDEN);
+        }
+
+        // Other tenants' resources are reported as missing, as they are for sessions
+        let foreign_tenant = path_params.iter().flat_map(|params| params.iter()).any(|(name, value)| {
+            name == "tenant_id" && Uuid::parse_str(value).ok() != Some(principal.tenant_id)
+        });
+        if foreign_tenant {
+            tracing::warn!("API token {} addressed another tenant at {}", principal.token_id, req.uri().path());
+            return Err(StatusCode::NOT_FOUND);
+        }
+
+        let role = if principal.has_scope(resource, "admin") { UserRole::Admin } else { UserRole::Member };
+        req.extensions_mut().insert(ApiScopeGranted { role });
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// Default for route groups without a [`require_api_scope`] layer: API tokens are refused.
+pub async fn reject_api_tokens(req: Request<Body>, next: Next) -> Result<Response, StatusCode> {
+    if let Some(principal) = req.extensions().get::<ApiTokenPrincipal>() {
+        tracing::warn!("API token {} is not accepted at {}", principal.token_id, req.uri().path());
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(next.run(req).await)
+}
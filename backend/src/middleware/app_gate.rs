@@ -0,0 +1,53 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use crate::config::{ModuleFlags, SiteConfig};
+
+/// Route-group guard for an `AtlasApp`'s routers: requests resolved to a site whose tenant
+/// hasn't installed the app get a 404, as if the routes didn't exist.
+/// Requests without a `SiteConfig` (the debug `localhost` fallback) pass through.
+///
+/// Usage: `.route_layer(axum::middleware::from_fn_with_state(app.app_id(), require_app_enabled))`
+pub async fn require_app_enabled(
+    State(app_id): State<&'static str>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if let Some(site_config) = req.extensions().get::<SiteConfig>()
+        && !site_config.is_app_enabled(app_id)
+    {
+        tracing::debug!(
+            "App {} is not installed for tenant {}, hiding {}",
+            app_id, site_config.tenant_id, req.uri().path()
+        );
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// Route-group guard for a platform module: requests resolved to a site that has the
+/// module disabled get a 404. Like [`require_app_enabled`], requests without a `SiteConfig` pass through.
+///
+/// Usage: `.route_layer(axum::middleware::from_fn_with_state(ModuleFlags::LISTINGS, require_module_enabled))`
+pub async fn require_module_enabled(
+    State(module): State<ModuleFlags>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if let Some(site_config) = req.extensions().get::<SiteConfig>()
+        && !site_config.is_module_enabled(module.clone())
+    {
+        tracing::debug!(
+            "Module {:?} is disabled for tenant {}, hiding {}",
+            module, site_config.tenant_id, req.uri().path()
+        );
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(next.run(req).await)
+}
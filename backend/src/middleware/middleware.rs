@@ -1,8 +1,6 @@
 use axum::{
     middleware::Next,
-    response::Response,
-    extract::State,
-    body::Body,
+    response::{IntoResponse, Response},
     http::{StatusCode, Request, Method},
     Extension,
 };
@@ -11,66 +9,11 @@ use sea_orm::{EntityTrait, DatabaseConnection, QueryFilter, ColumnTrait, Set};
 use uuid::Uuid;
 use chrono::Utc;
 use axum::http;
-use crate::handlers::request_logs;
-use crate::models::request_log::{RequestType, RequestStatus};
-use http::header;
 use crate::models::request_log::RequestInfo;
-use crate::middleware::rate_limiter::RateLimiter;
-
-pub async fn log_request_middleware<B>(
-    State(db): State<DatabaseConnection>,
-    request: Request<Body>,
-    next: Next,
-) -> Response {
-    tracing::debug!("Logging request");
-    let method = request.method().clone();
-    let path = request.uri().path().to_string();
-    if path == "/validate-session" {
-        tracing::debug!("Skipping request logging for /validate-session endpoint");
-        return next.run(request).await;
-    }
-    let request_id = Uuid::new_v4();
-    let uri = request.uri().clone();
-    let headers = request.headers().clone();
-    let user_id = request.extensions().get::<crate::entities::user::Model>().map(|user| {
-        tracing::debug!("Request associated with authenticated user ID: {}", user.id);
-        user.id
-    });
-    let ip_address = headers.get("x-forwarded-for").and_then(|h| h.to_str().ok()).unwrap_or("Unknown").to_string();
-    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok()).unwrap_or("Unknown").to_string();
-    let request_type = if path == "/login" { RequestType::Login } else { RequestType::API };
-    tracing::info!(
-        "Request received: ID: {}, Method: {}, Path: {}, User ID: {:?}, IP: {}, User-Agent: {}, Type: {:?}",
-        request_id, method, path, user_id, ip_address, 
-        if user_agent.len() > 30 { &user_agent[0..30] } else { &user_agent },
-        request_type
-    );
-    if path == "/login" {
-        tracing::debug!("Processing login request - Headers present: {:?}", headers.keys().map(|k| k.as_str()).collect::<Vec<_>>());
-        if let Some(origin) = headers.get("origin").and_then(|h| h.to_str().ok()) {
-            tracing::debug!("Login request origin: {}", origin);
-        }
-        if method == Method::OPTIONS {
-            tracing::debug!("Received OPTIONS preflight request for login endpoint");
-        }
-    }
-    match request_logs::log_request(
-        method, 
-        uri, 
-        StatusCode::OK, 
-        user_id, 
-        &user_agent, 
-        &ip_address, 
-        request_type, 
-        RequestStatus::Success, 
-        None, 
-        &db
-    ).await {
-        Ok(_) => tracing::debug!("Successfully logged request to database"),
-        Err(e) => tracing::error!("Failed to log request to database: {}", e),
-    }
-    next.run(request).await
-}
+use crate::middleware::rate_limiter::{BucketKey, Policy, RateLimiter};
+use crate::middleware::api_token;
+use crate::middleware::request_logger::{LoggedUser, RedactedHeaders, RequestId};
+use crate::services::tenant::TenantService;
 
 pub async fn auth_middleware(
     Extension(db): Extension<DatabaseConnection>,
@@ -78,18 +21,16 @@ pub async fn auth_middleware(
     mut req: Request<axum::body::Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    let request_id = Uuid::new_v4();
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .map(|RequestId(id)| id.clone())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
     let path = req.uri().path().to_owned();
     let method = req.method().clone();
-    let uri = req.uri().clone();
     
     tracing::info!("[{}] Request started: {} {}", request_id, method, path);
-    
-    // Log all headers for debugging
-    tracing::debug!("[{}] Request headers:", request_id);
-    for (name, value) in req.headers() {
-        tracing::debug!("[{}]   {}: {:?}", request_id, name, value);
-    }
+    tracing::debug!("[{}] Request headers: {:?}", request_id, RedactedHeaders(req.headers()));
 
     // Allow OPTIONS requests to pass through without authentication
     if req.method() == Method::OPTIONS {
@@ -99,83 +40,36 @@ pub async fn auth_middleware(
         return Ok(response);
     }
 
-    // Extract user information from request headers
-    let (user_id, user_agent, ip_address) = {
-        let headers = req.headers();
-        tracing::debug!("[{}] Request headers: {:?}", request_id, headers.keys().map(|k| k.as_str()).collect::<Vec<_>>());
-        
-        let user_agent = headers
-            .get(header::USER_AGENT)
-            .and_then(|h| h.to_str().ok())
-            .unwrap_or("Unknown")
-            .to_string();
-            
-        let ip_address = headers
-            .get("x-forwarded-for")
-            .and_then(|h| h.to_str().ok())
-            .unwrap_or("Unknown")
-            .to_string();
-            
-        let user_id = req.extensions().get::<user::Model>().map(|user| user.id);
-        
-        tracing::debug!("[{}] Client info - IP: {}, User-Agent: {}, User ID: {:?}", 
-            request_id, ip_address, 
-            if user_agent.len() > 30 { &user_agent[0..30] } else { &user_agent },
-            user_id);
-            
-        (user_id, user_agent, ip_address)
-    };
-
-    // Determine the request type (Login or API)
-    let request_type = if path == "/login" { 
-        tracing::info!("[{}] Login request detected", request_id);
-        RequestType::Login 
-    } else { 
-        RequestType::API 
-    };
-    
-    tracing::debug!("[{}] Request type: {:?}", request_id, request_type);
-
     // Handle public routes with rate limiting
     if is_public_route(&path) {
         tracing::info!("[{}] Public route detected: {}", request_id, path);
-        
-        // Apply rate limiting using extracted IP
-        tracing::debug!("[{}] Applying rate limiting", request_id);
-        match rate_limiter.check_rate_limit(&ip_address).await {
-            Ok(_) => {
-                tracing::debug!("[{}] Rate limit check passed", request_id);
-                let (parts, body) = req.into_parts();
-                let _req_info = RequestInfo::from_parts(&parts);
-                let req = Request::from_parts(parts, body);
 
-                // Log the request
-                match request_logs::log_request(
-                    method.clone(),
-                    uri.clone(),
-                    StatusCode::OK,
-                    user_id,
-                    &user_agent,
-                    &ip_address,
-                    request_type,
-                    RequestStatus::Success,
-                    None,
-                    &db
-                ).await {
-                    Ok(_) => tracing::debug!("[{}] Request logged successfully", request_id),
-                    Err(e) => tracing::error!("[{}] Failed to log request: {:?}", request_id, e),
+        // Health checks are exempt; probes behind one proxy would otherwise share a bucket
+        let decision = match public_route_policy(&path) {
+            Some(policy) => {
+                tracing::debug!("[{}] Applying the {} rate limit", request_id, policy.name());
+                let decision = rate_limiter.check(policy, &BucketKey::Ip(rate_limiter.client_ip(&req))).await;
+                if !decision.allowed {
+                    tracing::warn!("[{}] Rate limit exceeded, returning status: {}", request_id, StatusCode::TOO_MANY_REQUESTS);
+                    return Ok(decision.into_response());
                 }
-                
-                tracing::info!("[{}] Forwarding public route request to handler", request_id);
-                let response = next.run(req).await;
-                tracing::info!("[{}] Public route request completed with status: {}", request_id, response.status());
-                return Ok(response)
-            },
-            Err(status) => {
-                tracing::warn!("[{}] Rate limit exceeded, returning status: {}", request_id, status);
-                return Err(status);
-            },
+                tracing::debug!("[{}] Rate limit check passed", request_id);
+                Some(decision)
+            }
+            None => None,
+        };
+
+        let (parts, body) = req.into_parts();
+        let _req_info = RequestInfo::from_parts(&parts);
+        let req = Request::from_parts(parts, body);
+
+        tracing::info!("[{}] Forwarding public route request to handler", request_id);
+        let mut response = next.run(req).await;
+        if let Some(decision) = decision {
+            decision.apply_headers(response.headers_mut());
         }
+        tracing::info!("[{}] Public route request completed with status: {}", request_id, response.status());
+        return Ok(response)
     }
 
     tracing::debug!("[{}] Protected route - authenticating request", request_id);
@@ -184,6 +78,50 @@ pub async fn auth_middleware(
     let token = extract_token(&req);
     tracing::debug!("[{}] Bearer token extracted: {}", request_id, token.is_some());
 
+    // Developer console API tokens resolve to a tenant-scoped machine principal instead of a user session
+    if let Some(api_token) = token.as_deref().filter(|t| api_token::is_api_token(t)) {
+        let client = BucketKey::Ip(rate_limiter.client_ip(&req));
+        let principal = match api_token::authenticate_api_token(&db, &rate_limiter, &client, api_token).await {
+            Ok(principal) => {
+                tracing::info!("[{}] API token {} authenticated for tenant {}", request_id, principal.token_id, principal.tenant_id);
+                principal
+            },
+            Err(rejection) => {
+                tracing::warn!("[{}] API token authentication failed with status: {}", request_id, rejection.status());
+                return Ok(rejection);
+            }
+        };
+
+        if path.starts_with("/api/admin") {
+            tracing::warn!("[{}] API token {} attempted to access admin route: {}", request_id, principal.token_id, path);
+            return Err(StatusCode::FORBIDDEN);
+        }
+
+        // Each token has its own bucket, and all of a tenant's tokens share another
+        let mut decisions = Vec::new();
+        for (policy, key) in [
+            (Policy::ApiToken, BucketKey::ApiToken(principal.token_id)),
+            (Policy::Tenant, BucketKey::Tenant(principal.tenant_id)),
+        ] {
+            let decision = rate_limiter.check(policy, &key).await;
+            if !decision.allowed {
+                return Ok(decision.into_response());
+            }
+            decisions.push(decision);
+        }
+
+        req.extensions_mut().insert(vec![principal.tenant_id]);
+        req.extensions_mut().insert(principal);
+
+        tracing::info!("[{}] Forwarding API token request to handler", request_id);
+        let mut response = next.run(req).await;
+        for decision in decisions {
+            decision.apply_headers(response.headers_mut());
+        }
+        tracing::info!("[{}] Request completed: {} {} - Status: {}", request_id, method, path, response.status());
+        return Ok(response);
+    }
+
     // Validate session using the token
     let session = match validate_session(&db, token).await {
         Ok(session) => {
@@ -208,6 +146,21 @@ pub async fn auth_middleware(
         }
     };
 
+    // Sessions stop working once every tenant the user belongs to is suspended or being deleted
+    if !user.is_admin {
+        match TenantService::user_can_sign_in(&db, user.id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::warn!("[{}] User {} has no active tenant", request_id, user.id);
+                return Err(StatusCode::FORBIDDEN);
+            }
+            Err(e) => {
+                tracing::error!("[{}] Failed to check the tenants of user {}: {:?}", request_id, user.id, e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+
     // Check admin access for admin routes
     if req.uri().path().starts_with("/api/admin") {
         tracing::debug!("[{}] Admin route access attempt", request_id);
@@ -219,6 +172,11 @@ pub async fn auth_middleware(
         tracing::info!("[{}] Admin access granted for user: {}", request_id, user.id);
     }
 
+    let decision = rate_limiter.check(Policy::User, &BucketKey::User(user.id)).await;
+    if !decision.allowed {
+        return Ok(decision.into_response());
+    }
+
     // Update session's last accessed time
     tracing::debug!("[{}] Updating session last accessed time", request_id);
     if let Err(e) = update_session(&db, &session).await {
@@ -245,26 +203,11 @@ pub async fn auth_middleware(
     };
     req.extensions_mut().insert(network_ids);
 
-    // Log the request
-    tracing::debug!("[{}] Logging authenticated request", request_id);
-    if let Err(e) = request_logs::log_request(
-        method.clone(),
-        uri.clone(),
-        StatusCode::OK,
-        Some(user.id),
-        &user_agent,
-        &ip_address,
-        request_type,
-        RequestStatus::Success,
-        None,
-        &db
-    ).await {
-        tracing::error!("[{}] Failed to log request: {:?}", request_id, e);
-    }
-    
     // Execute the next middleware in the chain
     tracing::info!("[{}] Forwarding authenticated request to handler", request_id);
-    let response = next.run(req).await;
+    let mut response = next.run(req).await;
+    decision.apply_headers(response.headers_mut());
+    response.extensions_mut().insert(LoggedUser(user.id));
     let status_code = response.status();
 
     tracing::info!("[{}] Request completed: {} {} - Status: {}", 
@@ -294,6 +237,19 @@ fn is_public_route(path: &str) -> bool {
     is_public
 }
 
+/// Rate limit policy of a public route: `auth` for the credential endpoints, `public_read` for
+/// the rest, and none for health checks.
+fn public_route_policy(path: &str) -> Option<Policy> {
+    const CREDENTIAL_ROUTES: [&str; 5] = ["/login", "/register", "/refresh-token", "/api/login", "/api/register"];
+    if path.starts_with("/api/health") {
+        None
+    } else if CREDENTIAL_ROUTES.iter().any(|route| path.starts_with(route)) {
+        Some(Policy::Auth)
+    } else {
+        Some(Policy::PublicRead)
+    }
+}
+
 // Extract bearer token from the request headers
 fn extract_token<B>(req: &Request<B>) -> Option<String> {
     let token = req.headers()
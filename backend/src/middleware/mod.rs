@@ -2,5 +2,8 @@ pub mod middleware;
 pub mod request_logger;
 pub mod rate_limiter;
 pub mod site_context;
+pub mod api_token;
+pub mod permissions;
+pub mod app_gate;
 pub use middleware::auth_middleware;
 pub use site_context::site_context_middleware;
\ No newline at end of file
@@ -0,0 +1,217 @@
+// backend/src/middleware/permissions.rs
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Select};
+use uuid::Uuid;
+use crate::config::SiteConfig;
+use crate::entities::{account, user, user_account};
+use crate::entities::user_account::UserRole;
+use crate::middleware::api_token::{ApiScopeGranted, ApiTokenPrincipal};
+use crate::services::tenant::TenantService;
+
+/// Coarse-grained actions gated by a caller's role within a tenant. Reading needs no
+/// permission: any member sees the records [`TenantAccess::scope`] lets through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    Write,
+    Delete,
+    /// Changing the tenant's own configuration: pipelines, SLA policies, custom fields, settings.
+    Configure,
+}
+
+/// Effective tenant and role of the caller, derived from `user_account` memberships.
+///
+/// Handlers take this as an extractor and run every query through [`TenantAccess::scope`],
+/// so rows belonging to another tenant are indistinguishable from rows that do not exist.
+/// Platform admins without a tenant context resolve to `tenant_id: None` and are unscoped.
+#[derive(Clone, Debug)]
+pub struct TenantAccess {
+    pub user_id: Option<Uuid>,
+    pub tenant_id: Option<Uuid>,
+    pub role: UserRole,
+    pub is_platform_admin: bool,
+}
+
+impl TenantAccess {
+    /// Every role may write its tenant's records, since members do the day-to-day CRM and
+    /// listing work. Deletes and configuration changes are reserved for owners and admins.
+    pub fn can(&self, permission: Permission) -> bool {
+        if self.is_platform_admin {
+            return true;
+        }
+        match permission {
+            Permission::Write => true,
+            Permission::Delete | Permission::Configure => matches!(self.role, UserRole::Owner | UserRole::Admin),
+        }
+    }
+
+    pub fn require(&self, permission: Permission) -> Result<(), StatusCode> {
+        if self.can(permission) {
+            Ok(())
+        } else {
+            tracing::warn!(
+                "User {:?} with role {:?} denied {:?} in tenant {:?}",
+                self.user_id, self.role, permission, self.tenant_id
+            );
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+
+    /// Restricts a select to the caller's tenant. No-op for unscoped platform admins.
+    pub fn scope<E: EntityTrait>(&self, select: Select<E>, column: E::Column) -> Select<E> {
+        match self.tenant_id {
+            Some(tenant_id) => select.filter(column.eq(tenant_id)),
+            None => select,
+        }
+    }
+
+    /// Post-load check for rows reached through a relation rather than a scoped query.
+    pub fn ensure_visible(&self, row_tenant_id: Option<Uuid>) -> Result<(), StatusCode> {
+        match self.tenant_id {
+            Some(tenant_id) if row_tenant_id != Some(tenant_id) => Err(StatusCode::NOT_FOUND),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn role_rank(role: &UserRole) -> u8 {
+    match role {
+        UserRole::Owner => 3,
+        UserRole::Admin => 2,
+        UserRole::Member => 1,
+    }
+}
+
+/// Tenant requested by the site context (resolved from the Host) or an explicit `X-Tenant-Id` header.
+fn requested_tenant(parts: &Parts) -> Result<Option<Uuid>, StatusCode> {
+    if let Some(site_config) = parts.extensions.get::<SiteConfig>() {
+        return Ok(Some(site_config.tenant_id));
+    }
+    match parts.headers.get("X-Tenant-Id") {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|v| Uuid::parse_str(v).ok())
+            .map(Some)
+            .ok_or(StatusCode::BAD_REQUEST),
+        None => Ok(None),
+    }
+}
+
+async fn resolve(parts: &Parts) -> Result<TenantAccess, StatusCode> {
+    if let Some(principal) = parts.extensions.get::<ApiTokenPrincipal>() {
+        // Token scopes are enforced by `require_api_scope`; a handler it didn't guard refuses tokens
+        let Some(granted) = parts.extensions.get::<ApiScopeGranted>() else {
+            tracing::warn!("API token {} reached {} without a scope check", principal.token_id, parts.uri.path());
+            return Err(StatusCode::FORBIDDEN);
+        };
+        return Ok(TenantAccess {
+            user_id: None,
+            tenant_id: Some(principal.tenant_id),
+            role: granted.role.clone(),
+            is_platform_admin: false,
+        });
+    }
+
+    let user = parts
+        .extensions
+        .get::<user::Model>()
+        .cloned()
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let db = parts
+        .extensions
+        .get::<DatabaseConnection>()
+        .cloned()
+        .ok_or_else(|| {
+            tracing::error!("DatabaseConnection extension missing while resolving tenant access");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let memberships = user_account::Entity::find()
+        .filter(user_account::Column::UserId.eq(user.id))
+        .filter(user_account::Column::IsActive.eq(true))
+        .find_also_related(account::Entity)
+        .all(&db)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load memberships for user {}: {:?}", user.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // Highest role held per tenant
+    let mut roles: Vec<(Uuid, UserRole)> = Vec::new();
+    for (membership, account) in memberships {
+        let Some(account) = account.filter(|a| a.is_active) else { continue };
+        match roles.iter_mut().find(|(tenant_id, _)| *tenant_id == account.tenant_id) {
+            Some((_, role)) if role_rank(&membership.role) > role_rank(role) => *role = membership.role,
+            Some(_) => {}
+            None => roles.push((account.tenant_id, membership.role)),
+        }
+    }
+
+    // Suspended and deleted tenants keep their memberships but grant no access
+    let active = TenantService::active_tenant_ids(&db, roles.iter().map(|(tenant_id, _)| *tenant_id).collect())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load the tenants of user {}: {:?}", user.id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    roles.retain(|(tenant_id, _)| active.contains(tenant_id));
+
+    let requested = requested_tenant(parts)?;
+
+    if user.is_admin {
+        return Ok(TenantAccess {
+            user_id: Some(user.id),
+            tenant_id: requested,
+            role: UserRole::Owner,
+            is_platform_admin: true,
+        });
+    }
+
+    let (tenant_id, role) = match requested {
+        Some(requested) => roles
+            .into_iter()
+            .find(|(tenant_id, _)| *tenant_id == requested)
+            .ok_or_else(|| {
+                tracing::warn!("User {} is not a member of tenant {}", user.id, requested);
+                StatusCode::FORBIDDEN
+            })?,
+        None => match roles.len() {
+            1 => roles.remove(0),
+            0 => {
+                tracing::warn!("User {} has no tenant membership", user.id);
+                return Err(StatusCode::FORBIDDEN);
+            }
+            _ => {
+                tracing::warn!("User {} belongs to several tenants and sent no X-Tenant-Id", user.id);
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        },
+    };
+
+    Ok(TenantAccess {
+        user_id: Some(user.id),
+        tenant_id: Some(tenant_id),
+        role,
+        is_platform_admin: false,
+    })
+}
+
+impl<S> FromRequestParts<S> for TenantAccess
+where
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(access) = parts.extensions.get::<TenantAccess>() {
+            return Ok(access.clone());
+        }
+        let access = resolve(parts).await?;
+        parts.extensions.insert(access.clone());
+        Ok(access)
+    }
+}
@@ -1,141 +1,485 @@
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use dashmap::DashMap;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use axum::http::StatusCode;
-use dashmap::DashMap;
 use uuid::Uuid;
 
-const MAX_REQUESTS: u32 = 100;
-const WINDOW_SIZE: Duration = Duration::from_secs(60);
+/// Stores drop idle buckets every this many checks.
+const SWEEP_EVERY: u64 = 1024;
 
-#[derive(Clone)]
-pub struct RateLimiter {
-    store: Arc<DashMap<String, (Instant, u32)>>,
+/// Networks whose `X-Forwarded-For` is believed when `TRUSTED_PROXIES` is unset: loopback
+/// and private ranges, where the edge proxy runs in the Docker setup.
+const DEFAULT_TRUSTED_PROXIES: &str = "127.0.0.0/8,::1/128,10.0.0.0/8,172.16.0.0/12,192.168.0.0/16,fc00::/7";
+
+/// A named limit. Each bucket holds `capacity` requests and refills completely over `period`,
+/// so short bursts are allowed while the average rate stays at `capacity / period`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Policy {
+    /// Login, registration, token refresh and magic links, per client IP.
+    Auth,
+    /// Public lead submission, per client IP.
+    LeadIngest,
+    /// Public site routes, per client IP.
+    PublicRead,
+    /// Requests authenticated with a developer console API token, per token.
+    ApiToken,
+    /// All API token requests of one tenant together.
+    Tenant,
+    /// Requests authenticated with a user session, per user.
+    User,
 }
 
-impl RateLimiter {
-    pub fn new() -> Self {
-        tracing::info!("Initializing rate limiter with max {} requests per {} seconds", 
-            MAX_REQUESTS, WINDOW_SIZE.as_secs());
-        RateLimiter {
-            store: Arc::new(DashMap::new()),
+impl Policy {
+    pub const ALL: [Policy; 6] =
+        [Policy::Auth, Policy::LeadIngest, Policy::PublicRead, Policy::ApiToken, Policy::Tenant, Policy::User];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Policy::Auth => "auth",
+            Policy::LeadIngest => "lead_ingest",
+            Policy::PublicRead => "public_read",
+            Policy::ApiToken => "api_token",
+            Policy::Tenant => "tenant",
+            Policy::User => "user",
         }
     }
 
-    pub async fn check_rate_limit(&self, ip: &str) -> Result<(), StatusCode> {
-        let request_id = Uuid::new_v4();
-        tracing::info!("[{}] Rate limit check started for IP: {}", request_id, ip);
-        
+    fn default_limit(self) -> Limit {
+        let per_minute = |capacity| Limit { capacity, period: Duration::from_secs(60) };
+        match self {
+            Policy::Auth => per_minute(10),
+            Policy::LeadIngest => per_minute(3),
+            Policy::PublicRead => per_minute(300),
+            Policy::ApiToken => per_minute(600),
+            Policy::Tenant => per_minute(3000),
+            Policy::User => per_minute(1200),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limit {
+    pub capacity: u32,
+    pub period: Duration,
+}
+
+impl Limit {
+    /// Parses `<requests>/<seconds>`, e.g. `10/60`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (capacity, seconds) = value.trim().split_once('/')?;
+        let capacity: u32 = capacity.trim().parse().ok()?;
+        let seconds: u64 = seconds.trim().parse().ok()?;
+        (capacity > 0 && seconds > 0).then(|| Limit { capacity, period: Duration::from_secs(seconds) })
+    }
+
+    fn per_second(&self) -> f64 {
+        self.capacity as f64 / self.period.as_secs_f64()
+    }
+}
+
+/// What a bucket is counted against.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BucketKey {
+    Ip(IpAddr),
+    User(Uuid),
+    ApiToken(Uuid),
+    Tenant(Uuid),
+}
+
+impl std::fmt::Display for BucketKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BucketKey::Ip(ip) => write!(f, "ip:{}", ip),
+            BucketKey::User(id) => write!(f, "user:{}", id),
+            BucketKey::ApiToken(id) => write!(f, "api_token:{}", id),
+            BucketKey::Tenant(id) => write!(f, "tenant:{}", id),
+        }
+    }
+}
+
+/// Outcome of one check, rendered as `RateLimit-*` headers.
+#[derive(Debug, Clone, Copy)]
+pub struct Decision {
+    pub policy: Policy,
+    pub allowed: bool,
+    pub limit: Limit,
+    pub remaining: u32,
+    /// Until the bucket is full again.
+    pub reset: Duration,
+    /// Until the next request is allowed; zero when this one was.
+    pub retry_after: Duration,
+}
+
+impl Decision {
+    fn new(policy: Policy, limit: Limit, tokens: f64, allowed: bool) -> Self {
+        let rate = limit.per_second();
+        let tokens = tokens.clamp(0.0, limit.capacity as f64);
+        Self {
+            policy,
+            allowed,
+            limit,
+            remaining: tokens.floor() as u32,
+            reset: Duration::from_secs_f64((limit.capacity as f64 - tokens) / rate),
+            retry_after: if allowed { Duration::ZERO } else { Duration::from_secs_f64((1.0 - tokens) / rate) },
+        }
+    }
+
+    /// Sets the `RateLimit-*` headers unless a stricter policy already set them, so a response
+    /// checked against several buckets reports the one closest to running out.
+    pub fn apply_headers(&self, headers: &mut HeaderMap) {
+        let stricter_set = headers
+            .get("ratelimit-remaining")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u32>().ok())
+            .is_some_and(|remaining| remaining < self.remaining);
+        if stricter_set {
+            return;
+        }
+
+        let seconds = |d: Duration| d.as_secs_f64().ceil() as u64;
+        headers.insert("ratelimit-limit", HeaderValue::from(self.limit.capacity));
+        headers.insert("ratelimit-remaining", HeaderValue::from(self.remaining));
+        headers.insert("ratelimit-reset", HeaderValue::from(seconds(self.reset)));
+        if let Ok(policy) = HeaderValue::from_str(&format!(
+            "{};w={};name=\"{}\"",
+            self.limit.capacity,
+            self.limit.period.as_secs(),
+            self.policy.name()
+        )) {
+            headers.insert("ratelimit-policy", policy);
+        }
+        if !self.allowed {
+            headers.insert("retry-after", HeaderValue::from(seconds(self.retry_after).max(1)));
+        }
+    }
+}
+
+impl IntoResponse for Decision {
+    fn into_response(self) -> Response {
+        let mut response = (StatusCode::TOO_MANY_REQUESTS, "Too many requests").into_response();
+        self.apply_headers(response.headers_mut());
+        response
+    }
+}
+
+/// Where bucket state lives.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Refills the bucket for the time since it was last used, then takes one token if there
+    /// is one. Returns the tokens left and whether one was taken.
+    async fn take(&self, key: &str, limit: Limit) -> Result<(f64, bool), String>;
+}
+
+/// Refills `tokens` for `elapsed` and takes one if available.
+fn take_token(tokens: f64, elapsed: Duration, limit: Limit) -> (f64, bool) {
+    let tokens = (tokens + elapsed.as_secs_f64() * limit.per_second()).min(limit.capacity as f64);
+    if tokens >= 1.0 {
+        (tokens - 1.0, true)
+    } else {
+        (tokens, false)
+    }
+}
+
+/// Buckets in process memory; each replica limits on its own.
+#[derive(Default)]
+pub struct MemoryStore {
+    buckets: DashMap<String, (f64, Instant, Duration)>,
+    checks: AtomicU64,
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn take(&self, key: &str, limit: Limit) -> Result<(f64, bool), String> {
         let now = Instant::now();
-        let mut should_allow = true;
-        let mut current_count = 0;
-
-        // Check if this IP is already in our store
-        if let Some(mut entry) = self.store.get_mut(ip) {
-            let (window_start, count) = &mut *entry;
-            let elapsed = now.duration_since(*window_start);
-            
-            tracing::debug!("[{}] Existing rate limit entry found - Count: {}, Window age: {:.2}s", 
-                request_id, *count, elapsed.as_secs_f32());
-                
-            // Reset counter if window has expired
-            if elapsed > WINDOW_SIZE {
-                tracing::debug!("[{}] Rate limit window expired, resetting counter", request_id);
-                *window_start = now;
-                *count = 1;
-                current_count = 1;
-            } else {
-                // Increment counter within current window
-                *count += 1;
-                current_count = *count;
-                
-                tracing::debug!("[{}] Incremented request count to {} (max: {})", 
-                    request_id, current_count, MAX_REQUESTS);
-                    
-                // Check if rate limit exceeded
-                if current_count > MAX_REQUESTS {
-                    should_allow = false;
-                    tracing::warn!("[{}] Rate limit exceeded for IP: {} - {} requests in {:.2}s", 
-                        request_id, ip, current_count, elapsed.as_secs_f32());
-                }
-            }
-        } else {
-            // First request from this IP
-            tracing::debug!("[{}] First request from IP: {}, initializing rate limit counter", request_id, ip);
-            self.store.insert(ip.to_string(), (now, 1));
-            current_count = 1;
+        let result = {
+            let mut bucket = self
+                .buckets
+                .entry(key.to_string())
+                .or_insert((limit.capacity as f64, now, limit.period));
+            let (tokens, updated_at, period) = &mut *bucket;
+            let (left, allowed) = take_token(*tokens, now.duration_since(*updated_at), limit);
+            *tokens = left;
+            *updated_at = now;
+            *period = limit.period;
+            (left, allowed)
+        };
+
+        if self.checks.fetch_add(1, Ordering::Relaxed) % SWEEP_EVERY == SWEEP_EVERY - 1 {
+            // A bucket idle for a whole period is full, the same as no bucket at all
+            self.buckets.retain(|_, (_, updated_at, period)| now.duration_since(*updated_at) < *period);
+        }
+        Ok(result)
+    }
+}
+
+/// Buckets in the `rate_limit_buckets` table, so limits hold across replicas.
+pub struct PostgresStore {
+    db: DatabaseConnection,
+    checks: AtomicU64,
+}
+
+impl PostgresStore {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db, checks: AtomicU64::new(0) }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for PostgresStore {
+    async fn take(&self, key: &str, limit: Limit) -> Result<(f64, bool), String> {
+        // The refill is computed from the locked row, so concurrent replicas can't both take
+        // the last token
+        let refilled = "LEAST($2, b.tokens + EXTRACT(EPOCH FROM now() - b.updated_at)::float8 * $3)";
+        let sql = format!(
+            "INSERT INTO rate_limit_buckets AS b (key, tokens, allowed, updated_at, expires_at)
+             VALUES ($1, $2 - 1, true, now(), now() + make_interval(secs => $4))
+             ON CONFLICT (key) DO UPDATE SET
+                 tokens = {refilled} - CASE WHEN {refilled} >= 1 THEN 1 ELSE 0 END,
+                 allowed = {refilled} >= 1,
+                 updated_at = now(),
+                 expires_at = now() + make_interval(secs => $4)
+             RETURNING tokens, allowed",
+        );
+        let row = self
+            .db
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                sql,
+                vec![
+                    key.into(),
+                    (limit.capacity as f64).into(),
+                    limit.per_second().into(),
+                    limit.period.as_secs_f64().into(),
+                ],
+            ))
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or("rate limit upsert returned no row")?;
+
+        if self.checks.fetch_add(1, Ordering::Relaxed) % SWEEP_EVERY == SWEEP_EVERY - 1
+            && let Err(e) = self.db.execute_unprepared("DELETE FROM rate_limit_buckets WHERE expires_at < now()").await
+        {
+            tracing::warn!("Failed to sweep rate limit buckets: {}", e);
         }
 
-        // Log current rate limit status
-        if should_allow {
-            tracing::debug!("[{}] Request allowed: {} of {} in current window for IP: {}", 
-                request_id, current_count, MAX_REQUESTS, ip);
-                
-            // Log when approaching limit
-            if current_count > MAX_REQUESTS * 3 / 4 {
-                tracing::info!("[{}] IP approaching rate limit: {} ({:.0}% of max {})", 
-                    request_id, ip, (current_count as f32 / MAX_REQUESTS as f32) * 100.0, MAX_REQUESTS);
+        Ok((
+            row.try_get::<f64>("", "tokens").map_err(|e| e.to_string())?,
+            row.try_get::<bool>("", "allowed").map_err(|e| e.to_string())?,
+        ))
+    }
+}
+
+/// An IPv4 or IPv6 network, or a single address.
+#[derive(Debug, Clone, Copy)]
+struct Network {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Network {
+    fn parse(value: &str) -> Option<Self> {
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, Some(prefix.parse::<u8>().ok()?)),
+            None => (value.parse::<IpAddr>().ok()?, None),
+        };
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(max);
+        (prefix <= max).then_some(Network { addr: addr.to_canonical(), prefix })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
             }
-            
-            Ok(())
-        } else {
-            // Rate limit exceeded
-            tracing::warn!("[{}] Rate limit exceeded - returning 429 Too Many Requests for IP: {}", 
-                request_id, ip);
-                
-            // Periodically clean up the store to prevent memory leaks
-            if current_count % 1000 == 0 {
-                tracing::info!("Rate limiter store size: {} entries", self.store.len());
-                self.cleanup_expired_entries();
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
             }
-            
-            Err(StatusCode::TOO_MANY_REQUESTS)
+            _ => false,
         }
     }
-    
-    // Clean up expired entries to prevent memory leaks
-    fn cleanup_expired_entries(&self) {
-        let now = Instant::now();
-        let mut removed_count = 0;
-        
-        tracing::debug!("Starting cleanup of expired rate limit entries");
-        
-        self.store.retain(|_, (window_start, _)| {
-            let retain = now.duration_since(*window_start) <= WINDOW_SIZE * 2;
-            if !retain {
-                removed_count += 1;
+}
+
+/// Proxies allowed to report the client address in `X-Forwarded-For`.
+#[derive(Debug, Clone)]
+pub struct TrustedProxies(Vec<Network>);
+
+impl TrustedProxies {
+    /// Comma-separated addresses and CIDR networks; `none` trusts no proxy.
+    pub fn parse(list: &str) -> Result<Self, String> {
+        if list.trim().eq_ignore_ascii_case("none") {
+            return Ok(Self(vec![]));
+        }
+        list.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| Network::parse(entry).ok_or_else(|| format!("invalid trusted proxy '{}'", entry)))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// `TRUSTED_PROXIES`, defaulting to loopback and private networks.
+    pub fn from_env() -> Self {
+        let list = std::env::var("TRUSTED_PROXIES").unwrap_or_else(|_| DEFAULT_TRUSTED_PROXIES.to_string());
+        Self::parse(&list).unwrap_or_else(|e| {
+            tracing::error!("Ignoring TRUSTED_PROXIES: {}", e);
+            Self::parse(DEFAULT_TRUSTED_PROXIES).unwrap()
+        })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|network| network.contains(ip))
+    }
+
+    /// The client's address: the peer, or while the peer and each hop before it are trusted
+    /// proxies, the next `X-Forwarded-For` entry from the right. Entries a client added
+    /// itself sit left of the first untrusted hop and are never read.
+    pub fn client_ip(&self, peer: Option<IpAddr>, headers: &HeaderMap) -> IpAddr {
+        // Requests without a socket (e.g. in-process tests) come from this host
+        let mut client = peer.unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST)).to_canonical();
+        let mut hops: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect();
+        if hops.is_empty() {
+            hops.extend(headers.get("x-real-ip").and_then(|value| value.to_str().ok()).map(str::trim));
+        }
+
+        for hop in hops.iter().rev() {
+            if !self.contains(client) {
+                break;
             }
-            retain
-        });
-        
-        tracing::info!("Rate limiter cleanup complete - removed {} expired entries", removed_count);
-    }
-    
-    // Get current statistics about rate limiting
-    pub fn get_stats(&self) -> RateLimiterStats {
-        let total_entries = self.store.len();
-        let mut blocked_ips = 0;
-        let mut high_usage_ips = 0;
-        
-        for entry in self.store.iter() {
-            let (_, count) = *entry.value();
-            if count > MAX_REQUESTS {
-                blocked_ips += 1;
-            } else if count > MAX_REQUESTS * 3 / 4 {
-                high_usage_ips += 1;
+            match hop.parse::<IpAddr>() {
+                Ok(ip) => client = ip.to_canonical(),
+                // A proxy wrote something unreadable; the proxy itself is the best we know
+                Err(_) => break,
             }
         }
-        
-        RateLimiterStats {
-            total_tracked_ips: total_entries,
-            blocked_ips,
-            high_usage_ips,
+        client
+    }
+}
+
+/// Token-bucket limiter shared by every policy.
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    limits: Arc<HashMap<Policy, Limit>>,
+    proxies: Arc<TrustedProxies>,
+}
+
+impl RateLimiter {
+    /// In-memory limiter with limits from the environment: `RATE_LIMIT_<POLICY>=<requests>/<seconds>`,
+    /// e.g. `RATE_LIMIT_AUTH=5/60`.
+    pub fn new() -> Self {
+        Self::with_store(Arc::new(MemoryStore::default()))
+    }
+
+    /// Like [`RateLimiter::new`], but keeps buckets in Postgres when `RATE_LIMIT_BACKEND=postgres`.
+    pub fn from_env(db: &DatabaseConnection) -> Self {
+        match std::env::var("RATE_LIMIT_BACKEND").as_deref() {
+            Ok("postgres") => Self::with_store(Arc::new(PostgresStore::new(db.clone()))),
+            Ok("memory") | Err(_) => Self::new(),
+            Ok(other) => {
+                tracing::error!("Unknown RATE_LIMIT_BACKEND '{}', limiting in memory", other);
+                Self::new()
+            }
+        }
+    }
+
+    pub fn with_store(store: Arc<dyn RateLimitStore>) -> Self {
+        let limits = Policy::ALL
+            .into_iter()
+            .map(|policy| {
+                let var = format!("RATE_LIMIT_{}", policy.name().to_uppercase());
+                let limit = match std::env::var(&var) {
+                    Ok(value) => Limit::parse(&value).unwrap_or_else(|| {
+                        tracing::error!("Ignoring {}={}: expected <requests>/<seconds>", var, value);
+                        policy.default_limit()
+                    }),
+                    Err(_) => policy.default_limit(),
+                };
+                (policy, limit)
+            })
+            .collect();
+        Self { store, limits: Arc::new(limits), proxies: Arc::new(TrustedProxies::from_env()) }
+    }
+
+    pub fn with_limit(mut self, policy: Policy, limit: Limit) -> Self {
+        Arc::make_mut(&mut self.limits).insert(policy, limit);
+        self
+    }
+
+    pub fn with_trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.proxies = Arc::new(proxies);
+        self
+    }
+
+    pub fn limit(&self, policy: Policy) -> Limit {
+        self.limits.get(&policy).copied().unwrap_or_else(|| policy.default_limit())
+    }
+
+    pub fn client_ip<B>(&self, req: &Request<B>) -> IpAddr {
+        let peer = req.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip());
+        self.proxies.client_ip(peer, req.headers())
+    }
+
+    /// Takes one request from the policy's bucket for `key`. A failing store lets the
+    /// request through rather than taking the site down with it.
+    pub async fn check(&self, policy: Policy, key: &BucketKey) -> Decision {
+        let limit = self.limit(policy);
+        let bucket = format!("{}:{}", policy.name(), key);
+        match self.store.take(&bucket, limit).await {
+            Ok((tokens, allowed)) => {
+                if !allowed {
+                    tracing::warn!("Rate limit '{}' exceeded for {}", policy.name(), key);
+                }
+                Decision::new(policy, limit, tokens, allowed)
+            }
+            Err(e) => {
+                tracing::error!("Rate limit store failed, allowing request: {}", e);
+                Decision::new(policy, limit, limit.capacity as f64, true)
+            }
         }
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct RateLimiterStats {
-    pub total_tracked_ips: usize,
-    pub blocked_ips: usize,
-    pub high_usage_ips: usize,
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Limits requests per client IP under `policy`:
+/// `.route_layer(axum::middleware::from_fn_with_state(Policy::Auth, rate_limit))`.
+/// Expects the `RateLimiter` as an extension from an outer layer.
+pub async fn rate_limit(
+    State(policy): State<Policy>,
+    Extension(limiter): Extension<RateLimiter>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let decision = limiter.check(policy, &BucketKey::Ip(limiter.client_ip(&req))).await;
+    if !decision.allowed {
+        return decision.into_response();
+    }
+    let mut response = next.run(req).await;
+    decision.apply_headers(response.headers_mut());
+    response
 }
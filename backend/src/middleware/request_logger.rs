@@ -1,183 +1,290 @@
 use axum::{
-    http::{Method, Request, StatusCode, Uri},
+    body::{Body, HttpBody},
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, HeaderValue, Request},
+    middleware::Next,
     response::Response,
 };
-use sea_orm::DatabaseConnection;
+use chrono::Utc;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, Set, Statement};
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use crate::handlers::request_logs::log_request;
-use crate::models::request_log::{RequestType, RequestStatus};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tracing::Instrument;
 use uuid::Uuid;
 
+use crate::entities::request_log;
+use crate::middleware::rate_limiter::TrustedProxies;
+use crate::models::request_log::{RequestStatus, RequestType};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Entries waiting for the writer. When the database falls this far behind, new entries are dropped.
+const QUEUE_CAPACITY: usize = 10_000;
+const BATCH_SIZE: usize = 500;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+const PRUNE_BATCH_SIZE: i64 = 10_000;
+const DEFAULT_RETENTION_DAYS: i64 = 90;
+
+/// Paths polled often enough that logging them would drown out everything else.
+const UNLOGGED_PATHS: &[&str] = &["/validate-session", "/health"];
+
+/// Headers whose values never reach the logs.
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "x-auth-token",
+    "x-csrf-token",
+];
+
+/// The request's correlation ID, available to handlers as an extension.
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
+/// Response extension naming the user a request ran as, so the request log can attribute it.
+#[derive(Clone, Copy, Debug)]
+pub struct LoggedUser(pub Uuid);
+
+/// Formats headers for logging with credential values replaced.
+pub struct RedactedHeaders<'a>(pub &'a HeaderMap);
+
+impl fmt::Debug for RedactedHeaders<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(name, value)| {
+                let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                    "[redacted]"
+                } else {
+                    value.to_str().unwrap_or("[binary]")
+                };
+                (name.as_str(), value)
+            }))
+            .finish()
+    }
+}
+
+/// Accepts a caller's ID only if it's short and safe to echo into headers and logs.
+fn valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 128
+        && id.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
+
+enum Command {
+    Entry(Box<request_log::ActiveModel>),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Queues request log entries for a background task that writes them in batches, so
+/// requests never wait on the insert.
 #[derive(Clone)]
 pub struct RequestLogger {
-    db: Arc<DatabaseConnection>,
+    tx: mpsc::Sender<Command>,
+    proxies: Arc<TrustedProxies>,
+    dropped: Arc<AtomicU64>,
 }
 
 impl RequestLogger {
-    pub fn new(db: DatabaseConnection) -> Self {
-        RequestLogger {
-            db: Arc::new(db),
+    /// Spawns the writer task; must be called inside the Tokio runtime.
+    pub fn start(db: DatabaseConnection) -> Self {
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(run_writer(db, rx));
+        Self {
+            tx,
+            proxies: Arc::new(TrustedProxies::from_env()),
+            dropped: Arc::new(AtomicU64::new(0)),
         }
     }
 
-    pub async fn log_request<B>(
-        &self,
-        req: &Request<B>,
-    ) -> Result<(), StatusCode> {
-        let method = req.method().clone();
-        let path = req.uri().path().to_string();
-        tracing::debug!("Request logging started - Method: {:?}, Path: {:?}", method, path);
-    
-        // Skip logging for validate-session to reduce noise
-        if path == "/validate-session" {
-            tracing::debug!("Skipping request logging for /validate-session endpoint");
-            return Ok(());
-        }
-    
-        // Generate a unique ID for this request for correlation
-        let request_id = Uuid::new_v4();
-        let uri = req.uri().clone();
-        let headers = req.headers().clone();
-        
-        // Extract user information if available
-        let user_id = req.extensions().get::<crate::entities::user::Model>().map(|user| {
-            tracing::debug!("Request associated with authenticated user ID: {}", user.id);
-            user.id
-        });
-    
-        // Extract client information
-        let ip_address = headers
-            .get("x-forwarded-for")
-            .and_then(|h| h.to_str().ok())
-            .unwrap_or("Unknown")
-            .to_string();
-            
-        let user_agent = headers
-            .get("user-agent")
-            .and_then(|h| h.to_str().ok())
-            .unwrap_or("Unknown")
-            .to_string();
-    
-        // Determine request type
-        let request_type = if path == "/login" {
-            tracing::debug!("Login request detected");
-            RequestType::Login
-        } else {
-            RequestType::API
-        };
-    
-        // Log detailed request information
-        tracing::info!(
-            "Request received: ID: {}, Method: {}, Path: {}, User ID: {:?}, IP: {}, User-Agent: {}, Type: {:?}",
-            request_id, method, path, user_id, ip_address, 
-            if user_agent.len() > 30 { &user_agent[0..30] } else { &user_agent }, // Truncate long user agents
-            request_type
-        );
-    
-        // For login requests, add extra debugging
-        if path == "/login" {
-            tracing::debug!(
-                "Processing login request - Headers present: {:?}",
-                headers.keys().map(|k| k.as_str()).collect::<Vec<_>>()
-            );
-            
-            // Check for CORS-related headers
-            if let Some(origin) = headers.get("origin").and_then(|h| h.to_str().ok()) {
-                tracing::debug!("Login request origin: {}", origin);
-            }
-            
-            if method == Method::OPTIONS {
-                tracing::debug!("Received OPTIONS preflight request for login endpoint");
+    pub fn record(&self, entry: request_log::ActiveModel) {
+        if self.tx.try_send(Command::Entry(Box::new(entry))).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            if dropped == 1 || dropped.is_multiple_of(1000) {
+                tracing::warn!("Request log queue is full; {} entries dropped so far", dropped);
             }
         }
-    
-        // Log the request to the database
-        match log_request(
-            method, 
-            uri, 
-            StatusCode::OK, 
-            user_id, 
-            &user_agent, 
-            &ip_address, 
-            request_type, 
-            RequestStatus::Success, 
-            None, 
-            &self.db
-        ).await {
-            Ok(_) => tracing::debug!("Successfully logged request to database"),
-            Err(e) => {
-                tracing::error!("Failed to log request to database: {}", e);
-                eprintln!("Failed to log request: {}", e);
-            }
+    }
+
+    /// Waits until everything queued so far has been written.
+    pub async fn flush(&self) {
+        let (ack, done) = oneshot::channel();
+        if self.tx.send(Command::Flush(ack)).await.is_ok() {
+            let _ = done.await;
         }
-    
-        Ok(())
     }
+}
 
-    pub async fn log_response(
-        &self,
-        response: &Response,
-        method: Method,
-        uri: Uri,
-        user_id: Option<Uuid>,
-        user_agent: &str,
-        ip_address: &str,
-        request_type: RequestType,
-    ) -> Result<(), StatusCode> {
-        let status = response.status();
-        let path = uri.path();
-        
-        tracing::debug!(
-            "Logging response - Path: {}, Method: {}, Status: {}", 
-            path, method, status
-        );
-        
-        let request_status = if status.is_success() {
-            RequestStatus::Success
-        } else {
-            tracing::warn!(
-                "Request failed - Path: {}, Method: {}, Status: {}", 
-                path, method, status
-            );
-            RequestStatus::Failure
-        };
-        
-        let failure_reason = if status.is_client_error() || status.is_server_error() {
-            let reason = status.canonical_reason().unwrap_or("Unknown error").to_string();
-            tracing::warn!("Request error: {}", reason);
-            Some(reason)
-        } else {
-            None
-        };
-
-        // For login endpoint, add extra logging
-        if path == "/login" {
-            tracing::info!(
-                "Login request completed - Status: {}, Success: {}", 
-                status, status.is_success()
-            );
-            
-            if let Some(reason) = &failure_reason {
-                tracing::warn!("Login failed: {}", reason);
-            }
+async fn run_writer(db: DatabaseConnection, mut rx: mpsc::Receiver<Command>) {
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+    loop {
+        tokio::select! {
+            command = rx.recv() => match command {
+                Some(Command::Entry(entry)) => {
+                    batch.push(*entry);
+                    if batch.len() >= BATCH_SIZE {
+                        write_batch(&db, &mut batch).await;
+                    }
+                }
+                Some(Command::Flush(ack)) => {
+                    write_batch(&db, &mut batch).await;
+                    let _ = ack.send(());
+                }
+                None => {
+                    write_batch(&db, &mut batch).await;
+                    break;
+                }
+            },
+            _ = interval.tick() => write_batch(&db, &mut batch).await,
         }
+    }
+}
+
+async fn write_batch(db: &DatabaseConnection, batch: &mut Vec<request_log::ActiveModel>) {
+    if batch.is_empty() {
+        return;
+    }
+    let entries = std::mem::take(batch);
+    let count = entries.len();
+    if let Err(e) = request_log::Entity::insert_many(entries).exec(db).await {
+        tracing::error!("Failed to write {} request log entries: {}", count, e);
+    }
+}
+
+/// Assigns each request a correlation ID (the caller's `X-Request-Id` if valid), runs it inside a
+/// span carrying that ID, and once the response is ready queues a log entry with its real status,
+/// latency and size.
+pub async fn log_requests(
+    State(logger): State<RequestLogger>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Response {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let peer = req.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip());
+    let ip_address = logger.proxies.client_ip(peer, req.headers());
+    let user_agent = req
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let span = tracing::info_span!("request", request_id = %request_id, method = %method, path = %path);
+    let started = Instant::now();
+    let mut response = next.run(req).instrument(span).await;
+    let elapsed = started.elapsed();
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    if UNLOGGED_PATHS.contains(&path.as_str()) {
+        return response;
+    }
+
+    let status = response.status();
+    let response_bytes = response.body().size_hint().exact().or_else(|| {
+        response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+    });
+    let failed = status.is_client_error() || status.is_server_error();
+    logger.record(request_log::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(response.extensions().get::<LoggedUser>().map(|LoggedUser(id)| *id)),
+        ip_address: Set(ip_address.to_string()),
+        user_agent: Set(user_agent),
+        request_type: Set(if path == "/login" { RequestType::Login } else { RequestType::API }),
+        path: Set(path),
+        method: Set(method.to_string()),
+        status_code: Set(status.as_u16() as i32),
+        request_status: Set(if failed { RequestStatus::Failure } else { RequestStatus::Success }),
+        failure_reason: Set(failed.then(|| status.canonical_reason().unwrap_or("Unknown error").to_string())),
+        created_at: Set(Utc::now()),
+        request_id: Set(Some(request_id)),
+        duration_ms: Set(Some(elapsed.as_millis().min(i32::MAX as u128) as i32)),
+        response_bytes: Set(response_bytes.map(|bytes| bytes.min(i64::MAX as u64) as i64)),
+    });
 
-        if let Err(e) = log_request(
-            method,
-            uri,
-            status,
-            user_id,
-            user_agent,
-            ip_address,
-            request_type,
-            request_status,
-            failure_reason,
-            &self.db
-        ).await {
-            tracing::error!("Failed to log response: {:?}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    response
+}
+
+/// Deletes request log entries older than `retention` in batches, so a large backlog doesn't
+/// hold one long lock. Returns the number of rows removed.
+pub async fn prune_request_log(db: &DatabaseConnection, retention: chrono::Duration) -> Result<u64, DbErr> {
+    let cutoff = Utc::now() - retention;
+    let mut removed = 0;
+    loop {
+        let result = db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"DELETE FROM request_log WHERE id IN (
+                    SELECT id FROM request_log WHERE created_at < $1 LIMIT $2
+                )"#,
+                vec![cutoff.into(), PRUNE_BATCH_SIZE.into()],
+            ))
+            .await?;
+        removed += result.rows_affected();
+        if result.rows_affected() < PRUNE_BATCH_SIZE as u64 {
+            return Ok(removed);
         }
+    }
+}
+
+/// A retention window of `days`, or `None` when it is too long to count back from today.
+pub fn retention_window(days: i64) -> Option<chrono::Duration> {
+    chrono::Duration::try_days(days).filter(|window| Utc::now().checked_sub_signed(*window).is_some())
+}
 
-        Ok(())
+/// Hourly retention sweep for `request_log`. `REQUEST_LOG_RETENTION_DAYS` sets the window
+/// (default 90); `0` keeps entries forever.
+pub async fn start_request_log_pruner(db: DatabaseConnection) {
+    let mut days = std::env::var("REQUEST_LOG_RETENTION_DAYS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+    if days <= 0 {
+        tracing::info!("Request log retention disabled");
+        return;
     }
+    let retention = match retention_window(days) {
+        Some(retention) => retention,
+        None => {
+            tracing::warn!(
+                "REQUEST_LOG_RETENTION_DAYS={} is out of range, keeping request logs for the default {} days",
+                days,
+                DEFAULT_RETENTION_DAYS
+            );
+            days = DEFAULT_RETENTION_DAYS;
+            chrono::Duration::days(DEFAULT_RETENTION_DAYS)
+        }
+    };
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            match prune_request_log(&db, retention).await {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!("Pruned {} request log entries older than {} days", removed, days),
+                Err(e) => tracing::error!("Request log pruning failed: {}", e),
+            }
+        }
+    });
 }
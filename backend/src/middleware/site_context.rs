@@ -1,22 +1,39 @@
 // backend/src/middleware/site_context.rs
 use axum::{
     extract::{Extension},
-    http::{Request, StatusCode},
+    http::{header, Request, StatusCode},
     middleware::Next,
     response::Response,
 };
 use axum_extra::extract::{Host};
 use sea_orm::{DatabaseConnection, EntityTrait, ColumnTrait, QueryFilter};
-use std::sync::Arc;
+use moka::future::Cache;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use uuid::Uuid;
 use crate::entities::{tenant, app_domain, app_instance};
 use crate::config::{SiteConfig, ModuleFlags};
+use crate::services::tenant::{STATUS_PURGED, STATUS_SCHEDULED_FOR_DELETION, STATUS_SUSPENDED};
 
-// Cache for site configurations to avoid frequent DB lookups
-static SITE_CACHE: Lazy<Arc<RwLock<HashMap<String, SiteConfig>>>> = 
-    Lazy::new(|| Arc::new(RwLock::new(HashMap::new())));
+// Cache for site configurations to avoid frequent DB lookups.
+// Mutations made through the API invalidate entries directly; the TTL bounds how long
+// changes made elsewhere (another replica, a migration) keep being served.
+static SITE_CACHE: Lazy<Cache<String, SiteConfig>> = Lazy::new(|| {
+    Cache::builder()
+        .max_capacity(10_000)
+        .time_to_live(Duration::from_secs(300))
+        .support_invalidation_closures()
+        .build()
+});
+
+// Hosts that resolved to no site, so random Host headers don't hit the DB on every request
+static UNKNOWN_HOST_CACHE: Lazy<Cache<String, ()>> = Lazy::new(|| {
+    Cache::builder()
+        .max_capacity(10_000)
+        .time_to_live(Duration::from_secs(30))
+        .build()
+});
 
 // We'll hardcode the admin routing check as well
 fn is_admin_route(path: &str) -> bool {
@@ -31,7 +48,7 @@ fn is_auth_route(path: &str) -> bool {
 }
 
 fn is_setup_route(path: &str) -> bool {
-    path.starts_with("/setup") || path.starts_with("/api/health") || path == "/health"
+    path.starts_with("/setup") || path.starts_with("/api/health") || path == "/health" || path.starts_with("/api/tls")
 }
 
 pub async fn site_context_middleware(
@@ -42,74 +59,31 @@ pub async fn site_context_middleware(
 ) -> Result<Response, StatusCode> {
     let domain = hostname.split(':').next().unwrap_or(&hostname).to_string();
     
-    // Skip site context for admin routes, authentication routes, setup routes, and system endpoints
-    if is_admin_route(req.uri().path()) || is_auth_route(req.uri().path()) || is_setup_route(req.uri().path()) || req.uri().path().starts_with("/api/app-instances") {
+    // Skip site context for admin routes, setup routes, and system endpoints
+    if is_admin_route(req.uri().path()) || is_setup_route(req.uri().path()) || req.uri().path().starts_with("/api/app-instances") {
         return Ok(next.run(req).await);
     }
-    
-    // Try to get from cache first
-    let site_config = {
-        let cache = SITE_CACHE.read().await;
-        cache.get(&domain).cloned()
-    };
-    
-    let site_config = match site_config {
-        Some(config) => config,
-        None => {
-            // Find AppDomain
-            let app_domain = app_domain::Entity::find()
-                .filter(app_domain::Column::DomainName.eq(&domain))
-                .one(&db)
-                .await
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-            
-            let app_domain = match app_domain {
-                Some(d) => d,
-                None => {
-                    // Fallback to "localhost" if debugging
-                    if domain == "localhost" && cfg!(debug_assertions) {
-                        return Ok(next.run(req).await);
-                    }
-                    return Err(StatusCode::NOT_FOUND);
-                }
-            };
-
-            // Find AppInstance
-            let app_instance = app_instance::Entity::find_by_id(app_domain.app_instance_id)
-                .one(&db)
-                .await
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-                .ok_or(StatusCode::NOT_FOUND)?;
-
-            // Find Tenant
-            let tenant = tenant::Entity::find_by_id(app_instance.tenant_id)
-                .one(&db)
-                .await
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-                .ok_or(StatusCode::NOT_FOUND)?;
-            
-            let config = SiteConfig {
-                tenant_id: tenant.id,
-                name: tenant.name.clone(),
-                domain: app_domain.domain_name,
-                subdomain: None,
-                custom_domain: None,
-                enabled_modules: ModuleFlags::all(),
-                theme: None,
-                site_status: Some(tenant.site_status),
-                custom_settings: HashMap::new(),
-            };
-            
-            // Update cache
-            {
-                let mut cache = SITE_CACHE.write().await;
-                cache.insert(domain.clone(), config.clone());
-            }
-            
-            config
+    let is_auth = is_auth_route(req.uri().path());
+
+    let site_config = cached_site_config(&db, &domain).await?;
+
+    let Some(site_config) = site_config else {
+        // Authentication also runs on platform hosts that aren't tenant sites
+        // Fallback to "localhost" if debugging
+        if is_auth || (domain == "localhost" && cfg!(debug_assertions)) {
+            return Ok(next.run(req).await);
         }
+        return Err(StatusCode::NOT_FOUND);
     };
-    
+
+    // Sites of suspended and deleted tenants are down, including their sign-in
+    if let Some(response) = unavailable_response(&site_config) {
+        return Ok(response);
+    }
+    if is_auth {
+        return Ok(next.run(req).await);
+    }
+
     // Add site config to request extensions
     req.extensions_mut().insert(site_config.clone());
     
@@ -121,7 +95,119 @@ pub async fn site_context_middleware(
     Ok(next.run(req).await)
 }
 
+async fn cached_site_config(db: &DatabaseConnection, domain: &str) -> Result<Option<SiteConfig>, StatusCode> {
+    if let Some(config) = SITE_CACHE.get(domain).await {
+        return Ok(Some(config));
+    }
+    if UNKNOWN_HOST_CACHE.contains_key(domain) {
+        return Ok(None);
+    }
+    let config = load_site_config(db, domain).await?;
+    match &config {
+        Some(config) => SITE_CACHE.insert(domain.to_string(), config.clone()).await,
+        None => UNKNOWN_HOST_CACHE.insert(domain.to_string(), ()).await,
+    }
+    Ok(config)
+}
+
+/// The response a site gives instead of its content while its tenant isn't active: a
+/// maintenance page while suspended or awaiting deletion, a 404 once purged.
+fn unavailable_response(site_config: &SiteConfig) -> Option<Response> {
+    match site_config.site_status.as_deref() {
+        Some(STATUS_SUSPENDED) | Some(STATUS_SCHEDULED_FOR_DELETION) => {
+            let name = site_config
+                .name
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;");
+            let page = format!(
+                "<!DOCTYPE html><html><head><title>{0} is unavailable</title></head>\
+                 <body><h1>{0} is temporarily unavailable</h1><p>Please check back later.</p></body></html>",
+                name
+            );
+            Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+                .header(header::RETRY_AFTER, "3600")
+                .body(axum::body::Body::from(page))
+                .ok()
+        }
+        Some(STATUS_PURGED) => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(axum::body::Body::empty())
+            .ok(),
+        _ => None,
+    }
+}
+
+async fn load_site_config(db: &DatabaseConnection, domain: &str) -> Result<Option<SiteConfig>, StatusCode> {
+    // Find AppDomain; domains still being verified aren't served
+    let Some(app_domain) = app_domain::Entity::find()
+        .filter(app_domain::Column::DomainName.eq(domain))
+        .filter(app_domain::Column::Status.eq(crate::services::custom_domains::STATUS_ACTIVE))
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    else {
+        return Ok(None);
+    };
+
+    // Find AppInstance
+    let Some(app_instance) = app_instance::Entity::find_by_id(app_domain.app_instance_id)
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    else {
+        return Ok(None);
+    };
+
+    // Find Tenant
+    let Some(tenant) = tenant::Entity::find_by_id(app_instance.tenant_id)
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    else {
+        return Ok(None);
+    };
+
+    // Every app the tenant installed is reachable from any of its domains
+    let installed = app_instance::Entity::find()
+        .filter(app_instance::Column::TenantId.eq(tenant.id))
+        .all(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let enabled_apps = crate::atlas_apps::enabled_app_ids(installed.iter().map(|i| i.app_type.as_str()));
+
+    Ok(Some(SiteConfig {
+        tenant_id: tenant.id,
+        name: tenant.name.clone(),
+        domain: app_domain.domain_name,
+        subdomain: None,
+        custom_domain: None,
+        enabled_modules: ModuleFlags::from_db(tenant.enabled_modules),
+        theme: None,
+        site_status: Some(tenant.site_status),
+        custom_settings: HashMap::new(),
+        enabled_apps,
+    }))
+}
+
+/// Forgets what `domain` resolved to, including a cached miss. Call after adding or removing the domain.
+pub async fn invalidate_domain(domain: &str) {
+    SITE_CACHE.invalidate(domain).await;
+    UNKNOWN_HOST_CACHE.invalidate(domain).await;
+}
+
+/// Forgets every cached site of a tenant. Call after changing the tenant or its app instances.
+pub fn invalidate_tenant(tenant_id: Uuid) {
+    if let Err(e) = SITE_CACHE.invalidate_entries_if(move |_, config| config.tenant_id == tenant_id) {
+        tracing::warn!("Failed to invalidate cached sites of tenant {}: {:?}; clearing the cache", tenant_id, e);
+        SITE_CACHE.invalidate_all();
+    }
+}
+
 pub async fn clear_site_cache() {
-    let mut cache = SITE_CACHE.write().await;
-    cache.clear();
+    SITE_CACHE.invalidate_all();
+    UNKNOWN_HOST_CACHE.invalidate_all();
 }
@@ -0,0 +1,39 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Argon2 hashes are salted, so tokens are resolved via a non-secret prefix before verification
+                ALTER TABLE api_tokens ADD COLUMN IF NOT EXISTS token_prefix VARCHAR(16);
+                ALTER TABLE api_tokens ADD COLUMN IF NOT EXISTS last_used_at TIMESTAMP WITH TIME ZONE;
+
+                CREATE INDEX IF NOT EXISTS idx_api_tokens_token_prefix ON api_tokens (token_prefix);
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP INDEX IF EXISTS idx_api_tokens_token_prefix;
+                ALTER TABLE api_tokens DROP COLUMN IF EXISTS last_used_at;
+                ALTER TABLE api_tokens DROP COLUMN IF EXISTS token_prefix;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
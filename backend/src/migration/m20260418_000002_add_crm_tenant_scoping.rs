@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Child CRM rows carry their own tenant so handlers can scope queries without joins
+                ALTER TABLE "case" ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenant(id) ON DELETE CASCADE;
+                ALTER TABLE activity ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenant(id) ON DELETE CASCADE;
+                -- `notes`/`files` are the entity tables; older schemas may only have the legacy `note`/`file` tables
+                ALTER TABLE IF EXISTS notes ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenant(id) ON DELETE CASCADE;
+                ALTER TABLE IF EXISTS files ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenant(id) ON DELETE CASCADE;
+
+                -- Backfill from parents. Rows whose parents were never tenant-stamped stay NULL
+                -- and are only visible to platform admins.
+                UPDATE contact c SET tenant_id = cu.tenant_id
+                    FROM customer cu WHERE c.customer_id = cu.id AND c.tenant_id IS NULL;
+                UPDATE deal d SET tenant_id = cu.tenant_id
+                    FROM customer cu WHERE d.customer_id = cu.id AND d.tenant_id IS NULL;
+                UPDATE "case" c SET tenant_id = cu.tenant_id
+                    FROM customer cu WHERE c.customer_id = cu.id AND c.tenant_id IS NULL;
+
+                UPDATE activity a SET tenant_id = COALESCE(
+                        (SELECT tenant_id FROM deal WHERE id = a.deal_id),
+                        (SELECT tenant_id FROM lead WHERE id = a.lead_id),
+                        (SELECT tenant_id FROM customer WHERE id = a.customer_id),
+                        (SELECT tenant_id FROM contact WHERE id = a.contact_id),
+                        (SELECT tenant_id FROM "case" WHERE id = a.case_id)
+                    )
+                    WHERE a.tenant_id IS NULL;
+
+                DO $$
+                BEGIN
+                    IF to_regclass('notes') IS NOT NULL THEN
+                        UPDATE notes n SET tenant_id = CASE n.entity_type
+                                WHEN 'Deal' THEN (SELECT tenant_id FROM deal WHERE id = n.entity_id)
+                                WHEN 'Lead' THEN (SELECT tenant_id FROM lead WHERE id = n.entity_id)
+                                WHEN 'Customer' THEN (SELECT tenant_id FROM customer WHERE id = n.entity_id)
+                                WHEN 'Contact' THEN (SELECT tenant_id FROM contact WHERE id = n.entity_id)
+                                WHEN 'Case' THEN (SELECT tenant_id FROM "case" WHERE id = n.entity_id)
+                                WHEN 'Activity' THEN (SELECT tenant_id FROM activity WHERE id = n.entity_id)
+                            END
+                            WHERE n.tenant_id IS NULL;
+                        CREATE INDEX IF NOT EXISTS idx_notes_tenant_id ON notes (tenant_id);
+                    END IF;
+                    IF to_regclass('files') IS NOT NULL THEN
+                        CREATE INDEX IF NOT EXISTS idx_files_tenant_id ON files (tenant_id);
+                    END IF;
+                END $$;
+
+                CREATE INDEX IF NOT EXISTS idx_case_tenant_id ON "case" (tenant_id);
+                CREATE INDEX IF NOT EXISTS idx_activity_tenant_id ON activity (tenant_id);
+                CREATE INDEX IF NOT EXISTS idx_customer_tenant_id ON customer (tenant_id);
+                CREATE INDEX IF NOT EXISTS idx_contact_tenant_id ON contact (tenant_id);
+                CREATE INDEX IF NOT EXISTS idx_deal_tenant_id ON deal (tenant_id);
+                CREATE INDEX IF NOT EXISTS idx_lead_tenant_id ON lead (tenant_id);
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP INDEX IF EXISTS idx_lead_tenant_id;
+                DROP INDEX IF EXISTS idx_deal_tenant_id;
+                DROP INDEX IF EXISTS idx_contact_tenant_id;
+                DROP INDEX IF EXISTS idx_customer_tenant_id;
+                ALTER TABLE IF EXISTS files DROP COLUMN IF EXISTS tenant_id;
+                ALTER TABLE IF EXISTS notes DROP COLUMN IF EXISTS tenant_id;
+                ALTER TABLE activity DROP COLUMN IF EXISTS tenant_id;
+                ALTER TABLE "case" DROP COLUMN IF EXISTS tenant_id;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
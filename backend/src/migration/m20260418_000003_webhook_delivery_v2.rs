@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Endpoints are switched off once they fail `failure_threshold` deliveries in a row
+                ALTER TABLE webhook_endpoints ADD COLUMN IF NOT EXISTS consecutive_failures INT NOT NULL DEFAULT 0;
+                ALTER TABLE webhook_endpoints ADD COLUMN IF NOT EXISTS failure_threshold INT NOT NULL DEFAULT 10;
+                ALTER TABLE webhook_endpoints ADD COLUMN IF NOT EXISTS disabled_at TIMESTAMP WITH TIME ZONE;
+
+                -- Deliveries that exhausted their retries were left as 'failed' with no next_retry_at
+                UPDATE webhook_deliveries
+                SET status = 'dead_letter'
+                WHERE status = 'failed' AND next_retry_at IS NULL;
+
+                CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_tenant_created ON webhook_deliveries (tenant_id, created_at);
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP INDEX IF EXISTS idx_webhook_deliveries_tenant_created;
+                UPDATE webhook_deliveries SET status = 'failed' WHERE status = 'dead_letter';
+                ALTER TABLE webhook_endpoints DROP COLUMN IF EXISTS disabled_at;
+                ALTER TABLE webhook_endpoints DROP COLUMN IF EXISTS failure_threshold;
+                ALTER TABLE webhook_endpoints DROP COLUMN IF EXISTS consecutive_failures;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Side effects (audit, telemetry, webhooks) are written here in the same transaction
+                -- as the mutation that caused them and fanned out by the relay worker
+                CREATE TABLE IF NOT EXISTS outbox (
+                    id UUID PRIMARY KEY,
+                    kind VARCHAR NOT NULL,
+                    tenant_id UUID,
+                    payload JSONB NOT NULL,
+                    status VARCHAR NOT NULL DEFAULT 'pending',
+                    attempts INT NOT NULL DEFAULT 0,
+                    last_error TEXT,
+                    available_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    processed_at TIMESTAMP WITH TIME ZONE,
+                    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_outbox_pending ON outbox (available_at) WHERE status = 'pending';
+
+                -- One delivery per (outbox message, endpoint) so a relayed message can be fanned out again safely
+                ALTER TABLE webhook_deliveries ADD COLUMN IF NOT EXISTS idempotency_key VARCHAR;
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_webhook_deliveries_idempotency_key ON webhook_deliveries (idempotency_key);
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP INDEX IF EXISTS idx_webhook_deliveries_idempotency_key;
+                ALTER TABLE webhook_deliveries DROP COLUMN IF EXISTS idempotency_key;
+                DROP TABLE IF EXISTS outbox;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Either interval_seconds or a cron expression drives the schedule; next_run_at is what replicas lease on
+                ALTER TABLE tenant_background_jobs ADD COLUMN IF NOT EXISTS cron_expression VARCHAR;
+                ALTER TABLE tenant_background_jobs ADD COLUMN IF NOT EXISTS next_run_at TIMESTAMP WITH TIME ZONE;
+                ALTER TABLE tenant_background_jobs ADD COLUMN IF NOT EXISTS max_concurrency INT NOT NULL DEFAULT 1;
+                ALTER TABLE tenant_background_jobs ADD COLUMN IF NOT EXISTS timeout_seconds INT NOT NULL DEFAULT 300;
+
+                CREATE INDEX IF NOT EXISTS idx_tenant_background_jobs_due ON tenant_background_jobs (next_run_at) WHERE is_active;
+
+                -- A tenant has at most one job per type, so concurrent installs can't provision it twice
+                DELETE FROM tenant_background_jobs a
+                    USING tenant_background_jobs b
+                    WHERE a.tenant_id = b.tenant_id AND a.job_type = b.job_type AND a.id > b.id;
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_tenant_background_jobs_tenant_job_type ON tenant_background_jobs (tenant_id, job_type);
+
+                CREATE TABLE IF NOT EXISTS job_runs (
+                    id UUID PRIMARY KEY,
+                    job_id UUID NOT NULL REFERENCES tenant_background_jobs(id) ON DELETE CASCADE,
+                    tenant_id UUID NOT NULL,
+                    job_type VARCHAR NOT NULL,
+                    status VARCHAR NOT NULL,
+                    worker_id VARCHAR NOT NULL,
+                    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    lease_expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+                    finished_at TIMESTAMP WITH TIME ZONE,
+                    duration_ms BIGINT,
+                    error TEXT
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_job_runs_job_started ON job_runs (job_id, started_at DESC);
+                CREATE INDEX IF NOT EXISTS idx_job_runs_running ON job_runs (job_id) WHERE status = 'running';
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP TABLE IF EXISTS job_runs;
+                DROP INDEX IF EXISTS idx_tenant_background_jobs_tenant_job_type;
+                DROP INDEX IF EXISTS idx_tenant_background_jobs_due;
+                ALTER TABLE tenant_background_jobs DROP COLUMN IF EXISTS timeout_seconds;
+                ALTER TABLE tenant_background_jobs DROP COLUMN IF EXISTS max_concurrency;
+                ALTER TABLE tenant_background_jobs DROP COLUMN IF EXISTS next_run_at;
+                ALTER TABLE tenant_background_jobs DROP COLUMN IF EXISTS cron_expression;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Per-tenant AES-256-GCM data keys, stored wrapped by the master key named in master_key_id
+                CREATE TABLE IF NOT EXISTS tenant_data_keys (
+                    id UUID PRIMARY KEY,
+                    tenant_id UUID NOT NULL REFERENCES tenant(id) ON DELETE CASCADE,
+                    wrapped_key TEXT NOT NULL,
+                    master_key_id VARCHAR NOT NULL,
+                    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+                    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    retired_at TIMESTAMP WITH TIME ZONE
+                );
+
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_tenant_data_keys_active ON tenant_data_keys (tenant_id) WHERE is_active;
+
+                -- NULL for plaintext rows, including legacy rows flagged is_encrypted before encryption existed
+                ALTER TABLE tenant_setting ADD COLUMN IF NOT EXISTS data_key_id UUID REFERENCES tenant_data_keys(id);
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                ALTER TABLE tenant_setting DROP COLUMN IF EXISTS data_key_id;
+                DROP TABLE IF EXISTS tenant_data_keys;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- ModuleFlags bits; existing tenants keep every module (0b11111111)
+                ALTER TABLE tenant ADD COLUMN IF NOT EXISTS enabled_modules INTEGER NOT NULL DEFAULT 255;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("ALTER TABLE tenant DROP COLUMN IF EXISTS enabled_modules;")
+            .await?;
+
+        Ok(())
+    }
+}
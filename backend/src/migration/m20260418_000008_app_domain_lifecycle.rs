@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Domains that already exist are serving traffic, so they start out active
+                ALTER TABLE app_domains
+                    ADD COLUMN IF NOT EXISTS status VARCHAR NOT NULL DEFAULT 'active',
+                    ADD COLUMN IF NOT EXISTS verification_token VARCHAR,
+                    ADD COLUMN IF NOT EXISTS provider_hostname_id VARCHAR,
+                    ADD COLUMN IF NOT EXISTS ssl_status VARCHAR,
+                    ADD COLUMN IF NOT EXISTS last_error TEXT,
+                    ADD COLUMN IF NOT EXISTS last_checked_at TIMESTAMPTZ,
+                    ADD COLUMN IF NOT EXISTS verified_at TIMESTAMPTZ;
+
+                CREATE INDEX IF NOT EXISTS idx_app_domains_status ON app_domains (status) WHERE status <> 'active';
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP INDEX IF EXISTS idx_app_domains_status;
+                ALTER TABLE app_domains
+                    DROP COLUMN IF EXISTS status,
+                    DROP COLUMN IF EXISTS verification_token,
+                    DROP COLUMN IF EXISTS provider_hostname_id,
+                    DROP COLUMN IF EXISTS ssl_status,
+                    DROP COLUMN IF EXISTS last_error,
+                    DROP COLUMN IF EXISTS last_checked_at,
+                    DROP COLUMN IF EXISTS verified_at;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
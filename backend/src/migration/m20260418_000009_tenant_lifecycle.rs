@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- site_status used to be free-form ('ACTIVE', 'active', ...); anything that
+                -- isn't a lifecycle state was serving traffic, so it becomes active
+                UPDATE tenant SET site_status = lower(site_status);
+                UPDATE tenant SET site_status = 'active'
+                    WHERE site_status NOT IN ('active', 'suspended', 'scheduled_for_deletion', 'purged');
+
+                ALTER TABLE tenant
+                    ALTER COLUMN site_status SET DEFAULT 'active',
+                    ADD COLUMN IF NOT EXISTS suspended_at TIMESTAMPTZ,
+                    ADD COLUMN IF NOT EXISTS deletion_scheduled_at TIMESTAMPTZ,
+                    ADD COLUMN IF NOT EXISTS purge_after TIMESTAMPTZ,
+                    ADD COLUMN IF NOT EXISTS purged_at TIMESTAMPTZ,
+                    DROP CONSTRAINT IF EXISTS tenant_site_status_check;
+                ALTER TABLE tenant ADD CONSTRAINT tenant_site_status_check
+                    CHECK (site_status IN ('active', 'suspended', 'scheduled_for_deletion', 'purged'));
+
+                CREATE INDEX IF NOT EXISTS idx_tenant_purge_after ON tenant (purge_after)
+                    WHERE site_status = 'scheduled_for_deletion';
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP INDEX IF EXISTS idx_tenant_purge_after;
+                ALTER TABLE tenant
+                    DROP CONSTRAINT IF EXISTS tenant_site_status_check,
+                    DROP COLUMN IF EXISTS suspended_at,
+                    DROP COLUMN IF EXISTS deletion_scheduled_at,
+                    DROP COLUMN IF EXISTS purge_after,
+                    DROP COLUMN IF EXISTS purged_at;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
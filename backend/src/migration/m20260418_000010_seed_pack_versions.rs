@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Which version of the data_seed_name pack was last applied, and when
+                ALTER TABLE app_instances
+                    ADD COLUMN IF NOT EXISTS data_seed_version INTEGER,
+                    ADD COLUMN IF NOT EXISTS data_seed_applied_at TIMESTAMPTZ;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                ALTER TABLE app_instances
+                    DROP COLUMN IF EXISTS data_seed_version,
+                    DROP COLUMN IF EXISTS data_seed_applied_at;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
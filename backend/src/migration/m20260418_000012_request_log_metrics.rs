@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                ALTER TABLE request_log ADD COLUMN IF NOT EXISTS request_id VARCHAR(128);
+                ALTER TABLE request_log ADD COLUMN IF NOT EXISTS duration_ms INTEGER;
+                ALTER TABLE request_log ADD COLUMN IF NOT EXISTS response_bytes BIGINT;
+                -- Retention deletes by age; support lookups by correlation ID
+                CREATE INDEX IF NOT EXISTS idx_request_log_created_at ON request_log (created_at);
+                CREATE INDEX IF NOT EXISTS idx_request_log_request_id ON request_log (request_id);
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP INDEX IF EXISTS idx_request_log_request_id;
+                DROP INDEX IF EXISTS idx_request_log_created_at;
+                ALTER TABLE request_log DROP COLUMN IF EXISTS response_bytes;
+                ALTER TABLE request_log DROP COLUMN IF EXISTS duration_ms;
+                ALTER TABLE request_log DROP COLUMN IF EXISTS request_id;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
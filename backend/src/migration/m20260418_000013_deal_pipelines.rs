@@ -0,0 +1,134 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                CREATE TABLE IF NOT EXISTS deal_pipeline (
+                    id UUID PRIMARY KEY,
+                    tenant_id UUID REFERENCES tenant(id) ON DELETE CASCADE,
+                    name VARCHAR(255) NOT NULL,
+                    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    UNIQUE (tenant_id, name)
+                );
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_deal_pipeline_one_default
+                    ON deal_pipeline (tenant_id) WHERE is_default;
+
+                -- outcome is 'open', 'won' or 'lost'; probability is the chance (0-100) that
+                -- a deal in the stage is won
+                CREATE TABLE IF NOT EXISTS deal_pipeline_stage (
+                    id UUID PRIMARY KEY,
+                    pipeline_id UUID NOT NULL REFERENCES deal_pipeline(id) ON DELETE CASCADE,
+                    name VARCHAR(255) NOT NULL,
+                    position INTEGER NOT NULL,
+                    probability INTEGER NOT NULL CHECK (probability BETWEEN 0 AND 100),
+                    outcome VARCHAR(16) NOT NULL DEFAULT 'open' CHECK (outcome IN ('open', 'won', 'lost')),
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    UNIQUE (pipeline_id, name)
+                );
+                CREATE INDEX IF NOT EXISTS idx_deal_pipeline_stage_pipeline ON deal_pipeline_stage (pipeline_id, position);
+
+                ALTER TABLE deal ADD COLUMN IF NOT EXISTS pipeline_id UUID REFERENCES deal_pipeline(id) ON DELETE SET NULL;
+                ALTER TABLE deal ADD COLUMN IF NOT EXISTS stage_id UUID REFERENCES deal_pipeline_stage(id) ON DELETE SET NULL;
+                ALTER TABLE deal ADD COLUMN IF NOT EXISTS stage_entered_at TIMESTAMPTZ;
+                CREATE INDEX IF NOT EXISTS idx_deal_stage_id ON deal (stage_id);
+
+                -- One row per stage a deal entered, starting with the stage it was created in
+                CREATE TABLE IF NOT EXISTS deal_stage_history (
+                    id UUID PRIMARY KEY,
+                    deal_id UUID NOT NULL REFERENCES deal(id) ON DELETE CASCADE,
+                    tenant_id UUID REFERENCES tenant(id) ON DELETE CASCADE,
+                    pipeline_id UUID REFERENCES deal_pipeline(id) ON DELETE SET NULL,
+                    from_stage_id UUID REFERENCES deal_pipeline_stage(id) ON DELETE SET NULL,
+                    to_stage_id UUID REFERENCES deal_pipeline_stage(id) ON DELETE SET NULL,
+                    from_stage VARCHAR(255),
+                    to_stage VARCHAR(255) NOT NULL,
+                    amount DOUBLE PRECISION NOT NULL,
+                    changed_by UUID REFERENCES "user"(id) ON DELETE SET NULL,
+                    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                );
+                CREATE INDEX IF NOT EXISTS idx_deal_stage_history_deal ON deal_stage_history (deal_id, changed_at);
+                CREATE INDEX IF NOT EXISTS idx_deal_stage_history_pipeline ON deal_stage_history (pipeline_id, changed_at);
+
+                -- Existing deals move into their tenant's default pipeline, which gets the stages of
+                -- `deal_pipelines::DEFAULT_STAGES`. A deal keeps the stage matching its name, or else
+                -- the first stage whose outcome fits its status.
+                DO $$
+                DECLARE
+                    t RECORD;
+                    p UUID;
+                BEGIN
+                    FOR t IN SELECT DISTINCT tenant_id FROM deal WHERE pipeline_id IS NULL LOOP
+                        SELECT id INTO p FROM deal_pipeline
+                            WHERE tenant_id IS NOT DISTINCT FROM t.tenant_id AND is_default
+                            ORDER BY created_at LIMIT 1;
+                        IF p IS NULL THEN
+                            p := gen_random_uuid();
+                            INSERT INTO deal_pipeline (id, tenant_id, name, is_default) VALUES (p, t.tenant_id, 'Sales', TRUE);
+                            INSERT INTO deal_pipeline_stage (id, pipeline_id, name, position, probability, outcome) VALUES
+                                (gen_random_uuid(), p, 'Prospecting', 0, 10, 'open'),
+                                (gen_random_uuid(), p, 'Qualification', 1, 25, 'open'),
+                                (gen_random_uuid(), p, 'Proposal', 2, 50, 'open'),
+                                (gen_random_uuid(), p, 'Negotiation', 3, 75, 'open'),
+                                (gen_random_uuid(), p, 'Closed Won', 4, 100, 'won'),
+                                (gen_random_uuid(), p, 'Closed Lost', 5, 0, 'lost');
+                        END IF;
+
+                        UPDATE deal d SET
+                            pipeline_id = p,
+                            stage_id = COALESCE(
+                                (SELECT s.id FROM deal_pipeline_stage s
+                                    WHERE s.pipeline_id = p AND lower(s.name) = lower(d.stage)),
+                                (SELECT s.id FROM deal_pipeline_stage s
+                                    WHERE s.pipeline_id = p AND s.outcome = CASE d.status
+                                        WHEN 'Won' THEN 'won' WHEN 'Lost' THEN 'lost' ELSE 'open' END
+                                    ORDER BY s.position LIMIT 1)
+                            ),
+                            stage_entered_at = d.updated_at
+                            WHERE d.pipeline_id IS NULL AND d.tenant_id IS NOT DISTINCT FROM t.tenant_id;
+                        UPDATE deal d SET stage = s.name
+                            FROM deal_pipeline_stage s
+                            WHERE s.id = d.stage_id AND d.pipeline_id = p AND d.stage IS DISTINCT FROM s.name;
+                    END LOOP;
+                END $$;
+
+                -- Every deal's history starts with the stage it is in
+                INSERT INTO deal_stage_history (id, deal_id, tenant_id, pipeline_id, to_stage_id, to_stage, amount, changed_at)
+                    SELECT gen_random_uuid(), d.id, d.tenant_id, d.pipeline_id, d.stage_id, s.name, d.amount,
+                           COALESCE(d.stage_entered_at, d.updated_at)
+                    FROM deal d JOIN deal_pipeline_stage s ON s.id = d.stage_id
+                    WHERE NOT EXISTS (SELECT 1 FROM deal_stage_history h WHERE h.deal_id = d.id);
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP TABLE IF EXISTS deal_stage_history;
+                ALTER TABLE deal DROP COLUMN IF EXISTS stage_entered_at;
+                ALTER TABLE deal DROP COLUMN IF EXISTS stage_id;
+                ALTER TABLE deal DROP COLUMN IF EXISTS pipeline_id;
+                DROP TABLE IF EXISTS deal_pipeline_stage;
+                DROP TABLE IF EXISTS deal_pipeline;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Columns the activity entity has always mapped but the CRM tables never created
+                ALTER TABLE activity ADD COLUMN IF NOT EXISTS account_id UUID REFERENCES account(id) ON DELETE SET NULL;
+                ALTER TABLE activity ADD COLUMN IF NOT EXISTS associated_entities JSONB NOT NULL DEFAULT '[]'::jsonb;
+                ALTER TABLE activity ALTER COLUMN description DROP NOT NULL;
+
+                -- recurrence is 'daily', 'weekly' or 'monthly', repeating every recurrence_interval
+                -- periods; completing an occurrence creates the next one in the same series
+                ALTER TABLE activity ADD COLUMN IF NOT EXISTS recurrence VARCHAR(16)
+                    CHECK (recurrence IN ('daily', 'weekly', 'monthly'));
+                ALTER TABLE activity ADD COLUMN IF NOT EXISTS recurrence_interval INTEGER NOT NULL DEFAULT 1
+                    CHECK (recurrence_interval > 0);
+                ALTER TABLE activity ADD COLUMN IF NOT EXISTS recurrence_ends_at TIMESTAMPTZ;
+                ALTER TABLE activity ADD COLUMN IF NOT EXISTS series_id UUID;
+                ALTER TABLE activity ADD COLUMN IF NOT EXISTS snooze_count INTEGER NOT NULL DEFAULT 0;
+
+                -- When the assignee was told about the current due date; cleared when it moves
+                ALTER TABLE activity ADD COLUMN IF NOT EXISTS upcoming_notified_at TIMESTAMPTZ;
+                ALTER TABLE activity ADD COLUMN IF NOT EXISTS overdue_notified_at TIMESTAMPTZ;
+
+                CREATE INDEX IF NOT EXISTS idx_activity_open_due ON activity (tenant_id, due_date)
+                    WHERE status IN ('Pending', 'InProgress') AND due_date IS NOT NULL;
+                CREATE INDEX IF NOT EXISTS idx_activity_assigned_to ON activity (assigned_to)
+                    WHERE status IN ('Pending', 'InProgress');
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP INDEX IF EXISTS idx_activity_assigned_to;
+                DROP INDEX IF EXISTS idx_activity_open_due;
+                ALTER TABLE activity DROP COLUMN IF EXISTS overdue_notified_at;
+                ALTER TABLE activity DROP COLUMN IF EXISTS upcoming_notified_at;
+                ALTER TABLE activity DROP COLUMN IF EXISTS snooze_count;
+                ALTER TABLE activity DROP COLUMN IF EXISTS series_id;
+                ALTER TABLE activity DROP COLUMN IF EXISTS recurrence_ends_at;
+                ALTER TABLE activity DROP COLUMN IF EXISTS recurrence_interval;
+                ALTER TABLE activity DROP COLUMN IF EXISTS recurrence;
+                ALTER TABLE activity DROP COLUMN IF EXISTS associated_entities;
+                ALTER TABLE activity DROP COLUMN IF EXISTS account_id;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
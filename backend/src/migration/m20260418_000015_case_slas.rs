@@ -0,0 +1,87 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Response and resolution targets for cases of one priority; breached cases
+                -- are reassigned to escalate_to when it is set
+                CREATE TABLE IF NOT EXISTS case_sla_policy (
+                    id UUID PRIMARY KEY,
+                    tenant_id UUID REFERENCES tenant(id) ON DELETE CASCADE,
+                    priority VARCHAR(16) NOT NULL CHECK (priority IN ('Low', 'Medium', 'High', 'Urgent')),
+                    first_response_minutes INTEGER NOT NULL CHECK (first_response_minutes > 0),
+                    resolution_minutes INTEGER NOT NULL CHECK (resolution_minutes > 0),
+                    escalate_to UUID REFERENCES "user"(id) ON DELETE SET NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    UNIQUE (tenant_id, priority)
+                );
+
+                -- Statuses and priorities were free text; fold them onto the fixed sets
+                UPDATE "case" SET status = CASE lower(trim(status))
+                    WHEN 'new' THEN 'New'
+                    WHEN 'pending' THEN 'Pending'
+                    WHEN 'resolved' THEN 'Resolved'
+                    WHEN 'closed' THEN 'Closed'
+                    ELSE 'Open'
+                END;
+                UPDATE "case" SET priority = CASE lower(trim(priority))
+                    WHEN 'low' THEN 'Low'
+                    WHEN 'high' THEN 'High'
+                    WHEN 'urgent' THEN 'Urgent'
+                    WHEN 'critical' THEN 'Urgent'
+                    ELSE 'Medium'
+                END;
+                ALTER TABLE "case" DROP CONSTRAINT IF EXISTS case_status_check;
+                ALTER TABLE "case" ADD CONSTRAINT case_status_check
+                    CHECK (status IN ('New', 'Open', 'Pending', 'Resolved', 'Closed'));
+                ALTER TABLE "case" DROP CONSTRAINT IF EXISTS case_priority_check;
+                ALTER TABLE "case" ADD CONSTRAINT case_priority_check
+                    CHECK (priority IN ('Low', 'Medium', 'High', 'Urgent'));
+
+                ALTER TABLE "case" ADD COLUMN IF NOT EXISTS first_response_due_at TIMESTAMPTZ;
+                ALTER TABLE "case" ADD COLUMN IF NOT EXISTS resolution_due_at TIMESTAMPTZ;
+                ALTER TABLE "case" ADD COLUMN IF NOT EXISTS first_responded_at TIMESTAMPTZ;
+                ALTER TABLE "case" ADD COLUMN IF NOT EXISTS resolved_at TIMESTAMPTZ;
+                ALTER TABLE "case" ADD COLUMN IF NOT EXISTS response_escalated_at TIMESTAMPTZ;
+                ALTER TABLE "case" ADD COLUMN IF NOT EXISTS resolution_escalated_at TIMESTAMPTZ;
+                UPDATE "case" SET resolved_at = COALESCE(closed_at, updated_at)
+                    WHERE status IN ('Resolved', 'Closed') AND resolved_at IS NULL;
+                CREATE INDEX IF NOT EXISTS idx_case_sla_active
+                    ON "case" (tenant_id, resolution_due_at) WHERE status IN ('New', 'Open', 'Pending');
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP INDEX IF EXISTS idx_case_sla_active;
+                ALTER TABLE "case" DROP COLUMN IF EXISTS resolution_escalated_at;
+                ALTER TABLE "case" DROP COLUMN IF EXISTS response_escalated_at;
+                ALTER TABLE "case" DROP COLUMN IF EXISTS resolved_at;
+                ALTER TABLE "case" DROP COLUMN IF EXISTS first_responded_at;
+                ALTER TABLE "case" DROP COLUMN IF EXISTS resolution_due_at;
+                ALTER TABLE "case" DROP COLUMN IF EXISTS first_response_due_at;
+                ALTER TABLE "case" DROP CONSTRAINT IF EXISTS case_priority_check;
+                ALTER TABLE "case" DROP CONSTRAINT IF EXISTS case_status_check;
+                DROP TABLE IF EXISTS case_sla_policy;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Describes one key of an entity's `properties` JSONB. options holds the
+                -- allowed values of a select field; default_value is filled in on create.
+                CREATE TABLE IF NOT EXISTS custom_field_definition (
+                    id UUID PRIMARY KEY,
+                    tenant_id UUID REFERENCES tenant(id) ON DELETE CASCADE,
+                    entity_type VARCHAR(16) NOT NULL
+                        CHECK (entity_type IN ('lead', 'deal', 'customer', 'contact', 'case', 'profile')),
+                    key VARCHAR(64) NOT NULL CHECK (key ~ '^[a-z][a-z0-9_]*$'),
+                    label VARCHAR(255) NOT NULL,
+                    field_type VARCHAR(16) NOT NULL
+                        CHECK (field_type IN ('text', 'textarea', 'email', 'number', 'boolean', 'date', 'select')),
+                    required BOOLEAN NOT NULL DEFAULT FALSE,
+                    options JSONB,
+                    default_value JSONB,
+                    position INTEGER NOT NULL DEFAULT 0,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    UNIQUE (tenant_id, entity_type, key)
+                );
+
+                -- Custom field filters are containment queries (properties @> '{"key": value}')
+                CREATE INDEX IF NOT EXISTS idx_lead_properties ON lead USING GIN (properties jsonb_path_ops);
+                CREATE INDEX IF NOT EXISTS idx_deal_properties ON deal USING GIN (properties jsonb_path_ops);
+                CREATE INDEX IF NOT EXISTS idx_customer_properties ON customer USING GIN (properties jsonb_path_ops);
+                CREATE INDEX IF NOT EXISTS idx_contact_properties ON contact USING GIN (properties jsonb_path_ops);
+                CREATE INDEX IF NOT EXISTS idx_case_properties ON "case" USING GIN (properties jsonb_path_ops);
+                CREATE INDEX IF NOT EXISTS idx_profile_properties ON profile USING GIN (properties jsonb_path_ops);
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP INDEX IF EXISTS idx_profile_properties;
+                DROP INDEX IF EXISTS idx_case_properties;
+                DROP INDEX IF EXISTS idx_contact_properties;
+                DROP INDEX IF EXISTS idx_customer_properties;
+                DROP INDEX IF EXISTS idx_deal_properties;
+                DROP INDEX IF EXISTS idx_lead_properties;
+                DROP TABLE IF EXISTS custom_field_definition;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- The CRM app migrations create the legacy `note` table while the entity reads `notes`,
+                -- so the tenant scoping in m20260418_000002 never applied to it
+                DO $$
+                BEGIN
+                    IF to_regclass('notes') IS NULL AND to_regclass('note') IS NOT NULL THEN
+                        ALTER TABLE note RENAME TO notes;
+                    END IF;
+                END $$;
+                CREATE TABLE IF NOT EXISTS notes (
+                    id UUID PRIMARY KEY,
+                    content VARCHAR NOT NULL,
+                    created_by UUID NOT NULL REFERENCES "user"(id) ON DELETE CASCADE,
+                    entity_type VARCHAR NOT NULL,
+                    entity_id UUID NOT NULL,
+                    created_at TIMESTAMPTZ NOT NULL,
+                    updated_at TIMESTAMPTZ NOT NULL
+                );
+                ALTER TABLE notes ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenant(id) ON DELETE CASCADE;
+
+                UPDATE notes n SET tenant_id = CASE n.entity_type
+                        WHEN 'Deal' THEN (SELECT tenant_id FROM deal WHERE id = n.entity_id)
+                        WHEN 'Lead' THEN (SELECT tenant_id FROM lead WHERE id = n.entity_id)
+                        WHEN 'Customer' THEN (SELECT tenant_id FROM customer WHERE id = n.entity_id)
+                        WHEN 'Contact' THEN (SELECT tenant_id FROM contact WHERE id = n.entity_id)
+                        WHEN 'Case' THEN (SELECT tenant_id FROM "case" WHERE id = n.entity_id)
+                        WHEN 'Activity' THEN (SELECT tenant_id FROM activity WHERE id = n.entity_id)
+                    END
+                    WHERE n.tenant_id IS NULL;
+                CREATE INDEX IF NOT EXISTS idx_notes_tenant_id ON notes (tenant_id);
+                CREATE INDEX IF NOT EXISTS idx_notes_entity ON notes (entity_type, entity_id);
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP INDEX IF EXISTS idx_notes_entity;
+                DROP INDEX IF EXISTS idx_notes_tenant_id;
+                ALTER TABLE IF EXISTS notes DROP COLUMN IF EXISTS tenant_id;
+                DO $$
+                BEGIN
+                    IF to_regclass('note') IS NULL AND to_regclass('notes') IS NOT NULL THEN
+                        ALTER TABLE notes RENAME TO note;
+                    END IF;
+                END $$;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Tables behind the `file`/`file_association` entities. The legacy `file` tables the
+                -- CRM app migrations create use another layout and are left alone.
+                CREATE TABLE IF NOT EXISTS files (
+                    id VARCHAR PRIMARY KEY,
+                    name VARCHAR NOT NULL,
+                    size BIGINT NOT NULL,
+                    mime_type VARCHAR NOT NULL,
+                    hash_sha256 VARCHAR NOT NULL,
+                    storage_type VARCHAR(1) NOT NULL,
+                    storage_path VARCHAR NOT NULL,
+                    views INTEGER NOT NULL DEFAULT 0,
+                    downloads INTEGER NOT NULL DEFAULT 0,
+                    bandwidth_used BIGINT NOT NULL DEFAULT 0,
+                    bandwidth_used_paid BIGINT NOT NULL DEFAULT 0,
+                    date_upload TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    date_last_view TIMESTAMPTZ,
+                    is_anonymous BOOLEAN NOT NULL DEFAULT FALSE,
+                    user_id VARCHAR,
+                    tenant_id UUID REFERENCES tenant(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_files_tenant_id ON files (tenant_id);
+
+                CREATE TABLE IF NOT EXISTS file_associations (
+                    id UUID PRIMARY KEY,
+                    file_id VARCHAR NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                    associated_entity_type VARCHAR NOT NULL,
+                    associated_entity_id UUID NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_file_associations_entity
+                    ON file_associations (associated_entity_type, associated_entity_id);
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP TABLE IF EXISTS file_associations;
+                DROP TABLE IF EXISTS files;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Notes and activities created through an API token have no authoring user.
+                ALTER TABLE notes ALTER COLUMN created_by DROP NOT NULL;
+                ALTER TABLE activity ALTER COLUMN created_by DROP NOT NULL;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DELETE FROM notes WHERE created_by IS NULL;
+                DELETE FROM activity WHERE created_by IS NULL;
+                ALTER TABLE notes ALTER COLUMN created_by SET NOT NULL;
+                ALTER TABLE activity ALTER COLUMN created_by SET NOT NULL;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- Tokens without a lookup prefix are refused without hashing, so show them as expired
+                UPDATE api_tokens SET expires_at = NOW()
+                WHERE token_prefix IS NULL AND (expires_at IS NULL OR expires_at > NOW());
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // Their original expiry isn't kept, and they can't be resolved either way
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                -- When a resolved or closed case was last reopened; its resolution target counts from here
+                ALTER TABLE "case" ADD COLUMN IF NOT EXISTS reopened_at TIMESTAMPTZ;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                ALTER TABLE "case" DROP COLUMN IF EXISTS reopened_at;
+                "#,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
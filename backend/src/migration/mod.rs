@@ -5,29 +5,16 @@ pub mod m20230911_create_accounts_table;
 pub mod m20230912_000001_create_user_accounts_table;
 pub mod m20230913_create_directory_types_table;
 pub mod m20230914_create_directories_table;
-pub mod m20230915_create_profiles_table;
-pub mod m20230916_create_categories_table;
-pub mod m20230917_create_templates_table;
-pub mod m20230918_create_listings_table;
-pub mod m20230919_create_listing_attributes_table;
-pub mod m20230920_create_ad_purchases_table;
 pub mod m20240001_update_timestamp_migration;
 pub mod m20230911_create_sessions_table;
-pub mod m20240922_create_crm_tables;
 pub mod m20240922_create_request_log_table;
 pub mod m20240923_create_feed_tables;
-pub mod m20240924_update_listings_nullable_category;
 pub mod m20240924_update_directory_multisite_fields;
 pub mod m20240315_add_directory_domain_fields;
-pub mod m20241001_add_icon_and_slug_to_categories;
 pub mod m20241002_add_directory_id_to_crm_and_categories;
-pub mod m20241003_add_slug_to_listings;
 pub mod m20250101_create_ab_testing_tables;
 pub mod m20260320_create_passkeys_table;
-pub mod m20260324_000001_collapse_eav_to_jsonb;
-pub mod m20260326_add_service_area_to_profile;
 pub mod m20260326_add_stripe_fields_to_account;
-pub mod m20260326_create_lead_charges_table;
 pub mod m20260404_000000_core_tenant_shift;
 pub mod m20260404_000001_create_cms_tables;
 pub mod m20260404_000002_anchor_seed;
@@ -41,9 +28,6 @@ pub mod m20260406_000003_create_developer_console_tables;
 pub mod m20260408_000000_create_audit_logs;
 pub mod m20260408_000000_fix_tenant_app_alignments;
 pub mod m20260408_000001_fix_uat_app_domains;
-pub mod m20260408_000002_create_anchor_legacy_tables;
-pub mod m20260408_000003_seed_anchor_background_jobs;
-pub mod m20260408_000004_fix_anchor_tables_and_seed;
 pub mod m20260408_000006_create_app_content;
 pub mod m20260412_000001_form_engine;
 pub mod m20260412_000002_add_tenant_slug;
@@ -56,13 +40,37 @@ pub mod m20260416_000001_rename_resume_tables;
 pub mod m20260416_000002_seed_buildwithruud_block_pages;
 pub mod m20260417_000001_seed_design_system_config;
 pub mod m20260417_000002_fix_buildwithruud_pages;
+pub mod m20260418_000001_add_api_token_lookup;
+pub mod m20260418_000002_add_crm_tenant_scoping;
+pub mod m20260418_000003_webhook_delivery_v2;
+pub mod m20260418_000004_create_outbox;
+pub mod m20260418_000005_job_scheduler;
+pub mod m20260418_000006_tenant_data_keys;
+pub mod m20260418_000007_tenant_enabled_modules;
+pub mod m20260418_000008_app_domain_lifecycle;
+pub mod m20260418_000009_tenant_lifecycle;
+pub mod m20260418_000010_seed_pack_versions;
+pub mod m20260418_000011_rate_limit_buckets;
+pub mod m20260418_000012_request_log_metrics;
+pub mod m20260418_000013_deal_pipelines;
+pub mod m20260418_000014_activity_reminders;
+pub mod m20260418_000015_case_slas;
+pub mod m20260418_000016_custom_fields;
+pub mod m20260418_000017_align_notes_table;
+pub mod m20260418_000018_file_tables;
+pub mod m20260418_000019_nullable_note_activity_author;
+pub mod m20260418_000020_expire_legacy_api_tokens;
+pub mod m20260418_000021_case_reopened_at;
+pub mod runner;
 
+/// Core platform migrations. App migrations live with their `AtlasApp`; apply both
+/// through [`runner::up`].
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        let mut base: Vec<Box<dyn MigrationTrait>> = vec![
+        let mut migrations: Vec<Box<dyn MigrationTrait>> = vec![
             Box::new(m20230911_create_accounts_table::Migration),
             Box::new(m20230911_create_sessions_table::Migration),
             Box::new(m20230912_000000_create_users_table::Migration),
@@ -103,14 +111,31 @@ impl MigratorTrait for Migrator {
             Box::new(m20260416_000002_seed_buildwithruud_block_pages::Migration),
             Box::new(m20260417_000001_seed_design_system_config::Migration),
             Box::new(m20260417_000002_fix_buildwithruud_pages::Migration),
+            Box::new(m20260418_000001_add_api_token_lookup::Migration),
+            Box::new(m20260418_000002_add_crm_tenant_scoping::Migration),
+            Box::new(m20260418_000003_webhook_delivery_v2::Migration),
+            Box::new(m20260418_000004_create_outbox::Migration),
+            Box::new(m20260418_000005_job_scheduler::Migration),
+            Box::new(m20260418_000006_tenant_data_keys::Migration),
+            Box::new(m20260418_000007_tenant_enabled_modules::Migration),
+            Box::new(m20260418_000008_app_domain_lifecycle::Migration),
+            Box::new(m20260418_000009_tenant_lifecycle::Migration),
+            Box::new(m20260418_000010_seed_pack_versions::Migration),
+            Box::new(m20260418_000011_rate_limit_buckets::Migration),
+            Box::new(m20260418_000012_request_log_metrics::Migration),
+            Box::new(m20260418_000013_deal_pipelines::Migration),
+            Box::new(m20260418_000014_activity_reminders::Migration),
+            Box::new(m20260418_000015_case_slas::Migration),
+            Box::new(m20260418_000016_custom_fields::Migration),
+            Box::new(m20260418_000017_align_notes_table::Migration),
+            Box::new(m20260418_000018_file_tables::Migration),
+            Box::new(m20260418_000019_nullable_note_activity_author::Migration),
+            Box::new(m20260418_000020_expire_legacy_api_tokens::Migration),
+            Box::new(m20260418_000021_case_reopened_at::Migration),
         ];
 
-        for app in crate::atlas_apps::get_active_apps() {
-            base.extend(app.migrations());
-        }
+        migrations.sort_by(|a, b| a.name().cmp(b.name()));
 
-        base.sort_by(|a, b| a.name().cmp(b.name()));
-
-        base
+        migrations
     }
 }
\ No newline at end of file
@@ -0,0 +1,149 @@
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Statement, TransactionTrait};
+use sea_orm_migration::{MigrationTrait, MigratorTrait, SchemaManager};
+use std::collections::{HashMap, HashSet};
+
+use super::Migrator;
+
+/// Tracking table of the core migrations, shared with `sea-orm-cli`.
+pub const CORE_MIGRATION_TABLE: &str = "seaql_migrations";
+
+/// Tracking table of an app's migrations, e.g. `seaql_migrations_anchor`.
+pub fn app_migration_table(app_id: &str) -> String {
+    format!("{}_{}", CORE_MIGRATION_TABLE, app_id)
+}
+
+struct Step {
+    table: String,
+    migration: Box<dyn MigrationTrait>,
+}
+
+/// Applies every pending core and app migration.
+///
+/// Core and app schemas reference each other, so all migrations run in a single
+/// name-ordered sequence. Each app's migrations are recorded in the app's own table
+/// rather than in `seaql_migrations`.
+pub async fn up(db: &DatabaseConnection) -> Result<(), DbErr> {
+    ensure_table(db, CORE_MIGRATION_TABLE).await?;
+    let mut steps: Vec<Step> = Migrator::migrations()
+        .into_iter()
+        .map(|migration| Step { table: CORE_MIGRATION_TABLE.to_string(), migration })
+        .collect();
+
+    for app in crate::atlas_apps::get_active_apps() {
+        let table = app_migration_table(app.app_id());
+        ensure_table(db, &table).await?;
+        let migrations = app.migrations();
+        adopt_core_records(db, &table, &migrations).await?;
+        steps.extend(migrations.into_iter().map(|migration| Step { table: table.clone(), migration }));
+    }
+
+    steps.sort_by(|a, b| a.migration.name().cmp(b.migration.name()));
+    if let Some(pair) = steps.windows(2).find(|pair| pair[0].migration.name() == pair[1].migration.name()) {
+        return Err(DbErr::Migration(format!(
+            "Migration {} is registered by both {} and {}",
+            pair[0].migration.name(), pair[0].table, pair[1].table
+        )));
+    }
+
+    let mut applied: HashMap<String, HashSet<String>> = HashMap::new();
+    for step in &steps {
+        if !applied.contains_key(&step.table) {
+            applied.insert(step.table.clone(), applied_versions(db, &step.table).await?);
+        }
+    }
+
+    for step in steps {
+        let name = step.migration.name().to_string();
+        if applied[&step.table].contains(&name) {
+            continue;
+        }
+
+        tracing::info!("Applying migration {} ({})", name, step.table);
+        let txn = db.begin().await?;
+        step.migration.up(&SchemaManager::new(&txn)).await?;
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!(r#"INSERT INTO "{}" (version, applied_at) VALUES ($1, $2)"#, step.table),
+            [name.into(), chrono::Utc::now().timestamp().into()],
+        ))
+        .await?;
+        txn.commit().await?;
+    }
+
+    Ok(())
+}
+
+/// Drops every table and enum type in the current schema, then runs [`up`].
+pub async fn fresh(db: &DatabaseConnection) -> Result<(), DbErr> {
+    db.execute_unprepared(
+        r#"
+        DO $$
+        DECLARE
+            r RECORD;
+        BEGIN
+            FOR r IN SELECT tablename FROM pg_tables WHERE schemaname = current_schema() LOOP
+                EXECUTE format('DROP TABLE IF EXISTS %I CASCADE', r.tablename);
+            END LOOP;
+            FOR r IN
+                SELECT t.typname FROM pg_type t
+                JOIN pg_namespace n ON n.oid = t.typnamespace
+                WHERE n.nspname = current_schema() AND t.typtype = 'e'
+            LOOP
+                EXECUTE format('DROP TYPE IF EXISTS %I CASCADE', r.typname);
+            END LOOP;
+        END $$;
+        "#,
+    )
+    .await?;
+    up(db).await
+}
+
+async fn ensure_table(db: &DatabaseConnection, table: &str) -> Result<(), DbErr> {
+    db.execute_unprepared(&format!(
+        r#"CREATE TABLE IF NOT EXISTS "{}" (version VARCHAR PRIMARY KEY, applied_at BIGINT NOT NULL)"#,
+        table
+    ))
+    .await
+    .map(|_| ())
+}
+
+async fn applied_versions(db: &DatabaseConnection, table: &str) -> Result<HashSet<String>, DbErr> {
+    let rows = db
+        .query_all(Statement::from_string(
+            DbBackend::Postgres,
+            format!(r#"SELECT version FROM "{}""#, table),
+        ))
+        .await?;
+    rows.iter().map(|row| row.try_get::<String>("", "version")).collect()
+}
+
+/// Databases migrated before apps had their own tables recorded app migrations in
+/// `seaql_migrations`. Those records move to the app's table so they are not re-run.
+async fn adopt_core_records(
+    db: &DatabaseConnection,
+    table: &str,
+    migrations: &[Box<dyn MigrationTrait>],
+) -> Result<(), DbErr> {
+    let txn = db.begin().await?;
+    for migration in migrations {
+        let name = migration.name().to_string();
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!(
+                r#"INSERT INTO "{}" (version, applied_at)
+                   SELECT version, applied_at FROM "{}" WHERE version = $1
+                   ON CONFLICT (version) DO NOTHING"#,
+                table, CORE_MIGRATION_TABLE
+            ),
+            [name.clone().into()],
+        ))
+        .await?;
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!(r#"DELETE FROM "{}" WHERE version = $1"#, CORE_MIGRATION_TABLE),
+            [name.into()],
+        ))
+        .await?;
+    }
+    txn.commit().await
+}
@@ -21,10 +21,13 @@ pub struct ActivityModel {
     pub due_date: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub associated_entities: Vec<AssociatedEntity>,
-    pub created_by: Uuid,
+    pub created_by: Option<Uuid>,
     pub assigned_to: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub recurrence: Option<Recurrence>,
+    pub series_id: Option<Uuid>,
+    pub snooze_count: i32,
     pub files: Vec<FileModel>,
 }
 
@@ -50,6 +53,8 @@ pub struct CreateActivityInput {
     pub associated_entities: Vec<AssociatedEntity>,
     pub assigned_to: Option<Uuid>,
     pub files: Vec<FileModel>,
+    /// Repeats the activity; requires a `due_date`.
+    pub recurrence: Option<Recurrence>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -68,11 +73,13 @@ pub struct UpdateActivityInput {
     pub associated_entities: Option<Vec<AssociatedEntity>>,
     pub assigned_to: Option<Uuid>,
     pub files: Option<Vec<Uuid>>,
+    pub recurrence: Option<Recurrence>,
 }
 
 impl From<crate::entities::activity::Model> for ActivityModel {
     fn from(activity: crate::entities::activity::Model) -> Self {
         let associated_entities = activity.get_associated_entities().unwrap_or_default();
+        let recurrence = Recurrence::from_activity(&activity);
         Self {
             id: activity.id,
             account_id: activity.account_id.unwrap_or_default(),
@@ -92,6 +99,9 @@ impl From<crate::entities::activity::Model> for ActivityModel {
             assigned_to: activity.assigned_to,
             created_at: activity.created_at,
             updated_at: activity.updated_at,
+            recurrence,
+            series_id: activity.series_id,
+            snooze_count: activity.snooze_count,
             files: Vec::new(), // Initialize with empty vec
         }
     }
@@ -104,3 +114,110 @@ impl ActivityModel {
         model
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecurrenceFrequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl RecurrenceFrequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            "monthly" => Some(Self::Monthly),
+            _ => None,
+        }
+    }
+}
+
+fn default_recurrence_interval() -> i32 {
+    1
+}
+
+/// How a task repeats: completing an occurrence schedules the next one `interval` periods after
+/// its due date, until `until` passes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub frequency: RecurrenceFrequency,
+    #[serde(default = "default_recurrence_interval")]
+    pub interval: i32,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl Recurrence {
+    pub fn from_activity(activity: &crate::entities::activity::Model) -> Option<Self> {
+        let frequency = RecurrenceFrequency::parse(activity.recurrence.as_deref()?)?;
+        Some(Self {
+            frequency,
+            interval: activity.recurrence_interval,
+            until: activity.recurrence_ends_at,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnoozeActivityInput {
+    /// New due date; takes precedence over `minutes`.
+    pub until: Option<DateTime<Utc>>,
+    /// Pushes the due date this many minutes past now.
+    pub minutes: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompleteActivityResult {
+    pub completed: ActivityModel,
+    /// The next occurrence of a recurring activity.
+    pub next: Option<ActivityModel>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MyTasksQuery {
+    /// Includes completed and cancelled activities.
+    #[serde(default)]
+    pub include_closed: bool,
+    /// Only activities due before this time.
+    pub due_before: Option<DateTime<Utc>>,
+}
+
+/// A record an activity belongs to.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RelatedRecord {
+    pub entity_type: AssociatedEntityType,
+    pub entity_id: Uuid,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MyTask {
+    #[serde(flatten)]
+    pub activity: ActivityModel,
+    pub related: Vec<RelatedRecord>,
+    pub overdue: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MyTasksSummary {
+    pub overdue: usize,
+    pub due_today: usize,
+    pub upcoming: usize,
+    pub unscheduled: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MyTasks {
+    pub summary: MyTasksSummary,
+    /// Overdue first, then by due date; unscheduled tasks last.
+    pub tasks: Vec<MyTask>,
+}
@@ -1,6 +1,7 @@
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use crate::entities::case;
 use crate::models::note::NoteModel;
 use crate::models::activity::ActivityModel;
@@ -19,6 +20,15 @@ pub struct CaseModel {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub closed_at: Option<DateTime<Utc>>,
+    pub first_response_due_at: Option<DateTime<Utc>>,
+    pub resolution_due_at: Option<DateTime<Utc>>,
+    pub first_responded_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// The first response came, or is still missing, after its target.
+    pub first_response_breached: bool,
+    /// The case was resolved, or is still unresolved, after its target.
+    pub resolution_breached: bool,
+    pub properties: Option<Value>,
     pub notes: Vec<NoteModel>,
     pub activities: Vec<ActivityModel>,
     pub files: Vec<FileModel>,
@@ -31,6 +41,7 @@ pub struct CreateCaseInput {
     pub description: String,
     pub priority: String,
     pub assigned_to: Option<Uuid>,
+    pub properties: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -40,6 +51,7 @@ pub struct UpdateCaseInput {
     pub status: Option<String>,
     pub priority: Option<String>,
     pub assigned_to: Option<Uuid>,
+    pub properties: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -75,9 +87,145 @@ impl From<case::Model> for CaseModel {
             created_at: case.created_at,
             updated_at: case.updated_at,
             closed_at: case.closed_at,
+            first_response_breached: breached(case.first_response_due_at, case.first_responded_at),
+            resolution_breached: breached(case.resolution_due_at, case.resolved_at),
+            first_response_due_at: case.first_response_due_at,
+            resolution_due_at: case.resolution_due_at,
+            first_responded_at: case.first_responded_at,
+            resolved_at: case.resolved_at,
+            properties: case.properties,
             notes: Vec::new(),
             activities: Vec::new(),
             files: Vec::new(),
         }
     }
 }
+
+fn breached(due: Option<DateTime<Utc>>, done: Option<DateTime<Utc>>) -> bool {
+    due.is_some_and(|due| done.unwrap_or_else(Utc::now) > due)
+}
+
+/// Where a case is in its lifecycle: new → open → pending → resolved → closed. Resolved and
+/// closed cases can be reopened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CaseStatus {
+    New,
+    Open,
+    Pending,
+    Resolved,
+    Closed,
+}
+
+impl CaseStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::New => "New",
+            Self::Open => "Open",
+            Self::Pending => "Pending",
+            Self::Resolved => "Resolved",
+            Self::Closed => "Closed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [Self::New, Self::Open, Self::Pending, Self::Resolved, Self::Closed]
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(value.trim()))
+    }
+
+    /// Statuses a case in this status can move to.
+    pub fn transitions(&self) -> &'static [CaseStatus] {
+        match self {
+            Self::New => &[Self::Open, Self::Pending, Self::Resolved],
+            Self::Open => &[Self::Pending, Self::Resolved],
+            Self::Pending => &[Self::Open, Self::Resolved],
+            Self::Resolved => &[Self::Closed, Self::Open],
+            Self::Closed => &[Self::Open],
+        }
+    }
+
+    /// New, open and pending cases still count against their SLA.
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::New | Self::Open | Self::Pending)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CasePriority {
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl CasePriority {
+    pub const ALL: [CasePriority; 4] = [Self::Low, Self::Medium, Self::High, Self::Urgent];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "Low",
+            Self::Medium => "Medium",
+            Self::High => "High",
+            Self::Urgent => "Urgent",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|priority| priority.as_str().eq_ignore_ascii_case(value.trim()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeCaseStatusInput {
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SlaPolicyInput {
+    pub first_response_minutes: i32,
+    pub resolution_minutes: i32,
+    /// User breached cases are reassigned to; without one the current assignee is notified.
+    pub escalate_to: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SlaReportQuery {
+    /// Only cases opened at or after this time.
+    pub from: Option<DateTime<Utc>>,
+    /// Only cases opened before this time.
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// How a set of cases did against one SLA target.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SlaTargetStats {
+    pub met: usize,
+    pub breached: usize,
+    /// Not yet answered or resolved, and still within the target.
+    pub in_progress: usize,
+    /// `met / (met + breached)`; absent when no case has an outcome yet.
+    pub compliance_rate: Option<f64>,
+    /// Average minutes from opening the case to the response or resolution.
+    pub avg_minutes: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SlaPriorityReport {
+    pub priority: CasePriority,
+    pub case_count: usize,
+    /// Cases opened while the priority had no SLA policy.
+    pub without_sla: usize,
+    pub first_response: SlaTargetStats,
+    pub resolution: SlaTargetStats,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SlaReport {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub priorities: Vec<SlaPriorityReport>,
+    pub first_response: SlaTargetStats,
+    pub resolution: SlaTargetStats,
+}
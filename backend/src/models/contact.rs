@@ -1,6 +1,7 @@
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use crate::entities::contact;
 use crate::models::address::{AddressJson};
 use crate::models::Validate;
@@ -25,6 +26,7 @@ pub struct Contact {
     pub shipping_address: Option<AddressJson>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub properties: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -42,6 +44,7 @@ pub struct CreateContactInput {
     pub facebook: Option<String>,
     pub billing_address: Option<AddressJson>,
     pub shipping_address: Option<AddressJson>,
+    pub properties: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -59,6 +62,7 @@ pub struct UpdateContactInput {
     pub facebook: Option<String>,
     pub billing_address: Option<AddressJson>,
     pub shipping_address: Option<AddressJson>,
+    pub properties: Option<Value>,
 }
 
 impl From<contact::Model> for Contact {
@@ -80,6 +84,7 @@ impl From<contact::Model> for Contact {
             shipping_address: model.shipping_address,
             created_at: model.created_at,
             updated_at: model.updated_at,
+            properties: model.properties,
         }
     }
 }
@@ -0,0 +1,208 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::entities::custom_field_definition;
+
+/// Records whose `properties` can carry custom fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomFieldEntity {
+    Lead,
+    Deal,
+    Customer,
+    Contact,
+    Case,
+    Profile,
+}
+
+impl CustomFieldEntity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lead => "lead",
+            Self::Deal => "deal",
+            Self::Customer => "customer",
+            Self::Contact => "contact",
+            Self::Case => "case",
+            Self::Profile => "profile",
+        }
+    }
+
+    /// Accepts the singular or plural name in any case, so `/api/custom-fields/leads` works too.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.to_ascii_lowercase();
+        let value = value.strip_suffix('s').unwrap_or(&value);
+        [Self::Lead, Self::Deal, Self::Customer, Self::Contact, Self::Case, Self::Profile]
+            .into_iter()
+            .find(|entity| entity.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomFieldType {
+    Text,
+    Textarea,
+    Email,
+    Number,
+    Boolean,
+    /// An ISO 8601 date, `YYYY-MM-DD`.
+    Date,
+    /// One of the definition's options.
+    Select,
+}
+
+impl CustomFieldType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Textarea => "textarea",
+            Self::Email => "email",
+            Self::Number => "number",
+            Self::Boolean => "boolean",
+            Self::Date => "date",
+            Self::Select => "select",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [Self::Text, Self::Textarea, Self::Email, Self::Number, Self::Boolean, Self::Date, Self::Select]
+            .into_iter()
+            .find(|field_type| field_type.as_str().eq_ignore_ascii_case(value))
+    }
+
+    /// Input type of the platform-admin `DynamicForm`, which has no date picker.
+    pub fn form_type(&self) -> &'static str {
+        match self {
+            Self::Text | Self::Date => "text",
+            Self::Textarea => "textarea",
+            Self::Email => "email",
+            Self::Number => "number",
+            Self::Boolean => "checkbox",
+            Self::Select => "select",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCustomFieldInput {
+    /// Key in `properties`: lowercase letters, digits and underscores.
+    pub key: String,
+    pub label: String,
+    pub field_type: String,
+    #[serde(default)]
+    pub required: bool,
+    /// Allowed values; required for select fields.
+    pub options: Option<Vec<String>>,
+    /// Filled in when a record is created without the field.
+    pub default_value: Option<Value>,
+    /// Display order; defaults to after the existing fields.
+    pub position: Option<i32>,
+}
+
+/// Replaces a definition's attributes. The key and type can't change once records may be
+/// using them.
+#[derive(Debug, Deserialize)]
+pub struct UpdateCustomFieldInput {
+    pub label: String,
+    #[serde(default)]
+    pub required: bool,
+    pub options: Option<Vec<String>>,
+    pub default_value: Option<Value>,
+    /// Keeps the current position when omitted.
+    pub position: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomFieldModel {
+    pub id: Uuid,
+    pub entity_type: String,
+    pub key: String,
+    pub label: String,
+    pub field_type: String,
+    pub required: bool,
+    pub options: Vec<String>,
+    pub default_value: Option<Value>,
+    pub position: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<custom_field_definition::Model> for CustomFieldModel {
+    fn from(field: custom_field_definition::Model) -> Self {
+        Self {
+            id: field.id,
+            entity_type: field.entity_type,
+            key: field.key,
+            label: field.label,
+            field_type: field.field_type,
+            required: field.required,
+            options: options_of(&field.options),
+            default_value: field.default_value,
+            position: field.position,
+            created_at: field.created_at,
+            updated_at: field.updated_at,
+        }
+    }
+}
+
+/// Reads the stored JSON array of a select field's options.
+pub fn options_of(options: &Option<Value>) -> Vec<String> {
+    options
+        .as_ref()
+        .and_then(Value::as_array)
+        .map(|values| values.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FormFieldOption {
+    pub label: String,
+    pub value: String,
+}
+
+/// A field in the shape of the platform-admin `DynamicField`
+/// (apps/platform-admin/src/components/dynamic_form.rs), ready for its `DynamicForm`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FormField {
+    pub id: String,
+    pub name: String,
+    pub label: String,
+    pub field_type: String,
+    pub required: bool,
+    pub placeholder: Option<String>,
+    pub default_value: Option<String>,
+    pub options: Option<Vec<FormFieldOption>>,
+}
+
+impl From<&CustomFieldModel> for FormField {
+    fn from(field: &CustomFieldModel) -> Self {
+        let field_type = CustomFieldType::parse(&field.field_type).unwrap_or(CustomFieldType::Text);
+        Self {
+            id: field.key.clone(),
+            name: field.key.clone(),
+            label: field.label.clone(),
+            field_type: field_type.form_type().to_string(),
+            required: field.required,
+            placeholder: (field_type == CustomFieldType::Date).then(|| "YYYY-MM-DD".to_string()),
+            default_value: field.default_value.as_ref().map(|value| match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            }),
+            options: (field_type == CustomFieldType::Select).then(|| {
+                field
+                    .options
+                    .iter()
+                    .map(|option| FormFieldOption { label: option.clone(), value: option.clone() })
+                    .collect()
+            }),
+        }
+    }
+}
+
+/// The custom fields of one entity type, in display order.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomFieldSchema {
+    pub entity_type: String,
+    pub fields: Vec<CustomFieldModel>,
+    pub form: Vec<FormField>,
+}
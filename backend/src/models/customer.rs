@@ -1,6 +1,7 @@
 use crate::entities::customer::{Model as CustomerEntity, CustomerAttributes};
 use crate::models::address::AddressJson;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use crate::models::Validate;
@@ -31,6 +32,7 @@ pub struct Customer {
     pub billing_address: Option<AddressJson>,
     #[validate(nested)]
     pub shipping_address: Option<AddressJson>,
+    pub properties: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -53,6 +55,7 @@ pub struct CreateCustomerInput {
     pub employee_count: Option<i32>,
     pub billing_address: Option<AddressJson>,
     pub shipping_address: Option<AddressJson>,
+    pub properties: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -77,6 +80,7 @@ pub struct UpdateCustomerInput {
     pub billing_address: Option<AddressJson>,
     pub shipping_address: Option<AddressJson>,
     pub primary_contact_id: Option<Uuid>,
+    pub properties: Option<Value>,
 }
 
 impl From<CustomerEntity> for Customer {
@@ -104,6 +108,7 @@ impl From<CustomerEntity> for Customer {
             updated_at: entity.updated_at,
             billing_address: entity.billing_address,
             shipping_address: entity.shipping_address,
+            properties: entity.properties,
         }
     }
 }
@@ -1,8 +1,11 @@
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
-use crate::entities::deal;
+use serde_json::Value;
+use crate::entities::{deal, deal_pipeline, deal_pipeline_stage, deal_stage_history};
 use crate::models::file::FileModel;
+use crate::services::deal_pipelines::{OUTCOME_LOST, OUTCOME_OPEN, OUTCOME_WON};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DealModel {
     pub id: Uuid,
@@ -15,6 +18,10 @@ pub struct DealModel {
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub pipeline_id: Option<Uuid>,
+    pub stage_id: Option<Uuid>,
+    pub stage_entered_at: Option<DateTime<Utc>>,
+    pub properties: Option<Value>,
     pub files: Vec<FileModel>,
 }
 
@@ -23,19 +30,23 @@ pub struct CreateDealInput {
     pub customer_id: Uuid,
     pub name: String,
     pub amount: f64,
-    pub status: String,
-    pub stage: String,
+    /// Stage name within the pipeline; defaults to its first open stage.
+    pub stage: Option<String>,
+    /// Defaults to the tenant's default pipeline.
+    pub pipeline_id: Option<Uuid>,
     pub close_date: Option<DateTime<Utc>>,
+    pub properties: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateDealInput {
     pub name: Option<String>,
     pub amount: Option<f64>,
-    pub status: Option<String>,
+    /// Moves the deal within its pipeline, validated like `POST /api/deals/{id}/stage`.
     pub stage: Option<String>,
     pub close_date: Option<DateTime<Utc>>,
     pub is_active: Option<bool>,
+    pub properties: Option<Value>,
 }
 
 impl From<deal::Model> for DealModel {
@@ -51,7 +62,197 @@ impl From<deal::Model> for DealModel {
             is_active: deal.is_active,
             created_at: deal.created_at,
             updated_at: deal.updated_at,
+            pipeline_id: deal.pipeline_id,
+            stage_id: deal.stage_id,
+            stage_entered_at: deal.stage_entered_at,
+            properties: deal.properties,
             files: vec![], // This will be populated when needed
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PipelineStageModel {
+    pub id: Uuid,
+    pub name: String,
+    pub position: i32,
+    pub probability: i32,
+    pub outcome: String,
+}
+
+impl From<deal_pipeline_stage::Model> for PipelineStageModel {
+    fn from(stage: deal_pipeline_stage::Model) -> Self {
+        Self {
+            id: stage.id,
+            name: stage.name,
+            position: stage.position,
+            probability: stage.probability,
+            outcome: stage.outcome,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PipelineModel {
+    pub id: Uuid,
+    pub name: String,
+    pub is_default: bool,
+    pub stages: Vec<PipelineStageModel>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PipelineModel {
+    pub fn new(pipeline: deal_pipeline::Model, stages: Vec<deal_pipeline_stage::Model>) -> Self {
+        Self {
+            id: pipeline.id,
+            name: pipeline.name,
+            is_default: pipeline.is_default,
+            stages: stages.into_iter().map(PipelineStageModel::from).collect(),
+            created_at: pipeline.created_at,
+            updated_at: pipeline.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PipelineStageInput {
+    /// Id of an existing stage when updating a pipeline; omitted for new stages.
+    pub id: Option<Uuid>,
+    pub name: String,
+    /// Required for open stages; won and lost stages default to 100 and 0.
+    pub probability: Option<i32>,
+    /// "open" (default), "won" or "lost".
+    pub outcome: Option<String>,
+}
+
+impl PipelineStageInput {
+    pub fn outcome(&self) -> &str {
+        self.outcome.as_deref().unwrap_or(OUTCOME_OPEN)
+    }
+
+    pub fn probability(&self) -> Option<i32> {
+        self.probability.or(match self.outcome() {
+            OUTCOME_WON => Some(100),
+            OUTCOME_LOST => Some(0),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePipelineInput {
+    pub name: String,
+    #[serde(default)]
+    pub is_default: bool,
+    /// In pipeline order.
+    pub stages: Vec<PipelineStageInput>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePipelineInput {
+    pub name: Option<String>,
+    /// Only `true` is meaningful; make another pipeline the default to change it.
+    pub is_default: Option<bool>,
+    /// Replaces the stage list, in pipeline order.
+    pub stages: Option<Vec<PipelineStageInput>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeStageInput {
+    /// Target stage by id or by name.
+    pub stage_id: Option<Uuid>,
+    pub stage: Option<String>,
+    /// Moves the deal to another pipeline; the stage has to belong to it.
+    pub pipeline_id: Option<Uuid>,
+    /// Required to move a won or lost deal back into the pipeline.
+    #[serde(default)]
+    pub reopen: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StageHistoryModel {
+    pub id: Uuid,
+    pub pipeline_id: Option<Uuid>,
+    pub from_stage_id: Option<Uuid>,
+    pub to_stage_id: Option<Uuid>,
+    pub from_stage: Option<String>,
+    pub to_stage: String,
+    pub amount: f64,
+    pub changed_by: Option<Uuid>,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl From<deal_stage_history::Model> for StageHistoryModel {
+    fn from(entry: deal_stage_history::Model) -> Self {
+        Self {
+            id: entry.id,
+            pipeline_id: entry.pipeline_id,
+            from_stage_id: entry.from_stage_id,
+            to_stage_id: entry.to_stage_id,
+            from_stage: entry.from_stage,
+            to_stage: entry.to_stage,
+            amount: entry.amount,
+            changed_by: entry.changed_by,
+            changed_at: entry.changed_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DealReportQuery {
+    /// Defaults to the tenant's default pipeline.
+    pub pipeline_id: Option<Uuid>,
+    /// Forecast only: limits deals to those expected to close in this window.
+    pub close_from: Option<DateTime<Utc>>,
+    pub close_to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForecastStage {
+    pub stage_id: Uuid,
+    pub name: String,
+    pub probability: i32,
+    pub deal_count: usize,
+    pub amount: f64,
+    /// `amount` times the stage's win probability.
+    pub weighted_amount: f64,
+}
+
+/// Open deals in a pipeline; won and lost stages are left out.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForecastReport {
+    pub pipeline_id: Uuid,
+    pub pipeline: String,
+    pub stages: Vec<ForecastStage>,
+    pub deal_count: usize,
+    pub total_amount: f64,
+    pub weighted_amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VelocityStage {
+    pub stage_id: Uuid,
+    pub name: String,
+    pub outcome: String,
+    /// Times a deal entered the stage.
+    pub entered_count: usize,
+    /// Average days deals spent in the stage before leaving it.
+    pub avg_days_in_stage: Option<f64>,
+    /// Active deals in the stage now, and how long they have been there.
+    pub current_count: usize,
+    pub avg_current_age_days: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VelocityReport {
+    pub pipeline_id: Uuid,
+    pub pipeline: String,
+    pub stages: Vec<VelocityStage>,
+    pub won_count: usize,
+    pub lost_count: usize,
+    /// Won deals out of all closed ones.
+    pub win_rate: Option<f64>,
+    /// Average days from a deal's first stage to its win.
+    pub avg_days_to_win: Option<f64>,
+}
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
+use serde_json::Value;
 use crate::entities::lead;
 use crate::models::address::AddressJson;
 
@@ -30,6 +31,7 @@ pub struct LeadModel {
     pub converted_contact_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub properties: Option<Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -50,6 +52,8 @@ pub struct CreateLeadInput {
     pub shipping_address: Option<AddressJson>,
     pub message: Option<String>,
     pub source: Option<String>,
+    /// Custom field values, checked against the tenant's lead fields.
+    pub properties: Option<Value>,
     pub _bot_check: Option<String>,
 }
 
@@ -76,6 +80,8 @@ pub struct UpdateLeadInput {
     pub associated_deal_id: Option<Uuid>,
     pub converted_customer_id: Option<Uuid>,
     pub converted_contact_id: Option<Uuid>,
+    /// Replaces the lead's custom field values.
+    pub properties: Option<Value>,
 }
 
 impl From<lead::Model> for LeadModel {
@@ -105,6 +111,59 @@ impl From<lead::Model> for LeadModel {
             converted_contact_id: lead.converted_contact_id,
             created_at: lead.created_at,
             updated_at: lead.updated_at,
+            properties: lead.properties,
         }
     }
 }
+
+/// Body of `POST /api/leads/{id}/convert`. Every field is optional.
+#[derive(Debug, Default, Deserialize)]
+pub struct ConvertLeadInput {
+    /// Attach the lead to this customer instead of matching or creating one.
+    pub customer_id: Option<Uuid>,
+    /// Attach the lead to this contact instead of matching or creating one.
+    pub contact_id: Option<Uuid>,
+    /// Type of a newly created customer; defaults to `Person`.
+    pub customer_type: Option<String>,
+    /// Opens a deal for the customer when present.
+    pub deal: Option<ConvertLeadDealInput>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConvertLeadDealInput {
+    /// Defaults to the lead's name.
+    pub name: Option<String>,
+    #[serde(default)]
+    pub amount: f64,
+    /// Defaults to the tenant's default pipeline.
+    pub pipeline_id: Option<Uuid>,
+    /// Defaults to the pipeline's first open stage.
+    pub stage: Option<String>,
+    pub close_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversionOutcome {
+    Created,
+    Matched,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConvertedRecord {
+    pub id: Uuid,
+    pub outcome: ConversionOutcome,
+    /// Fields of a matched record that were empty and have been filled from the lead.
+    pub filled: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LeadConversionResult {
+    pub lead: LeadModel,
+    pub customer: ConvertedRecord,
+    pub contact: ConvertedRecord,
+    pub deal_id: Option<Uuid>,
+    pub notes_moved: u64,
+    pub activities_moved: u64,
+    pub files_moved: u64,
+}
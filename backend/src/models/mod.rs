@@ -19,6 +19,7 @@ pub mod deal;
 pub mod customer;
 pub mod contact;
 pub mod case;
+pub mod custom_field;
 pub mod activity;
 pub mod note;
 pub mod file;
@@ -8,7 +8,7 @@ use crate::models::file::FileModel;
 pub struct NoteModel {
     pub id: Uuid,
     pub content: String,
-    pub created_by: Uuid,
+    pub created_by: Option<Uuid>,
     pub entity_type: String,
     pub entity_id: Uuid,
     pub created_at: DateTime<Utc>,
@@ -1,5 +1,6 @@
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
+use serde_json::Value;
 use crate::entities::profile;
 
 #[derive(Debug, Deserialize)]
@@ -15,6 +16,7 @@ pub struct CreateProfileInput {
     pub display_name: String,
     pub contact_info: String,
     pub business_details: Option<profile::BusinessDetails>,
+    pub properties: Option<Value>,
 }
 
 #[derive(Serialize)]
@@ -32,4 +34,5 @@ pub struct UpdateProfileInput {
     pub display_name: Option<String>,
     pub contact_info: Option<String>,
     pub business_details: Option<profile::BusinessDetails>,
+    pub properties: Option<Value>,
 }
\ No newline at end of file
@@ -0,0 +1,375 @@
+use chrono::{DateTime, Months, TimeDelta, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::entities::activity::{self, ActivityStatus, ActivityType};
+use crate::entities::{account, user, user_account};
+use crate::models::activity::{Recurrence, RecurrenceFrequency};
+use crate::services::job_scheduler::JobScheduler;
+use crate::services::mailer::{self, escape_html};
+use crate::traits::atlas_app::BackgroundJob;
+
+pub const REMINDER_JOB_TYPE: &str = "ActivityReminders";
+
+/// How far ahead of its due date an activity counts as upcoming, unless the job config says
+/// otherwise.
+const DEFAULT_UPCOMING_MINUTES: i64 = 60;
+
+/// Activity types the reminder job notifies about.
+const REMINDED_TYPES: [ActivityType; 2] = [ActivityType::Task, ActivityType::Reminder];
+
+#[derive(Debug)]
+pub enum TaskError {
+    /// The activity is already completed or cancelled.
+    Closed,
+    Invalid(String),
+    Db(DbErr),
+}
+
+impl std::fmt::Display for TaskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Closed => write!(f, "Activity is already completed or cancelled"),
+            Self::Invalid(msg) => write!(f, "{}", msg),
+            Self::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for TaskError {}
+
+impl From<DbErr> for TaskError {
+    fn from(e: DbErr) -> Self {
+        Self::Db(e)
+    }
+}
+
+/// What one reminder run did.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReminderReport {
+    /// Digest emails sent, one per assignee.
+    pub emails: usize,
+    pub overdue: usize,
+    pub upcoming: usize,
+    /// Assignees whose digest couldn't be sent; they are retried on the next run.
+    pub failed: usize,
+}
+
+pub fn is_open(activity: &activity::Model) -> bool {
+    matches!(activity.status, ActivityStatus::Pending | ActivityStatus::InProgress)
+}
+
+/// Longest recurrence period, in days, weeks or months, an activity may repeat on.
+pub const MAX_RECURRENCE_INTERVAL: i32 = 365;
+
+/// The due date one recurrence period after `due`, or `None` past the last representable date.
+pub fn next_due(due: DateTime<Utc>, recurrence: &Recurrence) -> Option<DateTime<Utc>> {
+    let interval = recurrence.interval.clamp(1, MAX_RECURRENCE_INTERVAL);
+    match recurrence.frequency {
+        RecurrenceFrequency::Daily => due.checked_add_signed(TimeDelta::try_days(interval as i64)?),
+        RecurrenceFrequency::Weekly => due.checked_add_signed(TimeDelta::try_weeks(interval as i64)?),
+        RecurrenceFrequency::Monthly => due.checked_add_months(Months::new(interval as u32)),
+    }
+}
+
+/// Checks a recurrence before it's stored on an activity.
+pub fn validate_recurrence(recurrence: &Recurrence, due_date: Option<DateTime<Utc>>) -> Result<(), TaskError> {
+    let Some(due_date) = due_date else {
+        return Err(TaskError::Invalid("a recurring activity needs a due_date".to_string()));
+    };
+    if recurrence.interval < 1 {
+        return Err(TaskError::Invalid("recurrence interval must be at least 1".to_string()));
+    }
+    if recurrence.interval > MAX_RECURRENCE_INTERVAL {
+        return Err(TaskError::Invalid(format!(
+            "recurrence interval can be at most {}",
+            MAX_RECURRENCE_INTERVAL
+        )));
+    }
+    if recurrence.until.is_some_and(|until| until < due_date) {
+        return Err(TaskError::Invalid("recurrence ends before the first due date".to_string()));
+    }
+    if next_due(due_date, recurrence).is_none() {
+        return Err(TaskError::Invalid("due_date is too far in the future to recur".to_string()));
+    }
+    Ok(())
+}
+
+pub struct TaskService;
+
+impl TaskService {
+    /// Emails assignees about their overdue and upcoming tasks and reminders every five minutes.
+    /// `upcoming_minutes` sets how far ahead a due date counts as upcoming.
+    pub fn reminder_job() -> BackgroundJob {
+        BackgroundJob {
+            job_type: REMINDER_JOB_TYPE.to_string(),
+            default_interval_seconds: 300,
+            is_active_by_default: true,
+            default_config_payload: Some(json!({ "upcoming_minutes": DEFAULT_UPCOMING_MINUTES })),
+            executor: Box::new(|db, tenant_id, config| {
+                Box::pin(async move {
+                    let upcoming = config
+                        .as_ref()
+                        .and_then(|config| config.get("upcoming_minutes"))
+                        .and_then(Value::as_i64)
+                        .and_then(TimeDelta::try_minutes)
+                        .unwrap_or(TimeDelta::minutes(DEFAULT_UPCOMING_MINUTES));
+                    Self::notify_due(&db, tenant_id, Utc::now(), upcoming)
+                        .await
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                })
+            }),
+        }
+    }
+
+    /// Checks that `assignee` may be given the tenant's activities: an active member of the
+    /// tenant, or any existing user for platform-level activities without a tenant. Reminders
+    /// go to the assignee by email, so a stranger would learn the task's title.
+    pub async fn ensure_assignable<C: ConnectionTrait>(
+        conn: &C,
+        tenant_id: Option<Uuid>,
+        assignee: Uuid,
+    ) -> Result<(), TaskError> {
+        let eligible = match tenant_id {
+            Some(tenant_id) => {
+                user_account::Entity::find()
+                    .inner_join(account::Entity)
+                    .filter(user_account::Column::UserId.eq(assignee))
+                    .filter(user_account::Column::IsActive.eq(true))
+                    .filter(account::Column::TenantId.eq(tenant_id))
+                    .filter(account::Column::IsActive.eq(true))
+                    .count(conn)
+                    .await?
+                    > 0
+            }
+            None => user::Entity::find_by_id(assignee).one(conn).await?.is_some(),
+        };
+        if !eligible {
+            return Err(TaskError::Invalid(format!("user {} is not an active member of the tenant", assignee)));
+        }
+        Ok(())
+    }
+
+    /// Makes sure the tenant runs the reminder job once the activity has a due date and an
+    /// assignee. Called by the handlers that create and update activities.
+    pub async fn ensure_reminders<C: ConnectionTrait>(conn: &C, activity: &activity::Model) -> Result<(), DbErr> {
+        match (activity.tenant_id, activity.due_date, activity.assigned_to) {
+            (Some(tenant_id), Some(_), Some(_)) => {
+                JobScheduler::provision(conn, tenant_id, &[Self::reminder_job()]).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sends each assignee one digest of their open tasks and reminders that became overdue, or
+    /// fall due within `upcoming`, since they were last told. Each due date is announced once
+    /// as upcoming and once as overdue; snoozing or rescheduling starts over.
+    pub async fn notify_due(
+        db: &DatabaseConnection,
+        tenant_id: Uuid,
+        now: DateTime<Utc>,
+        upcoming: TimeDelta,
+    ) -> Result<ReminderReport, DbErr> {
+        let overdue = Condition::all()
+            .add(activity::Column::DueDate.lte(now))
+            .add(activity::Column::OverdueNotifiedAt.is_null());
+        let soon = Condition::all()
+            .add(activity::Column::DueDate.gt(now))
+            .add(activity::Column::DueDate.lte(now.checked_add_signed(upcoming).unwrap_or(DateTime::<Utc>::MAX_UTC)))
+            .add(activity::Column::UpcomingNotifiedAt.is_null());
+        let due = activity::Entity::find()
+            .filter(activity::Column::TenantId.eq(tenant_id))
+            .filter(activity::Column::Status.is_in([ActivityStatus::Pending, ActivityStatus::InProgress]))
+            .filter(activity::Column::ActivityType.is_in(REMINDED_TYPES))
+            .filter(activity::Column::AssignedTo.is_not_null())
+            .filter(Condition::any().add(overdue).add(soon))
+            .order_by_asc(activity::Column::DueDate)
+            .all(db)
+            .await?;
+
+        let mut by_assignee: BTreeMap<Uuid, Vec<activity::Model>> = BTreeMap::new();
+        for activity in due {
+            if let Some(assignee) = activity.assigned_to {
+                by_assignee.entry(assignee).or_default().push(activity);
+            }
+        }
+        // Assignees who aren't, or are no longer, members of the tenant aren't mailed its tasks
+        let members: Vec<Uuid> = user_account::Entity::find()
+            .select_only()
+            .column(user_account::Column::UserId)
+            .inner_join(account::Entity)
+            .filter(user_account::Column::UserId.is_in(by_assignee.keys().copied()))
+            .filter(user_account::Column::IsActive.eq(true))
+            .filter(account::Column::TenantId.eq(tenant_id))
+            .filter(account::Column::IsActive.eq(true))
+            .into_tuple()
+            .all(db)
+            .await?;
+        let users: BTreeMap<Uuid, user::Model> = user::Entity::find()
+            .filter(user::Column::Id.is_in(members))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|user| (user.id, user))
+            .collect();
+
+        let mut report = ReminderReport::default();
+        for (assignee, activities) in by_assignee {
+            let Some(user) = users.get(&assignee) else {
+                continue;
+            };
+            let (overdue, upcoming): (Vec<_>, Vec<_>) = activities
+                .into_iter()
+                .partition(|activity| activity.due_date.is_some_and(|due| due <= now));
+
+            let subject = match (overdue.len(), upcoming.len()) {
+                (0, n) => format!("{} task{} due soon", n, if n == 1 { "" } else { "s" }),
+                (n, _) => format!("{} overdue task{}", n, if n == 1 { "" } else { "s" }),
+            };
+            let body = Self::digest_html(user, &overdue, &upcoming);
+            if let Err(e) = mailer::send_tenant_email(db, tenant_id, &user.email, &subject, body).await {
+                tracing::warn!("Could not send task reminders to user {}: {}", user.id, e);
+                report.failed += 1;
+                continue;
+            }
+
+            let overdue_ids: Vec<Uuid> = overdue.iter().map(|activity| activity.id).collect();
+            let upcoming_ids: Vec<Uuid> = upcoming.iter().map(|activity| activity.id).collect();
+            for (column, ids) in [
+                (activity::Column::OverdueNotifiedAt, overdue_ids),
+                (activity::Column::UpcomingNotifiedAt, upcoming_ids),
+            ] {
+                if ids.is_empty() {
+                    continue;
+                }
+                activity::Entity::update_many()
+                    .col_expr(column, Expr::value(now))
+                    .filter(activity::Column::Id.is_in(ids))
+                    .exec(db)
+                    .await?;
+            }
+            report.emails += 1;
+            report.overdue += overdue.len();
+            report.upcoming += upcoming.len();
+        }
+        Ok(report)
+    }
+
+    fn digest_html(user: &user::Model, overdue: &[activity::Model], upcoming: &[activity::Model]) -> String {
+        let list = |activities: &[activity::Model]| {
+            activities
+                .iter()
+                .map(|activity| {
+                    let due = activity
+                        .due_date
+                        .map(|due| due.format("%Y-%m-%d %H:%M UTC").to_string())
+                        .unwrap_or_default();
+                    format!("<li><b>{}</b> &mdash; due {}</li>", escape_html(&activity.title), due)
+                })
+                .collect::<String>()
+        };
+        let mut body = format!("<p>Hi {},</p>", escape_html(&user.first_name));
+        if !overdue.is_empty() {
+            body.push_str(&format!("<p>These are overdue:</p><ul>{}</ul>", list(overdue)));
+        }
+        if !upcoming.is_empty() {
+            body.push_str(&format!("<p>These are due soon:</p><ul>{}</ul>", list(upcoming)));
+        }
+        body
+    }
+
+    /// Pushes an open activity's due date to `until` and re-arms its reminders.
+    pub async fn snooze<C: ConnectionTrait>(
+        conn: &C,
+        activity: activity::Model,
+        until: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<activity::Model, TaskError> {
+        if !is_open(&activity) {
+            return Err(TaskError::Closed);
+        }
+        if until <= now {
+            return Err(TaskError::Invalid("snooze until a time in the future".to_string()));
+        }
+        let snoozes = activity.snooze_count;
+        let mut active: activity::ActiveModel = activity.into();
+        active.due_date = Set(Some(until));
+        active.upcoming_notified_at = Set(None);
+        active.overdue_notified_at = Set(None);
+        active.snooze_count = Set(snoozes + 1);
+        active.updated_at = Set(now);
+        Ok(active.update(conn).await?)
+    }
+
+    /// Completes an open activity. A recurring one gets its next occurrence: the first due date
+    /// in its series after `now`, unless the recurrence has ended.
+    pub async fn complete<C: ConnectionTrait>(
+        conn: &C,
+        activity: activity::Model,
+        now: DateTime<Utc>,
+    ) -> Result<(activity::Model, Option<activity::Model>), TaskError> {
+        if !is_open(&activity) {
+            return Err(TaskError::Closed);
+        }
+        let next = Self::next_occurrence(&activity, now);
+
+        let mut active: activity::ActiveModel = activity.into();
+        active.status = Set(ActivityStatus::Completed);
+        active.completed_at = Set(Some(now));
+        active.updated_at = Set(now);
+        let completed = active.update(conn).await?;
+        let next = match next {
+            Some(next) => Some(next.insert(conn).await?),
+            None => None,
+        };
+        Ok((completed, next))
+    }
+
+    /// The series ends when its next due date would be past the last representable date.
+    fn next_occurrence(activity: &activity::Model, now: DateTime<Utc>) -> Option<activity::ActiveModel> {
+        let recurrence = Recurrence::from_activity(activity)?;
+        let mut due = next_due(activity.due_date?, &recurrence)?;
+        while due <= now {
+            due = next_due(due, &recurrence)?;
+        }
+        if recurrence.until.is_some_and(|until| due > until) {
+            return None;
+        }
+
+        Some(activity::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            account_id: Set(activity.account_id),
+            deal_id: Set(activity.deal_id),
+            customer_id: Set(activity.customer_id),
+            lead_id: Set(activity.lead_id),
+            contact_id: Set(activity.contact_id),
+            case_id: Set(activity.case_id),
+            tenant_id: Set(activity.tenant_id),
+            activity_type: Set(activity.activity_type.clone()),
+            title: Set(activity.title.clone()),
+            description: Set(activity.description.clone()),
+            status: Set(ActivityStatus::Pending),
+            due_date: Set(Some(due)),
+            completed_at: Set(None),
+            associated_entities: Set(activity.associated_entities.clone()),
+            created_by: Set(activity.created_by),
+            assigned_to: Set(activity.assigned_to),
+            created_at: Set(now),
+            updated_at: Set(now),
+            recurrence: Set(activity.recurrence.clone()),
+            recurrence_interval: Set(activity.recurrence_interval),
+            recurrence_ends_at: Set(activity.recurrence_ends_at),
+            series_id: Set(Some(activity.series_id.unwrap_or(activity.id))),
+            snooze_count: Set(0),
+            upcoming_notified_at: Set(None),
+            overdue_notified_at: Set(None),
+        })
+    }
+}
@@ -0,0 +1,137 @@
+use crate::entities::app_instance;
+use crate::services::dns::PROVIDER_NAMES;
+use crate::services::telemetry::TelemetryService;
+use crate::traits::atlas_app::AtlasApp;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DatabaseConnection, DbErr, ModelTrait, TransactionTrait};
+use serde_json::{json, Value};
+
+pub struct AppLifecycle;
+
+impl AppLifecycle {
+    /// Checks settings against the app's `settings_schema`. The platform-level
+    /// `dns_provider` key is valid for every app and is checked separately.
+    pub fn validate_settings(app: &dyn AtlasApp, settings: Option<&Value>) -> Result<(), String> {
+        let mut settings = settings.cloned();
+        if let Some(provider) = settings.as_mut().and_then(Value::as_object_mut).and_then(|s| s.remove("dns_provider")) {
+            match provider.as_str() {
+                Some(name) if PROVIDER_NAMES.contains(&name) => {}
+                _ => {
+                    return Err(format!(
+                        "settings.dns_provider must be one of {}",
+                        PROVIDER_NAMES.join(", ")
+                    ))
+                }
+            }
+        }
+        match (app.settings_schema(), settings) {
+            (Some(schema), Some(settings)) => validate_schema(&schema, &settings, "settings"),
+            _ => Ok(()),
+        }
+    }
+
+    /// Inserts an app instance and runs the app's install hook in one transaction. Inside a
+    /// caller's transaction this is a savepoint, so later steps can still roll the install back.
+    pub async fn install<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        app: &dyn AtlasApp,
+        instance: app_instance::ActiveModel,
+    ) -> Result<app_instance::Model, DbErr> {
+        let txn = db.begin().await?;
+        let instance = instance.insert(&txn).await?;
+        app.on_tenant_installed(&txn, &instance).await?;
+        TelemetryService::log_event(
+            &txn,
+            instance.tenant_id,
+            app.telemetry_namespace(),
+            "app_installed".to_string(),
+            Some(json!({ "app_instance_id": instance.id })),
+        )
+        .await?;
+        txn.commit().await?;
+        Ok(instance)
+    }
+
+    /// Runs the app's uninstall hook and deletes the instance in one transaction.
+    pub async fn uninstall(
+        db: &DatabaseConnection,
+        app: &dyn AtlasApp,
+        instance: app_instance::Model,
+    ) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        app.on_tenant_uninstalled(&txn, &instance).await?;
+        let (tenant_id, instance_id) = (instance.tenant_id, instance.id);
+        instance.delete(&txn).await?;
+        TelemetryService::log_event(
+            &txn,
+            tenant_id,
+            app.telemetry_namespace(),
+            "app_uninstalled".to_string(),
+            Some(json!({ "app_instance_id": instance_id })),
+        )
+        .await?;
+        txn.commit().await
+    }
+}
+
+/// Validates against the subset of JSON Schema apps use for settings: `type`, `enum`,
+/// `properties`, `required`, `additionalProperties` (boolean) and `items`.
+fn validate_schema(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+        if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+            return Err(format!("'{}' must be of type {}", path, types.join(" or ")));
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        return Err(format!("'{}' must be one of {}", path, Value::Array(allowed.clone())));
+    }
+
+    if let Value::Object(fields) = value {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(key) {
+                    return Err(format!("'{}.{}' is required", path, key));
+                }
+            }
+        }
+        for (key, field) in fields {
+            let field_path = format!("{}.{}", path, key);
+            match properties.and_then(|p| p.get(key)) {
+                Some(field_schema) => validate_schema(field_schema, field, &field_path)?,
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    return Err(format!("Unknown field '{}'", field_path));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_schema(item_schema, item, &format!("{}[{}]", path, i))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn has_type(value: &Value, json_type: &str) -> bool {
+    match json_type {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
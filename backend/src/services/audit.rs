@@ -1,54 +1,40 @@
-use crate::entities::audit_log;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use crate::services::outbox::{Outbox, OutboxMessage};
+use sea_orm::{ConnectionTrait, DbErr};
 use serde_json::Value;
 use uuid::Uuid;
 use chrono::Utc;
-use std::sync::Arc;
 
 pub struct AuditService;
 
-impl AuditService {
-    /// Asynchronously logs an action in a non-blocking context (Soft-fail auditing)
-    pub fn log_action(
-        db: DatabaseConnection,
-        tenant_id: Option<Uuid>,
-        actor_id: Option<Uuid>,
-        action_type: String,
-        entity_type: String,
-        entity_id: Uuid,
-        old_state: Option<Value>,
-        new_state: Option<Value>,
-        ip_address: Option<String>,
-    ) {
-        // Spawn a background task to avoid blocking the primary web request
-        tokio::spawn(async move {
-            let active_log = audit_log::ActiveModel {
-                id: Set(Uuid::new_v4()),
-                tenant_id: Set(tenant_id),
-                actor_id: Set(actor_id),
-                action_type: Set(action_type.clone()),
-                entity_type: Set(entity_type.clone()),
-                entity_id: Set(entity_id),
-                old_state: Set(old_state),
-                new_state: Set(new_state),
-                ip_address: Set(ip_address),
-                created_at: Set(Utc::now()),
-            };
+/// One audited change: who did what to which entity, with its state before and after.
+pub struct AuditEntry {
+    pub tenant_id: Option<Uuid>,
+    pub actor_id: Option<Uuid>,
+    pub action_type: String,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub old_state: Option<Value>,
+    pub new_state: Option<Value>,
+    pub ip_address: Option<String>,
+}
 
-            match active_log.insert(&db).await {
-                Ok(_) => {
-                    tracing::debug!(
-                        "Successfully recorded audit log for {} action on {:?}",
-                         action_type, entity_id
-                    );
-                }
-                Err(e) => {
-                    tracing::error!(
-                        "Failed to insert audit log for {} action on {}: {:?}",
-                         action_type, entity_id, e
-                    );
-                }
-            }
-        });
+impl AuditService {
+    /// Records an action in the outbox on `conn`; the relay copies it into `audit_logs`.
+    /// Pass the transaction that performs the audited change so the entry commits or rolls
+    /// back together with it.
+    pub async fn log_action<C: ConnectionTrait>(conn: &C, entry: AuditEntry) -> Result<(), DbErr> {
+        Outbox::enqueue(conn, OutboxMessage::Audit {
+            tenant_id: entry.tenant_id,
+            actor_id: entry.actor_id,
+            action_type: entry.action_type,
+            entity_type: entry.entity_type,
+            entity_id: entry.entity_id,
+            old_state: entry.old_state,
+            new_state: entry.new_state,
+            ip_address: entry.ip_address,
+            occurred_at: Utc::now(),
+        })
+        .await
+        .map(|_| ())
     }
 }
@@ -1,6 +1,6 @@
 use crate::entities::{user, magic_link_token};
-use crate::services::audit::AuditService;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait, Set};
+use crate::services::audit::{AuditEntry, AuditService};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait, Set, TransactionTrait};
 use serde_json::json;
 use uuid::Uuid;
 use chrono::{Duration, Utc};
@@ -43,7 +43,11 @@ impl AuthService {
             created_at: Set(Utc::now()),
         };
 
-        let inserted_token = new_token.insert(db).await.map_err(|e| {
+        let txn = db.begin().await.map_err(|e| {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to generate magic link".to_string())
+        })?;
+        let inserted_token = new_token.insert(&txn).await.map_err(|e| {
             tracing::error!("Failed to create token: {:?}", e);
             (StatusCode::INTERNAL_SERVER_ERROR, "Failed to generate magic link".to_string())
         })?;
@@ -56,16 +60,27 @@ impl AuthService {
 
         // Audit the magic link generation
         AuditService::log_action(
-            db.clone(),
-            None, // Tenant ID unknown at this level without joining user, could expand if needed
-            Some(user.id),
-            "auth.magic_link.created".to_string(),
-            "MagicLinkToken".to_string(),
-            inserted_token.id,
-            None,
-            Some(new_state),
-            None,
-        );
+            &txn,
+            AuditEntry {
+                tenant_id: None, // Tenant ID unknown at this level without joining user, could expand if needed
+                actor_id: Some(user.id),
+                action_type: "auth.magic_link.created".to_string(),
+                entity_type: "MagicLinkToken".to_string(),
+                entity_id: inserted_token.id,
+                old_state: None,
+                new_state: Some(new_state),
+                ip_address: None,
+            },
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to record audit entry: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to generate magic link".to_string())
+        })?;
+        txn.commit().await.map_err(|e| {
+            tracing::error!("Failed to commit transaction: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to generate magic link".to_string())
+        })?;
 
         Ok(inserted_token)
     }
@@ -105,7 +120,11 @@ impl AuthService {
         // Mark token as used
         let mut updated_token: magic_link_token::ActiveModel = token_record.clone().into();
         updated_token.is_used = Set(true);
-        let consumed_token = updated_token.update(db).await.map_err(|e| {
+        let txn = db.begin().await.map_err(|e| {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to consume token".to_string())
+        })?;
+        let consumed_token = updated_token.update(&txn).await.map_err(|e| {
             tracing::error!("Failed to update token: {:?}", e);
             (StatusCode::INTERNAL_SERVER_ERROR, "Failed to consume token".to_string())
         })?;
@@ -114,16 +133,27 @@ impl AuthService {
         let new_state = json!({"is_used": true});
 
         AuditService::log_action(
-            db.clone(),
-            None, // Unknown at this domain level strictly
-            Some(user_record.id),
-            "auth.magic_link.consumed".to_string(),
-            "MagicLinkToken".to_string(),
-            consumed_token.id,
-            Some(old_state),
-            Some(new_state),
-            None,
-        );
+            &txn,
+            AuditEntry {
+                tenant_id: None, // Unknown at this domain level strictly
+                actor_id: Some(user_record.id),
+                action_type: "auth.magic_link.consumed".to_string(),
+                entity_type: "MagicLinkToken".to_string(),
+                entity_id: consumed_token.id,
+                old_state: Some(old_state),
+                new_state: Some(new_state),
+                ip_address: None,
+            },
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to record audit entry: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to consume token".to_string())
+        })?;
+        txn.commit().await.map_err(|e| {
+            tracing::error!("Failed to commit transaction: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to consume token".to_string())
+        })?;
 
         Ok(user_record)
     }
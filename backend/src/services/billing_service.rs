@@ -1,6 +1,6 @@
-use crate::services::audit::AuditService;
+use crate::services::audit::{AuditEntry, AuditService};
 use crate::entities::tenant_subscription;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait, Set};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, QueryFilter, ColumnTrait, Set, TransactionTrait};
 use serde_json::json;
 use uuid::Uuid;
 use chrono::Utc;
@@ -45,7 +45,11 @@ impl BillingService {
         active_sub.status = Set(new_status.to_string());
         active_sub.updated_at = Set(Some(Utc::now().into()));
 
-        let updated_sub = active_sub.update(db).await.map_err(|e| {
+        let txn = db.begin().await.map_err(|e| {
+            tracing::error!("Failed to begin transaction: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update subscription".to_string())
+        })?;
+        let updated_sub = active_sub.update(&txn).await.map_err(|e| {
             tracing::error!("Database update error: {:?}", e);
             (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update subscription".to_string())
         })?;
@@ -56,16 +60,27 @@ impl BillingService {
 
         // Audit the crucial billing status change
         AuditService::log_action(
-            db.clone(),
-            Some(tenant_id),
-            None, // System triggered, or trace actor from axum logic in expanded parameters
-            "billing.subscription.status_changed".to_string(),
-            "TenantSubscription".to_string(),
-            updated_sub.id,
-            Some(old_state),
-            Some(new_state),
-            None,
-        );
+            &txn,
+            AuditEntry {
+                tenant_id: Some(tenant_id),
+                actor_id: None, // System triggered, or trace actor from axum logic in expanded parameters
+                action_type: "billing.subscription.status_changed".to_string(),
+                entity_type: "TenantSubscription".to_string(),
+                entity_id: updated_sub.id,
+                old_state: Some(old_state),
+                new_state: Some(new_state),
+                ip_address: None,
+            },
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to record audit entry: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update subscription".to_string())
+        })?;
+        txn.commit().await.map_err(|e| {
+            tracing::error!("Failed to commit transaction: {:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update subscription".to_string())
+        })?;
 
         Ok(updated_sub)
     }
@@ -0,0 +1,463 @@
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::sea_query::{Expr, SimpleExpr};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QuerySelect, Set, TransactionTrait,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::entities::{account, case, case_sla_policy, user, user_account};
+use crate::models::case::{CasePriority, CaseStatus, SlaPolicyInput, SlaPriorityReport, SlaReport, SlaTargetStats};
+use crate::services::domain_events::{snapshot, DomainEvents};
+use crate::services::event_catalog::PlatformEvent;
+use crate::services::job_scheduler::JobScheduler;
+use crate::services::mailer::{self, escape_html};
+use crate::traits::atlas_app::BackgroundJob;
+
+pub const ESCALATION_JOB_TYPE: &str = "CaseSlaEscalations";
+
+const ACTIVE_STATUSES: [&str; 3] = ["New", "Open", "Pending"];
+
+#[derive(Debug)]
+pub enum SlaError {
+    Invalid(String),
+    /// The status change isn't allowed from the case's current status.
+    Conflict(String),
+    Db(DbErr),
+}
+
+impl std::fmt::Display for SlaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(msg) => write!(f, "Invalid SLA policy: {}", msg),
+            Self::Conflict(msg) => write!(f, "{}", msg),
+            Self::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SlaError {}
+
+impl From<DbErr> for SlaError {
+    fn from(e: DbErr) -> Self {
+        Self::Db(e)
+    }
+}
+
+/// What one escalation run did.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EscalationReport {
+    /// Cases that breached a target since the last run.
+    pub escalated: usize,
+    pub reassigned: usize,
+    pub notified: usize,
+    /// Notifications that couldn't be sent. The case still counts as escalated.
+    pub failed: usize,
+}
+
+fn in_tenant(tenant_id: Option<Uuid>) -> SimpleExpr {
+    match tenant_id {
+        Some(tenant_id) => case_sla_policy::Column::TenantId.eq(tenant_id),
+        None => case_sla_policy::Column::TenantId.is_null(),
+    }
+}
+
+/// The tenant's SLA policies, most urgent priority first.
+pub async fn policies<C: ConnectionTrait>(conn: &C, tenant_id: Option<Uuid>) -> Result<Vec<case_sla_policy::Model>, DbErr> {
+    let mut policies = case_sla_policy::Entity::find()
+        .filter(in_tenant(tenant_id))
+        .all(conn)
+        .await?;
+    policies.sort_by_key(|policy| std::cmp::Reverse(CasePriority::parse(&policy.priority)));
+    Ok(policies)
+}
+
+pub async fn policy_for<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+    priority: CasePriority,
+) -> Result<Option<case_sla_policy::Model>, DbErr> {
+    case_sla_policy::Entity::find()
+        .filter(in_tenant(tenant_id))
+        .filter(case_sla_policy::Column::Priority.eq(priority.as_str()))
+        .one(conn)
+        .await
+}
+
+/// Creates or replaces the policy for `priority`. Existing cases keep the targets they were
+/// opened with.
+pub async fn upsert_policy<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+    priority: CasePriority,
+    input: SlaPolicyInput,
+    now: DateTime<Utc>,
+) -> Result<case_sla_policy::Model, SlaError> {
+    if input.first_response_minutes < 1 || input.resolution_minutes < 1 {
+        return Err(SlaError::Invalid("targets must be at least one minute".to_string()));
+    }
+    if input.resolution_minutes < input.first_response_minutes {
+        return Err(SlaError::Invalid("resolution target is shorter than the first response target".to_string()));
+    }
+    if let Some(escalate_to) = input.escalate_to {
+        // Escalations go to someone who can act on the tenant's cases
+        let eligible = match tenant_id {
+            Some(tenant_id) => {
+                user_account::Entity::find()
+                    .inner_join(account::Entity)
+                    .filter(user_account::Column::UserId.eq(escalate_to))
+                    .filter(user_account::Column::IsActive.eq(true))
+                    .filter(account::Column::TenantId.eq(tenant_id))
+                    .filter(account::Column::IsActive.eq(true))
+                    .count(conn)
+                    .await?
+                    > 0
+            }
+            None => user::Entity::find_by_id(escalate_to).one(conn).await?.is_some(),
+        };
+        if !eligible {
+            return Err(SlaError::Invalid(format!(
+                "escalation user {} is not an active member of the tenant",
+                escalate_to
+            )));
+        }
+    }
+
+    let policy = match policy_for(conn, tenant_id, priority).await? {
+        Some(existing) => {
+            let mut policy: case_sla_policy::ActiveModel = existing.into();
+            policy.first_response_minutes = Set(input.first_response_minutes);
+            policy.resolution_minutes = Set(input.resolution_minutes);
+            policy.escalate_to = Set(input.escalate_to);
+            policy.updated_at = Set(now);
+            policy.update(conn).await?
+        }
+        None => {
+            case_sla_policy::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                tenant_id: Set(tenant_id),
+                priority: Set(priority.as_str().to_string()),
+                first_response_minutes: Set(input.first_response_minutes),
+                resolution_minutes: Set(input.resolution_minutes),
+                escalate_to: Set(input.escalate_to),
+                created_at: Set(now),
+                updated_at: Set(now),
+            }
+            .insert(conn)
+            .await?
+        }
+    };
+    if let Some(tenant_id) = tenant_id {
+        ensure_escalations(conn, tenant_id).await?;
+    }
+    Ok(policy)
+}
+
+/// Sets a case's response and resolution deadlines from the policy, counted from `opened_at`.
+/// Without a policy the case has no SLA.
+pub fn apply_targets(case: &mut case::ActiveModel, opened_at: DateTime<Utc>, policy: Option<&case_sla_policy::Model>) {
+    case.first_response_due_at =
+        Set(policy.map(|policy| opened_at + Duration::minutes(policy.first_response_minutes as i64)));
+    case.resolution_due_at = Set(policy.map(|policy| opened_at + Duration::minutes(policy.resolution_minutes as i64)));
+}
+
+/// Recomputes a case's targets after its priority changed. The response target counts from
+/// when the case was opened and the resolution target from when it was last reopened. A target
+/// that moves is escalated again if the case misses it.
+pub fn retarget(case: &mut case::ActiveModel, policy: Option<&case_sla_policy::Model>) {
+    let opened_at = *case.created_at.as_ref();
+    let resumed_at = case.reopened_at.as_ref().unwrap_or(opened_at);
+    let response_due = policy.map(|policy| opened_at + Duration::minutes(policy.first_response_minutes as i64));
+    let resolution_due = policy.map(|policy| resumed_at + Duration::minutes(policy.resolution_minutes as i64));
+    if *case.first_response_due_at.as_ref() != response_due {
+        case.first_response_due_at = Set(response_due);
+        case.response_escalated_at = Set(None);
+    }
+    if *case.resolution_due_at.as_ref() != resolution_due {
+        case.resolution_due_at = Set(resolution_due);
+        case.resolution_escalated_at = Set(None);
+    }
+}
+
+/// Moves a case to `to` if its current status allows it. Leaving `New` counts as the first
+/// response; reopening a resolved or closed case restarts its resolution target.
+pub fn transition(
+    current: &case::Model,
+    to: CaseStatus,
+    policy: Option<&case_sla_policy::Model>,
+    now: DateTime<Utc>,
+) -> Result<case::ActiveModel, SlaError> {
+    let from = CaseStatus::parse(&current.status).unwrap_or(CaseStatus::Open);
+    if from == to {
+        return Err(SlaError::Conflict(format!("Case is already {}", to.as_str())));
+    }
+    if !from.transitions().contains(&to) {
+        return Err(SlaError::Conflict(format!(
+            "A {} case can't move to {}",
+            from.as_str(),
+            to.as_str()
+        )));
+    }
+
+    let mut case: case::ActiveModel = current.clone().into();
+    case.status = Set(to.as_str().to_string());
+    if from == CaseStatus::New && current.first_responded_at.is_none() {
+        case.first_responded_at = Set(Some(now));
+    }
+    match to {
+        CaseStatus::Resolved => case.resolved_at = Set(Some(now)),
+        CaseStatus::Closed => {
+            case.closed_at = Set(Some(now));
+            case.resolved_at = Set(current.resolved_at.or(Some(now)));
+        }
+        CaseStatus::Open if !from.is_active() => {
+            case.resolved_at = Set(None);
+            case.closed_at = Set(None);
+            case.resolution_escalated_at = Set(None);
+            case.reopened_at = Set(Some(now));
+            case.resolution_due_at =
+                Set(policy.map(|policy| now + Duration::minutes(policy.resolution_minutes as i64)));
+        }
+        _ => {}
+    }
+    case.updated_at = Set(now);
+    Ok(case)
+}
+
+/// Marks the case as answered unless it already was.
+pub async fn record_first_response<C: ConnectionTrait>(conn: &C, case_id: Uuid, now: DateTime<Utc>) -> Result<(), DbErr> {
+    case::Entity::update_many()
+        .col_expr(case::Column::FirstRespondedAt, Expr::value(now))
+        .filter(case::Column::Id.eq(case_id))
+        .filter(case::Column::FirstRespondedAt.is_null())
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
+/// Escalates cases that breached their SLA every five minutes.
+pub fn escalation_job() -> BackgroundJob {
+    BackgroundJob {
+        job_type: ESCALATION_JOB_TYPE.to_string(),
+        default_interval_seconds: 300,
+        is_active_by_default: true,
+        default_config_payload: None,
+        executor: Box::new(|db, tenant_id, _config| {
+            Box::pin(async move {
+                escalate_breached(&db, tenant_id, Utc::now())
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
+        }),
+    }
+}
+
+/// Makes sure the tenant runs the escalation job. Called whenever it sets an SLA policy.
+pub async fn ensure_escalations<C: ConnectionTrait>(conn: &C, tenant_id: Uuid) -> Result<(), DbErr> {
+    JobScheduler::provision(conn, tenant_id, &[escalation_job()]).await
+}
+
+/// Escalates every active case that missed its first response or resolution target since the
+/// last run. When the priority's policy names an escalation user the case is reassigned to
+/// them; either way whoever holds the case is emailed. Each target escalates once; reopening
+/// a case re-arms its resolution target.
+pub async fn escalate_breached(
+    db: &DatabaseConnection,
+    tenant_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<EscalationReport, DbErr> {
+    let escalate_to: HashMap<String, Uuid> = policies(db, Some(tenant_id))
+        .await?
+        .into_iter()
+        .filter_map(|policy| Some((policy.priority, policy.escalate_to?)))
+        .collect();
+    let response_breached = Condition::all()
+        .add(case::Column::FirstRespondedAt.is_null())
+        .add(case::Column::FirstResponseDueAt.lte(now))
+        .add(case::Column::ResponseEscalatedAt.is_null());
+    let resolution_breached = Condition::all()
+        .add(case::Column::ResolutionDueAt.lte(now))
+        .add(case::Column::ResolutionEscalatedAt.is_null());
+    let breached = case::Entity::find()
+        .filter(case::Column::TenantId.eq(tenant_id))
+        .filter(case::Column::Status.is_in(ACTIVE_STATUSES))
+        .filter(Condition::any().add(response_breached).add(resolution_breached))
+        .all(db)
+        .await?;
+
+    let user_ids: Vec<Uuid> = breached
+        .iter()
+        .filter_map(|case| case.assigned_to)
+        .chain(escalate_to.values().copied())
+        .collect();
+    // Holders who aren't, or are no longer, members of the tenant aren't mailed its cases
+    let members: Vec<Uuid> = user_account::Entity::find()
+        .select_only()
+        .column(user_account::Column::UserId)
+        .inner_join(account::Entity)
+        .filter(user_account::Column::UserId.is_in(user_ids))
+        .filter(user_account::Column::IsActive.eq(true))
+        .filter(account::Column::TenantId.eq(tenant_id))
+        .filter(account::Column::IsActive.eq(true))
+        .into_tuple()
+        .all(db)
+        .await?;
+    let users: HashMap<Uuid, user::Model> = user::Entity::find()
+        .filter(user::Column::Id.is_in(members.iter().copied()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|user| (user.id, user))
+        .collect();
+
+    let mut report = EscalationReport::default();
+    for case in breached {
+        let response = case.first_responded_at.is_none()
+            && case.first_response_due_at.is_some_and(|due| due <= now)
+            && case.response_escalated_at.is_none();
+        let resolution = case.resolution_due_at.is_some_and(|due| due <= now) && case.resolution_escalated_at.is_none();
+        let reassign_to = escalate_to
+            .get(&case.priority)
+            .copied()
+            .filter(|user_id| case.assigned_to != Some(*user_id))
+            .filter(|user_id| {
+                // An escalation user who has left the tenant keeps the case with its current holder
+                let member = members.contains(user_id);
+                if !member {
+                    tracing::warn!(
+                        "Not reassigning case {} to escalation user {}: they're no longer a member of the tenant",
+                        case.id,
+                        user_id
+                    );
+                }
+                member
+            });
+
+        let before = snapshot(&case);
+        let mut active: case::ActiveModel = case.clone().into();
+        if response {
+            active.response_escalated_at = Set(Some(now));
+        }
+        if resolution {
+            active.resolution_escalated_at = Set(Some(now));
+        }
+        if let Some(user_id) = reassign_to {
+            active.assigned_to = Set(Some(user_id));
+        }
+        active.updated_at = Set(now);
+
+        let txn = db.begin().await?;
+        let escalated = active.update(&txn).await?;
+        DomainEvents::emit(&txn, PlatformEvent::CaseUpdated, escalated.tenant_id, None, escalated.id, before, snapshot(&escalated))
+            .await?;
+        txn.commit().await?;
+        report.escalated += 1;
+        if reassign_to.is_some() {
+            report.reassigned += 1;
+        }
+
+        let Some(holder) = escalated.assigned_to.and_then(|user_id| users.get(&user_id)) else {
+            tracing::warn!("Case {} breached its SLA but no member of the tenant holds it", escalated.id);
+            continue;
+        };
+        let target = match (response, resolution) {
+            (true, true) => "first response and resolution targets",
+            (true, false) => "first response target",
+            _ => "resolution target",
+        };
+        let body = format!(
+            "<p>Hi {},</p><p>The {} priority case <b>{}</b> has missed its {}{}.</p>",
+            escape_html(&holder.first_name),
+            escape_html(&escalated.priority),
+            escape_html(&escalated.title),
+            target,
+            if reassign_to.is_some() { " and has been reassigned to you" } else { "" },
+        );
+        let subject = format!("SLA breached: {}", escalated.title);
+        match mailer::send_tenant_email(db, tenant_id, &holder.email, &subject, body).await {
+            Ok(_) => report.notified += 1,
+            Err(e) => {
+                tracing::warn!("Could not notify user {} about case {}: {}", holder.id, escalated.id, e);
+                report.failed += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[derive(Default)]
+struct Tally {
+    stats: SlaTargetStats,
+    minutes: Vec<f64>,
+}
+
+impl Tally {
+    fn add(&mut self, opened_at: DateTime<Utc>, due: Option<DateTime<Utc>>, done: Option<DateTime<Utc>>, now: DateTime<Utc>) {
+        if let Some(done) = done {
+            self.minutes.push((done - opened_at).num_seconds().max(0) as f64 / 60.0);
+        }
+        let Some(due) = due else {
+            return;
+        };
+        match done {
+            Some(done) if done <= due => self.stats.met += 1,
+            Some(_) => self.stats.breached += 1,
+            None if now > due => self.stats.breached += 1,
+            None => self.stats.in_progress += 1,
+        }
+    }
+
+    fn finish(mut self) -> SlaTargetStats {
+        let decided = self.stats.met + self.stats.breached;
+        self.stats.compliance_rate = (decided > 0).then(|| self.stats.met as f64 / decided as f64);
+        self.stats.avg_minutes =
+            (!self.minutes.is_empty()).then(|| self.minutes.iter().sum::<f64>() / self.minutes.len() as f64);
+        self.stats
+    }
+}
+
+/// SLA compliance of the given cases per priority and overall. Unanswered or unresolved
+/// cases count as breached once their deadline has passed.
+pub fn compliance(
+    cases: &[case::Model],
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> SlaReport {
+    let (mut total_response, mut total_resolution) = (Tally::default(), Tally::default());
+    let mut priorities = Vec::new();
+    for priority in CasePriority::ALL.into_iter().rev() {
+        let (mut response, mut resolution) = (Tally::default(), Tally::default());
+        let in_priority: Vec<&case::Model> = cases
+            .iter()
+            .filter(|case| CasePriority::parse(&case.priority) == Some(priority))
+            .collect();
+        for case in &in_priority {
+            for tally in [&mut response, &mut total_response] {
+                tally.add(case.created_at, case.first_response_due_at, case.first_responded_at, now);
+            }
+            for tally in [&mut resolution, &mut total_resolution] {
+                tally.add(case.created_at, case.resolution_due_at, case.resolved_at, now);
+            }
+        }
+        priorities.push(SlaPriorityReport {
+            priority,
+            case_count: in_priority.len(),
+            without_sla: in_priority
+                .iter()
+                .filter(|case| case.first_response_due_at.is_none() && case.resolution_due_at.is_none())
+                .count(),
+            first_response: response.finish(),
+            resolution: resolution.finish(),
+        });
+    }
+    SlaReport {
+        from,
+        to,
+        priorities,
+        first_response: total_response.finish(),
+        resolution: total_resolution.finish(),
+    }
+}
@@ -0,0 +1,261 @@
+use crate::entities::{app_domain, app_instance};
+use crate::middleware::site_context::invalidate_domain;
+use crate::services::dns::{self, cname_target, DnsProvider, DnsRecord, DnsResolver, DohResolver, HostnameState};
+use crate::services::job_scheduler::JobScheduler;
+use crate::traits::atlas_app::BackgroundJob;
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use uuid::Uuid;
+
+pub const STATUS_PENDING_VERIFICATION: &str = "pending_verification";
+pub const STATUS_VERIFYING: &str = "verifying";
+pub const STATUS_ACTIVE: &str = "active";
+pub const STATUS_FAILED: &str = "failed";
+
+pub const SYNC_JOB_TYPE: &str = "CustomDomainSync";
+
+/// How long a customer has to publish the ownership record.
+const VERIFICATION_WINDOW_DAYS: i64 = 7;
+
+const CHALLENGE_PREFIX: &str = "_atlas-challenge";
+
+#[derive(Debug)]
+pub enum DomainError {
+    Invalid(String),
+    Provider(dns::DnsError),
+    Db(DbErr),
+}
+
+impl std::fmt::Display for DomainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(msg) => write!(f, "{}", msg),
+            Self::Provider(e) => write!(f, "{}", e),
+            Self::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<DbErr> for DomainError {
+    fn from(e: DbErr) -> Self {
+        Self::Db(e)
+    }
+}
+
+impl From<dns::DnsError> for DomainError {
+    fn from(e: dns::DnsError) -> Self {
+        Self::Provider(e)
+    }
+}
+
+pub struct CustomDomainService;
+
+impl CustomDomainService {
+    /// Lower-cases the name and rejects anything that isn't a plain hostname.
+    pub fn normalize(domain_name: &str) -> Result<String, DomainError> {
+        let domain = domain_name.trim().trim_end_matches('.').to_lowercase();
+        let valid_label = |label: &str| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        };
+        if domain.len() > 253 || !domain.contains('.') || !domain.split('.').all(valid_label) {
+            return Err(DomainError::Invalid(format!("'{}' is not a valid domain name", domain_name)));
+        }
+        Ok(domain)
+    }
+
+    /// The records that prove ownership of a pending domain; either one is enough.
+    pub fn verification_records(domain: &app_domain::Model) -> Vec<DnsRecord> {
+        let Some(token) = &domain.verification_token else {
+            return Vec::new();
+        };
+        vec![
+            DnsRecord {
+                record_type: "TXT".to_string(),
+                name: format!("{}.{}", CHALLENGE_PREFIX, domain.domain_name),
+                value: format!("atlas-verify={}", token),
+            },
+            DnsRecord {
+                record_type: "CNAME".to_string(),
+                name: domain.domain_name.clone(),
+                value: cname_target(),
+            },
+        ]
+    }
+
+    /// Adds a domain awaiting ownership verification and makes sure the tenant runs the
+    /// sync job that advances it. Without an edge provider, which only happens with
+    /// `ENVIRONMENT=development`, the domain is active right away.
+    pub async fn create(
+        db: &DatabaseConnection,
+        instance: &app_instance::Model,
+        domain_name: &str,
+        provider: Option<&dyn DnsProvider>,
+    ) -> Result<app_domain::Model, DomainError> {
+        let domain_name = Self::normalize(domain_name)?;
+        let status = if provider.is_some() { STATUS_PENDING_VERIFICATION } else { STATUS_ACTIVE };
+
+        let txn = db.begin().await?;
+        let domain = app_domain::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            app_instance_id: Set(instance.id),
+            domain_name: Set(domain_name),
+            created_at: Set(Utc::now()),
+            status: Set(status.to_string()),
+            verification_token: Set(Some(Uuid::new_v4().simple().to_string())),
+            verified_at: Set((status == STATUS_ACTIVE).then(Utc::now)),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        JobScheduler::provision(&txn, instance.tenant_id, &[Self::background_job()]).await?;
+        txn.commit().await?;
+
+        // The host may have been cached as unknown
+        invalidate_domain(&domain.domain_name).await;
+        Ok(domain)
+    }
+
+    /// Advances a domain one step through its lifecycle:
+    /// `pending_verification` → `verifying` once ownership is proven and the hostname is
+    /// provisioned, `verifying` → `active` once the edge serves it with a certificate.
+    /// Either step can end in `failed`.
+    pub async fn check(
+        db: &DatabaseConnection,
+        provider: &dyn DnsProvider,
+        resolver: &dyn DnsResolver,
+        domain: app_domain::Model,
+    ) -> Result<app_domain::Model, DomainError> {
+        let now = Utc::now();
+        let mut active: app_domain::ActiveModel = domain.clone().into();
+        active.last_checked_at = Set(Some(now));
+
+        match domain.status.as_str() {
+            STATUS_PENDING_VERIFICATION => {
+                if Self::owns(resolver, &domain).await? {
+                    let hostname_id = provider.provision_hostname(&domain.domain_name).await?;
+                    active.provider_hostname_id = Set(Some(hostname_id));
+                    active.status = Set(STATUS_VERIFYING.to_string());
+                    active.verified_at = Set(Some(now));
+                    active.last_error = Set(None);
+                } else if now - domain.created_at > Duration::days(VERIFICATION_WINDOW_DAYS) {
+                    active.status = Set(STATUS_FAILED.to_string());
+                    active.last_error = Set(Some(format!(
+                        "Ownership was not verified within {} days",
+                        VERIFICATION_WINDOW_DAYS
+                    )));
+                }
+            }
+            STATUS_VERIFYING => {
+                let Some(hostname_id) = domain.provider_hostname_id.as_deref() else {
+                    // Verified but never provisioned; retry from the start
+                    active.status = Set(STATUS_PENDING_VERIFICATION.to_string());
+                    return Ok(active.update(db).await?);
+                };
+                let status = provider.hostname_status(hostname_id).await?;
+                active.ssl_status = Set(status.ssl_status);
+                active.last_error = Set(status.error);
+                match status.state {
+                    HostnameState::Active => active.status = Set(STATUS_ACTIVE.to_string()),
+                    HostnameState::Failed => active.status = Set(STATUS_FAILED.to_string()),
+                    HostnameState::Pending => {}
+                }
+            }
+            _ => {}
+        }
+
+        let updated = active.update(db).await?;
+        if updated.status != domain.status {
+            tracing::info!("Domain {} moved from {} to {}", updated.domain_name, domain.status, updated.status);
+            invalidate_domain(&updated.domain_name).await;
+        }
+        Ok(updated)
+    }
+
+    /// Removes the hostname from the edge, then deletes the domain.
+    pub async fn remove(
+        db: &DatabaseConnection,
+        provider: Option<&dyn DnsProvider>,
+        domain: app_domain::Model,
+    ) -> Result<(), DomainError> {
+        // Never provisioned domains have nothing at the edge
+        if let (Some(provider), true) = (provider, domain.status != STATUS_PENDING_VERIFICATION) {
+            provider
+                .remove_hostname(&domain.domain_name, domain.provider_hostname_id.as_deref())
+                .await?;
+        }
+        app_domain::Entity::delete_by_id(domain.id).exec(db).await?;
+        invalidate_domain(&domain.domain_name).await;
+        Ok(())
+    }
+
+    /// Checks every domain of the tenant that is still on its way to `active`, each
+    /// through its app instance's provider.
+    pub async fn sync_tenant(
+        db: &DatabaseConnection,
+        resolver: &dyn DnsResolver,
+        tenant_id: Uuid,
+    ) -> Result<(), DomainError> {
+        let domains = app_domain::Entity::find()
+            .find_also_related(app_instance::Entity)
+            .filter(app_instance::Column::TenantId.eq(tenant_id))
+            .filter(app_domain::Column::Status.is_in([STATUS_PENDING_VERIFICATION, STATUS_VERIFYING]))
+            .all(db)
+            .await?;
+
+        for (domain, instance) in domains {
+            let Some(instance) = instance else { continue };
+            let domain_name = domain.domain_name.clone();
+            let result = match dns::provider_for_instance(&instance) {
+                Ok(Some(provider)) => Self::check(db, provider.as_ref(), resolver, domain).await.map(|_| ()),
+                Ok(None) => Ok(()),
+                Err(e) => Err(e.into()),
+            };
+            // One misbehaving domain shouldn't hold up the others
+            if let Err(e) = result {
+                tracing::warn!("Failed to check domain {}: {}", domain_name, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Polls the tenant's pending domains.
+    pub fn background_job() -> BackgroundJob {
+        BackgroundJob {
+            job_type: SYNC_JOB_TYPE.to_string(),
+            default_interval_seconds: 60,
+            is_active_by_default: true,
+            default_config_payload: None,
+            executor: Box::new(|db, tenant_id, _config| {
+                Box::pin(async move {
+                    Self::sync_tenant(&db, &DohResolver::from_env(), tenant_id)
+                        .await
+                        .map_err(|e| e.to_string())
+                })
+            }),
+        }
+    }
+
+    async fn owns(resolver: &dyn DnsResolver, domain: &app_domain::Model) -> Result<bool, DomainError> {
+        let Some(token) = &domain.verification_token else {
+            return Ok(false);
+        };
+        let expected = format!("atlas-verify={}", token);
+        let challenge = format!("{}.{}", CHALLENGE_PREFIX, domain.domain_name);
+        if resolver.lookup_txt(&challenge).await?.iter().any(|value| value == &expected) {
+            return Ok(true);
+        }
+
+        let target = cname_target();
+        Ok(resolver
+            .lookup_cname(&domain.domain_name)
+            .await?
+            .iter()
+            .any(|value| value.eq_ignore_ascii_case(&target)))
+    }
+}
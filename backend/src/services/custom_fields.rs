@@ -0,0 +1,399 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::sea_query::extension::postgres::{PgBinOper, PgExpr};
+use sea_orm::sea_query::{Alias, Expr, Func, NullOrdering, SimpleExpr};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, Order, QueryFilter, QueryOrder, Select, Set,
+};
+use serde_json::{Map, Number, Value};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+use validator::ValidateEmail;
+
+use crate::entities::custom_field_definition;
+use crate::models::custom_field::{
+    options_of, CreateCustomFieldInput, CustomFieldEntity, CustomFieldModel, CustomFieldSchema, CustomFieldType,
+    FormField, UpdateCustomFieldInput,
+};
+
+/// Prefix of the list query parameters that filter and sort on custom fields, as in
+/// `?properties.industry=Retail&sort=properties.employees&order=desc`.
+pub const PROPERTY_PARAM_PREFIX: &str = "properties.";
+
+#[derive(Debug)]
+pub enum FieldError {
+    NotFound,
+    Invalid(String),
+    Conflict(String),
+    Db(DbErr),
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Custom field not found"),
+            Self::Invalid(msg) => write!(f, "Invalid custom field: {}", msg),
+            Self::Conflict(msg) => write!(f, "{}", msg),
+            Self::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+impl From<DbErr> for FieldError {
+    fn from(e: DbErr) -> Self {
+        Self::Db(e)
+    }
+}
+
+fn in_tenant(tenant_id: Option<Uuid>) -> SimpleExpr {
+    match tenant_id {
+        Some(tenant_id) => custom_field_definition::Column::TenantId.eq(tenant_id),
+        None => custom_field_definition::Column::TenantId.is_null(),
+    }
+}
+
+/// The tenant's fields for `entity`, in display order.
+pub async fn definitions<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+    entity: CustomFieldEntity,
+) -> Result<Vec<custom_field_definition::Model>, DbErr> {
+    custom_field_definition::Entity::find()
+        .filter(in_tenant(tenant_id))
+        .filter(custom_field_definition::Column::EntityType.eq(entity.as_str()))
+        .order_by_asc(custom_field_definition::Column::Position)
+        .order_by_asc(custom_field_definition::Column::Key)
+        .all(conn)
+        .await
+}
+
+pub async fn schema<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+    entity: CustomFieldEntity,
+) -> Result<CustomFieldSchema, DbErr> {
+    let fields: Vec<CustomFieldModel> = definitions(conn, tenant_id, entity)
+        .await?
+        .into_iter()
+        .map(CustomFieldModel::from)
+        .collect();
+    let form = fields.iter().map(FormField::from).collect();
+    Ok(CustomFieldSchema { entity_type: entity.as_str().to_string(), fields, form })
+}
+
+async fn find<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+    entity: CustomFieldEntity,
+    key: &str,
+) -> Result<Option<custom_field_definition::Model>, DbErr> {
+    custom_field_definition::Entity::find()
+        .filter(in_tenant(tenant_id))
+        .filter(custom_field_definition::Column::EntityType.eq(entity.as_str()))
+        .filter(custom_field_definition::Column::Key.eq(key))
+        .one(conn)
+        .await
+}
+
+fn check_key(key: &str) -> Result<(), FieldError> {
+    let mut chars = key.chars();
+    let valid = key.len() <= 64
+        && chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(FieldError::Invalid(format!(
+            "key {:?} must start with a lowercase letter and contain only lowercase letters, digits and underscores",
+            key
+        )))
+    }
+}
+
+/// Checks a definition's label, options and default, returning the options and default to store.
+fn check_definition(
+    field_type: CustomFieldType,
+    label: &str,
+    options: Option<Vec<String>>,
+    default_value: Option<Value>,
+) -> Result<(Option<Value>, Option<Value>), FieldError> {
+    if label.trim().is_empty() {
+        return Err(FieldError::Invalid("label is required".to_string()));
+    }
+    let options = options.unwrap_or_default();
+    if field_type == CustomFieldType::Select {
+        if options.is_empty() {
+            return Err(FieldError::Invalid("select fields need at least one option".to_string()));
+        }
+        let mut seen = HashSet::new();
+        if let Some(option) = options.iter().find(|option| option.trim().is_empty() || !seen.insert(option.as_str())) {
+            return Err(FieldError::Invalid(format!("option {:?} is blank or repeated", option)));
+        }
+    } else if !options.is_empty() {
+        return Err(FieldError::Invalid("only select fields have options".to_string()));
+    }
+
+    let default_value = match default_value {
+        None | Some(Value::Null) => None,
+        Some(value) => Some(
+            coerce(field_type, &options, value)
+                .map_err(|msg| FieldError::Invalid(format!("default value {}", msg)))?,
+        ),
+    };
+    let options = (field_type == CustomFieldType::Select)
+        .then(|| Value::Array(options.into_iter().map(Value::String).collect()));
+    Ok((options, default_value))
+}
+
+pub async fn create<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+    entity: CustomFieldEntity,
+    input: CreateCustomFieldInput,
+    now: DateTime<Utc>,
+) -> Result<custom_field_definition::Model, FieldError> {
+    check_key(&input.key)?;
+    let field_type = CustomFieldType::parse(&input.field_type)
+        .ok_or_else(|| FieldError::Invalid(format!("unknown field type {:?}", input.field_type)))?;
+    let (options, default_value) = check_definition(field_type, &input.label, input.options, input.default_value)?;
+    if find(conn, tenant_id, entity, &input.key).await?.is_some() {
+        return Err(FieldError::Conflict(format!("{} already has a field {:?}", entity.as_str(), input.key)));
+    }
+    let position = match input.position {
+        Some(position) => position,
+        None => definitions(conn, tenant_id, entity)
+            .await?
+            .last()
+            .map_or(0, |field| field.position + 1),
+    };
+
+    let field = custom_field_definition::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant_id),
+        entity_type: Set(entity.as_str().to_string()),
+        key: Set(input.key),
+        label: Set(input.label.trim().to_string()),
+        field_type: Set(field_type.as_str().to_string()),
+        required: Set(input.required),
+        options: Set(options),
+        default_value: Set(default_value),
+        position: Set(position),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(conn)
+    .await?;
+    Ok(field)
+}
+
+/// Replaces a field's attributes. Records already saved are not revalidated; they are checked
+/// against the new definition the next time their properties change.
+pub async fn update<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+    entity: CustomFieldEntity,
+    key: &str,
+    input: UpdateCustomFieldInput,
+    now: DateTime<Utc>,
+) -> Result<custom_field_definition::Model, FieldError> {
+    let existing = find(conn, tenant_id, entity, key).await?.ok_or(FieldError::NotFound)?;
+    let field_type = CustomFieldType::parse(&existing.field_type).unwrap_or(CustomFieldType::Text);
+    let (options, default_value) = check_definition(field_type, &input.label, input.options, input.default_value)?;
+
+    let mut field: custom_field_definition::ActiveModel = existing.into();
+    field.label = Set(input.label.trim().to_string());
+    field.required = Set(input.required);
+    field.options = Set(options);
+    field.default_value = Set(default_value);
+    if let Some(position) = input.position {
+        field.position = Set(position);
+    }
+    field.updated_at = Set(now);
+    Ok(field.update(conn).await?)
+}
+
+/// Removes the definition. Values already stored under the key are kept.
+pub async fn delete<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+    entity: CustomFieldEntity,
+    key: &str,
+) -> Result<(), FieldError> {
+    let result = custom_field_definition::Entity::delete_many()
+        .filter(in_tenant(tenant_id))
+        .filter(custom_field_definition::Column::EntityType.eq(entity.as_str()))
+        .filter(custom_field_definition::Column::Key.eq(key))
+        .exec(conn)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(FieldError::NotFound);
+    }
+    Ok(())
+}
+
+/// Checks a value against a field's type and returns it in its stored form. Numbers and booleans
+/// sent as strings, as form inputs do, are converted.
+fn coerce(field_type: CustomFieldType, options: &[String], value: Value) -> Result<Value, String> {
+    match (field_type, value) {
+        (CustomFieldType::Number, Value::Number(n)) => Ok(Value::Number(n)),
+        (CustomFieldType::Number, Value::String(s)) => parse_number(s.trim()).ok_or_else(|| "must be a number".to_string()),
+        (CustomFieldType::Number, _) => Err("must be a number".to_string()),
+        (CustomFieldType::Boolean, Value::Bool(b)) => Ok(Value::Bool(b)),
+        (CustomFieldType::Boolean, Value::String(s)) => match s.to_ascii_lowercase().as_str() {
+            "true" | "on" => Ok(Value::Bool(true)),
+            "false" | "off" => Ok(Value::Bool(false)),
+            _ => Err("must be true or false".to_string()),
+        },
+        (CustomFieldType::Boolean, _) => Err("must be true or false".to_string()),
+        (_, Value::String(s)) => {
+            match field_type {
+                CustomFieldType::Email if !s.validate_email() => return Err("must be an email address".to_string()),
+                CustomFieldType::Date if NaiveDate::parse_from_str(&s, "%Y-%m-%d").is_err() => {
+                    return Err("must be a date (YYYY-MM-DD)".to_string())
+                }
+                CustomFieldType::Select if !options.contains(&s) => {
+                    return Err(format!("must be one of {}", options.join(", ")))
+                }
+                _ => {}
+            }
+            Ok(Value::String(s))
+        }
+        (_, _) => Err("must be a string".to_string()),
+    }
+}
+
+/// Integers stay integers so `5` and `"5"` are stored, and matched by filters, the same way.
+fn parse_number(value: &str) -> Option<Value> {
+    if let Ok(n) = value.parse::<i64>() {
+        return Some(Value::Number(n.into()));
+    }
+    value.parse::<f64>().ok().and_then(Number::from_f64).map(Value::Number)
+}
+
+/// Validates a record's properties against the tenant's fields for `entity`.
+///
+/// On create (`is_new`) missing fields get their defaults. Required fields must have a value,
+/// defined fields are type-checked and converted, and null values are dropped. Keys without a
+/// definition are kept as they are. Returns `None` when there is nothing to store.
+pub async fn validate_properties<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+    entity: CustomFieldEntity,
+    properties: Option<Value>,
+    is_new: bool,
+) -> Result<Option<Value>, FieldError> {
+    let mut values = match properties {
+        None | Some(Value::Null) => Map::new(),
+        Some(Value::Object(values)) => values,
+        Some(_) => return Err(FieldError::Invalid("properties must be an object".to_string())),
+    };
+    values.retain(|_, value| !value.is_null());
+
+    let mut problems = Vec::new();
+    for field in definitions(conn, tenant_id, entity).await? {
+        let field_type = CustomFieldType::parse(&field.field_type).unwrap_or(CustomFieldType::Text);
+        let value = match values.remove(&field.key) {
+            Some(value) => Some(value),
+            None if is_new => field.default_value.clone(),
+            None => None,
+        };
+        match value {
+            Some(value) => match coerce(field_type, &options_of(&field.options), value) {
+                Ok(value) => {
+                    values.insert(field.key, value);
+                }
+                Err(msg) => problems.push(format!("{} {}", field.key, msg)),
+            },
+            None if field.required => problems.push(format!("{} is required", field.key)),
+            None => {}
+        }
+    }
+    if !problems.is_empty() {
+        return Err(FieldError::Invalid(problems.join("; ")));
+    }
+    Ok((!values.is_empty()).then_some(Value::Object(values)))
+}
+
+/// Custom field filters and sort order read from a list endpoint's query parameters.
+#[derive(Debug, Default)]
+pub struct PropertyQuery {
+    /// Matched with `properties @> {...}`, which the GIN index on `properties` serves.
+    contains: Map<String, Value>,
+    sort: Option<(String, CustomFieldType, Order)>,
+}
+
+impl PropertyQuery {
+    /// Reads `properties.<key>=<value>` filters and `sort=properties.<key>` with `order=asc|desc`.
+    /// Only defined fields can be filtered or sorted on; filter values are converted like
+    /// property values.
+    pub async fn parse<C: ConnectionTrait>(
+        conn: &C,
+        tenant_id: Option<Uuid>,
+        entity: CustomFieldEntity,
+        params: &HashMap<String, String>,
+    ) -> Result<Self, FieldError> {
+        let sort_key = params.get("sort").and_then(|sort| sort.strip_prefix(PROPERTY_PARAM_PREFIX));
+        let filters: Vec<(&str, &String)> = params
+            .iter()
+            .filter_map(|(param, value)| param.strip_prefix(PROPERTY_PARAM_PREFIX).map(|key| (key, value)))
+            .collect();
+        if sort_key.is_none() && filters.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let fields: HashMap<String, custom_field_definition::Model> = definitions(conn, tenant_id, entity)
+            .await?
+            .into_iter()
+            .map(|field| (field.key.clone(), field))
+            .collect();
+        let field = |key: &str| {
+            fields
+                .get(key)
+                .map(|field| (field, CustomFieldType::parse(&field.field_type).unwrap_or(CustomFieldType::Text)))
+                .ok_or_else(|| FieldError::Invalid(format!("{} has no custom field {:?}", entity.as_str(), key)))
+        };
+
+        let mut query = Self::default();
+        for (key, value) in filters {
+            let (definition, field_type) = field(key)?;
+            let value = coerce(field_type, &options_of(&definition.options), Value::String(value.clone()))
+                .map_err(|msg| FieldError::Invalid(format!("filter on {} {}", key, msg)))?;
+            query.contains.insert(key.to_string(), value);
+        }
+        if let Some(key) = sort_key {
+            let (_, field_type) = field(key)?;
+            let order = match params.get("order").map(|order| order.to_ascii_lowercase()) {
+                None => Order::Asc,
+                Some(order) if order == "asc" => Order::Asc,
+                Some(order) if order == "desc" => Order::Desc,
+                Some(order) => return Err(FieldError::Invalid(format!("unknown sort order {:?}", order))),
+            };
+            query.sort = Some((key.to_string(), field_type, order));
+        }
+        Ok(query)
+    }
+
+    /// Adds the filters and sort order to a select, on the entity's `properties` column.
+    /// Records without a value for the sort field come last.
+    pub fn apply<E: EntityTrait>(&self, mut select: Select<E>, properties: E::Column) -> Select<E> {
+        let column = || Expr::col((E::default(), properties));
+        if !self.contains.is_empty() {
+            let contains = Expr::val(Value::Object(self.contains.clone())).cast_as(Alias::new("jsonb"));
+            select = select.filter(column().binary(PgBinOper::Contains, contains));
+        }
+        if let Some((key, field_type, order)) = &self.sort {
+            let expr: SimpleExpr = match field_type {
+                CustomFieldType::Number => Expr::case(
+                    Expr::expr(Func::cust(Alias::new("jsonb_typeof")).arg(column().get_json_field(key.as_str())))
+                        .eq("number"),
+                    column().cast_json_field(key.as_str()).cast_as(Alias::new("numeric")),
+                )
+                .into(),
+                _ => column().cast_json_field(key.as_str()),
+            };
+            select = select.order_by_with_nulls(expr, order.clone(), NullOrdering::Last);
+        }
+        select
+    }
+}
@@ -1,82 +1,12 @@
-use crate::entities::{bitcoin_block, tenant_background_job};
-use sea_orm::{
-    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, Condition
-};
+use crate::entities::bitcoin_block;
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
 use sea_orm::sea_query::OnConflict;
 use chrono::Utc;
-use std::time::Duration;
-use tracing::{info, error, debug};
 use uuid::Uuid;
 
 pub struct DataSyncService;
 
 impl DataSyncService {
-    pub async fn start_worker(db: DatabaseConnection) {
-        tokio::spawn(async move {
-            info!("Starting DataSyncService background worker.");
-            let mut interval = tokio::time::interval(Duration::from_secs(60)); // Check every minute
-            
-            loop {
-                interval.tick().await;
-                if let Err(e) = Self::process_due_jobs(&db).await {
-                    error!("DataSyncService encountered an error: {}", e);
-                }
-            }
-        });
-    }
-
-    async fn process_due_jobs(db: &DatabaseConnection) -> Result<(), sea_orm::DbErr> {
-        let now = Utc::now();
-        
-        // Find all active jobs that are due for execution
-        // Since SeaORM doesn't natively map complicated Postgres interval math seamlessly across all adapters efficiently,
-        // we fetch active jobs and filter internally unless we write raw SQL. For 100s of configs, mem filtering is perfectly fine.
-        let active_jobs = tenant_background_job::Entity::find()
-            .filter(tenant_background_job::Column::IsActive.eq(true))
-            .all(db)
-            .await?;
-
-        for job in active_jobs {
-            let should_run = match job.last_run {
-                None => true,
-                Some(last) => {
-                    let diff = now.signed_duration_since(last).num_seconds();
-                    diff >= (job.interval_seconds as i64)
-                }
-            };
-
-            if !should_run {
-                continue;
-            }
-
-            debug!("Executing job {} / {} for tenant {}", job.id, job.job_type, job.tenant_id);
-
-            // Dispatch based on dynamic AtlasApp definitions
-            let mut executed = false;
-            for app in crate::atlas_apps::get_active_apps() {
-                if let Some(app_job) = app.background_jobs().into_iter().find(|j| j.job_type == job.job_type) {
-                    // For now we pass None for the config payload since the legacy schema doesn't fully serialize it yet
-                    if let Err(e) = (app_job.executor)(db.clone(), job.tenant_id, None).await {
-                        error!("Failed to execute {} for tenant {}: {}", job.job_type, job.tenant_id, e);
-                    }
-                    executed = true;
-                    break;
-                }
-            }
-
-            if !executed {
-                debug!("Unknown job type: {}", job.job_type);
-            }
-
-            // Update last_run
-            let mut active_model: tenant_background_job::ActiveModel = job.into();
-            active_model.last_run = Set(Some(Utc::now().into()));
-            active_model.update(db).await?;
-        }
-
-        Ok(())
-    }
-
     pub async fn sync_bitcoin_blocks(db: &DatabaseConnection, tenant_id: Uuid, api_url: &str) -> Result<(), anyhow::Error> {
         let client = reqwest::Client::builder().user_agent("AtlasPlatform/1.0").build()?;
         let res = client.get(api_url).send().await?;
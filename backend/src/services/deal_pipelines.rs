@@ -0,0 +1,526 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, DbErr, EntityTrait, QueryFilter, QueryOrder, Set,
+    Statement,
+};
+use sea_orm::sea_query::{Expr, SimpleExpr};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::entities::{deal, deal_pipeline, deal_pipeline_stage, deal_stage_history};
+use crate::models::deal::{
+    ForecastReport, ForecastStage, PipelineStageInput, VelocityReport, VelocityStage,
+};
+
+pub const OUTCOME_OPEN: &str = "open";
+pub const OUTCOME_WON: &str = "won";
+pub const OUTCOME_LOST: &str = "lost";
+
+/// Name of the pipeline every tenant gets the first time it works with deals.
+pub const DEFAULT_PIPELINE_NAME: &str = "Sales";
+
+/// Stages of the default pipeline: name, win probability and outcome.
+const DEFAULT_STAGES: &[(&str, i32, &str)] = &[
+    ("Prospecting", 10, OUTCOME_OPEN),
+    ("Qualification", 25, OUTCOME_OPEN),
+    ("Proposal", 50, OUTCOME_OPEN),
+    ("Negotiation", 75, OUTCOME_OPEN),
+    ("Closed Won", 100, OUTCOME_WON),
+    ("Closed Lost", 0, OUTCOME_LOST),
+];
+
+#[derive(Debug)]
+pub enum PipelineError {
+    NotFound,
+    Invalid(String),
+    Conflict(String),
+    Db(DbErr),
+}
+
+impl std::fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Pipeline not found"),
+            Self::Invalid(msg) => write!(f, "Invalid pipeline: {}", msg),
+            Self::Conflict(msg) => write!(f, "{}", msg),
+            Self::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+impl From<DbErr> for PipelineError {
+    fn from(e: DbErr) -> Self {
+        Self::Db(e)
+    }
+}
+
+/// Deal status that goes with a stage outcome.
+pub fn status_for(outcome: &str) -> &'static str {
+    match outcome {
+        OUTCOME_WON => "Won",
+        OUTCOME_LOST => "Lost",
+        _ => "Open",
+    }
+}
+
+fn in_tenant(tenant_id: Option<Uuid>) -> SimpleExpr {
+    match tenant_id {
+        Some(tenant_id) => deal_pipeline::Column::TenantId.eq(tenant_id),
+        None => deal_pipeline::Column::TenantId.is_null(),
+    }
+}
+
+/// The tenant's default pipeline, created with the standard sales stages if the tenant has none.
+pub async fn default_pipeline<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+) -> Result<deal_pipeline::Model, DbErr> {
+    let find = || {
+        deal_pipeline::Entity::find()
+            .filter(in_tenant(tenant_id))
+            .filter(deal_pipeline::Column::IsDefault.eq(true))
+            .order_by_asc(deal_pipeline::Column::CreatedAt)
+    };
+    if let Some(pipeline) = find().one(conn).await? {
+        return Ok(pipeline);
+    }
+
+    // Concurrent first requests race here; the unique indexes let exactly one insert win
+    let pipeline_id = Uuid::new_v4();
+    let inserted = conn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO deal_pipeline (id, tenant_id, name, is_default, created_at, updated_at)
+               VALUES ($1, $2, $3, TRUE, NOW(), NOW()) ON CONFLICT DO NOTHING"#,
+            vec![pipeline_id.into(), tenant_id.into(), DEFAULT_PIPELINE_NAME.into()],
+        ))
+        .await?
+        .rows_affected();
+    if inserted == 1 {
+        let now = Utc::now();
+        let stages = DEFAULT_STAGES.iter().enumerate().map(|(position, (name, probability, outcome))| {
+            deal_pipeline_stage::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                pipeline_id: Set(pipeline_id),
+                name: Set(name.to_string()),
+                position: Set(position as i32),
+                probability: Set(*probability),
+                outcome: Set(outcome.to_string()),
+                created_at: Set(now),
+                updated_at: Set(now),
+            }
+        });
+        deal_pipeline_stage::Entity::insert_many(stages).exec(conn).await?;
+    }
+
+    if let Some(pipeline) = find().one(conn).await? {
+        return Ok(pipeline);
+    }
+    // Another pipeline holds the default name without the flag; promote the oldest one instead
+    let oldest = deal_pipeline::Entity::find()
+        .filter(in_tenant(tenant_id))
+        .order_by_asc(deal_pipeline::Column::CreatedAt)
+        .one(conn)
+        .await?
+        .ok_or_else(|| DbErr::RecordNotFound("default deal pipeline".to_string()))?;
+    let mut pipeline: deal_pipeline::ActiveModel = oldest.into();
+    pipeline.is_default = Set(true);
+    pipeline.update(conn).await
+}
+
+/// A pipeline in the tenant, or its default pipeline when no id is given.
+pub async fn find_or_default<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+    pipeline_id: Option<Uuid>,
+) -> Result<deal_pipeline::Model, PipelineError> {
+    match pipeline_id {
+        Some(id) => deal_pipeline::Entity::find_by_id(id)
+            .filter(in_tenant(tenant_id))
+            .one(conn)
+            .await?
+            .ok_or(PipelineError::NotFound),
+        None => Ok(default_pipeline(conn, tenant_id).await?),
+    }
+}
+
+pub async fn stages<C: ConnectionTrait>(conn: &C, pipeline_id: Uuid) -> Result<Vec<deal_pipeline_stage::Model>, DbErr> {
+    deal_pipeline_stage::Entity::find()
+        .filter(deal_pipeline_stage::Column::PipelineId.eq(pipeline_id))
+        .order_by_asc(deal_pipeline_stage::Column::Position)
+        .all(conn)
+        .await
+}
+
+/// Picks a stage by id or by case-insensitive name; it has to belong to `stages`.
+fn resolve_stage(
+    stages: &[deal_pipeline_stage::Model],
+    stage_id: Option<Uuid>,
+    name: Option<&str>,
+) -> Result<deal_pipeline_stage::Model, PipelineError> {
+    let found = match (stage_id, name) {
+        (Some(id), _) => stages.iter().find(|stage| stage.id == id),
+        (None, Some(name)) => stages.iter().find(|stage| stage.name.eq_ignore_ascii_case(name.trim())),
+        (None, None) => return Err(PipelineError::Invalid("a stage is required".to_string())),
+    };
+    found.cloned().ok_or_else(|| {
+        let requested = name.map(str::to_string).or(stage_id.map(|id| id.to_string())).unwrap_or_default();
+        PipelineError::Invalid(format!("stage '{}' is not part of the deal's pipeline", requested))
+    })
+}
+
+/// Where a deal sits: the pipeline and the stage within it.
+#[derive(Debug, Clone)]
+pub struct Placement {
+    pub pipeline_id: Uuid,
+    pub stage: deal_pipeline_stage::Model,
+}
+
+impl Placement {
+    /// Sets the deal's stage fields. The status follows the stage's outcome, and entering a won
+    /// or lost stage records the actual close date.
+    pub fn apply(&self, deal: &mut deal::ActiveModel, now: DateTime<Utc>) {
+        deal.pipeline_id = Set(Some(self.pipeline_id));
+        deal.stage_id = Set(Some(self.stage.id));
+        deal.stage = Set(self.stage.name.clone());
+        deal.status = Set(status_for(&self.stage.outcome).to_string());
+        deal.stage_entered_at = Set(Some(now));
+        if self.stage.outcome != OUTCOME_OPEN {
+            deal.close_date = Set(Some(now));
+        }
+    }
+}
+
+/// Placement of a new deal: the named stage of the given (or default) pipeline, or its first
+/// open stage when no stage is named.
+pub async fn place_new_deal<C: ConnectionTrait>(
+    conn: &C,
+    tenant_id: Option<Uuid>,
+    pipeline_id: Option<Uuid>,
+    stage: Option<&str>,
+) -> Result<Placement, PipelineError> {
+    let pipeline = find_or_default(conn, tenant_id, pipeline_id).await?;
+    let stages = stages(conn, pipeline.id).await?;
+    let stage = match stage {
+        Some(name) => resolve_stage(&stages, None, Some(name))?,
+        None => stages
+            .iter()
+            .find(|stage| stage.outcome == OUTCOME_OPEN)
+            .cloned()
+            .ok_or_else(|| PipelineError::Invalid("pipeline has no open stage".to_string()))?,
+    };
+    Ok(Placement { pipeline_id: pipeline.id, stage })
+}
+
+/// Requested stage change: a stage by id or name, optionally in another pipeline.
+#[derive(Debug, Default)]
+pub struct StageTarget<'a> {
+    pub pipeline_id: Option<Uuid>,
+    pub stage_id: Option<Uuid>,
+    pub stage: Option<&'a str>,
+    /// Allows moving a won or lost deal back into the pipeline.
+    pub reopen: bool,
+}
+
+/// Validates a stage change. Returns `None` when the deal is already in the target stage.
+///
+/// The target has to belong to the deal's pipeline (deals created before pipelines existed are
+/// moved into the default one), and a won or lost deal only leaves its stage when reopened.
+pub async fn plan_transition<C: ConnectionTrait>(
+    conn: &C,
+    deal: &deal::Model,
+    target: StageTarget<'_>,
+) -> Result<Option<Placement>, PipelineError> {
+    let pipeline = find_or_default(conn, deal.tenant_id, target.pipeline_id.or(deal.pipeline_id)).await?;
+    let stage = resolve_stage(&stages(conn, pipeline.id).await?, target.stage_id, target.stage)?;
+    if deal.stage_id == Some(stage.id) {
+        return Ok(None);
+    }
+
+    let current = match deal.stage_id {
+        Some(id) => deal_pipeline_stage::Entity::find_by_id(id).one(conn).await?,
+        None => None,
+    };
+    let closed = match &current {
+        Some(current) => current.outcome != OUTCOME_OPEN,
+        // Free-form values from before pipelines existed
+        None => [&deal.status, &deal.stage].iter().any(|value| {
+            ["won", "lost", "closed won", "closed lost"].iter().any(|closed| value.eq_ignore_ascii_case(closed))
+        }),
+    };
+    if closed && !target.reopen {
+        return Err(PipelineError::Conflict(format!(
+            "Deal is closed in stage '{}'; set reopen to move it",
+            deal.stage
+        )));
+    }
+    Ok(Some(Placement { pipeline_id: pipeline.id, stage }))
+}
+
+/// Appends the stage `after` entered to its history; `before` is `None` for a new deal.
+pub async fn record_stage_entry<C: ConnectionTrait>(
+    conn: &C,
+    before: Option<&deal::Model>,
+    after: &deal::Model,
+    changed_by: Option<Uuid>,
+) -> Result<deal_stage_history::Model, DbErr> {
+    deal_stage_history::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        deal_id: Set(after.id),
+        tenant_id: Set(after.tenant_id),
+        pipeline_id: Set(after.pipeline_id),
+        from_stage_id: Set(before.and_then(|deal| deal.stage_id)),
+        to_stage_id: Set(after.stage_id),
+        from_stage: Set(before.map(|deal| deal.stage.clone())),
+        to_stage: Set(after.stage.clone()),
+        amount: Set(after.amount),
+        changed_by: Set(changed_by),
+        changed_at: Set(after.stage_entered_at.unwrap_or_else(Utc::now)),
+    }
+    .insert(conn)
+    .await
+}
+
+/// Checks a pipeline definition: unique names, probabilities within 0-100, and at least one
+/// open and one won stage. Won and lost stages default to 100% and 0%.
+pub fn validate_stages(stages: &[PipelineStageInput]) -> Result<(), PipelineError> {
+    let mut names = HashSet::new();
+    for stage in stages {
+        let name = stage.name.trim();
+        if name.is_empty() {
+            return Err(PipelineError::Invalid("stage names can't be empty".to_string()));
+        }
+        if !names.insert(name.to_lowercase()) {
+            return Err(PipelineError::Invalid(format!("stage '{}' is listed twice", name)));
+        }
+        let outcome = stage.outcome();
+        if ![OUTCOME_OPEN, OUTCOME_WON, OUTCOME_LOST].contains(&outcome) {
+            return Err(PipelineError::Invalid(format!("unknown outcome '{}'", outcome)));
+        }
+        match stage.probability() {
+            Some(probability) if (0..=100).contains(&probability) => {}
+            Some(probability) => {
+                return Err(PipelineError::Invalid(format!("probability {} is outside 0-100", probability)))
+            }
+            None => return Err(PipelineError::Invalid(format!("stage '{}' needs a probability", name))),
+        }
+    }
+    if !stages.iter().any(|stage| stage.outcome() == OUTCOME_OPEN) {
+        return Err(PipelineError::Invalid("at least one open stage is required".to_string()));
+    }
+    if !stages.iter().any(|stage| stage.outcome() == OUTCOME_WON) {
+        return Err(PipelineError::Invalid("at least one won stage is required".to_string()));
+    }
+    Ok(())
+}
+
+/// Replaces a pipeline's stages with `inputs`, in order. Stages given with an id are updated,
+/// new ones are added, and omitted ones are removed unless deals are still in them.
+pub async fn replace_stages<C: ConnectionTrait>(
+    conn: &C,
+    pipeline_id: Uuid,
+    inputs: &[PipelineStageInput],
+) -> Result<(), PipelineError> {
+    validate_stages(inputs)?;
+    let existing = stages(conn, pipeline_id).await?;
+    let kept: HashSet<Uuid> = inputs.iter().filter_map(|input| input.id).collect();
+    if let Some(unknown) = kept.iter().find(|id| !existing.iter().any(|stage| stage.id == **id)) {
+        return Err(PipelineError::Invalid(format!("stage {} is not part of this pipeline", unknown)));
+    }
+
+    let removed: Vec<Uuid> = existing.iter().map(|stage| stage.id).filter(|id| !kept.contains(id)).collect();
+    if !removed.is_empty() {
+        let in_use = deal::Entity::find()
+            .filter(deal::Column::StageId.is_in(removed.clone()))
+            .one(conn)
+            .await?;
+        if let Some(deal) = in_use {
+            return Err(PipelineError::Conflict(format!(
+                "Stage '{}' still has deals; move them before removing it",
+                deal.stage
+            )));
+        }
+        deal_pipeline_stage::Entity::delete_many()
+            .filter(deal_pipeline_stage::Column::Id.is_in(removed))
+            .exec(conn)
+            .await?;
+    }
+
+    // Names are unique per pipeline, so renamed stages first move out of each other's way
+    deal_pipeline_stage::Entity::update_many()
+        .col_expr(
+            deal_pipeline_stage::Column::Name,
+            Expr::cust("'~' || id::text"),
+        )
+        .filter(deal_pipeline_stage::Column::PipelineId.eq(pipeline_id))
+        .exec(conn)
+        .await?;
+
+    let now = Utc::now();
+    for (position, input) in inputs.iter().enumerate() {
+        let name = input.name.trim().to_string();
+        let stage = deal_pipeline_stage::ActiveModel {
+            id: Set(input.id.unwrap_or_else(Uuid::new_v4)),
+            pipeline_id: Set(pipeline_id),
+            name: Set(name.clone()),
+            position: Set(position as i32),
+            probability: Set(input.probability().unwrap_or_default()),
+            outcome: Set(input.outcome().to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+        };
+        if let Some(stage_id) = input.id {
+            let mut stage = stage;
+            stage.created_at = sea_orm::ActiveValue::NotSet;
+            stage.update(conn).await?;
+            // Deals in the stage follow its new name and outcome
+            deal::Entity::update_many()
+                .col_expr(deal::Column::Stage, Expr::value(name))
+                .col_expr(deal::Column::Status, Expr::value(status_for(input.outcome())))
+                .filter(deal::Column::StageId.eq(stage_id))
+                .exec(conn)
+                .await?;
+        } else {
+            stage.insert(conn).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Open deals per stage with their amounts weighted by the stage's win probability. Deals
+/// can be limited to those expected to close within `[close_from, close_to]`.
+pub async fn forecast<C: ConnectionTrait>(
+    conn: &C,
+    pipeline: &deal_pipeline::Model,
+    close_from: Option<DateTime<Utc>>,
+    close_to: Option<DateTime<Utc>>,
+) -> Result<ForecastReport, DbErr> {
+    let stages = stages(conn, pipeline.id).await?;
+    let mut query = deal::Entity::find()
+        .filter(deal::Column::PipelineId.eq(pipeline.id))
+        .filter(deal::Column::IsActive.eq(true));
+    if let Some(from) = close_from {
+        query = query.filter(deal::Column::CloseDate.gte(from));
+    }
+    if let Some(to) = close_to {
+        query = query.filter(deal::Column::CloseDate.lte(to));
+    }
+    let deals = query.all(conn).await?;
+
+    let mut report = ForecastReport {
+        pipeline_id: pipeline.id,
+        pipeline: pipeline.name.clone(),
+        stages: Vec::new(),
+        deal_count: 0,
+        total_amount: 0.0,
+        weighted_amount: 0.0,
+    };
+    for stage in stages.into_iter().filter(|stage| stage.outcome == OUTCOME_OPEN) {
+        let in_stage: Vec<&deal::Model> = deals.iter().filter(|deal| deal.stage_id == Some(stage.id)).collect();
+        let amount: f64 = in_stage.iter().map(|deal| deal.amount).sum();
+        let weighted = amount * stage.probability as f64 / 100.0;
+        report.deal_count += in_stage.len();
+        report.total_amount += amount;
+        report.weighted_amount += weighted;
+        report.stages.push(ForecastStage {
+            stage_id: stage.id,
+            name: stage.name,
+            probability: stage.probability,
+            deal_count: in_stage.len(),
+            amount,
+            weighted_amount: weighted,
+        });
+    }
+    Ok(report)
+}
+
+fn days_between(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    (to - from).num_seconds().max(0) as f64 / 86_400.0
+}
+
+fn average(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Time spent in each stage, derived from consecutive history entries, along with the age of
+/// deals currently in the stage, the win rate and the average days from creation to a win.
+pub async fn velocity<C: ConnectionTrait>(
+    conn: &C,
+    pipeline: &deal_pipeline::Model,
+    now: DateTime<Utc>,
+) -> Result<VelocityReport, DbErr> {
+    let stages = stages(conn, pipeline.id).await?;
+    let history = deal_stage_history::Entity::find()
+        .filter(deal_stage_history::Column::PipelineId.eq(pipeline.id))
+        .order_by_asc(deal_stage_history::Column::DealId)
+        .order_by_asc(deal_stage_history::Column::ChangedAt)
+        .all(conn)
+        .await?;
+    let deals: HashMap<Uuid, deal::Model> = deal::Entity::find()
+        .filter(deal::Column::PipelineId.eq(pipeline.id))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|deal| (deal.id, deal))
+        .collect();
+
+    let mut completed: HashMap<Uuid, Vec<f64>> = HashMap::new();
+    let mut entered: HashMap<Uuid, usize> = HashMap::new();
+    let mut cycles = Vec::new();
+    for entries in history.chunk_by(|a, b| a.deal_id == b.deal_id) {
+        for pair in entries.windows(2) {
+            if let Some(stage_id) = pair[0].to_stage_id {
+                completed.entry(stage_id).or_default().push(days_between(pair[0].changed_at, pair[1].changed_at));
+            }
+        }
+        for entry in entries {
+            if let Some(stage_id) = entry.to_stage_id {
+                *entered.entry(stage_id).or_default() += 1;
+            }
+        }
+        let won_at = entries.iter().rev().find(|entry| {
+            stages.iter().any(|stage| Some(stage.id) == entry.to_stage_id && stage.outcome == OUTCOME_WON)
+        });
+        if let (Some(first), Some(won)) = (entries.first(), won_at) {
+            cycles.push(days_between(first.changed_at, won.changed_at));
+        }
+    }
+
+    let current_outcome = |deal: &deal::Model| {
+        stages.iter().find(|stage| Some(stage.id) == deal.stage_id).map(|stage| stage.outcome.as_str())
+    };
+    let won_count = deals.values().filter(|deal| current_outcome(deal) == Some(OUTCOME_WON)).count();
+    let lost_count = deals.values().filter(|deal| current_outcome(deal) == Some(OUTCOME_LOST)).count();
+
+    let stage_reports = stages
+        .iter()
+        .map(|stage| {
+            let ages: Vec<f64> = deals
+                .values()
+                .filter(|deal| deal.is_active && deal.stage_id == Some(stage.id))
+                .map(|deal| days_between(deal.stage_entered_at.unwrap_or(deal.updated_at), now))
+                .collect();
+            VelocityStage {
+                stage_id: stage.id,
+                name: stage.name.clone(),
+                outcome: stage.outcome.clone(),
+                entered_count: entered.get(&stage.id).copied().unwrap_or_default(),
+                avg_days_in_stage: completed.get(&stage.id).and_then(|days| average(days)),
+                current_count: ages.len(),
+                avg_current_age_days: average(&ages),
+            }
+        })
+        .collect();
+
+    Ok(VelocityReport {
+        pipeline_id: pipeline.id,
+        pipeline: pipeline.name.clone(),
+        stages: stage_reports,
+        won_count,
+        lost_count,
+        win_rate: (won_count + lost_count > 0).then(|| won_count as f64 / (won_count + lost_count) as f64),
+        avg_days_to_win: average(&cycles),
+    })
+}
@@ -1,12 +1,17 @@
 use async_trait::async_trait;
 use reqwest::Client;
+use std::collections::HashMap;
 use std::env;
-use serde_json::json;
+use std::sync::Mutex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use crate::entities::app_instance;
 
 #[derive(Debug)]
 pub enum DnsError {
     ProviderConfigError(String),
     ProvisioningError(String),
+    LookupError(String),
 }
 
 impl std::fmt::Display for DnsError {
@@ -14,16 +19,66 @@ impl std::fmt::Display for DnsError {
         match self {
             Self::ProviderConfigError(msg) => write!(f, "Provider Config Error: {}", msg),
             Self::ProvisioningError(msg) => write!(f, "Provisioning Error: {}", msg),
+            Self::LookupError(msg) => write!(f, "Lookup Error: {}", msg),
         }
     }
 }
 
 impl std::error::Error for DnsError {}
 
+/// Where a custom hostname stands at the edge provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostnameState {
+    Pending,
+    Active,
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub struct HostnameStatus {
+    pub state: HostnameState,
+    /// The provider's certificate status, e.g. "pending_validation" or "active".
+    pub ssl_status: Option<String>,
+    pub error: Option<String>,
+}
+
+/// A DNS record a customer has to publish.
+#[derive(Debug, Clone, Serialize)]
+pub struct DnsRecord {
+    pub record_type: String,
+    pub name: String,
+    pub value: String,
+}
+
+/// The host customers point their domain's CNAME at.
+pub fn cname_target() -> String {
+    env::var("CUSTOM_DOMAIN_CNAME_TARGET").unwrap_or_else(|_| "edge.atlas-platform.local".to_string())
+}
+
 #[async_trait]
 pub trait DnsProvider: Send + Sync {
-    async fn provision_hostname(&self, hostname: &str) -> Result<(), DnsError>;
-    async fn remove_hostname(&self, hostname: &str) -> Result<(), DnsError>;
+    /// Registers the hostname at the edge and returns the provider's id for it.
+    async fn provision_hostname(&self, hostname: &str) -> Result<String, DnsError>;
+    async fn hostname_status(&self, hostname_id: &str) -> Result<HostnameStatus, DnsError>;
+    /// Removes the hostname from the edge. Without an id the provider looks the hostname up first.
+    async fn remove_hostname(&self, hostname: &str, hostname_id: Option<&str>) -> Result<(), DnsError>;
+
+    /// Records that route the hostname's traffic to the edge.
+    fn routing_records(&self, hostname: &str) -> Vec<DnsRecord> {
+        vec![DnsRecord {
+            record_type: "CNAME".to_string(),
+            name: hostname.to_string(),
+            value: cname_target(),
+        }]
+    }
+}
+
+/// Looks up the public DNS records used to prove domain ownership.
+#[async_trait]
+pub trait DnsResolver: Send + Sync {
+    async fn lookup_txt(&self, name: &str) -> Result<Vec<String>, DnsError>;
+    async fn lookup_cname(&self, name: &str) -> Result<Vec<String>, DnsError>;
+    async fn lookup_a(&self, name: &str) -> Result<Vec<String>, DnsError>;
 }
 
 /// Cloudflare SSL for SaaS Implementation
@@ -46,16 +101,34 @@ impl CloudflareProvider {
             zone_id,
         })
     }
-}
 
-#[async_trait]
-impl DnsProvider for CloudflareProvider {
-    async fn provision_hostname(&self, hostname: &str) -> Result<(), DnsError> {
-        let url = format!(
+    fn custom_hostnames_url(&self) -> String {
+        format!(
             "https://api.cloudflare.com/client/v4/zones/{}/custom_hostnames",
             self.zone_id
-        );
+        )
+    }
 
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Value, DnsError> {
+        let res = request
+            .header("Authorization", format!("Bearer {}", self.api_token))
+            .send()
+            .await
+            .map_err(|e| DnsError::ProvisioningError(e.to_string()))?;
+
+        if !res.status().is_success() {
+            let error_text = res.text().await.unwrap_or_default();
+            tracing::error!("Cloudflare request failed: {}", error_text);
+            return Err(DnsError::ProvisioningError(error_text));
+        }
+
+        res.json().await.map_err(|e| DnsError::ProvisioningError(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl DnsProvider for CloudflareProvider {
+    async fn provision_hostname(&self, hostname: &str) -> Result<String, DnsError> {
         let payload = json!({
             "hostname": hostname,
             "ssl": {
@@ -64,53 +137,357 @@ impl DnsProvider for CloudflareProvider {
             }
         });
 
-        let res = self.client.post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_token))
-            .header("Content-Type", "application/json")
-            .json(&payload)
+        let body = self
+            .send(self.client.post(self.custom_hostnames_url()).json(&payload))
+            .await?;
+        let id = body["result"]["id"]
+            .as_str()
+            .ok_or_else(|| DnsError::ProvisioningError("Cloudflare response has no custom hostname id".into()))?;
+
+        tracing::info!("Cloudflare custom hostname {} provisioned for: {}", id, hostname);
+        Ok(id.to_string())
+    }
+
+    async fn hostname_status(&self, hostname_id: &str) -> Result<HostnameStatus, DnsError> {
+        let url = format!("{}/{}", self.custom_hostnames_url(), hostname_id);
+        let body = self.send(self.client.get(url)).await?;
+        let result = &body["result"];
+
+        let hostname_status = result["status"].as_str().unwrap_or("pending");
+        let ssl_status = result["ssl"]["status"].as_str().map(str::to_string);
+        let errors: Vec<String> = result["verification_errors"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .chain(result["ssl"]["validation_errors"].as_array().into_iter().flatten())
+            .filter_map(|e| e.as_str().or_else(|| e["message"].as_str()).map(str::to_string))
+            .collect();
+
+        let state = match (hostname_status, ssl_status.as_deref()) {
+            ("active", Some("active")) => HostnameState::Active,
+            ("blocked" | "moved" | "deleted", _) => HostnameState::Failed,
+            (_, Some("validation_timed_out" | "issuance_timed_out" | "deleted")) => HostnameState::Failed,
+            _ => HostnameState::Pending,
+        };
+
+        Ok(HostnameStatus {
+            state,
+            ssl_status,
+            error: (!errors.is_empty()).then(|| errors.join("; ")),
+        })
+    }
+
+    async fn remove_hostname(&self, hostname: &str, hostname_id: Option<&str>) -> Result<(), DnsError> {
+        let hostname_id = match hostname_id {
+            Some(id) => id.to_string(),
+            None => {
+                let body = self
+                    .send(self.client.get(self.custom_hostnames_url()).query(&[("hostname", hostname)]))
+                    .await?;
+                match body["result"][0]["id"].as_str() {
+                    Some(id) => id.to_string(),
+                    None => {
+                        tracing::info!("Cloudflare has no custom hostname for {}, nothing to remove", hostname);
+                        return Ok(());
+                    }
+                }
+            }
+        };
+
+        let url = format!("{}/{}", self.custom_hostnames_url(), hostname_id);
+        self.send(self.client.delete(url)).await?;
+        tracing::info!("Cloudflare custom hostname {} removed for: {}", hostname_id, hostname);
+        Ok(())
+    }
+}
+
+/// Resolves records over DNS-over-HTTPS (JSON API), so no system resolver configuration is needed.
+pub struct DohResolver {
+    client: Client,
+    endpoint: String,
+}
+
+impl DohResolver {
+    pub fn from_env() -> Self {
+        Self {
+            client: Client::new(),
+            endpoint: env::var("DNS_OVER_HTTPS_URL").unwrap_or_else(|_| "https://cloudflare-dns.com/dns-query".to_string()),
+        }
+    }
+
+    async fn lookup(&self, name: &str, record_type: &str) -> Result<Vec<String>, DnsError> {
+        let body: Value = self
+            .client
+            .get(&self.endpoint)
+            .query(&[("name", name), ("type", record_type)])
+            .header("Accept", "application/dns-json")
             .send()
             .await
-            .map_err(|e| DnsError::ProvisioningError(e.to_string()))?;
+            .map_err(|e| DnsError::LookupError(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| DnsError::LookupError(e.to_string()))?;
 
-        if !res.status().is_success() {
-            let error_text = res.text().await.unwrap_or_default();
-            tracing::error!("Cloudflare provisioning failed: {}", error_text);
-            return Err(DnsError::ProvisioningError(error_text));
+        Ok(body["Answer"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|answer| answer["data"].as_str())
+            .map(|data| data.trim_matches('"').trim_end_matches('.').to_string())
+            .collect())
+    }
+}
+
+#[async_trait]
+impl DnsResolver for DohResolver {
+    async fn lookup_txt(&self, name: &str) -> Result<Vec<String>, DnsError> {
+        self.lookup(name, "TXT").await
+    }
+
+    async fn lookup_cname(&self, name: &str) -> Result<Vec<String>, DnsError> {
+        self.lookup(name, "CNAME").await
+    }
+
+    async fn lookup_a(&self, name: &str) -> Result<Vec<String>, DnsError> {
+        self.lookup(name, "A").await
+    }
+}
+
+/// Caddy on-demand TLS: Caddy obtains a certificate on the first TLS handshake for a
+/// hostname once `GET /api/tls/ask` authorizes it, so there is nothing to call at the edge.
+pub struct CaddyProvider;
+
+#[async_trait]
+impl DnsProvider for CaddyProvider {
+    async fn provision_hostname(&self, hostname: &str) -> Result<String, DnsError> {
+        Ok(format!("caddy:{}", hostname))
+    }
+
+    async fn hostname_status(&self, _hostname_id: &str) -> Result<HostnameStatus, DnsError> {
+        Ok(HostnameStatus {
+            state: HostnameState::Active,
+            ssl_status: Some("on_demand".to_string()),
+            error: None,
+        })
+    }
+
+    async fn remove_hostname(&self, _hostname: &str, _hostname_id: Option<&str>) -> Result<(), DnsError> {
+        // Once the domain is gone the ask endpoint refuses it and Caddy stops renewing
+        Ok(())
+    }
+}
+
+/// For edges without an API: the customer creates the routing records and TLS is
+/// terminated outside the platform. A hostname is active once its records point at the edge.
+pub struct ManualProvider {
+    resolver: Box<dyn DnsResolver>,
+}
+
+impl ManualProvider {
+    pub fn new(resolver: Box<dyn DnsResolver>) -> Self {
+        Self { resolver }
+    }
+
+    /// Apex domains can't have a CNAME, so an A record is offered when the edge has a fixed address.
+    fn edge_ipv4() -> Option<String> {
+        env::var("EDGE_IPV4_ADDRESS").ok().filter(|ip| !ip.is_empty())
+    }
+}
+
+#[async_trait]
+impl DnsProvider for ManualProvider {
+    async fn provision_hostname(&self, hostname: &str) -> Result<String, DnsError> {
+        Ok(format!("manual:{}", hostname))
+    }
+
+    async fn hostname_status(&self, hostname_id: &str) -> Result<HostnameStatus, DnsError> {
+        let hostname = hostname_id.strip_prefix("manual:").unwrap_or(hostname_id);
+        let target = cname_target();
+        let mut routed = self
+            .resolver
+            .lookup_cname(hostname)
+            .await?
+            .iter()
+            .any(|value| value.eq_ignore_ascii_case(&target));
+        if let (false, Some(ip)) = (routed, Self::edge_ipv4()) {
+            routed = self.resolver.lookup_a(hostname).await?.contains(&ip);
         }
 
-        tracing::info!("Cloudflare custom hostname correctly provisioned for: {}", hostname);
+        Ok(HostnameStatus {
+            state: if routed { HostnameState::Active } else { HostnameState::Pending },
+            ssl_status: Some("external".to_string()),
+            error: (!routed).then(|| format!("{} does not point at the edge yet", hostname)),
+        })
+    }
+
+    async fn remove_hostname(&self, _hostname: &str, _hostname_id: Option<&str>) -> Result<(), DnsError> {
         Ok(())
     }
 
-    async fn remove_hostname(&self, _hostname: &str) -> Result<(), DnsError> {
-        // Implementing removal dynamically requires querying the custom_hostname ID first 
-        // to pass to the DELETE endpoint. We can stub it for future implementation.
-        tracing::warn!("Cloudflare auto-delete domain not implemented natively yet.");
+    fn routing_records(&self, hostname: &str) -> Vec<DnsRecord> {
+        let mut records = vec![DnsRecord {
+            record_type: "CNAME".to_string(),
+            name: hostname.to_string(),
+            value: cname_target(),
+        }];
+        if let Some(ip) = Self::edge_ipv4() {
+            records.push(DnsRecord {
+                record_type: "A".to_string(),
+                name: hostname.to_string(),
+                value: ip,
+            });
+        }
+        records
+    }
+}
+
+/// In-memory provider and resolver for tests and offline development.
+/// Hostnames stay pending until `set_hostname_state` moves them on.
+#[derive(Default)]
+pub struct MockDnsProvider {
+    records: Mutex<HashMap<(String, &'static str), Vec<String>>>,
+    hostnames: Mutex<HashMap<String, (String, HostnameState)>>,
+}
+
+impl MockDnsProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_txt_record(&self, name: &str, value: &str) {
+        self.add_record(name, "TXT", value);
+    }
+
+    pub fn add_cname_record(&self, name: &str, target: &str) {
+        self.add_record(name, "CNAME", target);
+    }
+
+    pub fn add_a_record(&self, name: &str, address: &str) {
+        self.add_record(name, "A", address);
+    }
+
+    fn add_record(&self, name: &str, record_type: &'static str, value: &str) {
+        self.records
+            .lock()
+            .unwrap()
+            .entry((name.to_lowercase(), record_type))
+            .or_default()
+            .push(value.to_string());
+    }
+
+    pub fn set_hostname_state(&self, hostname: &str, state: HostnameState) {
+        for (name, current) in self.hostnames.lock().unwrap().values_mut() {
+            if name == hostname {
+                *current = state;
+            }
+        }
+    }
+
+    /// Whether the hostname is currently provisioned.
+    pub fn has_hostname(&self, hostname: &str) -> bool {
+        self.hostnames.lock().unwrap().values().any(|(name, _)| name == hostname)
+    }
+
+    fn records(&self, name: &str, record_type: &'static str) -> Vec<String> {
+        self.records
+            .lock()
+            .unwrap()
+            .get(&(name.to_lowercase(), record_type))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl DnsProvider for MockDnsProvider {
+    async fn provision_hostname(&self, hostname: &str) -> Result<String, DnsError> {
+        let id = format!("mock-{}", uuid::Uuid::new_v4().simple());
+        self.hostnames
+            .lock()
+            .unwrap()
+            .insert(id.clone(), (hostname.to_string(), HostnameState::Pending));
+        Ok(id)
+    }
+
+    async fn hostname_status(&self, hostname_id: &str) -> Result<HostnameStatus, DnsError> {
+        let hostnames = self.hostnames.lock().unwrap();
+        let (_, state) = hostnames
+            .get(hostname_id)
+            .ok_or_else(|| DnsError::ProvisioningError(format!("Unknown hostname id {}", hostname_id)))?;
+        Ok(HostnameStatus {
+            state: *state,
+            ssl_status: Some(if *state == HostnameState::Active { "active" } else { "pending_validation" }.to_string()),
+            error: (*state == HostnameState::Failed).then(|| "Certificate validation failed".to_string()),
+        })
+    }
+
+    async fn remove_hostname(&self, hostname: &str, hostname_id: Option<&str>) -> Result<(), DnsError> {
+        self.hostnames
+            .lock()
+            .unwrap()
+            .retain(|id, (name, _)| Some(id.as_str()) != hostname_id && name != hostname);
         Ok(())
     }
 }
 
-/// Helper function to automatically pick the correct edge orchestration logic.
-pub async fn provision_domain(domain: &str) -> Result<(), String> {
-    // Check if the administrator activated TLS_PROVIDER=cloudflare
-    let provider_name = env::var("TLS_PROVIDER").unwrap_or_else(|_| "cloudflare".to_string());
-    
-    tracing::info!("Dispatching DNS provision routing via Provider: {}", provider_name);
-    
-    let provider: Box<dyn DnsProvider> = match provider_name.as_str() {
+#[async_trait]
+impl DnsResolver for MockDnsProvider {
+    async fn lookup_txt(&self, name: &str) -> Result<Vec<String>, DnsError> {
+        Ok(self.records(name, "TXT"))
+    }
+
+    async fn lookup_cname(&self, name: &str) -> Result<Vec<String>, DnsError> {
+        Ok(self.records(name, "CNAME"))
+    }
+
+    async fn lookup_a(&self, name: &str) -> Result<Vec<String>, DnsError> {
+        Ok(self.records(name, "A"))
+    }
+}
+
+/// Names accepted by `TLS_PROVIDER` and by an app instance's `dns_provider` setting.
+pub const PROVIDER_NAMES: &[&str] = &["cloudflare", "caddy", "manual"];
+
+/// Builds the provider registered under `name`.
+///
+/// Returns `None` for `cloudflare` without credentials only with `ENVIRONMENT=development`
+/// (or in tests), so local development can add domains without an edge account. Anywhere
+/// else, including no `ENVIRONMENT`, missing credentials are a configuration error.
+pub fn provider_named(name: &str) -> Result<Option<Box<dyn DnsProvider>>, DnsError> {
+    match name {
         "cloudflare" => {
-            // Allow failing gracefully if secrets aren't set in local DEV.
-            if env::var("CLOUDFLARE_API_TOKEN").is_err() {
-                tracing::warn!("DNS Abstraction caught Missing CLOUDFLARE_API_TOKEN inside dev context. Passing through gracefully to allow local testing!");
-                return Ok(());
+            let is_development = cfg!(test) || env::var("ENVIRONMENT").as_deref() == Ok("development");
+            if env::var("CLOUDFLARE_API_TOKEN").is_err() && is_development {
+                tracing::warn!("CLOUDFLARE_API_TOKEN is not set; custom domains are activated without verification");
+                return Ok(None);
             }
-            Box::new(CloudflareProvider::new().map_err(|e| e.to_string())?)
-        },
-        _ => {
-            tracing::warn!("Unknown edge provider: {}, passing through gracefully.", provider_name);
-            return Ok(());
+            Ok(Some(Box::new(CloudflareProvider::new()?)))
         }
-    };
+        "caddy" => Ok(Some(Box::new(CaddyProvider))),
+        "manual" => Ok(Some(Box::new(ManualProvider::new(Box::new(DohResolver::from_env()))))),
+        other => Err(DnsError::ProviderConfigError(format!(
+            "Unknown edge provider '{}', expected one of {}",
+            other,
+            PROVIDER_NAMES.join(", ")
+        ))),
+    }
+}
+
+/// The platform-wide provider selected by `TLS_PROVIDER` (default `cloudflare`).
+pub fn provider_from_env() -> Result<Option<Box<dyn DnsProvider>>, DnsError> {
+    provider_named(&env::var("TLS_PROVIDER").unwrap_or_else(|_| "cloudflare".to_string()))
+}
 
-    provider.provision_hostname(domain).await.map_err(|e| e.to_string())
+/// The provider an app instance's domains go through: its `dns_provider` setting, or
+/// the platform default.
+pub fn provider_for_instance(instance: &app_instance::Model) -> Result<Option<Box<dyn DnsProvider>>, DnsError> {
+    match instance
+        .settings
+        .as_ref()
+        .and_then(|settings| settings.get("dns_provider"))
+        .and_then(Value::as_str)
+    {
+        Some(name) => provider_named(name),
+        None => provider_from_env(),
+    }
 }
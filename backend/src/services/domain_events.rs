@@ -0,0 +1,62 @@
+use crate::services::event_catalog::PlatformEvent;
+use crate::services::webhook;
+use chrono::Utc;
+use sea_orm::{ConnectionTrait, DbErr};
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+pub struct DomainEvents;
+
+impl DomainEvents {
+    /// Publishes a domain event through the outbox.
+    ///
+    /// Pass the transaction that performed the mutation so the event is only delivered if
+    /// that mutation commits. Rows without a tenant are skipped because webhook endpoints
+    /// are registered per tenant.
+    pub async fn emit<C: ConnectionTrait>(
+        conn: &C,
+        event: PlatformEvent,
+        tenant_id: Option<Uuid>,
+        actor_id: Option<Uuid>,
+        entity_id: Uuid,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> Result<(), DbErr> {
+        let Some(tenant_id) = tenant_id else {
+            tracing::debug!("Skipping {} for {}: no tenant", event.name(), entity_id);
+            return Ok(());
+        };
+
+        let payload = Self::envelope(event, tenant_id, actor_id, entity_id, before, after);
+        webhook::dispatch_event(conn, tenant_id, event, payload).await
+    }
+
+    /// The payload shape documented by `PlatformEvent::payload_schema`.
+    pub fn envelope(
+        event: PlatformEvent,
+        tenant_id: Uuid,
+        actor_id: Option<Uuid>,
+        entity_id: Uuid,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> Value {
+        json!({
+            "event": event.name(),
+            "version": event.version(),
+            "tenant_id": tenant_id,
+            "entity_id": entity_id,
+            "actor_id": actor_id,
+            "occurred_at": Utc::now(),
+            "data": {
+                "before": before,
+                "after": after,
+            }
+        })
+    }
+}
+
+/// Serializes an entity for the `before`/`after` fields of an event.
+pub fn snapshot<T: Serialize>(model: &T) -> Option<Value> {
+    serde_json::to_value(model).ok()
+}
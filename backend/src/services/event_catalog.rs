@@ -0,0 +1,244 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Every event the platform can deliver to webhook endpoints.
+///
+/// Call sites emit a variant rather than a string so the published catalog and the
+/// events actually fired can't drift apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PlatformEvent {
+    WebformSubmitted,
+    LeadCreated,
+    LeadUpdated,
+    LeadDeleted,
+    DealCreated,
+    DealUpdated,
+    DealStageChanged,
+    DealWon,
+    DealDeleted,
+    CustomerCreated,
+    CustomerUpdated,
+    CustomerDeleted,
+    ContactCreated,
+    ContactUpdated,
+    ContactDeleted,
+    CaseCreated,
+    CaseUpdated,
+    CaseDeleted,
+    ListingCreated,
+    ListingUpdated,
+    ListingApproved,
+    ListingRejected,
+    ListingDeleted,
+    FeedItemCreated,
+    FeedItemUpdated,
+    FeedItemDeleted,
+}
+
+/// Which entity snapshots a domain event carries in its `data` envelope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Snapshots {
+    After,
+    BeforeAndAfter,
+    Before,
+}
+
+struct EventSpec {
+    name: &'static str,
+    description: &'static str,
+    /// `None` for events whose payload is not an entity snapshot envelope.
+    snapshots: Option<Snapshots>,
+}
+
+impl PlatformEvent {
+    pub const ALL: &'static [PlatformEvent] = &[
+        PlatformEvent::WebformSubmitted,
+        PlatformEvent::LeadCreated,
+        PlatformEvent::LeadUpdated,
+        PlatformEvent::LeadDeleted,
+        PlatformEvent::DealCreated,
+        PlatformEvent::DealUpdated,
+        PlatformEvent::DealStageChanged,
+        PlatformEvent::DealWon,
+        PlatformEvent::DealDeleted,
+        PlatformEvent::CustomerCreated,
+        PlatformEvent::CustomerUpdated,
+        PlatformEvent::CustomerDeleted,
+        PlatformEvent::ContactCreated,
+        PlatformEvent::ContactUpdated,
+        PlatformEvent::ContactDeleted,
+        PlatformEvent::CaseCreated,
+        PlatformEvent::CaseUpdated,
+        PlatformEvent::CaseDeleted,
+        PlatformEvent::ListingCreated,
+        PlatformEvent::ListingUpdated,
+        PlatformEvent::ListingApproved,
+        PlatformEvent::ListingRejected,
+        PlatformEvent::ListingDeleted,
+        PlatformEvent::FeedItemCreated,
+        PlatformEvent::FeedItemUpdated,
+        PlatformEvent::FeedItemDeleted,
+    ];
+
+    fn spec(&self) -> EventSpec {
+        use Snapshots::*;
+        let (name, description, snapshots) = match self {
+            PlatformEvent::WebformSubmitted => ("webform.submitted", "A visitor submitted a form built with the form engine.", None),
+            PlatformEvent::LeadCreated => ("crm.lead.created", "A lead was captured or created.", Some(After)),
+            PlatformEvent::LeadUpdated => ("crm.lead.updated", "A lead was edited.", Some(BeforeAndAfter)),
+            PlatformEvent::LeadDeleted => ("crm.lead.deleted", "A lead was deleted.", Some(Before)),
+            PlatformEvent::DealCreated => ("crm.deal.created", "A deal was opened.", Some(After)),
+            PlatformEvent::DealUpdated => ("crm.deal.updated", "A deal was edited.", Some(BeforeAndAfter)),
+            PlatformEvent::DealStageChanged => ("crm.deal.stage_changed", "A deal moved to a different pipeline stage.", Some(BeforeAndAfter)),
+            PlatformEvent::DealWon => ("crm.deal.won", "A deal was marked as won.", Some(BeforeAndAfter)),
+            PlatformEvent::DealDeleted => ("crm.deal.deleted", "A deal was deleted.", Some(Before)),
+            PlatformEvent::CustomerCreated => ("crm.customer.created", "A customer was created.", Some(After)),
+            PlatformEvent::CustomerUpdated => ("crm.customer.updated", "A customer was edited.", Some(BeforeAndAfter)),
+            PlatformEvent::CustomerDeleted => ("crm.customer.deleted", "A customer was deleted.", Some(Before)),
+            PlatformEvent::ContactCreated => ("crm.contact.created", "A contact was created.", Some(After)),
+            PlatformEvent::ContactUpdated => ("crm.contact.updated", "A contact was edited.", Some(BeforeAndAfter)),
+            PlatformEvent::ContactDeleted => ("crm.contact.deleted", "A contact was deleted.", Some(Before)),
+            PlatformEvent::CaseCreated => ("crm.case.created", "A support case was opened.", Some(After)),
+            PlatformEvent::CaseUpdated => ("crm.case.updated", "A support case was edited.", Some(BeforeAndAfter)),
+            PlatformEvent::CaseDeleted => ("crm.case.deleted", "A support case was deleted.", Some(Before)),
+            PlatformEvent::ListingCreated => ("listing.created", "A listing was submitted.", Some(After)),
+            PlatformEvent::ListingUpdated => ("listing.updated", "A listing was edited.", Some(BeforeAndAfter)),
+            PlatformEvent::ListingApproved => ("listing.approved", "A listing was approved by a moderator.", Some(BeforeAndAfter)),
+            PlatformEvent::ListingRejected => ("listing.rejected", "A listing was rejected by a moderator.", Some(BeforeAndAfter)),
+            PlatformEvent::ListingDeleted => ("listing.deleted", "A listing was deleted.", Some(Before)),
+            PlatformEvent::FeedItemCreated => ("feed.item.created", "An item was published to a feed.", Some(After)),
+            PlatformEvent::FeedItemUpdated => ("feed.item.updated", "A feed item was edited.", Some(BeforeAndAfter)),
+            PlatformEvent::FeedItemDeleted => ("feed.item.deleted", "A feed item was removed.", Some(Before)),
+        };
+        EventSpec { name, description, snapshots }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.spec().name
+    }
+
+    /// Bumped whenever the payload shape changes incompatibly.
+    pub fn version(&self) -> u32 {
+        1
+    }
+
+    pub fn description(&self) -> &'static str {
+        self.spec().description
+    }
+
+    /// JSON Schema (draft 2020-12) describing the delivered payload.
+    pub fn payload_schema(&self) -> Value {
+        let Some(snapshots) = self.spec().snapshots else {
+            return json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "description": "The submitted field values, keyed by field name.",
+                "additionalProperties": true
+            });
+        };
+
+        let snapshot = |present: bool| if present {
+            json!({ "type": "object" })
+        } else {
+            json!({ "type": "null" })
+        };
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "required": ["event", "version", "tenant_id", "entity_id", "occurred_at", "data"],
+            "properties": {
+                "event": { "const": self.name() },
+                "version": { "const": self.version() },
+                "tenant_id": { "type": "string", "format": "uuid" },
+                "entity_id": { "type": "string", "format": "uuid" },
+                "actor_id": { "type": ["string", "null"], "format": "uuid" },
+                "occurred_at": { "type": "string", "format": "date-time" },
+                "data": {
+                    "type": "object",
+                    "required": ["before", "after"],
+                    "properties": {
+                        "before": snapshot(snapshots != Snapshots::After),
+                        "after": snapshot(snapshots != Snapshots::Before)
+                    }
+                }
+            }
+        })
+    }
+
+    pub fn from_name(name: &str) -> Option<PlatformEvent> {
+        Self::ALL.iter().copied().find(|event| event.name() == name)
+    }
+}
+
+/// Serializable catalog entry published to the developer console.
+#[derive(Debug, Serialize)]
+pub struct EventDescriptor {
+    pub name: &'static str,
+    pub version: u32,
+    pub description: &'static str,
+    pub payload_schema: Value,
+}
+
+pub fn catalog() -> Vec<EventDescriptor> {
+    PlatformEvent::ALL
+        .iter()
+        .map(|event| EventDescriptor {
+            name: event.name(),
+            version: event.version(),
+            description: event.description(),
+            payload_schema: event.payload_schema(),
+        })
+        .collect()
+}
+
+/// Matches a subscription pattern against an event name.
+/// `*` matches everything and `crm.*` matches any event under the `crm.` namespace.
+pub fn pattern_matches(pattern: &str, event_name: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    match pattern.strip_suffix(".*") {
+        Some(namespace) => event_name
+            .strip_prefix(namespace)
+            .is_some_and(|rest| rest.starts_with('.')),
+        None => pattern == event_name,
+    }
+}
+
+/// True when any entry of an endpoint's `subscribed_events` array matches the event.
+pub fn is_subscribed(subscribed_events: &Value, event_name: &str) -> bool {
+    subscribed_events
+        .as_array()
+        .map(|patterns| {
+            patterns
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|pattern| pattern_matches(pattern, event_name))
+        })
+        .unwrap_or(false)
+}
+
+/// Validates a `subscribed_events` payload: a non-empty array of catalog event names or
+/// wildcard patterns that match at least one catalog event.
+pub fn validate_subscriptions(subscribed_events: &Value) -> Result<(), String> {
+    let patterns = subscribed_events
+        .as_array()
+        .ok_or_else(|| "subscribed_events must be an array of event names".to_string())?;
+    if patterns.is_empty() {
+        return Err("subscribed_events must not be empty".to_string());
+    }
+
+    for pattern in patterns {
+        let pattern = pattern
+            .as_str()
+            .ok_or_else(|| "subscribed_events entries must be strings".to_string())?;
+        let known = PlatformEvent::ALL
+            .iter()
+            .any(|event| pattern_matches(pattern, event.name()));
+        if !known {
+            return Err(format!("Unknown event '{}'", pattern));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,424 @@
+use crate::entities::{job_run, tenant_background_job};
+use crate::traits::atlas_app::BackgroundJob;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use once_cell::sync::Lazy;
+use sea_orm::sea_query::{Expr, LockBehavior, LockType, OnConflict};
+use sea_orm::*;
+use serde_json::Value;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+pub const RUN_RUNNING: &str = "running";
+pub const RUN_SUCCEEDED: &str = "succeeded";
+pub const RUN_FAILED: &str = "failed";
+pub const RUN_TIMED_OUT: &str = "timed_out";
+
+const POLL_INTERVAL_SECS: u64 = 15;
+
+/// Jobs leased per scheduler transaction.
+const BATCH_SIZE: u64 = 50;
+
+/// Extra time past `timeout_seconds` before another replica treats a run as abandoned.
+const LEASE_GRACE_SECS: i64 = 30;
+
+/// Finished runs are kept this long for the admin run history before being pruned.
+const RUN_RETENTION_DAYS: i64 = 30;
+
+/// Scheduler ticks between prunes of old runs, roughly hourly.
+const PRUNE_EVERY_TICKS: u64 = 3600 / POLL_INTERVAL_SECS;
+
+/// Identifies this replica in `job_runs.worker_id`.
+static WORKER_ID: Lazy<String> = Lazy::new(|| {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "local".to_string());
+    format!("{}:{}", host, Uuid::new_v4().simple())
+});
+
+/// Parses a cron expression. Standard 5-field expressions are accepted and run at second 0.
+pub fn parse_cron(expression: &str) -> Result<Schedule, String> {
+    let normalized = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    Schedule::from_str(&normalized).map_err(|e| format!("Invalid cron expression '{}': {}", expression, e))
+}
+
+/// When a job should next run after `from`. A cron expression wins over `interval_seconds`.
+pub fn next_run_after(job: &tenant_background_job::Model, from: DateTime<Utc>) -> DateTime<Utc> {
+    if let Some(expression) = job.cron_expression.as_deref() {
+        match parse_cron(expression) {
+            Ok(schedule) => {
+                if let Some(next) = schedule.after(&from).next() {
+                    return next;
+                }
+            }
+            Err(e) => warn!("Job {} has an unusable schedule, falling back to its interval: {}", job.id, e),
+        }
+    }
+    from + chrono::Duration::seconds(job.interval_seconds.max(1) as i64)
+}
+
+/// Jobs the platform itself runs for tenants, next to those apps register.
+pub fn core_jobs() -> Vec<BackgroundJob> {
+    vec![
+        crate::services::custom_domains::CustomDomainService::background_job(),
+        crate::services::tenant::TenantService::purge_job(),
+        crate::services::activity_tasks::TaskService::reminder_job(),
+        crate::services::case_sla::escalation_job(),
+    ]
+}
+
+/// Looks up the executable definition the platform or an app registered for `job_type`.
+pub fn find_definition(job_type: &str) -> Option<BackgroundJob> {
+    crate::atlas_apps::get_active_apps()
+        .into_iter()
+        .flat_map(|app| app.background_jobs())
+        .chain(core_jobs())
+        .find(|definition| definition.job_type == job_type)
+}
+
+/// Checks a tenant config against the shape of the job's `default_config_payload`.
+///
+/// Only keys present in the default are allowed and each value must have the same JSON
+/// type as its default; nested objects are checked the same way and a `null` default
+/// accepts anything. Jobs without a default accept any object.
+pub fn validate_config(definition: &BackgroundJob, config: &Value) -> Result<(), String> {
+    match &definition.default_config_payload {
+        Some(shape) => validate_shape(shape, config, "config"),
+        None if config.is_object() || config.is_null() => Ok(()),
+        None => Err("config must be an object".to_string()),
+    }
+}
+
+fn validate_shape(shape: &Value, value: &Value, path: &str) -> Result<(), String> {
+    match (shape, value) {
+        (Value::Null, _) => Ok(()),
+        (Value::Object(shape_fields), Value::Object(fields)) => {
+            for (key, field) in fields {
+                let field_path = format!("{}.{}", path, key);
+                let field_shape = shape_fields
+                    .get(key)
+                    .ok_or_else(|| format!("Unknown field '{}'", field_path))?;
+                validate_shape(field_shape, field, &field_path)?;
+            }
+            Ok(())
+        }
+        (Value::Bool(_), Value::Bool(_))
+        | (Value::Number(_), Value::Number(_))
+        | (Value::String(_), Value::String(_))
+        | (Value::Array(_), Value::Array(_)) => Ok(()),
+        _ => Err(format!("'{}' must be {}", path, json_type(shape))),
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+pub struct JobScheduler;
+
+impl JobScheduler {
+    pub async fn start_worker(db: DatabaseConnection) {
+        tokio::spawn(async move {
+            info!("Starting job scheduler as worker {}", WORKER_ID.as_str());
+            let mut interval = tokio::time::interval(Duration::from_secs(POLL_INTERVAL_SECS));
+            let mut ticks: u64 = 0;
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = Self::reap_expired_runs(&db).await {
+                    error!("Failed to reap abandoned job runs: {}", e);
+                }
+
+                ticks += 1;
+                if ticks.is_multiple_of(PRUNE_EVERY_TICKS)
+                    && let Err(e) = Self::prune_runs(&db).await
+                {
+                    error!("Failed to prune job runs: {}", e);
+                }
+
+                let runs = match Self::claim_due_jobs(&db).await {
+                    Ok(runs) => runs,
+                    Err(e) => {
+                        error!("Job scheduler failed to claim due jobs: {}", e);
+                        continue;
+                    }
+                };
+
+                for run in runs {
+                    Self::spawn_run(&db, run);
+                }
+            }
+        });
+    }
+
+    /// Executes a claimed run on a background task.
+    pub fn spawn_run(db: &DatabaseConnection, run: job_run::Model) {
+        let db = db.clone();
+        tokio::spawn(async move {
+            let run_id = run.id;
+            if let Err(e) = Self::execute_run(&db, run).await {
+                error!("Failed to record outcome of job run {}: {}", run_id, e);
+            }
+        });
+    }
+
+    /// Leases every due job and opens a `running` row for each.
+    ///
+    /// Job rows are locked with `FOR UPDATE SKIP LOCKED` and `next_run_at` is advanced in the
+    /// same transaction, so each occurrence is claimed by exactly one replica. Jobs already at
+    /// `max_concurrency` stay due and are picked up once a run finishes.
+    pub async fn claim_due_jobs(db: &DatabaseConnection) -> Result<Vec<job_run::Model>, DbErr> {
+        Self::claim_due_jobs_for(db, None).await
+    }
+
+    /// [`Self::claim_due_jobs`] restricted to one tenant when `tenant_id` is given.
+    pub(crate) async fn claim_due_jobs_for(db: &DatabaseConnection, tenant_id: Option<Uuid>) -> Result<Vec<job_run::Model>, DbErr> {
+        let now = Utc::now();
+        let txn = db.begin().await?;
+
+        let mut due = tenant_background_job::Entity::find()
+            .filter(tenant_background_job::Column::IsActive.eq(true));
+        if let Some(tenant_id) = tenant_id {
+            due = due.filter(tenant_background_job::Column::TenantId.eq(tenant_id));
+        }
+        let due = due
+            .filter(
+                Condition::any()
+                    .add(tenant_background_job::Column::NextRunAt.is_null())
+                    .add(tenant_background_job::Column::NextRunAt.lte(now)),
+            )
+            .order_by_asc(tenant_background_job::Column::NextRunAt)
+            .limit(BATCH_SIZE)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .all(&txn)
+            .await?;
+
+        let mut claimed = Vec::new();
+        for job in due {
+            if !Self::has_free_slot(&txn, &job, now).await? {
+                continue;
+            }
+
+            claimed.push(Self::open_run(&txn, &job, now).await?);
+
+            let next_run_at = next_run_after(&job, now);
+            let mut active_job: tenant_background_job::ActiveModel = job.into();
+            active_job.last_run = Set(Some(now.into()));
+            active_job.next_run_at = Set(Some(next_run_at.into()));
+            active_job.update(&txn).await?;
+        }
+
+        txn.commit().await?;
+        Ok(claimed)
+    }
+
+    /// Starts a run outside the schedule, leaving `next_run_at` untouched.
+    /// Returns `None` when the job is already at `max_concurrency`.
+    pub async fn run_now(db: &DatabaseConnection, job_id: Uuid) -> Result<Option<job_run::Model>, DbErr> {
+        let now = Utc::now();
+        let txn = db.begin().await?;
+
+        // Wait for a concurrent claim of the same job instead of skipping it
+        let Some(job) = tenant_background_job::Entity::find_by_id(job_id)
+            .lock(LockType::Update)
+            .one(&txn)
+            .await?
+        else {
+            return Ok(None);
+        };
+        if !Self::has_free_slot(&txn, &job, now).await? {
+            return Ok(None);
+        }
+
+        let run = Self::open_run(&txn, &job, now).await?;
+        txn.commit().await?;
+
+        Self::spawn_run(db, run.clone());
+        Ok(Some(run))
+    }
+
+    /// Creates a tenant's job rows from an app's definitions, skipping job types the
+    /// tenant already has. Used by install hooks; the unique `(tenant_id, job_type)` index
+    /// keeps concurrent installs from provisioning a job twice.
+    pub async fn provision<C: ConnectionTrait>(
+        conn: &C,
+        tenant_id: Uuid,
+        definitions: &[BackgroundJob],
+    ) -> Result<(), DbErr> {
+        for definition in definitions {
+            tenant_background_job::Entity::insert(tenant_background_job::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                tenant_id: Set(tenant_id),
+                job_type: Set(definition.job_type.clone()),
+                config: Set(None),
+                interval_seconds: Set(definition.default_interval_seconds),
+                last_run: Set(None),
+                is_active: Set(definition.is_active_by_default),
+                cron_expression: Set(None),
+                next_run_at: Set(None),
+                max_concurrency: Set(1),
+                timeout_seconds: Set(300),
+            })
+            .on_conflict(
+                OnConflict::columns([tenant_background_job::Column::TenantId, tenant_background_job::Column::JobType])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(conn)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes a tenant's job rows for an app's definitions; their runs cascade.
+    pub async fn deprovision<C: ConnectionTrait>(
+        conn: &C,
+        tenant_id: Uuid,
+        definitions: &[BackgroundJob],
+    ) -> Result<(), DbErr> {
+        let job_types: Vec<String> = definitions.iter().map(|d| d.job_type.clone()).collect();
+        tenant_background_job::Entity::delete_many()
+            .filter(tenant_background_job::Column::TenantId.eq(tenant_id))
+            .filter(tenant_background_job::Column::JobType.is_in(job_types))
+            .exec(conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Runs whose lease has expired don't count; the reaper closes them.
+    async fn has_free_slot<C: ConnectionTrait>(
+        conn: &C,
+        job: &tenant_background_job::Model,
+        now: DateTime<Utc>,
+    ) -> Result<bool, DbErr> {
+        let running = job_run::Entity::find()
+            .filter(job_run::Column::JobId.eq(job.id))
+            .filter(job_run::Column::Status.eq(RUN_RUNNING))
+            .filter(job_run::Column::LeaseExpiresAt.gt(now))
+            .count(conn)
+            .await?;
+        if running >= job.max_concurrency.max(1) as u64 {
+            debug!("Job {} already has {} run(s) in flight", job.id, running);
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    async fn open_run<C: ConnectionTrait>(
+        conn: &C,
+        job: &tenant_background_job::Model,
+        now: DateTime<Utc>,
+    ) -> Result<job_run::Model, DbErr> {
+        let lease = chrono::Duration::seconds(job.timeout_seconds.max(1) as i64 + LEASE_GRACE_SECS);
+        job_run::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            job_id: Set(job.id),
+            tenant_id: Set(job.tenant_id),
+            job_type: Set(job.job_type.clone()),
+            status: Set(RUN_RUNNING.to_string()),
+            worker_id: Set(WORKER_ID.clone()),
+            started_at: Set(now.into()),
+            lease_expires_at: Set((now + lease).into()),
+            finished_at: Set(None),
+            duration_ms: Set(None),
+            error: Set(None),
+        }
+        .insert(conn)
+        .await
+    }
+
+    /// Runs a claimed job with its tenant config and records the outcome on the run row.
+    pub async fn execute_run(db: &DatabaseConnection, run: job_run::Model) -> Result<job_run::Model, DbErr> {
+        let Some(job) = tenant_background_job::Entity::find_by_id(run.job_id).one(db).await? else {
+            return Self::finish_run(db, run, RUN_FAILED, Some("Job no longer exists".to_string()), Duration::ZERO).await;
+        };
+        match find_definition(&job.job_type) {
+            Some(definition) => Self::execute_with(db, run, &job, &definition).await,
+            None => {
+                let error = format!("No app registers job type '{}'", job.job_type);
+                Self::finish_run(db, run, RUN_FAILED, Some(error), Duration::ZERO).await
+            }
+        }
+    }
+
+    pub(crate) async fn execute_with(
+        db: &DatabaseConnection,
+        run: job_run::Model,
+        job: &tenant_background_job::Model,
+        definition: &BackgroundJob,
+    ) -> Result<job_run::Model, DbErr> {
+        // The tenant's own config wins over the app's defaults
+        let config = job.config.clone().or_else(|| definition.default_config_payload.clone());
+        let timeout = Duration::from_secs(job.timeout_seconds.max(1) as u64);
+
+        debug!("Executing job {} / {} for tenant {}", job.id, job.job_type, job.tenant_id);
+        let started = Instant::now();
+        let outcome = tokio::time::timeout(timeout, (definition.executor)(db.clone(), job.tenant_id, config)).await;
+        let elapsed = started.elapsed();
+
+        let (status, error) = match outcome {
+            Ok(Ok(())) => (RUN_SUCCEEDED, None),
+            Ok(Err(e)) => {
+                error!("Failed to execute {} for tenant {}: {}", job.job_type, job.tenant_id, e);
+                (RUN_FAILED, Some(e))
+            }
+            Err(_) => {
+                warn!("Job {} for tenant {} timed out after {:?}", job.job_type, job.tenant_id, timeout);
+                (RUN_TIMED_OUT, Some(format!("Timed out after {}s", timeout.as_secs())))
+            }
+        };
+        Self::finish_run(db, run, status, error, elapsed).await
+    }
+
+    async fn finish_run(
+        db: &DatabaseConnection,
+        run: job_run::Model,
+        status: &str,
+        error: Option<String>,
+        elapsed: Duration,
+    ) -> Result<job_run::Model, DbErr> {
+        let mut active_run: job_run::ActiveModel = run.into();
+        active_run.status = Set(status.to_string());
+        active_run.error = Set(error);
+        active_run.finished_at = Set(Some(Utc::now().into()));
+        active_run.duration_ms = Set(Some(elapsed.as_millis() as i64));
+        active_run.update(db).await
+    }
+
+    /// Closes runs whose worker died before reporting back, freeing their concurrency slot.
+    pub async fn reap_expired_runs(db: &DatabaseConnection) -> Result<u64, DbErr> {
+        let now = Utc::now();
+        let result = job_run::Entity::update_many()
+            .col_expr(job_run::Column::Status, Expr::value(RUN_TIMED_OUT))
+            .col_expr(job_run::Column::FinishedAt, Expr::value(now))
+            .col_expr(job_run::Column::Error, Expr::value("Lease expired before the worker reported back"))
+            .filter(job_run::Column::Status.eq(RUN_RUNNING))
+            .filter(job_run::Column::LeaseExpiresAt.lt(now))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Deletes finished runs older than the retention window. Running rows are left to the reaper.
+    pub async fn prune_runs(db: &DatabaseConnection) -> Result<u64, DbErr> {
+        let cutoff = Utc::now() - chrono::Duration::days(RUN_RETENTION_DAYS);
+        let result = job_run::Entity::delete_many()
+            .filter(job_run::Column::Status.ne(RUN_RUNNING))
+            .filter(job_run::Column::StartedAt.lt(cutoff))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
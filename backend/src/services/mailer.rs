@@ -0,0 +1,132 @@
+use lettre::message::{header, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use sea_orm::DatabaseConnection;
+use uuid::Uuid;
+
+use crate::services::secrets::{Keyring, SecretsService};
+
+#[derive(Debug)]
+pub enum MailError {
+    /// Tenant settings or the secrets keyring couldn't be loaded.
+    Settings(String),
+    InvalidFrom,
+    InvalidTo,
+    Build(String),
+    Transport(String),
+}
+
+impl std::fmt::Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Settings(msg) => write!(f, "Mail settings unavailable: {}", msg),
+            Self::InvalidFrom => write!(f, "Invalid FROM email"),
+            Self::InvalidTo => write!(f, "Invalid TO email"),
+            Self::Build(msg) => write!(f, "Failed to build email message: {}", msg),
+            Self::Transport(msg) => write!(f, "Failed to send email over SMTP: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for MailError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Sent,
+    /// No SMTP host is configured; the message was only logged.
+    Mocked,
+}
+
+/// SMTP settings for a tenant: its `smtp_*` tenant settings, falling back to the `SMTP_*`
+/// environment variables.
+struct SmtpConfig {
+    host: String,
+    port: u16,
+    username: String,
+    token: String,
+    from: String,
+}
+
+impl SmtpConfig {
+    async fn for_tenant(db: &DatabaseConnection, tenant_id: Uuid) -> Result<Self, MailError> {
+        // smtp_token is stored encrypted
+        let keyring = Keyring::global().map_err(|e| MailError::Settings(e.to_string()))?;
+        let mut settings = SecretsService::load_settings(db, keyring, tenant_id)
+            .await
+            .map_err(|e| MailError::Settings(e.to_string()))?;
+
+        let env_or = |key: &str, default: &str| std::env::var(key).unwrap_or_else(|_| default.to_string());
+        Ok(Self {
+            host: settings.remove("smtp_server").unwrap_or_else(|| env_or("SMTP_SERVER", "localhost")),
+            port: settings
+                .remove("smtp_port")
+                .unwrap_or_else(|| env_or("SMTP_PORT", "587"))
+                .parse()
+                .unwrap_or(587),
+            username: settings.remove("smtp_username").unwrap_or_else(|| env_or("SMTP_USERNAME", "")),
+            token: settings.remove("smtp_token").unwrap_or_else(|| env_or("SMTP_TOKEN", "")),
+            from: settings
+                .remove("smtp_from")
+                .unwrap_or_else(|| env_or("SMTP_FROM", "noreply@atlas-platform.local")),
+        })
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, MailError> {
+        let builder = if self.port == 465 {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)
+        }
+        .map_err(|e| MailError::Transport(format!("invalid SMTP host {}: {}", self.host, e)))?;
+        Ok(builder
+            .port(self.port)
+            .credentials(Credentials::new(self.username.clone(), self.token.clone()))
+            .build())
+    }
+}
+
+/// Escapes text for interpolation into an HTML email body.
+pub fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Sends an HTML email through the tenant's SMTP server.
+pub async fn send_tenant_email(
+    db: &DatabaseConnection,
+    tenant_id: Uuid,
+    to: &str,
+    subject: &str,
+    body_html: String,
+) -> Result<Delivery, MailError> {
+    let config = SmtpConfig::for_tenant(db, tenant_id).await?;
+
+    let email = Message::builder()
+        .from(config.from.parse().map_err(|_| MailError::InvalidFrom)?)
+        .to(to.parse().map_err(|_| MailError::InvalidTo)?)
+        .subject(subject)
+        .multipart(
+            MultiPart::alternative().singlepart(
+                SinglePart::builder()
+                    .header(header::ContentType::TEXT_HTML)
+                    .body(body_html),
+            ),
+        )
+        .map_err(|e| MailError::Build(e.to_string()))?;
+
+    if config.host == "localhost" || config.host.is_empty() {
+        tracing::warn!("SMTP Host not configured. Mocking email send to: {}", to);
+        return Ok(Delivery::Mocked);
+    }
+
+    config
+        .transport()?
+        .send(email)
+        .await
+        .map_err(|e| MailError::Transport(e.to_string()))?;
+    tracing::info!("Email sent successfully to {}", to);
+    Ok(Delivery::Sent)
+}
@@ -1,4 +1,7 @@
 pub mod tenant;
+pub mod tenant_archive;
+pub mod deal_pipelines;
+pub mod seed_packs;
 pub mod telephony;
 pub mod billing;
 pub mod dns;
@@ -6,9 +9,20 @@ pub mod dns;
 pub mod search_sync;
 pub mod telemetry;
 pub mod webhook;
+pub mod event_catalog;
+pub mod domain_events;
+pub mod outbox;
 pub mod lead_billing;
 pub mod audit;
 pub mod user_service;
 pub mod auth_service;
 pub mod billing_service;
-pub mod data_sync;
\ No newline at end of file
+pub mod data_sync;
+pub mod job_scheduler;
+pub mod app_lifecycle;
+pub mod secrets;
+pub mod mailer;
+pub mod activity_tasks;
+pub mod case_sla;
+pub mod custom_fields;
+pub mod custom_domains;
@@ -0,0 +1,253 @@
+use crate::entities::{audit_log, outbox, telemetry_events};
+use crate::services::event_catalog::PlatformEvent;
+use crate::services::webhook;
+use chrono::{DateTime, Utc};
+use sea_orm::sea_query::{LockBehavior, LockType, OnConflict};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_PROCESSED: &str = "processed";
+pub const STATUS_FAILED: &str = "failed";
+
+/// Relay attempts before a message is parked as `failed` for manual inspection.
+pub const MAX_ATTEMPTS: i32 = 10;
+
+/// Messages claimed per relay transaction.
+const BATCH_SIZE: u64 = 100;
+
+/// Processed messages are kept this long for debugging before being pruned.
+const RETENTION_DAYS: i64 = 7;
+
+/// A side effect recorded in the same transaction as the mutation that caused it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OutboxMessage {
+    Audit {
+        tenant_id: Option<Uuid>,
+        actor_id: Option<Uuid>,
+        action_type: String,
+        entity_type: String,
+        entity_id: Uuid,
+        old_state: Option<Value>,
+        new_state: Option<Value>,
+        ip_address: Option<String>,
+        occurred_at: DateTime<Utc>,
+    },
+    Telemetry {
+        tenant_id: Uuid,
+        event_source: String,
+        event_type: String,
+        event_payload: Option<Value>,
+        occurred_at: DateTime<Utc>,
+    },
+    Webhook {
+        tenant_id: Uuid,
+        event: String,
+        payload: Value,
+    },
+}
+
+impl OutboxMessage {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            OutboxMessage::Audit { .. } => "audit",
+            OutboxMessage::Telemetry { .. } => "telemetry",
+            OutboxMessage::Webhook { .. } => "webhook",
+        }
+    }
+
+    pub fn tenant_id(&self) -> Option<Uuid> {
+        match self {
+            OutboxMessage::Audit { tenant_id, .. } => *tenant_id,
+            OutboxMessage::Telemetry { tenant_id, .. } | OutboxMessage::Webhook { tenant_id, .. } => Some(*tenant_id),
+        }
+    }
+}
+
+pub struct Outbox;
+
+impl Outbox {
+    /// Writes a message on `conn`. When `conn` is the mutation's transaction the message
+    /// only becomes visible to the relay if that transaction commits.
+    pub async fn enqueue<C: ConnectionTrait>(conn: &C, message: OutboxMessage) -> Result<Uuid, DbErr> {
+        let payload = serde_json::to_value(&message)
+            .map_err(|e| DbErr::Custom(format!("Failed to serialize outbox message: {}", e)))?;
+        let now = Utc::now();
+
+        let inserted = outbox::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            kind: Set(message.kind().to_string()),
+            tenant_id: Set(message.tenant_id()),
+            payload: Set(payload),
+            status: Set(STATUS_PENDING.to_string()),
+            attempts: Set(0),
+            last_error: Set(None),
+            available_at: Set(now.into()),
+            processed_at: Set(None),
+            created_at: Set(now.into()),
+        }
+        .insert(conn)
+        .await?;
+
+        Ok(inserted.id)
+    }
+
+    /// Claims one batch of due messages and fans each out to its target table.
+    ///
+    /// Rows are locked with `FOR UPDATE SKIP LOCKED` so several relays can run side by side,
+    /// and every write is keyed on the outbox id so a message relayed twice has no extra
+    /// effect. Returns the number of messages claimed.
+    pub async fn relay_once(db: &DatabaseConnection) -> Result<usize, DbErr> {
+        let txn = db.begin().await?;
+        let now = Utc::now();
+
+        let batch = outbox::Entity::find()
+            .filter(outbox::Column::Status.eq(STATUS_PENDING))
+            .filter(outbox::Column::AvailableAt.lte(now))
+            .order_by_asc(outbox::Column::CreatedAt)
+            .limit(BATCH_SIZE)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .all(&txn)
+            .await?;
+        let claimed = batch.len();
+
+        let mut deliveries = Vec::new();
+        for message in batch {
+            let attempts = message.attempts + 1;
+
+            // Savepoint per message so one bad message doesn't roll back the whole batch
+            let savepoint = txn.begin().await?;
+            let result = fan_out(&savepoint, &message).await;
+
+            let mut active_message: outbox::ActiveModel = message.clone().into();
+            active_message.attempts = Set(attempts);
+            match result {
+                Ok(created) => {
+                    savepoint.commit().await?;
+                    deliveries.extend(created);
+                    active_message.status = Set(STATUS_PROCESSED.to_string());
+                    active_message.processed_at = Set(Some(now.into()));
+                    active_message.last_error = Set(None);
+                }
+                Err(e) => {
+                    savepoint.rollback().await?;
+                    warn!("Outbox message {} ({}) failed attempt {}: {:?}", message.id, message.kind, attempts, e);
+                    active_message.last_error = Set(Some(e.to_string()));
+                    if attempts >= MAX_ATTEMPTS {
+                        active_message.status = Set(STATUS_FAILED.to_string());
+                    } else {
+                        // 2s, 4s, 8s, ... capped at roughly 17 minutes
+                        let delay_secs = 2_i64.pow(attempts.min(10) as u32);
+                        active_message.available_at = Set((now + chrono::Duration::seconds(delay_secs)).into());
+                    }
+                }
+            }
+            active_message.update(&txn).await?;
+        }
+
+        txn.commit().await?;
+
+        // Only attempt HTTP delivery once the deliveries are durable
+        for delivery_id in deliveries {
+            webhook::spawn_delivery(db, delivery_id);
+        }
+
+        Ok(claimed)
+    }
+
+    /// Deletes processed messages older than the retention window.
+    pub async fn prune(db: &DatabaseConnection) -> Result<u64, DbErr> {
+        let cutoff = Utc::now() - chrono::Duration::days(RETENTION_DAYS);
+        let result = outbox::Entity::delete_many()
+            .filter(outbox::Column::Status.eq(STATUS_PROCESSED))
+            .filter(outbox::Column::ProcessedAt.lt(cutoff))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// Writes the message's effect inside the relay transaction and returns any webhook
+/// deliveries it created.
+async fn fan_out(txn: &DatabaseTransaction, message: &outbox::Model) -> Result<Vec<Uuid>, DbErr> {
+    let decoded: OutboxMessage = serde_json::from_value(message.payload.clone())
+        .map_err(|e| DbErr::Custom(format!("Malformed outbox payload: {}", e)))?;
+
+    match decoded {
+        OutboxMessage::Audit { tenant_id, actor_id, action_type, entity_type, entity_id, old_state, new_state, ip_address, occurred_at } => {
+            // The outbox id becomes the row id, so a second relay is a no-op
+            audit_log::Entity::insert(audit_log::ActiveModel {
+                id: Set(message.id),
+                tenant_id: Set(tenant_id),
+                actor_id: Set(actor_id),
+                action_type: Set(action_type),
+                entity_type: Set(entity_type),
+                entity_id: Set(entity_id),
+                old_state: Set(old_state),
+                new_state: Set(new_state),
+                ip_address: Set(ip_address),
+                created_at: Set(occurred_at),
+            })
+            .on_conflict(OnConflict::column(audit_log::Column::Id).do_nothing().to_owned())
+            .exec_without_returning(txn)
+            .await?;
+            Ok(Vec::new())
+        }
+        OutboxMessage::Telemetry { tenant_id, event_source, event_type, event_payload, occurred_at } => {
+            telemetry_events::Entity::insert(telemetry_events::ActiveModel {
+                id: Set(message.id),
+                tenant_id: Set(tenant_id),
+                event_source: Set(event_source),
+                event_type: Set(event_type),
+                event_payload: Set(event_payload),
+                timestamp: Set(occurred_at),
+                processed: Set(false),
+            })
+            .on_conflict(OnConflict::column(telemetry_events::Column::Id).do_nothing().to_owned())
+            .exec_without_returning(txn)
+            .await?;
+            Ok(Vec::new())
+        }
+        OutboxMessage::Webhook { tenant_id, event, payload } => {
+            let event = PlatformEvent::from_name(&event)
+                .ok_or_else(|| DbErr::Custom(format!("Unknown event '{}'", event)))?;
+            webhook::fan_out(txn, message.id, tenant_id, event, payload).await
+        }
+    }
+}
+
+/// Drains the outbox every second. Messages are delivered at least once; a crash between
+/// claiming and committing simply leaves them pending for the next pass.
+pub async fn start_outbox_relay(db: DatabaseConnection) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        let mut ticks: u64 = 0;
+        loop {
+            interval.tick().await;
+
+            loop {
+                match Outbox::relay_once(&db).await {
+                    // A full batch means there is probably more waiting
+                    Ok(claimed) if claimed as u64 == BATCH_SIZE => continue,
+                    Ok(_) => break,
+                    Err(e) => {
+                        error!("Outbox relay failed: {:?}", e);
+                        break;
+                    }
+                }
+            }
+
+            ticks += 1;
+            if ticks.is_multiple_of(3600)
+                && let Err(e) = Outbox::prune(&db).await
+            {
+                error!("Failed to prune outbox: {:?}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,389 @@
+use crate::entities::{tenant_data_key, tenant_setting};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, QueryFilter,
+    QuerySelect, Set, Statement, TransactionTrait,
+};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use uuid::Uuid;
+
+/// Returned in place of encrypted values to callers without the `secrets:read` scope.
+pub const MASKED_VALUE: &str = "********";
+
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug)]
+pub enum SecretsError {
+    /// No usable master key is configured.
+    MasterKey(String),
+    /// A data key is wrapped by a master key that is no longer configured.
+    UnknownMasterKey(String),
+    /// Ciphertext is malformed or failed authentication.
+    Crypto,
+    Db(DbErr),
+}
+
+impl fmt::Display for SecretsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretsError::MasterKey(e) => write!(f, "Master key unavailable: {}", e),
+            SecretsError::UnknownMasterKey(id) => write!(f, "Master key '{}' is not configured", id),
+            SecretsError::Crypto => write!(f, "Ciphertext could not be decrypted"),
+            SecretsError::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SecretsError {}
+
+impl From<DbErr> for SecretsError {
+    fn from(e: DbErr) -> Self {
+        SecretsError::Db(e)
+    }
+}
+
+struct MasterKey {
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+impl MasterKey {
+    fn new(id: &str, key: &[u8]) -> Result<Self, SecretsError> {
+        let cipher = Aes256Gcm::new_from_slice(key)
+            .map_err(|_| SecretsError::MasterKey(format!("master key '{}' must be 32 bytes", id)))?;
+        Ok(MasterKey { id: id.to_string(), cipher })
+    }
+
+    fn from_base64(id: &str, encoded: &str) -> Result<Self, SecretsError> {
+        let key = STANDARD
+            .decode(encoded.trim())
+            .map_err(|_| SecretsError::MasterKey(format!("master key '{}' is not valid base64", id)))?;
+        Self::new(id, &key)
+    }
+}
+
+/// The master key that wraps new data keys, plus retired master keys that can still
+/// unwrap data keys created before a rotation.
+pub struct Keyring {
+    current: MasterKey,
+    retired: Vec<MasterKey>,
+}
+
+static GLOBAL_KEYRING: Lazy<Result<Keyring, String>> =
+    Lazy::new(|| Keyring::from_env().map_err(|e| e.to_string()));
+
+impl Keyring {
+    pub fn new(current: (&str, &[u8]), retired: &[(&str, &[u8])]) -> Result<Self, SecretsError> {
+        Ok(Keyring {
+            current: MasterKey::new(current.0, current.1)?,
+            retired: retired
+                .iter()
+                .map(|(id, key)| MasterKey::new(id, key))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Reads `SECRETS_MASTER_KEY` (base64 of 32 bytes) named by `SECRETS_MASTER_KEY_ID`, and
+    /// `SECRETS_RETIRED_MASTER_KEYS` as comma-separated `id=base64` pairs.
+    /// A fixed, publicly derivable development key stands in when none is configured, but only
+    /// with `ENVIRONMENT=development` (or in tests); anything else, including no `ENVIRONMENT`, fails.
+    pub fn from_env() -> Result<Self, SecretsError> {
+        let id = std::env::var("SECRETS_MASTER_KEY_ID").unwrap_or_else(|_| "primary".to_string());
+        let current = match std::env::var("SECRETS_MASTER_KEY") {
+            Ok(encoded) => MasterKey::from_base64(&id, &encoded)?,
+            Err(_) if cfg!(test) || std::env::var("ENVIRONMENT").as_deref() == Ok("development") => {
+                tracing::warn!("SECRETS_MASTER_KEY is not set; using the insecure development master key");
+                MasterKey::new("development", &Sha256::digest(b"atlas-development-master-key"))?
+            }
+            Err(_) => {
+                return Err(SecretsError::MasterKey(
+                    "SECRETS_MASTER_KEY must be set unless ENVIRONMENT=development".to_string(),
+                ));
+            }
+        };
+
+        let mut retired = Vec::new();
+        if let Ok(pairs) = std::env::var("SECRETS_RETIRED_MASTER_KEYS") {
+            for pair in pairs.split(',').filter(|p| !p.trim().is_empty()) {
+                let (retired_id, encoded) = pair.split_once('=').ok_or_else(|| {
+                    SecretsError::MasterKey("SECRETS_RETIRED_MASTER_KEYS entries must be id=base64".to_string())
+                })?;
+                retired.push(MasterKey::from_base64(retired_id.trim(), encoded)?);
+            }
+        }
+
+        Ok(Keyring { current, retired })
+    }
+
+    /// The keyring configured through the environment, loaded once per process.
+    pub fn global() -> Result<&'static Keyring, SecretsError> {
+        GLOBAL_KEYRING.as_ref().map_err(|e| SecretsError::MasterKey(e.clone()))
+    }
+
+    pub fn current_id(&self) -> &str {
+        &self.current.id
+    }
+
+    fn master(&self, id: &str) -> Result<&MasterKey, SecretsError> {
+        std::iter::once(&self.current)
+            .chain(self.retired.iter())
+            .find(|key| key.id == id)
+            .ok_or_else(|| SecretsError::UnknownMasterKey(id.to_string()))
+    }
+
+    fn unwrap_data_key(&self, data_key: &tenant_data_key::Model) -> Result<Aes256Gcm, SecretsError> {
+        let master = self.master(&data_key.master_key_id)?;
+        let raw = open(&master.cipher, &data_key.wrapped_key, data_key.tenant_id.as_bytes())?;
+        Aes256Gcm::new_from_slice(&raw).map_err(|_| SecretsError::Crypto)
+    }
+}
+
+/// Base64 of nonce || ciphertext.
+fn seal(cipher: &Aes256Gcm, plaintext: &[u8], aad: &[u8]) -> Result<String, SecretsError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad })
+        .map_err(|_| SecretsError::Crypto)?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(sealed))
+}
+
+fn open(cipher: &Aes256Gcm, sealed: &str, aad: &[u8]) -> Result<Vec<u8>, SecretsError> {
+    let bytes = STANDARD.decode(sealed).map_err(|_| SecretsError::Crypto)?;
+    if bytes.len() < NONCE_LEN {
+        return Err(SecretsError::Crypto);
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| SecretsError::Crypto)
+}
+
+/// Binds a ciphertext to its row so values can't be swapped between settings or tenants.
+fn setting_aad(tenant_id: Uuid, key: &str) -> Vec<u8> {
+    format!("{}:{}", tenant_id, key).into_bytes()
+}
+
+pub struct SecretsService;
+
+impl SecretsService {
+    /// Encrypts a setting value under the tenant's active data key, creating the key on
+    /// first use. Returns the ciphertext and the data key id to store alongside it.
+    pub async fn encrypt_value<C: ConnectionTrait>(
+        conn: &C,
+        keyring: &Keyring,
+        tenant_id: Uuid,
+        key: &str,
+        plaintext: &str,
+    ) -> Result<(String, Uuid), SecretsError> {
+        let (data_key_id, cipher) = Self::active_data_key(conn, keyring, tenant_id).await?;
+        let ciphertext = seal(&cipher, plaintext.as_bytes(), &setting_aad(tenant_id, key))?;
+        Ok((ciphertext, data_key_id))
+    }
+
+    /// The plaintext of a setting. Rows flagged `is_encrypted` that predate encryption
+    /// have no data key and are returned as stored.
+    pub async fn reveal<C: ConnectionTrait>(
+        conn: &C,
+        keyring: &Keyring,
+        setting: &tenant_setting::Model,
+    ) -> Result<String, SecretsError> {
+        let Some(data_key_id) = setting.data_key_id else {
+            return Ok(setting.value.clone());
+        };
+
+        let data_key = tenant_data_key::Entity::find_by_id(data_key_id)
+            .filter(tenant_data_key::Column::TenantId.eq(setting.tenant_id))
+            .one(conn)
+            .await?
+            .ok_or(SecretsError::Crypto)?;
+        let cipher = keyring.unwrap_data_key(&data_key)?;
+        let plaintext = open(&cipher, &setting.value, &setting_aad(setting.tenant_id, &setting.key))?;
+        String::from_utf8(plaintext).map_err(|_| SecretsError::Crypto)
+    }
+
+    /// Every setting of a tenant, decrypted, keyed by setting key. For server-side consumers.
+    pub async fn load_settings<C: ConnectionTrait>(
+        conn: &C,
+        keyring: &Keyring,
+        tenant_id: Uuid,
+    ) -> Result<HashMap<String, String>, SecretsError> {
+        let settings = tenant_setting::Entity::find()
+            .filter(tenant_setting::Column::TenantId.eq(tenant_id))
+            .all(conn)
+            .await?;
+
+        let mut values = HashMap::new();
+        for setting in settings {
+            let value = Self::reveal(conn, keyring, &setting).await?;
+            values.insert(setting.key, value);
+        }
+        Ok(values)
+    }
+
+    /// Hides the value of an encrypted setting.
+    pub fn mask(mut setting: tenant_setting::Model) -> tenant_setting::Model {
+        if setting.is_encrypted {
+            setting.value = MASKED_VALUE.to_string();
+        }
+        setting
+    }
+
+    /// Encrypts rows flagged `is_encrypted` that are still plaintext. Returns how many were encrypted.
+    pub async fn encrypt_pending(db: &DatabaseConnection, keyring: &Keyring) -> Result<usize, SecretsError> {
+        let pending = tenant_setting::Entity::find()
+            .filter(tenant_setting::Column::IsEncrypted.eq(true))
+            .filter(tenant_setting::Column::DataKeyId.is_null())
+            .all(db)
+            .await?;
+
+        let count = pending.len();
+        for setting in pending {
+            let txn = db.begin().await?;
+            let (ciphertext, data_key_id) =
+                Self::encrypt_value(&txn, keyring, setting.tenant_id, &setting.key, &setting.value).await?;
+            let mut active: tenant_setting::ActiveModel = setting.into();
+            active.value = Set(ciphertext);
+            active.data_key_id = Set(Some(data_key_id));
+            active.updated_at = Set(Utc::now());
+            active.update(&txn).await?;
+            txn.commit().await?;
+        }
+        Ok(count)
+    }
+
+    /// Re-wraps the data keys of every tenant (or just `tenant_id`) still wrapped by a retired
+    /// master key with the current one. Setting values are untouched. Returns how many data
+    /// keys were re-wrapped.
+    pub async fn rotate_master_key(
+        db: &DatabaseConnection,
+        keyring: &Keyring,
+        tenant_id: Option<Uuid>,
+    ) -> Result<usize, SecretsError> {
+        let mut stale = tenant_data_key::Entity::find()
+            .filter(tenant_data_key::Column::MasterKeyId.ne(keyring.current_id()));
+        if let Some(tenant_id) = tenant_id {
+            stale = stale.filter(tenant_data_key::Column::TenantId.eq(tenant_id));
+        }
+        let stale = stale.all(db).await?;
+
+        let count = stale.len();
+        for data_key in stale {
+            let master = keyring.master(&data_key.master_key_id)?;
+            let raw = open(&master.cipher, &data_key.wrapped_key, data_key.tenant_id.as_bytes())?;
+            let wrapped_key = seal(&keyring.current.cipher, &raw, data_key.tenant_id.as_bytes())?;
+
+            let mut active: tenant_data_key::ActiveModel = data_key.into();
+            active.wrapped_key = Set(wrapped_key);
+            active.master_key_id = Set(keyring.current_id().to_string());
+            active.update(db).await?;
+        }
+        Ok(count)
+    }
+
+    /// Gives each tenant (or just `tenant_id`) a fresh data key, re-encrypts its settings
+    /// under it and deletes the old keys. Returns how many settings were re-encrypted.
+    pub async fn rotate_data_keys(
+        db: &DatabaseConnection,
+        keyring: &Keyring,
+        tenant_id: Option<Uuid>,
+    ) -> Result<usize, SecretsError> {
+        let mut tenants = tenant_data_key::Entity::find()
+            .select_only()
+            .column(tenant_data_key::Column::TenantId)
+            .distinct();
+        if let Some(tenant_id) = tenant_id {
+            tenants = tenants.filter(tenant_data_key::Column::TenantId.eq(tenant_id));
+        }
+        let tenant_ids: Vec<Uuid> = tenants.into_tuple().all(db).await?;
+
+        let mut count = 0;
+        for tenant_id in tenant_ids {
+            let txn = db.begin().await?;
+            let settings = tenant_setting::Entity::find()
+                .filter(tenant_setting::Column::TenantId.eq(tenant_id))
+                .filter(tenant_setting::Column::DataKeyId.is_not_null())
+                .lock(sea_orm::sea_query::LockType::Update)
+                .all(&txn)
+                .await?;
+
+            let mut plaintexts = Vec::with_capacity(settings.len());
+            for setting in &settings {
+                plaintexts.push(Self::reveal(&txn, keyring, setting).await?);
+            }
+
+            tenant_data_key::Entity::update_many()
+                .col_expr(tenant_data_key::Column::IsActive, sea_orm::sea_query::Expr::value(false))
+                .col_expr(tenant_data_key::Column::RetiredAt, sea_orm::sea_query::Expr::value(Utc::now()))
+                .filter(tenant_data_key::Column::TenantId.eq(tenant_id))
+                .filter(tenant_data_key::Column::IsActive.eq(true))
+                .exec(&txn)
+                .await?;
+
+            for (setting, plaintext) in settings.into_iter().zip(plaintexts) {
+                let (ciphertext, data_key_id) =
+                    Self::encrypt_value(&txn, keyring, tenant_id, &setting.key, &plaintext).await?;
+                let mut active: tenant_setting::ActiveModel = setting.into();
+                active.value = Set(ciphertext);
+                active.data_key_id = Set(Some(data_key_id));
+                active.updated_at = Set(Utc::now());
+                active.update(&txn).await?;
+                count += 1;
+            }
+
+            // Nothing references the retired keys any more
+            tenant_data_key::Entity::delete_many()
+                .filter(tenant_data_key::Column::TenantId.eq(tenant_id))
+                .filter(tenant_data_key::Column::IsActive.eq(false))
+                .exec(&txn)
+                .await?;
+            txn.commit().await?;
+        }
+        Ok(count)
+    }
+
+    async fn active_data_key<C: ConnectionTrait>(
+        conn: &C,
+        keyring: &Keyring,
+        tenant_id: Uuid,
+    ) -> Result<(Uuid, Aes256Gcm), SecretsError> {
+        let find_active = || {
+            tenant_data_key::Entity::find()
+                .filter(tenant_data_key::Column::TenantId.eq(tenant_id))
+                .filter(tenant_data_key::Column::IsActive.eq(true))
+                .one(conn)
+        };
+
+        if let Some(data_key) = find_active().await? {
+            return Ok((data_key.id, keyring.unwrap_data_key(&data_key)?));
+        }
+
+        let raw = Aes256Gcm::generate_key(OsRng);
+        let wrapped_key = seal(&keyring.current.cipher, &raw, tenant_id.as_bytes())?;
+        conn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO tenant_data_keys (id, tenant_id, wrapped_key, master_key_id, is_active, created_at)
+               VALUES ($1, $2, $3, $4, TRUE, $5)
+               ON CONFLICT (tenant_id) WHERE is_active DO NOTHING"#,
+            [
+                Uuid::new_v4().into(),
+                tenant_id.into(),
+                wrapped_key.into(),
+                keyring.current_id().into(),
+                Utc::now().into(),
+            ],
+        ))
+        .await?;
+
+        // A concurrent writer may have created the key first
+        let data_key = find_active().await?.ok_or(SecretsError::Crypto)?;
+        Ok((data_key.id, keyring.unwrap_data_key(&data_key)?))
+    }
+}
@@ -0,0 +1,794 @@
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseTransaction, DbBackend, DbErr,
+    EntityTrait, QueryFilter, Set, Statement, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::entities::{app_instance, app_menu, app_page, category};
+use crate::services::audit::{AuditEntry, AuditService};
+
+/// Extension of seed pack files; `.sql` files next to them are legacy seeds and never applied.
+pub const PACK_EXTENSION: &str = "json";
+
+/// Prefix of a block string that resolves to the id of a form schema seeded by the same pack,
+/// e.g. `"form_id": "@form:cre-application"`.
+const FORM_REF_PREFIX: &str = "@form:";
+
+/// A declarative set of tenant content, stored as `seeds/<app_type>/<name>.json`.
+///
+/// Applying a pack creates or updates each item by its natural key (page slug, menu type and
+/// label, form slug, category name) and never deletes content the tenant added, so a pack can
+/// be re-applied after it is edited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeedPack {
+    pub name: String,
+    /// Bumped whenever the pack's content changes.
+    pub version: i32,
+    pub app_type: String,
+    #[serde(default)]
+    pub description: String,
+    /// Merged into the instance's `settings.design_config`.
+    #[serde(default)]
+    pub design_tokens: Map<String, Value>,
+    #[serde(default)]
+    pub pages: Vec<PageSeed>,
+    /// Parents are listed before their children.
+    #[serde(default)]
+    pub menus: Vec<MenuSeed>,
+    #[serde(default)]
+    pub form_schemas: Vec<FormSeed>,
+    /// Parents are listed before their children.
+    #[serde(default)]
+    pub categories: Vec<CategorySeed>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PageSeed {
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "default_page_type")]
+    pub page_type: String,
+    #[serde(default = "empty_object")]
+    pub hero: Value,
+    #[serde(default)]
+    pub blocks: Vec<Value>,
+    #[serde(default = "default_true")]
+    pub published: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MenuSeed {
+    #[serde(default = "default_menu_type")]
+    pub menu_type: String,
+    pub label: String,
+    #[serde(default)]
+    pub href: Option<String>,
+    /// Label of the parent item in the same menu.
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub display_order: i32,
+    #[serde(default = "default_true")]
+    pub visible: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FormSeed {
+    pub slug: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub schema: Value,
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    #[serde(default = "default_true")]
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CategorySeed {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Name of the parent category.
+    #[serde(default)]
+    pub parent: Option<String>,
+}
+
+fn default_page_type() -> String {
+    "landing".to_string()
+}
+
+fn default_menu_type() -> String {
+    "header".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn empty_object() -> Value {
+    json!({})
+}
+
+#[derive(Debug)]
+pub enum SeedPackError {
+    NotFound(String),
+    Invalid(Vec<String>),
+    Io(std::io::Error),
+    Db(DbErr),
+}
+
+impl std::fmt::Display for SeedPackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(name) => write!(f, "Seed pack '{}' not found", name),
+            Self::Invalid(problems) => write!(f, "Invalid seed pack: {}", problems.join("; ")),
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SeedPackError {}
+
+impl From<DbErr> for SeedPackError {
+    fn from(e: DbErr) -> Self {
+        Self::Db(e)
+    }
+}
+
+impl From<std::io::Error> for SeedPackError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeedAction {
+    Create,
+    Update,
+}
+
+/// One item the pack creates or changes.
+#[derive(Debug, Serialize)]
+pub struct SeedChange {
+    pub entity: &'static str,
+    pub key: String,
+    pub action: SeedAction,
+    /// Fields that differ from the stored row; empty for creates.
+    pub fields: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeedReport {
+    pub app_instance_id: Uuid,
+    pub pack: String,
+    pub version: i32,
+    /// Version applied before this run, if any.
+    pub previous_version: Option<i32>,
+    pub dry_run: bool,
+    pub changes: Vec<SeedChange>,
+    /// Items that already match the pack.
+    pub unchanged: usize,
+}
+
+impl SeedReport {
+    fn record(&mut self, entity: &'static str, key: impl Into<String>, fields: Option<Vec<&'static str>>) {
+        match fields {
+            None => self.changes.push(SeedChange { entity, key: key.into(), action: SeedAction::Create, fields: vec![] }),
+            Some(fields) if fields.is_empty() => self.unchanged += 1,
+            Some(fields) => self.changes.push(SeedChange { entity, key: key.into(), action: SeedAction::Update, fields }),
+        }
+    }
+}
+
+/// A pack file as listed for an app type, with any problems that keep it from being applied.
+#[derive(Debug, Serialize)]
+pub struct PackListing {
+    pub name: String,
+    pub version: Option<i32>,
+    pub description: Option<String>,
+    pub errors: Vec<String>,
+}
+
+/// Directory holding `<app_type>/<name>.json` packs: `SEED_PACKS_DIR`, or `seeds/` at the
+/// workspace root.
+pub fn seeds_root() -> PathBuf {
+    std::env::var("SEED_PACKS_DIR").map(PathBuf::from).unwrap_or_else(|_| {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .parent()
+            .unwrap()
+            .join("seeds")
+    })
+}
+
+/// Pack and app type names become path segments, so they are limited to a safe alphabet.
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+impl SeedPack {
+    /// Parses a pack and checks it against the app type and name it is stored under.
+    pub fn parse(bytes: &[u8], app_type: &str, name: &str) -> Result<Self, SeedPackError> {
+        let pack: SeedPack =
+            serde_json::from_slice(bytes).map_err(|e| SeedPackError::Invalid(vec![e.to_string()]))?;
+        let problems = pack.validate(app_type, name);
+        if problems.is_empty() {
+            Ok(pack)
+        } else {
+            Err(SeedPackError::Invalid(problems))
+        }
+    }
+
+    pub fn validate(&self, app_type: &str, name: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.name != name {
+            problems.push(format!("name '{}' does not match the file name '{}'", self.name, name));
+        }
+        if !self.app_type.eq_ignore_ascii_case(app_type) {
+            problems.push(format!("app_type '{}' does not match the directory '{}'", self.app_type, app_type));
+        }
+        if self.version < 1 {
+            problems.push("version must be at least 1".to_string());
+        }
+
+        let mut slugs = HashSet::new();
+        for page in &self.pages {
+            if page.slug.trim().is_empty() || page.slug.starts_with('/') {
+                problems.push(format!("page slug '{}' must be non-empty and relative", page.slug));
+            }
+            if !slugs.insert(page.slug.as_str()) {
+                problems.push(format!("duplicate page '{}'", page.slug));
+            }
+            if !page.hero.is_object() {
+                problems.push(format!("page '{}': hero must be an object", page.slug));
+            }
+        }
+
+        let mut forms = HashSet::new();
+        for form in &self.form_schemas {
+            if !is_safe_name(&form.slug) {
+                problems.push(format!("form slug '{}' may only contain letters, digits, '-' and '_'", form.slug));
+            }
+            if !forms.insert(form.slug.as_str()) {
+                problems.push(format!("duplicate form schema '{}'", form.slug));
+            }
+            if !form.schema.is_object() {
+                problems.push(format!("form schema '{}': schema must be an object", form.slug));
+            }
+        }
+        for page in &self.pages {
+            let mut refs = Vec::new();
+            collect_form_refs(&Value::Array(page.blocks.clone()), &mut refs);
+            for slug in refs {
+                if !forms.contains(slug.as_str()) {
+                    problems.push(format!("page '{}' references unknown form '{}'", page.slug, slug));
+                }
+            }
+        }
+
+        let mut menus = HashSet::new();
+        for menu in &self.menus {
+            if menu.label.trim().is_empty() {
+                problems.push("menu items need a label".to_string());
+            }
+            if let Some(parent) = &menu.parent
+                && !menus.contains(&(menu.menu_type.as_str(), parent.as_str()))
+            {
+                problems.push(format!(
+                    "menu item '{}': parent '{}' must be listed before it in the {} menu",
+                    menu.label, parent, menu.menu_type
+                ));
+            }
+            if !menus.insert((menu.menu_type.as_str(), menu.label.as_str())) {
+                problems.push(format!("duplicate {} menu item '{}'", menu.menu_type, menu.label));
+            }
+        }
+
+        let mut categories = HashSet::new();
+        for cat in &self.categories {
+            if cat.name.trim().is_empty() {
+                problems.push("categories need a name".to_string());
+            }
+            if let Some(parent) = &cat.parent
+                && !categories.contains(parent.as_str())
+            {
+                problems.push(format!("category '{}': parent '{}' must be listed before it", cat.name, parent));
+            }
+            if !categories.insert(cat.name.as_str()) {
+                problems.push(format!("duplicate category '{}'", cat.name));
+            }
+        }
+
+        problems
+    }
+}
+
+fn collect_form_refs(value: &Value, refs: &mut Vec<String>) {
+    match value {
+        Value::String(s) => {
+            if let Some(slug) = s.strip_prefix(FORM_REF_PREFIX) {
+                refs.push(slug.to_string());
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_form_refs(v, refs)),
+        Value::Object(map) => map.values().for_each(|v| collect_form_refs(v, refs)),
+        _ => {}
+    }
+}
+
+fn resolve_form_refs(value: &Value, forms: &HashMap<String, Uuid>) -> Value {
+    match value {
+        Value::String(s) => match s.strip_prefix(FORM_REF_PREFIX).and_then(|slug| forms.get(slug)) {
+            Some(id) => Value::String(id.to_string()),
+            None => value.clone(),
+        },
+        Value::Array(items) => Value::Array(items.iter().map(|v| resolve_form_refs(v, forms)).collect()),
+        Value::Object(map) => {
+            Value::Object(map.iter().map(|(k, v)| (k.clone(), resolve_form_refs(v, forms))).collect())
+        }
+        _ => value.clone(),
+    }
+}
+
+pub struct SeedPacks;
+
+impl SeedPacks {
+    /// Lists the `.json` packs for an app type, including ones that fail validation.
+    pub fn list(app_type: &str) -> Vec<PackListing> {
+        if !is_safe_name(app_type) {
+            return vec![];
+        }
+        let Ok(entries) = std::fs::read_dir(seeds_root().join(app_type)) else {
+            return vec![];
+        };
+
+        let mut packs: Vec<PackListing> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == PACK_EXTENSION))
+            .filter_map(|path| {
+                let name = path.file_stem()?.to_str()?.to_string();
+                Some(match Self::load(app_type, &name) {
+                    Ok(pack) => PackListing {
+                        name,
+                        version: Some(pack.version),
+                        description: Some(pack.description),
+                        errors: vec![],
+                    },
+                    Err(SeedPackError::Invalid(errors)) => {
+                        PackListing { name, version: None, description: None, errors }
+                    }
+                    Err(e) => PackListing { name, version: None, description: None, errors: vec![e.to_string()] },
+                })
+            })
+            .collect();
+        packs.sort_by(|a, b| a.name.cmp(&b.name));
+        packs
+    }
+
+    /// Whether `name` is a pack for `app_type`, as opposed to a legacy `.sql` seed.
+    pub fn exists(app_type: &str, name: &str) -> bool {
+        is_safe_name(app_type) && is_safe_name(name) && Self::path(app_type, name).is_file()
+    }
+
+    pub fn load(app_type: &str, name: &str) -> Result<SeedPack, SeedPackError> {
+        if !Self::exists(app_type, name) {
+            return Err(SeedPackError::NotFound(name.to_string()));
+        }
+        SeedPack::parse(&std::fs::read(Self::path(app_type, name))?, app_type, name)
+    }
+
+    fn path(app_type: &str, name: &str) -> PathBuf {
+        seeds_root().join(app_type).join(format!("{}.{}", name, PACK_EXTENSION))
+    }
+
+    /// Applies the pack to the instance's tenant and records it as the instance's seed.
+    ///
+    /// Everything runs in one transaction (a savepoint inside a caller's transaction); a dry run
+    /// rolls it back, so its report is exactly what a real run would change.
+    pub async fn apply<C: ConnectionTrait + TransactionTrait>(
+        db: &C,
+        instance: &app_instance::Model,
+        pack: &SeedPack,
+        actor_id: Option<Uuid>,
+        dry_run: bool,
+    ) -> Result<SeedReport, SeedPackError> {
+        let mut report = SeedReport {
+            app_instance_id: instance.id,
+            pack: pack.name.clone(),
+            version: pack.version,
+            previous_version: instance.data_seed_version,
+            dry_run,
+            changes: vec![],
+            unchanged: 0,
+        };
+
+        let txn = db.begin().await?;
+        let forms = apply_form_schemas(&txn, instance.tenant_id, pack, &mut report).await?;
+        apply_pages(&txn, instance.tenant_id, pack, &forms, &mut report).await?;
+        apply_menus(&txn, instance.tenant_id, pack, &mut report).await?;
+        apply_categories(&txn, instance.tenant_id, pack, &mut report).await?;
+        apply_design_tokens(&txn, instance, pack, &mut report).await?;
+
+        if dry_run {
+            txn.rollback().await?;
+            return Ok(report);
+        }
+
+        let mut active: app_instance::ActiveModel = instance.clone().into();
+        active.data_seed_name = Set(Some(pack.name.clone()));
+        active.data_seed_version = Set(Some(pack.version));
+        active.data_seed_applied_at = Set(Some(Utc::now()));
+        active.updated_at = Set(Utc::now());
+        active.update(&txn).await?;
+
+        AuditService::log_action(
+            &txn,
+            AuditEntry {
+                tenant_id: Some(instance.tenant_id),
+                actor_id,
+                action_type: "app_instance.seed_pack_applied".to_string(),
+                entity_type: "app_instance".to_string(),
+                entity_id: instance.id,
+                old_state: instance.data_seed_version.map(|v| json!({ "pack": instance.data_seed_name, "version": v })),
+                new_state: Some(json!({ "pack": pack.name, "version": pack.version, "changes": report.changes.len() })),
+                ip_address: None,
+            },
+        )
+        .await?;
+        txn.commit().await?;
+
+        crate::middleware::site_context::invalidate_tenant(instance.tenant_id);
+        Ok(report)
+    }
+}
+
+/// Creates or updates the pack's form schemas and returns every seeded form's id by slug.
+async fn apply_form_schemas(
+    txn: &DatabaseTransaction,
+    tenant_id: Uuid,
+    pack: &SeedPack,
+    report: &mut SeedReport,
+) -> Result<HashMap<String, Uuid>, DbErr> {
+    let mut ids = HashMap::new();
+    for form in &pack.form_schemas {
+        let existing = txn
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT id, name, description, schema_json, webhook_url, is_active
+                 FROM form_schemas WHERE tenant_id = $1 AND slug = $2",
+                vec![tenant_id.into(), form.slug.clone().into()],
+            ))
+            .await?;
+
+        let Some(row) = existing else {
+            let id = Uuid::new_v4();
+            txn.execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "INSERT INTO form_schemas (id, tenant_id, name, slug, description, schema_json, webhook_url, is_active)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                vec![
+                    id.into(),
+                    tenant_id.into(),
+                    form.name.clone().into(),
+                    form.slug.clone().into(),
+                    form.description.clone().into(),
+                    form.schema.clone().into(),
+                    form.webhook_url.clone().into(),
+                    form.active.into(),
+                ],
+            ))
+            .await?;
+            ids.insert(form.slug.clone(), id);
+            report.record("form_schema", &form.slug, None);
+            continue;
+        };
+
+        let id: Uuid = row.try_get("", "id")?;
+        let mut fields = vec![];
+        if row.try_get::<String>("", "name")? != form.name {
+            fields.push("name");
+        }
+        if row.try_get::<Option<String>>("", "description")? != form.description {
+            fields.push("description");
+        }
+        if row.try_get::<Value>("", "schema_json")? != form.schema {
+            fields.push("schema");
+        }
+        if row.try_get::<Option<String>>("", "webhook_url")? != form.webhook_url {
+            fields.push("webhook_url");
+        }
+        if row.try_get::<bool>("", "is_active")? != form.active {
+            fields.push("active");
+        }
+        if !fields.is_empty() {
+            txn.execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "UPDATE form_schemas
+                 SET name = $2, description = $3, schema_json = $4, webhook_url = $5, is_active = $6, updated_at = NOW()
+                 WHERE id = $1",
+                vec![
+                    id.into(),
+                    form.name.clone().into(),
+                    form.description.clone().into(),
+                    form.schema.clone().into(),
+                    form.webhook_url.clone().into(),
+                    form.active.into(),
+                ],
+            ))
+            .await?;
+        }
+        ids.insert(form.slug.clone(), id);
+        report.record("form_schema", &form.slug, Some(fields));
+    }
+    Ok(ids)
+}
+
+async fn apply_pages(
+    txn: &DatabaseTransaction,
+    tenant_id: Uuid,
+    pack: &SeedPack,
+    forms: &HashMap<String, Uuid>,
+    report: &mut SeedReport,
+) -> Result<(), DbErr> {
+    for page in &pack.pages {
+        let blocks = resolve_form_refs(&Value::Array(page.blocks.clone()), forms);
+        let existing = app_page::Entity::find()
+            .filter(app_page::Column::TenantId.eq(tenant_id))
+            .filter(app_page::Column::Slug.eq(page.slug.as_str()))
+            .one(txn)
+            .await?;
+
+        let Some(existing) = existing else {
+            app_page::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                tenant_id: Set(tenant_id),
+                slug: Set(page.slug.clone()),
+                title: Set(page.title.clone()),
+                description: Set(page.description.clone()),
+                page_type: Set(page.page_type.clone()),
+                hero_payload: Set(Some(page.hero.clone())),
+                blocks_payload: Set(Some(blocks)),
+                is_published: Set(page.published),
+                created_at: Set(Utc::now()),
+                updated_at: Set(Utc::now()),
+            }
+            .insert(txn)
+            .await?;
+            report.record("page", &page.slug, None);
+            continue;
+        };
+
+        let mut fields = vec![];
+        if existing.title != page.title {
+            fields.push("title");
+        }
+        if existing.description != page.description {
+            fields.push("description");
+        }
+        if existing.page_type != page.page_type {
+            fields.push("page_type");
+        }
+        if existing.hero_payload.as_ref() != Some(&page.hero) {
+            fields.push("hero");
+        }
+        if existing.blocks_payload.as_ref() != Some(&blocks) {
+            fields.push("blocks");
+        }
+        if existing.is_published != page.published {
+            fields.push("published");
+        }
+        if !fields.is_empty() {
+            let mut active: app_page::ActiveModel = existing.into();
+            active.title = Set(page.title.clone());
+            active.description = Set(page.description.clone());
+            active.page_type = Set(page.page_type.clone());
+            active.hero_payload = Set(Some(page.hero.clone()));
+            active.blocks_payload = Set(Some(blocks));
+            active.is_published = Set(page.published);
+            active.updated_at = Set(Utc::now());
+            active.update(txn).await?;
+        }
+        report.record("page", &page.slug, Some(fields));
+    }
+    Ok(())
+}
+
+async fn apply_menus(
+    txn: &DatabaseTransaction,
+    tenant_id: Uuid,
+    pack: &SeedPack,
+    report: &mut SeedReport,
+) -> Result<(), DbErr> {
+    // Ids of the pack's items by (menu type, label), for resolving parents
+    let mut ids: HashMap<(String, String), Uuid> = HashMap::new();
+    for menu in &pack.menus {
+        let key = format!("{}/{}", menu.menu_type, menu.label);
+        let parent_id = menu
+            .parent
+            .as_ref()
+            .and_then(|parent| ids.get(&(menu.menu_type.clone(), parent.clone())).copied());
+        let existing = app_menu::Entity::find()
+            .filter(app_menu::Column::TenantId.eq(tenant_id))
+            .filter(app_menu::Column::MenuType.eq(menu.menu_type.as_str()))
+            .filter(app_menu::Column::Label.eq(menu.label.as_str()))
+            .one(txn)
+            .await?;
+
+        let id = match existing {
+            None => {
+                let inserted = app_menu::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    tenant_id: Set(tenant_id),
+                    menu_type: Set(menu.menu_type.clone()),
+                    label: Set(menu.label.clone()),
+                    href: Set(menu.href.clone()),
+                    parent_id: Set(parent_id),
+                    display_order: Set(menu.display_order),
+                    is_visible: Set(menu.visible),
+                    created_at: Set(Utc::now()),
+                    updated_at: Set(Utc::now()),
+                }
+                .insert(txn)
+                .await?;
+                report.record("menu", key, None);
+                inserted.id
+            }
+            Some(existing) => {
+                let mut fields = vec![];
+                if existing.href != menu.href {
+                    fields.push("href");
+                }
+                if existing.parent_id != parent_id {
+                    fields.push("parent");
+                }
+                if existing.display_order != menu.display_order {
+                    fields.push("display_order");
+                }
+                if existing.is_visible != menu.visible {
+                    fields.push("visible");
+                }
+                let id = existing.id;
+                if !fields.is_empty() {
+                    let mut active: app_menu::ActiveModel = existing.into();
+                    active.href = Set(menu.href.clone());
+                    active.parent_id = Set(parent_id);
+                    active.display_order = Set(menu.display_order);
+                    active.is_visible = Set(menu.visible);
+                    active.updated_at = Set(Utc::now());
+                    active.update(txn).await?;
+                }
+                report.record("menu", key, Some(fields));
+                id
+            }
+        };
+        ids.insert((menu.menu_type.clone(), menu.label.clone()), id);
+    }
+    Ok(())
+}
+
+async fn apply_categories(
+    txn: &DatabaseTransaction,
+    tenant_id: Uuid,
+    pack: &SeedPack,
+    report: &mut SeedReport,
+) -> Result<(), DbErr> {
+    // Category slugs are unique across tenants, so seeded categories are matched by name
+    let mut ids: HashMap<String, Uuid> = HashMap::new();
+    for cat in &pack.categories {
+        let parent_id = cat.parent.as_ref().and_then(|parent| ids.get(parent).copied());
+        let existing = category::Entity::find()
+            .filter(category::Column::TenantId.eq(tenant_id))
+            .filter(category::Column::Name.eq(cat.name.as_str()))
+            .one(txn)
+            .await?;
+
+        let id = match existing {
+            None => {
+                let inserted = category::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    parent_category_id: Set(parent_id),
+                    name: Set(cat.name.clone()),
+                    description: Set(cat.description.clone()),
+                    icon: Set(cat.icon.clone()),
+                    slug: Set(None),
+                    is_custom: Set(true),
+                    is_active: Set(true),
+                    created_at: Set(Utc::now()),
+                    updated_at: Set(Utc::now()),
+                    tenant_id: Set(Some(tenant_id)),
+                }
+                .insert(txn)
+                .await?;
+                report.record("category", &cat.name, None);
+                inserted.id
+            }
+            Some(existing) => {
+                let mut fields = vec![];
+                if existing.description != cat.description {
+                    fields.push("description");
+                }
+                if existing.icon != cat.icon {
+                    fields.push("icon");
+                }
+                if existing.parent_category_id != parent_id {
+                    fields.push("parent");
+                }
+                let id = existing.id;
+                if !fields.is_empty() {
+                    let mut active: category::ActiveModel = existing.into();
+                    active.description = Set(cat.description.clone());
+                    active.icon = Set(cat.icon.clone());
+                    active.parent_category_id = Set(parent_id);
+                    active.updated_at = Set(Utc::now());
+                    active.update(txn).await?;
+                }
+                report.record("category", &cat.name, Some(fields));
+                id
+            }
+        };
+        ids.insert(cat.name.clone(), id);
+    }
+    Ok(())
+}
+
+async fn apply_design_tokens(
+    txn: &DatabaseTransaction,
+    instance: &app_instance::Model,
+    pack: &SeedPack,
+    report: &mut SeedReport,
+) -> Result<(), DbErr> {
+    if pack.design_tokens.is_empty() {
+        return Ok(());
+    }
+
+    let mut settings = instance.settings.clone().filter(Value::is_object).unwrap_or_else(empty_object);
+    let config = settings
+        .as_object_mut()
+        .unwrap()
+        .entry("design_config")
+        .or_insert_with(empty_object);
+    if !config.is_object() {
+        *config = empty_object();
+    }
+    let config = config.as_object_mut().unwrap();
+
+    let mut changed = false;
+    for (token, value) in &pack.design_tokens {
+        match config.get(token) {
+            Some(current) if current == value => report.record("design_token", token, Some(vec![])),
+            current => {
+                report.record("design_token", token, current.map(|_| vec!["value"]));
+                config.insert(token.clone(), value.clone());
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "UPDATE app_instances SET settings = $2, updated_at = NOW() WHERE id = $1",
+            vec![instance.id.into(), settings.into()],
+        ))
+        .await?;
+    }
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use crate::entities::{platform_metrics_daily, telemetry_events};
+use crate::services::outbox::{Outbox, OutboxMessage};
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, DatabaseTransaction, TransactionTrait
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, Set, TransactionTrait
 };
 use chrono::{Utc, NaiveDate, Datelike};
 use sea_orm::{sea_query::OnConflict, Condition};
@@ -11,29 +12,24 @@ use uuid::Uuid;
 pub struct TelemetryService;
 
 impl TelemetryService {
-    /// Non-blocking telemetry ingestion
-    pub fn log_event(
-        db: DatabaseConnection,
+    /// Records a telemetry event in the outbox on `conn`; the relay copies it into
+    /// `telemetry_events` for the daily aggregation.
+    pub async fn log_event<C: ConnectionTrait>(
+        conn: &C,
         tenant_id: Uuid,
         event_source: String,
         event_type: String,
         event_payload: Option<Value>,
-    ) {
-        tokio::spawn(async move {
-            let active_model = telemetry_events::ActiveModel {
-                id: Set(Uuid::new_v4()),
-                tenant_id: Set(tenant_id),
-                event_source: Set(event_source),
-                event_type: Set(event_type),
-                event_payload: Set(event_payload),
-                timestamp: Set(Utc::now()),
-                processed: Set(false),
-            };
-
-            if let Err(e) = active_model.insert(&db).await {
-                tracing::error!("Failed to asynchronously insert telemetry event: {}", e);
-            }
-        });
+    ) -> Result<(), sea_orm::DbErr> {
+        Outbox::enqueue(conn, OutboxMessage::Telemetry {
+            tenant_id,
+            event_source,
+            event_type,
+            event_payload,
+            occurred_at: Utc::now(),
+        })
+        .await
+        .map(|_| ())
     }
 
     /// Background cron task to aggregate events into daily KPIs
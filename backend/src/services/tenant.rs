@@ -1,8 +1,108 @@
-use sea_orm::{DatabaseConnection, EntityTrait, ActiveModelTrait, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, QueryFilter,
+    QuerySelect, Set, Statement, TransactionTrait,
+};
 use uuid::Uuid;
 use anyhow::Result;
-use crate::entities::tenant;
+use chrono::{Duration, Utc};
+use serde_json::json;
+use crate::entities::{account, app_instance, tenant, tenant_background_job, user_account};
+use crate::middleware::site_context::invalidate_tenant;
 use crate::models::tenant::{CreateTenant, UpdateTenant};
+use crate::services::app_lifecycle::AppLifecycle;
+use crate::services::audit::{AuditEntry, AuditService};
+use crate::services::job_scheduler::JobScheduler;
+use crate::traits::atlas_app::BackgroundJob;
+
+// Lifecycle: active <-> suspended, active/suspended -> scheduled_for_deletion -> purged.
+// A scheduled deletion can be cancelled until the purge job runs.
+pub const STATUS_ACTIVE: &str = "active";
+pub const STATUS_SUSPENDED: &str = "suspended";
+pub const STATUS_SCHEDULED_FOR_DELETION: &str = "scheduled_for_deletion";
+pub const STATUS_PURGED: &str = "purged";
+
+pub const PURGE_JOB_TYPE: &str = "TenantPurge";
+
+const DEFAULT_DELETION_GRACE_DAYS: i64 = 30;
+
+/// Longest grace period a deletion can be scheduled with.
+pub const MAX_DELETION_GRACE_DAYS: i64 = 365;
+
+/// Tenant-owned rows the purge removes, children before parents. Each filter binds the
+/// tenant id as `$1`. Billing records and audit logs are kept.
+const PURGED_ROWS: &[(&str, &str)] = &[
+    // CRM
+    ("activity", "tenant_id = $1"),
+    ("notes", "tenant_id = $1"),
+    ("case", "tenant_id = $1"),
+    ("case_sla_policy", "tenant_id = $1"),
+    ("lead", "tenant_id = $1"),
+    ("deal_contact", "deal_id IN (SELECT id FROM deal WHERE tenant_id = $1)"),
+    ("deal_stage_history", "tenant_id = $1"),
+    ("deal", "tenant_id = $1"),
+    ("deal_pipeline", "tenant_id = $1"),
+    ("contact", "tenant_id = $1"),
+    ("customer", "tenant_id = $1"),
+    ("custom_field_definition", "tenant_id = $1"),
+    // Listings
+    (
+        "listing_ab_variant",
+        "test_id IN (SELECT t.id FROM listing_ab_test t JOIN listing l ON l.id = t.listing_id WHERE l.tenant_id = $1)",
+    ),
+    ("listing_ab_test", "listing_id IN (SELECT id FROM listing WHERE tenant_id = $1)"),
+    (
+        "ad_purchase",
+        "listing_id IN (SELECT id FROM listing WHERE tenant_id = $1) OR profile_id IN (SELECT id FROM profile WHERE tenant_id = $1)",
+    ),
+    ("listing", "tenant_id = $1"),
+    ("profile", "tenant_id = $1"),
+    ("template", "tenant_id = $1"),
+    ("category", "tenant_id = $1"),
+    // Feeds and files; items and associations cascade
+    ("feed", "tenant_id = $1"),
+    ("files", "tenant_id = $1"),
+    // Site content and configuration
+    ("app_pages", "tenant_id = $1"),
+    ("app_menus", "tenant_id = $1"),
+    ("tenant_setting", "tenant_id = $1"),
+    ("tenant_data_keys", "tenant_id = $1"),
+    ("webhook_endpoints", "tenant_id = $1"),
+    ("api_tokens", "tenant_id = $1"),
+    // Telemetry and search
+    ("telemetry_events", "tenant_id = $1"),
+    ("page_views", "tenant_id = $1"),
+    ("platform_metrics_daily", "tenant_id = $1"),
+    ("global_search_index", "tenant_id = $1"),
+];
+
+#[derive(Debug)]
+pub enum LifecycleError {
+    NotFound,
+    InvalidTransition { from: String, to: &'static str },
+    InvalidGracePeriod(i64),
+    Db(DbErr),
+}
+
+impl std::fmt::Display for LifecycleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Tenant not found"),
+            Self::InvalidTransition { from, to } => write!(f, "A {} tenant can't become {}", from, to),
+            Self::InvalidGracePeriod(days) => {
+                write!(f, "A grace period of {} day(s) exceeds the maximum of {}", days, MAX_DELETION_GRACE_DAYS)
+            }
+            Self::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for LifecycleError {}
+
+impl From<DbErr> for LifecycleError {
+    fn from(e: DbErr) -> Self {
+        Self::Db(e)
+    }
+}
 
 pub struct TenantService;
 
@@ -48,8 +148,255 @@ impl TenantService {
         Ok(updated)
     }
 
-    pub async fn delete_tenant(db: &DatabaseConnection, tenant_id: Uuid) -> Result<()> {
-        let _result = tenant::Entity::delete_by_id(tenant_id).exec(db).await?;
+    /// Takes the tenant's sites offline and blocks sign-ins until it is reactivated.
+    pub async fn suspend(
+        db: &DatabaseConnection,
+        tenant_id: Uuid,
+        actor_id: Option<Uuid>,
+        reason: Option<String>,
+    ) -> Result<tenant::Model, LifecycleError> {
+        Self::transition(db, tenant_id, actor_id, STATUS_SUSPENDED, &[STATUS_ACTIVE], reason, |active| {
+            active.suspended_at = Set(Some(Utc::now()));
+        })
+        .await
+    }
+
+    /// Brings a suspended tenant back, or cancels a deletion that hasn't been purged yet.
+    pub async fn reactivate(
+        db: &DatabaseConnection,
+        tenant_id: Uuid,
+        actor_id: Option<Uuid>,
+    ) -> Result<tenant::Model, LifecycleError> {
+        let from = [STATUS_SUSPENDED, STATUS_SCHEDULED_FOR_DELETION];
+        let tenant = Self::transition(db, tenant_id, actor_id, STATUS_ACTIVE, &from, None, |active| {
+            active.suspended_at = Set(None);
+            active.deletion_scheduled_at = Set(None);
+            active.purge_after = Set(None);
+        })
+        .await?;
+        JobScheduler::deprovision(db, tenant_id, &[Self::purge_job()]).await?;
+        Ok(tenant)
+    }
+
+    /// Soft-deletes the tenant: its sites go offline at once and the purge job removes its
+    /// data once the grace period (`TENANT_DELETION_GRACE_DAYS`, default 30, at most
+    /// `MAX_DELETION_GRACE_DAYS`) has passed.
+    pub async fn schedule_deletion(
+        db: &DatabaseConnection,
+        tenant_id: Uuid,
+        actor_id: Option<Uuid>,
+        grace_days: Option<i64>,
+    ) -> Result<tenant::Model, LifecycleError> {
+        let grace_days = grace_days.unwrap_or_else(Self::deletion_grace_days).max(0);
+        if grace_days > MAX_DELETION_GRACE_DAYS {
+            return Err(LifecycleError::InvalidGracePeriod(grace_days));
+        }
+        let from = [STATUS_ACTIVE, STATUS_SUSPENDED];
+        let reason = Some(format!("Purge after {} day(s)", grace_days));
+        Self::transition(db, tenant_id, actor_id, STATUS_SCHEDULED_FOR_DELETION, &from, reason, |active| {
+            let now = Utc::now();
+            active.deletion_scheduled_at = Set(Some(now));
+            active.purge_after = Set(Some(now + Duration::days(grace_days)));
+        })
+        .await
+    }
+
+    /// The given tenants that are active, i.e. not suspended, awaiting deletion or purged.
+    pub async fn active_tenant_ids(db: &DatabaseConnection, tenant_ids: Vec<Uuid>) -> Result<Vec<Uuid>, DbErr> {
+        if tenant_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        tenant::Entity::find()
+            .select_only()
+            .column(tenant::Column::Id)
+            .filter(tenant::Column::Id.is_in(tenant_ids))
+            .filter(tenant::Column::SiteStatus.eq(STATUS_ACTIVE))
+            .into_tuple()
+            .all(db)
+            .await
+    }
+
+    /// Whether the user may sign in: members of tenants need at least one of them to be
+    /// active. Users without a membership, such as platform admins, aren't affected.
+    pub async fn user_can_sign_in(db: &DatabaseConnection, user_id: Uuid) -> Result<bool, DbErr> {
+        let tenant_ids: Vec<Uuid> = user_account::Entity::find()
+            .filter(user_account::Column::UserId.eq(user_id))
+            .filter(user_account::Column::IsActive.eq(true))
+            .find_also_related(account::Entity)
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|(_, account)| account.filter(|a| a.is_active).map(|a| a.tenant_id))
+            .collect();
+        if tenant_ids.is_empty() {
+            return Ok(true);
+        }
+        Ok(!Self::active_tenant_ids(db, tenant_ids).await?.is_empty())
+    }
+
+    /// Purges the tenant if its grace period is over. Returns whether it did.
+    pub async fn purge_if_due(db: &DatabaseConnection, tenant_id: Uuid) -> Result<bool, LifecycleError> {
+        let tenant = Self::find(db, tenant_id).await?;
+        let due = tenant.site_status == STATUS_SCHEDULED_FOR_DELETION
+            && tenant.purge_after.is_some_and(|purge_after| purge_after <= Utc::now());
+        if due {
+            Self::purge(db, tenant).await?;
+        }
+        Ok(due)
+    }
+
+    /// Uninstalls the tenant's apps, then deletes its rows in one transaction. The tenant
+    /// row stays behind as `purged` so audit logs and billing records keep their owner.
+    async fn purge(db: &DatabaseConnection, tenant: tenant::Model) -> Result<(), LifecycleError> {
+        let tenant_id = tenant.id;
+        tracing::info!("Purging tenant {}", tenant_id);
+
+        for instance in app_instance::Entity::find()
+            .filter(app_instance::Column::TenantId.eq(tenant_id))
+            .all(db)
+            .await?
+        {
+            match crate::atlas_apps::find_app(&instance.app_type) {
+                Some(app) => AppLifecycle::uninstall(db, app.as_ref(), instance).await?,
+                None => {
+                    app_instance::Entity::delete_by_id(instance.id).exec(db).await?;
+                }
+            }
+        }
+
+        let txn = db.begin().await?;
+        // Tables an optional app or an older schema never created have nothing to purge
+        let existing: Vec<String> = txn
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT table_name::text AS name FROM information_schema.tables \
+                 WHERE table_schema = current_schema() AND table_name = ANY($1)",
+                [PURGED_ROWS.iter().map(|(table, _)| table.to_string()).collect::<Vec<_>>().into()],
+            ))
+            .await?
+            .iter()
+            .map(|row| row.try_get("", "name"))
+            .collect::<Result<_, _>>()?;
+        for (table, filter) in PURGED_ROWS.iter().filter(|(table, _)| existing.iter().any(|name| name == table)) {
+            txn.execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                format!(r#"DELETE FROM "{}" WHERE {}"#, table, filter),
+                [tenant_id.into()],
+            ))
+            .await?;
+        }
+        // The purge job's own row stays so its run can be recorded
+        tenant_background_job::Entity::delete_many()
+            .filter(tenant_background_job::Column::TenantId.eq(tenant_id))
+            .filter(tenant_background_job::Column::JobType.ne(PURGE_JOB_TYPE))
+            .exec(&txn)
+            .await?;
+        tenant_background_job::Entity::update_many()
+            .col_expr(tenant_background_job::Column::IsActive, sea_orm::sea_query::Expr::value(false))
+            .filter(tenant_background_job::Column::TenantId.eq(tenant_id))
+            .exec(&txn)
+            .await?;
+
+        // update_many skips the entity hooks, which would put the tenant back in the search index
+        let now = Utc::now();
+        tenant::Entity::update_many()
+            .col_expr(tenant::Column::SiteStatus, sea_orm::sea_query::Expr::value(STATUS_PURGED))
+            .col_expr(tenant::Column::PurgedAt, sea_orm::sea_query::Expr::value(now))
+            .col_expr(tenant::Column::UpdatedAt, sea_orm::sea_query::Expr::value(now))
+            .filter(tenant::Column::Id.eq(tenant_id))
+            .exec(&txn)
+            .await?;
+        Self::audit(&txn, &tenant, None, STATUS_PURGED, None).await?;
+        txn.commit().await?;
+
+        invalidate_tenant(tenant_id);
         Ok(())
     }
+
+    /// Checks the tenant's scheduled deletion every hour and purges it once due.
+    pub fn purge_job() -> BackgroundJob {
+        BackgroundJob {
+            job_type: PURGE_JOB_TYPE.to_string(),
+            default_interval_seconds: 3600,
+            is_active_by_default: true,
+            default_config_payload: None,
+            executor: Box::new(|db, tenant_id, _config| {
+                Box::pin(async move {
+                    Self::purge_if_due(&db, tenant_id).await.map(|_| ()).map_err(|e| e.to_string())
+                })
+            }),
+        }
+    }
+
+    fn deletion_grace_days() -> i64 {
+        std::env::var("TENANT_DELETION_GRACE_DAYS")
+            .ok()
+            .and_then(|days| days.parse().ok())
+            .filter(|days| (0..=MAX_DELETION_GRACE_DAYS).contains(days))
+            .unwrap_or(DEFAULT_DELETION_GRACE_DAYS)
+    }
+
+    async fn find(db: &DatabaseConnection, tenant_id: Uuid) -> Result<tenant::Model, LifecycleError> {
+        tenant::Entity::find_by_id(tenant_id).one(db).await?.ok_or(LifecycleError::NotFound)
+    }
+
+    /// Moves the tenant to `to` if it is in one of the `from` states, audits the change and
+    /// makes sure a tenant scheduled for deletion runs the purge job.
+    async fn transition(
+        db: &DatabaseConnection,
+        tenant_id: Uuid,
+        actor_id: Option<Uuid>,
+        to: &'static str,
+        from: &[&str],
+        reason: Option<String>,
+        apply: impl FnOnce(&mut tenant::ActiveModel),
+    ) -> Result<tenant::Model, LifecycleError> {
+        let txn = db.begin().await?;
+        let tenant = tenant::Entity::find_by_id(tenant_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(LifecycleError::NotFound)?;
+        if !from.contains(&tenant.site_status.as_str()) {
+            return Err(LifecycleError::InvalidTransition { from: tenant.site_status, to });
+        }
+
+        let mut active: tenant::ActiveModel = tenant.clone().into();
+        active.site_status = Set(to.to_string());
+        active.updated_at = Set(Utc::now());
+        apply(&mut active);
+        let updated = active.update(&txn).await?;
+
+        if to == STATUS_SCHEDULED_FOR_DELETION {
+            JobScheduler::provision(&txn, tenant_id, &[Self::purge_job()]).await?;
+        }
+        Self::audit(&txn, &tenant, actor_id, to, reason).await?;
+        txn.commit().await?;
+
+        invalidate_tenant(tenant_id);
+        Ok(updated)
+    }
+
+    async fn audit<C: ConnectionTrait>(
+        conn: &C,
+        tenant: &tenant::Model,
+        actor_id: Option<Uuid>,
+        to: &str,
+        reason: Option<String>,
+    ) -> Result<(), DbErr> {
+        AuditService::log_action(
+            conn,
+            AuditEntry {
+                tenant_id: Some(tenant.id),
+                actor_id,
+                action_type: format!("tenant.lifecycle.{}", to),
+                entity_type: "Tenant".to_string(),
+                entity_id: tenant.id,
+                old_state: Some(json!({ "site_status": tenant.site_status })),
+                new_state: Some(json!({ "site_status": to, "reason": reason })),
+                ip_address: None,
+            },
+        )
+        .await
+    }
 }
\ No newline at end of file
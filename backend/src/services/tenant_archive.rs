@@ -0,0 +1,371 @@
+use chrono::{DateTime, Utc};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, QueryFilter, Statement,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use uuid::Uuid;
+
+use crate::entities::{tenant, tenant_setting};
+use crate::middleware::site_context::invalidate_tenant;
+use crate::services::custom_domains::STATUS_PENDING_VERIFICATION;
+
+/// Bumped whenever the archive layout or an archived table changes incompatibly.
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Caps on decompressed bytes, so a small upload can't expand into an unbounded allocation.
+const MAX_ARCHIVE_BYTES: u64 = 512 * 1024 * 1024;
+const MAX_ENTRY_BYTES: u64 = 128 * 1024 * 1024;
+
+/// Namespace for the tenant id an import derives when the caller doesn't pick one.
+const IMPORT_NAMESPACE: Uuid = uuid::uuid!("6f0c7b3e-2a51-4f7e-9d1c-8b6a4e2f9c10");
+
+/// Archived entities in insert order, parents first: archive name, table, and the filter
+/// selecting the tenant's rows with the tenant id bound as `$1`.
+const ENTITIES: &[(&str, &str, &str)] = &[
+    ("tenant", "tenant", "id = $1"),
+    // Encrypted settings only decrypt under this environment's master key
+    ("tenant_settings", "tenant_setting", "tenant_id = $1 AND NOT is_encrypted"),
+    ("accounts", "account", "tenant_id = $1"),
+    // Memberships of users that don't exist in the target environment fail and are reported
+    ("user_accounts", "user_account", "account_id IN (SELECT id FROM account WHERE tenant_id = $1)"),
+    ("app_instances", "app_instances", "tenant_id = $1"),
+    // Imported domains have to be verified again; see `reset_domain_verification`
+    ("app_domains", "app_domains", "app_instance_id IN (SELECT id FROM app_instances WHERE tenant_id = $1)"),
+    ("app_pages", "app_pages", "tenant_id = $1"),
+    ("app_menus", "app_menus", "tenant_id = $1"),
+    ("categories", "category", "tenant_id = $1"),
+    ("templates", "template", "tenant_id = $1"),
+    ("profiles", "profile", "tenant_id = $1"),
+    ("listings", "listing", "tenant_id = $1"),
+    ("customers", "customer", "tenant_id = $1"),
+    ("contacts", "contact", "tenant_id = $1"),
+    ("deal_pipelines", "deal_pipeline", "tenant_id = $1"),
+    ("deal_pipeline_stages", "deal_pipeline_stage", "pipeline_id IN (SELECT id FROM deal_pipeline WHERE tenant_id = $1)"),
+    ("deals", "deal", "tenant_id = $1"),
+    ("deal_stage_history", "deal_stage_history", "tenant_id = $1"),
+    ("deal_contacts", "deal_contact", "deal_id IN (SELECT id FROM deal WHERE tenant_id = $1)"),
+    ("leads", "lead", "tenant_id = $1"),
+    ("case_sla_policies", "case_sla_policy", "tenant_id = $1"),
+    ("cases", "case", "tenant_id = $1"),
+    ("activities", "activity", "tenant_id = $1"),
+    ("notes", "notes", "tenant_id = $1"),
+    ("custom_field_definitions", "custom_field_definition", "tenant_id = $1"),
+    ("feeds", "feed", "tenant_id = $1"),
+    ("feed_items", "feed_item", "feed_id IN (SELECT id FROM feed WHERE tenant_id = $1)"),
+    ("form_schemas", "form_schemas", "tenant_id = $1"),
+];
+
+#[derive(Debug)]
+pub enum ArchiveError {
+    NotFound,
+    Invalid(String),
+    UnsupportedVersion(u32),
+    Conflict(String),
+    Io(std::io::Error),
+    Db(DbErr),
+}
+
+impl std::fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "Tenant not found"),
+            Self::Invalid(msg) => write!(f, "Invalid archive: {}", msg),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "Archive format {} is newer than the supported format {}",
+                version, ARCHIVE_FORMAT_VERSION
+            ),
+            Self::Conflict(msg) => write!(f, "{}", msg),
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Db(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+impl From<DbErr> for ArchiveError {
+    fn from(e: DbErr) -> Self {
+        Self::Db(e)
+    }
+}
+
+impl From<std::io::Error> for ArchiveError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub source_tenant_id: Uuid,
+    pub source_tenant_slug: Option<String>,
+    /// Row count per archived entity.
+    pub entities: BTreeMap<String, usize>,
+    /// Keys of encrypted settings left out of the archive; they have to be set again.
+    pub omitted_secrets: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportOptions {
+    /// Id of the imported tenant. Defaults to one derived from the archived tenant, so
+    /// importing the same archive twice updates nothing and creates no duplicates.
+    pub tenant_id: Option<Uuid>,
+    /// Replaces the archived slug, e.g. when cloning a tenant within one environment.
+    pub slug: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct EntityReport {
+    pub inserted: u64,
+    /// Rows that already existed, e.g. from an earlier import.
+    pub skipped: u64,
+    pub failed: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub tenant_id: Uuid,
+    pub format_version: u32,
+    pub entities: BTreeMap<String, EntityReport>,
+    pub errors: Vec<String>,
+    pub omitted_secrets: Vec<String>,
+}
+
+pub struct TenantArchive;
+
+impl TenantArchive {
+    /// Writes the tenant as a gzipped tar: `manifest.json` and one `<entity>.jsonl` per
+    /// archived entity, each line a row as stored.
+    pub async fn export<C: ConnectionTrait>(db: &C, tenant_id: Uuid) -> Result<Vec<u8>, ArchiveError> {
+        let tenant = tenant::Entity::find_by_id(tenant_id).one(db).await?.ok_or(ArchiveError::NotFound)?;
+
+        let mut files = Vec::new();
+        let mut counts = BTreeMap::new();
+        for (name, table, filter) in ENTITIES {
+            let rows = db
+                .query_all(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    format!(r#"SELECT to_jsonb(t) AS row FROM "{}" t WHERE {}"#, table, filter),
+                    [tenant_id.into()],
+                ))
+                .await?;
+            let mut lines = Vec::new();
+            for row in &rows {
+                let value: Value = row.try_get("", "row")?;
+                serde_json::to_writer(&mut lines, &value).map_err(std::io::Error::from)?;
+                lines.push(b'\n');
+            }
+            counts.insert(name.to_string(), rows.len());
+            files.push((format!("{}.jsonl", name), lines));
+        }
+
+        let omitted_secrets = tenant_setting::Entity::find()
+            .filter(tenant_setting::Column::TenantId.eq(tenant_id))
+            .filter(tenant_setting::Column::IsEncrypted.eq(true))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|setting| setting.key)
+            .collect();
+        let manifest = Manifest {
+            format_version: ARCHIVE_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            source_tenant_id: tenant.id,
+            source_tenant_slug: tenant.slug,
+            entities: counts,
+            omitted_secrets,
+        };
+
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        let manifest = serde_json::to_vec_pretty(&manifest).map_err(std::io::Error::from)?;
+        append_file(&mut builder, MANIFEST_FILE, &manifest)?;
+        for (path, contents) in files {
+            append_file(&mut builder, &path, &contents)?;
+        }
+        Ok(builder.into_inner()?.finish()?)
+    }
+
+    /// Imports an archive in one transaction. Every archived id is replaced by one derived
+    /// from the target tenant id, including references inside JSON columns, and rows that
+    /// already exist are skipped, which makes the import idempotent. Rows whose references
+    /// can't be satisfied (e.g. a user that only exists in the source environment) are
+    /// counted as failed instead of aborting the import. Domains come back unverified.
+    pub async fn import(
+        db: &DatabaseConnection,
+        archive: &[u8],
+        options: ImportOptions,
+    ) -> Result<ImportReport, ArchiveError> {
+        let mut files = read_archive(archive)?;
+        let manifest: Manifest = serde_json::from_str(
+            &files.remove(MANIFEST_FILE).ok_or_else(|| ArchiveError::Invalid("missing manifest.json".to_string()))?,
+        )
+        .map_err(|e| ArchiveError::Invalid(format!("manifest.json: {}", e)))?;
+        if manifest.format_version > ARCHIVE_FORMAT_VERSION {
+            return Err(ArchiveError::UnsupportedVersion(manifest.format_version));
+        }
+
+        let mut entities = Vec::new();
+        for (name, table, _) in ENTITIES {
+            let mut rows = Vec::new();
+            for (line_no, line) in files.remove(&format!("{}.jsonl", name)).unwrap_or_default().lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let row: Value = serde_json::from_str(line)
+                    .map_err(|e| ArchiveError::Invalid(format!("{}.jsonl line {}: {}", name, line_no + 1, e)))?;
+                rows.push(row);
+            }
+            entities.push((*name, *table, rows));
+        }
+
+        let target_id = options
+            .tenant_id
+            .unwrap_or_else(|| Uuid::new_v5(&IMPORT_NAMESPACE, manifest.source_tenant_id.as_bytes()));
+        let mut ids = HashMap::from([(manifest.source_tenant_id, target_id)]);
+        for row in entities.iter().flat_map(|(_, _, rows)| rows) {
+            if let Some(id) = row.get("id").and_then(Value::as_str).and_then(|id| Uuid::parse_str(id).ok()) {
+                ids.entry(id).or_insert_with(|| Uuid::new_v5(&target_id, id.as_bytes()));
+            }
+        }
+
+        let mut report = ImportReport {
+            tenant_id: target_id,
+            format_version: manifest.format_version,
+            entities: BTreeMap::new(),
+            errors: Vec::new(),
+            omitted_secrets: manifest.omitted_secrets,
+        };
+
+        let txn = db.begin().await?;
+        for (name, table, mut rows) in entities {
+            for row in &mut rows {
+                remap_ids(row, &ids);
+                if name == "tenant"
+                    && let (Some(slug), Some(fields)) = (&options.slug, row.as_object_mut())
+                {
+                    fields.insert("slug".to_string(), Value::String(slug.clone()));
+                }
+                if name == "app_domains"
+                    && let Some(fields) = row.as_object_mut()
+                {
+                    reset_domain_verification(fields);
+                }
+            }
+
+            let entity_report = report.entities.entry(name.to_string()).or_default();
+            let sql = format!(
+                r#"INSERT INTO "{0}" SELECT * FROM jsonb_populate_record(NULL::"{0}", $1) ON CONFLICT DO NOTHING"#,
+                table
+            );
+            // Rows referencing a later row of the same table (menu parents, parent
+            // categories) succeed on a later pass
+            let mut pending = rows;
+            loop {
+                let attempted = pending.len();
+                let mut retry = Vec::new();
+                let mut last_errors = Vec::new();
+                for row in pending {
+                    let savepoint = txn.begin().await?;
+                    let result = savepoint
+                        .execute(Statement::from_sql_and_values(DbBackend::Postgres, &sql, [row.clone().into()]))
+                        .await;
+                    match result {
+                        Ok(result) => {
+                            savepoint.commit().await?;
+                            if result.rows_affected() > 0 {
+                                entity_report.inserted += 1;
+                            } else {
+                                entity_report.skipped += 1;
+                            }
+                        }
+                        Err(e) => {
+                            savepoint.rollback().await?;
+                            let id = row.get("id").and_then(Value::as_str).unwrap_or("-").to_string();
+                            last_errors.push(format!("{} {}: {}", name, id, e));
+                            retry.push(row);
+                        }
+                    }
+                }
+                if retry.is_empty() || retry.len() == attempted {
+                    entity_report.failed += retry.len() as u64;
+                    report.errors.extend(last_errors);
+                    break;
+                }
+                pending = retry;
+            }
+
+            if name == "tenant" && tenant::Entity::find_by_id(target_id).one(&txn).await?.is_none() {
+                return Err(ArchiveError::Conflict(
+                    "The archived tenant conflicts with an existing one; import it with another slug".to_string(),
+                ));
+            }
+        }
+        txn.commit().await?;
+
+        invalidate_tenant(target_id);
+        Ok(report)
+    }
+}
+
+fn append_file<W: Write>(builder: &mut tar::Builder<W>, path: &str, contents: &[u8]) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, path, contents)
+}
+
+fn read_archive(archive: &[u8]) -> Result<HashMap<String, String>, ArchiveError> {
+    let mut files = HashMap::new();
+    // Reading past the total cap ends the stream, which tar reports as a truncated archive
+    let mut tar = tar::Archive::new(GzDecoder::new(archive).take(MAX_ARCHIVE_BYTES));
+    for entry in tar.entries().map_err(|e| ArchiveError::Invalid(e.to_string()))? {
+        let mut entry = entry.map_err(|e| ArchiveError::Invalid(e.to_string()))?;
+        let path = entry.path().map_err(|e| ArchiveError::Invalid(e.to_string()))?.to_string_lossy().into_owned();
+        let mut contents = String::new();
+        (&mut entry)
+            .take(MAX_ENTRY_BYTES + 1)
+            .read_to_string(&mut contents)
+            .map_err(|e| ArchiveError::Invalid(format!("{}: {}", path, e)))?;
+        if contents.len() as u64 > MAX_ENTRY_BYTES {
+            return Err(ArchiveError::Invalid(format!("{} is larger than {} bytes", path, MAX_ENTRY_BYTES)));
+        }
+        files.insert(path, contents);
+    }
+    Ok(files)
+}
+
+/// Ownership and TLS state belong to the source environment: an imported domain starts over
+/// with a fresh challenge and isn't served (or issued certificates) until verified here.
+fn reset_domain_verification(fields: &mut Map<String, Value>) {
+    fields.insert("status".to_string(), Value::String(STATUS_PENDING_VERIFICATION.to_string()));
+    fields.insert("verification_token".to_string(), Value::String(Uuid::new_v4().simple().to_string()));
+    for column in ["provider_hostname_id", "ssl_status", "last_error", "last_checked_at", "verified_at"] {
+        fields.insert(column.to_string(), Value::Null);
+    }
+}
+
+/// Replaces every string holding an archived id with its new id, at any depth.
+fn remap_ids(value: &mut Value, ids: &HashMap<Uuid, Uuid>) {
+    match value {
+        Value::String(s) => {
+            if let Some(new_id) = Uuid::parse_str(s).ok().and_then(|id| ids.get(&id)) {
+                *s = new_id.to_string();
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| remap_ids(item, ids)),
+        Value::Object(fields) => fields.values_mut().for_each(|field| remap_ids(field, ids)),
+        _ => {}
+    }
+}
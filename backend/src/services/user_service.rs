@@ -1,6 +1,6 @@
 use crate::entities::user;
-use crate::services::audit::AuditService;
-use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, ColumnTrait, QueryFilter, Set};
+use crate::services::audit::{AuditEntry, AuditService};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, ColumnTrait, QueryFilter, Set, TransactionTrait};
 use serde_json::json;
 use crate::auth::hash_password;
 use chrono::Utc;
@@ -31,22 +31,30 @@ impl UserService {
         user_active.email = Set(new_email.clone());
         user_active.updated_at = Set(Utc::now());
 
-        let updated_user = user_active.update(db).await.map_err(|_| {
+        // The audit entry commits together with the change it records
+        let txn = db.begin().await.map_err(|_| {
+            "Failed to update email".to_string()
+        })?;
+        let updated_user = user_active.update(&txn).await.map_err(|_| {
             "Failed to update email".to_string()
         })?;
 
-        // Dispatch background audit logging
         AuditService::log_action(
-            db.clone(),
-            None, // Might need to pass tenant_id
-            Some(updated_user.id),
-            "user.email.updated".to_string(),
-            "User".to_string(),
-            updated_user.id,
-            Some(old_state),
-            Some(new_state),
-            None, // ip_address
-        );
+            &txn,
+            AuditEntry {
+                tenant_id: None, // Might need to pass tenant_id
+                actor_id: Some(updated_user.id),
+                action_type: "user.email.updated".to_string(),
+                entity_type: "User".to_string(),
+                entity_id: updated_user.id,
+                old_state: Some(old_state),
+                new_state: Some(new_state),
+                ip_address: None, // ip_address
+            },
+        )
+        .await
+        .map_err(|_| "Failed to record audit entry".to_string())?;
+        txn.commit().await.map_err(|_| "Failed to update email".to_string())?;
 
         Ok(updated_user)
     }
@@ -66,22 +74,29 @@ impl UserService {
         user_active.password_hash = Set(hashed_password);
         user_active.updated_at = Set(Utc::now());
 
-        let updated_user = user_active.update(db).await.map_err(|_| {
+        let txn = db.begin().await.map_err(|_| {
+            "Failed to update password".to_string()
+        })?;
+        let updated_user = user_active.update(&txn).await.map_err(|_| {
             "Failed to update password".to_string()
         })?;
 
-        // Dispatch background audit logging
         AuditService::log_action(
-            db.clone(),
-            None,
-            Some(updated_user.id),
-            "user.password.updated".to_string(),
-            "User".to_string(),
-            updated_user.id,
-            Some(old_state),
-            Some(new_state),
-            None,
-        );
+            &txn,
+            AuditEntry {
+                tenant_id: None,
+                actor_id: Some(updated_user.id),
+                action_type: "user.password.updated".to_string(),
+                entity_type: "User".to_string(),
+                entity_id: updated_user.id,
+                old_state: Some(old_state),
+                new_state: Some(new_state),
+                ip_address: None,
+            },
+        )
+        .await
+        .map_err(|_| "Failed to record audit entry".to_string())?;
+        txn.commit().await.map_err(|_| "Failed to update password".to_string())?;
 
         Ok(updated_user)
     }
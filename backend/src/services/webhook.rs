@@ -1,9 +1,12 @@
 use crate::entities::{webhook_delivery, webhook_endpoint};
+use crate::services::event_catalog::{self, PlatformEvent};
+use crate::services::outbox::{Outbox, OutboxMessage};
 use uuid::Uuid;
 use sea_orm::prelude::DateTimeWithTimeZone;
 use chrono::Utc;
 use hmac::{Hmac, Mac};
-use reqwest::{Client, StatusCode};
+use once_cell::sync::Lazy;
+use reqwest::Client;
 use sea_orm::*;
 use sea_orm::ActiveValue::Set;
 use serde_json::Value;
@@ -11,63 +14,143 @@ use sha2::Sha256;
 use std::time::Duration;
 use tracing::{error, info, warn};
 
-pub async fn dispatch_event(
-    db: &DatabaseConnection,
+/// Delivery attempts before a delivery is moved to the dead-letter queue.
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// Receivers should reject deliveries whose `X-Atlas-Timestamp` is further than this from
+/// their own clock. Because the timestamp is covered by the signature, a captured request
+/// cannot be replayed once it falls outside this window.
+pub const SIGNATURE_TOLERANCE_SECS: i64 = 300;
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_SENT: &str = "sent";
+pub const STATUS_FAILED: &str = "failed";
+pub const STATUS_DEAD_LETTER: &str = "dead_letter";
+
+/// Shared across deliveries so connection pools and TLS sessions are reused.
+static HTTP_CLIENT: Lazy<Client> = Lazy::new(|| {
+    Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Failed to build webhook HTTP client")
+});
+
+/// Computes the `X-Atlas-Signature` value: `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`.
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Receiver-side check: the signature must match and its timestamp must lie within
+/// `tolerance_secs` of `now`.
+pub fn verify_signature(secret: &str, header: &str, body: &str, now: i64, tolerance_secs: i64) -> bool {
+    let mut timestamp = None;
+    let mut signature = None;
+    for part in header.split(',') {
+        match part.split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signature = hex::decode(value).ok(),
+            _ => {}
+        }
+    }
+    let (Some(timestamp), Some(signature)) = (timestamp, signature) else {
+        return false;
+    };
+    if (now - timestamp).abs() > tolerance_secs {
+        return false;
+    }
+
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Queues `event` for every subscribed endpoint of the tenant. The message goes through the
+/// outbox on `conn`, so when called with a transaction nothing is sent unless it commits.
+pub async fn dispatch_event<C: ConnectionTrait>(
+    conn: &C,
     tenant_id: Uuid,
-    event_type: &str,
+    event: PlatformEvent,
     payload: Value,
 ) -> Result<(), DbErr> {
+    Outbox::enqueue(conn, OutboxMessage::Webhook {
+        tenant_id,
+        event: event.name().to_string(),
+        payload,
+    })
+    .await
+    .map(|_| ())
+}
+
+/// Creates one delivery per subscribed endpoint for a relayed outbox message and returns the
+/// ids of the deliveries that did not exist yet.
+pub(crate) async fn fan_out<C: ConnectionTrait>(
+    conn: &C,
+    outbox_id: Uuid,
+    tenant_id: Uuid,
+    event: PlatformEvent,
+    payload: Value,
+) -> Result<Vec<Uuid>, DbErr> {
+    let event_type = event.name();
+
     // Find active endpoints subscribed to this event
     let endpoints = webhook_endpoint::Entity::find()
         .filter(webhook_endpoint::Column::TenantId.eq(tenant_id))
         .filter(webhook_endpoint::Column::IsActive.eq(true))
-        .all(db)
+        .all(conn)
         .await?;
 
+    let mut created = Vec::new();
     for endpoint in endpoints {
-        // Basic check: is the event_type in the subscribed_events JSON array?
-        // (Assuming subscribed_events is an array of strings e.g. ["crm.deal.won", "listing.created"])
-        let subscriptions = endpoint.subscribed_events.as_array();
-        let is_subscribed = if let Some(subs) = subscriptions {
-            subs.iter().any(|v| v.as_str() == Some(event_type))
-        } else {
-            false
-        };
-
-        if !is_subscribed {
+        // subscribed_events holds exact names or wildcards, e.g. ["crm.*", "webform.submitted"]
+        if !event_catalog::is_subscribed(&endpoint.subscribed_events, event_type) {
             continue;
         }
 
-        // Create webhook delivery
-        let delivery = webhook_delivery::ActiveModel {
-            id: Set(Uuid::new_v4()),
+        let delivery_id = Uuid::new_v4();
+        let inserted = webhook_delivery::Entity::insert(webhook_delivery::ActiveModel {
+            id: Set(delivery_id),
             endpoint_id: Set(endpoint.id),
             tenant_id: Set(tenant_id),
             event_type: Set(event_type.to_string()),
             payload: Set(payload.clone()),
-            status: Set("pending".to_string()),
+            status: Set(STATUS_PENDING.to_string()),
             next_retry_at: Set(None),
             attempts: Set(0),
+            idempotency_key: Set(Some(format!("{}:{}", outbox_id, endpoint.id))),
             ..Default::default()
-        }
-        .insert(db)
+        })
+        .on_conflict(
+            sea_orm::sea_query::OnConflict::column(webhook_delivery::Column::IdempotencyKey)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(conn)
         .await?;
 
-        // 1. The "Spawn" (Happy Path)
-        let db_clone = db.clone();
-        let delivery_id = delivery.id;
-
-        tokio::spawn(async move {
-            if let Err(e) = process_delivery(&db_clone, delivery_id).await {
-                error!("Failed to process webhook delivery {}: {:?}", delivery_id, e);
-            }
-        });
+        if inserted > 0 {
+            created.push(delivery_id);
+        }
     }
 
-    Ok(())
+    Ok(created)
+}
+
+pub(crate) fn spawn_delivery(db: &DatabaseConnection, delivery_id: Uuid) {
+    let db_clone = db.clone();
+    tokio::spawn(async move {
+        if let Err(e) = process_delivery(&db_clone, delivery_id).await {
+            error!("Failed to process webhook delivery {}: {:?}", delivery_id, e);
+        }
+    });
 }
 
-async fn process_delivery(db: &DatabaseConnection, delivery_id: Uuid) -> Result<(), anyhow::Error> {
+/// Performs one delivery attempt and records the outcome on both the delivery and its endpoint.
+pub async fn process_delivery(db: &DatabaseConnection, delivery_id: Uuid) -> Result<webhook_delivery::Model, anyhow::Error> {
     // Fetch delivery and endpoint
     let delivery = webhook_delivery::Entity::find_by_id(delivery_id)
         .one(db)
@@ -79,29 +162,36 @@ async fn process_delivery(db: &DatabaseConnection, delivery_id: Uuid) -> Result<
         .await?
         .ok_or_else(|| anyhow::anyhow!("Endpoint not found"))?;
 
+    if !endpoint.is_active {
+        // Parked until the endpoint is re-enabled and the delivery replayed
+        let mut active_delivery: webhook_delivery::ActiveModel = delivery.into();
+        active_delivery.status = Set(STATUS_DEAD_LETTER.to_string());
+        active_delivery.next_retry_at = Set(None);
+        active_delivery.response_body = Set(Some("Endpoint is disabled".to_string()));
+        active_delivery.updated_at = Set(Some(Utc::now().into()));
+        return Ok(active_delivery.update(db).await?);
+    }
+
     let payload_str = serde_json::to_string(&delivery.payload)?;
-    
-    // Create HMAC signature
-    let mut mac = Hmac::<Sha256>::new_from_slice(endpoint.secret_key.as_bytes())?;
-    mac.update(payload_str.as_bytes());
-    let result = mac.finalize();
-    let signature = hex::encode(result.into_bytes());
-
-    let client = Client::new();
-    let response = client
+    let timestamp = Utc::now().timestamp();
+    let signature = sign_payload(&endpoint.secret_key, timestamp, &payload_str);
+
+    let response = HTTP_CLIENT
         .post(&endpoint.target_url)
         .header("Content-Type", "application/json")
         .header("X-Atlas-Signature", signature)
+        .header("X-Atlas-Timestamp", timestamp.to_string())
         .header("X-Atlas-Event", &delivery.event_type)
+        .header("X-Atlas-Delivery", delivery.id.to_string())
         .body(payload_str)
-        .timeout(Duration::from_secs(10))
         .send()
         .await;
 
+    let attempts = delivery.attempts + 1;
     let mut active_delivery: webhook_delivery::ActiveModel = delivery.into();
-    active_delivery.attempts = Set(active_delivery.attempts.clone().unwrap() + 1);
+    active_delivery.attempts = Set(attempts);
 
-    match response {
+    let delivered = match response {
         Ok(resp) => {
             let status_code = resp.status();
             active_delivery.response_status = Set(Some(status_code.as_u16() as i32));
@@ -111,36 +201,40 @@ async fn process_delivery(db: &DatabaseConnection, delivery_id: Uuid) -> Result<
             active_delivery.response_body = Set(Some(body_text.chars().take(2000).collect())); // truncate if too long
 
             if status_code.is_success() {
-                active_delivery.status = Set("sent".to_string());
+                active_delivery.status = Set(STATUS_SENT.to_string());
                 active_delivery.next_retry_at = Set(None);
                 info!("Webhook {} delivered successfully", delivery_id);
+                true
             } else {
-                handle_failure(&mut active_delivery);
+                handle_failure(&mut active_delivery, attempts);
                 warn!("Webhook {} failed with status {}", delivery_id, status_code);
+                false
             }
         }
         Err(e) => {
-            active_delivery.status = Set("failed".to_string());
+            active_delivery.response_status = Set(None);
             active_delivery.response_body = Set(Some(e.to_string()));
-            handle_failure(&mut active_delivery);
+            handle_failure(&mut active_delivery, attempts);
             warn!("Webhook {} failed request: {:?}", delivery_id, e);
+            false
         }
-    }
+    };
 
     active_delivery.updated_at = Set(Some(Utc::now().into()));
-    active_delivery.update(db).await?;
+    let updated = active_delivery.update(db).await?;
 
-    Ok(())
+    record_endpoint_outcome(db, endpoint, delivered).await?;
+
+    Ok(updated)
 }
 
-fn handle_failure(delivery: &mut webhook_delivery::ActiveModel) {
-    let attempts = delivery.attempts.clone().unwrap();
-    if attempts >= 5 {
-        delivery.status = Set("failed".to_string());
+fn handle_failure(delivery: &mut webhook_delivery::ActiveModel, attempts: i32) {
+    if attempts >= MAX_ATTEMPTS {
+        delivery.status = Set(STATUS_DEAD_LETTER.to_string());
         delivery.next_retry_at = Set(None); // no more retries
     } else {
-        delivery.status = Set("failed".to_string());
-        // Exponential backoff: e.g. 5m, 25m, 125m, etc. 
+        delivery.status = Set(STATUS_FAILED.to_string());
+        // Exponential backoff: e.g. 5m, 25m, 125m, etc.
         // 5 minutes * 5 ^ (attempt - 1)
         let delay_mins = 5_i64.pow(attempts as u32 - 1);
         let next_retry = Utc::now() + chrono::Duration::minutes(delay_mins);
@@ -148,6 +242,82 @@ fn handle_failure(delivery: &mut webhook_delivery::ActiveModel) {
     }
 }
 
+/// Tracks consecutive failures and disables the endpoint once `failure_threshold` is reached.
+async fn record_endpoint_outcome(
+    db: &DatabaseConnection,
+    endpoint: webhook_endpoint::Model,
+    delivered: bool,
+) -> Result<(), DbErr> {
+    if delivered && endpoint.consecutive_failures == 0 {
+        return Ok(());
+    }
+
+    let consecutive_failures = if delivered { 0 } else { endpoint.consecutive_failures + 1 };
+    let should_disable = !delivered && endpoint.failure_threshold > 0 && consecutive_failures >= endpoint.failure_threshold;
+
+    let endpoint_id = endpoint.id;
+    let mut active_endpoint: webhook_endpoint::ActiveModel = endpoint.into();
+    active_endpoint.consecutive_failures = Set(consecutive_failures);
+    if should_disable {
+        warn!("Disabling webhook endpoint {} after {} consecutive failures", endpoint_id, consecutive_failures);
+        active_endpoint.is_active = Set(false);
+        active_endpoint.disabled_at = Set(Some(Utc::now().into()));
+    }
+    active_endpoint.updated_at = Set(Some(Utc::now().into()));
+    active_endpoint.update(db).await?;
+
+    Ok(())
+}
+
+/// Resets a delivery to a fresh `pending` state so it gets a full set of attempts again.
+async fn reset_for_replay(db: &DatabaseConnection, delivery: webhook_delivery::Model) -> Result<webhook_delivery::Model, DbErr> {
+    let mut active_delivery: webhook_delivery::ActiveModel = delivery.into();
+    active_delivery.status = Set(STATUS_PENDING.to_string());
+    active_delivery.attempts = Set(0);
+    active_delivery.next_retry_at = Set(None);
+    active_delivery.updated_at = Set(Some(Utc::now().into()));
+    active_delivery.update(db).await
+}
+
+/// Manually redelivers a single delivery and waits for the attempt to finish.
+pub async fn redeliver(db: &DatabaseConnection, delivery: webhook_delivery::Model) -> Result<webhook_delivery::Model, anyhow::Error> {
+    let delivery = reset_for_replay(db, delivery).await?;
+    process_delivery(db, delivery.id).await
+}
+
+/// Re-queues every matching delivery in the tenant created within `[from, to]`.
+/// Attempts run in the background; returns the number of deliveries queued.
+pub async fn replay_range(
+    db: &DatabaseConnection,
+    tenant_id: Uuid,
+    from: DateTimeWithTimeZone,
+    to: DateTimeWithTimeZone,
+    endpoint_id: Option<Uuid>,
+    statuses: &[String],
+) -> Result<usize, DbErr> {
+    let mut query = webhook_delivery::Entity::find()
+        .filter(webhook_delivery::Column::TenantId.eq(tenant_id))
+        .filter(webhook_delivery::Column::CreatedAt.gte(from))
+        .filter(webhook_delivery::Column::CreatedAt.lte(to))
+        .filter(webhook_delivery::Column::Status.is_in(statuses.iter().cloned()));
+    if let Some(endpoint_id) = endpoint_id {
+        query = query.filter(webhook_delivery::Column::EndpointId.eq(endpoint_id));
+    }
+
+    let deliveries = query
+        .order_by_asc(webhook_delivery::Column::CreatedAt)
+        .all(db)
+        .await?;
+
+    let count = deliveries.len();
+    for delivery in deliveries {
+        let delivery = reset_for_replay(db, delivery).await?;
+        spawn_delivery(db, delivery.id);
+    }
+
+    Ok(count)
+}
+
 // 2. The "Sweeper" (Recovery Path)
 pub async fn start_webhook_sweeper(db: DatabaseConnection) {
     tokio::spawn(async move {
@@ -156,27 +326,23 @@ pub async fn start_webhook_sweeper(db: DatabaseConnection) {
             interval.tick().await;
 
             let now: DateTimeWithTimeZone = Utc::now().into();
-            
+
             // Find pendings older than 2 mins
             let two_mins_ago: DateTimeWithTimeZone = (Utc::now() - chrono::Duration::minutes(2)).into();
             let pendings = webhook_delivery::Entity::find()
-                .filter(webhook_delivery::Column::Status.eq("pending"))
+                .filter(webhook_delivery::Column::Status.eq(STATUS_PENDING))
                 .filter(webhook_delivery::Column::UpdatedAt.lt(two_mins_ago))
                 .all(&db)
                 .await
                 .unwrap_or_default();
 
             for delivery in pendings {
-                let db_clone = db.clone();
-                let delivery_id = delivery.id;
-                tokio::spawn(async move {
-                    let _ = process_delivery(&db_clone, delivery_id).await;
-                });
+                spawn_delivery(&db, delivery.id);
             }
 
             // Find failed that are due for retry
             let due_for_retry = webhook_delivery::Entity::find()
-                .filter(webhook_delivery::Column::Status.eq("failed"))
+                .filter(webhook_delivery::Column::Status.eq(STATUS_FAILED))
                 .filter(webhook_delivery::Column::NextRetryAt.lte(now))
                 .filter(webhook_delivery::Column::NextRetryAt.is_not_null())
                 .all(&db)
@@ -184,11 +350,7 @@ pub async fn start_webhook_sweeper(db: DatabaseConnection) {
                 .unwrap_or_default();
 
             for delivery in due_for_retry {
-                let db_clone = db.clone();
-                let delivery_id = delivery.id;
-                tokio::spawn(async move {
-                    let _ = process_delivery(&db_clone, delivery_id).await;
-                });
+                spawn_delivery(&db, delivery.id);
             }
         }
     });
@@ -0,0 +1,278 @@
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::entities::activity::{ActivityStatus, ActivityType};
+use crate::entities::customer::CustomerType;
+use crate::entities::{activity, customer, lead, tenant_background_job};
+use crate::services::activity_tasks::{TaskService, REMINDER_JOB_TYPE};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils::{self, call, join_tenant};
+
+fn new_task(tenant_id: Uuid, user_id: Uuid, title: &str, due_date: Option<chrono::DateTime<Utc>>) -> activity::ActiveModel {
+    activity::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(Some(tenant_id)),
+        activity_type: Set(ActivityType::Task),
+        title: Set(title.to_string()),
+        status: Set(ActivityStatus::Pending),
+        due_date: Set(due_date),
+        created_by: Set(Some(user_id)),
+        assigned_to: Set(Some(user_id)),
+        associated_entities: Set(json!([])),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_completing_recurring_task_schedules_next_occurrence() {
+    let (app, db) = setup_test_app().await;
+    let (admin, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let due = Utc::now() - Duration::days(1);
+    let account = join_tenant(&db, tenant.id, admin.id).await;
+
+    let (status, _) = call(&app, "POST", "/api/activities", &token, tenant.id, json!({
+        "account_id": account.id,
+        "activity_type": "Task",
+        "title": "Unscheduled weekly review",
+        "status": "Pending",
+        "associated_entities": [],
+        "files": [],
+        "recurrence": { "frequency": "weekly" }
+    })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) = call(&app, "POST", "/api/activities", &token, tenant.id, json!({
+        "account_id": account.id,
+        "activity_type": "Task",
+        "title": "Once in a lifetime",
+        "status": "Pending",
+        "associated_entities": [],
+        "files": [],
+        "due_date": due,
+        "recurrence": { "frequency": "monthly", "interval": i32::MAX }
+    })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, task) = call(&app, "POST", "/api/activities", &token, tenant.id, json!({
+        "account_id": account.id,
+        "activity_type": "Task",
+        "title": "Weekly review",
+        "status": "Pending",
+        "associated_entities": [],
+        "files": [],
+        "due_date": due,
+        "assigned_to": admin.id,
+        "recurrence": { "frequency": "weekly" }
+    })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", task);
+    assert_eq!(task["recurrence"]["frequency"], "weekly");
+
+    // Scheduling an assigned task turns on the tenant's reminder job
+    let job = tenant_background_job::Entity::find()
+        .filter(tenant_background_job::Column::TenantId.eq(tenant.id))
+        .filter(tenant_background_job::Column::JobType.eq(REMINDER_JOB_TYPE))
+        .one(&db)
+        .await
+        .unwrap();
+    assert!(job.is_some());
+
+    let complete_uri = format!("/api/activities/{}/complete", task["id"].as_str().unwrap());
+    let (status, result) = call(&app, "POST", &complete_uri, &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::OK, "{}", result);
+    assert_eq!(result["completed"]["status"], "Completed");
+    let next = &result["next"];
+    assert_eq!(next["status"], "Pending");
+    assert_eq!(next["series_id"], task["id"]);
+    let next_due: chrono::DateTime<Utc> = serde_json::from_value(next["due_date"].clone()).unwrap();
+    assert_eq!(next_due.timestamp(), (due + Duration::weeks(1)).timestamp());
+
+    let (status, _) = call(&app, "POST", &complete_uri, &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_reminders_are_sent_once_and_rearmed_by_snooze() {
+    let (app, db) = setup_test_app().await;
+    let (admin, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    join_tenant(&db, tenant.id, admin.id).await;
+    let now = Utc::now();
+
+    let overdue = new_task(tenant.id, admin.id, "Send proposal", Some(now - Duration::hours(1))).insert(&db).await.unwrap();
+    new_task(tenant.id, admin.id, "Call back", Some(now + Duration::minutes(30))).insert(&db).await.unwrap();
+    new_task(tenant.id, admin.id, "Next quarter", Some(now + Duration::days(90))).insert(&db).await.unwrap();
+
+    // No SMTP host is configured, so delivery is mocked
+    let report = TaskService::notify_due(&db, tenant.id, now, Duration::minutes(60)).await.unwrap();
+    assert_eq!((report.emails, report.overdue, report.upcoming, report.failed), (1, 1, 1, 0));
+    let report = TaskService::notify_due(&db, tenant.id, now, Duration::minutes(60)).await.unwrap();
+    assert_eq!(report.emails, 0);
+
+    let (status, snoozed) = call(&app, "POST", &format!("/api/activities/{}/snooze", overdue.id), &token, tenant.id, json!({ "minutes": 45 })).await;
+    assert_eq!(status, StatusCode::OK, "{}", snoozed);
+    assert_eq!(snoozed["snooze_count"], 1);
+    let stored = activity::Entity::find_by_id(overdue.id).one(&db).await.unwrap().unwrap();
+    assert!(stored.overdue_notified_at.is_none() && stored.upcoming_notified_at.is_none());
+    assert!(stored.due_date.unwrap() > now);
+
+    let report = TaskService::notify_due(&db, tenant.id, Utc::now(), Duration::minutes(60)).await.unwrap();
+    assert_eq!((report.emails, report.overdue, report.upcoming), (1, 0, 1));
+
+    let (status, _) = call(&app, "POST", &format!("/api/activities/{}/snooze", overdue.id), &token, tenant.id, json!({ "until": now - Duration::hours(2) })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    // Snoozing past the last representable date is rejected rather than overflowing
+    let (status, _) = call(&app, "POST", &format!("/api/activities/{}/snooze", overdue.id), &token, tenant.id, json!({ "minutes": i64::MAX })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_my_tasks_aggregates_related_records() {
+    let (app, db) = setup_test_app().await;
+    let (admin, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let now = Utc::now();
+
+    let lead = lead::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set("Grace Hopper".to_string()),
+        is_converted: Set(false),
+        converted_to_contact: Set(false),
+        created_at: Set(now),
+        updated_at: Set(now),
+        tenant_id: Set(Some(tenant.id)),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    let customer = customer::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set("Compilers Inc".to_string()),
+        customer_type: Set(CustomerType::BusinessEntity),
+        attributes: Set(Default::default()),
+        is_active: Set(true),
+        created_at: Set(now),
+        updated_at: Set(now),
+        tenant_id: Set(Some(tenant.id)),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    let mut lead_task = new_task(tenant.id, admin.id, "Qualify lead", Some(now - Duration::days(2)));
+    lead_task.lead_id = Set(Some(lead.id));
+    lead_task.insert(&db).await.unwrap();
+    let mut customer_task = new_task(tenant.id, admin.id, "Renewal check-in", Some(now + Duration::days(3)));
+    customer_task.customer_id = Set(Some(customer.id));
+    customer_task.insert(&db).await.unwrap();
+    new_task(tenant.id, admin.id, "Someday", None).insert(&db).await.unwrap();
+    let mut done = new_task(tenant.id, admin.id, "Already done", Some(now - Duration::days(5)));
+    done.status = Set(ActivityStatus::Completed);
+    done.insert(&db).await.unwrap();
+    let mut unassigned = new_task(tenant.id, admin.id, "Not mine", Some(now));
+    unassigned.assigned_to = Set(None);
+    unassigned.insert(&db).await.unwrap();
+
+    let (status, mine) = call(&app, "GET", "/api/activities/mine", &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::OK, "{}", mine);
+    assert_eq!(mine["summary"], json!({ "overdue": 1, "due_today": 0, "upcoming": 1, "unscheduled": 1 }));
+    let titles: Vec<&str> = mine["tasks"].as_array().unwrap().iter().map(|task| task["title"].as_str().unwrap()).collect();
+    assert_eq!(titles, vec!["Qualify lead", "Renewal check-in", "Someday"]);
+    assert_eq!(mine["tasks"][0]["overdue"], true);
+    assert_eq!(mine["tasks"][0]["related"], json!([{ "entity_type": "Lead", "entity_id": lead.id, "name": "Grace Hopper" }]));
+    assert_eq!(mine["tasks"][1]["related"][0]["name"], "Compilers Inc");
+
+    let (_, all) = call(&app, "GET", "/api/activities/mine?include_closed=true", &token, tenant.id, Value::Null).await;
+    assert_eq!(all["tasks"].as_array().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn test_tasks_are_only_assigned_to_and_mailed_to_tenant_members() {
+    let (app, db) = setup_test_app().await;
+    let (admin, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let (outsider, _) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let other_tenant = test_utils::create_test_tenant(&db).await;
+    let account = join_tenant(&db, tenant.id, admin.id).await;
+    join_tenant(&db, other_tenant.id, outsider.id).await;
+    let customer = test_utils::create_customer(&db, tenant.id).await;
+    let task = |assignee: Uuid| json!({
+        "account_id": account.id,
+        "activity_type": "Task",
+        "title": "Confidential follow-up",
+        "status": "Pending",
+        "associated_entities": [],
+        "files": [],
+        "due_date": Utc::now() + Duration::days(1),
+        "assigned_to": assignee
+    });
+
+    // A member of another tenant can't be handed the tenant's tasks...
+    let (status, _) = call(&app, "POST", "/api/activities", &token, tenant.id, task(outsider.id)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let customer_activities = format!("/api/customers/{}/activities", customer.id);
+    let (status, _) = call(&app, "POST", &customer_activities, &token, tenant.id, task(outsider.id)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, created) = call(&app, "POST", "/api/activities", &token, tenant.id, task(admin.id)).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+    let (status, _) = call(
+        &app,
+        "PUT",
+        &format!("/api/activities/{}", created["id"].as_str().unwrap()),
+        &token,
+        tenant.id,
+        json!({ "assigned_to": outsider.id }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // ...and one stored before the check isn't mailed them
+    let now = Utc::now();
+    new_task(tenant.id, outsider.id, "Leaked title", Some(now - Duration::hours(1))).insert(&db).await.unwrap();
+    let report = TaskService::notify_due(&db, tenant.id, now, Duration::minutes(60)).await.unwrap();
+    assert_eq!((report.emails, report.overdue), (0, 0));
+}
+
+#[tokio::test]
+async fn test_activities_only_link_records_in_their_tenant() {
+    let (app, db) = setup_test_app().await;
+    let (admin, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let other_tenant = test_utils::create_test_tenant(&db).await;
+    let account = join_tenant(&db, tenant.id, admin.id).await;
+    let foreign_account = join_tenant(&db, other_tenant.id, admin.id).await;
+    let customer = test_utils::create_customer(&db, tenant.id).await;
+    let foreign_customer = test_utils::create_customer(&db, other_tenant.id).await;
+    let note = |account_id: Uuid, customer_id: Uuid| json!({
+        "account_id": account_id,
+        "customer_id": customer_id,
+        "activity_type": "Note",
+        "title": "Call notes",
+        "status": "Completed",
+        "associated_entities": [],
+        "files": []
+    });
+
+    let (status, _) = call(&app, "POST", "/api/activities", &token, tenant.id, note(foreign_account.id, customer.id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = call(&app, "POST", "/api/activities", &token, tenant.id, note(account.id, foreign_customer.id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, created) = call(&app, "POST", "/api/activities", &token, tenant.id, note(account.id, customer.id)).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", created);
+
+    let uri = format!("/api/activities/{}", created["id"].as_str().unwrap());
+    let (status, _) = call(&app, "PUT", &uri, &token, tenant.id, json!({ "customer_id": foreign_customer.id })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let stored = activity::Entity::find_by_id(Uuid::parse_str(created["id"].as_str().unwrap()).unwrap())
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.customer_id, Some(customer.id));
+}
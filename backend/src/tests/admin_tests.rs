@@ -145,6 +145,8 @@ async fn test_admin_domain_management() {
         app_type: Set("anchor".to_string()),
         settings: Set(Some(serde_json::json!({}))),
         data_seed_name: sea_orm::ActiveValue::NotSet,
+        data_seed_version: sea_orm::ActiveValue::NotSet,
+        data_seed_applied_at: sea_orm::ActiveValue::NotSet,
         database_url: sea_orm::ActiveValue::NotSet,
         created_at: sea_orm::ActiveValue::NotSet,
         updated_at: sea_orm::ActiveValue::NotSet,
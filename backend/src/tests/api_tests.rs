@@ -23,6 +23,7 @@ use fake::{Fake, faker::{
 use urlencoding;
 use dotenv::dotenv;
 use crate::handlers::passkeys::{WebauthnStateRaw, WebauthnState};
+use crate::middleware::request_logger::RequestLogger;
 use webauthn_rs::prelude::*;
 use std::sync::Arc;
 use moka::future::Cache;
@@ -70,7 +71,7 @@ pub async fn setup_test_app() -> (Router, DatabaseConnection) {
         auth_state: Cache::builder().time_to_live(Duration::from_secs(300)).build(),
     });
 
-    let app = api::create_router(db.clone())
+    let app = api::create_router(db.clone(), RequestLogger::start(db.clone()))
         .layer(axum::Extension(webauthn_state));
 
     (app, db)
@@ -0,0 +1,215 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+use tower::ServiceExt;
+use serde_json::json;
+use uuid::Uuid;
+use crate::entities::api_token;
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+async fn mint_token(app: &Router, admin_token: &str, tenant_id: Uuid, scopes: serde_json::Value) -> (Uuid, String) {
+    let response = app.clone()
+        .oneshot(
+            Request::builder().header("Host", "localhost")
+                .method("POST")
+                .uri(format!("/api/admin/developer/tenant/{}/api-tokens", tenant_id))
+                .header("Authorization", format!("Bearer {}", admin_token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "scopes": scopes }).to_string()))
+                .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    let id = Uuid::parse_str(body["id"].as_str().unwrap()).unwrap();
+    let token = body["token"].as_str().unwrap().to_string();
+    assert!(token.starts_with("atls_"));
+
+    (id, token)
+}
+
+#[tokio::test]
+async fn test_api_token_scope_enforcement() {
+    let (app, db) = setup_test_app().await;
+    let (_admin_user, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+
+    let (token_id, token) = mint_token(&app, &admin_token, tenant.id, json!(["crm:read"])).await;
+
+    // crm:read grants reads on CRM route groups
+    assert_eq!(test_utils::call_without_tenant(&app, "GET", "/api/deals", &token, None).await.0, StatusCode::OK);
+
+    // ...but not writes
+    let create_status = test_utils::call_without_tenant(&app, "POST", "/api/deals", &token, Some(json!({
+        "customer_id": Uuid::new_v4(),
+        "name": "Scoped Deal",
+        "amount": 100.0,
+        "status": "Open",
+        "stage": "Prospecting"
+    }))).await.0;
+    assert_eq!(create_status, StatusCode::FORBIDDEN);
+
+    // Other route groups require their own scope
+    assert_eq!(test_utils::call_without_tenant(&app, "POST", "/api/feeds", &token, Some(json!({}))).await.0, StatusCode::FORBIDDEN);
+
+    // Machine principals never reach the platform admin surface
+    assert_eq!(test_utils::call_without_tenant(&app, "GET", "/api/admin/users", &token, None).await.0, StatusCode::FORBIDDEN);
+
+    let record = api_token::Entity::find_by_id(token_id).one(&db).await.unwrap().unwrap();
+    assert!(record.last_used_at.is_some(), "last_used_at should be stamped on use");
+}
+
+#[tokio::test]
+async fn test_api_token_rejects_expired_and_unknown_tokens() {
+    let (app, db) = setup_test_app().await;
+    let (_admin_user, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+
+    let (token_id, token) = mint_token(&app, &admin_token, tenant.id, json!(["crm:write"])).await;
+    assert_eq!(test_utils::call_without_tenant(&app, "GET", "/api/leads", &token, None).await.0, StatusCode::OK);
+
+    let unknown = format!("{}tampered", &token[..token.len() - 8]);
+    assert_eq!(test_utils::call_without_tenant(&app, "GET", "/api/leads", &unknown, None).await.0, StatusCode::UNAUTHORIZED);
+
+    api_token::ActiveModel {
+        id: Set(token_id),
+        expires_at: Set(Some((chrono::Utc::now() - chrono::Duration::days(1)).into())),
+        ..Default::default()
+    }
+    .update(&db)
+    .await
+    .unwrap();
+
+    assert_eq!(test_utils::call_without_tenant(&app, "GET", "/api/leads", &token, None).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_api_token_writes_without_a_user() {
+    let (app, db) = setup_test_app().await;
+    let (_admin_user, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+
+    let (_token_id, token) = mint_token(&app, &admin_token, tenant.id, json!(["crm:write"])).await;
+
+    // Notes written by a token have no authoring user
+    let customer = test_utils::create_customer(&db, tenant.id).await;
+    let (status, note) = test_utils::call(
+        &app,
+        "POST",
+        &format!("/api/customers/{}/notes", customer.id),
+        &token,
+        tenant.id,
+        json!({
+            "content": "Synced from the ERP",
+            "entity_type": "Customer",
+            "entity_id": customer.id,
+            "files": []
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(note["created_by"].is_null());
+
+    // "Mine" endpoints need a person behind the request
+    assert_eq!(test_utils::call_without_tenant(&app, "GET", "/api/activities/mine", &token, None).await.0, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_api_token_refused_on_routes_without_a_scope() {
+    let (app, db) = setup_test_app().await;
+    let (_admin_user, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let other_tenant = test_utils::create_test_tenant(&db).await;
+
+    let (_token_id, token) = mint_token(&app, &admin_token, tenant.id, json!(["crm:read"])).await;
+
+    // Tenant management declares no token scope, so tokens never reach it
+    let tenant_uri = format!("/api/tenants/{}", other_tenant.id);
+    assert_eq!(test_utils::call_without_tenant(&app, "DELETE", &tenant_uri, &token, None).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(test_utils::call_without_tenant(&app, "PUT", &tenant_uri, &token, Some(json!({ "name": "Taken over" }))).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(test_utils::call_without_tenant(&app, "GET", "/api/accounts", &token, None).await.0, StatusCode::FORBIDDEN);
+
+    // Settings need the secrets scope
+    let settings_uri = format!("/api/tenants/{}/settings", tenant.id);
+    assert_eq!(
+        test_utils::call_without_tenant(&app, "POST", &settings_uri, &token, Some(json!({ "key": "k", "value": "v" }))).await.0,
+        StatusCode::FORBIDDEN
+    );
+
+    let record = crate::entities::tenant::Entity::find_by_id(other_tenant.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(record.site_status, crate::services::tenant::STATUS_ACTIVE);
+}
+
+#[tokio::test]
+async fn test_api_token_without_prefix_must_be_reminted() {
+    let (app, db) = setup_test_app().await;
+    let (_admin_user, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+
+    let (token_id, token) = mint_token(&app, &admin_token, tenant.id, json!(["crm:read"])).await;
+
+    // Tokens minted before lookup prefixes were recorded
+    api_token::ActiveModel {
+        id: Set(token_id),
+        token_prefix: Set(None),
+        ..Default::default()
+    }
+    .update(&db)
+    .await
+    .unwrap();
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder().header("Host", "localhost")
+                .method("GET")
+                .uri("/api/leads")
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(String::from_utf8_lossy(&body_bytes).contains("mint a new token"));
+}
+
+#[tokio::test]
+async fn test_api_token_deletes_and_configures_only_with_an_admin_scope() {
+    let (app, db) = setup_test_app().await;
+    let (_admin_user, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let customer = test_utils::create_customer(&db, tenant.id).await;
+    let customer_uri = format!("/api/customers/{}", customer.id);
+    let pipeline = json!({
+        "name": "Integrations",
+        "stages": [
+            { "name": "Open", "probability": 10 },
+            { "name": "Won", "outcome": "won" },
+            { "name": "Lost", "outcome": "lost" }
+        ]
+    });
+
+    // crm:write edits records like a member...
+    let (_, writer) = mint_token(&app, &admin_token, tenant.id, json!(["crm:write"])).await;
+    let (status, _) = test_utils::call_without_tenant(&app, "PUT", &customer_uri, &writer, Some(json!({ "name": "Synced" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    // ...but can't delete them or change the tenant's configuration
+    let (status, _) = test_utils::call_without_tenant(&app, "DELETE", &customer_uri, &writer, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = test_utils::call_without_tenant(&app, "POST", "/api/deals/pipelines", &writer, Some(pipeline.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (_, admin) = mint_token(&app, &admin_token, tenant.id, json!(["crm:admin"])).await;
+    let (status, _) = test_utils::call_without_tenant(&app, "POST", "/api/deals/pipelines", &admin, Some(pipeline)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = test_utils::call_without_tenant(&app, "DELETE", &customer_uri, &admin, None).await;
+    assert!(status.is_success());
+}
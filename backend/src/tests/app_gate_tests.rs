@@ -0,0 +1,68 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use uuid::Uuid;
+
+use crate::entities::{app_domain, app_instance};
+use crate::middleware::site_context::clear_site_cache;
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils::{self, call_on_host};
+
+async fn install(db: &DatabaseConnection, tenant_id: Uuid, app_type: &str) -> app_instance::Model {
+    app_instance::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant_id),
+        app_type: Set(app_type.to_string()),
+        database_url: Set(None),
+        data_seed_name: Set(None),
+        data_seed_version: Set(None),
+        data_seed_applied_at: Set(None),
+        settings: Set(None),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_app_routes_are_hidden_until_the_tenant_installs_the_app() {
+    let (app, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let anchor = install(&db, tenant.id, "anchor").await;
+    let host = format!("anchor-{}.test", Uuid::new_v4().simple());
+    app_domain::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        app_instance_id: Set(anchor.id),
+        domain_name: Set(host.clone()),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    // Listings belong to the network app, which this tenant doesn't run
+    let listings_uri = format!("/listings?tenant_id={}", tenant.id);
+    let (status, _) = call_on_host(&app, "GET", &host, &listings_uri, None, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    install(&db, tenant.id, "Network").await;
+    clear_site_cache().await;
+
+    let (status, _) = call_on_host(&app, "GET", &host, &listings_uri, None, None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let (status, apps) = call_on_host(&app, "GET", "localhost", "/api/admin/platform/apps", Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let entry = apps
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|a| a["instance_id"] == anchor.id.to_string())
+        .unwrap();
+    assert_eq!(entry["app_id"], "anchor");
+    assert_eq!(entry["enabled_apps"], serde_json::json!(["anchor", "network_instance"]));
+}
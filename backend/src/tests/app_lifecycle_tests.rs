@@ -0,0 +1,156 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use sea_orm::{ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, QueryFilter, Set, Statement};
+use serde_json::json;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::atlas_apps::anchor::AnchorApp;
+use crate::entities::{app_instance, tenant_background_job};
+use crate::migration::runner;
+use crate::services::app_lifecycle::AppLifecycle;
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+#[tokio::test]
+async fn test_app_migrations_are_recorded_in_their_own_table() {
+    let (_, db) = setup_test_app().await;
+    let recorded_in = |table: String| {
+        let db = db.clone();
+        async move {
+            db.query_one(Statement::from_string(
+                DbBackend::Postgres,
+                format!(
+                    r#"SELECT COUNT(*) AS n FROM "{}" WHERE version = 'm20260408_000002_create_anchor_legacy_tables'"#,
+                    table
+                ),
+            ))
+            .await
+            .unwrap()
+            .unwrap()
+            .try_get::<i64>("", "n")
+            .unwrap()
+        }
+    };
+
+    assert_eq!(recorded_in(runner::app_migration_table("anchor")).await, 1);
+    assert_eq!(recorded_in(runner::CORE_MIGRATION_TABLE.to_string()).await, 0);
+}
+
+#[tokio::test]
+async fn test_settings_are_validated_against_the_app_schema() {
+    let app = AnchorApp;
+    assert!(AppLifecycle::validate_settings(&app, None).is_ok());
+    assert!(AppLifecycle::validate_settings(&app, Some(&json!({ "site_title": "Ruud", "theme": "dark" }))).is_ok());
+
+    let err = AppLifecycle::validate_settings(&app, Some(&json!({ "site_title": 42 }))).unwrap_err();
+    assert!(err.contains("settings.site_title"));
+    assert!(AppLifecycle::validate_settings(&app, Some(&json!(["not", "an", "object"]))).is_err());
+}
+
+#[tokio::test]
+async fn test_install_and_uninstall_run_app_hooks() {
+    let (_, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let app = AnchorApp;
+
+    let instance = AppLifecycle::install(&db, &app, app_instance::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant.id),
+        app_type: Set("anchor".to_string()),
+        database_url: Set(None),
+        data_seed_name: Set(None),
+        data_seed_version: Set(None),
+        data_seed_applied_at: Set(None),
+        settings: Set(Some(json!({ "site_title": "Anchor" }))),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    })
+    .await
+    .unwrap();
+
+    let jobs = tenant_background_job::Entity::find()
+        .filter(tenant_background_job::Column::TenantId.eq(tenant.id))
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].job_type, "BitcoinSync");
+
+    AppLifecycle::uninstall(&db, &app, instance.clone()).await.unwrap();
+
+    assert!(app_instance::Entity::find_by_id(instance.id).one(&db).await.unwrap().is_none());
+    let jobs = tenant_background_job::Entity::find()
+        .filter(tenant_background_job::Column::TenantId.eq(tenant.id))
+        .all(&db)
+        .await
+        .unwrap();
+    assert!(jobs.is_empty());
+}
+
+#[tokio::test]
+async fn test_create_app_instance_rejects_unknown_apps_and_invalid_settings() {
+    let (app, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let mut username = format!("apps{}", Uuid::new_v4().simple());
+    let (_, login) = test_utils::register_test_user(&app, tenant.id, &mut username).await;
+    let token = login["token"].as_str().unwrap().to_string();
+
+    for (app_type, settings) in [("NoSuchApp", json!({})), ("anchor", json!({ "booking_url": false }))] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/app-instances")
+                    .header("Host", "localhost")
+                    .header("Authorization", format!("Bearer {}", token))
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(json!({
+                        "tenant_id": tenant.id,
+                        "app_type": app_type,
+                        "settings": settings
+                    }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn test_create_app_instance_is_confined_to_the_callers_tenant() {
+    let (app, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let other_tenant = test_utils::create_test_tenant(&db).await;
+    let mut username = format!("apps{}", Uuid::new_v4().simple());
+    let (_, login) = test_utils::register_test_user(&app, other_tenant.id, &mut username).await;
+    let token = login["token"].as_str().unwrap().to_string();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/app-instances")
+                .header("Host", "localhost")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "tenant_id": tenant.id, "app_type": "anchor" }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let instances = app_instance::Entity::find()
+        .filter(app_instance::Column::TenantId.eq(tenant.id))
+        .all(&db)
+        .await
+        .unwrap();
+    assert!(instances.is_empty());
+}
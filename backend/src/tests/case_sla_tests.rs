@@ -0,0 +1,327 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::entities::{case, tenant_background_job, user_account};
+use crate::services::activity_tasks::REMINDER_JOB_TYPE;
+use crate::services::case_sla;
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils::{self, call, create_customer, join_tenant};
+
+fn timestamp(value: &Value) -> DateTime<Utc> {
+    serde_json::from_value(value.clone()).unwrap()
+}
+
+#[tokio::test]
+async fn test_case_status_workflow_and_sla_targets() {
+    let (app, db) = setup_test_app().await;
+    let (_, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let customer = create_customer(&db, tenant.id).await;
+    let new_case = json!({
+        "customer_id": customer.id,
+        "title": "Checkout fails",
+        "description": "500 on payment",
+        "priority": "high"
+    });
+
+    // Without a policy the case has no SLA
+    let (status, case) = call(&app, "POST", "/api/cases", &token, tenant.id, new_case.clone()).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", case);
+    assert_eq!((case["status"].as_str(), case["priority"].as_str()), (Some("New"), Some("High")));
+    assert!(case["resolution_due_at"].is_null());
+
+    let (status, _) = call(&app, "PUT", "/api/cases/sla-policies/High", &token, tenant.id, json!({
+        "first_response_minutes": 240, "resolution_minutes": 60
+    })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) = call(&app, "PUT", "/api/cases/sla-policies/Someday", &token, tenant.id, json!({
+        "first_response_minutes": 60, "resolution_minutes": 240
+    })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, policy) = call(&app, "PUT", "/api/cases/sla-policies/high", &token, tenant.id, json!({
+        "first_response_minutes": 60, "resolution_minutes": 240
+    })).await;
+    assert_eq!(status, StatusCode::OK, "{}", policy);
+    assert_eq!(policy["priority"], "High");
+
+    let (status, case) = call(&app, "POST", "/api/cases", &token, tenant.id, new_case).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let opened = timestamp(&case["created_at"]);
+    assert_eq!(timestamp(&case["first_response_due_at"]), opened + Duration::minutes(60));
+    assert_eq!(timestamp(&case["resolution_due_at"]), opened + Duration::minutes(240));
+    let status_uri = format!("/api/cases/{}/status", case["id"].as_str().unwrap());
+
+    let (status, _) = call(&app, "POST", &status_uri, &token, tenant.id, json!({ "status": "Closed" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = call(&app, "POST", &status_uri, &token, tenant.id, json!({ "status": "Escalated" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, open) = call(&app, "POST", &status_uri, &token, tenant.id, json!({ "status": "open" })).await;
+    assert_eq!(status, StatusCode::OK, "{}", open);
+    assert!(!open["first_responded_at"].is_null());
+    assert_eq!(open["first_response_breached"], false);
+    for next in ["Pending", "Resolved"] {
+        let (status, moved) = call(&app, "POST", &status_uri, &token, tenant.id, json!({ "status": next })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(moved["status"], next);
+    }
+    let (status, _) = call(&app, "POST", &status_uri, &token, tenant.id, json!({ "status": "Pending" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, closed) = call(&app, "POST", &status_uri, &token, tenant.id, json!({ "status": "Closed" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!closed["closed_at"].is_null() && !closed["resolved_at"].is_null());
+
+    // Reopening restarts the resolution clock
+    let (status, reopened) = call(&app, "POST", &status_uri, &token, tenant.id, json!({ "status": "Open" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(reopened["closed_at"].is_null() && reopened["resolved_at"].is_null());
+    assert!(timestamp(&reopened["resolution_due_at"]) > timestamp(&case["resolution_due_at"]));
+}
+
+#[tokio::test]
+async fn test_breached_cases_escalate_once_and_show_in_report() {
+    let (app, db) = setup_test_app().await;
+    let (agent, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let (supervisor, _) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let customer = create_customer(&db, tenant.id).await;
+
+    // Escalations only go to members of the tenant
+    let (status, _) = call(&app, "PUT", "/api/cases/sla-policies/Urgent", &token, tenant.id, json!({
+        "first_response_minutes": 30, "resolution_minutes": 120, "escalate_to": supervisor.id
+    })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    join_tenant(&db, tenant.id, supervisor.id).await;
+
+    let (status, _) = call(&app, "PUT", "/api/cases/sla-policies/Urgent", &token, tenant.id, json!({
+        "first_response_minutes": 30, "resolution_minutes": 120, "escalate_to": supervisor.id
+    })).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Cases are only assigned to members of the tenant too
+    let (status, _) = call(&app, "POST", "/api/cases", &token, tenant.id, json!({
+        "customer_id": customer.id,
+        "title": "Site down",
+        "description": "Customers can't get in",
+        "priority": "Urgent",
+        "assigned_to": agent.id
+    })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    join_tenant(&db, tenant.id, agent.id).await;
+
+    let mut case_ids = Vec::new();
+    for title in ["Site down", "Login broken"] {
+        let (status, case) = call(&app, "POST", "/api/cases", &token, tenant.id, json!({
+            "customer_id": customer.id,
+            "title": title,
+            "description": "Customers can't get in",
+            "priority": "Urgent",
+            "assigned_to": agent.id
+        })).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", case);
+        case_ids.push(Uuid::parse_str(case["id"].as_str().unwrap()).unwrap());
+    }
+
+    // The second case gets a reply right away; the first one sits past its response target
+    let (status, _) = call(&app, "POST", &format!("/api/cases/{}/notes", case_ids[1]), &token, tenant.id, json!({ "content": "Looking into it" })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let late = case::Entity::find_by_id(case_ids[0]).one(&db).await.unwrap().unwrap();
+    let mut late: case::ActiveModel = late.into();
+    late.created_at = Set(Utc::now() - Duration::minutes(45));
+    late.first_response_due_at = Set(Some(Utc::now() - Duration::minutes(15)));
+    late.update(&db).await.unwrap();
+
+    // No SMTP host is configured, so the notification is mocked
+    let report = case_sla::escalate_breached(&db, tenant.id, Utc::now()).await.unwrap();
+    assert_eq!((report.escalated, report.reassigned, report.notified, report.failed), (1, 1, 1, 0));
+    let report = case_sla::escalate_breached(&db, tenant.id, Utc::now()).await.unwrap();
+    assert_eq!(report.escalated, 0);
+    let escalated = case::Entity::find_by_id(case_ids[0]).one(&db).await.unwrap().unwrap();
+    assert_eq!(escalated.assigned_to, Some(supervisor.id));
+    assert!(escalated.response_escalated_at.is_some() && escalated.resolution_escalated_at.is_none());
+
+    let (status, report) = call(&app, "GET", "/api/cases/reports/sla", &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::OK, "{}", report);
+    let urgent = &report["priorities"][0];
+    assert_eq!(urgent["priority"], "Urgent");
+    assert_eq!(urgent["case_count"], 2);
+    assert_eq!(urgent["first_response"]["met"], 1);
+    assert_eq!(urgent["first_response"]["breached"], 1);
+    assert_eq!(urgent["first_response"]["compliance_rate"], 0.5);
+    assert_eq!(urgent["resolution"]["in_progress"], 2);
+    assert_eq!(report["first_response"]["compliance_rate"], 0.5);
+}
+
+#[tokio::test]
+async fn test_escalations_only_mail_tenant_members() {
+    let (app, db) = setup_test_app().await;
+    let (agent, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let (outsider, _) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let customer = create_customer(&db, tenant.id).await;
+    join_tenant(&db, tenant.id, agent.id).await;
+
+    let (status, _) = call(&app, "PUT", "/api/cases/sla-policies/High", &token, tenant.id, json!({
+        "first_response_minutes": 30, "resolution_minutes": 120
+    })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, case) = call(&app, "POST", "/api/cases", &token, tenant.id, json!({
+        "customer_id": customer.id,
+        "title": "Invoice missing",
+        "description": "No invoice for March",
+        "priority": "High",
+        "assigned_to": agent.id
+    })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", case);
+    let case_uri = format!("/api/cases/{}", case["id"].as_str().unwrap());
+    let (status, _) = call(&app, "PUT", &case_uri, &token, tenant.id, json!({ "assigned_to": outsider.id })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // A holder from before assignments were checked is escalated but not mailed
+    let stored = case::Entity::find_by_id(Uuid::parse_str(case["id"].as_str().unwrap()).unwrap()).one(&db).await.unwrap().unwrap();
+    let mut stored: case::ActiveModel = stored.into();
+    stored.assigned_to = Set(Some(outsider.id));
+    stored.first_response_due_at = Set(Some(Utc::now() - Duration::minutes(5)));
+    stored.update(&db).await.unwrap();
+
+    let report = case_sla::escalate_breached(&db, tenant.id, Utc::now()).await.unwrap();
+    assert_eq!((report.escalated, report.notified, report.failed), (1, 0, 0));
+}
+
+#[tokio::test]
+async fn test_escalation_keeps_the_holder_when_the_escalation_user_left() {
+    let (app, db) = setup_test_app().await;
+    let (agent, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let (supervisor, _) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let customer = create_customer(&db, tenant.id).await;
+    join_tenant(&db, tenant.id, agent.id).await;
+    let supervisor_account = join_tenant(&db, tenant.id, supervisor.id).await;
+
+    let (status, _) = call(&app, "PUT", "/api/cases/sla-policies/Urgent", &token, tenant.id, json!({
+        "first_response_minutes": 30, "resolution_minutes": 120, "escalate_to": supervisor.id
+    })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, case) = call(&app, "POST", "/api/cases", &token, tenant.id, json!({
+        "customer_id": customer.id,
+        "title": "Checkout failing",
+        "description": "Payments time out",
+        "priority": "Urgent",
+        "assigned_to": agent.id
+    })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", case);
+    let case_id = Uuid::parse_str(case["id"].as_str().unwrap()).unwrap();
+    let stored = case::Entity::find_by_id(case_id).one(&db).await.unwrap().unwrap();
+    let mut stored: case::ActiveModel = stored.into();
+    stored.first_response_due_at = Set(Some(Utc::now() - Duration::minutes(5)));
+    stored.update(&db).await.unwrap();
+
+    // The supervisor leaves the tenant after the policy named them
+    let membership = user_account::Entity::find()
+        .filter(user_account::Column::UserId.eq(supervisor.id))
+        .filter(user_account::Column::AccountId.eq(supervisor_account.id))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut membership: user_account::ActiveModel = membership.into();
+    membership.is_active = Set(false);
+    membership.update(&db).await.unwrap();
+
+    let report = case_sla::escalate_breached(&db, tenant.id, Utc::now()).await.unwrap();
+    assert_eq!((report.escalated, report.reassigned, report.notified, report.failed), (1, 0, 1, 0));
+    let escalated = case::Entity::find_by_id(case_id).one(&db).await.unwrap().unwrap();
+    assert_eq!(escalated.assigned_to, Some(agent.id));
+}
+
+#[tokio::test]
+async fn test_case_activities_check_the_holder_and_arm_reminders() {
+    let (app, db) = setup_test_app().await;
+    let (agent, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let (outsider, _) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let customer = create_customer(&db, tenant.id).await;
+    join_tenant(&db, tenant.id, agent.id).await;
+
+    let (status, case) = call(&app, "POST", "/api/cases", &token, tenant.id, json!({
+        "customer_id": customer.id,
+        "title": "Refund request",
+        "description": "Charged twice",
+        "priority": "Medium",
+        "assigned_to": agent.id
+    })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", case);
+    let case_id = Uuid::parse_str(case["id"].as_str().unwrap()).unwrap();
+    let activities_uri = format!("/api/cases/{}/activities", case_id);
+
+    let (status, task) = call(&app, "POST", &activities_uri, &token, tenant.id, json!({
+        "title": "Issue the refund",
+        "due_date": Utc::now() + Duration::days(1)
+    })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", task);
+    assert_eq!(task["assigned_to"], json!(agent.id));
+    let job = tenant_background_job::Entity::find()
+        .filter(tenant_background_job::Column::TenantId.eq(tenant.id))
+        .filter(tenant_background_job::Column::JobType.eq(REMINDER_JOB_TYPE))
+        .one(&db)
+        .await
+        .unwrap();
+    assert!(job.is_some());
+
+    // The case's holder must still be a member to be handed its tasks
+    let stored = case::Entity::find_by_id(case_id).one(&db).await.unwrap().unwrap();
+    let mut stored: case::ActiveModel = stored.into();
+    stored.assigned_to = Set(Some(outsider.id));
+    stored.update(&db).await.unwrap();
+    let (status, _) = call(&app, "POST", &activities_uri, &token, tenant.id, json!({ "title": "Follow up" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_priority_change_keeps_the_reopen_target_and_rearms_escalation() {
+    let (app, db) = setup_test_app().await;
+    let (_, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let customer = create_customer(&db, tenant.id).await;
+    for (priority, response, resolution) in [("High", 60, 240), ("Low", 600, 2400)] {
+        let (status, _) = call(&app, "PUT", &format!("/api/cases/sla-policies/{}", priority), &token, tenant.id, json!({
+            "first_response_minutes": response, "resolution_minutes": resolution
+        })).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (status, case) = call(&app, "POST", "/api/cases", &token, tenant.id, json!({
+        "customer_id": customer.id,
+        "title": "Export is slow",
+        "description": "Takes minutes",
+        "priority": "High"
+    })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", case);
+    let case_id = Uuid::parse_str(case["id"].as_str().unwrap()).unwrap();
+    let status_uri = format!("/api/cases/{}/status", case_id);
+    for next in ["Open", "Resolved", "Open"] {
+        let (status, _) = call(&app, "POST", &status_uri, &token, tenant.id, json!({ "status": next })).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    // The reopened case misses its resolution target and is escalated
+    let reopened = case::Entity::find_by_id(case_id).one(&db).await.unwrap().unwrap();
+    let reopened_at = reopened.reopened_at.expect("reopening is recorded");
+    let mut late: case::ActiveModel = reopened.into();
+    late.resolution_due_at = Set(Some(Utc::now() - Duration::minutes(1)));
+    late.update(&db).await.unwrap();
+    let report = case_sla::escalate_breached(&db, tenant.id, Utc::now()).await.unwrap();
+    assert_eq!(report.escalated, 1);
+
+    // Moving to a looser policy counts resolution from the reopen and clears the breach
+    let (status, updated) = call(&app, "PUT", &format!("/api/cases/{}", case_id), &token, tenant.id, json!({ "priority": "Low" })).await;
+    assert_eq!(status, StatusCode::OK, "{}", updated);
+    assert_eq!(timestamp(&updated["resolution_due_at"]), reopened_at + Duration::minutes(2400));
+    assert_eq!(timestamp(&updated["first_response_due_at"]), timestamp(&case["created_at"]) + Duration::minutes(600));
+    assert_eq!(updated["resolution_breached"], false);
+    let stored = case::Entity::find_by_id(case_id).one(&db).await.unwrap().unwrap();
+    assert!(stored.resolution_escalated_at.is_none());
+}
@@ -0,0 +1,118 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{Duration, Utc};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::entities::{app_domain, app_instance};
+use crate::services::custom_domains::{
+    CustomDomainService, STATUS_ACTIVE, STATUS_FAILED, STATUS_PENDING_VERIFICATION, STATUS_VERIFYING,
+};
+use crate::services::dns::{HostnameState, MockDnsProvider};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+async fn network_instance(db: &DatabaseConnection) -> app_instance::Model {
+    let tenant = test_utils::create_test_tenant(db).await;
+    app_instance::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant.id),
+        app_type: Set("Network".to_string()),
+        database_url: Set(None),
+        data_seed_name: Set(None),
+        data_seed_version: Set(None),
+        data_seed_applied_at: Set(None),
+        settings: Set(None),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+async fn listings_status(app: &Router, host: &str, tenant_id: Uuid) -> StatusCode {
+    let request = Request::builder()
+        .uri(format!("/listings?tenant_id={}", tenant_id))
+        .header("Host", host)
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_domain_is_served_only_after_verification_and_tls() {
+    let (app, db) = setup_test_app().await;
+    let instance = network_instance(&db).await;
+    let dns = MockDnsProvider::new();
+    let host = format!("shop-{}.example.com", Uuid::new_v4().simple());
+
+    let domain = CustomDomainService::create(&db, &instance, &host.to_uppercase(), Some(&dns)).await.unwrap();
+    assert_eq!(domain.domain_name, host);
+    assert_eq!(domain.status, STATUS_PENDING_VERIFICATION);
+    assert_eq!(listings_status(&app, &host, instance.tenant_id).await, StatusCode::NOT_FOUND);
+
+    // Nothing published yet
+    let domain = CustomDomainService::check(&db, &dns, &dns, domain).await.unwrap();
+    assert_eq!(domain.status, STATUS_PENDING_VERIFICATION);
+
+    let txt = &CustomDomainService::verification_records(&domain)[0];
+    dns.add_txt_record(&txt.name, &txt.value);
+    let domain = CustomDomainService::check(&db, &dns, &dns, domain).await.unwrap();
+    assert_eq!(domain.status, STATUS_VERIFYING);
+    assert!(dns.has_hostname(&host));
+
+    let domain = CustomDomainService::check(&db, &dns, &dns, domain).await.unwrap();
+    assert_eq!(domain.status, STATUS_VERIFYING);
+    assert_eq!(domain.ssl_status.as_deref(), Some("pending_validation"));
+
+    dns.set_hostname_state(&host, HostnameState::Active);
+    let domain = CustomDomainService::check(&db, &dns, &dns, domain).await.unwrap();
+    assert_eq!(domain.status, STATUS_ACTIVE);
+    assert_eq!(listings_status(&app, &host, instance.tenant_id).await, StatusCode::OK);
+
+    CustomDomainService::remove(&db, Some(&dns), domain.clone()).await.unwrap();
+    assert!(!dns.has_hostname(&host));
+    assert!(app_domain::Entity::find_by_id(domain.id).one(&db).await.unwrap().is_none());
+    assert_eq!(listings_status(&app, &host, instance.tenant_id).await, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_unverified_domains_fail_after_the_verification_window() {
+    let (_, db) = setup_test_app().await;
+    let instance = network_instance(&db).await;
+    let dns = MockDnsProvider::new();
+    let host = format!("late-{}.example.com", Uuid::new_v4().simple());
+
+    let domain = CustomDomainService::create(&db, &instance, &host, Some(&dns)).await.unwrap();
+    let mut stale: app_domain::ActiveModel = domain.into();
+    stale.created_at = Set(Utc::now() - Duration::days(8));
+    let domain = stale.update(&db).await.unwrap();
+
+    let domain = CustomDomainService::check(&db, &dns, &dns, domain).await.unwrap();
+    assert_eq!(domain.status, STATUS_FAILED);
+    assert!(domain.last_error.is_some());
+    assert!(!dns.has_hostname(&host));
+}
+
+#[tokio::test]
+async fn test_add_domain_rejects_invalid_names() {
+    let (app, db) = setup_test_app().await;
+    let (_, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let instance = network_instance(&db).await;
+
+    let request = Request::builder()
+        .method("POST")
+        .uri(format!("/api/admin/platform/apps/{}/domains", instance.id))
+        .header("Host", "localhost")
+        .header("Authorization", format!("Bearer {}", admin_token))
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::json!({ "domain_name": "not a domain" }).to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,172 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, Set};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::entities::lead;
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils::{self, call};
+
+#[tokio::test]
+async fn test_contact_properties_follow_field_definitions() {
+    let (app, db) = setup_test_app().await;
+    let (_, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+
+    for (field, expected) in [
+        (json!({ "key": "Tier", "label": "Tier", "field_type": "text" }), StatusCode::UNPROCESSABLE_ENTITY),
+        (json!({ "key": "tier", "label": "Tier", "field_type": "select" }), StatusCode::UNPROCESSABLE_ENTITY),
+        (json!({ "key": "tier", "label": "Tier", "field_type": "colour" }), StatusCode::UNPROCESSABLE_ENTITY),
+        (
+            json!({ "key": "tier", "label": "Tier", "field_type": "select", "options": ["Gold", "Silver"], "default_value": "Bronze" }),
+            StatusCode::UNPROCESSABLE_ENTITY,
+        ),
+        (
+            json!({ "key": "tier", "label": "Tier", "field_type": "select", "options": ["Gold", "Silver"], "default_value": "Silver" }),
+            StatusCode::CREATED,
+        ),
+        (json!({ "key": "tier", "label": "Tier again", "field_type": "text" }), StatusCode::CONFLICT),
+        (json!({ "key": "seats", "label": "Seats", "field_type": "number", "required": true }), StatusCode::CREATED),
+        (json!({ "key": "newsletter", "label": "Newsletter", "field_type": "boolean", "position": -1 }), StatusCode::CREATED),
+    ] {
+        let (status, body) = call(&app, "POST", "/api/custom-fields/contact", &token, tenant.id, field.clone()).await;
+        assert_eq!(status, expected, "{} -> {}", field, body);
+    }
+
+    let (status, schema) = call(&app, "GET", "/api/custom-fields/contacts", &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::OK, "{}", schema);
+    let keys: Vec<&str> = schema["fields"].as_array().unwrap().iter().map(|f| f["key"].as_str().unwrap()).collect();
+    assert_eq!(keys, vec!["newsletter", "tier", "seats"]);
+    assert_eq!(schema["form"][0]["field_type"], "checkbox");
+    assert_eq!(schema["form"][1]["options"], json!([{ "label": "Gold", "value": "Gold" }, { "label": "Silver", "value": "Silver" }]));
+    assert_eq!(schema["form"][1]["default_value"], "Silver");
+    assert_eq!(schema["form"][2]["required"], true);
+
+    let (status, _) = call(&app, "POST", "/api/contacts", &token, tenant.id, json!({ "name": "Ada" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) = call(&app, "POST", "/api/contacts", &token, tenant.id, json!({
+        "name": "Ada", "properties": { "seats": "many" }
+    })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Form inputs arrive as strings; undefined keys are kept
+    let (status, contact) = call(&app, "POST", "/api/contacts", &token, tenant.id, json!({
+        "name": "Ada", "properties": { "seats": "12", "newsletter": "on", "nickname": "Countess" }
+    })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", contact);
+    assert_eq!(contact["properties"], json!({ "seats": 12, "newsletter": true, "tier": "Silver", "nickname": "Countess" }));
+
+    let uri = format!("/api/contacts/{}", contact["id"].as_str().unwrap());
+    let (status, _) = call(&app, "PUT", &uri, &token, tenant.id, json!({ "properties": { "tier": "Gold" } })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, updated) = call(&app, "PUT", &uri, &token, tenant.id, json!({ "properties": { "tier": "Gold", "seats": 3 } })).await;
+    assert_eq!(status, StatusCode::OK, "{}", updated);
+    assert_eq!(updated["properties"], json!({ "tier": "Gold", "seats": 3 }));
+
+    // Relaxing the field lets records drop it
+    let (status, _) = call(&app, "PUT", "/api/custom-fields/contact/seats", &token, tenant.id, json!({ "label": "Licensed seats" })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call(&app, "POST", "/api/contacts", &token, tenant.id, json!({ "name": "Grace" })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, _) = call(&app, "DELETE", "/api/custom-fields/contact/seats", &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = call(&app, "DELETE", "/api/custom-fields/contact/seats", &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_lead_list_filters_and_sorts_on_custom_fields() {
+    let (app, db) = setup_test_app().await;
+    let (_, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+
+    for field in [
+        json!({ "key": "industry", "label": "Industry", "field_type": "select", "options": ["Retail", "Finance"] }),
+        json!({ "key": "employees", "label": "Employees", "field_type": "number" }),
+    ] {
+        let (status, body) = call(&app, "POST", "/api/custom-fields/lead", &token, tenant.id, field).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+    }
+    for (name, properties) in [
+        ("Small shop", json!({ "industry": "Retail", "employees": 9 })),
+        ("Big bank", json!({ "industry": "Finance", "employees": 5000 })),
+        ("Chain store", json!({ "industry": "Retail", "employees": 120 })),
+        ("Unknown", json!({ "industry": "Retail" })),
+    ] {
+        lead::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(name.to_string()),
+            is_converted: Set(false),
+            converted_to_contact: Set(false),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+            tenant_id: Set(Some(tenant.id)),
+            properties: Set(Some(properties)),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    let names = |leads: &Value| -> Vec<String> {
+        leads.as_array().unwrap().iter().map(|lead| lead["name"].as_str().unwrap().to_string()).collect()
+    };
+    let (status, retail) = call(&app, "GET", "/api/leads?properties.industry=Retail&sort=properties.employees&order=desc", &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::OK, "{}", retail);
+    assert_eq!(names(&retail), vec!["Chain store", "Small shop", "Unknown"]);
+
+    // Numbers sort numerically, not as text
+    let (_, all) = call(&app, "GET", "/api/leads?sort=properties.employees", &token, tenant.id, Value::Null).await;
+    assert_eq!(names(&all), vec!["Small shop", "Chain store", "Big bank", "Unknown"]);
+    let (_, exact) = call(&app, "GET", "/api/leads?properties.employees=120", &token, tenant.id, Value::Null).await;
+    assert_eq!(names(&exact), vec!["Chain store"]);
+
+    for uri in [
+        "/api/leads?properties.budget=10",
+        "/api/leads?properties.industry=Mining",
+        "/api/leads?properties.employees=lots",
+        "/api/leads?sort=properties.employees&order=sideways",
+    ] {
+        let (status, _) = call(&app, "GET", uri, &token, tenant.id, Value::Null).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn test_profiles_validate_and_filter_on_custom_fields() {
+    let (app, db) = setup_test_app().await;
+    let (_, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let other = test_utils::create_test_tenant(&db).await;
+
+    let field = json!({ "key": "region", "label": "Region", "field_type": "select", "options": ["EU", "US"] });
+    let (status, body) = call(&app, "POST", "/api/custom-fields/profile", &token, tenant.id, field).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let profile = |name: &str, tenant_id: Uuid, region: &str| json!({
+        "id": Uuid::new_v4(),
+        "tenant_id": tenant_id,
+        "profile_type": "Business",
+        "display_name": name,
+        "contact_info": "hello@example.com",
+        "properties": { "region": region }
+    });
+
+    // The schema is the caller's tenant's, and so is the profile
+    let (status, _) = call(&app, "POST", "/api/profiles", &token, tenant.id, profile("Moon base", tenant.id, "Moon")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, _) = call(&app, "POST", "/api/profiles", &token, tenant.id, profile("Elsewhere", other.id, "EU")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    for (name, region) in [("Lisbon office", "EU"), ("Boston office", "US")] {
+        let (status, body) = call(&app, "POST", "/api/profiles", &token, tenant.id, profile(name, tenant.id, region)).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+    }
+
+    let (status, eu) = call(&app, "GET", "/api/profiles?properties.region=EU", &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::OK, "{}", eu);
+    let names: Vec<&str> = eu.as_array().unwrap().iter().map(|profile| profile["display_name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["Lisbon office"]);
+    let (status, _) = call(&app, "GET", "/api/profiles?properties.region=Mars", &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
@@ -0,0 +1,157 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::entities::deal_stage_history;
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils::{self, call, create_customer};
+
+#[tokio::test]
+async fn test_stage_changes_are_validated_and_recorded() {
+    let (app, db) = setup_test_app().await;
+    let (_, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let customer = create_customer(&db, tenant.id).await;
+
+    // Without a stage the deal starts in the default pipeline's first stage
+    let (status, deal) = call(&app, "POST", "/api/deals", &token, tenant.id, json!({
+        "customer_id": customer.id,
+        "name": "Pipeline Deal",
+        "amount": 2000.0
+    })).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", deal);
+    assert_eq!(deal["stage"], "Prospecting");
+    assert_eq!(deal["status"], "Open");
+    let stage_uri = format!("/api/deals/{}/stage", deal["id"].as_str().unwrap());
+
+    let (status, moved) = call(&app, "POST", &stage_uri, &token, tenant.id, json!({ "stage": "proposal" })).await;
+    assert_eq!(status, StatusCode::OK, "{}", moved);
+    assert_eq!(moved["stage"], "Proposal");
+
+    let (status, _) = call(&app, "POST", &stage_uri, &token, tenant.id, json!({ "stage": "Proposal" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = call(&app, "POST", &stage_uri, &token, tenant.id, json!({ "stage": "Daydreaming" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, won) = call(&app, "POST", &stage_uri, &token, tenant.id, json!({ "stage": "Closed Won" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(won["status"], "Won");
+    assert!(!won["close_date"].is_null());
+
+    // A won deal only goes back into the pipeline when explicitly reopened
+    let (status, _) = call(&app, "POST", &stage_uri, &token, tenant.id, json!({ "stage": "Negotiation" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, reopened) = call(&app, "POST", &stage_uri, &token, tenant.id, json!({ "stage": "Negotiation", "reopen": true })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reopened["status"], "Open");
+
+    let (status, history) = call(&app, "GET", &format!("/api/deals/{}/history", deal["id"].as_str().unwrap()), &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    let transitions: Vec<(Value, Value)> = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| (entry["from_stage"].clone(), entry["to_stage"].clone()))
+        .collect();
+    assert_eq!(transitions, vec![
+        (Value::Null, json!("Prospecting")),
+        (json!("Prospecting"), json!("Proposal")),
+        (json!("Proposal"), json!("Closed Won")),
+        (json!("Closed Won"), json!("Negotiation")),
+    ]);
+}
+
+#[tokio::test]
+async fn test_custom_pipeline_reports_forecast_and_velocity() {
+    let (app, db) = setup_test_app().await;
+    let (_, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let customer = create_customer(&db, tenant.id).await;
+
+    let definition = json!({
+        "name": "Renewals",
+        "stages": [
+            { "name": "Discovery", "probability": 20 },
+            { "name": "Demo", "probability": 60 },
+            { "name": "Renewed", "outcome": "won" },
+            { "name": "Churned", "outcome": "lost" }
+        ]
+    });
+    let (status, pipeline) = call(&app, "POST", "/api/deals/pipelines", &token, tenant.id, definition.clone()).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", pipeline);
+    assert_eq!(pipeline["stages"][2]["probability"], 100);
+    let (status, _) = call(&app, "POST", "/api/deals/pipelines", &token, tenant.id, definition).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = call(&app, "POST", "/api/deals/pipelines", &token, tenant.id, json!({
+        "name": "No way to win",
+        "stages": [{ "name": "Open", "probability": 50 }]
+    })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // The default pipeline exists alongside the custom one
+    let (_, pipelines) = call(&app, "GET", "/api/deals/pipelines", &token, tenant.id, Value::Null).await;
+    assert_eq!(pipelines.as_array().unwrap().len(), 2);
+
+    let pipeline_id = pipeline["id"].as_str().unwrap();
+    let mut deal_ids = Vec::new();
+    for (stage, amount) in [("Discovery", 1000.0), ("Demo", 500.0)] {
+        let (status, deal) = call(&app, "POST", "/api/deals", &token, tenant.id, json!({
+            "customer_id": customer.id,
+            "name": format!("{} deal", stage),
+            "amount": amount,
+            "pipeline_id": pipeline_id,
+            "stage": stage
+        })).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", deal);
+        deal_ids.push(deal["id"].as_str().unwrap().to_string());
+    }
+
+    // Stages of another pipeline are rejected
+    let (status, _) = call(&app, "POST", &format!("/api/deals/{}/stage", deal_ids[0]), &token, tenant.id, json!({ "stage": "Prospecting" })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, forecast) = call(&app, "GET", &format!("/api/deals/reports/forecast?pipeline_id={}", pipeline_id), &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(forecast["deal_count"], 2);
+    assert_eq!(forecast["total_amount"], 1500.0);
+    assert_eq!(forecast["weighted_amount"], 500.0);
+    assert_eq!(forecast["stages"].as_array().unwrap().len(), 2);
+
+    // The first deal sat in Discovery for four days before renewing
+    let discovery_deal = Uuid::parse_str(&deal_ids[0]).unwrap();
+    let entry = deal_stage_history::Entity::find()
+        .filter(deal_stage_history::Column::DealId.eq(discovery_deal))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut entry: deal_stage_history::ActiveModel = entry.into();
+    entry.changed_at = Set(Utc::now() - chrono::Duration::days(4));
+    entry.update(&db).await.unwrap();
+    let (status, _) = call(&app, "POST", &format!("/api/deals/{}/stage", deal_ids[0]), &token, tenant.id, json!({ "stage": "Renewed" })).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, velocity) = call(&app, "GET", &format!("/api/deals/reports/velocity?pipeline_id={}", pipeline_id), &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    let discovery = &velocity["stages"][0];
+    assert_eq!(discovery["name"], "Discovery");
+    assert!((discovery["avg_days_in_stage"].as_f64().unwrap() - 4.0).abs() < 0.01, "{}", discovery);
+    assert_eq!(velocity["stages"][1]["current_count"], 1);
+    assert_eq!(velocity["won_count"], 1);
+    assert_eq!(velocity["win_rate"], 1.0);
+
+    // Stages and pipelines that still hold deals can't be removed
+    let stages: Vec<Value> = pipeline["stages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|stage| stage["name"] != "Demo")
+        .map(|stage| json!({ "id": stage["id"], "name": stage["name"], "probability": stage["probability"], "outcome": stage["outcome"] }))
+        .collect();
+    let (status, _) = call(&app, "PUT", &format!("/api/deals/pipelines/{}", pipeline_id), &token, tenant.id, json!({ "stages": stages })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, _) = call(&app, "DELETE", &format!("/api/deals/pipelines/{}", pipeline_id), &token, tenant.id, Value::Null).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
@@ -0,0 +1,113 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use serde_json::json;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::atlas_apps::anchor::AnchorApp;
+use crate::entities::app_instance;
+use crate::services::app_lifecycle::AppLifecycle;
+use crate::services::custom_domains::{CustomDomainService, STATUS_ACTIVE, STATUS_VERIFYING};
+use crate::services::dns::{self, cname_target, DnsProvider, ManualProvider, MockDnsProvider};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+async fn network_instance(db: &DatabaseConnection, settings: Option<serde_json::Value>) -> app_instance::Model {
+    let tenant = test_utils::create_test_tenant(db).await;
+    app_instance::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant.id),
+        app_type: Set("Network".to_string()),
+        database_url: Set(None),
+        data_seed_name: Set(None),
+        data_seed_version: Set(None),
+        data_seed_applied_at: Set(None),
+        settings: Set(settings),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+async fn ask(app: &Router, domain: &str) -> StatusCode {
+    let request = Request::builder()
+        .uri(format!("/api/tls/ask?domain={}", domain))
+        .header("Host", "backend")
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_ask_only_authorizes_verified_domains() {
+    let (app, db) = setup_test_app().await;
+    let instance = network_instance(&db, None).await;
+    let dns = MockDnsProvider::new();
+    let host = format!("shop-{}.example.com", Uuid::new_v4().simple());
+
+    assert_eq!(ask(&app, &host).await, StatusCode::NOT_FOUND);
+
+    let domain = CustomDomainService::create(&db, &instance, &host, Some(&dns)).await.unwrap();
+    // Pending domains must not get a certificate
+    assert_eq!(ask(&app, &host).await, StatusCode::NOT_FOUND);
+
+    dns.add_cname_record(&host, &cname_target());
+    let domain = CustomDomainService::check(&db, &dns, &dns, domain).await.unwrap();
+    assert_eq!(domain.status, STATUS_VERIFYING);
+    assert_eq!(ask(&app, &host).await, StatusCode::OK);
+    assert_eq!(ask(&app, &host.to_uppercase()).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_manual_provider_activates_once_records_point_at_the_edge() {
+    let (_, db) = setup_test_app().await;
+    let instance = network_instance(&db, Some(json!({ "dns_provider": "manual" }))).await;
+    let host = format!("manual-{}.example.com", Uuid::new_v4().simple());
+
+    let ownership = MockDnsProvider::new();
+    let domain = CustomDomainService::create(&db, &instance, &host, Some(&ownership)).await.unwrap();
+    ownership.add_txt_record(
+        &format!("_atlas-challenge.{}", host),
+        &format!("atlas-verify={}", domain.verification_token.clone().unwrap()),
+    );
+
+    // Ownership is proven but traffic isn't routed yet
+    let unrouted = ManualProvider::new(Box::new(MockDnsProvider::new()));
+    assert_eq!(unrouted.routing_records(&host)[0].record_type, "CNAME");
+    let domain = CustomDomainService::check(&db, &unrouted, &ownership, domain).await.unwrap();
+    assert_eq!(domain.status, STATUS_VERIFYING);
+    let domain = CustomDomainService::check(&db, &unrouted, &ownership, domain).await.unwrap();
+    assert_eq!(domain.status, STATUS_VERIFYING);
+    assert!(domain.last_error.is_some());
+
+    let routes = MockDnsProvider::new();
+    routes.add_cname_record(&host, &cname_target());
+    let routed = ManualProvider::new(Box::new(routes));
+    let domain = CustomDomainService::check(&db, &routed, &ownership, domain).await.unwrap();
+    assert_eq!(domain.status, STATUS_ACTIVE);
+    assert_eq!(domain.ssl_status.as_deref(), Some("external"));
+}
+
+#[tokio::test]
+async fn test_providers_are_selected_by_name() {
+    assert!(dns::provider_named("caddy").unwrap().is_some());
+    assert!(dns::provider_named("manual").unwrap().is_some());
+    assert!(dns::provider_named("bogus").is_err());
+
+    let (_, db) = setup_test_app().await;
+    let instance = network_instance(&db, Some(json!({ "dns_provider": "caddy" }))).await;
+    let provider = dns::provider_for_instance(&instance).unwrap().unwrap();
+    assert_eq!(provider.provision_hostname("a.example.com").await.unwrap(), "caddy:a.example.com");
+
+    let app = AnchorApp;
+    assert!(AppLifecycle::validate_settings(&app, Some(&json!({ "dns_provider": "manual" }))).is_ok());
+    let err = AppLifecycle::validate_settings(&app, Some(&json!({ "dns_provider": "bogus" }))).unwrap_err();
+    assert!(err.contains("settings.dns_provider"));
+}
@@ -0,0 +1,93 @@
+use axum::http::StatusCode;
+use sea_orm::{ActiveModelTrait, Set};
+use serde_json::json;
+use uuid::Uuid;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::entities::webhook_endpoint;
+use crate::services::event_catalog::PlatformEvent;
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+#[tokio::test]
+async fn test_crm_mutations_emit_domain_events() {
+    let (app, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let mut username = format!("events{}", Uuid::new_v4().simple());
+    let (_, login) = test_utils::register_test_user(&app, tenant.id, &mut username).await;
+    let token = login["token"].as_str().unwrap().to_string();
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    webhook_endpoint::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant.id),
+        target_url: Set(format!("{}/events", mock_server.uri())),
+        secret_key: Set("whsec_events".to_string()),
+        subscribed_events: Set(json!(["crm.*"])),
+        is_active: Set(true),
+        consecutive_failures: Set(0),
+        failure_threshold: Set(10),
+        created_at: Set(Some(chrono::Utc::now().into())),
+        updated_at: Set(Some(chrono::Utc::now().into())),
+        ..Default::default()
+    }.insert(&db).await.unwrap();
+
+    let (status, customer) = test_utils::call_without_tenant(&app, "POST", "/api/customers", &token, Some(json!({
+        "name": "Evented Customer",
+        "customer_type": "Person",
+        "attributes": {
+            "shipper": false, "carrier": false, "loan_seeker": false, "loan_broker": false,
+            "software_vendor": false, "tenant": false, "software_development_client": false,
+            "salesforce_client": false, "web3_client": false, "bitcoiner": false, "zk": false,
+            "lender": false, "advertiser": false, "gp": false, "construction_contractor": false,
+            "construction_client": false, "landlord": false
+        }
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, deal) = test_utils::call_without_tenant(&app, "POST", "/api/deals", &token, Some(json!({
+        "customer_id": customer["id"],
+        "name": "Evented Deal",
+        "amount": 1000.0,
+        "status": "Open",
+        "stage": "Prospecting"
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let deal_id = deal["id"].as_str().unwrap().to_string();
+
+    let (status, _) = test_utils::call_without_tenant(&app, "PUT", &format!("/api/deals/{}", deal_id), &token, Some(json!({
+        "stage": "Closed Won",
+    }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    test_utils::drain_outbox(&db).await;
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let received: Vec<serde_json::Value> = mock_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| serde_json::from_slice(&request.body).unwrap())
+        .collect();
+    let find = |event: PlatformEvent| received.iter().find(|body| body["event"] == event.name()).cloned();
+
+    let created = find(PlatformEvent::CustomerCreated).expect("crm.customer.created should be delivered");
+    assert_eq!(created["tenant_id"], json!(tenant.id));
+    assert!(created["data"]["before"].is_null());
+    assert_eq!(created["data"]["after"]["name"], "Evented Customer");
+
+    assert!(find(PlatformEvent::DealCreated).is_some());
+    assert!(find(PlatformEvent::DealUpdated).is_some());
+    assert!(find(PlatformEvent::DealWon).is_some());
+    let stage_changed = find(PlatformEvent::DealStageChanged).expect("crm.deal.stage_changed should be delivered");
+    assert_eq!(stage_changed["entity_id"], json!(deal_id));
+    assert_eq!(stage_changed["data"]["before"]["stage"], "Prospecting");
+    assert_eq!(stage_changed["data"]["after"]["stage"], "Closed Won");
+}
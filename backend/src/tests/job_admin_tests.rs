@@ -0,0 +1,128 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, Set};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::entities::job_run;
+use crate::services::job_scheduler;
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+#[tokio::test]
+async fn test_job_types_are_listed_per_app() {
+    let (app, db) = setup_test_app().await;
+    let (_, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+
+    let (status, types) = test_utils::call_without_tenant(&app, "GET", "/api/admin/jobs/types", &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let bitcoin_sync = types
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["job_type"] == "BitcoinSync")
+        .expect("BitcoinSync should be registered");
+    assert_eq!(bitcoin_sync["app_id"], "anchor");
+    assert!(bitcoin_sync["default_config_payload"]["api_url"].is_string());
+}
+
+#[tokio::test]
+async fn test_create_and_update_tenant_job_validates_config_and_schedule() {
+    let (app, db) = setup_test_app().await;
+    let (_, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let jobs_uri = format!("/api/admin/jobs/tenant/{}", tenant.id);
+
+    let (status, _) = test_utils::call_without_tenant(&app, "POST", &jobs_uri, &token, Some(json!({ "job_type": "NoSuchJob" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = test_utils::call_without_tenant(&app, "POST", &jobs_uri, &token, Some(json!({
+        "job_type": "BitcoinSync",
+        "config": { "api_url": 42 }
+    }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = test_utils::call_without_tenant(&app, "POST", &jobs_uri, &token, Some(json!({
+        "job_type": "BitcoinSync",
+        "config": { "api_url": "https://blocks.example.test", "unexpected": true }
+    }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, job) = test_utils::call_without_tenant(&app, "POST", &jobs_uri, &token, Some(json!({
+        "job_type": "BitcoinSync",
+        "config": { "api_url": "https://blocks.example.test" }
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let job_uri = format!("{}/{}", jobs_uri, job["id"].as_str().unwrap());
+
+    let (status, _) = test_utils::call_without_tenant(&app, "POST", &jobs_uri, &token, Some(json!({ "job_type": "BitcoinSync" }))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, _) = test_utils::call_without_tenant(&app, "PUT", &job_uri, &token, Some(json!({ "cron_expression": "every tuesday" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, updated) = test_utils::call_without_tenant(&app, "PUT", &job_uri, &token, Some(json!({
+        "is_active": false,
+        "interval_seconds": 120
+    }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["is_active"], false);
+    assert_eq!(updated["interval_seconds"], 120);
+    assert!(updated["next_run_at"].is_string());
+
+    let (status, listed) = test_utils::call_without_tenant(&app, "GET", &jobs_uri, &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+
+    // Jobs are only reachable through their own tenant
+    let other = test_utils::create_test_tenant(&db).await;
+    let (status, _) = test_utils::call_without_tenant(&app, "GET", &format!("/api/admin/jobs/tenant/{}/{}", other.id, job["id"].as_str().unwrap()), &token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_run_now_records_a_run_and_respects_concurrency() {
+    let (app, db) = setup_test_app().await;
+    let (_, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+
+    let (status, job) = test_utils::call_without_tenant(&app, "POST", &format!("/api/admin/jobs/tenant/{}", tenant.id), &token, Some(json!({
+        "job_type": "BitcoinSync",
+        "is_active": false,
+        "config": { "api_url": "http://127.0.0.1:9/blocks" }
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let job_id = Uuid::parse_str(job["id"].as_str().unwrap()).unwrap();
+    let job_uri = format!("/api/admin/jobs/tenant/{}/{}", tenant.id, job_id);
+
+    let (status, run) = test_utils::call_without_tenant(&app, "POST", &format!("{}/run", job_uri), &token, None).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(run["job_id"], job["id"]);
+
+    // Hold the job's only slot with a run from another worker
+    job_run::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        job_id: Set(job_id),
+        tenant_id: Set(tenant.id),
+        job_type: Set("BitcoinSync".to_string()),
+        status: Set(job_scheduler::RUN_RUNNING.to_string()),
+        worker_id: Set("other-worker".to_string()),
+        started_at: Set(Utc::now().into()),
+        lease_expires_at: Set((Utc::now() + chrono::Duration::minutes(5)).into()),
+        finished_at: Set(None),
+        duration_ms: Set(None),
+        error: Set(None),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    let (status, _) = test_utils::call_without_tenant(&app, "POST", &format!("{}/run", job_uri), &token, None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, runs) = test_utils::call_without_tenant(&app, "GET", &format!("{}/runs", job_uri), &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(runs.as_array().unwrap().len(), 2);
+
+    // Running on demand leaves the schedule alone
+    let (_, job) = test_utils::call_without_tenant(&app, "GET", &job_uri, &token, None).await;
+    assert!(job["next_run_at"].is_null());
+}
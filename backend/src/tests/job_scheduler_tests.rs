@@ -0,0 +1,212 @@
+use chrono::{TimeZone, Utc};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::entities::{job_run, tenant_background_job};
+use crate::services::job_scheduler::{self, JobScheduler};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+use crate::traits::atlas_app::BackgroundJob;
+
+async fn create_job(db: &DatabaseConnection, config: Option<serde_json::Value>, timeout_seconds: i32) -> tenant_background_job::Model {
+    let tenant = test_utils::create_test_tenant(db).await;
+    tenant_background_job::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant.id),
+        job_type: Set("TestJob".to_string()),
+        config: Set(config),
+        interval_seconds: Set(600),
+        last_run: Set(None),
+        is_active: Set(true),
+        cron_expression: Set(None),
+        next_run_at: Set(None),
+        max_concurrency: Set(1),
+        timeout_seconds: Set(timeout_seconds),
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+async fn runs_for(db: &DatabaseConnection, job_id: Uuid) -> Vec<job_run::Model> {
+    job_run::Entity::find()
+        .filter(job_run::Column::JobId.eq(job_id))
+        .all(db)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_cron_schedule_takes_precedence_over_interval() {
+    let from = Utc.with_ymd_and_hms(2026, 4, 18, 10, 2, 30).unwrap();
+    let mut job = tenant_background_job::Model {
+        id: Uuid::new_v4(),
+        tenant_id: Uuid::new_v4(),
+        job_type: "TestJob".to_string(),
+        config: None,
+        interval_seconds: 600,
+        last_run: None,
+        is_active: true,
+        cron_expression: Some("*/5 * * * *".to_string()),
+        next_run_at: None,
+        max_concurrency: 1,
+        timeout_seconds: 60,
+    };
+    assert_eq!(job_scheduler::next_run_after(&job, from), Utc.with_ymd_and_hms(2026, 4, 18, 10, 5, 0).unwrap());
+
+    job.cron_expression = None;
+    assert_eq!(job_scheduler::next_run_after(&job, from), from + chrono::Duration::seconds(600));
+
+    assert!(job_scheduler::parse_cron("not a schedule").is_err());
+}
+
+#[tokio::test]
+async fn test_concurrent_schedulers_claim_each_occurrence_once() {
+    let (_, db) = setup_test_app().await;
+    let job = create_job(&db, None, 60).await;
+
+    let (first, second) = tokio::join!(
+        JobScheduler::claim_due_jobs_for(&db, Some(job.tenant_id)),
+        JobScheduler::claim_due_jobs_for(&db, Some(job.tenant_id)),
+    );
+    assert_eq!(first.unwrap().len() + second.unwrap().len(), 1);
+
+    let job = tenant_background_job::Entity::find_by_id(job.id).one(&db).await.unwrap().unwrap();
+    assert!(job.next_run_at.is_some_and(|next| next > Utc::now()));
+
+    // Due again, but its only concurrency slot is still taken by the first run
+    tenant_background_job::ActiveModel {
+        id: Set(job.id),
+        next_run_at: Set(Some((Utc::now() - chrono::Duration::seconds(1)).into())),
+        ..Default::default()
+    }
+    .update(&db)
+    .await
+    .unwrap();
+    let claimed = JobScheduler::claim_due_jobs_for(&db, Some(job.tenant_id)).await.unwrap();
+    assert!(claimed.is_empty());
+    assert_eq!(runs_for(&db, job.id).await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_run_receives_tenant_config_and_records_history() {
+    let (_, db) = setup_test_app().await;
+    let job = create_job(&db, Some(json!({ "api_url": "https://example.test/blocks" })), 60).await;
+
+    let received = Arc::new(Mutex::new(None));
+    let sink = received.clone();
+    let definition = BackgroundJob {
+        job_type: "TestJob".to_string(),
+        default_interval_seconds: 600,
+        is_active_by_default: true,
+        default_config_payload: Some(json!({ "api_url": "https://default.test" })),
+        executor: Box::new(move |_db, _tenant_id, config| {
+            let sink = sink.clone();
+            Box::pin(async move {
+                *sink.lock().unwrap() = config;
+                Err("upstream unavailable".to_string())
+            })
+        }),
+    };
+
+    let run = JobScheduler::claim_due_jobs_for(&db, Some(job.tenant_id))
+        .await
+        .unwrap()
+        .remove(0);
+    let finished = JobScheduler::execute_with(&db, run, &job, &definition).await.unwrap();
+
+    assert_eq!(*received.lock().unwrap(), Some(json!({ "api_url": "https://example.test/blocks" })));
+    assert_eq!(finished.status, job_scheduler::RUN_FAILED);
+    assert_eq!(finished.error.as_deref(), Some("upstream unavailable"));
+    assert!(finished.finished_at.is_some());
+    assert!(finished.duration_ms.is_some());
+}
+
+#[tokio::test]
+async fn test_run_exceeding_timeout_is_marked_timed_out() {
+    let (_, db) = setup_test_app().await;
+    let job = create_job(&db, None, 1).await;
+
+    let definition = BackgroundJob {
+        job_type: "TestJob".to_string(),
+        default_interval_seconds: 600,
+        is_active_by_default: true,
+        default_config_payload: None,
+        executor: Box::new(|_db, _tenant_id, _config| {
+            Box::pin(async move {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                Ok(())
+            })
+        }),
+    };
+
+    let run = JobScheduler::claim_due_jobs_for(&db, Some(job.tenant_id))
+        .await
+        .unwrap()
+        .remove(0);
+    let finished = JobScheduler::execute_with(&db, run, &job, &definition).await.unwrap();
+    assert_eq!(finished.status, job_scheduler::RUN_TIMED_OUT);
+}
+
+#[tokio::test]
+async fn test_concurrent_provisioning_creates_one_job_per_type() {
+    let (_, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let definitions = || vec![BackgroundJob {
+        job_type: "TestJob".to_string(),
+        default_interval_seconds: 600,
+        is_active_by_default: true,
+        default_config_payload: None,
+        executor: Box::new(|_db, _tenant_id, _config| Box::pin(async { Ok(()) })),
+    }];
+    let (first, second) = (definitions(), definitions());
+
+    let (a, b) = tokio::join!(
+        JobScheduler::provision(&db, tenant.id, &first),
+        JobScheduler::provision(&db, tenant.id, &second),
+    );
+    a.unwrap();
+    b.unwrap();
+
+    let jobs = tenant_background_job::Entity::find()
+        .filter(tenant_background_job::Column::TenantId.eq(tenant.id))
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(jobs.len(), 1);
+}
+
+#[tokio::test]
+async fn test_prune_removes_only_old_finished_runs() {
+    let (_, db) = setup_test_app().await;
+    let job = create_job(&db, None, 60).await;
+    let run = |status: &str, age_days: i64| {
+        let started_at = Utc::now() - chrono::Duration::days(age_days);
+        job_run::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            job_id: Set(job.id),
+            tenant_id: Set(job.tenant_id),
+            job_type: Set(job.job_type.clone()),
+            status: Set(status.to_string()),
+            worker_id: Set("test".to_string()),
+            started_at: Set(started_at.into()),
+            lease_expires_at: Set(started_at.into()),
+            finished_at: Set(None),
+            duration_ms: Set(None),
+            error: Set(None),
+        }
+    };
+
+    let old = run(job_scheduler::RUN_SUCCEEDED, 90).insert(&db).await.unwrap();
+    let recent = run(job_scheduler::RUN_FAILED, 1).insert(&db).await.unwrap();
+    let stuck = run(job_scheduler::RUN_RUNNING, 90).insert(&db).await.unwrap();
+
+    JobScheduler::prune_runs(&db).await.unwrap();
+
+    let remaining: Vec<Uuid> = runs_for(&db, job.id).await.into_iter().map(|r| r.id).collect();
+    assert!(!remaining.contains(&old.id));
+    assert!(remaining.contains(&recent.id));
+    assert!(remaining.contains(&stuck.id));
+}
@@ -0,0 +1,196 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::entities::customer::CustomerType;
+use crate::entities::{activity, contact, customer, lead, note};
+use crate::entities::activity::{ActivityStatus, ActivityType};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+async fn convert(app: &Router, token: &str, tenant_id: Uuid, lead_id: Uuid, body: Value) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/leads/{}/convert", lead_id))
+                .header("Host", "localhost")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("X-Tenant-Id", tenant_id.to_string())
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn new_lead(tenant_id: Uuid, email: &str) -> lead::ActiveModel {
+    lead::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set("Ada Lovelace".to_string()),
+        first_name: Set(Some("Ada".to_string())),
+        last_name: Set(Some("Lovelace".to_string())),
+        email: Set(Some(email.to_string())),
+        phone: Set(Some("+1 555 0100".to_string())),
+        twitter: Set(Some("@ada".to_string())),
+        is_converted: Set(false),
+        converted_to_contact: Set(false),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        tenant_id: Set(Some(tenant_id)),
+        properties: Set(Some(json!({ "budget": "50k", "segment": "smb" }))),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_convert_matches_existing_customer_and_moves_history() {
+    let (app, db) = setup_test_app().await;
+    let (admin, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let email = format!("ada{}@example.com", Uuid::new_v4().simple());
+
+    // An existing customer with the same email, differently cased, and its own segment
+    let existing = customer::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set("Analytical Engines Ltd".to_string()),
+        primary_contact_id: Set(None),
+        customer_type: Set(CustomerType::BusinessEntity),
+        attributes: Set(Default::default()),
+        cpf: Set(None),
+        cnpj: Set(None),
+        tin: Set(None),
+        email: Set(Some(email.to_uppercase())),
+        phone: Set(None),
+        whatsapp: Set(None),
+        telegram: Set(None),
+        twitter: Set(None),
+        instagram: Set(None),
+        facebook: Set(None),
+        website: Set(None),
+        annual_revenue: Set(None),
+        employee_count: Set(None),
+        is_active: Set(true),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        billing_address: Set(None),
+        shipping_address: Set(None),
+        tenant_id: Set(Some(tenant.id)),
+        properties: Set(Some(json!({ "segment": "enterprise" }))),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    let lead = new_lead(tenant.id, &email).insert(&db).await.unwrap();
+    let lead_note = note::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        content: Set("Called about pricing".to_string()),
+        created_by: Set(Some(admin.id)),
+        entity_type: Set("Lead".to_string()),
+        entity_id: Set(lead.id),
+        tenant_id: Set(Some(tenant.id)),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    let lead_activity = activity::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        lead_id: Set(Some(lead.id)),
+        tenant_id: Set(Some(tenant.id)),
+        activity_type: Set(ActivityType::PhoneCall),
+        title: Set("Discovery call".to_string()),
+        status: Set(ActivityStatus::Pending),
+        created_by: Set(Some(admin.id)),
+        associated_entities: Set(json!([])),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    let (status, body) = convert(&app, &token, tenant.id, lead.id, json!({ "deal": { "amount": 5000.0 } })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["customer"]["id"], json!(existing.id));
+    assert_eq!(body["customer"]["outcome"], "matched");
+    let filled: Vec<&str> = body["customer"]["filled"].as_array().unwrap().iter().map(|f| f.as_str().unwrap()).collect();
+    assert!(filled.contains(&"phone") && filled.contains(&"twitter") && filled.contains(&"properties"), "{:?}", filled);
+    assert!(!filled.contains(&"email"));
+    assert_eq!(body["contact"]["outcome"], "created");
+    assert_eq!(body["notes_moved"], 1);
+    assert_eq!(body["activities_moved"], 1);
+    assert_eq!(body["lead"]["is_converted"], true);
+    let contact_id = Uuid::parse_str(body["contact"]["id"].as_str().unwrap()).unwrap();
+    let deal_id = Uuid::parse_str(body["deal_id"].as_str().unwrap()).unwrap();
+
+    let customer = customer::Entity::find_by_id(existing.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(customer.name, "Analytical Engines Ltd");
+    assert_eq!(customer.phone.as_deref(), Some("+1 555 0100"));
+    assert_eq!(customer.primary_contact_id, Some(contact_id));
+    // The customer's own value wins; the lead's new keys are added
+    assert_eq!(customer.properties, Some(json!({ "segment": "enterprise", "budget": "50k" })));
+
+    let contact = contact::Entity::find_by_id(contact_id).one(&db).await.unwrap().unwrap();
+    assert_eq!(contact.name, "Ada Lovelace");
+    assert_eq!(contact.customer_id, Some(existing.id));
+
+    let moved_note = note::Entity::find_by_id(lead_note.id).one(&db).await.unwrap().unwrap();
+    assert_eq!((moved_note.entity_type.as_str(), moved_note.entity_id), ("Customer", existing.id));
+    let linked = activity::Entity::find_by_id(lead_activity.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(linked.lead_id, Some(lead.id));
+    assert_eq!(linked.customer_id, Some(existing.id));
+    assert_eq!(linked.contact_id, Some(contact_id));
+    assert_eq!(linked.deal_id, Some(deal_id));
+
+    let converted = lead::Entity::find_by_id(lead.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(converted.converted_customer_id, Some(existing.id));
+    assert_eq!(converted.converted_contact_id, Some(contact_id));
+    assert_eq!(converted.associated_deal_id, Some(deal_id));
+
+    // A lead converts once
+    let (status, _) = convert(&app, &token, tenant.id, lead.id, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_convert_creates_records_and_rolls_back_on_failure() {
+    let (app, db) = setup_test_app().await;
+    let (_, token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let lead = new_lead(tenant.id, &format!("new{}@example.com", Uuid::new_v4().simple())).insert(&db).await.unwrap();
+
+    // An unknown explicit customer fails the whole conversion
+    let (status, _) = convert(&app, &token, tenant.id, lead.id, json!({ "customer_id": Uuid::new_v4() })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let untouched = lead::Entity::find_by_id(lead.id).one(&db).await.unwrap().unwrap();
+    assert!(!untouched.is_converted);
+
+    let (status, body) = convert(&app, &token, tenant.id, lead.id, json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["customer"]["outcome"], "created");
+    assert_eq!(body["contact"]["outcome"], "created");
+    assert_eq!(body["deal_id"], Value::Null);
+
+    let customer_id = Uuid::parse_str(body["customer"]["id"].as_str().unwrap()).unwrap();
+    let customer = customer::Entity::find_by_id(customer_id).one(&db).await.unwrap().unwrap();
+    assert_eq!(customer.customer_type, CustomerType::Person);
+    assert_eq!(customer.twitter.as_deref(), Some("@ada"));
+    assert_eq!(customer.tenant_id, Some(tenant.id));
+    assert_eq!(customer.properties, lead.properties);
+}
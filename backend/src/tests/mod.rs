@@ -14,4 +14,27 @@ pub mod magic_link_tests;
 pub mod telemetry_tests;
 pub mod audit_tests;
 pub mod webhook_tests;
+pub mod api_token_tests;
 pub mod search_tests;
+pub mod tenant_isolation_tests;
+pub mod domain_event_tests;
+pub mod outbox_tests;
+pub mod job_scheduler_tests;
+pub mod job_admin_tests;
+pub mod app_lifecycle_tests;
+pub mod app_gate_tests;
+pub mod secrets_tests;
+pub mod module_flags_tests;
+pub mod site_cache_tests;
+pub mod custom_domain_tests;
+pub mod dns_provider_tests;
+pub mod tenant_lifecycle_tests;
+pub mod tenant_archive_tests;
+pub mod seed_pack_tests;
+pub mod rate_limiter_tests;
+pub mod request_log_tests;
+pub mod lead_conversion_tests;
+pub mod deal_pipeline_tests;
+pub mod activity_task_tests;
+pub mod case_sla_tests;
+pub mod custom_field_tests;
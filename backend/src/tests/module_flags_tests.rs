@@ -0,0 +1,77 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, Set};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::entities::{app_domain, app_instance};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils::{self, call_on_host};
+
+#[tokio::test]
+async fn test_disabled_modules_hide_their_routes() {
+    let (app, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let instance = app_instance::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant.id),
+        app_type: Set("Network".to_string()),
+        database_url: Set(None),
+        data_seed_name: Set(None),
+        data_seed_version: Set(None),
+        data_seed_applied_at: Set(None),
+        settings: Set(None),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    let host = format!("modules-{}.test", Uuid::new_v4().simple());
+    app_domain::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        app_instance_id: Set(instance.id),
+        domain_name: Set(host.clone()),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    let listings_uri = format!("/listings?tenant_id={}", tenant.id);
+    let (status, _) = call_on_host(&app, "GET", &host, &listings_uri, None, None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let (status, _) = call_on_host(&app, "GET", &host, "/api/custom-fields/customer", Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let modules_uri = format!("/api/admin/modules/tenant/{}", tenant.id);
+    let (status, modules) = call_on_host(&app, "PUT", "localhost", &modules_uri, Some(&admin_token), Some(json!({
+        "modules": { "LISTINGS": false }
+    })))
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let enabled = modules["enabled_modules"].as_array().unwrap();
+    assert!(!enabled.contains(&json!("LISTINGS")));
+    assert!(enabled.contains(&json!("PROFILES")));
+
+    // The toggle invalidates the cached site config
+    let (status, _) = call_on_host(&app, "GET", &host, &listings_uri, None, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Signed-in requests are hidden too
+    let (status, _) = call_on_host(&app, "PUT", "localhost", &modules_uri, Some(&admin_token), Some(json!({
+        "modules": { "CUSTOM_FIELDS": false }
+    })))
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call_on_host(&app, "GET", &host, "/api/custom-fields/customer", Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = call_on_host(&app, "PUT", "localhost", &modules_uri, Some(&admin_token), Some(json!({
+        "modules": { "TELEPORTATION": true }
+    })))
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
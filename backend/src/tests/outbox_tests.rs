@@ -0,0 +1,174 @@
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+use sea_orm::sea_query::Expr;
+use serde_json::json;
+use uuid::Uuid;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::entities::{audit_log, outbox, webhook_delivery, webhook_endpoint};
+use crate::services::audit::{AuditEntry, AuditService};
+use crate::services::event_catalog::PlatformEvent;
+use crate::services::outbox::{Outbox, OutboxMessage, STATUS_PENDING, STATUS_PROCESSED};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+#[tokio::test]
+async fn test_rolled_back_transaction_leaves_no_side_effects() {
+    let (_, db) = setup_test_app().await;
+    let entity_id = Uuid::new_v4();
+
+    let txn = db.begin().await.unwrap();
+    AuditService::log_action(
+        &txn,
+        AuditEntry {
+            tenant_id: Some(Uuid::new_v4()),
+            actor_id: None,
+            action_type: "test.rolled_back".to_string(),
+            entity_type: "TestEntity".to_string(),
+            entity_id,
+            old_state: None,
+            new_state: None,
+            ip_address: None,
+        },
+    )
+    .await
+    .unwrap();
+    txn.rollback().await.unwrap();
+
+    test_utils::drain_outbox(&db).await;
+
+    let logged = audit_log::Entity::find()
+        .filter(audit_log::Column::EntityId.eq(entity_id))
+        .one(&db)
+        .await
+        .unwrap();
+    assert!(logged.is_none(), "Audit entry survived a rolled back transaction");
+}
+
+#[tokio::test]
+async fn test_relaying_a_message_twice_is_idempotent() {
+    let (_, db) = setup_test_app().await;
+    let entity_id = Uuid::new_v4();
+
+    let message_id = Outbox::enqueue(&db, OutboxMessage::Audit {
+        tenant_id: None,
+        actor_id: None,
+        action_type: "test.relayed_twice".to_string(),
+        entity_type: "TestEntity".to_string(),
+        entity_id,
+        old_state: None,
+        new_state: Some(json!({ "ok": true })),
+        ip_address: None,
+        occurred_at: chrono::Utc::now(),
+    })
+    .await
+    .unwrap();
+    test_utils::drain_outbox(&db).await;
+
+    // Simulate a relay that crashed after fanning out but before marking the message
+    outbox::Entity::update_many()
+        .col_expr(outbox::Column::Status, Expr::value(STATUS_PENDING))
+        .filter(outbox::Column::Id.eq(message_id))
+        .exec(&db)
+        .await
+        .unwrap();
+    test_utils::drain_outbox(&db).await;
+
+    let logged = audit_log::Entity::find()
+        .filter(audit_log::Column::EntityId.eq(entity_id))
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(logged.len(), 1);
+    assert_eq!(logged[0].id, message_id);
+
+    let message = outbox::Entity::find_by_id(message_id).one(&db).await.unwrap().unwrap();
+    assert_eq!(message.status, STATUS_PROCESSED);
+    assert_eq!(message.attempts, 2);
+}
+
+#[tokio::test]
+async fn test_webhook_fan_out_is_keyed_per_endpoint() {
+    let (_, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = webhook_endpoint::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant.id),
+        target_url: Set(format!("{}/hook", mock_server.uri())),
+        secret_key: Set("outbox_secret".to_string()),
+        subscribed_events: Set(json!(["webform.*"])),
+        is_active: Set(true),
+        failure_threshold: Set(10),
+        consecutive_failures: Set(0),
+        created_at: Set(Some(chrono::Utc::now().into())),
+        updated_at: Set(Some(chrono::Utc::now().into())),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    crate::services::webhook::dispatch_event(&db, tenant.id, PlatformEvent::WebformSubmitted, json!({ "name": "Ada" }))
+        .await
+        .unwrap();
+    test_utils::drain_outbox(&db).await;
+
+    let message = outbox::Entity::find()
+        .filter(outbox::Column::TenantId.eq(tenant.id))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    outbox::Entity::update_many()
+        .col_expr(outbox::Column::Status, Expr::value(STATUS_PENDING))
+        .filter(outbox::Column::Id.eq(message.id))
+        .exec(&db)
+        .await
+        .unwrap();
+    test_utils::drain_outbox(&db).await;
+
+    let deliveries = webhook_delivery::Entity::find()
+        .filter(webhook_delivery::Column::EndpointId.eq(endpoint.id))
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].idempotency_key, Some(format!("{}:{}", message.id, endpoint.id)));
+}
+
+#[tokio::test]
+async fn test_malformed_message_is_retried_with_backoff() {
+    let (_, db) = setup_test_app().await;
+    let now = chrono::Utc::now();
+
+    let message = outbox::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        kind: Set("webhook".to_string()),
+        tenant_id: Set(None),
+        payload: Set(json!({ "kind": "webhook", "event": "no.such.event" })),
+        status: Set(STATUS_PENDING.to_string()),
+        attempts: Set(0),
+        last_error: Set(None),
+        available_at: Set(now.into()),
+        processed_at: Set(None),
+        created_at: Set(now.into()),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    test_utils::drain_outbox(&db).await;
+
+    let message = outbox::Entity::find_by_id(message.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(message.status, STATUS_PENDING);
+    assert_eq!(message.attempts, 1);
+    assert!(message.last_error.is_some());
+    assert!(message.available_at > now);
+}
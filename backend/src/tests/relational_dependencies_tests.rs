@@ -2,7 +2,7 @@ use axum::{
     body::Body,
     http::{Request, StatusCode},
 };
-use sea_orm::{EntityTrait, ActiveModelTrait};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 use sea_orm_migration::MigratorTrait;
 use tower::ServiceExt;
 use serde_json::json;
@@ -87,7 +87,12 @@ async fn test_listing_relational_hierarchy() {
     let mut dummy_user = "listcreator".to_string();
     test_utils::register_test_user(&app, tenant.id, &mut dummy_user).await;
     let category = test_utils::create_default_category(&db, tenant.id).await;
-    let profile = crate::entities::profile::Entity::find().one(&db).await.unwrap().unwrap();
+    let profile = crate::entities::profile::Entity::find()
+        .filter(crate::entities::profile::Column::TenantId.eq(tenant.id))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
 
     // 1. Create Listing
     let payload = json!({
@@ -0,0 +1,146 @@
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, Request, StatusCode},
+    middleware::from_fn_with_state,
+    routing::get,
+    Extension, Router,
+};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::entities::request_log;
+use crate::middleware::request_logger::{
+    log_requests, prune_request_log, retention_window, LoggedUser, RedactedHeaders, RequestLogger,
+};
+use crate::models::request_log::{RequestStatus, RequestType};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+fn get_request(uri: &str, request_id: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder().uri(uri).header("Host", "localhost");
+    if let Some(id) = request_id {
+        builder = builder.header("X-Request-Id", id);
+    }
+    builder.body(Body::empty()).unwrap()
+}
+
+async fn logged(db: &sea_orm::DatabaseConnection, request_id: &str) -> request_log::Model {
+    request_log::Entity::find()
+        .filter(request_log::Column::RequestId.eq(request_id))
+        .one(db)
+        .await
+        .unwrap()
+        .expect("request should have been logged")
+}
+
+#[test]
+fn test_credentials_are_redacted_from_logged_headers() {
+    let mut headers = HeaderMap::new();
+    headers.insert("authorization", HeaderValue::from_static("Bearer secret-token"));
+    headers.insert("cookie", HeaderValue::from_static("session=secret-cookie"));
+    headers.insert("user-agent", HeaderValue::from_static("curl/8.0"));
+
+    let formatted = format!("{:?}", RedactedHeaders(&headers));
+    assert!(!formatted.contains("secret"), "{}", formatted);
+    assert!(formatted.contains("[redacted]"));
+    assert!(formatted.contains("curl/8.0"));
+}
+
+#[tokio::test]
+async fn test_responses_carry_a_request_id() {
+    let (app, _) = setup_test_app().await;
+
+    let response = app.clone().oneshot(get_request("/health", None)).await.unwrap();
+    let generated = response.headers()["x-request-id"].to_str().unwrap();
+    assert!(Uuid::parse_str(generated).is_ok());
+
+    let response = app.clone().oneshot(get_request("/health", Some("edge-1234.abc"))).await.unwrap();
+    assert_eq!(response.headers()["x-request-id"], "edge-1234.abc");
+
+    // IDs that aren't safe to echo are replaced
+    let response = app.oneshot(get_request("/health", Some("bad id; drop"))).await.unwrap();
+    assert_ne!(response.headers()["x-request-id"], "bad id; drop");
+}
+
+#[tokio::test]
+async fn test_log_records_the_real_status_latency_and_size() {
+    let (app, db) = setup_test_app().await;
+    let (admin, _) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let admin_id = admin.id;
+    let logger = RequestLogger::start(db.clone());
+    let router = Router::new()
+        .route("/teapot", get(|| async { (StatusCode::IM_A_TEAPOT, "short and stout") }))
+        .route("/mine", get(move || async move { (Extension(LoggedUser(admin_id)), "ok") }))
+        .route("/validate-session", get(|| async { "ok" }))
+        .layer(from_fn_with_state(logger.clone(), log_requests));
+
+    let teapot_id = format!("teapot-{}", Uuid::new_v4());
+    let mine_id = format!("mine-{}", Uuid::new_v4());
+    let skipped_id = format!("skipped-{}", Uuid::new_v4());
+    for (uri, id) in [("/teapot", &teapot_id), ("/mine", &mine_id), ("/validate-session", &skipped_id)] {
+        let mut request = get_request(uri, Some(id));
+        request.headers_mut().insert("x-forwarded-for", HeaderValue::from_static("198.51.100.30"));
+        router.clone().oneshot(request).await.unwrap();
+    }
+    logger.flush().await;
+
+    let teapot = logged(&db, &teapot_id).await;
+    assert_eq!(teapot.status_code, 418);
+    assert_eq!(teapot.request_status, RequestStatus::Failure);
+    assert_eq!(teapot.failure_reason.as_deref(), Some("I'm a teapot"));
+    assert_eq!(teapot.response_bytes, Some("short and stout".len() as i64));
+    assert!(teapot.duration_ms.is_some());
+    assert_eq!(teapot.ip_address, "198.51.100.30");
+    assert_eq!(teapot.request_type, RequestType::API);
+    assert_eq!(teapot.user_id, None);
+
+    let mine = logged(&db, &mine_id).await;
+    assert_eq!(mine.status_code, 200);
+    assert_eq!(mine.request_status, RequestStatus::Success);
+    assert_eq!(mine.user_id, Some(admin_id));
+
+    let skipped = request_log::Entity::find()
+        .filter(request_log::Column::RequestId.eq(skipped_id.as_str()))
+        .one(&db)
+        .await
+        .unwrap();
+    assert!(skipped.is_none());
+}
+
+#[tokio::test]
+async fn test_prune_removes_only_expired_entries() {
+    let (_, db) = setup_test_app().await;
+    let entry = |age: chrono::Duration| request_log::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(None),
+        ip_address: Set("127.0.0.1".to_string()),
+        user_agent: Set(None),
+        path: Set("/health".to_string()),
+        method: Set("GET".to_string()),
+        status_code: Set(200),
+        request_type: Set(RequestType::API),
+        created_at: Set(Utc::now() - age),
+        request_status: Set(RequestStatus::Success),
+        failure_reason: Set(None),
+        request_id: Set(None),
+        duration_ms: Set(Some(1)),
+        response_bytes: Set(Some(0)),
+    };
+    let expired = entry(chrono::Duration::days(45)).insert(&db).await.unwrap();
+    let recent = entry(chrono::Duration::days(5)).insert(&db).await.unwrap();
+
+    let removed = prune_request_log(&db, chrono::Duration::days(30)).await.unwrap();
+    assert!(removed >= 1);
+    assert!(request_log::Entity::find_by_id(expired.id).one(&db).await.unwrap().is_none());
+    assert!(request_log::Entity::find_by_id(recent.id).one(&db).await.unwrap().is_some());
+}
+
+#[test]
+fn test_retention_window_rejects_out_of_range_days() {
+    assert_eq!(retention_window(90), Some(chrono::Duration::days(90)));
+    assert_eq!(retention_window(i64::MAX), None);
+    // Representable as a duration, but reaching back before the earliest supported date
+    assert_eq!(retention_window(100_000_000_000), None);
+}
@@ -0,0 +1,199 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::entities::{tenant_data_key, tenant_setting};
+use crate::services::secrets::{Keyring, SecretsService, MASKED_VALUE};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils::{self, call_without_tenant};
+
+#[tokio::test]
+async fn test_encrypted_settings_are_stored_as_ciphertext_and_masked() {
+    let (app, db) = setup_test_app().await;
+    let (_, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let mut username = format!("secrets{}", Uuid::new_v4().simple());
+    let (_, login) = test_utils::register_test_user(&app, tenant.id, &mut username).await;
+    let user_token = login["token"].as_str().unwrap().to_string();
+    let uri = format!("/api/tenants/{}/settings", tenant.id);
+
+    let (status, created) = call_without_tenant(&app, "POST", &uri, &admin_token, Some(json!({
+        "key": "smtp_token",
+        "value": "hunter2",
+        "is_encrypted": true
+    })))
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["value"], MASKED_VALUE);
+
+    let stored = tenant_setting::Entity::find()
+        .filter(tenant_setting::Column::TenantId.eq(tenant.id))
+        .filter(tenant_setting::Column::Key.eq("smtp_token"))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_ne!(stored.value, "hunter2");
+    assert!(stored.data_key_id.is_some());
+
+    let (_, settings) = call_without_tenant(&app, "GET", &uri, &user_token, None).await;
+    assert_eq!(settings[0]["value"], MASKED_VALUE);
+
+    let (_, settings) = call_without_tenant(&app, "GET", &uri, &admin_token, None).await;
+    assert_eq!(settings[0]["value"], "hunter2");
+}
+
+#[tokio::test]
+async fn test_key_rotation_keeps_secrets_readable() {
+    let (_, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let old_master = [1u8; 32];
+    let new_master = [2u8; 32];
+    let keyring = Keyring::new(("old", &old_master), &[]).unwrap();
+
+    let (ciphertext, data_key_id) = SecretsService::encrypt_value(&db, &keyring, tenant.id, "smtp_token", "hunter2")
+        .await
+        .unwrap();
+    let setting = tenant_setting::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant.id),
+        key: Set("smtp_token".to_string()),
+        value: Set(ciphertext),
+        is_encrypted: Set(true),
+        data_key_id: Set(Some(data_key_id)),
+        updated_at: Set(Utc::now()),
+        created_at: Set(Utc::now()),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+
+    // The new master key alone can't unwrap the tenant's data key
+    let new_only = Keyring::new(("new", &new_master), &[]).unwrap();
+    assert!(SecretsService::reveal(&db, &new_only, &setting).await.is_err());
+    let rotated = Keyring::new(("new", &new_master), &[("old", &old_master)]).unwrap();
+    assert_eq!(SecretsService::rotate_master_key(&db, &rotated, Some(tenant.id)).await.unwrap(), 1);
+    assert_eq!(SecretsService::reveal(&db, &new_only, &setting).await.unwrap(), "hunter2");
+
+    assert_eq!(SecretsService::rotate_data_keys(&db, &new_only, Some(tenant.id)).await.unwrap(), 1);
+    let setting = tenant_setting::Entity::find_by_id(setting.id).one(&db).await.unwrap().unwrap();
+    assert_ne!(setting.data_key_id, Some(data_key_id));
+    assert_eq!(SecretsService::reveal(&db, &new_only, &setting).await.unwrap(), "hunter2");
+    assert!(tenant_data_key::Entity::find_by_id(data_key_id).one(&db).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_secrets_scope_is_confined_to_the_tokens_tenant() {
+    let (app, db) = setup_test_app().await;
+    let (_, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let other_tenant = test_utils::create_test_tenant(&db).await;
+
+    for tenant_id in [tenant.id, other_tenant.id] {
+        let (status, _) = call_without_tenant(&app, "POST", &format!("/api/tenants/{}/settings", tenant_id), &admin_token, Some(json!({
+            "key": "smtp_token",
+            "value": "hunter2",
+            "is_encrypted": true
+        })))
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let (status, minted) = call_without_tenant(
+        &app,
+        "POST",
+        &format!("/api/admin/developer/tenant/{}/api-tokens", tenant.id),
+        &admin_token,
+        Some(json!({ "scopes": ["secrets:admin"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let token = minted["token"].as_str().unwrap().to_string();
+
+    let (status, settings) = call_without_tenant(&app, "GET", &format!("/api/tenants/{}/settings", tenant.id), &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(settings[0]["value"], "hunter2");
+
+    let (status, settings) = call_without_tenant(&app, "GET", &format!("/api/tenants/{}/settings", other_tenant.id), &token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(!settings.to_string().contains("hunter2"));
+
+    // ...and can't overwrite them either
+    let (status, _) = call_without_tenant(&app, "POST", &format!("/api/tenants/{}/settings", other_tenant.id), &token, Some(json!({
+        "key": "smtp_token",
+        "value": "attacker",
+        "is_encrypted": true
+    })))
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, settings) = call_without_tenant(&app, "GET", &format!("/api/tenants/{}/settings", other_tenant.id), &admin_token, None).await;
+    assert_eq!(settings[0]["value"], "hunter2");
+}
+
+#[tokio::test]
+async fn test_settings_are_confined_to_the_sessions_tenant() {
+    let (app, db) = setup_test_app().await;
+    let (_, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let other_tenant = test_utils::create_test_tenant(&db).await;
+    let other_uri = format!("/api/tenants/{}/settings", other_tenant.id);
+
+    let (status, _) = call_without_tenant(&app, "POST", &other_uri, &admin_token, Some(json!({
+        "key": "smtp_server",
+        "value": "smtp.other.mail"
+    })))
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let mut username = format!("settings{}", Uuid::new_v4().simple());
+    let (_, login) = test_utils::register_test_user(&app, tenant.id, &mut username).await;
+    let owner_token = login["token"].as_str().unwrap().to_string();
+
+    // The owner of one tenant can neither list nor change another tenant's settings
+    let (status, settings) = call_without_tenant(&app, "GET", &other_uri, &owner_token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(!settings.to_string().contains("smtp_server"));
+    let (status, _) = call_without_tenant(&app, "POST", &other_uri, &owner_token, Some(json!({
+        "key": "smtp_server",
+        "value": "smtp.attacker.mail"
+    })))
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // ...while their own tenant's settings are theirs to manage
+    let (status, _) = call_without_tenant(&app, "POST", &format!("/api/tenants/{}/settings", tenant.id), &owner_token, Some(json!({
+        "key": "smtp_server",
+        "value": "smtp.own.mail"
+    })))
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (_, settings) = call_without_tenant(&app, "GET", &other_uri, &admin_token, None).await;
+    assert_eq!(settings[0]["value"], "smtp.other.mail");
+}
+
+#[tokio::test]
+async fn test_tenant_mail_is_only_sent_by_its_members() {
+    let (app, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let other = test_utils::create_test_tenant(&db).await;
+    let mut username = format!("mailer{}", Uuid::new_v4().simple());
+    let (_, login) = test_utils::register_test_user(&app, tenant.id, &mut username).await;
+    let token = login["token"].as_str().unwrap().to_string();
+    let email = |tenant_id: Uuid| json!({
+        "tenant_id": tenant_id,
+        "to_email": "someone@example.com",
+        "subject": "Hello",
+        "body_html": "<p>Hi</p>"
+    });
+
+    // Another tenant's SMTP account is out of reach
+    let (status, _) = call_without_tenant(&app, "POST", "/api/communications/email", &token, Some(email(other.id))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // No SMTP host is configured, so the email is mocked
+    let (status, sent) = call_without_tenant(&app, "POST", "/api/communications/email", &token, Some(email(tenant.id))).await;
+    assert_eq!(status, StatusCode::OK, "{}", sent);
+}
@@ -0,0 +1,137 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::entities::{app_instance, app_menu, app_page};
+use crate::services::seed_packs::{SeedAction, SeedPack, SeedPackError, SeedPacks};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+#[test]
+fn test_shipped_packs_are_valid() {
+    let listed = SeedPacks::list("anchor");
+    assert!(listed.iter().any(|pack| pack.name == "oplystusa"));
+    for pack in listed {
+        assert!(pack.errors.is_empty(), "{}: {:?}", pack.name, pack.errors);
+    }
+}
+
+#[test]
+fn test_invalid_packs_report_every_problem() {
+    let pack = json!({
+        "name": "broken",
+        "version": 1,
+        "app_type": "anchor",
+        "pages": [
+            { "slug": "home", "title": "Home", "blocks": [{ "FormBuilder": { "form_id": "@form:missing" } }] },
+            { "slug": "home", "title": "Home again" }
+        ],
+        "menus": [{ "label": "Child", "parent": "Root" }]
+    });
+    let Err(SeedPackError::Invalid(problems)) = SeedPack::parse(pack.to_string().as_bytes(), "anchor", "broken") else {
+        panic!("pack should be invalid");
+    };
+    assert_eq!(problems.len(), 3, "{:?}", problems);
+    assert!(problems.iter().any(|p| p.contains("duplicate page 'home'")));
+    assert!(problems.iter().any(|p| p.contains("unknown form 'missing'")));
+    assert!(problems.iter().any(|p| p.contains("parent 'Root'")));
+
+    // Typos in field names are rejected rather than ignored
+    let typo = json!({ "name": "typo", "version": 1, "app_type": "anchor", "page": [] });
+    assert!(SeedPack::parse(typo.to_string().as_bytes(), "anchor", "typo").is_err());
+}
+
+#[tokio::test]
+async fn test_apply_is_idempotent_and_dry_run_writes_nothing() {
+    let (app, db) = setup_test_app().await;
+    let (_, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let instance = app_instance::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant.id),
+        app_type: Set("anchor".to_string()),
+        database_url: Set(None),
+        data_seed_name: Set(Some("oplystusa".to_string())),
+        data_seed_version: Set(None),
+        data_seed_applied_at: Set(None),
+        settings: Set(Some(json!({ "site_title": "Template" }))),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    let pack = SeedPacks::load("anchor", "oplystusa").unwrap();
+    let pages = || {
+        app_page::Entity::find()
+            .filter(app_page::Column::TenantId.eq(tenant.id))
+            .all(&db)
+    };
+
+    let apply = |dry_run: bool| {
+        let app = app.clone();
+        let admin_token = admin_token.clone();
+        async move {
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/api/admin/platform/apps/{}/seed?dry_run={}", instance.id, dry_run))
+                        .header("Host", "localhost")
+                        .header("Authorization", format!("Bearer {}", admin_token))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&body).unwrap()
+        }
+    };
+
+    let dry = apply(true).await;
+    assert_eq!(dry["dry_run"], json!(true));
+    assert!(dry["changes"].as_array().unwrap().iter().all(|c| c["action"] == "create"));
+    assert!(pages().await.unwrap().is_empty());
+
+    let applied = apply(false).await;
+    assert_eq!(applied["changes"], dry["changes"]);
+    assert_eq!(pages().await.unwrap().len(), pack.pages.len());
+    let apply_page = pages().await.unwrap().into_iter().find(|p| p.slug == "apply/cre").unwrap();
+    let form_id = apply_page.blocks_payload.unwrap()[0]["FormBuilder"]["form_id"].as_str().unwrap().to_string();
+    assert!(Uuid::parse_str(&form_id).is_ok());
+    let bridge = app_menu::Entity::find()
+        .filter(app_menu::Column::TenantId.eq(tenant.id))
+        .filter(app_menu::Column::Label.eq("Bridge Loans"))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(bridge.parent_id.is_some());
+    let seeded = app_instance::Entity::find_by_id(instance.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(seeded.data_seed_version, Some(pack.version));
+    let settings = seeded.settings.clone().unwrap();
+    assert_eq!(settings["site_title"], json!("Template"));
+    assert_eq!(settings["design_config"]["nav_layout"], json!("floating-glass"));
+
+    // Re-running changes nothing; an edited page shows up as an update
+    let rerun = SeedPacks::apply(&db, &seeded, &pack, None, true).await.unwrap();
+    assert!(rerun.changes.is_empty(), "{:?}", rerun.changes);
+    let mut home: app_page::ActiveModel =
+        pages().await.unwrap().into_iter().find(|p| p.slug == "home").unwrap().into();
+    home.title = Set("Edited".to_string());
+    home.update(&db).await.unwrap();
+    let diff = SeedPacks::apply(&db, &seeded, &pack, None, true).await.unwrap();
+    assert_eq!(diff.changes.len(), 1);
+    assert_eq!(diff.changes[0].key, "home");
+    assert_eq!(diff.changes[0].action, SeedAction::Update);
+    assert_eq!(diff.changes[0].fields, vec!["title"]);
+    assert_eq!(diff.previous_version, Some(pack.version));
+}
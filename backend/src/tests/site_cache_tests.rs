@@ -0,0 +1,46 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, Set};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::entities::app_instance;
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils::{self, call_on_host};
+
+#[tokio::test]
+async fn test_domain_changes_take_effect_without_clearing_the_cache() {
+    let (app, db) = setup_test_app().await;
+    let (_, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let instance = app_instance::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant.id),
+        app_type: Set("Network".to_string()),
+        database_url: Set(None),
+        data_seed_name: Set(None),
+        data_seed_version: Set(None),
+        data_seed_applied_at: Set(None),
+        settings: Set(None),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    let host = format!("cache-{}.test", Uuid::new_v4().simple());
+    let listings_uri = format!("/listings?tenant_id={}", tenant.id);
+
+    // Unknown hosts are negatively cached...
+    assert_eq!(call_on_host(&app, "GET", &host, &listings_uri, None, None).await.0, StatusCode::NOT_FOUND);
+
+    // ...until the domain is added
+    let domains_uri = format!("/api/admin/platform/apps/{}/domains", instance.id);
+    let (status, _) = call_on_host(&app, "POST", "localhost", &domains_uri, Some(&admin_token), Some(json!({ "domain_name": host }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(call_on_host(&app, "GET", &host, &listings_uri, None, None).await.0, StatusCode::OK);
+
+    let (status, _) = call_on_host(&app, "DELETE", "localhost", &format!("{}/{}", domains_uri, host), Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(call_on_host(&app, "GET", &host, &listings_uri, None, None).await.0, StatusCode::NOT_FOUND);
+}
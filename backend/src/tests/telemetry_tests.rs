@@ -43,31 +43,37 @@ async fn test_telemetry_kpi_engine_aggregates_correctly() {
 
     // Insert 2 signups and 1 churn
     TelemetryService::log_event(
-        db.clone(),
+        &db,
         tenant_id,
         "app:core".to_string(),
         "user_signed_up".to_string(),
         None,
-    );
+    )
+    .await
+    .unwrap();
 
     TelemetryService::log_event(
-        db.clone(),
+        &db,
         tenant_id,
         "app:core".to_string(),
         "user_signed_up".to_string(),
         None,
-    );
+    )
+    .await
+    .unwrap();
     
     TelemetryService::log_event(
-        db.clone(),
+        &db,
         tenant_id,
         "app:core".to_string(),
         "subscription_created".to_string(),
         Some(json!({"mrr": 50.0})),
-    );
+    )
+    .await
+    .unwrap();
 
-    // Wait a brief moment for tokio::spawn to finish inserting
-    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    // Relay the queued events into telemetry_events
+    crate::tests::test_utils::drain_outbox(&db).await;
 
     // Run processor
     let process_res = TelemetryService::process_daily_metrics(&db).await;
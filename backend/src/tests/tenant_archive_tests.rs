@@ -0,0 +1,332 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+use std::io::Read;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::entities::profile::ProfileType;
+use crate::entities::user_account::UserRole;
+use crate::entities::{
+    account, app_domain, app_instance, category, customer, listing, note, profile, tenant, tenant_setting, user_account,
+};
+use crate::models::listing::ListingStatus;
+use crate::services::custom_domains::STATUS_PENDING_VERIFICATION;
+use crate::services::tenant_archive::{ImportOptions, TenantArchive};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+#[tokio::test]
+async fn test_archive_round_trip_remaps_ids_and_is_idempotent() {
+    let (app, db) = setup_test_app().await;
+    let (admin, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let source = test_utils::create_test_tenant(&db).await;
+    let parent = test_utils::create_default_category(&db, source.id).await;
+    let child = category::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        parent_category_id: Set(Some(parent.id)),
+        name: Set("Child".to_string()),
+        description: Set("Nested category".to_string()),
+        tenant_id: Set(Some(source.id)),
+        is_custom: Set(true),
+        is_active: Set(true),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    let customer = test_utils::create_customer(&db, source.id).await;
+    note::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        content: Set("Renewal due in March".to_string()),
+        created_by: Set(Some(admin.id)),
+        entity_type: Set("Customer".to_string()),
+        entity_id: Set(customer.id),
+        tenant_id: Set(Some(source.id)),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    }
+    .insert(&db)
+    .await
+    .unwrap();
+    for (key, is_encrypted) in [("site_title", false), ("stripe_secret", true)] {
+        tenant_setting::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            tenant_id: Set(source.id),
+            key: Set(key.to_string()),
+            value: Set("value".to_string()),
+            is_encrypted: Set(is_encrypted),
+            data_key_id: Set(None),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/admin/tenants/{}/export", source.id))
+                .header("Host", "localhost")
+                .header("Authorization", format!("Bearer {}", admin_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let archive = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    let report = TenantArchive::import(&db, &archive, ImportOptions::default()).await.unwrap();
+    assert_ne!(report.tenant_id, source.id);
+    assert_eq!(report.entities["tenant"].inserted, 1);
+    assert_eq!(report.entities["categories"].inserted, 2);
+    assert_eq!(report.entities["tenant_settings"].inserted, 1);
+    assert_eq!(report.entities["notes"].inserted, 1);
+    assert_eq!(report.omitted_secrets, vec!["stripe_secret".to_string()]);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+
+    let imported = tenant::Entity::find_by_id(report.tenant_id).one(&db).await.unwrap().unwrap();
+    assert_eq!(imported.name, source.name);
+    let categories = category::Entity::find()
+        .filter(category::Column::TenantId.eq(imported.id))
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(categories.len(), 2);
+    let imported_parent = categories.iter().find(|c| c.parent_category_id.is_none()).unwrap();
+    let imported_child = categories.iter().find(|c| c.name == child.name).unwrap();
+    assert_ne!(imported_parent.id, parent.id);
+    assert_eq!(imported_child.parent_category_id, Some(imported_parent.id));
+    let imported_customer = customer::Entity::find()
+        .filter(customer::Column::TenantId.eq(imported.id))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    let imported_note = note::Entity::find()
+        .filter(note::Column::TenantId.eq(imported.id))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(imported_note.entity_id, imported_customer.id);
+
+    // Importing the same archive again changes nothing
+    let again = TenantArchive::import(&db, &archive, ImportOptions::default()).await.unwrap();
+    assert_eq!(again.tenant_id, report.tenant_id);
+    assert!(again.entities.values().all(|entity| entity.inserted == 0 && entity.failed == 0));
+    let categories = category::Entity::find()
+        .filter(category::Column::TenantId.eq(imported.id))
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(categories.len(), 2);
+}
+
+#[tokio::test]
+async fn test_import_rejects_archives_from_a_newer_format() {
+    let (_, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let archive = TenantArchive::export(&db, tenant.id).await.unwrap();
+
+    assert!(TenantArchive::import(&db, b"not an archive", ImportOptions::default()).await.is_err());
+
+    // Rewrite the manifest with a future version
+    let mut files = Vec::new();
+    let mut reader = tar::Archive::new(flate2::read::GzDecoder::new(&archive[..]));
+    for entry in reader.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let path = entry.path().unwrap().to_string_lossy().into_owned();
+        let mut contents = Vec::new();
+        std::io::Read::read_to_end(&mut entry, &mut contents).unwrap();
+        if path == "manifest.json" {
+            let mut manifest: serde_json::Value = serde_json::from_slice(&contents).unwrap();
+            manifest["format_version"] = serde_json::json!(999);
+            contents = serde_json::to_vec(&manifest).unwrap();
+        }
+        files.push((path, contents));
+    }
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+    for (path, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, &contents[..]).unwrap();
+    }
+    let future = builder.into_inner().unwrap().finish().unwrap();
+
+    let err = TenantArchive::import(&db, &future, ImportOptions::default()).await.unwrap_err();
+    assert!(err.to_string().contains("999"));
+}
+
+#[tokio::test]
+async fn test_import_into_an_environment_without_the_source_tenant() {
+    let (app, db) = setup_test_app().await;
+    let (admin, _) = test_utils::create_and_login_admin_user(&app, &db).await;
+
+    // The source tenant only exists inside a transaction that is rolled back after the export
+    let txn = db.begin().await.unwrap();
+    let source = test_utils::create_test_tenant(&txn).await;
+    let account = account::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(source.id),
+        name: Set("Source Account".to_string()),
+        is_active: Set(true),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .unwrap();
+    user_account::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(admin.id),
+        account_id: Set(account.id),
+        role: Set(UserRole::Owner),
+        is_active: Set(true),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    }
+    .insert(&txn)
+    .await
+    .unwrap();
+    let profile = profile::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        account_id: Set(account.id),
+        tenant_id: Set(source.id),
+        profile_type: Set(ProfileType::Individual),
+        display_name: Set("Source Profile".to_string()),
+        contact_info: Set("source@example.com".to_string()),
+        is_active: Set(true),
+        properties: Set(None),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .unwrap();
+    listing::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        profile_id: Set(profile.id),
+        tenant_id: Set(source.id),
+        title: Set("Source Listing".to_string()),
+        description: Set("Listed in the source environment".to_string()),
+        listing_type: Set("Business".to_string()),
+        status: Set(ListingStatus::Active),
+        is_featured: Set(false),
+        is_based_on_template: Set(false),
+        is_ad_placement: Set(false),
+        is_active: Set(true),
+        properties: Set(None),
+        country: Set(Some("United States".to_string())),
+        state: Set(Some("CA".to_string())),
+        city: Set(Some("San Francisco".to_string())),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .unwrap();
+    let instance = app_instance::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(source.id),
+        app_type: Set("Network".to_string()),
+        database_url: Set(None),
+        data_seed_name: Set(None),
+        data_seed_version: Set(None),
+        data_seed_applied_at: Set(None),
+        settings: Set(None),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    }
+    .insert(&txn)
+    .await
+    .unwrap();
+    let domain = format!("archive-{}.test", Uuid::new_v4().simple());
+    app_domain::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        app_instance_id: Set(instance.id),
+        domain_name: Set(domain.clone()),
+        status: Set("active".to_string()),
+        verification_token: Set(Some("source-token".to_string())),
+        provider_hostname_id: Set(Some("source-hostname".to_string())),
+        verified_at: Set(Some(Utc::now())),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .unwrap();
+    let archive = TenantArchive::export(&txn, source.id).await.unwrap();
+    txn.rollback().await.unwrap();
+    assert!(tenant::Entity::find_by_id(source.id).one(&db).await.unwrap().is_none());
+
+    let report = TenantArchive::import(&db, &archive, ImportOptions::default()).await.unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    for entity in ["accounts", "user_accounts", "profiles", "listings", "app_domains"] {
+        assert_eq!(report.entities[entity].inserted, 1, "{}", entity);
+    }
+
+    let imported_account = account::Entity::find()
+        .filter(account::Column::TenantId.eq(report.tenant_id))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    let imported_profile = profile::Entity::find()
+        .filter(profile::Column::TenantId.eq(report.tenant_id))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(imported_profile.account_id, imported_account.id);
+    let imported_listing = listing::Entity::find()
+        .filter(listing::Column::TenantId.eq(report.tenant_id))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(imported_listing.profile_id, imported_profile.id);
+
+    // The domain has to be verified again before it is served
+    let imported_domain = app_domain::Entity::find()
+        .filter(app_domain::Column::DomainName.eq(domain))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(imported_domain.status, STATUS_PENDING_VERIFICATION);
+    assert!(imported_domain.provider_hostname_id.is_none());
+    assert!(imported_domain.verified_at.is_none());
+    assert_ne!(imported_domain.verification_token.as_deref(), Some("source-token"));
+}
+
+#[tokio::test]
+async fn test_import_refuses_oversized_entries() {
+    let (_, db) = setup_test_app().await;
+
+    // Compresses to a few hundred kilobytes but expands past the per-entry cap
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best()));
+    let mut header = tar::Header::new_gnu();
+    header.set_size(200 * 1024 * 1024);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, "manifest.json", std::io::repeat(b' ').take(200 * 1024 * 1024)).unwrap();
+    let bomb = builder.into_inner().unwrap().finish().unwrap();
+
+    let err = TenantArchive::import(&db, &bomb, ImportOptions::default()).await.unwrap_err();
+    assert!(err.to_string().contains("larger than"), "{}", err);
+}
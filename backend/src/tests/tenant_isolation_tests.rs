@@ -0,0 +1,261 @@
+use axum::{http::StatusCode, Router};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::sea_query::Expr;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::entities::{file, user, user_account};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+async fn register(app: &Router, tenant_id: Uuid, prefix: &str) -> (String, String) {
+    let mut username = format!("{}{}", prefix, Uuid::new_v4().simple());
+    let (status, login) = test_utils::register_test_user(app, tenant_id, &mut username).await;
+    assert_eq!(status, StatusCode::CREATED);
+    (username, login["token"].as_str().unwrap().to_string())
+}
+
+async fn insert_file(db: &sea_orm::DatabaseConnection, tenant_id: Uuid) -> Uuid {
+    let id = Uuid::new_v4();
+    file::ActiveModel {
+        id: Set(id.to_string()),
+        name: Set("contract.pdf".to_string()),
+        size: Set(1024),
+        mime_type: Set("application/pdf".to_string()),
+        hash_sha256: Set("0".repeat(64)),
+        storage_type: Set(file::StorageType::Local),
+        storage_path: Set(format!("/tmp/{}", id)),
+        views: Set(0),
+        downloads: Set(0),
+        bandwidth_used: Set(0),
+        bandwidth_used_paid: Set(0),
+        date_upload: Set(chrono::Utc::now().into()),
+        date_last_view: Set(None),
+        is_anonymous: Set(false),
+        user_id: Set(None),
+        tenant_id: Set(Some(tenant_id)),
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    id
+}
+
+fn customer_payload(name: &str) -> serde_json::Value {
+    json!({
+        "name": name,
+        "customer_type": "BusinessEntity",
+        "attributes": {
+            "shipper": false,
+            "carrier": false,
+            "loan_seeker": false,
+            "loan_broker": false,
+            "software_vendor": false,
+            "tenant": false,
+            "software_development_client": false,
+            "salesforce_client": false,
+            "web3_client": false,
+            "bitcoiner": false,
+            "zk": false,
+            "lender": false,
+            "advertiser": false,
+            "gp": false,
+            "construction_contractor": false,
+            "construction_client": false,
+            "landlord": false
+        },
+        "status": "Active"
+    })
+}
+
+#[tokio::test]
+async fn test_crm_rows_are_invisible_across_tenants() {
+    let (app, db) = setup_test_app().await;
+    let tenant_a = test_utils::create_test_tenant(&db).await;
+    let tenant_b = test_utils::create_test_tenant(&db).await;
+    let (_, token_a) = register(&app, tenant_a.id, "isoa").await;
+    let (_, token_b) = register(&app, tenant_b.id, "isob").await;
+
+    let (status, customer) = test_utils::call_without_tenant(&app, "POST", "/api/customers", &token_a, Some(customer_payload("Tenant A Client"))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let customer_id = customer["id"].as_str().unwrap().to_string();
+
+    let (status, case) = test_utils::call_without_tenant(&app, "POST", "/api/cases", &token_a, Some(json!({
+        "customer_id": customer_id,
+        "title": "Broken widget",
+        "description": "Widget stopped working",
+        "status": "Open",
+        "priority": "High"
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let case_id = case["id"].as_str().unwrap().to_string();
+
+    // Owner sees their own rows
+    let (status, _) = test_utils::call_without_tenant(&app, "GET", &format!("/api/customers/{}", customer_id), &token_a, None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Another tenant cannot tell the rows exist
+    let (status, _) = test_utils::call_without_tenant(&app, "GET", &format!("/api/customers/{}", customer_id), &token_b, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = test_utils::call_without_tenant(&app, "GET", &format!("/api/cases/{}", case_id), &token_b, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = test_utils::call_without_tenant(&app, "PUT", &format!("/api/customers/{}", customer_id), &token_b, Some(customer_payload("Hijacked"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = test_utils::call_without_tenant(&app, "DELETE", &format!("/api/customers/{}", customer_id), &token_b, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // ...nor attach children to them
+    let (status, _) = test_utils::call_without_tenant(&app, "POST", "/api/cases", &token_b, Some(json!({
+        "customer_id": customer_id,
+        "title": "Injected",
+        "description": "Should not attach",
+        "status": "Open",
+        "priority": "Low"
+    }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Lists only contain the caller's tenant
+    let (status, cases_b) = test_utils::call_without_tenant(&app, "GET", "/api/cases", &token_b, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!cases_b.to_string().contains(&case_id));
+    let (status, cases_a) = test_utils::call_without_tenant(&app, "GET", "/api/cases", &token_a, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(cases_a.to_string().contains(&case_id));
+}
+
+#[tokio::test]
+async fn test_member_role_can_write_but_not_delete() {
+    let (app, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let (_, owner_token) = register(&app, tenant.id, "owner").await;
+    let (member_username, member_token) = register(&app, tenant.id, "member").await;
+
+    let member = user::Entity::find()
+        .filter(user::Column::Username.eq(member_username))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    user_account::Entity::update_many()
+        .col_expr(user_account::Column::Role, Expr::value("Member"))
+        .filter(user_account::Column::UserId.eq(member.id))
+        .exec(&db)
+        .await
+        .unwrap();
+
+    let (status, customer) = test_utils::call_without_tenant(&app, "POST", "/api/customers", &owner_token, Some(customer_payload("Shared Client"))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let customer_id = customer["id"].as_str().unwrap().to_string();
+
+    // Members of the same tenant can read and write...
+    let (status, _) = test_utils::call_without_tenant(&app, "GET", &format!("/api/customers/{}", customer_id), &member_token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = test_utils::call_without_tenant(&app, "PUT", &format!("/api/customers/{}", customer_id), &member_token, Some(json!({ "name": "Renamed Client" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // ...but deletes are reserved for owners and admins
+    let (status, _) = test_utils::call_without_tenant(&app, "DELETE", &format!("/api/customers/{}", customer_id), &member_token, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = test_utils::call_without_tenant(&app, "DELETE", &format!("/api/customers/{}", customer_id), &owner_token, None).await;
+    assert!(status.is_success());
+}
+
+#[tokio::test]
+async fn test_member_role_cannot_configure_the_tenant() {
+    let (app, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let (_, owner_token) = register(&app, tenant.id, "owner").await;
+    let (member_username, member_token) = register(&app, tenant.id, "member").await;
+
+    let member = user::Entity::find()
+        .filter(user::Column::Username.eq(member_username))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    user_account::Entity::update_many()
+        .col_expr(user_account::Column::Role, Expr::value("Member"))
+        .filter(user_account::Column::UserId.eq(member.id))
+        .exec(&db)
+        .await
+        .unwrap();
+
+    let pipeline = json!({
+        "name": "Partnerships",
+        "stages": [
+            { "name": "Open", "probability": 10 },
+            { "name": "Won", "outcome": "won" },
+            { "name": "Lost", "outcome": "lost" }
+        ]
+    });
+    let sla_policy = json!({ "first_response_minutes": 60, "resolution_minutes": 480 });
+    let field = json!({ "key": "tier", "label": "Tier", "field_type": "text" });
+
+    // Pipelines, SLA policies and custom fields are tenant configuration, owned by admins
+    let (status, _) = test_utils::call_without_tenant(&app, "POST", "/api/deals/pipelines", &member_token, Some(pipeline.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = test_utils::call_without_tenant(&app, "PUT", "/api/cases/sla-policies/high", &member_token, Some(sla_policy.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = test_utils::call_without_tenant(&app, "POST", "/api/custom-fields/customer", &member_token, Some(field.clone())).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = test_utils::call_without_tenant(&app, "POST", "/api/deals/pipelines", &owner_token, Some(pipeline)).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let (status, _) = test_utils::call_without_tenant(&app, "PUT", "/api/cases/sla-policies/high", &owner_token, Some(sla_policy)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = test_utils::call_without_tenant(&app, "POST", "/api/custom-fields/customer", &owner_token, Some(field)).await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_files_from_another_tenant_cannot_be_attached() {
+    let (app, db) = setup_test_app().await;
+    let tenant_a = test_utils::create_test_tenant(&db).await;
+    let tenant_b = test_utils::create_test_tenant(&db).await;
+    let (_, token_b) = register(&app, tenant_b.id, "isob").await;
+    let foreign_file = insert_file(&db, tenant_a.id).await;
+    let own_file = insert_file(&db, tenant_b.id).await;
+
+    let (status, customer) = test_utils::call_without_tenant(&app, "POST", "/api/customers", &token_b, Some(customer_payload("Tenant B Client"))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let customer_id = customer["id"].as_str().unwrap().to_string();
+    let (status, deal) = test_utils::call_without_tenant(&app, "POST", "/api/deals", &token_b, Some(json!({
+        "customer_id": customer_id,
+        "name": "Renewal",
+        "amount": 1000.0,
+        "status": "Open",
+        "stage": "Prospecting",
+        "close_date": null
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, case) = test_utils::call_without_tenant(&app, "POST", "/api/cases", &token_b, Some(json!({
+        "customer_id": customer_id,
+        "title": "Missing invoice",
+        "description": "Invoice never arrived",
+        "status": "Open",
+        "priority": "Low"
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, contact) = test_utils::call_without_tenant(&app, "POST", "/api/contacts", &token_b, Some(json!({
+        "customer_id": customer_id,
+        "name": "Jane Roe",
+        "first_name": "Jane",
+        "last_name": "Roe",
+        "email": "jane@example.com"
+    }))).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let parents = [
+        format!("/api/deals/{}", deal["id"].as_str().unwrap()),
+        format!("/api/cases/{}", case["id"].as_str().unwrap()),
+        format!("/api/contacts/{}", contact["id"].as_str().unwrap()),
+    ];
+    for parent in &parents {
+        // Another tenant's file id is reported as missing...
+        let (status, _) = test_utils::call_without_tenant(&app, "POST", &format!("{}/files/{}", parent, foreign_file), &token_b, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", parent);
+        // ...while the tenant's own files attach as before
+        let (status, _) = test_utils::call_without_tenant(&app, "POST", &format!("{}/files/{}", parent, own_file), &token_b, None).await;
+        assert_eq!(status, StatusCode::OK, "{}", parent);
+    }
+}
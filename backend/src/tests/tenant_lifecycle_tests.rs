@@ -0,0 +1,188 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::entities::{app_domain, app_instance, audit_log, category, tenant, tenant_background_job};
+use crate::services::tenant::{
+    LifecycleError, TenantService, MAX_DELETION_GRACE_DAYS, PURGE_JOB_TYPE, STATUS_ACTIVE, STATUS_PURGED,
+    STATUS_SCHEDULED_FOR_DELETION, STATUS_SUSPENDED,
+};
+use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils::{self, call_on_host};
+
+/// A tenant running the network app on its own host.
+async fn tenant_site(db: &DatabaseConnection) -> (tenant::Model, String) {
+    let tenant = test_utils::create_test_tenant(db).await;
+    let instance = app_instance::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant.id),
+        app_type: Set("Network".to_string()),
+        database_url: Set(None),
+        data_seed_name: Set(None),
+        data_seed_version: Set(None),
+        data_seed_applied_at: Set(None),
+        settings: Set(None),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    let host = format!("lifecycle-{}.test", Uuid::new_v4().simple());
+    app_domain::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        app_instance_id: Set(instance.id),
+        domain_name: Set(host.clone()),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    (tenant, host)
+}
+
+#[tokio::test]
+async fn test_suspended_tenant_shows_maintenance_page_until_reactivated() {
+    let (app, db) = setup_test_app().await;
+    let (_, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let (tenant, host) = tenant_site(&db).await;
+    let listings = format!("/listings?tenant_id={}", tenant.id);
+    assert_eq!(call_on_host(&app, "GET", &host, &listings, None, None).await.0, StatusCode::OK);
+
+    let suspend = format!("/api/admin/tenants/{}/suspend", tenant.id);
+    assert_eq!(call_on_host(&app, "POST", "localhost", &suspend, Some(&admin_token), Some(json!({}))).await.0, StatusCode::OK);
+    assert_eq!(call_on_host(&app, "GET", &host, &listings, None, None).await.0, StatusCode::SERVICE_UNAVAILABLE);
+    // Signing in on the tenant's site is blocked too
+    assert_eq!(call_on_host(&app, "POST", &host, "/login", None, Some(json!({}))).await.0, StatusCode::SERVICE_UNAVAILABLE);
+    // Suspending twice isn't a valid transition
+    assert_eq!(call_on_host(&app, "POST", "localhost", &suspend, Some(&admin_token), Some(json!({}))).await.0, StatusCode::CONFLICT);
+
+    let reactivate = format!("/api/admin/tenants/{}/reactivate", tenant.id);
+    assert_eq!(call_on_host(&app, "POST", "localhost", &reactivate, Some(&admin_token), Some(json!({}))).await.0, StatusCode::OK);
+    assert_eq!(call_on_host(&app, "GET", &host, &listings, None, None).await.0, StatusCode::OK);
+
+    test_utils::drain_outbox(&db).await;
+    let actions: Vec<String> = audit_log::Entity::find()
+        .filter(audit_log::Column::EntityId.eq(tenant.id))
+        .all(&db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.action_type)
+        .collect();
+    assert!(actions.contains(&format!("tenant.lifecycle.{}", STATUS_SUSPENDED)));
+    assert!(actions.contains(&format!("tenant.lifecycle.{}", STATUS_ACTIVE)));
+}
+
+#[tokio::test]
+async fn test_deleted_tenant_is_purged_after_the_grace_period() {
+    let (app, db) = setup_test_app().await;
+    let (tenant, host) = tenant_site(&db).await;
+    test_utils::create_default_category(&db, tenant.id).await;
+
+    let scheduled = TenantService::schedule_deletion(&db, tenant.id, None, Some(30)).await.unwrap();
+    assert_eq!(scheduled.site_status, STATUS_SCHEDULED_FOR_DELETION);
+    let listings = format!("/listings?tenant_id={}", tenant.id);
+    assert_eq!(call_on_host(&app, "GET", &host, &listings, None, None).await.0, StatusCode::SERVICE_UNAVAILABLE);
+    let jobs = tenant_background_job::Entity::find()
+        .filter(tenant_background_job::Column::TenantId.eq(tenant.id))
+        .filter(tenant_background_job::Column::JobType.eq(PURGE_JOB_TYPE))
+        .all(&db)
+        .await
+        .unwrap();
+    assert_eq!(jobs.len(), 1);
+
+    // Still within the grace period
+    assert!(!TenantService::purge_if_due(&db, tenant.id).await.unwrap());
+
+    // Cancel and reschedule without a grace period
+    TenantService::reactivate(&db, tenant.id, None).await.unwrap();
+    TenantService::schedule_deletion(&db, tenant.id, None, Some(0)).await.unwrap();
+    assert!(TenantService::purge_if_due(&db, tenant.id).await.unwrap());
+
+    let purged = tenant::Entity::find_by_id(tenant.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(purged.site_status, STATUS_PURGED);
+    assert!(purged.purged_at.is_some());
+    let categories = category::Entity::find()
+        .filter(category::Column::TenantId.eq(tenant.id))
+        .all(&db)
+        .await
+        .unwrap();
+    assert!(categories.is_empty());
+    let instances = app_instance::Entity::find()
+        .filter(app_instance::Column::TenantId.eq(tenant.id))
+        .all(&db)
+        .await
+        .unwrap();
+    assert!(instances.is_empty());
+    assert_eq!(call_on_host(&app, "GET", &host, &listings, None, None).await.0, StatusCode::NOT_FOUND);
+
+    // A purged tenant can't come back
+    assert!(TenantService::reactivate(&db, tenant.id, None).await.is_err());
+}
+
+#[tokio::test]
+async fn test_members_of_a_suspended_tenant_cannot_sign_in() {
+    let (app, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let mut username = String::new();
+    let (status, registered) = test_utils::register_test_user(&app, tenant.id, &mut username).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let token = registered["token"].as_str().unwrap().to_string();
+    let email = registered["user"]["email"].as_str().unwrap().to_string();
+    let password = std::env::var("TEST_PASSWORD").unwrap_or_default();
+    let credentials = json!({ "email": email, "password": password });
+    assert_eq!(call_on_host(&app, "GET", "localhost", "/api/profiles", Some(&token), None).await.0, StatusCode::OK);
+
+    TenantService::suspend(&db, tenant.id, None, None).await.unwrap();
+    // Signing in through the platform host is refused as well, and open sessions stop working
+    assert_eq!(call_on_host(&app, "POST", "localhost", "/login", None, Some(credentials.clone())).await.0, StatusCode::FORBIDDEN);
+    assert_eq!(call_on_host(&app, "GET", "localhost", "/api/profiles", Some(&token), None).await.0, StatusCode::FORBIDDEN);
+
+    TenantService::reactivate(&db, tenant.id, None).await.unwrap();
+    assert_eq!(call_on_host(&app, "POST", "localhost", "/login", None, Some(credentials.clone())).await.0, StatusCode::OK);
+    assert_eq!(call_on_host(&app, "GET", "localhost", "/api/profiles", Some(&token), None).await.0, StatusCode::OK);
+
+    TenantService::schedule_deletion(&db, tenant.id, None, Some(30)).await.unwrap();
+    assert_eq!(call_on_host(&app, "POST", "localhost", "/login", None, Some(credentials.clone())).await.0, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_only_owners_and_platform_admins_can_delete_a_tenant() {
+    let (app, db) = setup_test_app().await;
+    let (_, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let other_tenant = test_utils::create_test_tenant(&db).await;
+    let mut username = String::new();
+    let (_, registered) = test_utils::register_test_user(&app, other_tenant.id, &mut username).await;
+    let outsider_token = registered["token"].as_str().unwrap().to_string();
+    let uri = format!("/api/tenants/{}", tenant.id);
+
+    // The owner of another tenant can't tell this one exists
+    assert_eq!(call_on_host(&app, "DELETE", "localhost", &uri, Some(&outsider_token), None).await.0, StatusCode::NOT_FOUND);
+    let record = tenant::Entity::find_by_id(tenant.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(record.site_status, STATUS_ACTIVE);
+
+    assert_eq!(call_on_host(&app, "DELETE", "localhost", &uri, Some(&admin_token), None).await.0, StatusCode::NO_CONTENT);
+    let record = tenant::Entity::find_by_id(tenant.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(record.site_status, STATUS_SCHEDULED_FOR_DELETION);
+}
+
+#[tokio::test]
+async fn test_grace_periods_beyond_the_maximum_are_refused() {
+    let (_, db) = setup_test_app().await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+
+    for grace_days in [MAX_DELETION_GRACE_DAYS + 1, i64::MAX] {
+        let result = TenantService::schedule_deletion(&db, tenant.id, None, Some(grace_days)).await;
+        assert!(matches!(result, Err(LifecycleError::InvalidGracePeriod(days)) if days == grace_days));
+    }
+    let record = tenant::Entity::find_by_id(tenant.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(record.site_status, STATUS_ACTIVE);
+
+    let scheduled = TenantService::schedule_deletion(&db, tenant.id, None, Some(MAX_DELETION_GRACE_DAYS)).await.unwrap();
+    assert_eq!(scheduled.site_status, STATUS_SCHEDULED_FOR_DELETION);
+}
@@ -16,13 +16,15 @@ use fake::{
     },
     Fake,
 };
-use sea_orm::{ActiveModelTrait, ConnectionTrait, DatabaseConnection, Set};
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, Set};
 use serde_json::json;
 use tower::ServiceExt;
 use uuid::Uuid;
 
 use dotenv::dotenv;
-use crate::entities::{category, tenant, profile, user, user_account};
+use crate::entities::{account, category, outbox, tenant, profile, user, user_account};
+use crate::entities::customer::{self, CustomerType};
+use crate::services::outbox::{Outbox, STATUS_PENDING};
 use tokio::sync::OnceCell;
 
 pub static DB_INIT: OnceCell<()> = OnceCell::const_new();
@@ -31,15 +33,32 @@ pub static DB_INIT: OnceCell<()> = OnceCell::const_new();
 pub async fn initialize_database(db: &DatabaseConnection) {
     let db_clone = db.clone();
     DB_INIT.get_or_init(|| async move {
-        use sea_orm_migration::MigratorTrait;
         // Drops tables in case the previous test run crashed. Then immediately rebuilds it via the strict async task lifecycle exactly once.
-        let _ = crate::migration::Migrator::fresh(&db_clone).await;
-        crate::migration::Migrator::up(&db_clone, None)
+        crate::migration::runner::fresh(&db_clone)
             .await
             .expect("Failed to run migrations");
     }).await;
 }
 
+/// Stands in for the background relay: keeps relaying until no due message is left.
+/// Messages claimed by a concurrently running test still count as pending until that
+/// relay commits, so this also waits for those.
+pub async fn drain_outbox(db: &DatabaseConnection) {
+    for _ in 0..50 {
+        Outbox::relay_once(db).await.expect("Outbox relay failed");
+        let pending = outbox::Entity::find()
+            .filter(outbox::Column::Status.eq(STATUS_PENDING))
+            .filter(outbox::Column::AvailableAt.lte(Utc::now()))
+            .count(db)
+            .await
+            .expect("Failed to count pending outbox messages");
+        if pending == 0 {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+}
+
 pub async fn create_test_tenant<C: ConnectionTrait>(db: &C) -> tenant::Model {
     let tenant_id = Uuid::new_v4();
 
@@ -325,6 +344,118 @@ pub async fn create_default_category(
     .expect("Failed to create default category")
 }
 
+pub async fn create_customer(db: &DatabaseConnection, tenant_id: Uuid) -> customer::Model {
+    customer::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        name: Set(CompanyName().fake()),
+        customer_type: Set(CustomerType::BusinessEntity),
+        attributes: Set(Default::default()),
+        is_active: Set(true),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        tenant_id: Set(Some(tenant_id)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .expect("Failed to create test customer")
+}
+
+/// Makes the user an active member of the tenant through a new account.
+pub async fn join_tenant(db: &DatabaseConnection, tenant_id: Uuid, user_id: Uuid) -> account::Model {
+    let account = account::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant_id),
+        name: Set("Member Account".to_string()),
+        is_active: Set(true),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    user_account::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        account_id: Set(account.id),
+        role: Set(user_account::UserRole::Member),
+        is_active: Set(true),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+    }
+    .insert(db)
+    .await
+    .unwrap();
+    account
+}
+
+/// Sends a JSON request as the token's user in the given tenant and returns the status and the
+/// JSON body (`Null` when the body is empty or not JSON).
+pub async fn call(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    token: &str,
+    tenant_id: Uuid,
+    body: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    send_json(app, method, "localhost", uri, Some(token), Some(tenant_id), Some(body)).await
+}
+
+/// Like [`call`] without an `X-Tenant-Id` header, so the tenant is the API token's or the
+/// user's only membership. `None` sends an empty body.
+pub async fn call_without_tenant(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    token: &str,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+    send_json(app, method, "localhost", uri, Some(token), None, body).await
+}
+
+/// Sends a JSON request to the site served on `host`, signed in when a token is given, so the
+/// tenant is resolved from the host. `None` sends an empty body.
+pub async fn call_on_host(
+    app: &Router,
+    method: &str,
+    host: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+    send_json(app, method, host, uri, token, None, body).await
+}
+
+async fn send_json(
+    app: &Router,
+    method: &str,
+    host: &str,
+    uri: &str,
+    token: Option<&str>,
+    tenant_id: Option<Uuid>,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, serde_json::Value) {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Host", host)
+        .header("Content-Type", "application/json");
+    if let Some(token) = token {
+        builder = builder.header("Authorization", format!("Bearer {}", token));
+    }
+    if let Some(tenant_id) = tenant_id {
+        builder = builder.header("X-Tenant-Id", tenant_id.to_string());
+    }
+    let body = body.map_or_else(Body::empty, |json_body| Body::from(json_body.to_string()));
+
+    let response = app.clone().oneshot(builder.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null))
+}
+
 fn turn_name_to_domain(companyName: String) -> String {
     let processed = companyName
         .to_lowercase()
@@ -1,12 +1,51 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
 use sea_orm::{DatabaseConnection, Set, EntityTrait, ActiveModelTrait};
+use tower::ServiceExt;
 use uuid::Uuid;
 use wiremock::matchers::{method, path, header, body_json};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 use serde_json::json;
 
-use crate::entities::webhook_endpoint;
+use crate::entities::{webhook_delivery, webhook_endpoint};
+use crate::services::event_catalog::{self, PlatformEvent};
 use crate::services::webhook;
 use crate::tests::api_tests::setup_test_app;
+use crate::tests::test_utils;
+
+async fn create_endpoint(db: &DatabaseConnection, tenant_id: Uuid, target_url: String, failure_threshold: i32) -> webhook_endpoint::Model {
+    webhook_endpoint::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant_id: Set(tenant_id),
+        target_url: Set(target_url),
+        secret_key: Set("whsec_test_secret".to_string()),
+        subscribed_events: Set(json!(["crm.deal.won"])),
+        is_active: Set(true),
+        consecutive_failures: Set(0),
+        failure_threshold: Set(failure_threshold),
+        created_at: Set(Some(chrono::Utc::now().into())),
+        updated_at: Set(Some(chrono::Utc::now().into())),
+        ..Default::default()
+    }.insert(db).await.unwrap()
+}
+
+async fn create_delivery(db: &DatabaseConnection, endpoint: &webhook_endpoint::Model, status: &str) -> webhook_delivery::Model {
+    webhook_delivery::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        endpoint_id: Set(endpoint.id),
+        tenant_id: Set(endpoint.tenant_id),
+        event_type: Set("crm.deal.won".to_string()),
+        payload: Set(json!({ "deal_id": "123" })),
+        status: Set(status.to_string()),
+        next_retry_at: Set(None),
+        attempts: Set(webhook::MAX_ATTEMPTS),
+        created_at: Set(Some(chrono::Utc::now().into())),
+        updated_at: Set(Some(chrono::Utc::now().into())),
+        ..Default::default()
+    }.insert(db).await.unwrap()
+}
 
 #[tokio::test]
 async fn test_webhook_event_dispatch_to_wiremock() {
@@ -26,7 +65,7 @@ async fn test_webhook_event_dispatch_to_wiremock() {
 
     Mock::given(method("POST"))
         .and(path("/webhook"))
-        .and(header("X-Atlas-Event", "crm.deal.won"))
+        .and(header("X-Atlas-Event", "webform.submitted"))
         // We will just verify it receives the json payload
         .and(body_json(&payload))
         .respond_with(ResponseTemplate::new(200))
@@ -41,17 +80,20 @@ async fn test_webhook_event_dispatch_to_wiremock() {
         tenant_id: Set(tenant_id),
         target_url: Set(format!("{}/webhook", mock_server.uri())),
         secret_key: Set(secret.to_string()),
-        subscribed_events: Set(json!(["crm.deal.won", "listing.created"])),
+        subscribed_events: Set(json!(["crm.deal.won", "webform.*"])),
         is_active: Set(true),
+        failure_threshold: Set(10),
+        consecutive_failures: Set(0),
         created_at: Set(Some(chrono::Utc::now().into())),
         updated_at: Set(Some(chrono::Utc::now().into())),
         ..Default::default()
     }.insert(&db).await.unwrap();
 
     // 4. Dispatch the event
-    webhook::dispatch_event(&db, tenant_id, "crm.deal.won", payload)
+    webhook::dispatch_event(&db, tenant_id, PlatformEvent::WebformSubmitted, payload)
         .await
         .unwrap();
+    test_utils::drain_outbox(&db).await;
 
     // Give the async task a bit of time to make the HTTP request
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
@@ -61,3 +103,223 @@ async fn test_webhook_event_dispatch_to_wiremock() {
     // Or we can manually verify bindings if we wanted to assert the exact HMAC hash,
     // but the `body_json` and `header` ensures the wire protocol is respected.
 }
+
+#[tokio::test]
+async fn test_webhook_signature_covers_timestamp() {
+    let (_, db) = setup_test_app().await;
+    let tenant_id = Uuid::new_v4();
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/signed"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = create_endpoint(&db, tenant_id, format!("{}/signed", mock_server.uri()), 10).await;
+    let delivery = create_delivery(&db, &endpoint, webhook::STATUS_PENDING).await;
+    let delivered = webhook::process_delivery(&db, delivery.id).await.unwrap();
+    assert_eq!(delivered.status, webhook::STATUS_SENT);
+
+    let requests = mock_server.received_requests().await.unwrap();
+    let request = &requests[0];
+    let signature = request.headers.get("X-Atlas-Signature").unwrap().to_str().unwrap();
+    let timestamp: i64 = request.headers.get("X-Atlas-Timestamp").unwrap().to_str().unwrap().parse().unwrap();
+    let body = String::from_utf8(request.body.clone()).unwrap();
+
+    assert!(webhook::verify_signature(&endpoint.secret_key, signature, &body, timestamp, webhook::SIGNATURE_TOLERANCE_SECS));
+    // Replayed outside the tolerance window
+    assert!(!webhook::verify_signature(&endpoint.secret_key, signature, &body, timestamp + webhook::SIGNATURE_TOLERANCE_SECS + 1, webhook::SIGNATURE_TOLERANCE_SECS));
+    // Timestamp swapped without re-signing
+    let forged = signature.replacen(&format!("t={}", timestamp), &format!("t={}", timestamp + 60), 1);
+    assert!(!webhook::verify_signature(&endpoint.secret_key, &forged, &body, timestamp + 60, webhook::SIGNATURE_TOLERANCE_SECS));
+}
+
+#[tokio::test]
+async fn test_webhook_exhausted_retries_move_to_dead_letter() {
+    let (_, db) = setup_test_app().await;
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&mock_server)
+        .await;
+
+    // Threshold 0 keeps the endpoint enabled regardless of failures
+    let endpoint = create_endpoint(&db, Uuid::new_v4(), format!("{}/down", mock_server.uri()), 0).await;
+    let delivery = create_delivery(&db, &endpoint, webhook::STATUS_PENDING).await;
+    webhook_delivery::ActiveModel { id: Set(delivery.id), attempts: Set(0), ..Default::default() }
+        .update(&db).await.unwrap();
+
+    for attempt in 1..webhook::MAX_ATTEMPTS {
+        let delivery = webhook::process_delivery(&db, delivery.id).await.unwrap();
+        assert_eq!(delivery.attempts, attempt);
+        assert_eq!(delivery.status, webhook::STATUS_FAILED);
+        assert!(delivery.next_retry_at.is_some());
+    }
+
+    let delivery = webhook::process_delivery(&db, delivery.id).await.unwrap();
+    assert_eq!(delivery.status, webhook::STATUS_DEAD_LETTER);
+    assert!(delivery.next_retry_at.is_none());
+    assert_eq!(delivery.response_status, Some(500));
+}
+
+#[tokio::test]
+async fn test_webhook_endpoint_disabled_after_consecutive_failures() {
+    let (_, db) = setup_test_app().await;
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = create_endpoint(&db, Uuid::new_v4(), format!("{}/flaky", mock_server.uri()), 2).await;
+    for _ in 0..2 {
+        let delivery = create_delivery(&db, &endpoint, webhook::STATUS_PENDING).await;
+        webhook::process_delivery(&db, delivery.id).await.unwrap();
+    }
+
+    let endpoint = webhook_endpoint::Entity::find_by_id(endpoint.id).one(&db).await.unwrap().unwrap();
+    assert!(!endpoint.is_active);
+    assert!(endpoint.disabled_at.is_some());
+    assert_eq!(endpoint.consecutive_failures, 2);
+
+    // Further deliveries are parked without hitting the receiver
+    let parked = create_delivery(&db, &endpoint, webhook::STATUS_PENDING).await;
+    let parked = webhook::process_delivery(&db, parked.id).await.unwrap();
+    assert_eq!(parked.status, webhook::STATUS_DEAD_LETTER);
+    assert_eq!(mock_server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_webhook_redeliver_and_bulk_replay_endpoints() {
+    let (app, db) = setup_test_app().await;
+    let (_admin_user, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/recovered"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let endpoint = create_endpoint(&db, tenant.id, format!("{}/recovered", mock_server.uri()), 10).await;
+
+    // Manual redelivery of a single dead-lettered delivery
+    let dead = create_delivery(&db, &endpoint, webhook::STATUS_DEAD_LETTER).await;
+    let response = app.clone()
+        .oneshot(
+            Request::builder().header("Host", "localhost")
+                .method("POST")
+                .uri(format!("/api/admin/developer/tenant/{}/webhook-deliveries/{}/redeliver", tenant.id, dead.id))
+                .header("Authorization", format!("Bearer {}", admin_token))
+                .body(Body::empty())
+                .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(body["status"], webhook::STATUS_SENT);
+    assert_eq!(body["attempts"], 1);
+
+    // Bulk replay of a time range
+    let window_start = chrono::Utc::now() - chrono::Duration::minutes(1);
+    let dead = create_delivery(&db, &endpoint, webhook::STATUS_DEAD_LETTER).await;
+    let response = app.clone()
+        .oneshot(
+            Request::builder().header("Host", "localhost")
+                .method("POST")
+                .uri(format!("/api/admin/developer/tenant/{}/webhook-deliveries/replay", tenant.id))
+                .header("Authorization", format!("Bearer {}", admin_token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({
+                    "from": window_start,
+                    "to": chrono::Utc::now() + chrono::Duration::minutes(1),
+                }).to_string()))
+                .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(body["replayed"], 1);
+
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    let replayed = webhook_delivery::Entity::find_by_id(dead.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(replayed.status, webhook::STATUS_SENT);
+}
+
+#[tokio::test]
+async fn test_event_subscription_wildcards() {
+    assert!(event_catalog::pattern_matches("*", "webform.submitted"));
+    assert!(event_catalog::pattern_matches("webform.*", "webform.submitted"));
+    assert!(event_catalog::pattern_matches("webform.submitted", "webform.submitted"));
+    assert!(!event_catalog::pattern_matches("web.*", "webform.submitted"));
+    assert!(!event_catalog::pattern_matches("crm.*", "webform.submitted"));
+
+    assert!(event_catalog::validate_subscriptions(&json!(["*"])).is_ok());
+    assert!(event_catalog::validate_subscriptions(&json!(["webform.*"])).is_ok());
+    assert!(event_catalog::validate_subscriptions(&json!(["webform.deleted"])).is_err());
+    assert!(event_catalog::validate_subscriptions(&json!([])).is_err());
+    assert!(event_catalog::validate_subscriptions(&json!("webform.submitted")).is_err());
+}
+
+#[tokio::test]
+async fn test_event_catalog_and_endpoint_validation() {
+    let (app, db) = setup_test_app().await;
+    let (_admin_user, admin_token) = test_utils::create_and_login_admin_user(&app, &db).await;
+    let tenant = test_utils::create_test_tenant(&db).await;
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder().header("Host", "localhost")
+                .method("GET")
+                .uri("/api/admin/developer/events")
+                .header("Authorization", format!("Bearer {}", admin_token))
+                .body(Body::empty())
+                .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body_bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let catalog: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    let entry = catalog.as_array().unwrap().iter()
+        .find(|e| e["name"] == PlatformEvent::WebformSubmitted.name())
+        .expect("webform.submitted should be published");
+    assert_eq!(entry["version"], 1);
+    assert!(entry["payload_schema"].is_object());
+
+    let create = |events: serde_json::Value| {
+        let app = app.clone();
+        let admin_token = admin_token.clone();
+        async move {
+            app.oneshot(
+                Request::builder().header("Host", "localhost")
+                    .method("POST")
+                    .uri(format!("/api/admin/developer/tenant/{}/webhooks", tenant.id))
+                    .header("Authorization", format!("Bearer {}", admin_token))
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(json!({
+                        "target_url": "https://example.com/hook",
+                        "subscribed_events": events,
+                    }).to_string()))
+                    .unwrap()
+            )
+            .await
+            .unwrap()
+            .status()
+        }
+    };
+
+    assert_eq!(create(json!(["made.up.event"])).await, StatusCode::BAD_REQUEST);
+    assert_eq!(create(json!(["webform.*"])).await, StatusCode::OK);
+    assert_eq!(create(json!(["*"])).await, StatusCode::OK);
+}
@@ -1,9 +1,10 @@
 use axum::Router;
-use sea_orm::DatabaseConnection;
+use sea_orm::{DatabaseConnection, DatabaseTransaction, DbErr};
 use sea_orm_migration::MigrationTrait;
 use async_trait::async_trait;
 use std::future::Future;
 use std::pin::Pin;
+use crate::entities::app_instance;
 
 /// Represents a dynamic asynchronous executor closure for background jobs.
 pub type JobExecutor = Box<
@@ -44,6 +45,12 @@ pub trait AtlasApp: Send + Sync {
     /// Exposes the Axum router containing the app's native endpoints that explicitly require authentication via core platform middleware.
     fn authenticated_router(&self, state: DatabaseConnection) -> Router<DatabaseConnection>;
 
+    /// Authenticated endpoints that developer-console API tokens may also call. Every route group
+    /// here must declare its scope with `require_api_scope`; tokens are refused everywhere else.
+    fn api_token_router(&self, _state: DatabaseConnection) -> Router<DatabaseConnection> {
+        Router::new()
+    }
+
     /// Provides SeaORM standard migrations localized strictly to this app.
     /// Validates tenant_id architecture rather than relying on legacy local tables.
     fn migrations(&self) -> Vec<Box<dyn MigrationTrait>>;
@@ -51,6 +58,32 @@ pub trait AtlasApp: Send + Sync {
     /// Defines standard templates and execution capsules for background sync services.
     /// This pattern prevents frontend apps from silently pinging APIs by moving the burden to platform pollers.
     fn background_jobs(&self) -> Vec<BackgroundJob>;
+
+    /// The `app_instances.app_type` value identifying installs of this app.
+    fn app_type(&self) -> &'static str {
+        self.app_id()
+    }
+
+    /// JSON Schema that `app_instance.settings` must satisfy. `None` accepts any settings.
+    fn settings_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// `event_source` of the telemetry events recorded on behalf of this app.
+    fn telemetry_namespace(&self) -> String {
+        format!("app:{}", self.app_id())
+    }
+
+    /// Seeds tenant data after an `app_instance` of this app is created.
+    /// Runs inside the transaction that inserted the instance, so an error aborts the install.
+    async fn on_tenant_installed(&self, _txn: &DatabaseTransaction, _instance: &app_instance::Model) -> Result<(), DbErr> {
+        Ok(())
+    }
+
+    /// Removes tenant data before an `app_instance` of this app is deleted, inside the same transaction.
+    async fn on_tenant_uninstalled(&self, _txn: &DatabaseTransaction, _instance: &app_instance::Model) -> Result<(), DbErr> {
+        Ok(())
+    }
 }
 
 // ==========================================
@@ -130,11 +163,16 @@ mod tests {
         // 1. Verify Application Identifier Isolation
         assert_eq!(app.app_id(), "dummy_app");
 
-        // 2. Verify Migrations payload
+        // 2. Verify contract defaults
+        assert_eq!(app.app_type(), "dummy_app");
+        assert_eq!(app.telemetry_namespace(), "app:dummy_app");
+        assert!(app.settings_schema().is_none());
+
+        // 3. Verify Migrations payload
         let migrations = app.migrations();
         assert_eq!(migrations.len(), 1);
 
-        // 3. Verify Background Jobs Encapsulation
+        // 4. Verify Background Jobs Encapsulation
         let jobs = app.background_jobs();
         assert_eq!(jobs.len(), 1);
         
@@ -142,7 +180,7 @@ mod tests {
         assert_eq!(job.job_type, "dummy_sync");
         assert_eq!(job.default_interval_seconds, 60);
 
-        // 4. Verify Execution Closure executes within correct Isolated Context
+        // 5. Verify Execution Closure executes within correct Isolated Context
         // Safe to instantiate via transient in-memory sqlite connection
         let db = sea_orm::Database::connect("sqlite::memory:").await.expect("Failed to create in-memory test db");
 